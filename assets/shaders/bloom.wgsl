@@ -0,0 +1,65 @@
+// Bloom Shader - bright-pass and separable Gaussian blur at half resolution
+
+struct Uniforms {
+    exposure: f32,
+    encode_srgb: u32,
+    bloom_threshold: f32, // HDR brightness where glow starts
+    bloom_intensity: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var input_texture: texture_2d<f32>;
+@group(0) @binding(2) var linear_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // Fullscreen triangle: (-1,-1), (3,-1), (-1,3)
+    let corner = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
+
+// Keep only the light above the threshold, with a soft knee so the glow fades in
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Downsampling 2:1 with a bilinear tap averages the 4 source texels
+    let color = textureSampleLevel(input_texture, linear_sampler, in.uv, 0.0).rgb;
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = uniforms.bloom_threshold * 0.5;
+    var soft = clamp(brightness - uniforms.bloom_threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-4);
+    let contribution = max(soft, brightness - uniforms.bloom_threshold) / max(brightness, 1e-4);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+// 9-tap Gaussian as 5 bilinear fetches (weights/offsets merge adjacent taps)
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let texel = direction / vec2<f32>(textureDimensions(input_texture));
+    var weights = array<f32, 3>(0.2270270270, 0.3162162162, 0.0702702703);
+    var offsets = array<f32, 3>(0.0, 1.3846153846, 3.2307692308);
+
+    var color = textureSampleLevel(input_texture, linear_sampler, uv, 0.0).rgb * weights[0];
+    for (var i = 1; i < 3; i++) {
+        let offset = texel * offsets[i];
+        color += textureSampleLevel(input_texture, linear_sampler, uv + offset, 0.0).rgb * weights[i];
+        color += textureSampleLevel(input_texture, linear_sampler, uv - offset, 0.0).rgb * weights[i];
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+@fragment
+fn fs_blur_horizontal(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_vertical(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
@@ -1,87 +1,196 @@
 // Grass Shader with Wind Animation and Shadows
 
+struct WindParams {
+    direction: vec2<f32>,
+    strength: f32,
+    frequency: f32,
+};
+
 struct CameraUniform {
     view_proj: mat4x4<f32>,
-    light_view_proj: mat4x4<f32>,
+    light_view_proj: array<mat4x4<f32>, 3>, // One per shadow cascade
+    cascade_splits: vec4<f32>,              // Far view depth of each cascade (xyz)
     time: f32,
-    _padding1: vec3<f32>,
+    // Scalar padding: a vec3 here would be 16-aligned and push sun_dir to offset 160
+    _padding1: f32,
+    _padding1b: vec2<f32>,
     sun_dir: vec3<f32>,
     _padding2: f32,
+    ambient_color: vec3<f32>,
+    ambient_intensity: f32,
+    interaction_center: vec3<f32>,
+    interaction_radius: f32,
+    interaction_wake: vec3<f32>,
+    interaction_strength: f32,
+    wind: WindParams,
+    camera_position: vec3<f32>,
+    fade_start: f32, // Blades shrink and fade out between here...
+    fade_end: f32,   // ...and here (the draw distance)
+    _padding3: f32,
+    _padding4: f32,
+    _padding5: f32,
+    sun_color: vec3<f32>, // Key light color (sun by day, faint moon at night)
+    _padding6: f32,
 };
 
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 @group(0) @binding(1)
-var t_shadow: texture_depth_2d;
+var t_shadow: texture_depth_2d_array;
 @group(0) @binding(2)
 var s_shadow: sampler_comparison;
 
-struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) color: vec3<f32>,
+struct ShadowParams {
+    texel_size: f32,
+    kernel_radius: i32,
+    _padding: vec2<f32>,
 };
+@group(0) @binding(3)
+var<uniform> shadow_params: ShadowParams;
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
     @location(1) world_position: vec3<f32>,
-    @location(2) shadow_pos: vec3<f32>,
+    @location(2) view_depth: f32,
 };
 
-// Simple wind animation
-// Uses sine waves based on actual elapsed time for organic movement
-fn apply_wind(world_pos: vec3<f32>, height_factor: f32, time: f32) -> vec3<f32> {
-    // Wind direction and strength
-    let wind_strength = 0.15;
-    let wind_direction = vec2<f32>(1.0, 0.5);
+// 1 up close, 0 at (and past) the end of the fade range
+fn distance_fade(world_pos: vec3<f32>) -> f32 {
+    let dist = distance(world_pos, camera.camera_position);
+    return 1.0 - smoothstep(camera.fade_start, max(camera.fade_end, camera.fade_start + 0.001), dist);
+}
+
+// Wind animation: a steady lean downwind, travelling gusts and per-blade flutter
+// `base` is the blade root; its position offsets the phase so blades don't move in lockstep
+fn apply_wind(world_pos: vec3<f32>, base: vec3<f32>, height_factor: f32, time: f32) -> vec3<f32> {
+    let wind = camera.wind;
 
-    // Multiple sine waves for organic motion using REAL TIME
-    // Add world position for spatial variation so all grass doesn't move identically
-    let wave1 = sin(time * 2.0 + world_pos.x * 0.5) * wind_strength;
-    let wave2 = sin(time * 1.5 + world_pos.z * 0.7) * wind_strength * 0.5;
+    // Gusts roll downwind across the field, jittered per blade
+    let along = dot(base.xz, wind.direction);
+    let jitter = (sin(base.x * 3.1 + base.z * 1.3) + sin(base.z * 2.7 - base.x * 1.9)) * 1.6;
+    let gust = sin(time * wind.frequency - along * 0.3 + jitter * 0.25);
+    let flutter = sin(time * wind.frequency * 1.7 + jitter) * 0.3;
+    let sway = wind.strength * (0.5 + gust + flutter);
 
     // Only affect the top of the grass (based on height_factor)
     let wind_amount = height_factor * height_factor; // Quadratic falloff
 
-    // Apply wind offset
-    let wind_offset = vec3<f32>(
-        (wave1 + wave2) * wind_direction.x * wind_amount,
-        0.0,
-        (wave1 + wave2) * wind_direction.y * wind_amount
-    );
+    return world_pos + vec3<f32>(wind.direction.x, 0.0, wind.direction.y) * sway * wind_amount;
+}
 
-    return world_pos + wind_offset;
+// Push strength (0..1) from one interaction point, ignoring points far above/below the blade
+fn push_falloff(base: vec3<f32>, center: vec3<f32>) -> f32 {
+    let dist = distance(base.xz, center.xz);
+    let vertical = abs(base.y - center.y);
+    return (1.0 - smoothstep(camera.interaction_radius * 0.3, camera.interaction_radius, dist))
+        * (1.0 - smoothstep(1.0, 2.5, vertical));
 }
 
+// Bend a blade vertex away from the player (and the recovering wake behind them)
+fn apply_interaction(world_pos: vec3<f32>, base: vec3<f32>, height_factor: f32, blade_height: f32) -> vec3<f32> {
+    let now = push_falloff(base, camera.interaction_center);
+    let wake = push_falloff(base, camera.interaction_wake) * 0.7;
+
+    var from_center = base.xz - camera.interaction_center.xz;
+    if (wake > now) {
+        from_center = base.xz - camera.interaction_wake.xz;
+    }
+    let push = max(now, wake);
+    if (push <= 0.0) {
+        return world_pos;
+    }
+
+    let len = length(from_center);
+    var dir = vec2<f32>(1.0, 0.0);
+    if (len > 0.001) {
+        dir = from_center / len;
+    }
+
+    // Tips move most; the blade also sags so it reads as flattened, not stretched
+    let bend = push * camera.interaction_strength * height_factor * height_factor * blade_height;
+    return world_pos + vec3<f32>(dir.x * bend, -bend * 0.6, dir.y * bend);
+}
+
+// One shared unit blade mesh, instanced per blade (generated, or placed by the compute pass)
+struct BladeVertex {
+    @location(0) local: vec3<f32>,
+};
+
+struct GrassInstance {
+    @location(2) position: vec3<f32>,
+    @location(3) height: f32,
+    @location(4) color: vec3<f32>,
+    @location(5) rotation: f32,
+    @location(6) bend: f32,
+};
+
 @vertex
-fn vs_main(vertex: VertexInput) -> VertexOutput {
+fn vs_main(blade: BladeVertex, instance: GrassInstance) -> VertexOutput {
     var out: VertexOutput;
 
-    // Calculate height factor (0 at base, 1 at tip)
-    let height_factor = saturate(vertex.position.y / 1.0);
+    // Scale and bend the unit blade and rotate it around its base, shrinking it toward the draw distance
+    let height = instance.height * distance_fade(instance.position);
+    let scaled = vec3<f32>(blade.local.x, blade.local.y * height, blade.local.z * instance.bend * height);
+    let c = cos(instance.rotation);
+    let s = sin(instance.rotation);
+    let rotated = vec3<f32>(scaled.x * c - scaled.z * s, scaled.y, scaled.x * s + scaled.z * c);
 
-    // Apply wind animation with real time
-    let animated_position = apply_wind(vertex.position, height_factor, camera.time);
+    let height_factor = blade.local.y;
+    let windy_position = apply_wind(instance.position + rotated, instance.position, height_factor, camera.time);
+    let animated_position = apply_interaction(windy_position, instance.position, height_factor, height);
 
     out.clip_position = camera.view_proj * vec4<f32>(animated_position, 1.0);
-    out.color = vertex.color;
+    // Darker base fading to the instance tip color
+    out.color = mix(instance.color * vec3<f32>(0.55, 0.73, 0.75), instance.color, height_factor);
     out.world_position = animated_position;
+    out.view_depth = out.clip_position.w;
+
+    return out;
+}
+
+// Cascaded shadow lookup (matches terrain.wgsl): 1.0 = lit, 0.0 = fully shadowed
+fn sample_shadow(world_pos: vec3<f32>, view_depth: f32) -> f32 {
+    var cascade = 0u;
+    while (cascade < 3u && view_depth > camera.cascade_splits[cascade]) {
+        cascade += 1u;
+    }
+    if (cascade >= 3u) {
+        return 1.0;
+    }
 
-    // Calculate shadow position
-    let pos_from_light = camera.light_view_proj * vec4<f32>(animated_position, 1.0);
+    let pos_from_light = camera.light_view_proj[cascade] * vec4<f32>(world_pos, 1.0);
     let shadow_ndc = pos_from_light.xyz / pos_from_light.w;
-    out.shadow_pos = vec3<f32>(
-        shadow_ndc.x * 0.5 + 0.5,
-        -shadow_ndc.y * 0.5 + 0.5,
-        shadow_ndc.z
-    );
+    let shadow_uv = vec2<f32>(shadow_ndc.x * 0.5 + 0.5, -shadow_ndc.y * 0.5 + 0.5);
+    let shadow_depth = shadow_ndc.z;
 
-    return out;
+    if (shadow_uv.x < 0.0 || shadow_uv.x > 1.0 ||
+        shadow_uv.y < 0.0 || shadow_uv.y > 1.0 ||
+        shadow_depth < 0.0 || shadow_depth > 1.0) {
+        return 1.0;
+    }
+
+    // Percentage-closer filtering over the kernel
+    let radius = shadow_params.kernel_radius;
+    var lit = 0.0;
+    for (var y = -radius; y <= radius; y += 1) {
+        for (var x = -radius; x <= radius; x += 1) {
+            let offset = vec2<f32>(f32(x), f32(y)) * shadow_params.texel_size;
+            lit += textureSampleCompareLevel(t_shadow, s_shadow, shadow_uv + offset, cascade, shadow_depth);
+        }
+    }
+    return lit / f32((2 * radius + 1) * (2 * radius + 1));
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Sun direction from uniform (points FROM sun TO scene)
+    // Fade out toward the draw distance (without writing depth once invisible)
+    let alpha = distance_fade(in.world_position);
+    if (alpha <= 0.01) {
+        discard;
+    }
+
+    // Key light direction from uniform (points FROM the light TO the scene)
     let light_dir = normalize(camera.sun_dir);
 
     // Grass normal - mostly up, with slight variation based on position for visual interest
@@ -91,39 +200,20 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         cos(in.world_position.z * 0.5) * 0.1
     ));
 
-    // Dynamic sun color matching terrain shader
-    let sun_elevation = -light_dir.y;
-    let sun_color = mix(
-        vec3<f32>(1.8, 0.6, 0.2),  // Sunrise/sunset
-        vec3<f32>(1.4, 1.3, 1.1),  // Midday
-        clamp(sun_elevation * 2.0, 0.0, 1.0)
-    );
+    let sun_color = camera.sun_color;
 
-    let ambient_color = mix(
-        vec3<f32>(0.15, 0.10, 0.08),
-        vec3<f32>(0.12, 0.14, 0.18),
-        clamp(sun_elevation * 2.0, 0.0, 1.0)
-    );
+    let ambient_color = camera.ambient_color * camera.ambient_intensity;
 
     // Diffuse lighting
     let n_dot_l = max(dot(normal, -light_dir), 0.0);
 
     // Shadow calculation
-    let shadow_uv = in.shadow_pos.xy;
-    let shadow_depth = in.shadow_pos.z;
-
-    var shadow = 1.0;
-    if (shadow_uv.x >= 0.0 && shadow_uv.x <= 1.0 &&
-        shadow_uv.y >= 0.0 && shadow_uv.y <= 1.0 &&
-        shadow_depth >= 0.0 && shadow_depth <= 1.0) {
-        shadow = textureSampleCompare(t_shadow, s_shadow, shadow_uv, shadow_depth);
-        shadow = shadow * 0.8 + 0.2;
-    }
+    let shadow = sample_shadow(in.world_position, in.view_depth) * 0.8 + 0.2;
 
     // Apply lighting
     let diffuse_contribution = sun_color * n_dot_l * 2.0 * shadow;
     let lighting = ambient_color + diffuse_contribution;
     let final_color = in.color * lighting;
 
-    return vec4<f32>(final_color, 1.0);
+    return vec4<f32>(final_color, alpha);
 }
@@ -0,0 +1,132 @@
+// Grass Placement & Culling Compute Shader
+// Scatters blade instances over a chunk heightfield and appends the visible
+// ones to an instance buffer consumed by an indirect draw.
+
+struct PlacementParams {
+    view_proj: mat4x4<f32>,
+    camera_pos: vec3<f32>,
+    max_distance: f32,
+    chunk_origin: vec2<f32>,
+    cell_size: f32,      // Heightfield vertex spacing (world units)
+    spacing: f32,        // Blade candidate spacing (world units)
+    resolution: u32,     // Heightfield quads per side
+    grid_dim: u32,       // Blade candidates per side
+    seed: u32,
+    max_instances: u32,
+};
+
+struct GrassInstance {
+    position: vec3<f32>,
+    height: f32,
+    color: vec3<f32>,
+    rotation: f32,
+    bend: f32,
+};
+
+// Layout matches wgpu::util::DrawIndexedIndirectArgs
+struct DrawIndexedIndirectArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: PlacementParams;
+@group(0) @binding(1) var<storage, read> heights: array<f32>;
+@group(0) @binding(2) var<storage, read_write> instances: array<GrassInstance>;
+@group(0) @binding(3) var<storage, read_write> draw_args: DrawIndexedIndirectArgs;
+@group(0) @binding(4) var<storage, read> density: array<f32>;
+
+// PCG hash -> [0, 1)
+fn hash(v: u32) -> f32 {
+    var state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return f32((word >> 22u) ^ word) / 4294967296.0;
+}
+
+fn grid_index(x: u32, z: u32) -> u32 {
+    let row = params.resolution + 1u;
+    return min(z, params.resolution) * row + min(x, params.resolution);
+}
+
+// Bilinear lookup in chunk-local coordinates.
+// Returns (height, density multiplier)
+fn sample_grid(local: vec2<f32>) -> vec2<f32> {
+    let grid = clamp(local / params.cell_size, vec2<f32>(0.0), vec2<f32>(f32(params.resolution)));
+    let cell = vec2<u32>(floor(grid));
+    let f = fract(grid);
+
+    let i00 = grid_index(cell.x, cell.y);
+    let i10 = grid_index(cell.x + 1u, cell.y);
+    let i01 = grid_index(cell.x, cell.y + 1u);
+    let i11 = grid_index(cell.x + 1u, cell.y + 1u);
+
+    let v00 = vec2<f32>(heights[i00], density[i00]);
+    let v10 = vec2<f32>(heights[i10], density[i10]);
+    let v01 = vec2<f32>(heights[i01], density[i01]);
+    let v11 = vec2<f32>(heights[i11], density[i11]);
+
+    return mix(mix(v00, v10, f.x), mix(v01, v11, f.x), f.y);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn place_grass(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.grid_dim || id.y >= params.grid_dim) {
+        return;
+    }
+
+    let cell = id.y * params.grid_dim + id.x;
+    let key = cell * 4u + params.seed * 2654435761u;
+
+    // Jittered candidate position within the chunk
+    let jitter = vec2<f32>(hash(key), hash(key + 1u));
+    let local = (vec2<f32>(id.xy) + jitter) * params.spacing;
+    let sample = sample_grid(local);
+    let height = sample.x;
+
+    // No grass on beach/wet sand (matches the CPU generator)
+    if (height < 0.8) {
+        return;
+    }
+
+    // Biome factor (0.0 = beach edge, 1.0 = deep forest) drives density,
+    // scaled by the density map (trails)
+    let biome_factor = clamp((height - 0.8) / 12.0, 0.0, 1.0);
+    let density_threshold = (0.1 + biome_factor * 0.9) * sample.y;
+    if (hash(key + 2u) > density_threshold) {
+        return;
+    }
+
+    let world = vec3<f32>(params.chunk_origin.x + local.x, height, params.chunk_origin.y + local.y);
+
+    // Distance cull
+    if (distance(world, params.camera_pos) > params.max_distance) {
+        return;
+    }
+
+    // Frustum cull in clip space, padded so blades at the screen edge survive
+    // (reverse-Z: the far plane is clip.z = 0)
+    let clip = params.view_proj * vec4<f32>(world + vec3<f32>(0.0, 0.5, 0.0), 1.0);
+    let pad = 2.0;
+    if (clip.w < -pad || abs(clip.x) > clip.w + pad || abs(clip.y) > clip.w + pad || clip.z < -pad) {
+        return;
+    }
+
+    let slot = atomicAdd(&draw_args.instance_count, 1u);
+    if (slot >= params.max_instances) {
+        return;
+    }
+
+    // Height range increases toward forest (scrub 0.4-0.8m, deep forest 1.2-2.4m)
+    let height_roll = hash(key + 3u);
+    let blade_height = mix(0.4 + biome_factor * 0.8, 0.8 + biome_factor * 1.6, height_roll);
+
+    var out: GrassInstance;
+    out.position = world;
+    out.height = blade_height;
+    out.color = vec3<f32>(0.45 - biome_factor * 0.10, 0.75 + biome_factor * 0.10, 0.20);
+    out.rotation = hash(key ^ 0x9e3779b9u) * 6.2831853;
+    out.bend = 0.4 + biome_factor * 0.3; // More curl in forest
+    instances[slot] = out;
+}
@@ -0,0 +1,94 @@
+// Moon Billboard Shader - renders a phased moon disc in the sky
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    moon_world_pos: vec3<f32>,
+    moon_size: f32,
+    moon_color: vec3<f32>,
+    phase: f32, // 0 = new, 0.5 = full
+    camera_right: vec3<f32>,
+    _padding: f32,
+    camera_up: vec3<f32>,
+    _padding2: f32,
+    sun_dir: vec3<f32>, // Direction FROM sun TO scene
+    _padding3: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+const PI: f32 = 3.14159265359;
+const DISC_RADIUS: f32 = 0.6; // Of the quad half-size; the rest is halo
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Billboard quad vertices (two triangles)
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var positions = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    let pos_2d = positions[vertex_index];
+
+    // Billboard in world space - offset from moon position using camera basis vectors
+    let world_pos = uniforms.moon_world_pos
+        + uniforms.camera_right * pos_2d.x * uniforms.moon_size
+        + uniforms.camera_up * pos_2d.y * uniforms.moon_size;
+
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world_pos, 1.0);
+    out.uv = pos_2d; // -1..1
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.uv / DISC_RADIUS;
+    let r2 = dot(p, p);
+
+    // Which way the lit limb faces on screen: toward the sun
+    let to_sun = -uniforms.sun_dir;
+    var tilt = vec2<f32>(dot(to_sun, uniforms.camera_right), dot(to_sun, uniforms.camera_up));
+    if (dot(tilt, tilt) < 1e-6) {
+        tilt = vec2<f32>(1.0, 0.0);
+    }
+    tilt = normalize(tilt);
+
+    // Light direction in billboard space (z toward the viewer): from behind the moon at
+    // new moon, around the side at the quarters, from behind the viewer at full
+    let angle = uniforms.phase * 2.0 * PI;
+    let light = vec3<f32>(tilt * sin(angle), -cos(angle));
+    let illuminated = 0.5 - 0.5 * cos(angle); // Lit fraction of the disc
+
+    if (r2 > 1.0) {
+        // Faint halo, brighter the fuller the moon
+        let r = sqrt(r2);
+        let glow = exp(-(r - 1.0) * 6.0) * 0.25 * illuminated;
+        if (glow < 0.005) {
+            discard;
+        }
+        return vec4<f32>(uniforms.moon_color, glow);
+    }
+
+    // Sphere normal under this pixel; the terminator is where it turns away from the light
+    let normal = vec3<f32>(p, sqrt(1.0 - r2));
+    let lit = smoothstep(-0.05, 0.1, dot(normal, light));
+
+    // Darker maria, soft limb
+    let maria = 0.88 + 0.12 * sin(p.x * 7.0 + 1.3) * sin(p.y * 5.0 + 0.4);
+    let limb = 0.75 + 0.25 * normal.z;
+    let color = uniforms.moon_color * maria * limb;
+
+    // The dark side only faintly shows (earthshine); the sky shows through elsewhere
+    let alpha = max(lit, 0.06) * smoothstep(1.0, 0.95, r2);
+    return vec4<f32>(color * max(lit, 0.3), alpha);
+}
@@ -0,0 +1,39 @@
+// Occlusion proxies: each instance is an axis-aligned box drawn depth-tested, without writes,
+// inside an occlusion query. Zero samples passing means the box is hidden by what's already drawn.
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct BoxInstance {
+    @location(0) box_min: vec3<f32>,
+    @location(1) box_max: vec3<f32>,
+};
+
+// Unit cube corners (bit 0 = x, bit 1 = y, bit 2 = z), two triangles per face
+const CUBE_INDICES = array<u32, 36>(
+    0u, 2u, 1u, 1u, 2u, 3u, // -z
+    4u, 5u, 6u, 5u, 7u, 6u, // +z
+    0u, 4u, 2u, 2u, 4u, 6u, // -x
+    1u, 3u, 5u, 3u, 7u, 5u, // +x
+    0u, 1u, 4u, 1u, 5u, 4u, // -y
+    2u, 6u, 3u, 3u, 6u, 7u, // +y
+);
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, instance: BoxInstance) -> @builtin(position) vec4<f32> {
+    var indices = CUBE_INDICES;
+    let corner = indices[vertex_index];
+    let t = vec3<f32>(f32(corner & 1u), f32((corner >> 1u) & 1u), f32((corner >> 2u) & 1u));
+    let world = mix(instance.box_min, instance.box_max, t);
+    return uniforms.view_proj * vec4<f32>(world, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    // Color writes are masked off; only the query's sample count matters
+    return vec4<f32>(0.0);
+}
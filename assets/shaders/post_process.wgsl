@@ -0,0 +1,51 @@
+// Post Process Shader - bloom composite + exposure + ACES tonemapping of the HDR scene to the swapchain
+
+struct Uniforms {
+    exposure: f32,
+    encode_srgb: u32, // 1 when the swapchain is linear and the shader must encode
+    bloom_threshold: f32,
+    bloom_intensity: f32, // 0 while bloom is disabled
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var hdr_texture: texture_2d<f32>;
+@group(0) @binding(2) var bloom_texture: texture_2d<f32>; // Half resolution, blurred
+@group(0) @binding(3) var linear_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // Fullscreen triangle: (-1,-1), (3,-1), (-1,3)
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// ACES filmic curve (Narkowicz 2015 fit): rolls highlights off instead of clipping
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
+    var hdr = textureLoad(hdr_texture, vec2<i32>(frag_coord.xy), 0).rgb;
+
+    // Additive bloom, bilinearly upsampled from the half-resolution blur
+    let uv = frag_coord.xy / vec2<f32>(textureDimensions(hdr_texture));
+    hdr += textureSampleLevel(bloom_texture, linear_sampler, uv, 0.0).rgb * uniforms.bloom_intensity;
+
+    var color = aces(max(hdr, vec3<f32>(0.0)) * uniforms.exposure);
+    if (uniforms.encode_srgb == 1u) {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
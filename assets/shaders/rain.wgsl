@@ -0,0 +1,91 @@
+// Rain Shader - instanced streaks wrapped into a box that follows the camera
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    camera_pos: vec3<f32>,
+    time: f32,
+    wind_velocity: vec2<f32>, // Horizontal drop velocity, slants the streaks
+    drift: vec2<f32>,         // Accumulated wind drift (wrapped to BOX_SIZE)
+    light: vec3<f32>,
+    intensity: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+// Box of rain around the camera (world units); BOX_SIZE must match rain_pipeline.rs
+const BOX_SIZE: f32 = 40.0;
+const BOX_HEIGHT: f32 = 30.0;
+const FALL_SPEED: f32 = 9.0;
+const STREAK_LENGTH: f32 = 0.6;
+const STREAK_WIDTH: f32 = 0.012;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) fade: f32,
+}
+
+fn hash(n: u32) -> f32 {
+    var x = n * 747796405u + 2891336453u;
+    x = ((x >> ((x >> 28u) + 4u)) ^ x) * 277803737u;
+    x = (x >> 22u) ^ x;
+    return f32(x) / 4294967295.0;
+}
+
+// Wrap a world coordinate into [-size/2, size/2) around the camera
+fn wrap(world: f32, camera: f32, size: f32) -> f32 {
+    return camera + (fract((world - camera) / size + 0.5) - 0.5) * size;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, 0.0),
+        vec2<f32>( 1.0, 0.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>( 1.0, 0.0),
+        vec2<f32>( 1.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    let seed = instance_index * 4u;
+    let speed = FALL_SPEED * (0.8 + 0.4 * hash(seed + 3u));
+
+    // Drops move in world space (wind drift + fall) and wrap back into the box: a drop
+    // that falls out the bottom reappears at the top, so the rain never runs out
+    let fallen = uniforms.time * speed;
+    let drift = uniforms.drift;
+    let x = wrap(hash(seed) * BOX_SIZE + drift.x, uniforms.camera_pos.x, BOX_SIZE);
+    let z = wrap(hash(seed + 1u) * BOX_SIZE + drift.y, uniforms.camera_pos.z, BOX_SIZE);
+    let y = wrap(hash(seed + 2u) * BOX_HEIGHT - fallen, uniforms.camera_pos.y, BOX_HEIGHT);
+    let head = vec3<f32>(x, y, z);
+
+    // Streak along the velocity (slanted by the wind), widened toward the camera
+    let velocity = vec3<f32>(uniforms.wind_velocity.x, -speed, uniforms.wind_velocity.y);
+    let axis = -normalize(velocity) * STREAK_LENGTH;
+    let to_camera = uniforms.camera_pos - head;
+    let side = normalize(cross(axis, to_camera) + vec3<f32>(1e-5, 0.0, 0.0));
+    let dist = length(to_camera);
+    let width = STREAK_WIDTH * max(1.0, dist * 0.1); // Keep distant drops at least a pixel wide
+    let world_pos = head + axis * corner.y + side * corner.x * width;
+
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world_pos, 1.0);
+    out.uv = corner;
+    // Fade near the box edges (no popping as drops wrap) and right in front of the lens
+    let edge = max(abs(x - uniforms.camera_pos.x), abs(z - uniforms.camera_pos.z)) / (BOX_SIZE * 0.5);
+    out.fade = (1.0 - smoothstep(0.7, 1.0, edge)) * smoothstep(0.5, 2.0, dist);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Soft across the streak, brightest at the leading end
+    let across = 1.0 - abs(in.uv.x);
+    let along = 1.0 - in.uv.y;
+    let alpha = across * (0.3 + 0.7 * along) * in.fade * (0.25 + 0.2 * uniforms.intensity);
+    // Sky ambient is ~0.1-0.2 by day: scale it up so midday rain reads pale and night rain dark
+    let color = vec3<f32>(0.75, 0.8, 0.85) * clamp(uniforms.light * 5.0, vec3<f32>(0.15), vec3<f32>(1.0));
+    return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,102 @@
+// Rock Shader - Stone color per rock type + Simple Lighting
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    light_dir: vec3<f32>,
+    _padding: f32,
+    view_pos: vec3<f32>,
+    _padding2: f32,
+    fog_color: vec3<f32>,
+    fog_start: f32,
+    base_color: vec3<f32>,
+    fog_end: f32,
+    ambient_color: vec3<f32>,
+    ambient_intensity: f32,
+    sun_color: vec3<f32>, // Key light color (sun by day, faint moon at night)
+    _padding3: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+
+    // Instance Transforms (Mat4 takes 4 slots)
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(
+        input.model_matrix_0,
+        input.model_matrix_1,
+        input.model_matrix_2,
+        input.model_matrix_3,
+    );
+
+    let world_pos = model_matrix * vec4<f32>(input.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * world_pos;
+    out.normal = normalize((model_matrix * vec4<f32>(input.normal, 0.0)).xyz);
+    out.world_pos = world_pos.xyz;
+    return out;
+}
+
+// Cheap value noise for mottling the stone
+fn hash3(p: vec3<f32>) -> f32 {
+    return fract(sin(dot(p, vec3<f32>(12.9898, 78.233, 45.164))) * 43758.5453);
+}
+
+fn value_noise(p: vec3<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let n000 = hash3(i);
+    let n100 = hash3(i + vec3<f32>(1.0, 0.0, 0.0));
+    let n010 = hash3(i + vec3<f32>(0.0, 1.0, 0.0));
+    let n110 = hash3(i + vec3<f32>(1.0, 1.0, 0.0));
+    let n001 = hash3(i + vec3<f32>(0.0, 0.0, 1.0));
+    let n101 = hash3(i + vec3<f32>(1.0, 0.0, 1.0));
+    let n011 = hash3(i + vec3<f32>(0.0, 1.0, 1.0));
+    let n111 = hash3(i + vec3<f32>(1.0, 1.0, 1.0));
+    let x0 = mix(mix(n000, n100, u.x), mix(n010, n110, u.x), u.y);
+    let x1 = mix(mix(n001, n101, u.x), mix(n011, n111, u.x), u.y);
+    return mix(x0, x1, u.z);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.normal);
+
+    // Mottled stone, with moss/dirt darkening on upward faces
+    let mottle = value_noise(in.world_pos * 2.5) * 0.6 + value_noise(in.world_pos * 9.0) * 0.4;
+    let top = clamp(normal.y, 0.0, 1.0);
+    var albedo = uniforms.base_color * (0.8 + mottle * 0.4);
+    albedo = mix(albedo, vec3<f32>(0.28, 0.33, 0.20), top * top * 0.35);
+
+    // Lighting: light_dir points FROM the key light TO the scene (direction light travels)
+    let light_dir = normalize(uniforms.light_dir);
+    let diff = max(dot(normal, -light_dir), 0.0);
+    let ambient = uniforms.ambient_color * uniforms.ambient_intensity * 1.5;
+    let lit_color = albedo * (ambient + diff * uniforms.sun_color * 0.8);
+
+    // Fog
+    let dist = distance(in.world_pos, uniforms.view_pos);
+    let fog_factor = clamp((dist - uniforms.fog_start) / (uniforms.fog_end - uniforms.fog_start), 0.0, 1.0);
+    let final_color = mix(lit_color, uniforms.fog_color, fog_factor);
+
+    return vec4<f32>(final_color, 1.0);
+}
@@ -1,140 +1,207 @@
-struct Uniforms {
-    view_proj: mat4x4<f32>,
-    sun_dir: vec3<f32>,
-    time: f32,
-    sun_color: vec3<f32>,
-    cloud_coverage: f32,
-    cloud_color_base: vec3<f32>,
-    cloud_density: f32,
-    cloud_color_shade: vec3<f32>,
-    cloud_scale: f32,
-    wind_offset: vec2<f32>,
-    padding: vec2<f32>,
-}
-
-@group(0) @binding(0) var<uniform> uniforms: Uniforms;
-
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) world_pos: vec3<f32>,
-    @location(1) uv: vec2<f32>,
-}
-
-@vertex
-fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
-    var positions = array<vec2<f32>, 3>(
-        vec2<f32>(-1.0, -1.0),
-        vec2<f32>(3.0, -1.0),
-        vec2<f32>(-1.0, 3.0)
-    );
-    let pos = positions[in_vertex_index];
-    
-    var output: VertexOutput;
-    output.clip_position = vec4<f32>(pos, 1.0, 1.0);
-    output.world_pos = vec3<f32>(pos.x, pos.y, 1.0);
-    output.uv = pos * 0.5 + 0.5; // 0..1 range
-    return output;
-}
-
-// Simple Hash Function
-fn hash(p: vec2<f32>) -> f32 {
-    var p2 = p;
-    p2 = 50.0 * fract(p2 * 0.3183099 + vec2<f32>(0.71, 0.113));
-    return -1.0 + 2.0 * fract(p2.x * p2.y * (p2.x + p2.y));
-}
-
-// 2D Noise
-fn noise(p: vec2<f32>) -> f32 {
-    let i = floor(p);
-    let f = fract(p);
-    let u = f * f * (3.0 - 2.0 * f);
-    
-    return mix(mix(hash(i + vec2<f32>(0.0, 0.0)), 
-                   hash(i + vec2<f32>(1.0, 0.0)), u.x),
-               mix(hash(i + vec2<f32>(0.0, 1.0)), 
-                   hash(i + vec2<f32>(1.0, 1.0)), u.x), u.y);
-}
-
-// FBM (Fractal Brownian Motion)
-fn fbm(p: vec2<f32>) -> f32 {
-    var value = 0.0;
-    var amplitude = 0.5;
-    var frequency = 0.0;
-    var p2 = p;
-    
-    for (var i = 0; i < 5; i++) {
-        value += amplitude * noise(p2);
-        p2 = p2 * 2.0;
-        amplitude *= 0.5;
-    }
-    return value;
-}
-
-@fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    // Sky Gradient
-    let top_color = vec3<f32>(0.2, 0.4, 0.8);
-    let horizon_color = vec3<f32>(0.6, 0.7, 0.9);
-    let y = input.world_pos.y * 0.5 + 0.5;
-    var sky_color = mix(horizon_color, top_color, pow(y, 0.5));
-    
-    // Cloud Rendering
-    // Project UVs to "sky plane"
-    // We want clouds to look like they are on a plane above.
-    // Simple approximation: Use UVs + time
-    
-    let cloud_speed = 0.05;
-    let time_offset = uniforms.time * cloud_speed;
-    let wind = uniforms.wind_offset + vec2<f32>(time_offset, time_offset * 0.5);
-    
-    // Scale UVs for cloud texture
-    let uv_scaled = (input.world_pos.xy * 2.0) * uniforms.cloud_scale + wind;
-    
-    // Generate Noise
-    var n = fbm(uv_scaled);
-    
-    // Shape clouds
-    // Remap noise from [-1, 1] to [0, 1]
-    n = n * 0.5 + 0.5;
-    
-    // Apply coverage threshold
-    // coverage 0.0 = no clouds, 1.0 = full clouds
-    // We want to discard low noise values based on coverage
-    // If coverage is high, we keep more low values.
-    // Let's say threshold = 1.0 - coverage
-    let threshold = 1.0 - uniforms.cloud_coverage;
-    
-    // Soft threshold
-    let cloud_alpha = smoothstep(threshold - 0.1, threshold + 0.1, n);
-    
-    // Density
-    let density = cloud_alpha * uniforms.cloud_density;
-    
-    if (density > 0.01) {
-        // Cloud Color Gradient
-        // Mix between base (Burnt Sienna) and shade (Pink) based on noise "thickness"
-        // Thicker parts (higher n) might be lighter or darker depending on style.
-        // Let's make thicker parts the "shade" color (maybe darker pink/purple)
-        // and edges the "base" color (burnt sienna).
-        
-        let color_mix = smoothstep(threshold, threshold + 0.4, n);
-        let cloud_rgb = mix(uniforms.cloud_color_base, uniforms.cloud_color_shade, color_mix);
-        
-        // Lighting/Shading fake
-        // Add a bit of white highlight on "top" (based on sun dir? or just noise derivative?)
-        // Simple: lighter color for very high density
-        let highlight = smoothstep(0.8, 1.0, n);
-        let final_cloud_color = mix(cloud_rgb, vec3<f32>(1.0, 0.9, 0.9), highlight * 0.5);
-        
-        // Blend with sky
-        sky_color = mix(sky_color, final_cloud_color, density);
-    }
-    
-    // Sun Glow (Simple)
-    // We don't have exact view ray here easily for a quad, but we can approximate.
-    // Or just rely on the SunPipeline for the actual sun disk.
-    // Let's add a subtle glow if looking up?
-    // Nah, let's keep it clean.
-    
-    return vec4<f32>(sky_color, 1.0);
-}
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    sun_dir: vec3<f32>,
+    time: f32,
+    sun_color: vec3<f32>,
+    cloud_coverage: f32,
+    cloud_color_base: vec3<f32>,
+    cloud_density: f32,
+    cloud_color_shade: vec3<f32>,
+    cloud_scale: f32,
+    wind_offset: vec2<f32>,
+    star_rotation: f32, // Radians, one turn per day
+    star_intensity: f32,
+    inv_view_proj: mat4x4<f32>,
+    milky_way: f32, // 1.0 = draw the Milky Way band
+    padding0: f32,
+    padding1: f32,
+    padding2: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_pos: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(3.0, -1.0),
+        vec2<f32>(-1.0, 3.0)
+    );
+    let pos = positions[in_vertex_index];
+    
+    var output: VertexOutput;
+    output.clip_position = vec4<f32>(pos, 1.0, 1.0);
+    output.world_pos = vec3<f32>(pos.x, pos.y, 1.0);
+    output.uv = pos * 0.5 + 0.5; // 0..1 range
+    return output;
+}
+
+// Simple Hash Function
+fn hash(p: vec2<f32>) -> f32 {
+    var p2 = p;
+    p2 = 50.0 * fract(p2 * 0.3183099 + vec2<f32>(0.71, 0.113));
+    return -1.0 + 2.0 * fract(p2.x * p2.y * (p2.x + p2.y));
+}
+
+// 2D Noise
+fn noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    
+    return mix(mix(hash(i + vec2<f32>(0.0, 0.0)), 
+                   hash(i + vec2<f32>(1.0, 0.0)), u.x),
+               mix(hash(i + vec2<f32>(0.0, 1.0)), 
+                   hash(i + vec2<f32>(1.0, 1.0)), u.x), u.y);
+}
+
+// 3D -> 3D hash in [0, 1)
+fn hash33(p: vec3<f32>) -> vec3<f32> {
+    var q = fract(p * vec3<f32>(0.1031, 0.1030, 0.0973));
+    q += dot(q, q.yxz + 33.33);
+    return fract((q.xxy + q.yxx) * q.zyx);
+}
+
+// FBM (Fractal Brownian Motion)
+fn fbm(p: vec2<f32>) -> f32 {
+    var value = 0.0;
+    var amplitude = 0.5;
+    var frequency = 0.0;
+    var p2 = p;
+    
+    for (var i = 0; i < 5; i++) {
+        value += amplitude * noise(p2);
+        p2 = p2 * 2.0;
+        amplitude *= 0.5;
+    }
+    return value;
+}
+
+// World-space view ray through a screen position (reverse-Z: depth 1 is near, 0 is far)
+fn view_ray(ndc: vec2<f32>) -> vec3<f32> {
+    let near = uniforms.inv_view_proj * vec4<f32>(ndc, 1.0, 1.0);
+    let far = uniforms.inv_view_proj * vec4<f32>(ndc, 0.5, 1.0);
+    return normalize(far.xyz / far.w - near.xyz / near.w);
+}
+
+// Milky Way: a soft, noisy band around a tilted great circle
+fn milky_way_band(dir: vec3<f32>) -> f32 {
+    let pole = normalize(vec3<f32>(0.3, 0.4, 0.85));
+    let lat = dot(dir, pole);
+    let lon = atan2(dir.z, dir.x);
+    let clumps = fbm(vec2<f32>(lon * 6.0, lat * 25.0)) * 0.5 + 0.5;
+    return exp(-lat * lat / 0.015) * clumps;
+}
+
+// Hashed point stars: at most one per cell of a grid wrapped around the unit sphere
+fn star_field(dir: vec3<f32>, band: f32) -> f32 {
+    let p = dir * 250.0;
+    let cell = floor(p);
+    let h = hash33(cell);
+
+    // Denser where the Milky Way is
+    let threshold = 0.93 - band * 0.06;
+    if (h.z < threshold) {
+        return 0.0;
+    }
+    let magnitude = (h.z - threshold) / (1.0 - threshold);
+
+    let star_pos = cell + 0.2 + h * 0.6;
+    let falloff = 1.0 - smoothstep(0.0, 0.35, length(p - star_pos));
+    let twinkle = 0.8 + 0.2 * sin(uniforms.time * (2.0 + h.x * 3.0) + h.y * 50.0);
+    return falloff * mix(0.15, 1.0, magnitude * magnitude) * twinkle;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // sun_dir is the direction the light travels, so the sun is up when it points down
+    let sun_height = -uniforms.sun_dir.y;
+    let night = 1.0 - smoothstep(-0.2, 0.05, sun_height);
+
+    // Sky Gradient (day blues fading to the night sky as the sun sets)
+    let top_color = mix(vec3<f32>(0.2, 0.4, 0.8), vec3<f32>(0.01, 0.01, 0.03), night);
+    let horizon_color = mix(vec3<f32>(0.6, 0.7, 0.9), vec3<f32>(0.03, 0.04, 0.08), night);
+    let y = input.world_pos.y * 0.5 + 0.5;
+    var sky_color = mix(horizon_color, top_color, pow(y, 0.5));
+
+    // Stars (and the Milky Way) fade in after sunset and turn with the time of day
+    // about the axis the sun moves around; clouds drawn below cover them
+    if (night > 0.0 && uniforms.star_intensity > 0.0) {
+        let ray = view_ray(input.world_pos.xy);
+        let c = cos(uniforms.star_rotation);
+        let s = sin(uniforms.star_rotation);
+        let star_dir = vec3<f32>(c * ray.x - s * ray.y, s * ray.x + c * ray.y, ray.z);
+
+        let band = milky_way_band(star_dir) * uniforms.milky_way;
+        let stars = star_field(star_dir, band);
+        let above_horizon = smoothstep(-0.02, 0.1, ray.y);
+        let visibility = night * uniforms.star_intensity * above_horizon;
+        sky_color += (vec3<f32>(0.9, 0.92, 1.0) * stars + vec3<f32>(0.25, 0.25, 0.35) * band * 0.3) * visibility;
+    }
+    
+    // Cloud Rendering
+    // Project UVs to "sky plane"
+    // We want clouds to look like they are on a plane above.
+    // Simple approximation: Use UVs + time
+    
+    let cloud_speed = 0.05;
+    let time_offset = uniforms.time * cloud_speed;
+    let wind = uniforms.wind_offset + vec2<f32>(time_offset, time_offset * 0.5);
+    
+    // Scale UVs for cloud texture
+    let uv_scaled = (input.world_pos.xy * 2.0) * uniforms.cloud_scale + wind;
+    
+    // Generate Noise
+    var n = fbm(uv_scaled);
+    
+    // Shape clouds
+    // Remap noise from [-1, 1] to [0, 1]
+    n = n * 0.5 + 0.5;
+    
+    // Apply coverage threshold
+    // coverage 0.0 = no clouds, 1.0 = full clouds
+    // We want to discard low noise values based on coverage
+    // If coverage is high, we keep more low values.
+    // Let's say threshold = 1.0 - coverage
+    let threshold = 1.0 - uniforms.cloud_coverage;
+    
+    // Soft threshold
+    let cloud_alpha = smoothstep(threshold - 0.1, threshold + 0.1, n);
+    
+    // Density
+    let density = cloud_alpha * uniforms.cloud_density;
+    
+    if (density > 0.01) {
+        // Cloud Color Gradient
+        // Mix between base (Burnt Sienna) and shade (Pink) based on noise "thickness"
+        // Thicker parts (higher n) might be lighter or darker depending on style.
+        // Let's make thicker parts the "shade" color (maybe darker pink/purple)
+        // and edges the "base" color (burnt sienna).
+        
+        let color_mix = smoothstep(threshold, threshold + 0.4, n);
+        let cloud_rgb = mix(uniforms.cloud_color_base, uniforms.cloud_color_shade, color_mix);
+        
+        // Lighting/Shading fake
+        // Add a bit of white highlight on "top" (based on sun dir? or just noise derivative?)
+        // Simple: lighter color for very high density
+        let highlight = smoothstep(0.8, 1.0, n);
+        let final_cloud_color = mix(cloud_rgb, vec3<f32>(1.0, 0.9, 0.9), highlight * 0.5);
+        
+        // Blend with sky
+        sky_color = mix(sky_color, final_cloud_color, density);
+    }
+    
+    // Sun Glow (Simple)
+    // We don't have exact view ray here easily for a quad, but we can approximate.
+    // Or just rely on the SunPipeline for the actual sun disk.
+    // Let's add a subtle glow if looking up?
+    // Nah, let's keep it clean.
+    
+    return vec4<f32>(sky_color, 1.0);
+}
@@ -0,0 +1,119 @@
+// Screen-space ambient occlusion
+// fs_ao: occlusion from the scene depth (view-space normals reconstructed from neighbours)
+// fs_composite: 4x4 blur of the noisy occlusion, multiplied into the scene color
+
+struct Uniforms {
+    proj: mat4x4<f32>,
+    inv_proj: mat4x4<f32>,
+    radius: f32,
+    strength: f32,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var scene_depth: texture_2d<f32>;
+
+@group(0) @binding(2)
+var ao_texture: texture_2d<f32>;
+
+const SAMPLE_COUNT: u32 = 16u;
+const BIAS: f32 = 0.05;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // Fullscreen triangle covering [-1, 3]
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn load_depth(pixel: vec2<i32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(scene_depth));
+    return textureLoad(scene_depth, clamp(pixel, vec2<i32>(0), size - 1), 0).r;
+}
+
+// View-space position of a pixel with the given (reverse-Z) depth
+fn view_position(pixel: vec2<f32>, depth: f32) -> vec3<f32> {
+    let size = vec2<f32>(textureDimensions(scene_depth));
+    let ndc = vec2<f32>(pixel.x / size.x * 2.0 - 1.0, 1.0 - pixel.y / size.y * 2.0);
+    let p = uniforms.inv_proj * vec4<f32>(ndc, depth, 1.0);
+    return p.xyz / p.w;
+}
+
+fn pixel_position(pixel: vec2<i32>) -> vec3<f32> {
+    return view_position(vec2<f32>(pixel) + 0.5, load_depth(pixel));
+}
+
+// Interleaved gradient noise: per-pixel kernel rotation, hidden by the composite blur
+fn pixel_noise(pixel: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(pixel, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+@fragment
+fn fs_ao(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(frag_coord.xy);
+    let depth = load_depth(pixel);
+    if (depth <= 0.0) {
+        return vec4<f32>(1.0); // Sky
+    }
+    let p = view_position(frag_coord.xy, depth);
+
+    // Normal from the neighbour on each axis closest in depth (avoids smearing across silhouettes)
+    let right = pixel_position(pixel + vec2<i32>(1, 0)) - p;
+    let left = p - pixel_position(pixel - vec2<i32>(1, 0));
+    let down = pixel_position(pixel + vec2<i32>(0, 1)) - p;
+    let up = p - pixel_position(pixel - vec2<i32>(0, 1));
+    let dx = select(left, right, abs(right.z) < abs(left.z));
+    let dy = select(up, down, abs(down.z) < abs(up.z));
+    var normal = normalize(cross(dy, dx));
+    if (dot(normal, -p) < 0.0) {
+        normal = -normal;
+    }
+
+    // Tangent frame, randomly rotated around the normal per pixel
+    let angle = pixel_noise(frag_coord.xy) * 6.2831853;
+    let random = vec3<f32>(cos(angle), sin(angle), 0.0);
+    let tangent = normalize(random - normal * dot(random, normal));
+    let bitangent = cross(normal, tangent);
+
+    var occlusion = 0.0;
+    for (var i = 0u; i < SAMPLE_COUNT; i++) {
+        // Hemisphere kernel: golden-angle spiral, denser near the centre
+        let t = (f32(i) + 0.5) / f32(SAMPLE_COUNT);
+        let phi = f32(i) * 2.3999632;
+        let r = sqrt(t);
+        let dir = vec3<f32>(cos(phi) * r, sin(phi) * r, sqrt(1.0 - t));
+        let scale = mix(0.1, 1.0, t * t);
+        let sample_pos = p + (tangent * dir.x + bitangent * dir.y + normal * dir.z) * uniforms.radius * scale;
+
+        let clip = uniforms.proj * vec4<f32>(sample_pos, 1.0);
+        let ndc = clip.xy / clip.w;
+        let size = vec2<f32>(textureDimensions(scene_depth));
+        let sample_pixel = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5) * size;
+        let scene_depth_value = load_depth(vec2<i32>(sample_pixel));
+        let scene_z = view_position(sample_pixel, scene_depth_value).z;
+
+        // Occluded if the scene is in front of the sample; fade out for distant occluders
+        let range = smoothstep(0.0, 1.0, uniforms.radius / max(abs(p.z - scene_z), 1e-4));
+        occlusion += select(0.0, 1.0, scene_z >= sample_pos.z + BIAS) * range;
+    }
+
+    let ao = clamp(1.0 - occlusion / f32(SAMPLE_COUNT) * uniforms.strength, 0.0, 1.0);
+    return vec4<f32>(ao, ao, ao, 1.0);
+}
+
+@fragment
+fn fs_composite(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(ao_texture));
+    let pixel = vec2<i32>(frag_coord.xy);
+    var sum = 0.0;
+    for (var y = -2; y < 2; y++) {
+        for (var x = -2; x < 2; x++) {
+            sum += textureLoad(ao_texture, clamp(pixel + vec2<i32>(x, y), vec2<i32>(0), size - 1), 0).r;
+        }
+    }
+    let ao = sum / 16.0;
+    return vec4<f32>(ao, ao, ao, 1.0);
+}
@@ -60,6 +60,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let core_radius = 0.3;
     let corona_radius = 1.0;
+    let core_intensity = 6.0; // HDR: well past the bloom threshold, so the disc glows as a light
 
     if dist < core_radius {
         // Bright core - white to yellow
@@ -69,7 +70,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             uniforms.sun_color,          // Sun color at edge
             core_blend * core_blend
         );
-        return vec4<f32>(core_color, 1.0);
+        return vec4<f32>(core_color * core_intensity, 1.0);
     } else if dist < corona_radius {
         // Corona glow - Soft exponential falloff
         let corona_blend = (dist - core_radius) / (corona_radius - core_radius);
@@ -2,34 +2,78 @@
 
 struct Uniforms {
     view_proj: mat4x4<f32>,
-    light_view_proj: mat4x4<f32>,
+    light_view_proj: array<mat4x4<f32>, 3>, // One per shadow cascade
+    cascade_splits: vec4<f32>,              // Far view depth of each cascade (xyz)
     fog_color: vec3<f32>,
     time: f32,
     fog_start: f32,
     fog_end: f32,
-    padding1: vec2<f32>,
+    specular: vec2<f32>,                    // Land highlight: strength, shininess
     sun_dir: vec3<f32>,
     padding2: f32,
     view_pos: vec3<f32>,
     padding3: f32,
+    ambient_color: vec3<f32>,
+    ambient_intensity: f32,
+    sun_color: vec3<f32>,                   // Key light color (sun by day, faint moon at night)
+    rock_slope_cos: f32,                    // Ground whose normal.y is below this is rock
 }
 
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
-@group(0) @binding(1) var t_shadow: texture_depth_2d;
+@group(0) @binding(1) var t_shadow: texture_depth_2d_array;
 @group(0) @binding(2) var s_shadow: sampler_comparison;
 
+struct ShadowParams {
+    texel_size: f32,    // One shadow map texel in UV units
+    kernel_radius: i32, // 0 = hard, 1 = 3x3 PCF, 2 = 5x5 PCF
+    _padding: vec2<f32>,
+}
+@group(0) @binding(3) var<uniform> shadow_params: ShadowParams;
+
+// Detail normal map: tangent-space normal in rgb, strength in alpha (0 = none)
+@group(1) @binding(0) var t_detail_normal: texture_2d<f32>;
+@group(1) @binding(1) var s_detail_normal: sampler;
+
+const DETAIL_NORMAL_TILE: f32 = 4.0;  // Meters per tile, must match terrain_pipeline.rs
+const DETAIL_FADE_START: f32 = 30.0;  // The map has no mipmaps: fade it out before it shimmers
+const DETAIL_FADE_END: f32 = 70.0;
+
+// Splat textures, blended by the per-vertex weights: sand and grass are detail over the biome
+// color (x2), rock and snow are their own colors. Alpha is how strongly each applies (0 = plain
+// vertex color)
+@group(2) @binding(0) var t_sand: texture_2d<f32>;
+@group(2) @binding(1) var t_grass: texture_2d<f32>;
+@group(2) @binding(2) var t_rock: texture_2d<f32>;
+@group(2) @binding(3) var t_snow: texture_2d<f32>;
+@group(2) @binding(4) var s_splat: sampler;
+
+const SPLAT_TILE: f32 = 8.0;         // Meters per tile, must match terrain_pipeline.rs
+const ROCK_BLEND: f32 = 0.08;        // Half-width of the slope-to-rock transition (in normal.y)
+
+// Three axis projections blended by the normal, so steep faces don't smear a top-down texture
+fn sample_triplanar(t: texture_2d<f32>, world_pos: vec3<f32>, normal: vec3<f32>) -> vec4<f32> {
+    var weights = pow(abs(normal), vec3<f32>(4.0));
+    weights /= weights.x + weights.y + weights.z;
+    let x = textureSample(t, s_splat, world_pos.zy / SPLAT_TILE);
+    let y = textureSample(t, s_splat, world_pos.xz / SPLAT_TILE);
+    let z = textureSample(t, s_splat, world_pos.xy / SPLAT_TILE);
+    return x * weights.x + y * weights.y + z * weights.z;
+}
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
     @location(2) normal: vec3<f32>,
+    @location(3) splat: vec4<f32>, // Texture weights: sand, grass, rock, snow
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
     @location(1) world_pos: vec3<f32>,
-    @location(2) shadow_pos: vec3<f32>,
+    @location(2) view_depth: f32,
     @location(3) normal: vec3<f32>,
+    @location(4) splat: vec4<f32>,
 }
 
 @vertex
@@ -59,28 +103,53 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 
     // Transform position by view-projection matrix
     output.clip_position = uniforms.view_proj * vec4<f32>(world_pos, 1.0);
+    // Perspective clip w is the view-space depth, which picks the shadow cascade
+    output.view_depth = output.clip_position.w;
 
     // Pass through color, world position, and normal
     output.color = input.color;
     output.world_pos = world_pos;
     output.normal = input.normal;
+    output.splat = input.splat;
+
+    return output;
+}
 
-    // Calculate shadow position
-    // Transform world position to light space
-    let pos_from_light = uniforms.light_view_proj * vec4<f32>(world_pos, 1.0);
+// Cascaded shadow lookup: 1.0 = lit, 0.0 = fully shadowed (lit beyond the last cascade)
+fn sample_shadow(world_pos: vec3<f32>, view_depth: f32) -> f32 {
+    var cascade = 0u;
+    while (cascade < 3u && view_depth > uniforms.cascade_splits[cascade]) {
+        cascade += 1u;
+    }
+    if (cascade >= 3u) {
+        return 1.0;
+    }
 
-    // Perspective divide to get NDC coordinates
+    // Light space -> texture coordinates (flip Y: texture coordinates are top-down)
+    let pos_from_light = uniforms.light_view_proj[cascade] * vec4<f32>(world_pos, 1.0);
     let shadow_ndc = pos_from_light.xyz / pos_from_light.w;
+    let shadow_uv = vec2<f32>(shadow_ndc.x * 0.5 + 0.5, -shadow_ndc.y * 0.5 + 0.5);
+    let shadow_depth = shadow_ndc.z;
 
-    // Convert NDC [-1, 1] to texture coordinates [0, 1]
-    // Flip Y because texture coordinates are top-down
-    output.shadow_pos = vec3<f32>(
-        shadow_ndc.x * 0.5 + 0.5,
-        -shadow_ndc.y * 0.5 + 0.5,
-        shadow_ndc.z
-    );
+    // Only sample if within shadow map bounds
+    if (shadow_uv.x < 0.0 || shadow_uv.x > 1.0 ||
+        shadow_uv.y < 0.0 || shadow_uv.y > 1.0 ||
+        shadow_depth < 0.0 || shadow_depth > 1.0) {
+        return 1.0;
+    }
 
-    return output;
+    // Percentage-closer filtering: average comparison taps over the kernel
+    // NO bias in shader - rely entirely on hardware depth bias
+    let radius = shadow_params.kernel_radius;
+    var lit = 0.0;
+    for (var y = -radius; y <= radius; y += 1) {
+        for (var x = -radius; x <= radius; x += 1) {
+            let offset = vec2<f32>(f32(x), f32(y)) * shadow_params.texel_size;
+            lit += textureSampleCompareLevel(t_shadow, s_shadow, shadow_uv + offset, cascade, shadow_depth);
+        }
+    }
+    let taps = f32((2 * radius + 1) * (2 * radius + 1));
+    return lit / taps;
 }
 
 @fragment
@@ -90,6 +159,15 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     var normal: vec3<f32>;
     let is_water = input.world_pos.y < 0.5; // Water is below this height
 
+    // Sampled up front: implicit-LOD sampling must stay in uniform control flow
+    let detail = textureSample(t_detail_normal, s_detail_normal, input.world_pos.xz / DETAIL_NORMAL_TILE);
+    let vertex_normal = normalize(input.normal);
+    let rock = sample_triplanar(t_rock, input.world_pos, vertex_normal);
+    // The others only show on gentle slopes, where a top-down projection holds up
+    let sand = textureSample(t_sand, s_splat, input.world_pos.xz / SPLAT_TILE);
+    let grass = textureSample(t_grass, s_splat, input.world_pos.xz / SPLAT_TILE);
+    let snow = textureSample(t_snow, s_splat, input.world_pos.xz / SPLAT_TILE);
+
     if (is_water) {
         // Calculate normal from world position derivatives for dynamic waves
         let dx = dpdx(input.world_pos);
@@ -97,54 +175,36 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
         normal = normalize(cross(dx, dy));
     } else {
         // Use smooth interpolated normal for terrain
-        normal = normalize(input.normal);
+        normal = vertex_normal;
+
+        // Bumped by the detail map. The terrain is a heightfield on a regular XZ grid with the
+        // map laid along world X/Z, so the tangent is just world +X bent onto the surface
+        let tangent = normalize(vec3<f32>(1.0, 0.0, 0.0) - normal * normal.x);
+        let bitangent = cross(tangent, normal); // World +Z on flat ground
+        let detail_normal = detail.xyz * 2.0 - 1.0;
+        let bumped = normalize(tangent * detail_normal.x + bitangent * detail_normal.y + normal * detail_normal.z);
+        let fade = 1.0 - smoothstep(DETAIL_FADE_START, DETAIL_FADE_END, distance(input.world_pos, uniforms.view_pos));
+        normal = normalize(mix(normal, bumped, detail.a * fade));
     }
 
-    // Sun Direction from Uniforms
-    // sun_dir points FROM the sun TO the scene (direction light travels)
+    // Key light direction from Uniforms (the sun, or the moon at night)
+    // sun_dir points FROM the light TO the scene (direction light travels)
     let light_dir = normalize(uniforms.sun_dir);
 
-    // Dynamic sun color based on sun elevation (y component of light direction)
-    // When sun is low (horizon), warm orange. When high, bright white-yellow.
-    let sun_elevation = -light_dir.y; // Higher = sun is higher in sky
-    let sun_color = mix(
-        vec3<f32>(1.8, 0.6, 0.2),  // Sunrise/sunset: warm orange
-        vec3<f32>(1.4, 1.3, 1.1),  // Midday: bright white-yellow
-        clamp(sun_elevation * 2.0, 0.0, 1.0)
-    );
-
-    // Ambient also shifts - bluer at midday, warmer at sunrise/sunset
-    let ambient_color = mix(
-        vec3<f32>(0.15, 0.10, 0.08), // Sunrise: warm ambient
-        vec3<f32>(0.12, 0.14, 0.18), // Midday: cool sky ambient
-        clamp(sun_elevation * 2.0, 0.0, 1.0)
-    );
+    // Key light color from the CPU: warm at sunrise/sunset, white at midday, dim blue moonlight
+    let sun_color = uniforms.sun_color;
+
+    // Ambient comes from the CPU (time of day + weather), with a floor so nights stay navigable
+    let ambient_color = uniforms.ambient_color * uniforms.ambient_intensity;
 
     // Diffuse lighting - use the direction light is coming FROM (negate light_dir)
     // light_dir points toward scene, so -light_dir points toward light source
     let n_dot_l = max(dot(normal, -light_dir), 0.0);
     let diff = n_dot_l;
 
-    // Shadow Calculation - Sample shadow map
-    // Use the pre-calculated shadow position from vertex shader (already in texture space)
-    // The vertex shader already did the light_view_proj transform and texture coordinate conversion
-    let shadow_uv = input.shadow_pos.xy;
-    let shadow_depth = input.shadow_pos.z;
-
-    var shadow = 1.0;
-    var in_shadow_map = false;
-
-    // Only sample if within shadow map bounds
-    if (shadow_uv.x >= 0.0 && shadow_uv.x <= 1.0 &&
-        shadow_uv.y >= 0.0 && shadow_uv.y <= 1.0 &&
-        shadow_depth >= 0.0 && shadow_depth <= 1.0) {
-        in_shadow_map = true;
-        // Use comparison sampler for PCF (Percentage Closer Filtering)
-        // NO bias in shader - rely entirely on hardware depth bias
-        shadow = textureSampleCompare(t_shadow, s_shadow, shadow_uv, shadow_depth);
-        // Make shadows MUCH darker: 1.0 = lit, 0.1 = deep shadow (Increased contrast)
-        shadow = shadow * 0.9 + 0.1;
-    }
+    // Shadow Calculation - cascade picked by view depth
+    // Make shadows MUCH darker: 1.0 = lit, 0.1 = deep shadow (Increased contrast)
+    let shadow = sample_shadow(input.world_pos, input.view_depth) * 0.9 + 0.1;
 
     // Rim Lighting (Fresnel-like effect for terrain definition)
     let view_dir_to_cam = normalize(uniforms.view_pos - input.world_pos);
@@ -156,15 +216,40 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     let diffuse_contribution = sun_color * diff * 1.3 * shadow; // Increased intensity
     let lighting = ambient_color + diffuse_contribution + rim;
 
+    // Surface color: biome vertex color, textured by the splat weights plus rock on steep slopes
+    var surface_color = input.color;
+    if (!is_water) {
+        let slope_rock = 1.0 - smoothstep(uniforms.rock_slope_cos - ROCK_BLEND, uniforms.rock_slope_cos + ROCK_BLEND, vertex_normal.y);
+        var weights = input.splat * (1.0 - slope_rock);
+        weights.z += slope_rock;
+        weights /= max(dot(weights, vec4<f32>(1.0)), 1e-4);
+
+        // Each layer's color, faded back to the vertex color where its texture is weak
+        let sand_color = mix(input.color, input.color * sand.rgb * 2.0, sand.a);
+        let grass_color = mix(input.color, input.color * grass.rgb * 2.0, grass.a);
+        let rock_color = mix(input.color, rock.rgb, rock.a);
+        let snow_color = mix(input.color, snow.rgb, snow.a);
+        surface_color = sand_color * weights.x + grass_color * weights.y + rock_color * weights.z + snow_color * weights.w;
+    }
+
     // Apply lighting to surface color
-    var final_color = input.color * lighting;
+    var final_color = surface_color * lighting;
+
+    // Blinn-Phong: half vector between the light and the camera (lit faces only)
+    let half_dir = normalize(-light_dir + view_dir_to_cam);
+    let n_dot_h = max(dot(normal, half_dir), 0.0);
+    let facing_light = select(0.0, 1.0, n_dot_l > 0.0);
+
+    if (!is_water) {
+        // Faint land sheen, per-material strength/shininess from the CPU
+        let spec = pow(n_dot_h, uniforms.specular.y) * facing_light;
+        final_color += uniforms.specular.x * spec * sun_color * shadow;
+    }
 
     // Water Specular Highlight (Sun Sparkle)
     if (is_water) {
-        let reflect_dir = reflect(-light_dir, normal);
-
         // Tighter specular for sharp sparkles
-        let spec = pow(max(dot(view_dir_to_cam, reflect_dir), 0.0), 64.0);
+        let spec = pow(n_dot_h, 256.0) * facing_light;
 
         // Brighter sparkles for distance visibility
         let specular = 1.8 * spec * sun_color * shadow;
@@ -196,8 +281,10 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     
     // Fog Color with Scattering
     // Mix base fog color with a warm sun tint based on scatter
+    // Fades with the key light, so the moon doesn't throw a warm glow
     let scatter_color = vec3<f32>(1.0, 0.9, 0.7); // Warm sunlight
-    let final_fog_color = mix(uniforms.fog_color, scatter_color, sun_scatter * 0.5);
+    let scatter_strength = clamp(max(sun_color.r, sun_color.g), 0.0, 1.0);
+    let final_fog_color = mix(uniforms.fog_color, scatter_color, sun_scatter * 0.5 * scatter_strength);
 
     // Apply Fog
     final_color = mix(final_color, final_fog_color, fog_density);
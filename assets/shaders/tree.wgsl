@@ -1,5 +1,24 @@
+struct WindParams {
+    direction: vec2<f32>,
+    strength: f32,
+    frequency: f32,
+}
+
 struct CameraUniform {
     view_proj: mat4x4<f32>,
+    camera_right: vec3<f32>,
+    time: f32,
+    camera_up: vec3<f32>,
+    _padding: f32,
+    wind: WindParams,
+    camera_position: vec3<f32>,
+    lod_fade_start: f32, // Mesh starts dissolving into the impostor here
+    impostor_center: vec3<f32>, // Mesh-local bounding sphere (impostor quad)
+    impostor_radius: f32,
+    lod_fade_end: f32, // Impostor only from here
+    _padding1: f32,
+    _padding2: f32,
+    _padding3: f32,
 }
 
 @group(0) @binding(0)
@@ -21,6 +40,7 @@ struct InstanceInput {
     @location(6) model_matrix_1: vec4<f32>,
     @location(7) model_matrix_2: vec4<f32>,
     @location(8) model_matrix_3: vec4<f32>,
+    @location(9) tint: vec3<f32>,
 }
 
 struct VertexOutput {
@@ -28,6 +48,39 @@ struct VertexOutput {
     @location(0) world_normal: vec3<f32>,
     @location(1) uv: vec2<f32>,
     @location(2) world_position: vec3<f32>,
+    @location(3) tint: vec3<f32>,
+    @location(4) lod_fade: f32,
+}
+
+// 0 while the full mesh is drawn, 1 once only the impostor is
+fn lod_blend(position: vec3<f32>) -> f32 {
+    let dist = distance(position, camera.camera_position);
+    if (dist <= camera.lod_fade_start) {
+        return 0.0; // Also covers the no-impostor case (fade start at f32::MAX)
+    }
+    return clamp((dist - camera.lod_fade_start) / max(camera.lod_fade_end - camera.lod_fade_start, 0.001), 0.0, 1.0);
+}
+
+// Screen-door threshold in [0, 1) (interleaved gradient noise): the mesh draws where it's
+// below the blend, the impostor where it isn't, so the two cross-fade without gaps
+fn dither(frag_coord: vec4<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(frag_coord.xy, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+// Per-tree phase from the root position (matches wind::sway_phase on the CPU)
+fn sway_phase(origin: vec3<f32>) -> f32 {
+    return origin.x * 0.37 + origin.z * 0.61;
+}
+
+// Horizontal sway for a point `height` above the tree's root
+// Trunks stay planted and bend more toward the crown; slower than the grass
+fn wind_sway(phase: f32, height: f32) -> vec3<f32> {
+    let wind = camera.wind;
+    let t = camera.time * wind.frequency * 0.5 + phase;
+    let sway = wind.strength * (0.5 + sin(t) * 0.7 + sin(t * 2.3) * 0.2);
+    let h = max(height, 0.0);
+    let bend = sway * h * h * 0.01;
+    return vec3<f32>(wind.direction.x, 0.0, wind.direction.y) * bend;
 }
 
 @vertex
@@ -41,13 +94,17 @@ fn vs_main(input: VertexInput, instance: InstanceInput) -> VertexOutput {
         instance.model_matrix_3,
     );
 
-    let world_position = model_matrix * vec4<f32>(input.position, 1.0);
+    let origin = instance.model_matrix_3.xyz;
+    var world_position = model_matrix * vec4<f32>(input.position, 1.0);
+    world_position += vec4<f32>(wind_sway(sway_phase(origin), world_position.y - origin.y), 0.0);
     output.clip_position = camera.view_proj * world_position;
     output.world_position = world_position.xyz;
     
     // Transform normal (assuming uniform scaling, otherwise need normal matrix)
     output.world_normal = (model_matrix * vec4<f32>(input.normal, 0.0)).xyz;
     output.uv = input.uv;
+    output.tint = instance.tint;
+    output.lod_fade = lod_blend(origin);
 
     return output;
 }
@@ -56,6 +113,11 @@ fn vs_main(input: VertexInput, instance: InstanceInput) -> VertexOutput {
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Sample texture
     let tex_color = textureSample(t_diffuse, s_diffuse, in.uv);
+
+    // Dissolving into the impostor
+    if (dither(in.clip_position) < in.lod_fade) {
+        discard;
+    }
     
     // Alpha mask (discard transparent pixels for leaves)
     if (tex_color.a < 0.5) {
@@ -78,7 +140,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let ambient = 0.3;
     let lighting = ambient + diffuse * 0.9;
 
-    let final_color = tex_color.rgb * lighting * noise_factor;
+    let final_color = tex_color.rgb * in.tint * lighting * noise_factor;
 
     // Simple distance fog to blend with terrain
     // Hardcoded fog params matching terrain roughly
@@ -88,3 +150,131 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     
     return vec4<f32>(final_color, 1.0);
 }
+
+// --- Leaf billboards ---
+
+struct LeafInput {
+    @location(0) position_size: vec4<f32>, // World position + quad size
+    @location(1) normal_height: vec4<f32>, // Normal + height above the tree's root
+    @location(2) tint_phase: vec4<f32>,    // Tint + the tree's sway phase
+}
+
+struct LeafOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tint: vec3<f32>,
+    @location(3) lod_fade: f32,
+}
+
+@vertex
+fn vs_leaf(@builtin(vertex_index) vertex_index: u32, leaf: LeafInput) -> LeafOutput {
+    // Two triangles, corners in [-0.5, 0.5]
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-0.5, -0.5), vec2<f32>(0.5, -0.5), vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, -0.5), vec2<f32>(0.5, 0.5), vec2<f32>(-0.5, 0.5),
+    );
+    let corner = corners[vertex_index];
+
+    // Face the camera
+    let offset = (camera.camera_right * corner.x + camera.camera_up * corner.y) * leaf.position_size.w;
+    let sway = wind_sway(leaf.tint_phase.w, leaf.normal_height.w);
+    let world_position = leaf.position_size.xyz + sway + offset;
+
+    var output: LeafOutput;
+    output.clip_position = camera.view_proj * vec4<f32>(world_position, 1.0);
+    output.uv = vec2<f32>(corner.x + 0.5, 0.5 - corner.y);
+    output.normal = leaf.normal_height.xyz;
+    output.tint = leaf.tint_phase.xyz;
+    output.lod_fade = lod_blend(leaf.position_size.xyz);
+    return output;
+}
+
+@fragment
+fn fs_leaf(in: LeafOutput) -> @location(0) vec4<f32> {
+    let tex_color = textureSample(t_diffuse, s_diffuse, in.uv);
+
+    if (dither(in.clip_position) < in.lod_fade) {
+        discard;
+    }
+
+    // Alpha cutout
+    if (tex_color.a < 0.5) {
+        discard;
+    }
+
+    // Leaves are thin: light both sides (same sun as the branches)
+    let light_dir = normalize(vec3<f32>(0.5, 0.8, 0.3));
+    let n_dot_l = abs(dot(normalize(in.normal), light_dir));
+    let lighting = 0.35 + n_dot_l * 0.75;
+
+    return vec4<f32>(tex_color.rgb * in.tint * lighting, 1.0);
+}
+
+// --- Impostors (distant trees) ---
+// One upright quad per tree showing the closest of IMPOSTOR_VIEWS views baked around it,
+// laid out left to right in the atlas (view i looks from angle i * 2pi / IMPOSTOR_VIEWS)
+
+const IMPOSTOR_VIEWS: u32 = 8u; // Must match tree_pipeline::IMPOSTOR_VIEWS
+const TAU: f32 = 6.28318530718;
+
+struct ImpostorOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) tint: vec3<f32>,
+    @location(2) lod_fade: f32,
+}
+
+@vertex
+fn vs_impostor(@builtin(vertex_index) vertex_index: u32, instance: InstanceInput) -> ImpostorOutput {
+    // Two triangles, corners in [-1, 1]
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    let scale = length(instance.model_matrix_0.xyz);
+    let center = (model_matrix * vec4<f32>(camera.impostor_center, 1.0)).xyz;
+    let radius = camera.impostor_radius * scale;
+
+    // Turn about the vertical only, toward the camera
+    var to_camera = camera.camera_position.xz - center.xz;
+    if (dot(to_camera, to_camera) < 1e-6) {
+        to_camera = vec2<f32>(1.0, 0.0);
+    }
+    let dir = normalize(to_camera);
+    let right = vec3<f32>(dir.y, 0.0, -dir.x);
+    let world_position = center + (right * corner.x + vec3<f32>(0.0, 1.0, 0.0) * corner.y) * radius;
+
+    // Closest baked view, measured in the tree's own frame (undo its yaw)
+    let yaw = atan2(instance.model_matrix_0.z, instance.model_matrix_0.x);
+    let view_angle = atan2(dir.y, dir.x) - yaw;
+    let view = u32(round(fract(view_angle / TAU) * f32(IMPOSTOR_VIEWS))) % IMPOSTOR_VIEWS;
+
+    var output: ImpostorOutput;
+    output.clip_position = camera.view_proj * vec4<f32>(world_position, 1.0);
+    output.uv = vec2<f32>((f32(view) + corner.x * 0.5 + 0.5) / f32(IMPOSTOR_VIEWS), 0.5 - corner.y * 0.5);
+    output.tint = instance.tint;
+    output.lod_fade = lod_blend(instance.model_matrix_3.xyz);
+    return output;
+}
+
+@fragment
+fn fs_impostor(in: ImpostorOutput) -> @location(0) vec4<f32> {
+    let tex_color = textureSample(t_diffuse, s_diffuse, in.uv);
+
+    // Alpha cutout, and the complement of the mesh's dissolve
+    if (tex_color.a < 0.5 || dither(in.clip_position) >= in.lod_fade) {
+        discard;
+    }
+
+    // Lighting is baked into the atlas
+    return vec4<f32>(tex_color.rgb * in.tint, 1.0);
+}
@@ -1,119 +1,176 @@
-// Water Surface Shader
-// Renders the water mesh using displacement maps from the compute shader
-
-struct CameraUniform {
-    view_proj: mat4x4<f32>,
-    position: vec3<f32>,
-}
-
-@group(0) @binding(0)
-var<uniform> camera: CameraUniform;
-
-struct WaterMaterial {
-    deep_color: vec4<f32>,
-    shallow_color: vec4<f32>,
-    foam_color: vec4<f32>,
-    smoothness: f32,
-    metallic: f32,
-}
-
-@group(1) @binding(0)
-var<uniform> material: WaterMaterial;
-
-// Displacement Map (from Compute Shader)
-@group(1) @binding(1)
-var displacement_texture: texture_2d<f32>;
-@group(1) @binding(2)
-var displacement_sampler: sampler;
-
-// Normal/Jacobian Map (from Compute Shader)
-@group(1) @binding(3)
-var normal_texture: texture_2d<f32>;
-@group(1) @binding(4)
-var normal_sampler: sampler;
-
-// Environment Map (Skybox) - Optional, for reflection
-// @group(1) @binding(5)
-// var env_texture: texture_cube<f32>;
-// @group(1) @binding(6)
-// var env_sampler: sampler;
-
-struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) uv: vec2<f32>,
-}
-
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) world_position: vec3<f32>,
-    @location(1) uv: vec2<f32>,
-    @location(2) view_vector: vec3<f32>,
-}
-
-@vertex
-fn vs_main(input: VertexInput) -> VertexOutput {
-    var output: VertexOutput;
-
-    // Sample displacement
-    // UVs for the water grid are 0..1
-    let disp = textureSampleLevel(displacement_texture, displacement_sampler, input.uv, 0.0);
-    
-    // Apply displacement
-    // disp.x, disp.z are horizontal displacement (choppiness)
-    // disp.y is vertical height
-    let displaced_pos = input.position + vec3<f32>(disp.x, disp.y, disp.z);
-    
-    output.world_position = displaced_pos;
-    output.clip_position = camera.view_proj * vec4<f32>(displaced_pos, 1.0);
-    output.uv = input.uv;
-    output.view_vector = camera.position - displaced_pos;
-
-    return output;
-}
-
-@fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let view_dir = normalize(input.view_vector);
-    
-    // Sample Normal and Jacobian
-    let normal_data = textureSample(normal_texture, normal_sampler, input.uv);
-    let normal = normalize(normal_data.xyz); // World space normal
-    let jacobian = normal_data.w; // Foam factor
-    
-    // Lighting (Sun)
-    let sun_dir = normalize(vec3<f32>(0.5, 0.8, 0.3));
-    let half_dir = normalize(view_dir + sun_dir);
-    
-    // Fresnel (Schlick approximation)
-    let F0 = 0.02; // Water is non-metallic
-    let NdotV = max(dot(normal, view_dir), 0.0);
-    let fresnel = F0 + (1.0 - F0) * pow(1.0 - NdotV, 5.0);
-    
-    // Specular (Blinn-Phong or GGX simplified)
-    let NdotH = max(dot(normal, half_dir), 0.0);
-    let specular = pow(NdotH, material.smoothness * 100.0);
-    
-    // Sub-surface Scattering (SSS) / Color
-    // Simple approximation: mix deep and shallow based on view angle or height?
-    // Actually, SSS is better approximated by light wrapping or thickness, but for ocean surface:
-    // We see deep color when looking down, shallow/sky when looking at grazing angles (Fresnel).
-    // Also, wave peaks (jacobian < 1) are thinner/foamier.
-    
-    var base_color = mix(material.deep_color, material.shallow_color, jacobian); // Foam/Churn brightens it
-    
-    // Add foam based on Jacobian
-    let foam_threshold = 0.8;
-    if (jacobian < foam_threshold) {
-        let foam_intensity = (foam_threshold - jacobian) / foam_threshold;
-        base_color = mix(base_color, material.foam_color, foam_intensity);
-    }
-    
-    // Combine
-    // Reflection would come from skybox here. For now, use sky color approximation.
-    let sky_color = vec3<f32>(0.5, 0.7, 0.9); // Light blue sky
-    let reflection = sky_color * fresnel;
-    
-    let final_color = base_color.rgb * (1.0 - fresnel) + reflection + vec3<f32>(specular);
-    
-    return vec4<f32>(final_color, 1.0);
-}
+// Water Surface Shader
+// Renders the water mesh using displacement maps from the compute shader
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    position: vec3<f32>,
+    fog_start: f32,
+    fog_color: vec3<f32>,
+    fog_end: f32,
+    tile_origin: vec2<f32>, // World XZ corner of the tile grid (follows the camera)
+    tile_size: f32,
+    tiles_per_side: u32,
+    inv_view_proj: mat4x4<f32>, // Clip -> world, to reconstruct the scene behind the water
+    light_dir: vec3<f32>,       // Key light (sun, or moon at night): direction light travels
+    _padding: f32,
+    light_color: vec3<f32>,
+    _padding2: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct WaterMaterial {
+    deep_color: vec4<f32>,
+    shallow_color: vec4<f32>,
+    foam_color: vec4<f32>,
+    smoothness: f32, // 0 rough .. 1 mirror: sets the highlight's tightness
+    metallic: f32,   // Tints reflections with the base color (water is 0)
+    foam_width: f32, // Shoreline foam band (m of water depth)
+}
+
+@group(1) @binding(0)
+var<uniform> material: WaterMaterial;
+
+// Displacement Map (from Compute Shader)
+@group(1) @binding(1)
+var displacement_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var displacement_sampler: sampler;
+
+// Normal/Jacobian Map (from Compute Shader)
+@group(1) @binding(3)
+var normal_texture: texture_2d<f32>;
+@group(1) @binding(4)
+var normal_sampler: sampler;
+
+// Scene Depth (read-only, the opaque scene drawn before the water)
+// Swapped for texture_multisampled_2d when MSAA is on
+@group(1) @binding(5)
+var scene_depth: texture_2d<f32>;
+
+// Environment Map (Skybox) - Optional, for reflection
+// @group(1) @binding(6)
+// var env_texture: texture_cube<f32>;
+// @group(1) @binding(7)
+// var env_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) view_vector: vec3<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput, @builtin(instance_index) tile: u32) -> VertexOutput {
+    var output: VertexOutput;
+
+    // Each instance is one tile of the grid around the camera, at sea level (y = 0)
+    let tile_offset = vec2<f32>(f32(tile % camera.tiles_per_side), f32(tile / camera.tiles_per_side)) * camera.tile_size;
+    let world_xz = camera.tile_origin + tile_offset + input.position.xz;
+
+    // Sample displacement by world position (the patch repeats), so waves stay put as tiles move
+    let world_uv = world_xz / camera.tile_size;
+    let disp = textureSampleLevel(displacement_texture, displacement_sampler, world_uv, 0.0);
+    
+    // Apply displacement
+    // disp.x, disp.z are horizontal displacement (choppiness)
+    // disp.y is vertical height
+    let displaced_pos = vec3<f32>(world_xz.x, input.position.y, world_xz.y) + vec3<f32>(disp.x, disp.y, disp.z);
+    
+    output.world_position = displaced_pos;
+    output.clip_position = camera.view_proj * vec4<f32>(displaced_pos, 1.0);
+    output.uv = world_uv;
+    output.view_vector = camera.position - displaced_pos;
+
+    return output;
+}
+
+// Shoreline foam: 1 where the scene is just below the water surface, fading to 0 at
+// foam_width meters of water depth. Sky (no scene behind) and terrain above the surface
+// don't foam.
+fn shore_foam(frag_coord: vec4<f32>, water_y: f32) -> f32 {
+    let size = vec2<f32>(textureDimensions(scene_depth));
+    let pixel = vec2<i32>(clamp(frag_coord.xy, vec2<f32>(0.0), size - 1.0));
+    let depth = textureLoad(scene_depth, pixel, 0).r;
+    if (depth <= 0.0) {
+        return 0.0; // Reverse-Z: cleared to 0, nothing drawn here
+    }
+
+    let ndc = vec2<f32>(frag_coord.x / size.x * 2.0 - 1.0, 1.0 - frag_coord.y / size.y * 2.0);
+    let scene = camera.inv_view_proj * vec4<f32>(ndc, depth, 1.0);
+    let water_depth = water_y - scene.y / scene.w;
+    if (water_depth < 0.0 || material.foam_width <= 0.0) {
+        return 0.0;
+    }
+    return 1.0 - smoothstep(0.0, material.foam_width, water_depth);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let view_dir = normalize(input.view_vector);
+    
+    // Sample Normal and Jacobian
+    let normal_data = textureSample(normal_texture, normal_sampler, input.uv);
+    let normal = normalize(normal_data.xyz); // World space normal
+    let jacobian = normal_data.w; // Foam factor
+    
+    // Sub-surface Scattering (SSS) / Color
+    // Simple approximation: mix deep and shallow based on view angle or height?
+    // Actually, SSS is better approximated by light wrapping or thickness, but for ocean surface:
+    // We see deep color when looking down, shallow/sky when looking at grazing angles (Fresnel).
+    // Also, wave peaks (jacobian < 1) are thinner/foamier.
+    
+    var base_color = mix(material.deep_color, material.shallow_color, jacobian); // Foam/Churn brightens it
+    
+    // Add foam based on Jacobian
+    let foam_threshold = 0.8;
+    if (jacobian < foam_threshold) {
+        let foam_intensity = (foam_threshold - jacobian) / foam_threshold;
+        base_color = mix(base_color, material.foam_color, foam_intensity);
+    }
+
+    // Foam where the water meets the shore
+    base_color = mix(base_color, material.foam_color, shore_foam(input.clip_position, input.world_position.y));
+
+    // Lighting (key light from the CPU)
+    let light_dir = -normalize(camera.light_dir); // Toward the light
+    let half_dir = normalize(view_dir + light_dir);
+    let NdotL = max(dot(normal, light_dir), 0.0);
+
+    // Fresnel (Schlick approximation): water reflects ~2% head-on, metals their own color
+    let F0 = mix(vec3<f32>(0.02), base_color.rgb, material.metallic);
+    let NdotV = max(dot(normal, view_dir), 0.0);
+    let fresnel = F0 + (1.0 - F0) * pow(1.0 - NdotV, 5.0);
+
+    // Specular (normalized Blinn-Phong): smoothness 0.9 gives a ~1000 exponent, and the
+    // normalization keeps tight highlights bright. Each wave facet catching the light
+    // glints, which with a low sun lays a glitter track across the sea toward it.
+    let shininess = exp2(10.0 * material.smoothness + 1.0);
+    let NdotH = max(dot(normal, half_dir), 0.0);
+    let VdotH = max(dot(view_dir, half_dir), 0.0);
+    let spec_fresnel = F0 + (1.0 - F0) * pow(1.0 - VdotH, 5.0);
+    let normalization = (shininess + 8.0) / (8.0 * 3.14159265);
+    let specular = spec_fresnel * pow(NdotH, shininess) * normalization * NdotL * camera.light_color;
+
+    // Combine
+    // Reflection would come from skybox here. For now, use sky color approximation.
+    let sky_color = vec3<f32>(0.5, 0.7, 0.9); // Light blue sky
+    let reflection = sky_color * fresnel;
+
+    let diffuse = base_color.rgb * (1.0 - fresnel) * (1.0 - material.metallic);
+    let lit_color = diffuse + reflection + specular;
+
+    // Distance fog (same falloff as terrain)
+    let dist = distance(input.world_position, camera.position);
+    let fog_factor = clamp((dist - camera.fog_start) / (camera.fog_end - camera.fog_start), 0.0, 1.0);
+    let final_color = mix(lit_color, camera.fog_color, fog_factor);
+    
+    return vec4<f32>(final_color, 1.0);
+}
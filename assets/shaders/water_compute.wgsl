@@ -1,192 +1,166 @@
-// Water Compute Shader
-// Implements Phillips Spectrum generation and IFFT for ocean simulation
-
-// --- Constants ---
-const PI: f32 = 3.14159265359;
-const G: f32 = 9.81;
-const N: u32 = 256u; // Grid size (must match texture size)
-
-struct WaterUniforms {
-    time: f32,
-    delta_time: f32,
-    wind_direction: vec2<f32>,
-    wind_speed: f32,
-    amplitude: f32,
-    choppiness: f32,
-    size: f32, // Physical size of the patch in meters
-}
-
-@group(0) @binding(0)
-var<uniform> uniforms: WaterUniforms;
-
-// H0 (Initial Spectrum) - Complex numbers (Real, Imag)
-// Texture format: Rg32Float
-@group(0) @binding(1)
-var h0_texture: texture_2d<f32>;
-
-// Hkt (Time-dependent Spectrum) - Complex numbers
-// Texture format: Rg32Float (Storage)
-@group(0) @binding(2)
-var<storage, read_write> hkt_texture: array<vec2<f32>>; 
-
-// Butterfly Texture for IFFT
-@group(0) @binding(3)
-var butterfly_texture: texture_2d<f32>;
-
-// Output Displacement Map (XYZ)
-// Texture format: Rgba32Float (Storage)
-@group(0) @binding(4)
-var displacement_texture: texture_storage_2d<rgba32float, write>;
-
-// Output Normal/Jacobian Map
-// Texture format: Rgba32Float (Storage)
-@group(0) @binding(5)
-var normal_map_texture: texture_storage_2d<rgba32float, write>;
-
-// --- Complex Number Math ---
-fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
-    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
-}
-
-fn complex_exp(a: vec2<f32>) -> vec2<f32> {
-    let e = exp(a.x);
-    return vec2<f32>(e * cos(a.y), e * sin(a.y));
-}
-
-// --- Kernel: Generate Spectrum (Time Dependent) ---
-@compute @workgroup_size(16, 16)
-fn generate_spectrum(@builtin(global_invocation_id) id: vec3<u32>) {
-    let x = id.x;
-    let y = id.y;
-    
-    if (x >= N || y >= N) { return; }
-    
-    let index = y * N + x;
-    
-    // Calculate wave vector k
-    let n_float = f32(N);
-    let kx = (2.0 * PI * f32(x) / uniforms.size) - (PI * n_float / uniforms.size);
-    let kz = (2.0 * PI * f32(y) / uniforms.size) - (PI * n_float / uniforms.size);
-    
-    let k_len = sqrt(kx * kx + kz * kz);
-    let w = sqrt(G * k_len); // Dispersion relation for deep water
-    
-    // Load H0(k)
-    // Note: In a real implementation, we'd sample the H0 texture or generate it here if using noise buffers
-    // For now, assuming h0_texture contains pre-calculated Phillips spectrum (or Gaussian noise * Phillips)
-    // Since we can't easily read texture_2d in compute without sampler or storage, let's assume it's a storage buffer for now or use load
-    // Changing binding 1 to storage for simplicity in this draft, or use textureLoad
-    let h0 = textureLoad(h0_texture, vec2<i32>(i32(x), i32(y)), 0).rg;
-    
-    // Calculate H(k, t) = h0(k) * exp(i * w * t)
-    let phase = w * uniforms.time;
-    let exponent = vec2<f32>(0.0, phase);
-    let hkt = complex_mul(h0, complex_exp(exponent));
-    
-    // Store in buffer
-    hkt_texture[index] = hkt;
-    
-    // Also calculate choppiness (displacement in X/Z)
-    // Dx = -i * (kx/k) * hkt
-    // Dz = -i * (kz/k) * hkt
-    // This requires multiple output buffers or a struct, simplified for now to just height (Y)
-}
-
-// --- Kernel: Horizontal IFFT ---
-@compute @workgroup_size(256, 1) // One thread per row
-fn ifft_horizontal(@builtin(global_invocation_id) id: vec3<u32>) {
-    // Simplified butterfly operation placeholder
-    // Real IFFT requires log2(N) stages
-    // For this task, we might use a library or a simpler sum of sines if IFFT is too complex to debug in one go
-    // But the user asked for IFFT.
-    
-    // NOTE: Implementing a full IFFT in a single kernel is hard without shared memory barriers.
-    // Usually done in passes.
-    // For this prototype, let's use a Direct Fourier Transform (DFT) for small N or assume a multi-pass dispatch structure.
-    // Given N=256, DFT is O(N^2) per row, total O(N^3) -> 16 million ops, might be slow but acceptable for a single chunk?
-    // No, N=256 is too big for DFT.
-    
-    // Let's implement a single butterfly stage.
-    // The host code needs to dispatch this log2(N) times.
-    // To avoid complexity, I will implement a "Simulation" kernel that uses Sum of Sines (Gerstner Waves) 
-    // if IFFT proves too difficult to setup without a complex host-side pipeline.
-    
-    // WAIT: The user specifically asked for "taking the IFFT of the Phillips spectrum".
-    // I should try to implement it.
-    
-    // However, setting up the multi-pass IFFT pipeline in `main.rs` is complex.
-    // I will write the shader to support a compute-based IFFT, but maybe start with a simpler Sum of Sines 
-    // that approximates the spectrum to ensure we get *something* on screen, then upgrade.
-    
-    // Actually, let's stick to the plan. I will provide the kernels.
-    // But I need to know the stage index.
-}
-
-// --- Alternative: Sum of Sines (Gerstner) approximation of Phillips ---
-// This is much easier to implement in a single compute dispatch and often looks great.
-// Let's provide this as a fallback or primary implementation if IFFT is too heavy.
-// User asked for IFFT though.
-
-// Let's implement a "Vertex Displacement" kernel that does the IFFT result processing
-// assuming the IFFT has been done or we do a naive summation (slow but correct).
-
-@compute @workgroup_size(16, 16)
-fn compute_displacement(@builtin(global_invocation_id) id: vec3<u32>) {
-    let x = id.x;
-    let y = id.y;
-    if (x >= N || y >= N) { return; }
-    
-    let index = y * N + x;
-    
-    // For now, let's generate a procedural wave height using noise/sines 
-    // to verify the pipeline before debugging a complex IFFT.
-    // This ensures the user sees water immediately.
-    
-    let u = f32(x) / f32(N);
-    let v = f32(y) / f32(N);
-    
-    let world_x = u * uniforms.size;
-    let world_z = v * uniforms.size;
-    
-    var height = 0.0;
-    var dx = 0.0;
-    var dz = 0.0;
-    
-    // Sum of sines based on Phillips-like distribution
-    let num_waves = 16u;
-    for (var i = 0u; i < num_waves; i = i + 1u) {
-        let iter = f32(i);
-        let freq = (2.0 * PI / uniforms.size) * pow(1.18, iter) * 10.0;
-        let amp = uniforms.amplitude * exp(-iter * 0.5) / (freq * 0.5); // Phillips-ish decay
-        let phase = uniforms.time * sqrt(G * freq);
-        
-        let dir_angle = iter * 1.0 + uniforms.wind_direction.x; // Randomize direction slightly
-        let dir = vec2<f32>(cos(dir_angle), sin(dir_angle));
-        
-        let theta = dot(dir, vec2<f32>(world_x, world_z)) * freq + phase;
-        
-        height += amp * cos(theta);
-        
-        // Derivatives for normals
-        let wa = amp * freq * sin(theta);
-        dx -= dir.x * wa;
-        dz -= dir.y * wa;
-    }
-    
-    // Apply choppiness (Trochoidal waves)
-    let chop_x = -dx * uniforms.choppiness;
-    let chop_z = -dz * uniforms.choppiness;
-    
-    textureStore(displacement_texture, vec2<i32>(i32(x), i32(y)), vec4<f32>(chop_x, height, chop_z, 1.0));
-    
-    // Calculate Normal
-    // N = (-dh/dx, 1, -dh/dz)
-    let normal = normalize(vec3<f32>(-dx, 1.0, -dz));
-    
-    // Jacobian (determinant of transformation Jacobian) for foam/churn
-    // J = Jxx * Jzz - Jxz * Jzx
-    // Simplified: just use height peaks for foam for now
-    let jacobian = clamp(1.0 - (dx * dx + dz * dz), 0.0, 1.0);
-    
-    textureStore(normal_map_texture, vec2<i32>(i32(x), i32(y)), vec4<f32>(normal, jacobian));
-}
+// Water Compute Shader
+// Tessendorf ocean: animates the Phillips spectrum H0 (built on the CPU) and takes its
+// inverse FFT with radix-2 butterfly passes to get displacement, normals and foam.
+//
+// Per frame: generate_spectrum, then log2(N) fft_horizontal and log2(N) fft_vertical passes
+// ping-ponging between two buffers (an even count, so the result lands back in the first),
+// then resolve.
+
+// --- Constants ---
+const PI: f32 = 3.14159265359;
+const G: f32 = 9.81;
+const N: u32 = 256u; // Grid size (must match texture size)
+
+struct WaterUniforms {
+    time: f32,
+    delta_time: f32,
+    wind_direction: vec2<f32>,
+    wind_speed: f32,
+    amplitude: f32,
+    choppiness: f32,
+    size: f32, // Physical size of the patch in meters
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: WaterUniforms;
+
+// H0 (Initial Spectrum): (h0(k), conj(h0(-k))) as two complex numbers
+// Texture format: Rgba32Float
+@group(0) @binding(1)
+var h0_texture: texture_2d<f32>;
+
+// Butterfly table, one column per FFT stage: (twiddle re, twiddle im, input index a, input index b)
+// Texture format: Rgba32Float, log2(N) x N
+@group(0) @binding(2)
+var butterfly_texture: texture_2d<f32>;
+
+// Output Displacement Map (XYZ)
+// Texture format: Rgba32Float (Storage)
+@group(0) @binding(3)
+var displacement_texture: texture_storage_2d<rgba32float, write>;
+
+// Output Normal/Jacobian Map
+// Texture format: Rgba32Float (Storage)
+@group(0) @binding(4)
+var normal_map_texture: texture_storage_2d<rgba32float, write>;
+
+// Ping-pong spectrum buffers: 4 complex signals packed two per vec4, N*N vec4s per pair
+//   pair 0: (height + i*Dx, Dz + i*slope_x)
+//   pair 1: (slope_z + i*dDx/dx, dDz/dz + i*dDx/dz)
+// Each signal is real in the spatial domain, so two share one complex IFFT.
+@group(1) @binding(0)
+var<storage, read> src: array<vec4<f32>>;
+@group(1) @binding(1)
+var<storage, read_write> dst: array<vec4<f32>>;
+
+struct FftStage {
+    stage: u32,
+}
+
+@group(2) @binding(0)
+var<uniform> fft: FftStage;
+
+// --- Complex Number Math ---
+fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn complex_exp_i(phase: f32) -> vec2<f32> {
+    return vec2<f32>(cos(phase), sin(phase));
+}
+
+// a + i*b for complex a, b
+fn pack(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x - b.y, a.y + b.x);
+}
+
+// --- Kernel: Generate Spectrum (Time Dependent) ---
+@compute @workgroup_size(16, 16)
+fn generate_spectrum(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = id.x;
+    let y = id.y;
+    if (x >= N || y >= N) { return; }
+
+    let index = y * N + x;
+
+    // Wave vector k (centered, so the DC term sits at N/2)
+    let kx = 2.0 * PI * (f32(x) - f32(N / 2u)) / uniforms.size;
+    let kz = 2.0 * PI * (f32(y) - f32(N / 2u)) / uniforms.size;
+    let k_len = max(sqrt(kx * kx + kz * kz), 0.0001);
+    let w = sqrt(G * k_len); // Dispersion relation for deep water
+
+    // H(k, t) = h0(k) * exp(i*w*t) + conj(h0(-k)) * exp(-i*w*t)
+    let h0 = textureLoad(h0_texture, vec2<i32>(i32(x), i32(y)), 0);
+    let h = complex_mul(h0.xy, complex_exp_i(w * uniforms.time)) + complex_mul(h0.zw, complex_exp_i(-w * uniforms.time));
+
+    // Horizontal displacement D = -i * (k / |k|) * h, slopes i * k * h, and the
+    // displacement derivatives (for the Jacobian) k_a * k_b / |k| * h
+    let ih = vec2<f32>(-h.y, h.x);
+    let dx = -ih * (kx / k_len);
+    let dz = -ih * (kz / k_len);
+    let slope_x = ih * kx;
+    let slope_z = ih * kz;
+    let dxdx = h * (kx * kx / k_len);
+    let dzdz = h * (kz * kz / k_len);
+    let dxdz = h * (kx * kz / k_len);
+
+    dst[index] = vec4<f32>(pack(h, dx), pack(dz, slope_x));
+    dst[N * N + index] = vec4<f32>(pack(slope_z, dxdx), pack(dzdz, dxdz));
+}
+
+// One butterfly: out = in[a] + twiddle * in[b], for both packed pairs
+fn butterfly(out_index: u32, a: u32, b: u32, twiddle: vec2<f32>) {
+    for (var pair = 0u; pair < 2u; pair = pair + 1u) {
+        let base = pair * N * N;
+        let p = src[base + a];
+        let q = src[base + b];
+        dst[base + out_index] = vec4<f32>(p.xy + complex_mul(twiddle, q.xy), p.zw + complex_mul(twiddle, q.zw));
+    }
+}
+
+// --- Kernel: One IFFT stage along rows ---
+@compute @workgroup_size(16, 16)
+fn fft_horizontal(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= N || id.y >= N) { return; }
+    let data = textureLoad(butterfly_texture, vec2<i32>(i32(fft.stage), i32(id.x)), 0);
+    let row = id.y * N;
+    butterfly(row + id.x, row + u32(data.z), row + u32(data.w), data.xy);
+}
+
+// --- Kernel: One IFFT stage along columns ---
+@compute @workgroup_size(16, 16)
+fn fft_vertical(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= N || id.y >= N) { return; }
+    let data = textureLoad(butterfly_texture, vec2<i32>(i32(fft.stage), i32(id.y)), 0);
+    butterfly(id.y * N + id.x, u32(data.z) * N + id.x, u32(data.w) * N + id.x, data.xy);
+}
+
+// --- Kernel: Unpack the spatial signals into the displacement and normal maps ---
+@compute @workgroup_size(16, 16)
+fn resolve(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = id.x;
+    let y = id.y;
+    if (x >= N || y >= N) { return; }
+
+    let index = y * N + x;
+
+    // Undo the centered spectrum: exp(i * (n - N/2) * 2pi * m / N) = (-1)^m * exp(i * 2pi * n * m / N)
+    let sign = select(1.0, -1.0, ((x + y) & 1u) == 1u);
+    let p0 = src[index] * sign;
+    let p1 = src[N * N + index] * sign;
+
+    let height = p0.x;
+    let chop = uniforms.choppiness;
+    textureStore(displacement_texture, vec2<i32>(i32(x), i32(y)), vec4<f32>(p0.y * chop, height, p0.z * chop, 1.0));
+
+    // N = (-dh/dx, 1, -dh/dz)
+    let normal = normalize(vec3<f32>(-p0.w, 1.0, -p1.x));
+
+    // Jacobian of the horizontal displacement: < 1 where the surface compresses (crests fold, foam)
+    let jxx = 1.0 + chop * p1.y;
+    let jzz = 1.0 + chop * p1.z;
+    let jxz = chop * p1.w;
+    let jacobian = clamp(jxx * jzz - jxz * jxz, 0.0, 1.0);
+
+    textureStore(normal_map_texture, vec2<i32>(i32(x), i32(y)), vec4<f32>(normal, jacobian));
+}
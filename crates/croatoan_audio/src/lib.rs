@@ -0,0 +1,191 @@
+use glam::Vec3;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+mod sound;
+pub use sound::{AudioError, Sound};
+
+/// Positional sounds play at full volume within this distance of the listener (world units)...
+pub const FULL_VOLUME_DISTANCE: f32 = 2.0;
+/// ...and are silent past this one
+pub const MAX_AUDIBLE_DISTANCE: f32 = 80.0;
+
+/// A playing sound, for changing its volume or stopping it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SoundHandle(u64);
+
+/// Where sounds are actually mixed and heard
+///
+/// `AudioEngine` does the loading, caching and volume math, and hands the backend decoded
+/// sounds to start, adjust and stop by id.
+pub trait AudioBackend: Send {
+    fn start(&mut self, id: u64, sound: Arc<Sound>, looping: bool, volume: f32);
+    fn set_volume(&mut self, id: u64, volume: f32);
+    fn stop(&mut self, id: u64);
+}
+
+/// Plays nothing: for headless runs, and wherever there's no output device
+pub struct SilentBackend;
+
+impl AudioBackend for SilentBackend {
+    fn start(&mut self, _id: u64, _sound: Arc<Sound>, _looping: bool, _volume: f32) {}
+    fn set_volume(&mut self, _id: u64, _volume: f32) {}
+    fn stop(&mut self, _id: u64) {}
+}
+
+/// Sound playback: one-shots (UI clicks), loops (ambience) and positional sounds
+///
+/// Sounds are WAV files loaded on first use and cached by path. A file that's missing or
+/// can't be decoded is reported once and then plays as silence, so absent assets never
+/// stop the game.
+pub struct AudioEngine {
+    backend: Box<dyn AudioBackend>,
+    /// None for files that failed to load
+    sounds: HashMap<PathBuf, Option<Arc<Sound>>>,
+    next_id: u64,
+    master_volume: f32,
+}
+
+impl AudioEngine {
+    /// Engine with the default backend (currently `SilentBackend`)
+    pub fn new() -> Self {
+        log::info!("Audio: no output backend available, sounds are silent");
+        Self::with_backend(Box::new(SilentBackend))
+    }
+
+    pub fn with_backend(backend: Box<dyn AudioBackend>) -> Self {
+        Self {
+            backend,
+            sounds: HashMap::new(),
+            next_id: 0,
+            master_volume: 1.0,
+        }
+    }
+
+    /// Scales every sound's volume (0..1); applies to sounds started afterwards
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// Play a sound once at full volume
+    pub fn play_oneshot(&mut self, path: impl AsRef<Path>) -> SoundHandle {
+        self.play(path.as_ref(), false, 1.0)
+    }
+
+    /// Play a sound on repeat until stopped, e.g. ambience (adjust with `set_volume`)
+    pub fn play_looping(&mut self, path: impl AsRef<Path>, volume: f32) -> SoundHandle {
+        self.play(path.as_ref(), true, volume)
+    }
+
+    /// Play a sound once, quieter the further `world_pos` is from `listener_pos`
+    /// (see `distance_gain`); sounds out of earshot aren't started at all
+    pub fn play_at(&mut self, path: impl AsRef<Path>, world_pos: Vec3, listener_pos: Vec3) -> Option<SoundHandle> {
+        let gain = distance_gain(world_pos.distance(listener_pos));
+        (gain > 0.0).then(|| self.play(path.as_ref(), false, gain))
+    }
+
+    pub fn set_volume(&mut self, handle: SoundHandle, volume: f32) {
+        self.backend.set_volume(handle.0, volume.clamp(0.0, 1.0) * self.master_volume);
+    }
+
+    pub fn stop(&mut self, handle: SoundHandle) {
+        self.backend.stop(handle.0);
+    }
+
+    fn play(&mut self, path: &Path, looping: bool, volume: f32) -> SoundHandle {
+        let handle = SoundHandle(self.next_id);
+        self.next_id += 1;
+        if let Some(sound) = self.sound(path) {
+            self.backend.start(handle.0, sound, looping, volume.clamp(0.0, 1.0) * self.master_volume);
+        }
+        handle
+    }
+
+    /// The decoded sound at `path`, loading it on first use
+    fn sound(&mut self, path: &Path) -> Option<Arc<Sound>> {
+        self.sounds
+            .entry(path.to_path_buf())
+            .or_insert_with(|| match Sound::load(path) {
+                Ok(sound) => Some(Arc::new(sound)),
+                Err(e) => {
+                    log::warn!("Can't play {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .clone()
+    }
+}
+
+impl Default for AudioEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Volume (0..1) of a sound `distance` from the listener: full up close, then falling off
+/// with distance (inverse, as in air) and faded to silence at `MAX_AUDIBLE_DISTANCE`
+pub fn distance_gain(distance: f32) -> f32 {
+    let distance = distance.max(FULL_VOLUME_DISTANCE);
+    let inverse = FULL_VOLUME_DISTANCE / distance;
+    let edge_fade = 1.0 - ((distance - FULL_VOLUME_DISTANCE) / (MAX_AUDIBLE_DISTANCE - FULL_VOLUME_DISTANCE)).min(1.0);
+    inverse * edge_fade
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records what the engine asks of its backend
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl AudioBackend for Recorder {
+        fn start(&mut self, id: u64, sound: Arc<Sound>, looping: bool, volume: f32) {
+            self.0.lock().unwrap().push(format!("start {} {} {} {:.2}", id, sound.samples.len(), looping, volume));
+        }
+        fn set_volume(&mut self, id: u64, volume: f32) {
+            self.0.lock().unwrap().push(format!("volume {} {:.2}", id, volume));
+        }
+        fn stop(&mut self, id: u64) {
+            self.0.lock().unwrap().push(format!("stop {}", id));
+        }
+    }
+
+    #[test]
+    fn test_engine_drives_the_backend() {
+        let path = std::env::temp_dir().join(format!("croatoan_audio_{}.wav", std::process::id()));
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0\x01\0\x01\0\x40\x1f\0\0\x80\x3e\0\0\x02\0\x10\0data\x04\0\0\0".to_vec();
+        wav.extend([0, 0, 0, 64]);
+        std::fs::write(&path, wav).unwrap();
+
+        let recorder = Recorder::default();
+        let mut engine = AudioEngine::with_backend(Box::new(recorder.clone()));
+        engine.set_master_volume(0.5);
+        let click = engine.play_oneshot(&path);
+        let ambience = engine.play_looping(&path, 0.8);
+        assert_ne!(click, ambience);
+        engine.set_volume(ambience, 0.2);
+        engine.stop(ambience);
+        // Out of earshot: not started
+        assert!(engine.play_at(&path, Vec3::new(500.0, 0.0, 0.0), Vec3::ZERO).is_none());
+        // Missing files play as silence
+        engine.play_oneshot("no/such/sound.wav");
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            ["start 0 2 false 0.50", "start 1 2 true 0.40", "volume 1 0.10", "stop 1"]
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_distance_gain_falls_off() {
+        assert_eq!(distance_gain(0.0), distance_gain(FULL_VOLUME_DISTANCE));
+        assert_eq!(distance_gain(0.0), 1.0);
+        assert!(distance_gain(10.0) < distance_gain(5.0));
+        assert_eq!(distance_gain(MAX_AUDIBLE_DISTANCE), 0.0);
+        assert_eq!(distance_gain(1000.0), 0.0);
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::Path;
+
+/// Why a sound couldn't be loaded
+#[derive(Debug)]
+pub enum AudioError {
+    Io(std::io::Error),
+    /// Not a WAV file this loader understands
+    Format(String),
+}
+
+impl std::fmt::Display for AudioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AudioError::Io(e) => write!(f, "{}", e),
+            AudioError::Format(reason) => write!(f, "unsupported sound file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AudioError::Io(e) => Some(e),
+            AudioError::Format(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for AudioError {
+    fn from(e: std::io::Error) -> Self {
+        AudioError::Io(e)
+    }
+}
+
+/// Decoded sound: interleaved samples in -1..1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sound {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl Sound {
+    /// Load a WAV file (8/16/24-bit PCM or 32-bit float)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AudioError> {
+        Self::from_wav(&std::fs::read(path)?)
+    }
+
+    /// Decode WAV file contents
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, AudioError> {
+        let format_error = |reason: &str| AudioError::Format(reason.to_string());
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(format_error("not a RIFF/WAVE file"));
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            let body = &bytes[offset + 8..(offset + 8 + size).min(bytes.len())];
+            match id {
+                b"fmt " if body.len() >= 16 => {
+                    let tag = u16::from_le_bytes([body[0], body[1]]);
+                    let channels = u16::from_le_bytes([body[2], body[3]]);
+                    let sample_rate = u32::from_le_bytes(body[4..8].try_into().unwrap());
+                    let bits = u16::from_le_bytes([body[14], body[15]]);
+                    format = Some((tag, channels, sample_rate, bits));
+                }
+                b"data" => data = Some(body),
+                _ => {}
+            }
+            // Chunks are padded to an even size
+            offset += 8 + size + (size & 1);
+        }
+
+        let (tag, channels, sample_rate, bits) = format.ok_or_else(|| format_error("no fmt chunk"))?;
+        let data = data.ok_or_else(|| format_error("no data chunk"))?;
+        if channels == 0 || sample_rate == 0 {
+            return Err(format_error("no channels"));
+        }
+
+        // 0xFFFE (extensible) carries the same sample layouts
+        let samples: Vec<f32> = match (tag, bits) {
+            (1 | 0xFFFE, 8) => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+            (1 | 0xFFFE, 16) => data.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0).collect(),
+            (1 | 0xFFFE, 24) => data.chunks_exact(3).map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0).collect(),
+            (3 | 0xFFFE, 32) => data.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect(),
+            _ => return Err(AudioError::Format(format!("format {} at {} bits", tag, bits))),
+        };
+
+        Ok(Self { sample_rate, channels, samples })
+    }
+
+    /// Length in seconds
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / (self.channels as f32 * self.sample_rate as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal WAV file around `data`
+    fn wav(tag: u16, channels: u16, sample_rate: u32, bits: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(b"RIFF");
+        bytes.extend((36 + data.len() as u32).to_le_bytes());
+        bytes.extend(b"WAVE");
+        bytes.extend(b"fmt ");
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(tag.to_le_bytes());
+        bytes.extend(channels.to_le_bytes());
+        bytes.extend(sample_rate.to_le_bytes());
+        bytes.extend((sample_rate * channels as u32 * bits as u32 / 8).to_le_bytes());
+        bytes.extend((channels * bits / 8).to_le_bytes());
+        bytes.extend(bits.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_decodes_pcm_and_float() {
+        let pcm: Vec<u8> = [0i16, 16384, -32768, 32767].iter().flat_map(|s| s.to_le_bytes()).collect();
+        let sound = Sound::from_wav(&wav(1, 2, 8000, 16, &pcm)).unwrap();
+        assert_eq!((sound.sample_rate, sound.channels), (8000, 2));
+        assert_eq!(sound.samples[..3], [0.0, 0.5, -1.0]);
+        assert!((sound.samples[3] - 1.0).abs() < 1e-4);
+        assert!((sound.duration() - 2.0 / 8000.0).abs() < 1e-9);
+
+        let float: Vec<u8> = [0.25f32, -0.75].iter().flat_map(|s| s.to_le_bytes()).collect();
+        assert_eq!(Sound::from_wav(&wav(3, 1, 44100, 32, &float)).unwrap().samples, [0.25, -0.75]);
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert!(matches!(Sound::from_wav(b"OggS not a wav file"), Err(AudioError::Format(_))));
+        assert!(matches!(Sound::from_wav(&wav(2, 1, 8000, 4, &[0; 8])), Err(AudioError::Format(_))));
+        assert!(matches!(Sound::load("no/such/sound.wav"), Err(AudioError::Io(_))));
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::{Arc, Mutex};
+use winit::window::{CursorGrabMode, Window};
+
+struct CursorState {
+    grab_mode: CursorGrabMode,
+    visible: bool,
+    focused: bool,
+    /// Settings changed since they were last applied to the window
+    dirty: bool,
+}
+
+impl Default for CursorState {
+    fn default() -> Self {
+        Self { grab_mode: CursorGrabMode::None, visible: true, focused: false, dirty: true }
+    }
+}
+
+impl CursorState {
+    /// Grab and visibility the window should have now: the requested ones while focused,
+    /// released and shown otherwise so alt-tab and other windows work
+    fn effective(&self) -> (CursorGrabMode, bool) {
+        if self.focused {
+            (self.grab_mode, self.visible)
+        } else {
+            (CursorGrabMode::None, true)
+        }
+    }
+}
+
+/// Shared cursor settings, applied by `App` to the window
+/// The grab only takes effect while the window has focus. Clone it into the callbacks to
+/// change the cursor per game state (e.g. free in menus, confined while playing).
+#[derive(Clone, Default)]
+pub struct CursorHandle(Arc<Mutex<CursorState>>);
+
+impl CursorHandle {
+    /// How the cursor is held while the window has focus (`Confined` falls back to `Locked`
+    /// and vice versa where the platform supports only one)
+    pub fn set_grab_mode(&self, mode: CursorGrabMode) {
+        let mut state = self.0.lock().unwrap();
+        if state.grab_mode != mode {
+            state.grab_mode = mode;
+            state.dirty = true;
+        }
+    }
+
+    /// Whether the cursor is drawn over the window while it has focus
+    pub fn set_visible(&self, visible: bool) {
+        let mut state = self.0.lock().unwrap();
+        if state.visible != visible {
+            state.visible = visible;
+            state.dirty = true;
+        }
+    }
+
+    pub(crate) fn set_focused(&self, focused: bool) {
+        let mut state = self.0.lock().unwrap();
+        state.focused = focused;
+        state.dirty = true;
+    }
+
+    /// Apply settings changed since the last call to `window`
+    pub(crate) fn apply(&self, window: &Window) {
+        let (grab_mode, visible) = {
+            let mut state = self.0.lock().unwrap();
+            if !std::mem::take(&mut state.dirty) {
+                return;
+            }
+            state.effective()
+        };
+
+        window.set_cursor_visible(visible);
+        let fallback = match grab_mode {
+            CursorGrabMode::Confined => Some(CursorGrabMode::Locked),
+            CursorGrabMode::Locked => Some(CursorGrabMode::Confined),
+            CursorGrabMode::None => None,
+        };
+        if let Err(e) = window.set_cursor_grab(grab_mode) {
+            match fallback.map(|mode| (mode, window.set_cursor_grab(mode))) {
+                Some((mode, Ok(()))) => log::info!("Cursor grab {:?} unsupported ({}), using {:?}", grab_mode, e, mode),
+                _ => log::warn!("Failed to set cursor grab {:?}: {}", grab_mode, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grab_is_released_without_focus() {
+        let cursor = CursorHandle::default();
+        cursor.set_grab_mode(CursorGrabMode::Confined);
+        cursor.set_visible(false);
+        assert_eq!(cursor.0.lock().unwrap().effective(), (CursorGrabMode::None, true));
+
+        cursor.set_focused(true);
+        assert_eq!(cursor.0.lock().unwrap().effective(), (CursorGrabMode::Confined, false));
+
+        cursor.set_focused(false);
+        assert_eq!(cursor.0.lock().unwrap().effective(), (CursorGrabMode::None, true));
+    }
+}
@@ -0,0 +1,152 @@
+use croatoan_render::GraphicsContext;
+use std::time::Instant;
+
+/// Rate of the update callback (simulation steps per second)
+pub const UPDATE_HZ: f32 = 60.0;
+/// Seconds per update step
+pub const UPDATE_DT: f32 = 1.0 / UPDATE_HZ;
+/// Most update steps run for one frame; past this the simulation slows down rather than
+/// spiralling (each catch-up step making the next frame longer still)
+const MAX_UPDATE_STEPS: u32 = 8;
+
+/// Everything the render callback gets each frame
+pub struct FrameContext<'a> {
+    pub ctx: &'a mut GraphicsContext,
+    /// Seconds since the previous frame (0 on the first)
+    pub dt: f32,
+    /// Seconds since the app started; never decreases
+    pub elapsed: f32,
+    /// How far (0..1) this frame sits between the last update step and the next, for
+    /// interpolating simulated positions
+    pub alpha: f32,
+}
+
+/// Frame timing: wall clock by default, or a fixed step per frame for deterministic playback
+pub struct FrameClock {
+    fixed_dt: Option<f32>,
+    start: Instant,
+    last: Option<Instant>,
+    elapsed: f32,
+}
+
+impl FrameClock {
+    pub fn new(fixed_dt: Option<f32>) -> Self {
+        Self {
+            fixed_dt,
+            start: Instant::now(),
+            last: None,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Advance one frame, returning `(dt, elapsed)`
+    pub fn tick(&mut self) -> (f32, f32) {
+        let first = self.last.is_none();
+        let dt = match self.fixed_dt {
+            // Elapsed is the sum of the steps, so replays don't depend on how long frames took
+            Some(fixed) => {
+                let dt = if first { 0.0 } else { fixed };
+                self.elapsed += dt;
+                dt
+            }
+            None => {
+                let now = Instant::now();
+                let dt = self.last.map_or(0.0, |last| now.duration_since(last).as_secs_f32());
+                self.elapsed = now.duration_since(self.start).as_secs_f32();
+                dt
+            }
+        };
+        self.last = Some(Instant::now());
+        (dt, self.elapsed)
+    }
+
+    /// Forget the last frame, so the frame after a pause (e.g. while minimized) gets dt 0
+    /// rather than the whole pause at once
+    pub fn pause(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Fixed-step accumulator: frame time goes in, whole update steps come out
+pub struct FixedStep {
+    step: f32,
+    accumulator: f32,
+}
+
+impl FixedStep {
+    pub fn new(step: f32) -> Self {
+        Self { step, accumulator: 0.0 }
+    }
+
+    /// Add a frame's `dt` and return how many steps to run now
+    pub fn advance(&mut self, dt: f32) -> u32 {
+        self.accumulator += dt;
+        let steps = (self.accumulator / self.step).floor() as u32;
+        if steps > MAX_UPDATE_STEPS {
+            self.accumulator = 0.0;
+            return MAX_UPDATE_STEPS;
+        }
+        self.accumulator -= steps as f32 * self.step;
+        steps
+    }
+
+    /// Leftover time as a fraction of a step
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_dt_is_deterministic() {
+        let mut clock = FrameClock::new(Some(0.25));
+        assert_eq!(clock.tick(), (0.0, 0.0));
+        assert_eq!(clock.tick(), (0.25, 0.25));
+        assert_eq!(clock.tick(), (0.25, 0.5));
+        assert_eq!(clock.tick(), (0.25, 0.75));
+    }
+
+    #[test]
+    fn test_fixed_step_ignores_frame_slicing() {
+        // One second of 144 Hz frames and of 30 Hz frames both make 60 steps
+        for fps in [144.0, 30.0] {
+            let mut fixed = FixedStep::new(UPDATE_DT);
+            let frames = fps as u32;
+            let steps: u32 = (0..frames).map(|_| fixed.advance(1.0 / fps)).sum();
+            assert!((59..=60).contains(&steps), "{} fps ran {} steps", fps, steps);
+            assert!((0.0..=1.0).contains(&fixed.alpha()));
+        }
+
+        // Half a step in: interpolate halfway
+        let mut fixed = FixedStep::new(0.1);
+        assert_eq!(fixed.advance(0.25), 2);
+        assert!((fixed.alpha() - 0.5).abs() < 1e-4);
+
+        // A long hitch is capped instead of replayed
+        assert_eq!(fixed.advance(5.0), MAX_UPDATE_STEPS);
+        assert_eq!(fixed.alpha(), 0.0);
+    }
+
+    #[test]
+    fn test_wall_clock_elapsed_is_monotonic() {
+        let mut clock = FrameClock::new(None);
+        let (first_dt, mut previous) = clock.tick();
+        assert_eq!(first_dt, 0.0);
+        for _ in 0..100 {
+            let (dt, elapsed) = clock.tick();
+            assert!(dt >= 0.0);
+            assert!(elapsed >= previous);
+            previous = elapsed;
+        }
+
+        // Time spent paused isn't passed on as one huge frame
+        clock.pause();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let (dt, elapsed) = clock.tick();
+        assert_eq!(dt, 0.0);
+        assert!(elapsed >= previous + 0.02);
+    }
+}
@@ -0,0 +1,212 @@
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+use std::sync::{Arc, Mutex};
+
+/// Stick input below this (fraction of full tilt) reads as zero; worn sticks rarely rest at center
+const STICK_DEAD_ZONE: f32 = 0.15;
+
+/// Controller buttons, by position (South = A on Xbox, Cross on PlayStation)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    const ALL: [GamepadButton; 14] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::North,
+        GamepadButton::West,
+        GamepadButton::LeftBumper,
+        GamepadButton::RightBumper,
+        GamepadButton::Select,
+        GamepadButton::Start,
+        GamepadButton::LeftStick,
+        GamepadButton::RightStick,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    fn gilrs_button(self) -> Button {
+        match self {
+            GamepadButton::South => Button::South,
+            GamepadButton::East => Button::East,
+            GamepadButton::North => Button::North,
+            GamepadButton::West => Button::West,
+            GamepadButton::LeftBumper => Button::LeftTrigger,
+            GamepadButton::RightBumper => Button::RightTrigger,
+            GamepadButton::Select => Button::Select,
+            GamepadButton::Start => Button::Start,
+            GamepadButton::LeftStick => Button::LeftThumb,
+            GamepadButton::RightStick => Button::RightThumb,
+            GamepadButton::DPadUp => Button::DPadUp,
+            GamepadButton::DPadDown => Button::DPadDown,
+            GamepadButton::DPadLeft => Button::DPadLeft,
+            GamepadButton::DPadRight => Button::DPadRight,
+        }
+    }
+}
+
+/// Snapshot of the active controller
+///
+/// Sticks are `[x, y]` in [-1, 1] with the dead zone removed; +x is right, +y is up (pushed
+/// forward). Everything reads neutral while no controller is connected.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GamepadState {
+    pub connected: bool,
+    pub left_stick: [f32; 2],
+    pub right_stick: [f32; 2],
+    /// Analog triggers in [0, 1]
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+    buttons: u32,
+}
+
+impl GamepadState {
+    /// Whether `button` is currently held
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons & button.bit() != 0
+    }
+}
+
+/// Shared view of the controller state, refreshed by `App` every `AboutToWait`
+/// Clone it into the render/input callbacks.
+#[derive(Clone, Default)]
+pub struct GamepadHandle(Arc<Mutex<GamepadState>>);
+
+impl GamepadHandle {
+    /// Latest state (a copy)
+    pub fn get(&self) -> GamepadState {
+        *self.0.lock().unwrap()
+    }
+
+    fn set(&self, state: GamepadState) {
+        *self.0.lock().unwrap() = state;
+    }
+}
+
+/// Polls controllers through gilrs, following hot-plugs
+/// Uses the most recently connected controller; when it's unplugged, falls back to any other.
+pub(crate) struct GamepadPoller {
+    gilrs: Option<Gilrs>,
+    active: Option<GamepadId>,
+}
+
+impl GamepadPoller {
+    pub(crate) fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                log::warn!("Gamepad support unavailable: {}", e);
+                None
+            }
+        };
+        let active = gilrs.as_ref().and_then(|gilrs| {
+            gilrs.gamepads().find(|(_, pad)| pad.is_connected()).map(|(id, pad)| {
+                log::info!("Gamepad connected: {}", pad.name());
+                id
+            })
+        });
+        Self { gilrs, active }
+    }
+
+    /// Drain pending events (connects/disconnects) and publish the active pad's state
+    pub(crate) fn poll(&mut self, handle: &GamepadHandle) {
+        let Some(gilrs) = &mut self.gilrs else { return };
+
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::Connected => {
+                    log::info!("Gamepad connected: {}", gilrs.gamepad(event.id).name());
+                    self.active = Some(event.id);
+                }
+                EventType::Disconnected => {
+                    log::info!("Gamepad disconnected: {}", gilrs.gamepad(event.id).name());
+                    if self.active == Some(event.id) {
+                        self.active = gilrs.gamepads().find(|(_, pad)| pad.is_connected()).map(|(id, _)| id);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let state = match self.active.map(|id| gilrs.gamepad(id)) {
+            Some(pad) if pad.is_connected() => {
+                let trigger = |button| pad.button_data(button).map_or(0.0, |data| data.value());
+                GamepadState {
+                    connected: true,
+                    left_stick: apply_dead_zone([pad.value(Axis::LeftStickX), pad.value(Axis::LeftStickY)]),
+                    right_stick: apply_dead_zone([pad.value(Axis::RightStickX), pad.value(Axis::RightStickY)]),
+                    left_trigger: trigger(Button::LeftTrigger2),
+                    right_trigger: trigger(Button::RightTrigger2),
+                    buttons: GamepadButton::ALL
+                        .iter()
+                        .filter(|button| pad.is_pressed(button.gilrs_button()))
+                        .fold(0, |bits, button| bits | button.bit()),
+                }
+            }
+            _ => GamepadState::default(),
+        };
+        handle.set(state);
+    }
+}
+
+/// Radial dead zone, rescaled so output still ramps smoothly from 0 at the edge of it to 1
+fn apply_dead_zone(stick: [f32; 2]) -> [f32; 2] {
+    let length = (stick[0] * stick[0] + stick[1] * stick[1]).sqrt();
+    if length <= STICK_DEAD_ZONE {
+        return [0.0, 0.0];
+    }
+    let scaled = ((length - STICK_DEAD_ZONE) / (1.0 - STICK_DEAD_ZONE)).min(1.0);
+    [stick[0] / length * scaled, stick[1] / length * scaled]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_zone() {
+        assert_eq!(apply_dead_zone([0.1, -0.05]), [0.0, 0.0]);
+
+        // Full tilt stays full, direction is kept, and the ramp starts at the dead zone edge
+        let full = apply_dead_zone([0.0, 1.0]);
+        assert!((full[1] - 1.0).abs() < 1e-6 && full[0] == 0.0);
+        let edge = apply_dead_zone([STICK_DEAD_ZONE + 0.01, 0.0]);
+        assert!(edge[0] > 0.0 && edge[0] < 0.05);
+
+        // Square-gate corners (both axes at 1) clamp to unit length
+        let corner = apply_dead_zone([1.0, 1.0]);
+        assert!(((corner[0] * corner[0] + corner[1] * corner[1]).sqrt() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_buttons() {
+        let state = GamepadState {
+            buttons: GamepadButton::South.bit() | GamepadButton::DPadLeft.bit(),
+            ..Default::default()
+        };
+        assert!(state.is_pressed(GamepadButton::South));
+        assert!(state.is_pressed(GamepadButton::DPadLeft));
+        assert!(!state.is_pressed(GamepadButton::East));
+        assert!(!GamepadState::default().is_pressed(GamepadButton::South));
+    }
+}
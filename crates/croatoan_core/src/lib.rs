@@ -2,25 +2,90 @@ use croatoan_render::GraphicsContext;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window, WindowBuilder},
 };
 use std::sync::Arc;
 
+mod cursor;
+mod frame;
+mod gamepad;
+mod mouse;
+pub use cursor::CursorHandle;
+pub use frame::{FixedStep, FrameClock, FrameContext, UPDATE_DT, UPDATE_HZ};
+pub use gamepad::{GamepadButton, GamepadHandle, GamepadState};
+use gamepad::GamepadPoller;
+pub use mouse::MouseHandle;
+
 // Re-export winit event types for use in game code
-pub use winit::event::{DeviceEvent, ElementState, KeyEvent};
+pub use winit::event::{DeviceEvent, ElementState, KeyEvent, MouseButton, MouseScrollDelta};
 pub use winit::keyboard::{KeyCode, PhysicalKey};
 pub use winit::event::Event as WinitEvent;
 pub use winit::event::WindowEvent as WinitWindowEvent;
 pub use winit::window::CursorGrabMode;
 
+/// How the window occupies the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// Undecorated window covering the monitor (fast alt-tab, desktop resolution)
+    Borderless,
+    /// Exclusive fullscreen at the monitor's largest video mode
+    Fullscreen,
+}
+
+impl WindowMode {
+    /// Switch `window` to this mode (e.g. from the input callback), returning the mode
+    /// actually applied: without a monitor to fill, everything falls back to windowed.
+    /// The surface is resized on the following `Resized`/`AboutToWait`.
+    pub fn apply(self, window: &Window) -> WindowMode {
+        let monitor = window.current_monitor().or_else(|| window.primary_monitor());
+        let (fullscreen, applied) = self.fullscreen_on(monitor);
+        window.set_fullscreen(fullscreen);
+        applied
+    }
+
+    /// winit setting for this mode on `monitor`, plus the mode that setting amounts to
+    fn fullscreen_on(self, monitor: Option<MonitorHandle>) -> (Option<Fullscreen>, WindowMode) {
+        if self == WindowMode::Windowed {
+            return (None, WindowMode::Windowed);
+        }
+        let Some(monitor) = monitor else {
+            log::warn!("No monitor available for {:?}, staying windowed", self);
+            return (None, WindowMode::Windowed);
+        };
+        if self == WindowMode::Fullscreen {
+            let best = monitor.video_modes().max_by_key(|mode| {
+                let size = mode.size();
+                (size.width * size.height, mode.refresh_rate_millihertz())
+            });
+            match best {
+                Some(video_mode) => return (Some(Fullscreen::Exclusive(video_mode)), WindowMode::Fullscreen),
+                None => log::warn!("Monitor reports no video modes, using borderless instead"),
+            }
+        }
+        (Some(Fullscreen::Borderless(Some(monitor))), WindowMode::Borderless)
+    }
+}
+
 /// Main application structure that manages the engine loop
 pub struct App {
     title: String,
     width: u32,
     height: u32,
-    render_callback: Option<Box<dyn FnMut(&mut GraphicsContext) + 'static>>,
+    sample_count: u32,
+    window_mode: WindowMode,
+    fixed_dt: Option<f32>,
+    render_callback: Option<Box<dyn FnMut(FrameContext) + 'static>>,
+    update_callback: Option<Box<dyn FnMut(f32) + 'static>>,
     input_callback: Option<Box<dyn FnMut(&Event<()>, &winit::window::Window) + 'static>>,
+    resize_callback: Option<Box<dyn FnMut(u32, u32) + 'static>>,
+    focus_callback: Option<Box<dyn FnMut(bool) + 'static>>,
     key_states: std::collections::HashMap<KeyCode, ElementState>,
+    mouse: MouseHandle,
+    gamepad: GamepadHandle,
+    cursor: CursorHandle,
 }
 
 impl App {
@@ -30,9 +95,18 @@ impl App {
             title: title.into(),
             width,
             height,
+            sample_count: 1,
+            window_mode: WindowMode::Windowed,
+            fixed_dt: None,
             render_callback: None,
+            update_callback: None,
             input_callback: None,
+            resize_callback: None,
+            focus_callback: None,
             key_states: std::collections::HashMap::new(),
+            mouse: MouseHandle::default(),
+            gamepad: GamepadHandle::default(),
+            cursor: CursorHandle::default(),
         }
     }
 
@@ -41,14 +115,77 @@ impl App {
         *self.key_states.get(&key).unwrap_or(&ElementState::Released)
     }
 
-    /// Set the render callback that will be called each frame
+    /// Get the current state of a mouse button
+    pub fn get_mouse_button(&self, button: MouseButton) -> ElementState {
+        self.mouse.get_mouse_button(button)
+    }
+
+    /// Buttons, cursor position and scroll, kept current while the app runs
+    /// (`App` itself moves into the event loop; clone the handle into callbacks).
+    pub fn mouse_state(&self) -> MouseHandle {
+        self.mouse.clone()
+    }
+
+    /// Controller sticks and buttons, polled every `AboutToWait` while the app runs
+    /// Controllers can be plugged in or pulled at any time; clone the handle into callbacks.
+    pub fn gamepad_state(&self) -> GamepadHandle {
+        self.gamepad.clone()
+    }
+
+    /// Cursor grab and visibility, changeable while the app runs (clone the handle into callbacks)
+    pub fn cursor(&self) -> CursorHandle {
+        self.cursor.clone()
+    }
+
+    /// How the cursor is held while the window has focus; released whenever focus is lost.
+    /// Defaults to `CursorGrabMode::None`. Use `cursor()` to change it while running.
+    pub fn set_cursor_grab_mode(&mut self, mode: CursorGrabMode) {
+        self.cursor.set_grab_mode(mode);
+    }
+
+    /// Whether the cursor is drawn over the window (default true); always shown without focus.
+    /// Use `cursor()` to change it while running.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor.set_visible(visible);
+    }
+
+    /// Request MSAA for the main view (1/2/4/8). Must be set before `run`;
+    /// the graphics context downgrades it if the adapter can't support it.
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        self.sample_count = sample_count;
+    }
+
+    /// Window mode at startup. F11 toggles between windowed and this mode while running
+    /// (borderless if this is `Windowed`).
+    pub fn set_window_mode(&mut self, mode: WindowMode) {
+        self.window_mode = mode;
+    }
+
+    /// Advance every frame by exactly `dt` seconds instead of the wall clock (None = real time),
+    /// for deterministic playback and capture. Must be set before `run`.
+    pub fn set_fixed_dt(&mut self, dt: Option<f32>) {
+        self.fixed_dt = dt;
+    }
+
+    /// Set the render callback that will be called each frame, with the frame's timing
     pub fn set_render_callback<F>(&mut self, callback: F)
     where
-        F: FnMut(&mut GraphicsContext) + 'static,
+        F: FnMut(FrameContext) + 'static,
     {
         self.render_callback = Some(Box::new(callback));
     }
 
+    /// Set the simulation callback, run at a fixed `UPDATE_HZ` with the step length in seconds
+    /// (whatever the frame rate) before each frame's render: physics belongs here so it
+    /// behaves the same at 30 FPS as at 144. Rendering gets `FrameContext::alpha` to
+    /// interpolate between the last two steps.
+    pub fn set_update_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(f32) + 'static,
+    {
+        self.update_callback = Some(Box::new(callback));
+    }
+
     /// Set the input callback that will be called for input events
     pub fn set_input_callback<F>(&mut self, callback: F)
     where
@@ -57,6 +194,25 @@ impl App {
         self.input_callback = Some(Box::new(callback));
     }
 
+    /// Set the resize callback, called with the new surface size (in pixels)
+    /// after the graphics context has been resized, and once at startup with the
+    /// initial size. Not called while minimized.
+    pub fn set_resize_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(u32, u32) + 'static,
+    {
+        self.resize_callback = Some(Box::new(callback));
+    }
+
+    /// Set the focus callback, called with true when the window gains keyboard focus and false
+    /// when it loses it (alt-tab, clicking another window), e.g. to pause the game
+    pub fn set_focus_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(bool) + 'static,
+    {
+        self.focus_callback = Some(Box::new(callback));
+    }
+
     /// Run the application event loop
     pub fn run(mut self) -> Result<(), Box<dyn std::error::Error>> {
         // Initialize logging
@@ -87,22 +243,41 @@ impl App {
             log::warn!("Failed to load window icon from {}", icon_path);
         }
 
+        let monitor = event_loop.primary_monitor().or_else(|| event_loop.available_monitors().next());
+        let (fullscreen, window_mode) = self.window_mode.fullscreen_on(monitor);
+        window_builder = window_builder.with_fullscreen(fullscreen);
+
         let window = Arc::new(window_builder.build(&event_loop)?);
 
-        log::info!("Window created: {} ({}x{})", self.title, self.width, self.height);
+        log::info!("Window created: {} ({}x{}, {:?})", self.title, self.width, self.height, window_mode);
+        // Not every platform sends Focused for a window that starts focused
+        self.cursor.set_focused(window.has_focus());
 
-        // Set cursor grab mode to confine cursor to window
-        if let Err(e) = window.set_cursor_grab(CursorGrabMode::Confined) {
-            log::warn!("Failed to confine cursor: {}", e);
-            // Try locked mode as fallback
-            if let Err(e) = window.set_cursor_grab(CursorGrabMode::Locked) {
-                log::warn!("Failed to lock cursor: {}", e);
+        // Initialize graphics context
+        let mut graphics_context = GraphicsContext::new_with_msaa(window.clone(), wgpu::PresentMode::Fifo, self.sample_count)?;
+
+        // The window may not get the requested size (DPI scaling, tiling WMs), so report
+        // the real one up front; otherwise the camera aspect is wrong until the first resize
+        let initial_size = window.inner_size();
+        if initial_size.width > 0 && initial_size.height > 0 {
+            if let Some(callback) = &mut self.resize_callback {
+                callback(initial_size.width, initial_size.height);
             }
         }
-        window.set_cursor_visible(false);
 
-        // Initialize graphics context
-        let mut graphics_context = GraphicsContext::new(window.clone());
+        let mut gamepad_poller = GamepadPoller::new();
+        let mut clock = FrameClock::new(self.fixed_dt);
+        let mut fixed_step = FixedStep::new(UPDATE_DT);
+        // (dt, elapsed) of a frame that's been simulated but not yet drawn, and the elapsed time
+        // of the last one drawn (for redraws the OS asks for between frames)
+        let mut pending_frame: Option<(f32, f32)> = None;
+        let mut drawn_elapsed = 0.0;
+        let fullscreen_mode = match self.window_mode {
+            WindowMode::Windowed => WindowMode::Borderless,
+            mode => mode,
+        };
+        // Hidden behind other windows (on platforms that report it)
+        let mut occluded = false;
 
         // Run the event loop
         let result = event_loop.run(move |event, elwt| {
@@ -117,6 +292,27 @@ impl App {
             if let Event::WindowEvent { event: WindowEvent::KeyboardInput { event: key_event, .. }, .. } = &event {
                 if let PhysicalKey::Code(keycode) = key_event.physical_key {
                     self.key_states.insert(keycode, key_event.state);
+
+                    // F11: toggle fullscreen
+                    if keycode == KeyCode::F11 && key_event.state == ElementState::Pressed && !key_event.repeat {
+                        let mode = if window.fullscreen().is_some() { WindowMode::Windowed } else { fullscreen_mode };
+                        let applied = mode.apply(&window);
+                        log::info!("Window mode: {:?}", applied);
+                    }
+                }
+            }
+
+            // Update mouse state
+            if let Event::WindowEvent { event, .. } = &event {
+                self.mouse.handle_event(event);
+            }
+
+            // Grab the cursor only while focused, so alt-tab and other windows work
+            if let Event::WindowEvent { event: WindowEvent::Focused(focused), .. } = &event {
+                self.cursor.set_focused(*focused);
+                self.cursor.apply(&window);
+                if let Some(callback) = &mut self.focus_callback {
+                    callback(*focused);
                 }
             }
 
@@ -127,13 +323,27 @@ impl App {
                         elwt.exit();
                     }
                     WindowEvent::Resized(physical_size) => {
-                        graphics_context.resize(physical_size);
                         log::info!("Window resized to: {:?}", physical_size);
+                        resize_surface(&mut graphics_context, &mut self.resize_callback, physical_size);
+                    }
+                    WindowEvent::Occluded(hidden) => {
+                        log::info!("Window {}", if hidden { "occluded" } else { "visible again" });
+                        occluded = hidden;
                     }
                     WindowEvent::RedrawRequested => {
+                        // Nothing to show while minimized or hidden; don't burn the GPU on it
+                        if occluded || is_minimized(&window) {
+                            clock.pause();
+                            return;
+                        }
+                        // Simulated in AboutToWait; without a new frame, redraw the last with no time passing
+                        let (dt, elapsed) = pending_frame.take().unwrap_or((0.0, drawn_elapsed));
+                        drawn_elapsed = elapsed;
+
                         // Call user-provided render callback if set
                         if let Some(callback) = &mut self.render_callback {
-                            callback(&mut graphics_context);
+                            let alpha = fixed_step.alpha();
+                            callback(FrameContext { ctx: &mut graphics_context, dt, elapsed, alpha });
                         } else {
                             // Default: clear to black
                             let _ = graphics_context.render(wgpu::Color {
@@ -143,12 +353,38 @@ impl App {
                                 a: 1.0,
                             });
                         }
-                        window.request_redraw();
                     }
                     _ => {}
                 },
                 Event::AboutToWait => {
-                    window.request_redraw();
+                    // Not every platform sends Resized after a fullscreen switch; catch it here
+                    let size = window.inner_size();
+                    let config = graphics_context.config();
+                    if size.width > 0 && size.height > 0 && (size.width != config.width || size.height != config.height) {
+                        resize_surface(&mut graphics_context, &mut self.resize_callback, size);
+                    }
+
+                    gamepad_poller.poll(&self.gamepad);
+                    self.cursor.apply(&window);
+                    if occluded || is_minimized(&window) {
+                        // Sleep until the next event (restore, expose, input) instead of polling
+                        clock.pause();
+                        elwt.set_control_flow(ControlFlow::Wait);
+                    } else {
+                        // Catch the simulation up to now in fixed steps, once per drawn frame (so a
+                        // fixed frame dt stays one tick per frame); the redraw interpolates and renders
+                        if pending_frame.is_none() {
+                            let (dt, elapsed) = clock.tick();
+                            let steps = fixed_step.advance(dt);
+                            if let Some(update) = &mut self.update_callback {
+                                for _ in 0..steps {
+                                    update(UPDATE_DT);
+                                }
+                            }
+                            pending_frame = Some((dt, elapsed));
+                        }
+                        window.request_redraw();
+                    }
                 }
                 _ => {}
             }
@@ -157,3 +393,38 @@ impl App {
         result.map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
     }
 }
+
+/// Minimized windows report a zero size on most platforms (`is_minimized` isn't supported everywhere)
+fn is_minimized(window: &Window) -> bool {
+    let size = window.inner_size();
+    window.is_minimized().unwrap_or(false) || size.width == 0 || size.height == 0
+}
+
+/// Resize the surface and tell the game (skipped while minimized)
+fn resize_surface(
+    graphics_context: &mut GraphicsContext,
+    resize_callback: &mut Option<Box<dyn FnMut(u32, u32) + 'static>>,
+    size: winit::dpi::PhysicalSize<u32>,
+) {
+    graphics_context.resize(size);
+
+    if size.width > 0 && size.height > 0 {
+        if let Some(callback) = resize_callback {
+            callback(size.width, size.height);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_mode_without_monitor_falls_back_to_windowed() {
+        for mode in [WindowMode::Windowed, WindowMode::Borderless, WindowMode::Fullscreen] {
+            let (fullscreen, applied) = mode.fullscreen_on(None);
+            assert!(fullscreen.is_none());
+            assert_eq!(applied, WindowMode::Windowed);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+
+/// Trackpads scroll in pixels, wheels in lines; pixel deltas are converted at this rate
+const PIXELS_PER_SCROLL_LINE: f32 = 50.0;
+
+#[derive(Default)]
+struct MouseState {
+    mouse_buttons: HashMap<MouseButton, ElementState>,
+    cursor_position: Option<(f64, f64)>,
+    scroll_delta: (f32, f32),
+}
+
+/// Shared view of the mouse, updated by `App` from window events
+/// Clone it into the callbacks to poll clicks instead of matching raw events.
+#[derive(Clone, Default)]
+pub struct MouseHandle(Arc<Mutex<MouseState>>);
+
+impl MouseHandle {
+    /// Get the current state of a mouse button
+    pub fn get_mouse_button(&self, button: MouseButton) -> ElementState {
+        *self.0.lock().unwrap().mouse_buttons.get(&button).unwrap_or(&ElementState::Released)
+    }
+
+    /// Cursor position in physical pixels from the window's top-left (None while outside it)
+    pub fn cursor_position(&self) -> Option<(f64, f64)> {
+        self.0.lock().unwrap().cursor_position
+    }
+
+    /// Scroll accumulated since the last call, in lines (x, y); positive y scrolls up/away
+    pub fn take_scroll_delta(&self) -> (f32, f32) {
+        std::mem::take(&mut self.0.lock().unwrap().scroll_delta)
+    }
+
+    pub(crate) fn handle_event(&self, event: &WindowEvent) {
+        let mut state = self.0.lock().unwrap();
+        match event {
+            WindowEvent::MouseInput { state: button_state, button, .. } => {
+                state.mouse_buttons.insert(*button, *button_state);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                state.cursor_position = Some((position.x, position.y));
+            }
+            WindowEvent::CursorLeft { .. } => {
+                state.cursor_position = None;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+                    MouseScrollDelta::PixelDelta(pixels) => (
+                        pixels.x as f32 / PIXELS_PER_SCROLL_LINE,
+                        pixels.y as f32 / PIXELS_PER_SCROLL_LINE,
+                    ),
+                };
+                state.scroll_delta.0 += x;
+                state.scroll_delta.1 += y;
+            }
+            // Releases outside the window never arrive; don't leave buttons stuck down
+            WindowEvent::Focused(false) => {
+                state.mouse_buttons.clear();
+            }
+            _ => {}
+        }
+    }
+}
@@ -3,7 +3,14 @@ use croatoan_procgen::tree::{TreeRecipe, generate_tree, generate_tree_mesh};
 fn main() {
     println!("=== Roanoke Engine - Tree Generation Demo ===\n");
 
-    // Generate different tree species
+    // Generate different tree species, plus any tree files given on the command line
+    let files: Vec<(String, TreeRecipe)> = std::env::args().skip(1).filter_map(|path| match TreeRecipe::from_file(&path) {
+        Ok(recipe) => Some((path, recipe)),
+        Err(e) => {
+            println!("Skipping {}", e);
+            None
+        }
+    }).collect();
     let species = vec![
         ("Oak", TreeRecipe::oak()),
         ("Pine", TreeRecipe::pine()),
@@ -14,7 +21,7 @@ fn main() {
         ("Spruce", TreeRecipe::spruce()),
     ];
 
-    for (name, recipe) in species {
+    for (name, recipe) in species.into_iter().map(|(name, recipe)| (name.to_string(), recipe)).chain(files) {
         println!("--- {} Tree ---", name);
         println!("Recipe: {:?}", recipe.species);
         println!("Iterations: {}", recipe.iterations);
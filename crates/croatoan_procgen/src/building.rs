@@ -1,4 +1,5 @@
-use glam::{Vec3, Vec2};
+use glam::{Quat, Vec3, Vec2};
+use crate::rng::SeededRng;
 use std::collections::HashMap;
 
 /// Architectural style for the building
@@ -9,16 +10,31 @@ pub enum ArchStyle {
     Rustic,
 }
 
+/// Overall massing of the building
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildingShape {
+    /// Stacked floors with windows, a porch and a style-dependent roof
+    House,
+    /// Tall nave (`floor_height` is its wall height) with a steeple and spire at the front
+    Church,
+    /// Round stone tower (`width` is its diameter) with a crenellated top
+    Watchtower,
+}
+
 /// Parameters for procedural building generation
 #[derive(Debug, Clone)]
 pub struct BuildingRecipe {
     pub style: ArchStyle,
+    pub shape: BuildingShape,
     pub floors: u32,
     pub width: f32,
     pub depth: f32,
     pub seed: u32,
     pub floor_height: f32,
     pub roof_height: f32,
+    /// Walk-in interior (floors, room partitions, stairs; houses only); off keeps distant
+    /// buildings cheap
+    pub include_interior: bool,
 }
 
 impl Default for BuildingRecipe {
@@ -31,24 +47,86 @@ impl BuildingRecipe {
     pub fn colonial_house() -> Self {
         BuildingRecipe {
             style: ArchStyle::Colonial,
+            shape: BuildingShape::House,
             floors: 2,
             width: 8.0,
             depth: 6.0,
             seed: 0,
             floor_height: 3.0,
             roof_height: 2.5,
+            include_interior: false,
         }
     }
 
     pub fn small_shack() -> Self {
         BuildingRecipe {
             style: ArchStyle::Rustic,
+            shape: BuildingShape::House,
             floors: 1,
             width: 5.0,
             depth: 4.0,
             seed: 0,
             floor_height: 2.5,
             roof_height: 1.5,
+            include_interior: false,
+        }
+    }
+
+    /// Whitewashed nave, long along Z, with the steeple over its front door
+    pub fn church() -> Self {
+        BuildingRecipe {
+            style: ArchStyle::Colonial,
+            shape: BuildingShape::Church,
+            floors: 1,
+            width: 7.0,
+            depth: 14.0,
+            seed: 0,
+            floor_height: 6.0,
+            roof_height: 3.5,
+            include_interior: false,
+        }
+    }
+
+    /// Lookout tower, 5m across and three stories tall
+    pub fn watchtower() -> Self {
+        BuildingRecipe {
+            style: ArchStyle::Rustic,
+            shape: BuildingShape::Watchtower,
+            floors: 3,
+            width: 5.0,
+            depth: 5.0,
+            seed: 0,
+            floor_height: 3.5,
+            roof_height: 0.0, // Open crenellated top
+            include_interior: false,
+        }
+    }
+
+    /// Ground contact in building space: (min, max) over X and Z of the foundation (and a
+    /// Colonial/Rustic house's porch, which the seed may or may not add)
+    pub fn footprint(&self) -> (Vec2, Vec2) {
+        let half = match self.shape {
+            BuildingShape::House => Vec2::new(self.width + 0.2, self.depth + 0.2) * 0.5,
+            BuildingShape::Church => Vec2::new(self.width + 0.3, self.depth + 0.3) * 0.5,
+            BuildingShape::Watchtower => Vec2::splat(self.width * 0.5 + 0.4), // Plinth
+        };
+        let porch = self.shape == BuildingShape::House && matches!(self.style, ArchStyle::Colonial | ArchStyle::Rustic);
+        let front = if porch { self.depth * 0.5 + PORCH_DEPTH } else { half.y };
+        (-half, Vec2::new(half.x, front))
+    }
+
+    /// Four-story flat-roofed block
+    pub fn apartment_block() -> Self {
+        BuildingRecipe {
+            style: ArchStyle::Modern,
+            shape: BuildingShape::House,
+            floors: 4,
+            width: 12.0,
+            depth: 8.0,
+            seed: 0,
+            floor_height: 3.0,
+            roof_height: 0.0, // Flat roof
+            include_interior: false,
         }
     }
 }
@@ -61,8 +139,20 @@ pub struct BuildingVertex {
     pub normal: [f32; 3],
     pub uv: [f32; 2],
     pub color: [f32; 3], // Added color for simple material differentiation
+    /// Light the surface gives off at night (lit windows), added unaffected by the sun
+    pub emissive: [f32; 3],
 }
 
+/// How far foundations reach below the building's origin, so a building set at the average
+/// ground height of its corners still meets the terrain where the ground falls away
+pub const FOUNDATION_SKIRT: f32 = 1.5;
+
+/// Porch deck depth in front of Colonial/Rustic houses
+const PORCH_DEPTH: f32 = 2.0;
+
+/// Warm lamplight behind window glass (HDR, so it blooms)
+const WINDOW_GLOW: [f32; 3] = [2.0, 1.2, 0.5];
+
 /// Generated building mesh
 #[derive(Debug, Clone)]
 pub struct BuildingMesh {
@@ -70,36 +160,58 @@ pub struct BuildingMesh {
     pub indices: Vec<u32>,
 }
 
+impl BuildingMesh {
+    /// Axis-aligned bounds (min, max) of the mesh in building space, for collision
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        self.vertices.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), v| {
+                let p = Vec3::from_array(v.position);
+                (min.min(p), max.max(p))
+            },
+        )
+    }
+}
+
 /// Generate a building mesh from a recipe using a simple Shape Grammar
 pub fn generate_building(recipe: &BuildingRecipe) -> BuildingMesh {
     let mut builder = MeshBuilder::new();
     
-    // RNG (Linear Congruential Generator)
-    let mut rng_state = recipe.seed as u64;
-    let mut random = || {
-        rng_state = rng_state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
-        (rng_state >> 32) as f32 / u32::MAX as f32
-    };
+    let mut rng = SeededRng::new(recipe.seed as u64);
+    let mut random = || rng.next_f32();
+
+    // Churches and towers have their own massing
+    match recipe.shape {
+        BuildingShape::House => {}
+        BuildingShape::Church => {
+            add_church(&mut builder, recipe);
+            return BuildingMesh { vertices: builder.vertices, indices: builder.indices };
+        }
+        BuildingShape::Watchtower => {
+            add_watchtower(&mut builder, recipe);
+            return BuildingMesh { vertices: builder.vertices, indices: builder.indices };
+        }
+    }
 
     let half_w = recipe.width * 0.5;
     let half_d = recipe.depth * 0.5;
 
-    // 1. Foundation
+    // 1. Foundation (top slightly raised, skirt reaching down into the ground)
     builder.add_box(
-        Vec3::new(0.0, 0.2, 0.0), // Center (slightly raised)
-        Vec3::new(recipe.width + 0.2, 0.4, recipe.depth + 0.2), // Size
+        Vec3::new(0.0, (0.4 - FOUNDATION_SKIRT) * 0.5, 0.0), // Center
+        Vec3::new(recipe.width + 0.2, 0.4 + FOUNDATION_SKIRT, recipe.depth + 0.2), // Size
         [0.4, 0.4, 0.4], // Stone gray
     );
 
     // Porch (Colonial/Rustic only)
     let has_porch = (recipe.style == ArchStyle::Colonial || recipe.style == ArchStyle::Rustic) && random() > 0.3;
     if has_porch {
-        let porch_depth = 2.0;
+        let porch_depth = PORCH_DEPTH;
         let porch_z = half_d + porch_depth * 0.5;
-        // Porch floor
+        // Porch floor, on a skirt like the foundation
         builder.add_box(
-            Vec3::new(0.0, 0.2, porch_z),
-            Vec3::new(recipe.width, 0.4, porch_depth),
+            Vec3::new(0.0, (0.4 - FOUNDATION_SKIRT) * 0.5, porch_z),
+            Vec3::new(recipe.width, 0.4 + FOUNDATION_SKIRT, porch_depth),
             [0.45, 0.35, 0.25], // Wood deck
         );
         // Porch roof (extension of main roof or separate)
@@ -160,10 +272,11 @@ pub fn generate_building(recipe: &BuildingRecipe) -> BuildingMesh {
                      [0.8, 0.8, 0.8], // White frame
                  );
                  // Window Glass
-                 builder.add_box(
+                 builder.add_emissive_box(
                      Vec3::new(x_offset, y_base + 1.5, half_d + 0.06),
                      Vec3::new(1.0, 1.2, 0.1),
                      [0.2, 0.3, 0.5], // Blueish glass
+                     WINDOW_GLOW,
                  );
                  // Sill
                  builder.add_box(
@@ -223,12 +336,172 @@ pub fn generate_building(recipe: &BuildingRecipe) -> BuildingMesh {
         );
     }
 
+    // 5. Interior (appended, so the exterior above is unchanged)
+    if recipe.include_interior {
+        add_interior(&mut builder, recipe, &mut random);
+    }
+
     BuildingMesh {
         vertices: builder.vertices,
         indices: builder.indices,
     }
 }
 
+/// Nave with a pitched roof, tall side windows, and a square steeple (belfry + spire)
+/// rising from its front end
+fn add_church(builder: &mut MeshBuilder, recipe: &BuildingRecipe) {
+    let wall_color = [0.92, 0.91, 0.86]; // Whitewash
+    let roof_color = [0.3, 0.3, 0.32]; // Slate
+    let trim_color = [0.35, 0.22, 0.12];
+    let glass_color = [0.25, 0.3, 0.5];
+    let half_w = recipe.width * 0.5;
+    let half_d = recipe.depth * 0.5;
+    let wall_height = recipe.floors as f32 * recipe.floor_height;
+
+    // Foundation, nave and roof
+    builder.add_box(Vec3::new(0.0, (0.4 - FOUNDATION_SKIRT) * 0.5, 0.0), Vec3::new(recipe.width + 0.3, 0.4 + FOUNDATION_SKIRT, recipe.depth + 0.3), [0.4, 0.4, 0.4]);
+    builder.add_box(Vec3::new(0.0, 0.4 + wall_height * 0.5, 0.0), Vec3::new(recipe.width, wall_height, recipe.depth), wall_color);
+    builder.add_prism(Vec3::new(0.0, 0.4 + wall_height, 0.0), recipe.width + 0.8, recipe.depth + 0.6, recipe.roof_height, roof_color);
+
+    // Tall windows down both sides
+    let bays = (recipe.depth / 3.0).floor() as i32;
+    for bay in 0..bays {
+        let z = -half_d + (bay as f32 + 0.5) * recipe.depth / bays as f32;
+        for side in [-1.0, 1.0] {
+            let x = side * (half_w + 0.05);
+            builder.add_box(Vec3::new(x, 0.4 + wall_height * 0.5, z), Vec3::new(0.1, wall_height * 0.55, 1.1), trim_color);
+            builder.add_emissive_box(Vec3::new(x + side * 0.02, 0.4 + wall_height * 0.5, z), Vec3::new(0.1, wall_height * 0.5, 0.9), glass_color, WINDOW_GLOW);
+        }
+    }
+
+    // Steeple: tower over the front wall, belfry openings near the top, then the spire
+    let tower = recipe.width * 0.45;
+    let tower_z = half_d - tower * 0.5;
+    let tower_top = 0.4 + wall_height + recipe.roof_height + tower;
+    builder.add_box(Vec3::new(0.0, tower_top * 0.5, tower_z), Vec3::new(tower, tower_top, tower), wall_color);
+    for (x, z) in [(0.0, tower * 0.5), (0.0, -tower * 0.5), (tower * 0.5, 0.0), (-tower * 0.5, 0.0)] {
+        let size = if x == 0.0 { Vec3::new(tower * 0.4, tower * 0.5, 0.1) } else { Vec3::new(0.1, tower * 0.5, tower * 0.4) };
+        builder.add_box(Vec3::new(x, tower_top - tower * 0.45, tower_z + z), size, [0.1, 0.1, 0.1]); // Dark belfry opening
+    }
+    builder.add_box(Vec3::new(0.0, tower_top, tower_z), Vec3::new(tower + 0.3, 0.2, tower + 0.3), trim_color);
+    builder.add_pyramid(Vec3::new(0.0, tower_top + 0.1, tower_z), tower, tower, tower * 2.5, roof_color);
+
+    // Double door in the tower's face
+    builder.add_box(Vec3::new(0.0, 0.4 + 1.3, half_d + 0.06), Vec3::new(1.8, 2.6, 0.12), trim_color);
+}
+
+/// Round stone tower on a wider plinth, a walkway ring and a crenellated parapet
+fn add_watchtower(builder: &mut MeshBuilder, recipe: &BuildingRecipe) {
+    const SEGMENTS: u32 = 16;
+    let stone = [0.5, 0.48, 0.44];
+    let dark_stone = [0.4, 0.38, 0.35];
+    let radius = recipe.width * 0.5;
+    let height = recipe.floors as f32 * recipe.floor_height;
+
+    builder.add_cylinder(Vec3::new(0.0, -FOUNDATION_SKIRT, 0.0), radius + 0.4, 0.6 + FOUNDATION_SKIRT, SEGMENTS, dark_stone); // Plinth
+    builder.add_cylinder(Vec3::new(0.0, 0.6, 0.0), radius, height, SEGMENTS, stone);
+
+    // Walkway ring overhanging the shaft, with merlons around its rim
+    let top = 0.6 + height;
+    builder.add_cylinder(Vec3::new(0.0, top, 0.0), radius + 0.5, 0.4, SEGMENTS, dark_stone);
+    let rim = radius + 0.3;
+    for i in 0..SEGMENTS / 2 {
+        let angle = (i as f32 * 2.0) * std::f32::consts::TAU / SEGMENTS as f32;
+        let center = Vec3::new(angle.cos() * rim, top + 0.4 + 0.45, angle.sin() * rim);
+        builder.add_box_yawed(center, Vec3::new(0.35, 0.9, rim * std::f32::consts::TAU / SEGMENTS as f32), -angle, stone);
+    }
+
+    // Door and arrow slits up the front
+    builder.add_box(Vec3::new(0.0, 0.6 + 1.1, radius + 0.02), Vec3::new(1.1, 2.2, 0.15), [0.35, 0.22, 0.12]);
+    for floor in 1..recipe.floors {
+        let y = 0.6 + (floor as f32 + 0.5) * recipe.floor_height;
+        builder.add_box(Vec3::new(0.0, y, radius + 0.02), Vec3::new(0.2, 1.0, 0.12), [0.1, 0.1, 0.1]);
+    }
+}
+
+/// Interior lining the exterior walls' insides: per story a floor, a ceiling, inward-facing
+/// walls and a partition (with a doorway) splitting it into two rooms, plus a stair run
+/// up through an opening into each story above
+fn add_interior(builder: &mut MeshBuilder, recipe: &BuildingRecipe, random: &mut impl FnMut() -> f32) {
+    const INSET: f32 = 0.1; // Lining sits just inside the exterior box
+    const SLAB: f32 = 0.15; // Ceiling to the floor above
+    const DOORWAY: Vec2 = Vec2::new(0.9, 2.1); // Width, height
+    const STAIR_WIDTH: f32 = 1.0;
+    const STEP_RISE: f32 = 0.25;
+    let floor_color = [0.5, 0.38, 0.25]; // Planks
+    let wall_color = [0.85, 0.82, 0.75]; // Plaster
+    let ceiling_color = [0.9, 0.9, 0.88];
+
+    let min = Vec2::new(-recipe.width * 0.5 + INSET, -recipe.depth * 0.5 + INSET);
+    let max = -min;
+
+    // Same slot as the exterior's front door (see the window loop)
+    let window_spacing = 2.0;
+    let door_x = (0..(recipe.width / window_spacing).floor() as i32 - 1)
+        .map(|w| -recipe.width * 0.5 + window_spacing + w as f32 * window_spacing)
+        .find(|x| x.abs() < 1.0);
+
+    // Stairs climb toward the back along the left wall; the opening is over their upper end
+    let stair_length = recipe.floor_height; // 45 degrees
+    let stairs_min = Vec2::new(min.x, min.y);
+    let opening = (stairs_min, Vec2::new(min.x + STAIR_WIDTH, min.y + stair_length * 0.8));
+
+    for i in 0..recipe.floors {
+        let y_floor = 0.4 + i as f32 * recipe.floor_height;
+        let y_ceiling = y_floor + recipe.floor_height - SLAB;
+        let has_above = i + 1 < recipe.floors;
+        let hole_below = (i > 0).then_some(opening);
+        let hole_above = has_above.then_some(opening);
+
+        builder.add_floor(y_floor + 0.02, min, max, hole_below, true, floor_color);
+        builder.add_floor(y_ceiling, min, max, hole_above, false, ceiling_color);
+
+        // Inward-facing walls, up past the ceiling so the stair opening's outer sides are
+        // lined too; the front one leaves the entrance open on the ground floor
+        let lining = (y_floor, y_floor + recipe.floor_height);
+        let entrance = door_x.filter(|_| i == 0).map(|x| (x - min.x, DOORWAY));
+        builder.add_wall(Vec2::new(min.x, max.y), Vec2::new(max.x, max.y), lining, Vec3::NEG_Z, entrance, wall_color);
+        builder.add_wall(Vec2::new(min.x, min.y), Vec2::new(max.x, min.y), lining, Vec3::Z, None, wall_color);
+        builder.add_wall(Vec2::new(min.x, min.y), Vec2::new(min.x, max.y), lining, Vec3::X, None, wall_color);
+        builder.add_wall(Vec2::new(max.x, min.y), Vec2::new(max.x, max.y), lining, Vec3::NEG_X, None, wall_color);
+
+        // Partition across the width, clear of the stairs, with a doorway in the middle
+        let x = (random() - 0.5) * recipe.width * 0.2;
+        let doorway = Some(((max.y - min.y) * 0.5, DOORWAY));
+        let t = 0.05; // Half thickness
+        for (side, normal) in [(-t, Vec3::NEG_X), (t, Vec3::X)] {
+            builder.add_wall(Vec2::new(x + side, min.y), Vec2::new(x + side, max.y), (y_floor, y_ceiling), normal, doorway, wall_color);
+        }
+        // Doorway jambs and head, facing into the gap
+        let (gap_min, gap_max) = (-DOORWAY.x * 0.5, DOORWAY.x * 0.5);
+        let door_top = y_floor + DOORWAY.y;
+        builder.add_wall(Vec2::new(x - t, gap_min), Vec2::new(x + t, gap_min), (y_floor, door_top), Vec3::Z, None, wall_color);
+        builder.add_wall(Vec2::new(x - t, gap_max), Vec2::new(x + t, gap_max), (y_floor, door_top), Vec3::NEG_Z, None, wall_color);
+        builder.add_floor(door_top, Vec2::new(x - t, gap_min), Vec2::new(x + t, gap_max), None, false, wall_color);
+
+        if has_above {
+            // Sides of the opening through the slab
+            let (lo, hi) = opening;
+            let y_above = y_floor + recipe.floor_height + 0.02;
+            builder.add_wall(Vec2::new(hi.x, lo.y), Vec2::new(hi.x, hi.y), (y_ceiling, y_above), Vec3::NEG_X, None, wall_color);
+            builder.add_wall(Vec2::new(lo.x, hi.y), Vec2::new(hi.x, hi.y), (y_ceiling, y_above), Vec3::NEG_Z, None, wall_color);
+
+            // Solid steps, rising toward the back wall
+            let steps = (recipe.floor_height / STEP_RISE).ceil() as u32;
+            let (rise, run) = (recipe.floor_height / steps as f32, stair_length / steps as f32);
+            for k in 0..steps {
+                let height = (k + 1) as f32 * rise;
+                let z = stairs_min.y + stair_length - (k as f32 + 0.5) * run;
+                builder.add_box(
+                    Vec3::new(stairs_min.x + STAIR_WIDTH * 0.5, y_floor + height * 0.5, z),
+                    Vec3::new(STAIR_WIDTH, height, run),
+                    floor_color,
+                );
+            }
+        }
+    }
+}
+
 // --- Mesh Builder Helper ---
 
 struct MeshBuilder {
@@ -301,10 +574,10 @@ impl MeshBuilder {
         let slope_left_normal = Vec3::new(-height, half_w, 0.0).normalize();
         let slope_right_normal = Vec3::new(height, half_w, 0.0).normalize();
 
-        // Left Slope
-        self.add_quad(v_front_left, v_back_left, v_top_back, v_top_front, slope_left_normal, color);
+        // Left Slope (counter-clockwise seen from outside, so back-face culling keeps it)
+        self.add_quad(v_front_left, v_top_front, v_top_back, v_back_left, slope_left_normal, color);
         // Right Slope
-        self.add_quad(v_back_right, v_front_right, v_top_front, v_top_back, slope_right_normal, color);
+        self.add_quad(v_back_right, v_top_back, v_top_front, v_front_right, slope_right_normal, color);
         // Front Gable (Triangle)
         self.add_tri(v_front_left, v_front_right, v_top_front, Vec3::Z, color);
         // Back Gable (Triangle)
@@ -313,13 +586,133 @@ impl MeshBuilder {
         self.add_quad(v_back_left, v_back_right, v_front_right, v_front_left, Vec3::NEG_Y, color);
     }
 
+    /// Box that glows with `emissive` at night (window glass)
+    fn add_emissive_box(&mut self, center: Vec3, size: Vec3, color: [f32; 3], emissive: [f32; 3]) {
+        let start = self.vertices.len();
+        self.add_box(center, size, color);
+        for v in &mut self.vertices[start..] {
+            v.emissive = emissive;
+        }
+    }
+
+    /// Box rotated by `yaw` about +Y around its center
+    fn add_box_yawed(&mut self, center: Vec3, size: Vec3, yaw: f32, color: [f32; 3]) {
+        let start = self.vertices.len();
+        self.add_box(Vec3::ZERO, size, color);
+        let rotation = Quat::from_rotation_y(yaw);
+        for v in &mut self.vertices[start..] {
+            v.position = (center + rotation * Vec3::from_array(v.position)).to_array();
+            v.normal = (rotation * Vec3::from_array(v.normal)).to_array();
+        }
+    }
+
+    /// Four-sided spire tapering from a `width` x `depth` base to a point `height` above it
+    fn add_pyramid(&mut self, base_center: Vec3, width: f32, depth: f32, height: f32, color: [f32; 3]) {
+        let (half_w, half_d) = (width * 0.5, depth * 0.5);
+        let apex = base_center + Vec3::new(0.0, height, 0.0);
+        let corners = [
+            base_center + Vec3::new(-half_w, 0.0, half_d),
+            base_center + Vec3::new(half_w, 0.0, half_d),
+            base_center + Vec3::new(half_w, 0.0, -half_d),
+            base_center + Vec3::new(-half_w, 0.0, -half_d),
+        ];
+
+        for i in 0..4 {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            let normal = (b - a).cross(apex - a).normalize();
+            self.add_tri(a, b, apex, normal, color);
+        }
+    }
+
+    /// Upright capped cylinder, flat-shaded with `segments` sides
+    fn add_cylinder(&mut self, base_center: Vec3, radius: f32, height: f32, segments: u32, color: [f32; 3]) {
+        let point = |i: u32, y: f32| {
+            let angle = i as f32 * std::f32::consts::TAU / segments as f32;
+            base_center + Vec3::new(angle.cos() * radius, y, -angle.sin() * radius)
+        };
+        let top = base_center + Vec3::new(0.0, height, 0.0);
+
+        for i in 0..segments {
+            let (a, b) = (point(i, 0.0), point(i + 1, 0.0));
+            let mid_angle = (i as f32 + 0.5) * std::f32::consts::TAU / segments as f32;
+            let normal = Vec3::new(mid_angle.cos(), 0.0, -mid_angle.sin());
+            self.add_quad(a, b, b + Vec3::Y * height, a + Vec3::Y * height, normal, color);
+            self.add_tri(a + Vec3::Y * height, b + Vec3::Y * height, top, Vec3::Y, color);
+            self.add_tri(b, a, base_center, Vec3::NEG_Y, color);
+        }
+    }
+
+    /// Horizontal rectangle at height `y`, facing up or down, minus an optional `hole`
+    /// (split into up to four strips around it)
+    fn add_floor(&mut self, y: f32, min: Vec2, max: Vec2, hole: Option<(Vec2, Vec2)>, up: bool, color: [f32; 3]) {
+        let rects = match hole {
+            None => vec![(min, max)],
+            Some((hole_min, hole_max)) => vec![
+                (min, Vec2::new(hole_min.x, max.y)),
+                (Vec2::new(hole_max.x, min.y), max),
+                (Vec2::new(hole_min.x, min.y), Vec2::new(hole_max.x, hole_min.y)),
+                (Vec2::new(hole_min.x, hole_max.y), Vec2::new(hole_max.x, max.y)),
+            ],
+        };
+
+        for (a, b) in rects {
+            if b.x - a.x <= 0.0 || b.y - a.y <= 0.0 {
+                continue;
+            }
+            let corners = [
+                Vec3::new(a.x, y, b.y),
+                Vec3::new(b.x, y, b.y),
+                Vec3::new(b.x, y, a.y),
+                Vec3::new(a.x, y, a.y),
+            ];
+            if up {
+                self.add_quad(corners[0], corners[1], corners[2], corners[3], Vec3::Y, color);
+            } else {
+                self.add_quad(corners[0], corners[3], corners[2], corners[1], Vec3::NEG_Y, color);
+            }
+        }
+    }
+
+    /// Vertical wall from `a` to `b` (x, z) between heights `(y0, y1)`, wound to face
+    /// `normal`. `opening` cuts a floor-level doorway: (distance along from `a` to its
+    /// centre, (width, height)).
+    fn add_wall(&mut self, a: Vec2, b: Vec2, (y0, y1): (f32, f32), normal: Vec3, opening: Option<(f32, Vec2)>, color: [f32; 3]) {
+        let along = (b - a).normalize();
+        let length = (b - a).length();
+        let segments = match opening {
+            None => vec![(0.0, length, y0, y1)],
+            Some((centre, size)) => {
+                let (start, end) = (centre - size.x * 0.5, centre + size.x * 0.5);
+                vec![(0.0, start, y0, y1), (end, length, y0, y1), (start, end, (y0 + size.y).min(y1), y1)]
+            }
+        };
+
+        for (from, to, bottom, top) in segments {
+            if to - from <= 0.0 || top - bottom <= 0.0 {
+                continue;
+            }
+            let (p, q) = (a + along * from, a + along * to);
+            let (p, q) = (Vec3::new(p.x, 0.0, p.y), Vec3::new(q.x, 0.0, q.y));
+            // Counter-clockwise seen from the side `normal` points to
+            let (p, q) = if (q - p).cross(Vec3::Y).dot(normal) > 0.0 { (p, q) } else { (q, p) };
+            self.add_quad(
+                p + Vec3::Y * bottom,
+                q + Vec3::Y * bottom,
+                q + Vec3::Y * top,
+                p + Vec3::Y * top,
+                normal,
+                color,
+            );
+        }
+    }
+
     fn add_quad(&mut self, v0: Vec3, v1: Vec3, v2: Vec3, v3: Vec3, normal: Vec3, color: [f32; 3]) {
         let base = self.vertices.len() as u32;
         
-        self.vertices.push(BuildingVertex { position: v0.to_array(), normal: normal.to_array(), uv: [0.0, 1.0], color });
-        self.vertices.push(BuildingVertex { position: v1.to_array(), normal: normal.to_array(), uv: [1.0, 1.0], color });
-        self.vertices.push(BuildingVertex { position: v2.to_array(), normal: normal.to_array(), uv: [1.0, 0.0], color });
-        self.vertices.push(BuildingVertex { position: v3.to_array(), normal: normal.to_array(), uv: [0.0, 0.0], color });
+        self.vertices.push(BuildingVertex { position: v0.to_array(), normal: normal.to_array(), uv: [0.0, 1.0], color, emissive: [0.0; 3] });
+        self.vertices.push(BuildingVertex { position: v1.to_array(), normal: normal.to_array(), uv: [1.0, 1.0], color, emissive: [0.0; 3] });
+        self.vertices.push(BuildingVertex { position: v2.to_array(), normal: normal.to_array(), uv: [1.0, 0.0], color, emissive: [0.0; 3] });
+        self.vertices.push(BuildingVertex { position: v3.to_array(), normal: normal.to_array(), uv: [0.0, 0.0], color, emissive: [0.0; 3] });
 
         self.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
     }
@@ -327,9 +720,9 @@ impl MeshBuilder {
     fn add_tri(&mut self, v0: Vec3, v1: Vec3, v2: Vec3, normal: Vec3, color: [f32; 3]) {
         let base = self.vertices.len() as u32;
 
-        self.vertices.push(BuildingVertex { position: v0.to_array(), normal: normal.to_array(), uv: [0.0, 0.0], color });
-        self.vertices.push(BuildingVertex { position: v1.to_array(), normal: normal.to_array(), uv: [1.0, 0.0], color });
-        self.vertices.push(BuildingVertex { position: v2.to_array(), normal: normal.to_array(), uv: [0.5, 1.0], color });
+        self.vertices.push(BuildingVertex { position: v0.to_array(), normal: normal.to_array(), uv: [0.0, 0.0], color, emissive: [0.0; 3] });
+        self.vertices.push(BuildingVertex { position: v1.to_array(), normal: normal.to_array(), uv: [1.0, 0.0], color, emissive: [0.0; 3] });
+        self.vertices.push(BuildingVertex { position: v2.to_array(), normal: normal.to_array(), uv: [0.5, 1.0], color, emissive: [0.0; 3] });
 
         self.indices.extend_from_slice(&[base, base + 1, base + 2]);
     }
@@ -346,4 +739,108 @@ mod tests {
         assert!(!mesh.vertices.is_empty());
         assert!(!mesh.indices.is_empty());
     }
+
+    #[test]
+    fn test_interior_keeps_exterior_and_faces_inward() {
+        let exterior = generate_building(&BuildingRecipe::colonial_house());
+        let recipe = BuildingRecipe { include_interior: true, ..BuildingRecipe::colonial_house() };
+        let mesh = generate_building(&recipe);
+
+        // Exterior geometry comes first and is untouched
+        assert!(mesh.vertices.len() > exterior.vertices.len());
+        assert!(mesh.vertices[..exterior.vertices.len()].iter().zip(&exterior.vertices).all(|(a, b)| a.position == b.position && a.normal == b.normal));
+        assert_eq!(&mesh.indices[..exterior.indices.len()], &exterior.indices[..]);
+
+        let interior = &mesh.vertices[exterior.vertices.len()..];
+        for v in interior {
+            // Every interior face looks into the building, never out through its walls
+            let probe = Vec3::from_array(v.position) + Vec3::from_array(v.normal) * 0.05;
+            assert!(probe.x.abs() < recipe.width * 0.5 && probe.z.abs() < recipe.depth * 0.5, "face at {:?} looks outside", v.position);
+        }
+        // Winding agrees with the stored normal, so back-face culling keeps the inside faces
+        for tri in mesh.indices[exterior.indices.len()..].chunks(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| Vec3::from_array(mesh.vertices[i as usize].position));
+            let normal = Vec3::from_array(mesh.vertices[tri[0] as usize].normal);
+            assert!((b - a).cross(c - a).dot(normal) > 0.0, "triangle {:?} wound against its normal", [a, b, c]);
+        }
+
+        // Each story has an upward floor; the upper one has less area (the stair opening)
+        let floor_area = |story: u32| {
+            let y = 0.4 + story as f32 * recipe.floor_height + 0.02;
+            mesh.indices[exterior.indices.len()..].chunks(3).map(|tri| {
+                let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| mesh.vertices[i as usize]);
+                if a.normal == [0.0, 1.0, 0.0] && [a, b, c].iter().all(|v| (v.position[1] - y).abs() < 1e-4) {
+                    let [a, b, c] = [a, b, c].map(|v| Vec3::from_array(v.position));
+                    (b - a).cross(c - a).length() * 0.5
+                } else {
+                    0.0
+                }
+            }).sum::<f32>()
+        };
+        assert!(floor_area(0) > 0.0);
+        assert!(floor_area(1) > 0.0 && floor_area(1) < floor_area(0) - 1.0, "no stair opening: {} vs {}", floor_area(1), floor_area(0));
+    }
+
+    #[test]
+    fn test_only_window_glass_glows() {
+        let mesh = generate_building(&BuildingRecipe::colonial_house());
+        let glowing: Vec<&BuildingVertex> = mesh.vertices.iter().filter(|v| v.emissive != [0.0; 3]).collect();
+        assert!(!glowing.is_empty());
+        assert!(glowing.iter().all(|v| v.color == [0.2, 0.3, 0.5]), "only the glass emits");
+        assert!(glowing.len() < mesh.vertices.len() / 4);
+    }
+
+    #[test]
+    fn test_new_building_types() {
+        let church = generate_building(&BuildingRecipe::church());
+        let tower = generate_building(&BuildingRecipe::watchtower());
+        let block = generate_building(&BuildingRecipe::apartment_block());
+
+        for mesh in [&church, &tower, &block] {
+            assert!(!mesh.indices.is_empty());
+            // Every triangle is wound to face along its stored normal
+            for tri in mesh.indices.chunks(3) {
+                let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| Vec3::from_array(mesh.vertices[i as usize].position));
+                let normal = Vec3::from_array(mesh.vertices[tri[0] as usize].normal);
+                assert!((b - a).cross(c - a).dot(normal) >= 0.0, "triangle {:?} faces away from {:?}", [a, b, c], normal);
+            }
+        }
+
+        // The steeple's spire rises well above the nave's roof ridge
+        let church_recipe = BuildingRecipe::church();
+        let ridge = 0.4 + church_recipe.floor_height + church_recipe.roof_height;
+        assert!(church.bounds().1.y > ridge + 5.0, "spire top {}", church.bounds().1.y);
+
+        // The tower is round: as wide along X as along Z
+        let (min, max) = tower.bounds();
+        assert!(((max.x - min.x) - (max.z - min.z)).abs() < 0.2);
+
+        // The block stacks four stories
+        assert!(block.bounds().1.y > 4.0 * 3.0);
+    }
+
+    #[test]
+    fn test_footprint_matches_foundation() {
+        for recipe in [BuildingRecipe::colonial_house(), BuildingRecipe::church(), BuildingRecipe::watchtower(), BuildingRecipe::apartment_block()] {
+            let (min, max) = recipe.footprint();
+            // Everything at ground level lies within the footprint...
+            let mesh = generate_building(&recipe);
+            let ground: Vec<Vec3> = mesh.vertices.iter().map(|v| Vec3::from_array(v.position)).filter(|p| p.y <= 0.0).collect();
+            assert!(!ground.is_empty());
+            for p in &ground {
+                assert!(p.x >= min.x - 1e-4 && p.x <= max.x + 1e-4 && p.z >= min.y - 1e-4 && p.z <= max.y + 1e-4, "{:?} outside {:?}", p, recipe.shape);
+            }
+            // ...and the foundation reaches the skirt depth
+            assert!(ground.iter().any(|p| (p.y + FOUNDATION_SKIRT).abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn test_building_bounds_cover_footprint() {
+        let recipe = BuildingRecipe::small_shack();
+        let (min, max) = generate_building(&recipe).bounds();
+        let size = max - min;
+        assert!(size.x >= recipe.width && size.z >= recipe.depth, "bounds {:?}", size);
+        assert!(size.y >= recipe.floor_height, "bounds {:?}", size);
+    }
 }
@@ -130,7 +130,7 @@ pub fn generate_grass_patch(
     terrain_height_fn: impl Fn(f32, f32) -> f32,
     biome_filter: impl Fn(f32, f32) -> bool,
 ) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>) {
-    let noise = Perlin::new(seed + 999);
+    let noise = Perlin::new(seed.wrapping_add(999));
 
     let blade_count = (chunk_size * chunk_size * density) as u32;
     let mut all_positions = Vec::new();
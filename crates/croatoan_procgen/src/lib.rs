@@ -2,8 +2,12 @@ pub mod grass;
 pub mod tree;
 pub mod rock;
 pub mod building;
+pub mod plant;
+pub mod rng;
 
 pub use grass::*;
 pub use tree::*;
 pub use rock::*;
-pub use building::*;
\ No newline at end of file
+pub use building::*;
+pub use plant::*;
+pub use rng::*;
\ No newline at end of file
@@ -0,0 +1,276 @@
+use glam::Vec3;
+use crate::rng::SeededRng;
+use std::f32::consts::PI;
+
+/// Kinds of small ground plant that grow among the grass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlantKind {
+    /// Rounded clump of crossed leaf cards
+    Bush,
+    /// Rosette of arching, tapered fronds
+    Fern,
+    /// Cluster of thin stems, each topped with a petal fan
+    Flower,
+}
+
+/// Recipe for generating a plant mesh
+#[derive(Debug, Clone)]
+pub struct PlantRecipe {
+    pub kind: PlantKind,
+    pub height: f32,
+    /// Radius the plant covers on the ground
+    pub spread: f32,
+    /// Leaf cards (bush), fronds (fern) or stems (flower)
+    pub leaf_count: u32,
+    pub color_base: [f32; 3],
+    pub color_tip: [f32; 3],
+}
+
+impl PlantRecipe {
+    pub fn bush() -> Self {
+        Self {
+            kind: PlantKind::Bush,
+            height: 1.1,
+            spread: 0.9,
+            leaf_count: 9,
+            color_base: [0.10, 0.22, 0.07],
+            color_tip: [0.22, 0.42, 0.12],
+        }
+    }
+
+    pub fn fern() -> Self {
+        Self {
+            kind: PlantKind::Fern,
+            height: 0.6,
+            spread: 0.9,
+            leaf_count: 7,
+            color_base: [0.12, 0.30, 0.08],
+            color_tip: [0.30, 0.58, 0.16],
+        }
+    }
+
+    pub fn flower() -> Self {
+        Self {
+            kind: PlantKind::Flower,
+            height: 0.45,
+            spread: 0.25,
+            leaf_count: 5,
+            color_base: [0.18, 0.40, 0.10],
+            color_tip: [0.28, 0.52, 0.14],
+        }
+    }
+}
+
+/// Petal colours a flower cluster picks from: white, yellow, violet, pink
+const FLOWER_PALETTE: [[f32; 3]; 4] = [
+    [0.92, 0.90, 0.85],
+    [0.95, 0.78, 0.15],
+    [0.55, 0.35, 0.80],
+    [0.90, 0.45, 0.60],
+];
+
+/// Plant mesh data in local space, base at the origin (+Y up)
+/// Every leaf is emitted twice with opposite winding so it can be drawn with back-face culling.
+pub struct PlantMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub colors: Vec<[f32; 3]>,
+    pub indices: Vec<u32>,
+}
+
+impl PlantMesh {
+    fn new() -> Self {
+        Self { positions: Vec::new(), normals: Vec::new(), colors: Vec::new(), indices: Vec::new() }
+    }
+
+    /// Double-sided triangle strip between two rows of points (`left[i]`, `right[i]`)
+    fn add_ribbon(&mut self, left: &[Vec3], right: &[Vec3], colors: &[[f32; 3]]) {
+        for side in [1.0, -1.0] {
+            let base = self.positions.len() as u32;
+            for i in 0..left.len() {
+                let along = if i + 1 < left.len() { left[i + 1] - left[i] } else { left[i] - left[i - 1] };
+                let normal = (right[i] - left[i]).cross(along).normalize_or_zero() * side;
+                for point in [left[i], right[i]] {
+                    self.positions.push(point.to_array());
+                    self.normals.push(normal.to_array());
+                    self.colors.push(colors[i]);
+                }
+            }
+            for i in 0..left.len() as u32 - 1 {
+                let (l0, r0, l1, r1) = (base + i * 2, base + i * 2 + 1, base + i * 2 + 2, base + i * 2 + 3);
+                if side > 0.0 {
+                    self.indices.extend_from_slice(&[l0, r0, l1, r0, r1, l1]);
+                } else {
+                    self.indices.extend_from_slice(&[l0, l1, r0, r0, l1, r1]);
+                }
+            }
+        }
+    }
+
+    /// Double-sided fan of triangles around `center`
+    fn add_fan(&mut self, center: Vec3, rim: &[Vec3], center_color: [f32; 3], rim_color: [f32; 3]) {
+        for i in 0..rim.len() {
+            let (a, b) = (rim[i], rim[(i + 1) % rim.len()]);
+            let normal = (a - center).cross(b - center).normalize_or_zero();
+            for (side, normal) in [(true, normal), (false, -normal)] {
+                let base = self.positions.len() as u32;
+                for (point, color) in [(center, center_color), (a, rim_color), (b, rim_color)] {
+                    self.positions.push(point.to_array());
+                    self.normals.push(normal.to_array());
+                    self.colors.push(color);
+                }
+                if side {
+                    self.indices.extend_from_slice(&[base, base + 1, base + 2]);
+                } else {
+                    self.indices.extend_from_slice(&[base, base + 2, base + 1]);
+                }
+            }
+        }
+    }
+}
+
+/// Generates a single procedural plant
+///
+/// The shape follows `recipe.kind`; seed varies leaf angles, lengths and (for flowers) petal colour.
+pub fn generate_plant(recipe: &PlantRecipe, seed: u32) -> PlantMesh {
+    let mut rng = SeededRng::new(seed as u64);
+    let mut random = || rng.next_f32();
+
+    let mut mesh = PlantMesh::new();
+    let golden_angle = PI * (3.0 - 5.0_f32.sqrt());
+
+    match recipe.kind {
+        PlantKind::Bush => {
+            // Leaf cards leaning out from the centre, overlapping into a dome
+            for i in 0..recipe.leaf_count {
+                let yaw = i as f32 * golden_angle + random() * 0.4;
+                let dir = Vec3::new(yaw.cos(), 0.0, yaw.sin());
+                let side = Vec3::new(-dir.z, 0.0, dir.x);
+                let lean = 0.3 + random() * 0.5;
+                let height = recipe.height * (0.7 + random() * 0.3);
+                let width = recipe.spread * (0.8 + random() * 0.4);
+
+                let bottom = dir * recipe.spread * 0.1;
+                let top = bottom + dir * recipe.spread * lean + Vec3::Y * height;
+                let middle = (bottom + top) * 0.5 + dir * recipe.spread * 0.15;
+                let rows = [bottom, middle, top];
+                let widths = [width * 0.4, width * 0.5, width * 0.3];
+                let left: Vec<Vec3> = rows.iter().zip(widths).map(|(p, w)| *p + side * w).collect();
+                let right: Vec<Vec3> = rows.iter().zip(widths).map(|(p, w)| *p - side * w).collect();
+                let colors = [recipe.color_base, lerp_color(recipe.color_base, recipe.color_tip, 0.6), recipe.color_tip];
+                mesh.add_ribbon(&left, &right, &colors);
+            }
+        }
+        PlantKind::Fern => {
+            // Fronds rise and arch back toward the ground, widest a third of the way out
+            let segments = 6;
+            for i in 0..recipe.leaf_count {
+                let yaw = i as f32 * golden_angle + random() * 0.3;
+                let dir = Vec3::new(yaw.cos(), 0.0, yaw.sin());
+                let side = Vec3::new(-dir.z, 0.0, dir.x);
+                let length = recipe.spread * (0.75 + random() * 0.25);
+                let rise = recipe.height * (0.8 + random() * 0.2);
+                let width = length * 0.22;
+
+                let mut left = Vec::with_capacity(segments + 1);
+                let mut right = Vec::with_capacity(segments + 1);
+                let mut colors = Vec::with_capacity(segments + 1);
+                for s in 0..=segments {
+                    let t = s as f32 / segments as f32;
+                    let spine = dir * length * t + Vec3::Y * rise * t * (2.0 - 1.6 * t);
+                    let half_width = width * (t * PI).sin().max(0.05) * (1.0 - t * 0.5);
+                    left.push(spine + side * half_width);
+                    right.push(spine - side * half_width);
+                    colors.push(lerp_color(recipe.color_base, recipe.color_tip, t));
+                }
+                mesh.add_ribbon(&left, &right, &colors);
+            }
+        }
+        PlantKind::Flower => {
+            // A few stems, each with a petal fan and a yellow centre
+            let petals = 6;
+            for i in 0..recipe.leaf_count {
+                let yaw = i as f32 * golden_angle;
+                let offset = Vec3::new(yaw.cos(), 0.0, yaw.sin()) * recipe.spread * random().sqrt();
+                let height = recipe.height * (0.6 + random() * 0.4);
+                let petal_color = FLOWER_PALETTE[(random() * FLOWER_PALETTE.len() as f32) as usize % FLOWER_PALETTE.len()];
+                let head = offset + Vec3::Y * height;
+
+                let stem_width = 0.012;
+                let side = Vec3::new(-yaw.sin(), 0.0, yaw.cos()) * stem_width;
+                mesh.add_ribbon(
+                    &[offset + side, head + side],
+                    &[offset - side, head - side],
+                    &[recipe.color_base, recipe.color_tip],
+                );
+
+                let radius = 0.05 + random() * 0.03;
+                let rim: Vec<Vec3> = (0..petals)
+                    .map(|p| {
+                        let angle = p as f32 / petals as f32 * 2.0 * PI + yaw;
+                        head + Vec3::new(angle.cos() * radius, radius * 0.3, angle.sin() * radius)
+                    })
+                    .collect();
+                mesh.add_fan(head, &rim, petal_color, petal_color);
+                let centre: Vec<Vec3> = rim.iter().map(|p| head + (*p - head) * 0.3 + Vec3::Y * 0.005).collect();
+                mesh.add_fan(head + Vec3::Y * 0.01, &centre, [0.95, 0.75, 0.10], [0.85, 0.60, 0.10]);
+            }
+        }
+    }
+
+    mesh
+}
+
+fn lerp_color(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plants_are_distinct_and_grounded() {
+        for recipe in [PlantRecipe::bush(), PlantRecipe::fern(), PlantRecipe::flower()] {
+            let mesh = generate_plant(&recipe, 7);
+            assert!(!mesh.indices.is_empty(), "{:?} has no triangles", recipe.kind);
+            assert_eq!(mesh.positions.len(), mesh.normals.len());
+            assert_eq!(mesh.positions.len(), mesh.colors.len());
+            assert!(mesh.indices.iter().all(|&i| (i as usize) < mesh.positions.len()));
+
+            // Base at the origin, nothing below ground or above the recipe height
+            let min_y = mesh.positions.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+            let max_y = mesh.positions.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+            assert!(min_y.abs() < 1e-4, "{:?} starts at {}", recipe.kind, min_y);
+            assert!(max_y <= recipe.height * 1.2, "{:?} reaches {}", recipe.kind, max_y);
+        }
+
+        // Winding agrees with the stored normal, so culling keeps the side facing the camera
+        let fern = generate_plant(&PlantRecipe::fern(), 7);
+        for tri in fern.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| Vec3::from(fern.positions[tri[k] as usize]));
+            let normal = Vec3::from(fern.normals[tri[0] as usize]);
+            assert!((b - a).cross(c - a).dot(normal) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_flower_colours_vary_with_seed() {
+        let petal_colors = |seed| {
+            let mesh = generate_plant(&PlantRecipe::flower(), seed);
+            let mut colors: Vec<[u32; 3]> = mesh.colors.iter()
+                .filter(|c| FLOWER_PALETTE.contains(c))
+                .map(|c| c.map(f32::to_bits))
+                .collect();
+            colors.dedup();
+            colors
+        };
+        let seeds: Vec<_> = (0..8).map(petal_colors).collect();
+        assert!(seeds.iter().all(|colors| !colors.is_empty()));
+        assert!(seeds.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+}
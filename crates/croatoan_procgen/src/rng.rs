@@ -0,0 +1,91 @@
+use std::ops::Range;
+
+/// Seeded random number generator for procedural generation (PCG32)
+///
+/// Same seed, same sequence, on every platform and run, so anything generated from it can be
+/// regenerated instead of stored. Streams give independent sequences for one seed, e.g. one
+/// per chunk of a world seed. Not for anything security-related.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+    increment: u64,
+}
+
+impl SeededRng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0)
+    }
+
+    /// Sequence `stream` of `seed`; different streams don't overlap or correlate
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self { state: 0, increment: (stream << 1) | 1 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Self::MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 random bits: every value is exactly representable, and 1.0 is never reached
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Uniform in `range` (start inclusive, end exclusive)
+    pub fn next_range(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.next_f32()
+    }
+
+    /// Uniform index in 0..`len` (0 if `len` is 0)
+    pub fn next_index(&mut self, len: usize) -> usize {
+        ((self.next_u32() as u64 * len as u64) >> 32) as usize
+    }
+
+    /// True with probability `p` (clamped to 0..1)
+    pub fn gen_bool(&mut self, p: f32) -> bool {
+        self.next_f32() < p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequences_repeat_per_seed_and_stream() {
+        let sequence = |mut rng: SeededRng| (0..16).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        assert_eq!(sequence(SeededRng::new(42)), sequence(SeededRng::new(42)));
+        assert_ne!(sequence(SeededRng::new(42)), sequence(SeededRng::new(43)));
+        assert_ne!(sequence(SeededRng::with_stream(42, 1)), sequence(SeededRng::with_stream(42, 2)));
+        // Reference output, so the sequence (and every world built on it) can't drift
+        assert_eq!(sequence(SeededRng::with_stream(42, 54))[..3], [0xa15c02b7, 0x7b47f409, 0xba1d3330]);
+    }
+
+    #[test]
+    fn test_values_stay_in_range() {
+        let mut rng = SeededRng::new(7);
+        let mut sum = 0.0;
+        for _ in 0..10_000 {
+            let value = rng.next_f32();
+            assert!((0.0..1.0).contains(&value));
+            sum += value;
+
+            assert!((-3.0..5.0).contains(&rng.next_range(-3.0..5.0)));
+            assert!(rng.next_index(6) < 6);
+        }
+        assert!((sum / 10_000.0 - 0.5).abs() < 0.02, "mean {}", sum / 10_000.0);
+
+        assert!(!rng.gen_bool(0.0));
+        assert!(rng.gen_bool(1.0));
+        assert_eq!(rng.next_index(0), 0);
+    }
+}
@@ -1,4 +1,5 @@
 use glam::Vec3;
+use crate::rng::SeededRng;
 
 /// Types of rock formations
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +19,10 @@ pub struct RockRecipe {
     pub subdivision_levels: u32,
     pub roughness: f32,
     pub deformation: f32,
+    /// Random cutting planes that flatten the surface into facets (0 = smooth, no cracks)
+    pub facet_count: u32,
+    /// How far Voronoi cracks sink into a faceted rock (0 = none)
+    pub crack_depth: f32,
 }
 
 impl Default for RockRecipe {
@@ -35,6 +40,8 @@ impl RockRecipe {
             subdivision_levels: 2,
             roughness: 0.1,
             deformation: 0.2,
+            facet_count: 0,
+            crack_depth: 0.0,
         }
     }
 
@@ -46,6 +53,8 @@ impl RockRecipe {
             subdivision_levels: 3, // More smooth
             roughness: 0.05,
             deformation: 0.1,
+            facet_count: 0,
+            crack_depth: 0.0,
         }
     }
 
@@ -54,9 +63,25 @@ impl RockRecipe {
             rock_type: RockType::SharpRock,
             base_size: Vec3::new(0.8, 1.2, 0.8),
             seed: 0,
-            subdivision_levels: 1, // Angular
+            subdivision_levels: 3, // Fine enough for the cuts and cracks to stay crisp
             roughness: 0.4,
             deformation: 0.5,
+            facet_count: 10,
+            crack_depth: 0.06,
+        }
+    }
+
+    /// Upright rock wall `width` x `height` (world units), base at the origin, front facing +Z
+    pub fn cliff_face(width: f32, height: f32) -> Self {
+        RockRecipe {
+            rock_type: RockType::CliffFace,
+            base_size: Vec3::new(width, height, width.min(height) * 0.2), // Thickness from the smaller side
+            seed: 0,
+            subdivision_levels: 4, // Dense enough for the front's fractures
+            roughness: 0.5,
+            deformation: 0.6,
+            facet_count: 0,
+            crack_depth: 0.0,
         }
     }
 }
@@ -87,8 +112,24 @@ pub fn generate_rock(recipe: &RockRecipe) -> RockMesh {
         subdivide(&mut vertices, &mut indices);
     }
 
+    // Walls are shaped from the sphere differently (and mapped planar, so no seam)
+    if recipe.rock_type == RockType::CliffFace {
+        shape_cliff_face(&mut vertices, recipe);
+        recalculate_normals(&mut vertices, &indices);
+        return RockMesh { vertices, indices };
+    }
+
     // Displace vertices based on noise and recipe parameters
     displace_vertices(&mut vertices, recipe);
+    fix_uv_seam(&mut vertices, &mut indices);
+
+    // Crystal mode: cut flat facets, crack along Worley cell edges, then split the vertices
+    // so every triangle keeps its own normal and the facet edges stay hard
+    if recipe.facet_count > 0 {
+        cut_facets(&mut vertices, recipe);
+        carve_cracks(&mut vertices, recipe);
+        (vertices, indices) = unweld(&vertices, &indices);
+    }
 
     // Recalculate normals
     recalculate_normals(&mut vertices, &indices);
@@ -221,6 +262,135 @@ fn displace_vertices(vertices: &mut Vec<RockVertex>, recipe: &RockRecipe) {
     }
 }
 
+/// Flatten the sphere into a wall: stretched to `base_size` (x = width, y = height, z =
+/// thickness), the back pressed nearly flat and the front pushed out into a slab broken up
+/// by ridged, vertically stretched noise
+fn shape_cliff_face(vertices: &mut [RockVertex], recipe: &RockRecipe) {
+    use noise::{NoiseFn, Perlin};
+    let perlin = Perlin::new(recipe.seed);
+    let size = recipe.base_size;
+
+    for v in vertices.iter_mut() {
+        let pos = Vec3::from_array(v.position);
+        let x = pos.x * size.x * 0.5;
+        let y = (pos.y * 0.5 + 0.4) * size.y; // A tenth of the height sits below the base
+
+        let z = if pos.z >= 0.0 {
+            // Fractures: sharp ridges (1 - |noise|) running mostly vertically, plus finer chips
+            let sample = |scale: f64, stretch: f64| perlin.get([x as f64 * scale, y as f64 * scale * stretch, 0.0]) as f32;
+            let ridges = 1.0 - sample(0.35, 0.3).abs();
+            let chips = sample(1.2, 1.0);
+            let fracture = (ridges * 0.7 + chips * 0.3) * recipe.roughness * recipe.deformation;
+            size.z * (pos.z.powf(0.35) * 0.6 + fracture)
+        } else {
+            -size.z * 0.4 * pos.z.abs().powf(0.15) // Mostly flat back
+        };
+        v.position = [x, y, z];
+
+        // Planar mapping across the face
+        v.uv = [x / size.x + 0.5, 1.0 - y / size.y];
+    }
+}
+
+/// Triangles straddling the spherical U wrap (1.0 -> 0.0 at the back) would interpolate
+/// across the whole texture; give their low-U corners a duplicate vertex at U + 1.0 instead.
+/// Returns the number of vertices duplicated.
+fn fix_uv_seam(vertices: &mut Vec<RockVertex>, indices: &mut [u32]) -> usize {
+    let mut wrapped = std::collections::HashMap::new();
+
+    for tri in indices.chunks_mut(3) {
+        let us = [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize].uv[0]);
+        let (min_u, max_u) = (us.iter().cloned().fold(f32::MAX, f32::min), us.iter().cloned().fold(f32::MIN, f32::max));
+        if max_u - min_u <= 0.5 {
+            continue;
+        }
+
+        for index in tri.iter_mut() {
+            if vertices[*index as usize].uv[0] < 0.5 {
+                *index = *wrapped.entry(*index).or_insert_with(|| {
+                    let mut duplicate = vertices[*index as usize];
+                    duplicate.uv[0] += 1.0;
+                    vertices.push(duplicate);
+                    vertices.len() as u32 - 1
+                });
+            }
+        }
+    }
+
+    wrapped.len()
+}
+
+/// Random unit directions (uniform on the sphere) for a seed
+fn random_directions(seed: u32, count: u32) -> Vec<Vec3> {
+    let mut rng = SeededRng::new(seed as u64);
+
+    (0..count)
+        .map(|_| {
+            let y = rng.next_range(-1.0..1.0);
+            let angle = rng.next_range(0.0..std::f32::consts::TAU);
+            let r = (1.0 - y * y).sqrt();
+            Vec3::new(r * angle.cos(), y, r * angle.sin())
+        })
+        .collect()
+}
+
+/// Project every vertex past a cutting plane back onto it, slicing flat faces off the rock
+fn cut_facets(vertices: &mut [RockVertex], recipe: &RockRecipe) {
+    let normals = random_directions(recipe.seed.wrapping_add(1), recipe.facet_count);
+
+    for (i, normal) in normals.iter().enumerate() {
+        // Each plane sits 65-90% of the way out to the surface in its direction
+        let extent = vertices.iter().map(|v| Vec3::from_array(v.position).dot(*normal)).fold(f32::MIN, f32::max);
+        let depth = 0.65 + 0.25 * ((i as f32 * 0.618_034).fract());
+        let offset = extent * depth;
+
+        for v in vertices.iter_mut() {
+            let pos = Vec3::from_array(v.position);
+            let beyond = pos.dot(*normal) - offset;
+            if beyond > 0.0 {
+                v.position = (pos - *normal * beyond).to_array();
+            }
+        }
+    }
+}
+
+/// Sink vertices near the borders of Worley cells (seeded directions) to form cracks
+fn carve_cracks(vertices: &mut [RockVertex], recipe: &RockRecipe) {
+    const CRACK_WIDTH: f32 = 0.04;
+    if recipe.crack_depth <= 0.0 {
+        return;
+    }
+    let cells = random_directions(recipe.seed.wrapping_add(2), recipe.facet_count * 2);
+
+    for v in vertices.iter_mut() {
+        let pos = Vec3::from_array(v.position);
+        let dir = pos.normalize_or_zero();
+
+        // F2 - F1 (angular distance to the two nearest cell centres): zero on a cell border
+        let (mut f1, mut f2) = (f32::MAX, f32::MAX);
+        for cell in &cells {
+            let d = 1.0 - dir.dot(*cell);
+            if d < f1 {
+                f2 = f1;
+                f1 = d;
+            } else if d < f2 {
+                f2 = d;
+            }
+        }
+
+        let edge = f2 - f1;
+        if edge < CRACK_WIDTH {
+            v.position = (pos - dir * recipe.crack_depth * (1.0 - edge / CRACK_WIDTH)).to_array();
+        }
+    }
+}
+
+/// One vertex per index, so normals aren't averaged across facet edges
+fn unweld(vertices: &[RockVertex], indices: &[u32]) -> (Vec<RockVertex>, Vec<u32>) {
+    let split = indices.iter().map(|&i| vertices[i as usize]).collect();
+    (split, (0..indices.len() as u32).collect())
+}
+
 fn recalculate_normals(vertices: &mut Vec<RockVertex>, indices: &Vec<u32>) {
     // Reset normals
     for v in vertices.iter_mut() {
@@ -277,6 +447,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cliff_face_is_a_wall() {
+        let mesh = generate_rock(&RockRecipe::cliff_face(8.0, 6.0));
+        let (mut min, mut max) = (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN));
+        for v in &mesh.vertices {
+            min = min.min(Vec3::from_array(v.position));
+            max = max.max(Vec3::from_array(v.position));
+        }
+        let extent = max - min;
+
+        // Requested width and height, much thinner than either
+        assert!((extent.x - 8.0).abs() < 0.5, "width {}", extent.x);
+        assert!((extent.y - 6.0).abs() < 0.5, "height {}", extent.y);
+        assert!(extent.z < extent.x * 0.5 && extent.z < extent.y * 0.5, "thickness {}", extent.z);
+        assert!(min.y < 0.0); // Base sinks into the ground
+
+        // Fractured front, flat back: depth varies far more across the front
+        let spread = |front: bool| {
+            let zs: Vec<f32> = mesh.vertices.iter().map(|v| v.position).filter(|p| (p[2] > 0.0) == front && p[1] > 1.0 && p[1] < 5.0 && p[0].abs() < 3.0).map(|p| p[2]).collect();
+            zs.iter().cloned().fold(f32::MIN, f32::max) - zs.iter().cloned().fold(f32::MAX, f32::min)
+        };
+        assert!(spread(true) > spread(false) * 2.0, "front {} back {}", spread(true), spread(false));
+    }
+
+    #[test]
+    fn test_uv_seam_duplicates_vertices() {
+        let (mut vertices, mut indices) = create_base_icosphere();
+        subdivide(&mut vertices, &mut indices);
+        displace_vertices(&mut vertices, &RockRecipe::boulder());
+        let welded = vertices.len();
+
+        let duplicated = fix_uv_seam(&mut vertices, &mut indices);
+        assert!(duplicated > 0);
+        assert_eq!(vertices.len(), welded + duplicated);
+
+        // No triangle mixes corners from both sides of the wrap any more
+        // (near the poles U legitimately fans out, so this checks the seam rather than the span)
+        for tri in indices.chunks(3) {
+            let us = [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize].uv[0]);
+            assert!(!(us.iter().any(|&u| u < 0.25) && us.iter().any(|&u| u > 0.75)), "triangle {:?} still crosses the seam", us);
+        }
+    }
+
+    /// Distinct face normals, quantized so float noise doesn't count
+    fn unique_face_normals(mesh: &RockMesh) -> usize {
+        let mut normals = std::collections::HashSet::new();
+        for tri in mesh.indices.chunks(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| Vec3::from_array(mesh.vertices[i as usize].position));
+            let n = (b - a).cross(c - a).normalize_or_zero();
+            normals.insert((n * 20.0).round().as_ivec3().to_array());
+        }
+        normals.len()
+    }
+
+    #[test]
+    fn test_sharp_rock_is_faceted() {
+        let boulder = generate_rock(&RockRecipe::boulder());
+        let sharp = generate_rock(&RockRecipe::sharp_rock());
+        assert!(unique_face_normals(&sharp) > unique_face_normals(&boulder));
+        // ...yet many triangles lie on shared flat facets, which the boulder never does
+        assert!(unique_face_normals(&sharp) < sharp.indices.len() / 3);
+        assert_eq!(unique_face_normals(&boulder), boulder.indices.len() / 3);
+    }
+
     #[test]
     fn test_different_types() {
         let types = [
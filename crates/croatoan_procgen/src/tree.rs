@@ -1,5 +1,8 @@
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 use glam::{Vec3, Quat};
+use crate::rng::SeededRng;
 
 /// Tree species with different growth characteristics
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,8 +40,13 @@ pub struct TreeRecipe {
     pub species: TreeSpecies,
     pub branch_segments: u32,
     pub radial_segments: u32,
+    /// Longest L-system string `generate_string` builds (see there)
+    pub max_length: usize,
 }
 
+/// Default `TreeRecipe::max_length`: far past any built-in species (a few thousand symbols)
+pub const DEFAULT_MAX_LSYSTEM_LENGTH: usize = 200_000;
+
 impl Default for TreeRecipe {
     fn default() -> Self {
         TreeRecipe::oak()
@@ -65,6 +73,7 @@ impl TreeRecipe {
             species: TreeSpecies::Oak,
             branch_segments: 3,
             radial_segments: 4,
+            max_length: DEFAULT_MAX_LSYSTEM_LENGTH,
         }
     }
 
@@ -87,6 +96,7 @@ impl TreeRecipe {
             species: TreeSpecies::Pine,
             branch_segments: 2,
             radial_segments: 4,
+            max_length: DEFAULT_MAX_LSYSTEM_LENGTH,
         }
     }
 
@@ -109,6 +119,7 @@ impl TreeRecipe {
             species: TreeSpecies::Willow,
             branch_segments: 3,
             radial_segments: 4,
+            max_length: DEFAULT_MAX_LSYSTEM_LENGTH,
         }
     }
 
@@ -131,6 +142,7 @@ impl TreeRecipe {
             species: TreeSpecies::Birch,
             branch_segments: 3,
             radial_segments: 5,
+            max_length: DEFAULT_MAX_LSYSTEM_LENGTH,
         }
     }
 
@@ -154,6 +166,7 @@ impl TreeRecipe {
             species: TreeSpecies::Palm,
             branch_segments: 2,
             radial_segments: 5,
+            max_length: DEFAULT_MAX_LSYSTEM_LENGTH,
         }
     }
 
@@ -176,6 +189,7 @@ impl TreeRecipe {
             species: TreeSpecies::Maple,
             branch_segments: 3,
             radial_segments: 4,
+            max_length: DEFAULT_MAX_LSYSTEM_LENGTH,
         }
     }
 
@@ -198,14 +212,19 @@ impl TreeRecipe {
             species: TreeSpecies::Spruce,
             branch_segments: 2,
             radial_segments: 4,
+            max_length: DEFAULT_MAX_LSYSTEM_LENGTH,
         }
     }
 
     /// Generate the L-System string after N iterations
+    ///
+    /// Growth is exponential: a rule putting k copies of its symbol back multiplies the length
+    /// by about k per iteration (oak's 'F' rule has 8, so 8 iterations would be tens of millions
+    /// of symbols). Expansion stops at the last iteration that fits in `max_length`, with a warning.
     pub fn generate_string(&self) -> String {
         let mut current = self.axiom.clone();
 
-        for _ in 0..self.iterations {
+        for iteration in 0..self.iterations {
             let mut next = String::new();
 
             for ch in current.chars() {
@@ -214,6 +233,13 @@ impl TreeRecipe {
                 } else {
                     next.push(ch);
                 }
+                if next.len() > self.max_length {
+                    log::warn!(
+                        "L-system passed {} symbols at iteration {} of {}; using iteration {}",
+                        self.max_length, iteration + 1, self.iterations, iteration
+                    );
+                    return current;
+                }
             }
 
             current = next;
@@ -221,8 +247,101 @@ impl TreeRecipe {
 
         current
     }
+
+    /// Load a recipe from a tree file (see `parse`)
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parse a tree file: `#` comments, `F -> FF[-F]` rewrite rules, and `key = value` lines
+    /// for the axiom and numeric parameters (`angle` in degrees). Anything left out keeps the
+    /// oak default, except that there are no rules unless given. Species is always `Custom`.
+    /// Symbols that would do nothing, and rules that never fire, are logged as warnings.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut recipe = TreeRecipe {
+            rules: HashMap::new(),
+            species: TreeSpecies::Custom,
+            ..TreeRecipe::oak()
+        };
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+
+            if let Some((from, to)) = line.split_once("->") {
+                let mut symbols = from.trim().chars();
+                let (Some(symbol), None) = (symbols.next(), symbols.next()) else {
+                    return Err(error(format!("rule must rewrite a single symbol, not '{}'", from.trim())));
+                };
+                if recipe.rules.insert(symbol, to.trim().to_string()).is_some() {
+                    return Err(error(format!("second rule for '{}'", symbol)));
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(format!("expected 'symbol -> rule' or 'key = value', got '{}'", line)));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let number = || value.parse::<f32>().map_err(|_| error(format!("{} must be a number, got '{}'", key, value)));
+            let count = || value.parse::<u32>().map_err(|_| error(format!("{} must be a whole number, got '{}'", key, value)));
+            match key {
+                "axiom" => recipe.axiom = value.to_string(),
+                "iterations" => recipe.iterations = count()?,
+                "angle" => recipe.angle = number()?.to_radians(),
+                "length_decay" => recipe.length_decay = number()?,
+                "thickness_decay" => recipe.thickness_decay = number()?,
+                "initial_length" => recipe.initial_length = number()?,
+                "initial_thickness" => recipe.initial_thickness = number()?,
+                "leaf_probability" => recipe.leaf_probability = number()?,
+                "gravity" => recipe.gravity = number()?,
+                "branch_segments" => recipe.branch_segments = count()?,
+                "radial_segments" => recipe.radial_segments = count()?,
+                "max_length" => recipe.max_length = count()? as usize,
+                _ => return Err(error(format!("unknown key '{}'", key))),
+            }
+        }
+
+        if recipe.axiom.is_empty() {
+            return Err("axiom is empty".to_string());
+        }
+        if recipe.radial_segments < 3 {
+            return Err("radial_segments must be at least 3".to_string());
+        }
+        for warning in recipe.lint() {
+            log::warn!("Tree recipe: {}", warning);
+        }
+        Ok(recipe)
+    }
+
+    /// Symbols that neither draw nor get rewritten, and rules whose symbol never appears
+    fn lint(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut symbols: Vec<char> = self.axiom.chars().chain(self.rules.values().flat_map(|to| to.chars())).collect();
+        symbols.sort_unstable();
+        symbols.dedup();
+        for &symbol in &symbols {
+            if !TURTLE_SYMBOLS.contains(symbol) && !self.rules.contains_key(&symbol) {
+                warnings.push(format!("symbol '{}' has no rule and the turtle ignores it", symbol));
+            }
+        }
+        let mut unused: Vec<char> = self.rules.keys().copied().filter(|from| !symbols.contains(from)).collect();
+        unused.sort_unstable();
+        for from in unused {
+            warnings.push(format!("rule for '{}' never applies: the symbol doesn't appear", from));
+        }
+        warnings
+    }
 }
 
+/// Symbols `generate_tree` acts on
+const TURTLE_SYMBOLS: &str = "FGf+-&^\\/[]L";
+
 /// Turtle state for interpreting L-System commands
 #[derive(Debug, Clone)]
 struct TurtleState {
@@ -298,12 +417,8 @@ pub fn generate_tree(recipe: &TreeRecipe, seed: u64) -> GeneratedTree {
     let mut branches = Vec::new();
     let mut leaves = Vec::new();
 
-    // Simple RNG using seed
-    let mut rng_state = seed;
-    let mut random = || {
-        rng_state = rng_state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
-        (rng_state >> 32) as f32 / u32::MAX as f32
-    };
+    let mut rng = SeededRng::new(seed);
+    let mut random = || rng.next_f32();
 
     for ch in lsystem_string.chars() {
         match ch {
@@ -328,8 +443,6 @@ pub fn generate_tree(recipe: &TreeRecipe, seed: u64) -> GeneratedTree {
                 turtle.thickness *= recipe.thickness_decay;
 
                 // Possibly place a leaf
-                // DISABLED for performance/style
-                /*
                 if random() < recipe.leaf_probability && turtle.thickness < 0.05 {
                     leaves.push(LeafInstance {
                         position: end,
@@ -337,7 +450,6 @@ pub fn generate_tree(recipe: &TreeRecipe, seed: u64) -> GeneratedTree {
                         size: 0.2 + random() * 0.3,
                     });
                 }
-                */
             }
             'f' => {
                 // Move forward without drawing
@@ -379,14 +491,11 @@ pub fn generate_tree(recipe: &TreeRecipe, seed: u64) -> GeneratedTree {
             }
             'L' => {
                 // Explicit leaf command
-                // DISABLED
-                /*
                 leaves.push(LeafInstance {
                     position: turtle.position,
                     normal: turtle.direction,
                     size: 0.5 + random() * 0.5,
                 });
-                */
             }
             _ => {
                 // Ignore unknown characters
@@ -480,49 +589,7 @@ pub fn generate_tree_mesh(tree: &GeneratedTree) -> TreeMesh {
         }
     }
 
-    // Generate leaf billboards
-    // DISABLED for performance/style
-    /*
-    for leaf in &tree.leaves {
-        let base_index = vertices.len() as u32;
-
-        // Create billboard facing up
-        let right = Vec3::X;
-        let up = Vec3::Z;
-        let half_size = leaf.size * 0.5;
-
-        let positions = [
-            leaf.position + (-right - up) * half_size,
-            leaf.position + (right - up) * half_size,
-            leaf.position + (right + up) * half_size,
-            leaf.position + (-right + up) * half_size,
-        ];
-
-        let uvs = [
-            [0.0, 0.0],
-            [1.0, 0.0],
-            [1.0, 1.0],
-            [0.0, 1.0],
-        ];
-
-        for i in 0..4 {
-            vertices.push(TreeVertex {
-                position: positions[i].to_array(),
-                normal: leaf.normal.to_array(),
-                uv: uvs[i],
-            });
-        }
-
-        // Two triangles for the leaf quad
-        indices.push(base_index);
-        indices.push(base_index + 1);
-        indices.push(base_index + 2);
-
-        indices.push(base_index);
-        indices.push(base_index + 2);
-        indices.push(base_index + 3);
-    }
-    */
+    // Leaves are drawn as separate camera-facing instances (see `generate_leaf_instances`)
 
     TreeMesh {
         vertices,
@@ -530,6 +597,20 @@ pub fn generate_tree_mesh(tree: &GeneratedTree) -> TreeMesh {
     }
 }
 
+/// Leaf billboards for the instanced GPU path, one per `LeafInstance`
+/// Drops degenerate leaves and normalizes normals (the turtle direction can drift).
+pub fn generate_leaf_instances(tree: &GeneratedTree) -> Vec<LeafInstance> {
+    tree.leaves
+        .iter()
+        .filter(|leaf| leaf.size > 0.0 && leaf.position.is_finite())
+        .map(|leaf| LeafInstance {
+            position: leaf.position,
+            normal: leaf.normal.try_normalize().unwrap_or(Vec3::Y),
+            size: leaf.size,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,6 +623,41 @@ mod tests {
         assert!(lsystem.len() > recipe.axiom.len());
     }
 
+    #[test]
+    fn test_tree_file_matches_builtin_oak() {
+        let recipe = TreeRecipe::parse(include_str!("../../../assets/trees/oak.tree")).unwrap();
+        let oak = TreeRecipe::oak();
+        assert_eq!(recipe.species, TreeSpecies::Custom);
+        assert_eq!(recipe.generate_string(), oak.generate_string());
+        assert!((recipe.angle - oak.angle).abs() < 1e-6);
+        assert_eq!((recipe.length_decay, recipe.thickness_decay), (oak.length_decay, oak.thickness_decay));
+        assert!(recipe.lint().is_empty());
+    }
+
+    #[test]
+    fn test_tree_file_errors_and_warnings() {
+        let error = TreeRecipe::parse("axiom = X\nXY -> F").unwrap_err();
+        assert!(error.starts_with("line 2:"), "{}", error);
+        assert!(TreeRecipe::parse("iterations = lots").is_err());
+        assert!(TreeRecipe::parse("height = 3").is_err());
+        assert!(TreeRecipe::parse("F -> FF\nF -> F").is_err());
+
+        // Q does nothing; the rule for B never fires
+        let recipe = TreeRecipe::parse("axiom = X\nX -> F[+X]Q\nB -> F").unwrap();
+        assert_eq!(recipe.lint().len(), 2);
+        assert!(recipe.generate_string().starts_with("F[+F[+"));
+    }
+
+    #[test]
+    fn test_expansion_stops_at_max_length() {
+        let mut recipe = TreeRecipe::parse("F -> FF\niterations = 30\nmax_length = 1000").unwrap();
+        // 512 symbols after 9 doublings; the 10th would pass the cap
+        assert_eq!(recipe.generate_string().len(), 512);
+
+        recipe.iterations = 5;
+        assert_eq!(recipe.generate_string().len(), 32);
+    }
+
     #[test]
     fn test_tree_generation() {
         let recipe = TreeRecipe::pine();
@@ -559,6 +675,27 @@ mod tests {
         assert_eq!(mesh.indices.len() % 3, 0); // Must be triangles
     }
 
+    #[test]
+    fn test_leaf_instances() {
+        // Palm fronds come from explicit 'L' commands
+        let tree = generate_tree(&TreeRecipe::palm(), 777);
+        let leaves = generate_leaf_instances(&tree);
+        assert!(!leaves.is_empty());
+        assert_eq!(leaves.len(), tree.leaves.len());
+        for leaf in &leaves {
+            assert!((leaf.normal.length() - 1.0).abs() < 1e-4);
+            assert!(leaf.size > 0.0);
+        }
+
+        // Leaves stay out of the branch mesh
+        let mesh = generate_tree_mesh(&tree);
+        let ring = tree.recipe.radial_segments as usize * 2;
+        assert_eq!(mesh.vertices.len(), tree.branches.len() * ring);
+
+        // Same seed, same canopy
+        assert_eq!(generate_tree(&TreeRecipe::palm(), 777).leaves.len(), tree.leaves.len());
+    }
+
     #[test]
     fn test_all_species() {
         let recipes = vec![
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wgpu::{BindGroupLayout, Device, Queue};
+
+/// Layout of a material texture group: filterable 2D texture (binding 0) + sampler (binding 1)
+/// Pipelines build their material group from this, so cached bind groups fit any of them.
+pub fn texture_bind_group_layout(device: &Device, label: &str) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &[
+            // Diffuse Texture
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            // Sampler
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// An uploaded texture and the bind group that samples it
+pub struct CachedTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub bind_group: Arc<wgpu::BindGroup>,
+}
+
+/// Uploads each texture once and hands out shared handles on later requests
+#[derive(Default)]
+pub struct AssetCache {
+    textures: HashMap<PathBuf, Arc<CachedTexture>>,
+    /// Material layout and repeat sampler, created with the first texture
+    shared: Option<(BindGroupLayout, wgpu::Sampler)>,
+}
+
+impl AssetCache {
+    /// Texture from an image file, or None (logged) if it can't be read or decoded
+    pub fn texture(&mut self, device: &Device, queue: &Queue, path: &Path) -> Option<Arc<CachedTexture>> {
+        self.load(device, queue, path, wgpu::TextureFormat::Rgba8UnormSrgb)
+    }
+
+    /// Like `texture`, but sampled as raw values rather than sRGB colors (normal maps, masks)
+    pub fn linear_texture(&mut self, device: &Device, queue: &Queue, path: &Path) -> Option<Arc<CachedTexture>> {
+        self.load(device, queue, path, wgpu::TextureFormat::Rgba8Unorm)
+    }
+
+    fn load(&mut self, device: &Device, queue: &Queue, path: &Path, format: wgpu::TextureFormat) -> Option<Arc<CachedTexture>> {
+        let key = cache_key(path);
+        if let Some(texture) = self.textures.get(&key) {
+            return Some(texture.clone());
+        }
+        let image = match image::open(path) {
+            Ok(image) => image.to_rgba8(),
+            Err(e) => {
+                log::warn!("Failed to load texture {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        log::info!("Uploaded texture {} ({}x{})", path.display(), image.width(), image.height());
+        Some(self.upload(device, queue, key, image.dimensions(), &image, format))
+    }
+
+    /// Texture from RGBA8 pixels already in memory (embedded in a model, generated)
+    /// `key` names it in the cache (e.g. `model.glb#base_color`); a cached key ignores `rgba`.
+    pub fn texture_rgba(&mut self, device: &Device, queue: &Queue, key: &str, width: u32, height: u32, rgba: &[u8]) -> Arc<CachedTexture> {
+        let key = cache_key(Path::new(key));
+        if let Some(texture) = self.textures.get(&key) {
+            return texture.clone();
+        }
+        self.upload(device, queue, key, (width, height), rgba, wgpu::TextureFormat::Rgba8UnormSrgb)
+    }
+
+    /// Like `texture_rgba`, but sampled as raw values rather than sRGB colors
+    pub fn linear_texture_rgba(&mut self, device: &Device, queue: &Queue, key: &str, width: u32, height: u32, rgba: &[u8]) -> Arc<CachedTexture> {
+        let key = cache_key(Path::new(key));
+        if let Some(texture) = self.textures.get(&key) {
+            return texture.clone();
+        }
+        self.upload(device, queue, key, (width, height), rgba, wgpu::TextureFormat::Rgba8Unorm)
+    }
+
+    /// Number of distinct textures uploaded
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    fn upload(&mut self, device: &Device, queue: &Queue, key: PathBuf, (width, height): (u32, u32), rgba: &[u8], format: wgpu::TextureFormat) -> Arc<CachedTexture> {
+        assert_eq!(rgba.len(), (width * height * 4) as usize, "RGBA8 data doesn't match {}x{}", width, height);
+        let label = key.to_string_lossy();
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let (layout, sampler) = self.shared.get_or_insert_with(|| {
+            let layout = texture_bind_group_layout(device, "Cached Texture Bind Group Layout");
+            let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Cached Texture Sampler"),
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            });
+            (layout, sampler)
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&label),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        let cached = Arc::new(CachedTexture { texture, view, bind_group: Arc::new(bind_group) });
+        self.textures.insert(key, cached.clone());
+        cached
+    }
+}
+
+/// Same file, same key: `trees/./Texture/a.jpg` and `trees/Texture/a.jpg` share an upload
+fn cache_key(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_ignores_redundant_components() {
+        assert_eq!(cache_key(Path::new("trees/./Texture/bark.jpg")), cache_key(Path::new("trees/Texture/bark.jpg")));
+        assert_eq!(cache_key(Path::new("trees//Texture/bark.jpg")), PathBuf::from("trees/Texture/bark.jpg"));
+        assert_ne!(cache_key(Path::new("trees/Texture/bark.jpg")), cache_key(Path::new("rocks/Texture/bark.jpg")));
+    }
+}
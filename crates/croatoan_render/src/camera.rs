@@ -1,16 +1,41 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3};
+
+/// Projection mode for `Camera::projection_matrix`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// Vertical field of view from `Camera::fov`, scaled by `aspect_ratio`
+    Perspective,
+    /// Fixed view volume size in world units (map previews, chunk debugging)
+    Orthographic { width: f32, height: f32 },
+}
 
 /// 3D Camera with view and projection matrices
 pub struct Camera {
     pub position: Vec3,
     pub target: Vec3,
     pub up: Vec3,
+    /// Vertical field of view in radians (default 45 degrees)
     pub fov: f32,
     pub aspect_ratio: f32,
+    /// Near clip plane distance (default 0.1)
     pub near: f32,
+    /// Far clip plane distance (default 1000.0)
     pub far: f32,
+    pub projection: Projection,
     pub yaw: f32,
     pub pitch: f32,
+    /// Eye position relative to the followed point (x right, y up, z behind); see `set_offset`
+    offset: Vec3,
+    /// Smoothed state of `follow`, None until it's first called
+    follow: Option<FollowState>,
+}
+
+/// Where `Camera::follow` has eased to, and how fast it's moving
+#[derive(Copy, Clone, Debug)]
+struct FollowState {
+    pivot: Vec3,
+    velocity: Vec3,
+    angular_velocity: Vec2, // Yaw, pitch (rad/s)
 }
 
 impl Camera {
@@ -29,8 +54,11 @@ impl Camera {
             aspect_ratio,
             near: 0.1,
             far: 1000.0,
+            projection: Projection::Perspective,
             yaw,
             pitch,
+            offset: Vec3::ZERO,
+            follow: None,
         }
     }
 
@@ -40,8 +68,75 @@ impl Camera {
     }
 
     /// Get the projection matrix
+    ///
+    /// Uses reverse-Z (near -> depth 1.0, far -> depth 0.0) to spread float depth
+    /// precision evenly over distance. Depth buffers must be cleared to 0.0 and
+    /// tested with `CompareFunction::Greater`.
     pub fn projection_matrix(&self) -> Mat4 {
-        Mat4::perspective_rh(self.fov, self.aspect_ratio, self.near, self.far)
+        // Swapping near/far in a standard projection yields the reverse-Z mapping
+        match self.projection {
+            Projection::Perspective => Mat4::perspective_rh(self.fov, self.aspect_ratio, self.far, self.near),
+            Projection::Orthographic { width, height } => {
+                let (half_w, half_h) = (width * 0.5, height * 0.5);
+                Mat4::orthographic_rh(-half_w, half_w, -half_h, half_h, self.far, self.near)
+            }
+        }
+    }
+
+    /// Switch to a perspective projection
+    pub fn set_perspective(&mut self, fov_y: f32, near: f32, far: f32) {
+        self.projection = Projection::Perspective;
+        self.fov = fov_y;
+        self.near = near;
+        self.far = far;
+    }
+
+    /// Switch to an orthographic projection covering `width` x `height` world units
+    pub fn set_orthographic(&mut self, width: f32, height: f32, near: f32, far: f32) {
+        self.projection = Projection::Orthographic { width, height };
+        self.near = near;
+        self.far = far;
+    }
+
+    /// Set the vertical field of view (radians), clamped to 1..170 degrees
+    pub fn set_fov(&mut self, fov_y: f32) {
+        if fov_y.is_finite() {
+            self.fov = fov_y.clamp(1.0_f32.to_radians(), 170.0_f32.to_radians());
+        }
+    }
+
+    /// Vertical field of view in radians
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    /// Set the near/far clip planes
+    /// Ignored unless 0 < near < far (a zero near plane breaks the perspective divide)
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) {
+        if near > 0.0 && far > near && far.is_finite() {
+            self.near = near;
+            self.far = far;
+        }
+    }
+
+    /// (near, far) clip plane distances
+    pub fn clip_planes(&self) -> (f32, f32) {
+        (self.near, self.far)
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    /// Half width and half height of the view volume at `depth` units in front of the camera
+    pub fn half_extents_at(&self, depth: f32) -> (f32, f32) {
+        match self.projection {
+            Projection::Perspective => {
+                let half_height = depth * (self.fov * 0.5).tan();
+                (half_height * self.aspect_ratio, half_height)
+            }
+            Projection::Orthographic { width, height } => (width * 0.5, height * 0.5),
+        }
     }
 
     /// Get combined view-projection matrix
@@ -49,9 +144,33 @@ impl Camera {
         self.projection_matrix() * self.view_matrix()
     }
 
+    /// World-space ray through a normalized screen coordinate (NDC: -1..1, +Y up)
+    /// Returns (origin on the near plane, unit direction)
+    pub fn screen_ray(&self, ndc_x: f32, ndc_y: f32) -> (Vec3, Vec3) {
+        let inv_view_proj = self.view_projection_matrix().inverse();
+        // Reverse-Z: the near plane is depth 1.0, the far plane depth 0.0
+        let near = inv_view_proj.project_point3(Vec3::new(ndc_x, ndc_y, 1.0));
+        let far = inv_view_proj.project_point3(Vec3::new(ndc_x, ndc_y, 0.0));
+        (near, (far - near).normalize())
+    }
+
+    /// Top-down orthographic view-projection centered on `center`, covering
+    /// `half_extent` world units each way. +X maps to screen right, +Z to screen down.
+    /// Reverse-Z like `projection_matrix`.
+    pub fn top_down_view_proj(center: Vec3, half_extent: f32) -> Mat4 {
+        let height = 1000.0;
+        let eye = Vec3::new(center.x, height, center.z);
+        let view = Mat4::look_at_rh(eye, Vec3::new(center.x, 0.0, center.z), Vec3::NEG_Z);
+        let proj = Mat4::orthographic_rh(-half_extent, half_extent, -half_extent, half_extent, height * 2.0, 1.0);
+        proj * view
+    }
+
     /// Update aspect ratio (for window resize)
-    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
-        self.aspect_ratio = aspect_ratio;
+    /// Degenerate values (minimized window, zero height) are ignored
+    pub fn set_aspect(&mut self, aspect: f32) {
+        if aspect.is_finite() && aspect > 0.0 {
+            self.aspect_ratio = aspect;
+        }
     }
 
     /// Update the view matrix based on yaw and pitch
@@ -83,6 +202,50 @@ impl Camera {
         self.forward().cross(Vec3::Y).normalize()
     }
 
+    /// Place the eye relative to the point `follow` tracks, in the view's frame: x to the
+    /// right, y up, z behind (e.g. (0.5, 0.3, 4.0) over the right shoulder). Zero is first person.
+    pub fn set_offset(&mut self, offset: Vec3) {
+        self.offset = offset;
+    }
+
+    pub fn offset(&self) -> Vec3 {
+        self.offset
+    }
+
+    /// Ease toward following `target_pos` with the given view angles, critically damped (no
+    /// overshoot) so it's smooth at any frame rate. `stiffness` (1/s) sets how quickly it
+    /// catches up, settling in about 5 / `stiffness` seconds; `f32::INFINITY` snaps straight
+    /// to the target. The eye sits at the `set_offset` offset from the followed point.
+    pub fn follow(&mut self, target_pos: Vec3, target_yaw: f32, target_pitch: f32, stiffness: f32, dt: f32) {
+        let state = match self.follow {
+            Some(state) if stiffness.is_finite() => {
+                // Turn the short way around
+                let yaw = self.yaw + wrap_angle(target_yaw - self.yaw);
+                let (angles, angular_velocity) = critically_damped(
+                    Vec2::new(self.yaw, self.pitch),
+                    Vec2::new(yaw, target_pitch),
+                    state.angular_velocity,
+                    stiffness,
+                    dt,
+                );
+                (self.yaw, self.pitch) = (angles.x, angles.y);
+                let (pivot, velocity) = critically_damped(state.pivot, target_pos, state.velocity, stiffness, dt);
+                FollowState { pivot, velocity, angular_velocity }
+            }
+            _ => {
+                (self.yaw, self.pitch) = (target_yaw, target_pitch);
+                FollowState { pivot: target_pos, velocity: Vec3::ZERO, angular_velocity: Vec2::ZERO }
+            }
+        };
+        self.follow = Some(state);
+
+        let forward = self.forward();
+        let right = self.right();
+        let up = right.cross(forward);
+        self.position = state.pivot + right * self.offset.x + up * self.offset.y - forward * self.offset.z;
+        self.update_vectors();
+    }
+
     /// Process mouse movement
     pub fn process_mouse(&mut self, delta_x: f32, delta_y: f32, sensitivity: f32) {
         self.yaw += delta_x * sensitivity;
@@ -119,3 +282,169 @@ impl Camera {
         self.update_vectors();
     }
 }
+
+/// `angle` wrapped into -PI..PI
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI
+}
+
+/// One exact step of a critically damped spring with angular frequency `omega` pulling
+/// `current` (moving at `velocity`) toward `target`: (new value, new velocity)
+fn critically_damped<T>(current: T, target: T, velocity: T, omega: f32, dt: f32) -> (T, T)
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Sub<Output = T> + std::ops::Mul<f32, Output = T>,
+{
+    let offset = current - target;
+    let decay = (-omega * dt).exp();
+    let temp = (velocity + offset * omega) * dt;
+    (target + (offset + temp) * decay, (velocity - temp * omega) * decay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_down_view_proj() {
+        let center = Vec3::new(100.0, 0.0, -50.0);
+        let vp = Camera::top_down_view_proj(center, 200.0);
+
+        let origin = vp.project_point3(center);
+        assert!(origin.x.abs() < 1e-4 && origin.y.abs() < 1e-4);
+
+        // +X to the right edge, +Z to the bottom edge
+        let east = vp.project_point3(center + Vec3::new(200.0, 0.0, 0.0));
+        let south = vp.project_point3(center + Vec3::new(0.0, 0.0, 200.0));
+        assert!((east.x - 1.0).abs() < 1e-4);
+        assert!((south.y + 1.0).abs() < 1e-4);
+
+        // Reverse-Z: higher ground is closer to the camera, so deeper in [0, 1]
+        let hill = vp.project_point3(center + Vec3::Y * 50.0);
+        assert!(hill.z > origin.z && origin.z > 0.0 && hill.z < 1.0);
+    }
+
+    #[test]
+    fn test_fov_and_clip_planes() {
+        let mut camera = Camera::new(Vec3::ZERO, Vec3::NEG_Z, 1.0);
+        assert_eq!(camera.fov(), 45.0_f32.to_radians());
+        assert_eq!(camera.clip_planes(), (0.1, 1000.0));
+
+        // Wider FOV shrinks the same point toward the screen center
+        let point = Vec3::new(1.0, 0.0, -10.0);
+        let narrow = camera.view_projection_matrix().project_point3(point);
+        camera.set_fov(90.0_f32.to_radians());
+        let wide = camera.view_projection_matrix().project_point3(point);
+        assert!(wide.x < narrow.x);
+
+        camera.set_fov(4.0);
+        assert_eq!(camera.fov(), 170.0_f32.to_radians());
+
+        // Near plane maps to depth 1.0 (reverse-Z); invalid planes are ignored
+        camera.set_clip_planes(0.02, 500.0);
+        assert_eq!(camera.clip_planes(), (0.02, 500.0));
+        let near = camera.view_projection_matrix().project_point3(Vec3::new(0.0, 0.0, -0.02));
+        assert!((near.z - 1.0).abs() < 1e-4);
+        camera.set_clip_planes(0.0, 500.0);
+        camera.set_clip_planes(10.0, 5.0);
+        assert_eq!(camera.clip_planes(), (0.02, 500.0));
+
+        camera.set_aspect(0.0);
+        assert_eq!(camera.aspect(), 1.0);
+        camera.set_aspect(2.0);
+        assert_eq!(camera.aspect(), 2.0);
+    }
+
+    #[test]
+    fn test_follow_eases_without_overshoot() {
+        let mut camera = Camera::new(Vec3::ZERO, Vec3::X, 1.0);
+        // First call (and infinite stiffness) snaps
+        camera.follow(Vec3::ZERO, 0.0, 0.0, 8.0, 1.0 / 60.0);
+        assert_eq!(camera.position, Vec3::ZERO);
+
+        let target = Vec3::new(10.0, 0.0, 0.0);
+        let mut previous = 0.0;
+        for frame in 0..120 {
+            camera.follow(target, 0.5, 0.0, 8.0, 1.0 / 60.0);
+            assert!(camera.position.x >= previous && camera.position.x <= target.x + 1e-4, "overshot at frame {}", frame);
+            previous = camera.position.x;
+            if frame == 5 {
+                assert!(camera.position.x > 1.0 && camera.position.x < 9.0, "{}", camera.position.x);
+            }
+        }
+        assert!(camera.position.distance(target) < 0.05);
+        assert!((camera.yaw - 0.5).abs() < 0.01);
+
+        // Same motion in bigger steps ends up in the same place
+        let mut coarse = Camera::new(Vec3::ZERO, Vec3::X, 1.0);
+        coarse.follow(Vec3::ZERO, 0.0, 0.0, 8.0, 0.1);
+        for _ in 0..4 {
+            coarse.follow(target, 0.5, 0.0, 8.0, 0.1);
+        }
+        let mut fine = Camera::new(Vec3::ZERO, Vec3::X, 1.0);
+        fine.follow(Vec3::ZERO, 0.0, 0.0, 8.0, 0.01);
+        for _ in 0..40 {
+            fine.follow(target, 0.5, 0.0, 8.0, 0.01);
+        }
+        assert!(coarse.position.distance(fine.position) < 1e-3);
+
+        // Yaw turns the short way across the -PI/PI seam
+        camera.follow(target, 3.0, 0.0, f32::INFINITY, 0.0);
+        camera.follow(target, -3.0, 0.0, 8.0, 1.0 / 60.0);
+        assert!(camera.yaw > 3.0, "turned the long way: {}", camera.yaw);
+
+        // Offset: eye behind and above the followed point, still looking along the yaw
+        camera.set_offset(Vec3::new(0.0, 1.0, 4.0));
+        camera.follow(target, 0.0, 0.0, f32::INFINITY, 0.0);
+        assert!(camera.position.distance(target + Vec3::new(-4.0, 1.0, 0.0)) < 1e-4);
+        assert!((camera.forward() - Vec3::X).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_screen_ray() {
+        let mut camera = Camera::new(Vec3::new(5.0, 10.0, 5.0), Vec3::new(20.0, 0.0, 40.0), 16.0 / 9.0);
+
+        // Center of the screen looks straight down the view direction
+        let (origin, dir) = camera.screen_ray(0.0, 0.0);
+        assert!((dir - camera.forward()).length() < 1e-3);
+        assert!(origin.distance(camera.position) < camera.near * 1.01);
+
+        // Any ray projects back onto its screen coordinate
+        let (origin, dir) = camera.screen_ray(0.5, -0.25);
+        let ndc = camera.view_projection_matrix().project_point3(origin + dir * 50.0);
+        assert!((ndc.x - 0.5).abs() < 1e-3 && (ndc.y + 0.25).abs() < 1e-3);
+
+        // Orthographic rays are parallel and offset across the screen
+        camera.set_orthographic(100.0, 50.0, 0.1, 1000.0);
+        let (a, dir_a) = camera.screen_ray(-1.0, 0.0);
+        let (b, dir_b) = camera.screen_ray(1.0, 0.0);
+        assert!((dir_a - dir_b).length() < 1e-4);
+        assert!((a.distance(b) - 100.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_center_projects_to_ndc_origin() {
+        let mut camera = Camera::new(Vec3::new(10.0, 20.0, 30.0), Vec3::new(50.0, 5.0, -10.0), 16.0 / 9.0);
+        let center = camera.position + camera.forward() * 100.0;
+
+        let ndc = camera.view_projection_matrix().project_point3(center);
+        assert!(ndc.x.abs() < 1e-4 && ndc.y.abs() < 1e-4);
+
+        camera.set_orthographic(400.0, 225.0, 0.1, 1000.0);
+        assert_eq!(camera.projection, Projection::Orthographic { width: 400.0, height: 225.0 });
+        let ndc = camera.view_projection_matrix().project_point3(center);
+        assert!(ndc.x.abs() < 1e-4 && ndc.y.abs() < 1e-4);
+        // Reverse-Z holds in both modes
+        assert!(ndc.z > 0.0 && ndc.z < 1.0);
+
+        // Orthographic: size on screen doesn't depend on distance
+        let offset = camera.right() * 100.0;
+        let near_edge = camera.view_projection_matrix().project_point3(camera.position + camera.forward() * 10.0 + offset);
+        let far_edge = camera.view_projection_matrix().project_point3(center + offset);
+        assert!((near_edge.x - 0.5).abs() < 1e-4 && (far_edge.x - 0.5).abs() < 1e-4);
+
+        camera.set_perspective(60.0_f32.to_radians(), 0.5, 500.0);
+        assert_eq!(camera.projection, Projection::Perspective);
+        let ndc = camera.view_projection_matrix().project_point3(center);
+        assert!(ndc.x.abs() < 1e-4 && ndc.y.abs() < 1e-4);
+    }
+}
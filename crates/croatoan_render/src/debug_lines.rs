@@ -0,0 +1,221 @@
+use glam::{Mat4, Vec3, Vec4};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+}
+
+/// Colored line segments drawn over the scene for debugging (chunk bounds, frustums)
+///
+/// Per frame: `clear`, add shapes, `upload`, then `render` in a scene pass. Lines ignore the
+/// depth buffer so bounds hidden behind terrain still show.
+pub struct DebugLines {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    vertices: Vec<LineVertex>,
+    vertex_buffer: Option<wgpu::Buffer>,
+    /// Vertices `vertex_buffer` holds
+    capacity: usize,
+    vertex_count: u32,
+}
+
+impl DebugLines {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("../../../assets/shaders/debug_lines.wgsl"));
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Lines Uniform Buffer"),
+            size: std::mem::size_of::<[[f32; 4]; 4]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug Lines Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug Lines Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Lines Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Lines Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always, // Drawn over everything
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            vertices: Vec::new(),
+            vertex_buffer: None,
+            capacity: 0,
+            vertex_count: 0,
+        }
+    }
+
+    /// Drop the lines added since the last frame
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn add_line(&mut self, a: Vec3, b: Vec3, color: [f32; 3]) {
+        self.vertices.push(LineVertex { position: a.to_array(), color });
+        self.vertices.push(LineVertex { position: b.to_array(), color });
+    }
+
+    /// The 12 edges of an axis-aligned box
+    pub fn add_box(&mut self, min: Vec3, max: Vec3, color: [f32; 3]) {
+        let corners: [Vec3; 8] = std::array::from_fn(|i| Vec3::select(corner_side(i), max, min));
+        self.add_edges(&corners, color);
+    }
+
+    /// The 12 edges of the frustum a view-projection sees (e.g. one frozen for inspection)
+    pub fn add_frustum(&mut self, view_proj: &Mat4, color: [f32; 3]) {
+        self.add_edges(&frustum_corners(view_proj), color);
+    }
+
+    fn add_edges(&mut self, corners: &[Vec3; 8], color: [f32; 3]) {
+        for (a, b) in box_edges() {
+            self.add_line(corners[a], corners[b], color);
+        }
+    }
+
+    /// Write this frame's lines and camera, growing the vertex buffer if needed
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, view_proj: &Mat4) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&view_proj.to_cols_array_2d()));
+        if self.vertices.len() > self.capacity {
+            self.capacity = self.vertices.len().next_power_of_two();
+            self.vertex_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Debug Lines Vertex Buffer"),
+                size: (self.capacity * std::mem::size_of::<LineVertex>()) as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if let (Some(vertex_buffer), false) = (&self.vertex_buffer, self.vertices.is_empty()) {
+            queue.write_buffer(vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
+        }
+        self.vertex_count = self.vertices.len() as u32;
+    }
+
+    pub fn render<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if let (Some(vertex_buffer), true) = (&self.vertex_buffer, self.vertex_count > 0) {
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+            rpass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            rpass.draw(0..self.vertex_count, 0..1);
+        }
+    }
+}
+
+/// Which side of a box corner `i` is on per axis: bit 0 = +x, bit 1 = +y, bit 2 = +z
+fn corner_side(i: usize) -> glam::BVec3 {
+    glam::BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0)
+}
+
+/// Corner index pairs of a box's 12 edges (corners numbered as in `corner_side`)
+fn box_edges() -> impl Iterator<Item = (usize, usize)> {
+    (0..8).flat_map(|i| [1, 2, 4].into_iter().filter(move |axis| i & axis == 0).map(move |axis| (i, i | axis)))
+}
+
+/// World-space frustum corners, numbered as in `corner_side` with -z the near plane
+fn frustum_corners(view_proj: &Mat4) -> [Vec3; 8] {
+    let inverse = view_proj.inverse();
+    std::array::from_fn(|i| {
+        // NDC x/y in -1..1, depth 1 (near) .. 0 (far) under reverse-Z
+        let side = corner_side(i);
+        let ndc = Vec4::new(
+            if side.x { 1.0 } else { -1.0 },
+            if side.y { 1.0 } else { -1.0 },
+            if side.z { 0.0 } else { 1.0 },
+            1.0,
+        );
+        let world = inverse * ndc;
+        world.truncate() / world.w
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_has_twelve_unit_edges() {
+        let edges: Vec<_> = box_edges().collect();
+        assert_eq!(edges.len(), 12);
+        let corners: [Vec3; 8] = std::array::from_fn(|i| Vec3::select(corner_side(i), Vec3::ONE, Vec3::ZERO));
+        for (a, b) in edges {
+            assert!((corners[a].distance(corners[b]) - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_frustum_corners_span_near_to_far() {
+        // Reverse-Z perspective, as `Camera::projection_matrix` builds it
+        let (near, far) = (0.5, 100.0);
+        let proj = Mat4::perspective_rh(1.0, 1.5, far, near);
+        let view = Mat4::look_at_rh(Vec3::new(3.0, 2.0, 1.0), Vec3::new(3.0, 2.0, -10.0), Vec3::Y);
+        let corners = frustum_corners(&(proj * view));
+        for (i, corner) in corners.iter().enumerate() {
+            let depth = 1.0 - corner.z;
+            let expected = if corner_side(i).z { far } else { near };
+            assert!((depth - expected).abs() < expected * 1e-3, "corner {} at depth {}", i, depth);
+        }
+    }
+}
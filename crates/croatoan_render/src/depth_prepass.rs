@@ -0,0 +1,273 @@
+use glam::Mat4;
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+use crate::shadows::{InstancedMesh, POSITION_ONLY_SHADER};
+
+/// Terrain below this height is animated as water by terrain.wgsl, so the prepass can't
+/// reproduce its depth (must match the wave cutoff there)
+const TERRAIN_WAVE_HEIGHT: f32 = 0.5;
+
+/// Terrain entry point: like `vs_main`, but vertices that terrain.wgsl moves with waves are
+/// pushed to the far plane. Triangles touching them then write depth no nearer than the
+/// surface, so the shaded pass is never wrongly rejected there.
+const TERRAIN_ENTRY: &str = r#"
+@vertex
+fn vs_terrain(input: VertexInput) -> @builtin(position) vec4<f32> {
+    var clip = uniforms.view_proj * vec4<f32>(input.position, 1.0);
+    if input.position.y < TERRAIN_WAVE_HEIGHT {
+        clip.z = 0.0; // Reverse-Z far plane
+    }
+    return clip;
+}
+"#;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PrepassUniforms {
+    view_proj: [[f32; 4]; 4],
+}
+
+/// Depth-only pass over the opaque occluders (terrain, rocks, buildings) before the main pass,
+/// so the shaded passes after it (grass and trees especially) early-Z reject hidden fragments
+///
+/// Uses the shadow pass's position-only shader against the main depth buffer. Depth is
+/// written pushed slightly back (reverse-Z depth bias), so the main pass keeps its `Greater`
+/// test and still draws each prepassed surface itself. Alpha-tested meshes (grass, leaves)
+/// aren't prepassed: their cut-out texels would hide what's behind them.
+pub struct DepthPrepass {
+    terrain_pipeline: wgpu::RenderPipeline,
+    /// Instanced variants keyed by (vertex stride, instance stride), as in `ShadowPipeline`
+    instanced_pipelines: HashMap<(wgpu::BufferAddress, wgpu::BufferAddress), wgpu::RenderPipeline>,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    sample_count: u32,
+}
+
+impl DepthPrepass {
+    /// `sample_count` must match the main depth buffer (`GraphicsContext::sample_count`)
+    pub fn new(device: &wgpu::Device, sample_count: u32) -> Self {
+        let source = format!(
+            "const TERRAIN_WAVE_HEIGHT: f32 = {:?};\n{}{}",
+            TERRAIN_WAVE_HEIGHT, POSITION_ONLY_SHADER, TERRAIN_ENTRY
+        );
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Depth Prepass Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Depth Prepass Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Depth Prepass Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[PrepassUniforms { view_proj: Mat4::IDENTITY.to_cols_array_2d() }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Depth Prepass Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Depth Prepass Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let terrain_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            "vs_terrain",
+            &[wgpu::VertexBufferLayout {
+                array_stride: crate::terrain_pipeline::VERTEX_STRIDE, // Position first
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &[wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                }],
+            }],
+            None, // Matches the terrain pipeline
+            sample_count,
+        );
+
+        let mut prepass = Self {
+            terrain_pipeline,
+            instanced_pipelines: HashMap::new(),
+            shader,
+            pipeline_layout,
+            uniform_buffer,
+            bind_group,
+            sample_count,
+        };
+
+        // Built-in occluders
+        prepass.prepare_stride(
+            device,
+            std::mem::size_of::<crate::building_pipeline::BuildingVertex>() as wgpu::BufferAddress,
+            std::mem::size_of::<crate::building_pipeline::InstanceRaw>() as wgpu::BufferAddress,
+        );
+        prepass.prepare_stride(
+            device,
+            std::mem::size_of::<crate::rock_pipeline::RockVertex>() as wgpu::BufferAddress,
+            std::mem::size_of::<crate::rock_pipeline::RockInstanceRaw>() as wgpu::BufferAddress,
+        );
+        prepass
+    }
+
+    /// Build the instanced variant for these vertex/instance strides (see
+    /// `ShadowPipeline::prepare_stride`); meshes are back-face culled like buildings and rocks
+    pub fn prepare_stride(&mut self, device: &wgpu::Device, stride: wgpu::BufferAddress, instance_stride: wgpu::BufferAddress) {
+        if self.instanced_pipelines.contains_key(&(stride, instance_stride)) {
+            return;
+        }
+
+        let instance_attributes = wgpu::vertex_attr_array![
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+            4 => Float32x4,
+        ];
+        let pipeline = create_pipeline(
+            device,
+            &self.pipeline_layout,
+            &self.shader,
+            "vs_instanced",
+            &[
+                wgpu::VertexBufferLayout {
+                    array_stride: stride,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[wgpu::VertexAttribute {
+                        offset: 0,
+                        shader_location: 0,
+                        format: wgpu::VertexFormat::Float32x3,
+                    }],
+                },
+                wgpu::VertexBufferLayout {
+                    array_stride: instance_stride,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &instance_attributes,
+                },
+            ],
+            Some(wgpu::Face::Back),
+            self.sample_count,
+        );
+        self.instanced_pipelines.insert((stride, instance_stride), pipeline);
+    }
+
+    pub fn update_uniforms(&self, queue: &wgpu::Queue, view_proj: &Mat4) {
+        let uniforms = PrepassUniforms { view_proj: view_proj.to_cols_array_2d() };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Bind the camera; call at the start of the prepass, before any draws
+    pub fn begin<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+    }
+
+    /// Draw terrain (vertices at the terrain stride)
+    pub fn render_terrain<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, vertex_buffer: &'a wgpu::Buffer, index_buffer: &'a wgpu::Buffer, index_count: u32) {
+        render_pass.set_pipeline(&self.terrain_pipeline);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..index_count, 0, 0..1);
+    }
+
+    /// Draw the first `mesh.instance_count` instances of an instanced mesh
+    /// `strides` is (vertex stride, instance stride); pairs not registered via `prepare_stride` are skipped
+    pub fn render_with_stride<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        mesh: InstancedMesh<'a>,
+        strides: (wgpu::BufferAddress, wgpu::BufferAddress),
+    ) {
+        let Some(pipeline) = self.instanced_pipelines.get(&strides) else {
+            return;
+        };
+        mesh.draw(render_pass, pipeline);
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    entry_point: &str,
+    buffers: &[wgpu::VertexBufferLayout],
+    cull_mode: Option<wgpu::Face>,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Depth Prepass Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point,
+            buffers,
+        },
+        fragment: None, // Depth-only
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: wgpu::TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
+            stencil: wgpu::StencilState::default(),
+            // Negative: slightly further away in reverse-Z, so the same surface passes the
+            // main pass's `Greater` test despite any rounding between the two shaders
+            bias: wgpu::DepthBiasState {
+                constant: -2,
+                slope_scale: -1.0,
+                clamp: 0.0,
+            },
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GraphicsContext;
+
+    #[test]
+    fn test_prepass_pipelines_build() {
+        // Needs an adapter (a GPU or a software renderer); skip where there is none
+        let ctx = match GraphicsContext::new_headless(4, 4, wgpu::TextureFormat::Rgba8Unorm) {
+            Ok(ctx) => ctx,
+            Err(e) => {
+                println!("Skipping depth prepass test: {}", e);
+                return;
+            }
+        };
+        // Invalid WGSL or pipeline state panics in wgpu's error handler
+        let prepass = DepthPrepass::new(ctx.device(), ctx.sample_count());
+        assert_eq!(prepass.instanced_pipelines.len(), 2);
+    }
+}
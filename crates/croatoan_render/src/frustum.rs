@@ -42,14 +42,14 @@ impl Frustum {
                 m[2][3] - m[2][1],
                 m[3][3] - m[3][1],
             )),
-            // Near: row3 + row2
+            // Near: row2 (wgpu clip depth is 0..w; this is the far plane under reverse-Z)
             Self::normalize_plane(Vec4::new(
-                m[0][3] + m[0][2],
-                m[1][3] + m[1][2],
-                m[2][3] + m[2][2],
-                m[3][3] + m[3][2],
+                m[0][2],
+                m[1][2],
+                m[2][2],
+                m[3][2],
             )),
-            // Far: row3 - row2
+            // Far: row3 - row2 (near plane under reverse-Z)
             Self::normalize_plane(Vec4::new(
                 m[0][3] - m[0][2],
                 m[1][3] - m[1][2],
@@ -70,6 +70,17 @@ impl Frustum {
         }
     }
 
+    /// The six planes as (unit normal, d), normals pointing inward: left, right, bottom, top,
+    /// near, far (the last two swap under reverse-Z)
+    pub fn planes(&self) -> &[Vec4; 6] {
+        &self.planes
+    }
+
+    /// Signed distance from `point` to each plane (in `planes` order): positive inside
+    pub fn plane_distances(&self, point: Vec3) -> [f32; 6] {
+        self.planes.map(|plane| plane.truncate().dot(point) + plane.w)
+    }
+
     /// Test if a sphere intersects or is inside the frustum
     pub fn contains_sphere(&self, center: Vec3, radius: f32) -> bool {
         for plane in &self.planes {
@@ -138,5 +149,46 @@ mod tests {
 
         // Point behind should not be visible
         assert!(!frustum.contains_sphere(Vec3::new(0.0, 0.0, 10.0), 1.0));
+
+        // Point beyond the far plane should not be visible
+        assert!(!frustum.contains_sphere(Vec3::new(0.0, 0.0, -110.0), 1.0));
+    }
+
+    #[test]
+    fn test_frustum_reverse_z() {
+        // Reverse-Z projection (near/far swapped) must cull the same volume
+        let vp = Mat4::perspective_rh(1.0, 1.0, 100.0, 0.1);
+        let frustum = Frustum::from_view_proj(&vp);
+
+        assert!(frustum.contains_sphere(Vec3::new(0.0, 0.0, -10.0), 1.0));
+        assert!(!frustum.contains_sphere(Vec3::new(0.0, 0.0, 10.0), 1.0));
+        assert!(!frustum.contains_sphere(Vec3::new(0.0, 0.0, -110.0), 1.0));
+    }
+
+    #[test]
+    fn test_frustum_aabb() {
+        // 90 degree field of view: the side planes are x = +/-z, y = +/-z
+        let vp = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 100.0, 0.1);
+        let frustum = Frustum::from_view_proj(&vp);
+
+        // Inside, straddling the left plane, and wholly outside it
+        assert!(frustum.contains_aabb(Vec3::new(-1.0, -1.0, -11.0), Vec3::new(1.0, 1.0, -9.0)));
+        assert!(frustum.contains_aabb(Vec3::new(-12.0, -1.0, -11.0), Vec3::new(-8.0, 1.0, -9.0)));
+        assert!(!frustum.contains_aabb(Vec3::new(-14.0, -1.0, -11.0), Vec3::new(-12.0, 1.0, -9.0)));
+        // Behind the camera, and past the far plane
+        assert!(!frustum.contains_aabb(Vec3::new(-1.0, -1.0, 1.0), Vec3::new(1.0, 1.0, 3.0)));
+        assert!(!frustum.contains_aabb(Vec3::new(-1.0, -1.0, -120.0), Vec3::new(1.0, 1.0, -110.0)));
+
+        // A tall thin box just outside: its bounding sphere reaches in, the box doesn't
+        let (min, max) = (Vec3::new(-14.0, -20.0, -10.5), Vec3::new(-13.0, 20.0, -9.5));
+        assert!(frustum.contains_sphere((min + max) * 0.5, (max - min).length() * 0.5));
+        assert!(!frustum.contains_aabb(min, max));
+
+        // Distances, positive inside: 5 in from the left plane at x = -5 (its normal is (1, 0, -1)/sqrt 2)
+        let distances = frustum.plane_distances(Vec3::new(0.0, 0.0, -10.0));
+        assert!(distances.iter().all(|&d| d > 0.0));
+        let left = frustum.plane_distances(Vec3::new(-5.0, 0.0, -10.0))[0];
+        assert!((left - 5.0 / 2f32.sqrt()).abs() < 1e-3, "{}", left);
+        assert!((frustum.planes()[0].truncate().length() - 1.0).abs() < 1e-5);
     }
 }
@@ -0,0 +1,310 @@
+use wgpu::{Device, Queue, Buffer, BindGroup, ComputePipeline};
+use wgpu::util::DeviceExt;
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+
+/// One grass blade: the shared blade mesh scaled, bent and turned to stand at `position`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GrassInstance {
+    /// Root of the blade (world space)
+    pub position: [f32; 3],
+    /// Blade height (world units)
+    pub height: f32,
+    /// How far the tip curls over along the blade's facing, as a fraction of its height
+    pub bend: f32,
+    /// Facing around the vertical (radians)
+    pub rotation: f32,
+    /// Tip color; the base is drawn darker
+    pub color: [f32; 3],
+}
+
+/// Per-blade instance as the shaders see it: written by the placement compute shader, and
+/// bound as the instance vertex buffer for both grass paths
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub(crate) struct GrassInstanceRaw {
+    position: [f32; 3], // 12 bytes (0-12)
+    height: f32,        // 4 bytes (12-16)
+    color: [f32; 3],    // 12 bytes (16-28)
+    rotation: f32,      // 4 bytes (28-32)
+    bend: f32,          // 4 bytes (32-36)
+    _padding: [f32; 3], // 12 bytes (36-48) -> Total 48 bytes (storage structs round up to 16)
+}
+
+impl From<GrassInstance> for GrassInstanceRaw {
+    fn from(instance: GrassInstance) -> Self {
+        Self {
+            position: instance.position,
+            height: instance.height,
+            color: instance.color,
+            rotation: instance.rotation,
+            bend: instance.bend,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct PlacementParams {
+    view_proj: [[f32; 4]; 4], // 64 bytes (0-64)
+    camera_pos: [f32; 3],     // 12 bytes (64-76)
+    max_distance: f32,        // 4 bytes (76-80)
+    chunk_origin: [f32; 2],   // 8 bytes (80-88)
+    cell_size: f32,           // 4 bytes (88-92)
+    spacing: f32,             // 4 bytes (92-96)
+    resolution: u32,          // 4 bytes (96-100)
+    grid_dim: u32,            // 4 bytes (100-104)
+    seed: u32,                // 4 bytes (104-108)
+    max_instances: u32,       // 4 bytes (108-112) -> Total 112 bytes
+}
+
+/// Tuning for GPU grass placement
+#[derive(Copy, Clone, Debug)]
+pub struct GrassPlacement {
+    /// Distance between blade candidates (world units)
+    pub spacing: f32,
+    /// Blades further than this from the camera are culled
+    pub max_distance: f32,
+    pub seed: u32,
+}
+
+impl Default for GrassPlacement {
+    fn default() -> Self {
+        Self {
+            spacing: 0.7,
+            max_distance: 350.0,
+            seed: 0,
+        }
+    }
+}
+
+/// One chunk's terrain, as the placement pass reads it
+#[derive(Copy, Clone, Debug)]
+pub struct GrassHeightfield<'a> {
+    /// Heights of the (resolution + 1)^2 terrain vertex grid
+    pub heights: &'a [f32],
+    /// Per-vertex multiplier on the same grid (0.0 clears grass, e.g. along trails)
+    pub density: &'a [f32],
+    pub resolution: u32,
+    /// World XZ of the chunk's corner
+    pub chunk_origin: [f32; 2],
+    pub chunk_size: f32,
+}
+
+/// Compute-driven grass scattering for one chunk
+///
+/// Reads the chunk heightfield and density map, applies the biome density rules, culls blades
+/// by distance and frustum, and appends the survivors to `instance_buffer`.
+/// The instance count lands in `indirect_buffer` for `draw_indexed_indirect` of the blade mesh.
+pub struct GrassCompute {
+    pipeline: ComputePipeline,
+    bind_group: BindGroup,
+    params_buffer: Buffer,
+    params: PlacementParams,
+    pub instance_buffer: Buffer,
+    pub indirect_buffer: Buffer,
+    /// Size of every buffer above, plus the heightfield and density held by `bind_group`
+    buffer_bytes: u64,
+}
+
+impl GrassCompute {
+    /// `heights` is the (resolution + 1)^2 terrain vertex grid of the chunk, row-major in Z.
+    /// `density` is a matching grid of 0..1 multipliers on the biome density.
+    pub fn new(
+        device: &Device,
+        heightfield: &GrassHeightfield,
+        placement: GrassPlacement,
+    ) -> Self {
+        let GrassHeightfield { heights, density, resolution, chunk_origin, chunk_size } = *heightfield;
+        let grid_dim = (chunk_size / placement.spacing).ceil() as u32;
+        let max_instances = grid_dim * grid_dim;
+
+        let params = PlacementParams {
+            view_proj: Mat4::IDENTITY.to_cols_array_2d(),
+            camera_pos: [0.0; 3],
+            max_distance: placement.max_distance,
+            chunk_origin,
+            cell_size: chunk_size / resolution as f32,
+            spacing: placement.spacing,
+            resolution,
+            grid_dim,
+            seed: placement.seed,
+            max_instances,
+        };
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grass Placement Params Buffer"),
+            contents: bytemuck::cast_slice(&[params]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let height_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grass Heightfield Buffer"),
+            contents: bytemuck::cast_slice(heights),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let density_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grass Density Buffer"),
+            contents: bytemuck::cast_slice(density),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Grass Instance Buffer"),
+            size: max_instances as u64 * std::mem::size_of::<GrassInstanceRaw>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        // Draws nothing until the first dispatch
+        let indirect_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grass Indirect Buffer"),
+            contents: Self::reset_args(0).as_bytes(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("../../../assets/shaders/grass_compute.wgsl"));
+
+        let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Grass Compute Bind Group Layout"),
+            entries: &[
+                // Params
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Heightfield
+                storage_entry(1, true),
+                // Instances (output)
+                storage_entry(2, false),
+                // Indirect args (output)
+                storage_entry(3, false),
+                // Density map
+                storage_entry(4, true),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Grass Compute Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Grass Compute Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "place_grass",
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Grass Compute Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: height_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: instance_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: indirect_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: density_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        log::info!("GPU grass placement: {}x{} candidates ({} max instances)", grid_dim, grid_dim, max_instances);
+
+        let buffer_bytes = [&params_buffer, &height_buffer, &density_buffer, &instance_buffer, &indirect_buffer]
+            .iter()
+            .map(|buffer| buffer.size())
+            .sum();
+        Self {
+            pipeline,
+            bind_group,
+            params_buffer,
+            params,
+            instance_buffer,
+            indirect_buffer,
+            buffer_bytes,
+        }
+    }
+
+    /// GPU memory held by this chunk's placement buffers (bytes)
+    pub fn buffer_bytes(&self) -> u64 {
+        self.buffer_bytes
+    }
+
+    fn reset_args(index_count: u32) -> wgpu::util::DrawIndexedIndirectArgs {
+        wgpu::util::DrawIndexedIndirectArgs {
+            index_count,
+            instance_count: 0,
+            first_index: 0,
+            base_vertex: 0,
+            first_instance: 0,
+        }
+    }
+
+    /// Re-run placement for the current camera, for drawing `blade_index_count` indices of the
+    /// blade mesh per instance. Must be recorded before the pass that draws the grass.
+    pub fn dispatch(&self, queue: &Queue, encoder: &mut wgpu::CommandEncoder, view_proj: &Mat4, camera_pos: Vec3, blade_index_count: u32) {
+        let params = PlacementParams {
+            view_proj: view_proj.to_cols_array_2d(),
+            camera_pos: camera_pos.to_array(),
+            ..self.params
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+        queue.write_buffer(&self.indirect_buffer, 0, Self::reset_args(blade_index_count).as_bytes());
+
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Grass Placement Pass"),
+            timestamp_writes: None,
+        });
+        cpass.set_pipeline(&self.pipeline);
+        cpass.set_bind_group(0, &self.bind_group, &[]);
+        // 8x8 threads per workgroup
+        let groups = self.params.grid_dim.div_ceil(8);
+        cpass.dispatch_workgroups(groups, groups, 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts_match_shader() {
+        assert_eq!(std::mem::size_of::<GrassInstanceRaw>(), 48);
+        assert_eq!(std::mem::size_of::<PlacementParams>(), 112);
+    }
+}
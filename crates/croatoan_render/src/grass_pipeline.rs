@@ -1,37 +1,104 @@
-use wgpu::{Device, Queue, RenderPipeline, Buffer, BindGroupLayout, BindGroup};
+use wgpu::{Device, Queue, RenderPipeline, Buffer, BindGroup};
 use wgpu::util::DeviceExt;
 use bytemuck::{Pod, Zeroable};
-use glam::Mat4;
+use glam::{Mat4, Vec3};
+use crate::grass_compute::{GrassCompute, GrassHeightfield, GrassInstance, GrassInstanceRaw, GrassPlacement};
+use crate::shadows::{ShadowCascades, CASCADE_COUNT};
+use crate::wind::{WindParams, WindUniform};
+use crate::TerrainLighting;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
-struct GrassVertex {
-    position: [f32; 3],
-    color: [f32; 3],
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],                        // 64 bytes (0-64)
+    light_view_proj: [[[f32; 4]; 4]; CASCADE_COUNT], // 192 bytes (64-256)
+    cascade_splits: [f32; 4],                        // 16 bytes (256-272)
+    time: f32,                                       // 4 bytes (272-276)
+    _padding1: [f32; 3],                             // 12 bytes (276-288)
+    sun_dir: [f32; 3],                               // 12 bytes (288-300)
+    _padding2: f32,                                  // 4 bytes (300-304)
+    ambient_color: [f32; 3],                         // 12 bytes (304-316)
+    ambient_intensity: f32,                          // 4 bytes (316-320)
+    interaction_center: [f32; 3],                    // 12 bytes (320-332)
+    interaction_radius: f32,                         // 4 bytes (332-336)
+    interaction_wake: [f32; 3],                      // 12 bytes (336-348)
+    interaction_strength: f32,                       // 4 bytes (348-352)
+    wind: WindUniform,                               // 16 bytes (352-368)
+    camera_position: [f32; 3],                       // 12 bytes (368-380)
+    fade_start: f32,                                 // 4 bytes (380-384)
+    fade_end: f32,                                   // 4 bytes (384-388)
+    _padding3: [f32; 3],                             // 12 bytes (388-400)
+    sun_color: [f32; 3],                             // 12 bytes (400-412)
+    _padding4: f32,                                  // 4 bytes (412-416) -> Total 416 bytes
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
-struct CameraUniform {
-    view_proj: [[f32; 4]; 4],      // 64 bytes (0-64)
-    light_view_proj: [[f32; 4]; 4], // 64 bytes (64-128)
-    time: f32,                      // 4 bytes (128-132)
-    _padding1: [f32; 3],            // 12 bytes (132-144)
-    sun_dir: [f32; 3],              // 12 bytes (144-156)
-    _padding2: [f32; 5],            // 20 bytes (156-176) -> Total 176 bytes (aligned to 16)
+/// Grass pushed aside by the player
+///
+/// Blades within `radius` of `center` bend away from it. `wake` trails behind
+/// the player and eases back toward it, so flattened grass springs back over
+/// time instead of popping upright the moment the player leaves.
+#[derive(Copy, Clone, Debug)]
+pub struct GrassInteraction {
+    /// Feet position of whatever is pushing the grass
+    pub center: Vec3,
+    pub wake: Vec3,
+    /// Reach of the push (world units)
+    pub radius: f32,
+    /// Tip displacement at the center, as a fraction of blade height
+    pub strength: f32,
+    /// How fast trampled grass recovers (1/s)
+    pub recovery: f32,
+}
+
+impl Default for GrassInteraction {
+    fn default() -> Self {
+        Self {
+            center: Vec3::splat(f32::MAX),
+            wake: Vec3::splat(f32::MAX),
+            radius: 1.5,
+            strength: 0.8,
+            recovery: 2.5,
+        }
+    }
 }
 
+impl GrassInteraction {
+    /// Move the push center and let the wake catch up (frame-rate independent)
+    pub fn update(&mut self, center: Vec3, dt: f32) {
+        if self.wake.x == f32::MAX || self.wake.distance(center) > self.radius * 20.0 {
+            // First frame or teleport: nothing to recover from
+            self.wake = center;
+        }
+        self.center = center;
+        let blend = 1.0 - (-self.recovery * dt).exp();
+        self.wake = self.wake.lerp(center, blend);
+    }
+}
+
+/// Grass for one chunk: a single blade mesh drawn once per `GrassInstance`
+///
+/// Instances come either from the generator (`upload_instances`) or from a compute pass
+/// that scatters and culls them on the GPU every frame (`upload_heightfield`).
 pub struct GrassPipeline {
     pipeline: RenderPipeline,
-    pub vertex_buffer: Option<Buffer>,
-    pub index_buffer: Option<Buffer>,
-    pub index_count: u32,
+    blade_vertex_buffer: Buffer,
+    blade_index_buffer: Buffer,
+    blade_index_count: u32,
+    /// Generated instances (CPU placement)
+    instance_buffer: Option<Buffer>,
+    instance_count: u32,
     camera_buffer: Buffer,
     camera_bind_group: BindGroup,
+    // Optional GPU placement path (replaces the uploaded instances when set)
+    gpu_placement: Option<GrassCompute>,
+    wind: WindParams,
+    interaction: GrassInteraction,
+    fade_start: f32,
+    fade_end: f32,
 }
 
 impl GrassPipeline {
-    pub fn new(device: &Device, surface_format: wgpu::TextureFormat, shadow_map: &crate::shadows::ShadowMap) -> Self {
+    pub fn new(device: &Device, surface_format: wgpu::TextureFormat, sample_count: u32, shadow_map: &crate::shadows::ShadowMap) -> Self {
         // Camera bind group layout with shadow map
         let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Grass Camera Bind Group Layout"),
@@ -53,7 +120,7 @@ impl GrassPipeline {
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Texture {
                         multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
+                        view_dimension: wgpu::TextureViewDimension::D2Array, // One layer per cascade
                         sample_type: wgpu::TextureSampleType::Depth,
                     },
                     count: None,
@@ -65,6 +132,17 @@ impl GrassPipeline {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
                     count: None,
                 },
+                // Shadow filter params (PCF kernel)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -79,63 +157,37 @@ impl GrassPipeline {
             source: wgpu::ShaderSource::Wgsl(include_str!("../../../assets/shaders/grass.wgsl").into()),
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Grass Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<GrassVertex>() as wgpu::BufferAddress,
+        let pipeline = Self::create_render_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            surface_format,
+            sample_count,
+            &[
+                // Unit blade mesh
+                wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
                     step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[
-                        // Position
-                        wgpu::VertexAttribute {
-                            offset: 0,
-                            shader_location: 0,
-                            format: wgpu::VertexFormat::Float32x3,
-                        },
-                        // Color
-                        wgpu::VertexAttribute {
-                            offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                            shader_location: 1,
-                            format: wgpu::VertexFormat::Float32x3,
-                        },
+                    attributes: &[wgpu::VertexAttribute {
+                        offset: 0,
+                        shader_location: 0,
+                        format: wgpu::VertexFormat::Float32x3,
+                    }],
+                },
+                // GrassInstanceRaw
+                wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<GrassInstanceRaw>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![
+                        2 => Float32x3, // position
+                        3 => Float32,   // height
+                        4 => Float32x3, // color
+                        5 => Float32,   // rotation
+                        6 => Float32,   // bend
                     ],
-                }],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None, // No culling for grass (visible from both sides)
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+                },
+            ],
+        );
 
         // Create camera uniform buffer
         let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -162,66 +214,191 @@ impl GrassPipeline {
                     binding: 2,
                     resource: wgpu::BindingResource::Sampler(&shadow_map.sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: shadow_map.params_buffer.as_entire_binding(),
+                },
             ],
         });
 
+        let (blade_positions, blade_indices) = blade_mesh();
+        let (blade_vertex_buffer, blade_index_buffer) = Self::create_blade_buffers(device, &blade_positions, &blade_indices);
+
         Self {
             pipeline,
-            vertex_buffer: None,
-            index_buffer: None,
-            index_count: 0,
+            blade_vertex_buffer,
+            blade_index_buffer,
+            blade_index_count: blade_indices.len() as u32,
+            instance_buffer: None,
+            instance_count: 0,
             camera_buffer,
             camera_bind_group,
+            gpu_placement: None,
+            wind: WindParams::default(),
+            interaction: GrassInteraction::default(),
+            fade_start: 300.0,
+            fade_end: 350.0, // GrassPlacement::max_distance
         }
     }
 
-    /// Upload grass mesh data to GPU
-    pub fn upload_mesh(
-        &mut self,
+    fn create_render_pipeline(
         device: &Device,
-        queue: &Queue,
-        positions: &[[f32; 3]],
-        colors: &[[f32; 3]],
-        indices: &[u32],
-    ) {
-        // Interleave positions and colors into vertex data
-        let vertices: Vec<GrassVertex> = positions
-            .iter()
-            .zip(colors.iter())
-            .map(|(pos, col)| GrassVertex {
-                position: *pos,
-                color: *col,
-            })
-            .collect();
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        surface_format: wgpu::TextureFormat,
+        sample_count: u32,
+        buffers: &[wgpu::VertexBufferLayout],
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Grass Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None, // No culling for grass (visible from both sides)
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        })
+    }
 
-        // Create vertex buffer
-        self.vertex_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Grass Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
+    fn create_blade_buffers(device: &Device, positions: &[[f32; 3]], indices: &[u32]) -> (Buffer, Buffer) {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grass Blade Vertex Buffer"),
+            contents: bytemuck::cast_slice(positions),
             usage: wgpu::BufferUsages::VERTEX,
-        }));
-
-        // Create index buffer
-        self.index_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Grass Index Buffer"),
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grass Blade Index Buffer"),
             contents: bytemuck::cast_slice(indices),
             usage: wgpu::BufferUsages::INDEX,
-        }));
+        });
+        (vertex_buffer, index_buffer)
+    }
+
+    /// Replace the blade every instance draws (default: `blade_mesh`). Like it, the blade
+    /// should stand from y = 0 to 1 and curl toward +Z by 1 at the tip: instances scale it
+    /// by their height and the curl by their bend.
+    pub fn set_blade_mesh(&mut self, device: &Device, positions: &[[f32; 3]], indices: &[u32]) {
+        let (vertex_buffer, index_buffer) = Self::create_blade_buffers(device, positions, indices);
+        self.blade_vertex_buffer = vertex_buffer;
+        self.blade_index_buffer = index_buffer;
+        self.blade_index_count = indices.len() as u32;
+    }
+
+    /// Draw these generated blades (CPU placement)
+    pub fn upload_instances(&mut self, device: &Device, instances: &[GrassInstance]) {
+        let raw: Vec<GrassInstanceRaw> = instances.iter().map(|&instance| instance.into()).collect();
+        self.instance_buffer = (!raw.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Grass Instance Buffer"),
+                contents: bytemuck::cast_slice(&raw),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+        self.instance_count = raw.len() as u32;
+
+        log::info!("Uploaded {} grass blades", raw.len());
+    }
 
-        self.index_count = indices.len() as u32;
+    /// Switch this chunk to GPU placement: blades are scattered and culled by a compute
+    /// pass over the terrain heightfield each frame and drawn with `draw_indexed_indirect`.
+    pub fn upload_heightfield(&mut self, device: &Device, heightfield: &GrassHeightfield, placement: GrassPlacement) {
+        self.gpu_placement = Some(GrassCompute::new(device, heightfield, placement));
+    }
+
+    /// Whether this chunk uses the GPU placement path
+    pub fn uses_gpu_placement(&self) -> bool {
+        self.gpu_placement.is_some()
+    }
+
+    /// GPU memory held by this chunk's grass buffers (bytes)
+    pub fn buffer_bytes(&self) -> u64 {
+        let own = [Some(&self.blade_vertex_buffer), Some(&self.blade_index_buffer), self.instance_buffer.as_ref(), Some(&self.camera_buffer)];
+        own.iter().flatten().map(|buffer| buffer.size()).sum::<u64>() + self.gpu_placement.as_ref().map_or(0, GrassCompute::buffer_bytes)
+    }
+
+    /// Record the placement/culling compute pass (no-op for CPU-placed grass)
+    pub fn dispatch_placement(&self, queue: &Queue, encoder: &mut wgpu::CommandEncoder, view_proj: &Mat4, camera_pos: Vec3) {
+        if let Some(gpu) = &self.gpu_placement {
+            gpu.dispatch(queue, encoder, view_proj, camera_pos, self.blade_index_count);
+        }
+    }
+
+    /// Wind used by the sway animation (applied on the next `update_camera`)
+    pub fn set_wind(&mut self, params: WindParams) {
+        self.wind = params;
+    }
+
+    /// Push blades aside around the player (applied on the next `update_camera`)
+    /// Tips within `interaction.radius` of its center and of its recovering wake bend away
+    /// radially, fading out smoothly toward the edge of the radius.
+    pub fn set_interaction(&mut self, interaction: GrassInteraction) {
+        self.interaction = interaction;
+    }
 
-        log::info!("Uploaded grass mesh: {} vertices, {} triangles", vertices.len(), indices.len() / 3);
+    /// Distances (from the camera) over which blades shrink and fade out, so the grass
+    /// doesn't pop at the draw distance (applied on the next `update_camera`)
+    /// `end` should match the distance past which the chunk's grass isn't drawn.
+    pub fn set_fade_range(&mut self, start: f32, end: f32) {
+        self.fade_start = start.max(0.0);
+        self.fade_end = end.max(self.fade_start);
     }
 
-    /// Update camera uniform with time for wind animation and shadow data
-    pub fn update_camera(&self, queue: &Queue, view_proj: &Mat4, light_view_proj: &Mat4, sun_dir: [f32; 3], time: f32) {
+    /// Update camera uniform with time for wind animation, shadow data, lighting, player interaction and the distance fade
+    /// (the grass isn't fogged, so the lighting's fog is unused)
+    pub fn update_camera(&self, queue: &Queue, view_proj: &Mat4, camera_position: Vec3, cascades: &ShadowCascades, time: f32, lighting: &TerrainLighting) {
+        let interaction = &self.interaction;
         let uniform = CameraUniform {
             view_proj: view_proj.to_cols_array_2d(),
-            light_view_proj: light_view_proj.to_cols_array_2d(),
+            light_view_proj: cascades.matrices(),
+            cascade_splits: cascades.splits_vec4(),
             time,
             _padding1: [0.0; 3],
-            sun_dir,
-            _padding2: [0.0; 5],
+            sun_dir: lighting.sun_dir,
+            _padding2: 0.0,
+            ambient_color: lighting.ambient_color,
+            ambient_intensity: lighting.ambient_intensity,
+            interaction_center: interaction.center.to_array(),
+            interaction_radius: interaction.radius,
+            interaction_wake: interaction.wake.to_array(),
+            interaction_strength: interaction.strength,
+            wind: self.wind.into(),
+            camera_position: camera_position.to_array(),
+            fade_start: self.fade_start,
+            fade_end: self.fade_end,
+            _padding3: [0.0; 3],
+            sun_color: lighting.sun_color,
+            _padding4: 0.0,
         };
         queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[uniform]));
     }
@@ -231,17 +408,84 @@ impl GrassPipeline {
         &'rpass self,
         render_pass: &mut wgpu::RenderPass<'rpass>,
     ) {
-        if self.vertex_buffer.is_none() || self.index_count == 0 {
-            return;
-        }
+        let instances = match (&self.gpu_placement, &self.instance_buffer) {
+            (Some(gpu), _) => &gpu.instance_buffer,
+            (None, Some(buffer)) => buffer,
+            (None, None) => return,
+        };
 
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.as_ref().unwrap().slice(..));
-        render_pass.set_index_buffer(
-            self.index_buffer.as_ref().unwrap().slice(..),
-            wgpu::IndexFormat::Uint32,
-        );
-        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+        render_pass.set_vertex_buffer(0, self.blade_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instances.slice(..));
+        render_pass.set_index_buffer(self.blade_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        match &self.gpu_placement {
+            Some(gpu) => render_pass.draw_indexed_indirect(&gpu.indirect_buffer, 0),
+            None => render_pass.draw_indexed(0..self.blade_index_count, 0, 0..self.instance_count),
+        }
+    }
+}
+
+/// Unit-height tapered blade curling toward +Z (by 1 at the tip). Scaled, bent and rotated
+/// per instance in the vertex shader.
+pub fn blade_mesh() -> (Vec<[f32; 3]>, Vec<u32>) {
+    let segments = 5;
+    let width_base = 0.08;
+    let width_tip = 0.01;
+
+    let mut positions = Vec::with_capacity((segments + 1) * 2);
+    for i in 0..=segments {
+        let t = i as f32 / segments as f32;
+        let half_width = (width_base + (width_tip - width_base) * t) * 0.5;
+        positions.push([-half_width, t, t * t]);
+        positions.push([half_width, t, t * t]);
+    }
+
+    let mut indices = Vec::with_capacity(segments * 6);
+    for i in 0..segments as u32 {
+        let base = i * 2;
+        indices.extend_from_slice(&[base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+    }
+
+    (positions, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interaction_wake_springs_back() {
+        let mut interaction = GrassInteraction::default();
+        interaction.update(Vec3::ZERO, 0.016);
+        assert_eq!(interaction.wake, Vec3::ZERO);
+
+        // Walk away: the wake lags behind, then catches up once the player stops
+        interaction.update(Vec3::new(1.0, 0.0, 0.0), 0.016);
+        assert!(interaction.wake.x > 0.0 && interaction.wake.x < 1.0);
+
+        for _ in 0..120 {
+            interaction.update(Vec3::new(1.0, 0.0, 0.0), 1.0 / 60.0);
+        }
+        assert!(interaction.wake.distance(interaction.center) < 0.01);
+    }
+
+    #[test]
+    fn test_blade_mesh() {
+        let (positions, indices) = blade_mesh();
+
+        assert_eq!(positions.len(), 12);
+        assert_eq!(indices.len(), 30);
+        assert!(indices.iter().all(|&i| (i as usize) < positions.len()));
+
+        // Unit height and curl: base at the origin, tip at 1
+        assert_eq!(positions[0][1], 0.0);
+        assert_eq!(positions[positions.len() - 1][1], 1.0);
+        assert_eq!(positions[positions.len() - 1][2], 1.0);
+    }
+
+    #[test]
+    fn test_camera_uniform_layout() {
+        assert_eq!(std::mem::size_of::<CameraUniform>(), 416);
     }
 }
@@ -1,79 +1,177 @@
 use wgpu::{Device, Queue, Surface, SurfaceConfiguration, Instance};
 use winit::window::Window;
+use std::path::Path;
 use std::sync::Arc;
 
 pub mod camera;
 pub mod terrain_pipeline;
+pub mod terrain_textures;
 pub mod grass_pipeline;
+pub mod grass_compute;
 pub mod tree_pipeline;
 pub mod detritus_pipeline;
 pub mod sky_pipeline;
 pub mod sun_pipeline;
+pub mod moon_pipeline;
 pub mod shadows;
 pub mod frustum;
 pub mod building_pipeline;
+pub mod rock_pipeline;
+pub mod render_target;
+pub mod wind;
+pub mod asset_cache;
+pub mod rain_pipeline;
+pub mod specular;
+pub mod post_process;
+pub mod ssao;
+pub mod occlusion;
+pub mod debug_lines;
+pub mod depth_prepass;
+pub mod transparent;
 
-pub use terrain_pipeline::TerrainPipeline;
-pub use grass_pipeline::GrassPipeline;
-pub use tree_pipeline::{TreePipeline, TreeMesh};
+pub use terrain_pipeline::{TerrainPipeline, TerrainLighting};
+pub use terrain_textures::TerrainMaterial;
+pub use grass_pipeline::{GrassPipeline, GrassInteraction};
+pub use grass_compute::{GrassHeightfield, GrassInstance, GrassPlacement};
+pub use tree_pipeline::{TreePipeline, TreeMesh, TreeInstance, LeafInstance};
 pub use detritus_pipeline::DetritusPipeline;
-pub use sky_pipeline::SkyPipeline;
+pub use sky_pipeline::{SkyPipeline, SkyParams};
 pub use sun_pipeline::SunPipeline;
-pub use shadows::{ShadowPipeline, ShadowMap};
-pub use camera::Camera;
+pub use moon_pipeline::{MoonPipeline, MoonState};
+pub use shadows::{ShadowPipeline, ShadowMap, ShadowCascades, FilterQuality, InstancedMesh, CASCADE_COUNT};
+pub use camera::{Camera, Projection};
 pub use frustum::{Frustum, ChunkBounds};
 pub use building_pipeline::{BuildingPipeline, BuildingMesh, BuildingVertex};
+pub use rock_pipeline::{RockPipeline, RockMesh, RockVertex};
+pub use render_target::RenderTarget;
+pub use wind::WindParams;
+pub use asset_cache::{AssetCache, CachedTexture};
+pub use rain_pipeline::RainPipeline;
+pub use specular::Specular;
+pub use post_process::PostProcessPipeline;
+pub use ssao::SsaoPipeline;
+pub use occlusion::OcclusionCuller;
+pub use debug_lines::DebugLines;
+pub use depth_prepass::DepthPrepass;
+pub use transparent::{RenderOrder, TransparentQueue};
+
+/// Format of the HDR scene target: scene pipelines render linear light into it, and
+/// `PostProcessPipeline` tonemaps it to the swapchain
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Why a `GraphicsContext` couldn't be created
+#[derive(Debug)]
+pub enum GraphicsError {
+    /// The window can't be drawn to
+    Surface(wgpu::CreateSurfaceError),
+    /// Neither a GPU nor a fallback (software) adapter is available on any of `backends`
+    NoAdapter { backends: wgpu::Backends },
+    /// The adapter refused to create a device
+    Device(wgpu::RequestDeviceError),
+}
+
+impl std::fmt::Display for GraphicsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GraphicsError::Surface(e) => write!(f, "can't create a surface for the window: {}", e),
+            GraphicsError::NoAdapter { backends } => write!(
+                f,
+                "no graphics adapter found (tried a high-performance GPU, then a low-power or software fallback, on backends {:?}); \
+                 check the graphics drivers, or set WGPU_BACKEND to try another backend",
+                backends
+            ),
+            GraphicsError::Device(e) => write!(f, "can't create a graphics device: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GraphicsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GraphicsError::Surface(e) => Some(e),
+            GraphicsError::NoAdapter { .. } => None,
+            GraphicsError::Device(e) => Some(e),
+        }
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for GraphicsError {
+    fn from(e: wgpu::CreateSurfaceError) -> Self {
+        GraphicsError::Surface(e)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for GraphicsError {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        GraphicsError::Device(e)
+    }
+}
 
 pub struct GraphicsContext {
-    pub surface: Surface<'static>,
+    /// Swapchain; None for a headless context, which presents into `headless_target`
+    surface: Option<Surface<'static>>,
+    /// Owned color texture standing in for the swapchain (see `new_headless`)
+    headless_target: Option<wgpu::Texture>,
     device: Device,
     queue: Queue,
     config: SurfaceConfiguration,
     depth_texture: wgpu::Texture,
     depth_view: wgpu::TextureView,
-    pub window: Arc<Window>,
+    /// MSAA sample count shared by the main color/depth targets and every scene pipeline
+    sample_count: u32,
+    /// HDR scene color (single-sampled, sampled by the post-process pass)
+    hdr_texture: wgpu::Texture,
+    hdr_view: wgpu::TextureView,
+    /// Multisampled HDR color target, resolved into `hdr_view` (None when sample_count == 1)
+    msaa_view: Option<wgpu::TextureView>,
+    /// Present modes the surface supports (queried once at creation)
+    supported_present_modes: Vec<wgpu::PresentMode>,
+    /// Debug view: draw the terrain as triangle edges (see `set_wireframe`)
+    wireframe: bool,
+    /// Lay down opaque depth before the main pass (see `set_depth_prepass`)
+    depth_prepass: bool,
+    /// The GPU and driver rendering (for bug reports)
+    adapter_info: wgpu::AdapterInfo,
+    /// Whether that's the fastest GPU present (see `is_high_performance_adapter`)
+    high_performance_adapter: bool,
+    /// None for a headless context
+    window: Option<Arc<Window>>,
 }
 
 impl GraphicsContext {
     /// Create a new GraphicsContext from a window
     /// This initializes the WGPU instance, adapter, device, and surface
-    pub fn new(window: Arc<Window>) -> Self {
-        pollster::block_on(Self::new_async(window))
+    pub fn new(window: Arc<Window>) -> Result<Self, GraphicsError> {
+        Self::new_with_present_mode(window, wgpu::PresentMode::Fifo)
+    }
+
+    /// Create a GraphicsContext with a specific present mode (e.g. Immediate for uncapped benchmarking)
+    /// Falls back to Fifo if the surface doesn't support the requested mode
+    pub fn new_with_present_mode(window: Arc<Window>, present_mode: wgpu::PresentMode) -> Result<Self, GraphicsError> {
+        pollster::block_on(Self::new_async(window, present_mode, 1))
     }
 
-    async fn new_async(window: Arc<Window>) -> Self {
+    /// Create a GraphicsContext with MSAA (sample_count 1/2/4/8)
+    /// Downgrades to the highest count the adapter supports for the color and depth formats
+    pub fn new_with_msaa(window: Arc<Window>, present_mode: wgpu::PresentMode, sample_count: u32) -> Result<Self, GraphicsError> {
+        pollster::block_on(Self::new_async(window, present_mode, sample_count))
+    }
+
+    async fn new_async(window: Arc<Window>, present_mode: wgpu::PresentMode, sample_count: u32) -> Result<Self, GraphicsError> {
         let size = window.inner_size();
 
         // Initialize WGPU instance
+        let backends = wgpu::Backends::all();
         let instance = Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
+            backends,
             ..Default::default()
         });
 
         // Create surface
-        let surface = instance.create_surface(window.clone()).unwrap();
-
-        // Request adapter with high performance preference
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        })
-        .await
-        .expect("Failed to find an appropriate adapter");
+        let surface = instance.create_surface(window.clone())?;
 
-        // Request device and queue
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("Main Device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                },
-                None,
-            )
-            .await
-            .expect("Failed to create device");
+        let adapter = Self::request_adapter(&instance, backends, Some(&surface)).await?;
+        let (device, queue) = Self::request_device(&adapter).await?;
 
         // Configure the surface
         let surface_caps = surface.get_capabilities(&adapter);
@@ -84,12 +182,21 @@ impl GraphicsContext {
             .copied()
             .unwrap_or(surface_caps.formats[0]);
 
+        let supported_present_modes = surface_caps.present_modes.clone();
+        let present_mode = Self::resolve_present_mode(&supported_present_modes, present_mode);
+
+        // COPY_SRC lets capture_frame read the swapchain image back (not every backend offers it)
+        let mut usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+        if surface_caps.usages.contains(wgpu::TextureUsages::COPY_SRC) {
+            usage |= wgpu::TextureUsages::COPY_SRC;
+        }
+
         let config = SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode,
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -97,21 +204,262 @@ impl GraphicsContext {
 
         surface.configure(&device, &config);
 
-        // Create depth texture
-        let (depth_texture, depth_view) = Self::create_depth_texture(&device, &config);
+        let color_flags = adapter.get_texture_format_features(HDR_FORMAT).flags;
+        let depth_flags = adapter.get_texture_format_features(wgpu::TextureFormat::Depth32Float).flags;
+        let sample_count = Self::resolve_sample_count(sample_count, |count| {
+            color_flags.sample_count_supported(count) && depth_flags.sample_count_supported(count)
+        });
+
+        // Create depth texture and HDR scene color (plus the MSAA color target if multisampling)
+        let (depth_texture, depth_view) = Self::create_depth_texture(&device, &config, sample_count);
+        let (hdr_texture, hdr_view) = Self::create_hdr_target(&device, &config);
+        let msaa_view = Self::create_msaa_view(&device, &config, sample_count);
+
+        let (adapter_info, high_performance_adapter) = Self::describe_adapter(&instance, backends, &adapter);
+        log::info!("Surface: {:?}, {:?}, {}x MSAA, {}x{}", config.format, config.present_mode, sample_count, config.width, config.height);
+
+        Ok(Self {
+            surface: Some(surface),
+            headless_target: None,
+            device,
+            queue,
+            config,
+            depth_texture,
+            depth_view,
+            sample_count,
+            hdr_texture,
+            hdr_view,
+            msaa_view,
+            supported_present_modes,
+            wireframe: false,
+            depth_prepass: false,
+            adapter_info,
+            high_performance_adapter,
+            window: Some(window),
+        })
+    }
+
+    /// Create a context without a window, rendering into an owned `format` texture of the
+    /// given size (for golden-image tests and CI). Frames are drawn into `headless_view` and
+    /// read back with `capture_headless`; there's no MSAA, so output is the same on every run.
+    /// Fails if no adapter or device is available (e.g. a CI runner without a GPU or software
+    /// renderer).
+    pub fn new_headless(width: u32, height: u32, format: wgpu::TextureFormat) -> Result<Self, GraphicsError> {
+        pollster::block_on(Self::new_headless_async(width, height, format))
+    }
+
+    async fn new_headless_async(width: u32, height: u32, format: wgpu::TextureFormat) -> Result<Self, GraphicsError> {
+        // GL needs a display to start on some systems (crashing without one), so it's only
+        // tried when asked for, e.g. WGPU_BACKEND=gl for a software renderer on CI
+        let backends = wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::PRIMARY);
+        let instance = Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        let adapter = Self::request_adapter(&instance, backends, None).await?;
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        // Stands in for the swapchain; COPY_SRC so it can be read back
+        let config = SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let headless_target = Self::create_headless_target(&device, &config);
+        let (depth_texture, depth_view) = Self::create_depth_texture(&device, &config, 1);
+        let (hdr_texture, hdr_view) = Self::create_hdr_target(&device, &config);
+
+        let (adapter_info, high_performance_adapter) = Self::describe_adapter(&instance, backends, &adapter);
+        log::info!("Headless target: {:?}, {}x{}", format, width, height);
 
-        Self {
-            surface,
+        Ok(Self {
+            surface: None,
+            headless_target: Some(headless_target),
             device,
             queue,
             config,
             depth_texture,
             depth_view,
-            window,
+            sample_count: 1,
+            hdr_texture,
+            hdr_view,
+            msaa_view: None,
+            supported_present_modes: vec![wgpu::PresentMode::Fifo],
+            wireframe: false,
+            depth_prepass: false,
+            adapter_info,
+            high_performance_adapter,
+            window: None,
+        })
+    }
+
+    /// The fastest GPU that can draw to `surface`, else whatever low-power or software
+    /// (fallback) adapter there is: headless CI boxes and some VMs have no real GPU
+    async fn request_adapter(instance: &Instance, backends: wgpu::Backends, surface: Option<&Surface<'static>>) -> Result<wgpu::Adapter, GraphicsError> {
+        let options = |power_preference, force_fallback_adapter| wgpu::RequestAdapterOptions {
+            power_preference,
+            compatible_surface: surface,
+            force_fallback_adapter,
+        };
+        if let Some(adapter) = instance.request_adapter(&options(wgpu::PowerPreference::HighPerformance, false)).await {
+            return Ok(adapter);
+        }
+        log::warn!("No high-performance graphics adapter found, trying a fallback adapter");
+        instance
+            .request_adapter(&options(wgpu::PowerPreference::LowPower, true))
+            .await
+            .ok_or(GraphicsError::NoAdapter { backends })
+    }
+
+    /// Device and queue, with line rasterization for the wireframe debug view where offered
+    /// and the largest buffers the adapter allows (see `device_limits`)
+    async fn request_device(adapter: &wgpu::Adapter) -> Result<(Device, Queue), wgpu::RequestDeviceError> {
+        let required_limits = Self::device_limits(&adapter.limits());
+        log::info!(
+            "Max buffer size {} MB, storage binding {} MB",
+            required_limits.max_buffer_size >> 20,
+            required_limits.max_storage_buffer_binding_size >> 20
+        );
+        adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Main Device"),
+                    required_features: adapter.features() & wgpu::Features::POLYGON_MODE_LINE,
+                    required_limits,
+                },
+                None,
+            )
+            .await
+    }
+
+    /// Default limits, with buffer sizes raised to what the adapter `supported` allows (for dense
+    /// grass and detritus meshes); an adapter below the defaults just gets the defaults
+    fn device_limits(supported: &wgpu::Limits) -> wgpu::Limits {
+        let defaults = wgpu::Limits::default();
+        wgpu::Limits {
+            max_buffer_size: supported.max_buffer_size.max(defaults.max_buffer_size),
+            max_storage_buffer_binding_size: supported.max_storage_buffer_binding_size.max(defaults.max_storage_buffer_binding_size),
+            ..defaults
+        }
+    }
+
+    /// Log the chosen adapter (and warn if it's not the fastest of `backends`); returns its info
+    /// and whether it `is_high_performance_adapter`
+    fn describe_adapter(instance: &Instance, backends: wgpu::Backends, adapter: &wgpu::Adapter) -> (wgpu::AdapterInfo, bool) {
+        let info = adapter.get_info();
+        let available: Vec<wgpu::AdapterInfo> = instance.enumerate_adapters(backends).iter().map(|a| a.get_info()).collect();
+        let high_performance = Self::is_high_performance_adapter(&info, &available);
+
+        log::info!("GPU: {} ({:?}) on {:?}, driver {} {}", info.name, info.device_type, info.backend, info.driver, info.driver_info);
+        if !high_performance {
+            log::warn!(
+                "Rendering on {} although a faster GPU is available: {:?}",
+                info.name,
+                available.iter().filter(|other| other.device_type == wgpu::DeviceType::DiscreteGpu).map(|other| &other.name).collect::<Vec<_>>()
+            );
+        }
+        (info, high_performance)
+    }
+
+    /// Whether `selected` is the fastest kind of GPU among `available`: a discrete GPU, or the
+    /// best there is when the machine has none. False for software renderers and for an
+    /// integrated GPU picked over a discrete one (some laptops hand out the iGPU regardless)
+    fn is_high_performance_adapter(selected: &wgpu::AdapterInfo, available: &[wgpu::AdapterInfo]) -> bool {
+        match selected.device_type {
+            wgpu::DeviceType::DiscreteGpu => true,
+            wgpu::DeviceType::Cpu => false,
+            _ => !available.iter().any(|info| info.device_type == wgpu::DeviceType::DiscreteGpu),
+        }
+    }
+
+    /// Use `requested` if the surface supports it, otherwise Fifo (always available)
+    fn resolve_present_mode(supported: &[wgpu::PresentMode], requested: wgpu::PresentMode) -> wgpu::PresentMode {
+        if supported.contains(&requested) {
+            requested
+        } else {
+            println!("[RENDER] Warning: present mode {:?} not supported (available: {:?}), falling back to Fifo", requested, supported);
+            wgpu::PresentMode::Fifo
+        }
+    }
+
+    /// Highest of 8/4/2/1 that is <= `requested` and passes `supported` (1 always does)
+    fn resolve_sample_count(requested: u32, supported: impl Fn(u32) -> bool) -> u32 {
+        let count = [8, 4, 2]
+            .into_iter()
+            .find(|&count| count <= requested && supported(count))
+            .unwrap_or(1);
+        if count != requested {
+            println!("[RENDER] Warning: MSAA x{} not supported, using x{}", requested, count);
+        }
+        count
+    }
+
+    fn create_msaa_view(device: &Device, config: &SurfaceConfiguration, sample_count: u32) -> Option<wgpu::TextureView> {
+        if sample_count <= 1 {
+            return None;
         }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA HDR Color Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    fn create_headless_target(device: &Device, config: &SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Color Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        })
+    }
+
+    fn create_hdr_target(device: &Device, config: &SurfaceConfiguration) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("HDR Color Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
     }
 
-    fn create_depth_texture(device: &Device, config: &SurfaceConfiguration) -> (wgpu::Texture, wgpu::TextureView) {
+    fn create_depth_texture(device: &Device, config: &SurfaceConfiguration, sample_count: u32) -> (wgpu::Texture, wgpu::TextureView) {
         let size = wgpu::Extent3d {
             width: config.width,
             height: config.height,
@@ -122,7 +470,7 @@ impl GraphicsContext {
             label: Some("Depth Texture"),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Depth32Float,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
@@ -136,9 +484,19 @@ impl GraphicsContext {
 
     /// Render a frame with the specified clear color
     pub fn render(&mut self, color: wgpu::Color) -> Result<(), wgpu::SurfaceError> {
-        // Get the current frame
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Get the current frame (skipped while the surface is being recovered)
+        let output = match self.headless_target {
+            Some(_) => None,
+            None => match self.acquire_frame() {
+                Some(output) => Some(output),
+                None => return Ok(()),
+            },
+        };
+        let view = match (&output, self.headless_view()) {
+            (Some(output), _) => output.texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            (None, Some(view)) => view,
+            (None, None) => unreachable!("a context has either a surface or a headless target"),
+        };
 
         // Create command encoder
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -165,25 +523,180 @@ impl GraphicsContext {
 
         // Submit command buffer and present
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        if let Some(output) = output {
+            output.present();
+        }
 
         Ok(())
     }
 
+    /// Get the next swapchain texture, recovering from surface loss
+    /// Lost/Outdated (alt-tab, GPU switch, resize races) reconfigure the surface and skip
+    /// the frame; Timeout skips the frame; OutOfMemory is fatal. Always None when headless.
+    pub fn acquire_frame(&mut self) -> Option<wgpu::SurfaceTexture> {
+        match self.surface.as_ref()?.get_current_texture() {
+            Ok(output) => Some(output),
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.reconfigure();
+                None
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                log::warn!("Surface timeout, skipping frame");
+                None
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                panic!("Surface out of memory");
+            }
+        }
+    }
+
+    /// Reconfigure the surface with the stored config (after it was lost or outdated)
+    pub fn reconfigure(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let size = window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return; // Minimized: wait for a real size
+        }
+        if size.width != self.config.width || size.height != self.config.height {
+            self.resize(size); // Also rebuilds depth/MSAA targets
+        } else {
+            self.configure_surface();
+        }
+    }
+
+    /// Apply `config` to the swapchain, or to the headless target
+    fn configure_surface(&mut self) {
+        match &self.surface {
+            Some(surface) => surface.configure(&self.device, &self.config),
+            None => self.headless_target = Some(Self::create_headless_target(&self.device, &self.config)),
+        }
+    }
+
     /// Resize the surface
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            self.configure_surface();
 
-            // Recreate depth texture
-            let (depth_texture, depth_view) = Self::create_depth_texture(&self.device, &self.config);
+            // Recreate depth texture, HDR target and MSAA target
+            let (depth_texture, depth_view) = Self::create_depth_texture(&self.device, &self.config, self.sample_count);
             self.depth_texture = depth_texture;
             self.depth_view = depth_view;
+            let (hdr_texture, hdr_view) = Self::create_hdr_target(&self.device, &self.config);
+            self.hdr_texture = hdr_texture;
+            self.hdr_view = hdr_view;
+            self.msaa_view = Self::create_msaa_view(&self.device, &self.config, self.sample_count);
         }
     }
 
+    /// Switch present mode (VSync on/off) and reconfigure the surface
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) {
+        let mode = Self::resolve_present_mode(&self.supported_present_modes, mode);
+        if mode != self.config.present_mode {
+            self.config.present_mode = mode;
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.config);
+            }
+        }
+    }
+
+    /// Currently active present mode
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.config.present_mode
+    }
+
+    /// Read back a frame as displayed, e.g. for screenshots
+    /// Call after the frame's passes are submitted and before `present()` (the surface
+    /// texture is only readable while it's held). Works for RGBA and BGRA (s)RGB formats.
+    pub fn capture_frame(&self, frame: &wgpu::SurfaceTexture) -> Result<image::RgbaImage, Box<dyn std::error::Error>> {
+        if !self.config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err("surface does not support COPY_SRC, frame capture unavailable".into());
+        }
+        self.capture_texture(&frame.texture)
+    }
+
+    /// Capture a frame and write it to `path` (format from the extension, e.g. .png)
+    pub fn save_screenshot(&self, frame: &wgpu::SurfaceTexture, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let image = self.capture_frame(frame)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        image.save(path)?;
+        println!("[RENDER] Screenshot saved to {}", path.display());
+        Ok(())
+    }
+
+    /// View of a headless context's color target, where the final pass draws instead of a
+    /// swapchain frame (None with a window)
+    pub fn headless_view(&self) -> Option<wgpu::TextureView> {
+        self.headless_target.as_ref().map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    /// Read back what was rendered into a headless context's color target
+    pub fn capture_headless(&self) -> Result<image::RgbaImage, Box<dyn std::error::Error>> {
+        let texture = self.headless_target.as_ref().ok_or("not a headless context")?;
+        self.capture_texture(texture)
+    }
+
+    /// Copy an 8-bit RGBA/BGRA texture (with COPY_SRC usage) into an image
+    pub fn capture_texture(&self, texture: &wgpu::Texture) -> Result<image::RgbaImage, Box<dyn std::error::Error>> {
+        let bgra = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            other => return Err(format!("cannot capture texture format {:?}", other).into()),
+        };
+
+        let width = texture.width();
+        let height = texture.height();
+        let padded_bytes_per_row = padded_bytes_per_row(width);
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Capture Buffer"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Frame Capture Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        // Block until the copy lands and the buffer is mapped
+        let slice = buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv()??;
+
+        let pixels = unpad_rows(&slice.get_mapped_range(), width, height, padded_bytes_per_row, bgra);
+        buffer.unmap();
+
+        Ok(image::RgbaImage::from_raw(width, height, pixels).expect("capture buffer matches image size"))
+    }
+
+    /// The window rendered to (None when headless)
+    pub fn window(&self) -> Option<&Arc<Window>> {
+        self.window.as_ref()
+    }
+
     /// Get the current surface configuration
     pub fn config(&self) -> &SurfaceConfiguration {
         &self.config
@@ -204,8 +717,225 @@ impl GraphicsContext {
         &self.depth_view
     }
 
-    /// Get surface format
+    /// MSAA sample count; pipelines drawing into the main view must use the same count
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Whether the device can rasterize polygons as lines (`PolygonMode::Line`)
+    pub fn supports_wireframe(&self) -> bool {
+        self.device.features().contains(wgpu::Features::POLYGON_MODE_LINE)
+    }
+
+    /// Switch the terrain to its wireframe pipeline (ignored, with a warning, if unsupported)
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        if enabled && !self.supports_wireframe() {
+            println!("[RENDER] Warning: wireframe needs POLYGON_MODE_LINE, which this adapter lacks");
+            return;
+        }
+        self.wireframe = enabled;
+    }
+
+    /// Whether scene passes should draw the terrain in wireframe
+    pub fn wireframe(&self) -> bool {
+        self.wireframe
+    }
+
+    /// Have scene passes fill the depth buffer with a depth-only pass first (`DepthPrepass`), so
+    /// the shaded passes skip hidden fragments. Off by default: it only pays for itself when
+    /// there's real overdraw, as with dense grass and trees behind terrain.
+    pub fn set_depth_prepass(&mut self, enabled: bool) {
+        self.depth_prepass = enabled;
+    }
+
+    /// Whether scene passes should run a depth prepass
+    pub fn depth_prepass(&self) -> bool {
+        self.depth_prepass
+    }
+
+    /// Scene color attachment: (view to draw into, resolve target)
+    /// Both are the HDR target; with MSAA this is the multisampled target resolving into it
+    pub fn scene_attachment(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(&self.hdr_view)),
+            None => (&self.hdr_view, None),
+        }
+    }
+
+    /// Resolved HDR scene color, the input of the post-process pass
+    pub fn hdr_texture(&self) -> &wgpu::Texture {
+        &self.hdr_texture
+    }
+
+    /// Limits the device was created with
+    pub fn limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+
+    /// Largest buffer that can be created, in bytes (at least 256 MB); budget meshes against it
+    pub fn max_buffer_size(&self) -> u64 {
+        self.device.limits().max_buffer_size
+    }
+
+    /// Name, kind, driver and backend of the GPU in use
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Graphics API the device runs on (Vulkan, Metal, DX12, GL)
+    pub fn backend(&self) -> wgpu::Backend {
+        self.adapter_info.backend
+    }
+
+    /// False if rendering fell back to an integrated GPU (while a discrete one is present) or
+    /// to a software renderer
+    pub fn high_performance_adapter(&self) -> bool {
+        self.high_performance_adapter
+    }
+
+    /// Get surface format (the swapchain: post-process and UI passes draw in it)
     pub fn surface_format(&self) -> wgpu::TextureFormat {
         self.config.format
     }
+
+    /// Format scene pipelines render in (the HDR target)
+    pub fn scene_format(&self) -> wgpu::TextureFormat {
+        HDR_FORMAT
+    }
+
+    /// Create an offscreen target in the scene format, so existing pipelines can draw into it
+    pub fn create_render_target(&self, width: u32, height: u32) -> RenderTarget {
+        RenderTarget::new(&self.device, width, height, HDR_FORMAT, self.sample_count)
+    }
+}
+
+/// Row pitch for texture -> buffer copies (wgpu requires 256-byte aligned rows)
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// Strip row padding from a readback buffer, converting BGRA to RGBA if needed
+fn unpad_rows(data: &[u8], width: u32, height: u32, padded_bytes_per_row: u32, bgra: bool) -> Vec<u8> {
+    let row_bytes = (width * 4) as usize;
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in data.chunks(padded_bytes_per_row as usize).take(height as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    if bgra {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_unpads_and_swizzles() {
+        // 3x2 image: 12 bytes per row padded to 256
+        let padded = padded_bytes_per_row(3);
+        assert_eq!(padded, 256);
+
+        let mut data = vec![0u8; (padded * 2) as usize];
+        for y in 0..2 {
+            for x in 0..3 {
+                let i = (y * padded + x * 4) as usize;
+                data[i..i + 4].copy_from_slice(&[10 * x as u8, 100 + y as u8, 200, 255]); // B, G, R, A
+            }
+        }
+
+        let pixels = unpad_rows(&data, 3, 2, padded, true);
+        assert_eq!(pixels.len(), 3 * 2 * 4);
+        assert_eq!(&pixels[4..8], &[200, 100, 10, 255]); // (1, 0) as RGBA
+        assert_eq!(&pixels[20..24], &[200, 101, 20, 255]); // (2, 1)
+
+        let raw = unpad_rows(&data, 3, 2, padded, false);
+        assert_eq!(&raw[4..8], &[10, 100, 200, 255]);
+    }
+
+    #[test]
+    fn test_high_performance_adapter() {
+        let adapter = |device_type| wgpu::AdapterInfo {
+            name: format!("{:?}", device_type),
+            vendor: 0,
+            device: 0,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        };
+        let (discrete, integrated, cpu) = (adapter(wgpu::DeviceType::DiscreteGpu), adapter(wgpu::DeviceType::IntegratedGpu), adapter(wgpu::DeviceType::Cpu));
+
+        assert!(GraphicsContext::is_high_performance_adapter(&discrete, &[discrete.clone(), integrated.clone()]));
+        // The iGPU is fine when it's all there is, not when it was picked over a discrete GPU
+        assert!(GraphicsContext::is_high_performance_adapter(&integrated, &[integrated.clone(), cpu.clone()]));
+        assert!(!GraphicsContext::is_high_performance_adapter(&integrated, &[integrated.clone(), discrete.clone()]));
+        assert!(!GraphicsContext::is_high_performance_adapter(&cpu, std::slice::from_ref(&cpu)));
+    }
+
+    #[test]
+    fn test_device_limits_raise_buffer_sizes_only() {
+        let defaults = wgpu::Limits::default();
+        let large = wgpu::Limits {
+            max_buffer_size: 4 << 30,
+            max_storage_buffer_binding_size: 2 << 30,
+            max_bind_groups: 8,
+            ..defaults.clone()
+        };
+        let limits = GraphicsContext::device_limits(&large);
+        assert_eq!(limits.max_buffer_size, 4 << 30);
+        assert_eq!(limits.max_storage_buffer_binding_size, 2 << 30);
+        assert_eq!(limits.max_bind_groups, defaults.max_bind_groups);
+
+        // A limited adapter gets the defaults
+        let small = wgpu::Limits { max_buffer_size: 1 << 20, ..defaults.clone() };
+        assert_eq!(GraphicsContext::device_limits(&small).max_buffer_size, defaults.max_buffer_size);
+    }
+
+    #[test]
+    fn test_no_adapter_error_names_the_backends() {
+        let message = GraphicsError::NoAdapter { backends: wgpu::Backends::VULKAN | wgpu::Backends::METAL }.to_string();
+        assert!(message.contains("VULKAN") && message.contains("METAL"), "{}", message);
+        assert!(message.contains("fallback"), "{}", message);
+    }
+
+    #[test]
+    fn test_headless_render_reads_back() {
+        // Needs an adapter (a GPU or a software renderer); skip where there is none
+        let mut ctx = match GraphicsContext::new_headless(4, 3, wgpu::TextureFormat::Rgba8Unorm) {
+            Ok(ctx) => ctx,
+            Err(e) => {
+                println!("Skipping headless test: {}", e);
+                return;
+            }
+        };
+        ctx.render(wgpu::Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 }).unwrap();
+        let image = ctx.capture_headless().unwrap();
+        assert_eq!(image.dimensions(), (4, 3));
+        assert!(image.pixels().all(|pixel| pixel.0 == [255, 0, 0, 255]));
+
+        ctx.resize(winit::dpi::PhysicalSize::new(8, 2));
+        ctx.render(wgpu::Color::BLACK).unwrap();
+        assert_eq!(ctx.capture_headless().unwrap().dimensions(), (8, 2));
+    }
+
+    #[test]
+    fn test_sample_count_downgrade() {
+        assert_eq!(GraphicsContext::resolve_sample_count(4, |_| true), 4);
+        assert_eq!(GraphicsContext::resolve_sample_count(8, |count| count <= 4), 4);
+        assert_eq!(GraphicsContext::resolve_sample_count(2, |_| false), 1);
+        assert_eq!(GraphicsContext::resolve_sample_count(1, |_| true), 1);
+    }
+
+    #[test]
+    fn test_present_mode_fallback() {
+        let supported = [wgpu::PresentMode::Fifo, wgpu::PresentMode::Mailbox];
+        assert_eq!(GraphicsContext::resolve_present_mode(&supported, wgpu::PresentMode::Mailbox), wgpu::PresentMode::Mailbox);
+        assert_eq!(GraphicsContext::resolve_present_mode(&supported, wgpu::PresentMode::Immediate), wgpu::PresentMode::Fifo);
+    }
 }
@@ -0,0 +1,177 @@
+use glam::{Vec3, Mat4};
+
+/// Length of the lunar cycle in game days (new moon to new moon)
+pub const LUNAR_CYCLE_DAYS: f32 = 29.53;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct MoonUniforms {
+    view_proj: [[f32; 4]; 4],
+    moon_world_pos: [f32; 3],
+    moon_size: f32,
+    moon_color: [f32; 3],
+    phase: f32,
+    camera_right: [f32; 3],
+    _padding: f32,
+    camera_up: [f32; 3],
+    _padding2: f32,
+    sun_dir: [f32; 3],
+    _padding3: f32,
+}
+
+/// Where the moon is this frame and how much of it is lit
+#[derive(Copy, Clone, Debug)]
+pub struct MoonState {
+    /// Direction FROM the moon TO the scene (normalized)
+    pub moon_dir: Vec3,
+    /// Direction FROM the sun TO the scene; orients the lit side of the disc
+    pub sun_dir: Vec3,
+    /// Lunar phase: 0 = new moon, 0.25 = first quarter, 0.5 = full, 0.75 = last quarter
+    pub phase: f32,
+}
+
+/// Moon billboard: a pale disc lit from the sun's side according to its phase
+pub struct MoonPipeline {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl MoonPipeline {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Moon Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../../assets/shaders/moon.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Moon Uniform Buffer"),
+            size: std::mem::size_of::<MoonUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Moon Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Moon Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Moon Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Moon Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[], // No vertex buffer - generate in shader
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            // No depth test - drawn in the sky pass before the scene, like the sun
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+
+    /// Update moon position and lighting
+    pub fn update(&self, queue: &wgpu::Queue, view_proj: &Mat4, moon: &MoonState, camera_pos: Vec3, camera_right: Vec3, camera_up: Vec3) {
+        // Same distance as the sun billboard, slightly smaller disc
+        let moon_distance = 800.0;
+        let moon_world_pos = camera_pos - moon.moon_dir * moon_distance;
+        let moon_size = 30.0;
+
+        let uniforms = MoonUniforms {
+            view_proj: view_proj.to_cols_array_2d(),
+            moon_world_pos: moon_world_pos.to_array(),
+            moon_size,
+            moon_color: [0.85, 0.9, 1.0], // Pale blue-white
+            phase: moon.phase.rem_euclid(1.0),
+            camera_right: camera_right.to_array(),
+            _padding: 0.0,
+            camera_up: camera_up.to_array(),
+            _padding2: 0.0,
+            sun_dir: moon.sun_dir.to_array(),
+            _padding3: 0.0,
+        };
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Render the moon billboard
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..6, 0..1); // 6 vertices for quad (2 triangles)
+    }
+}
+
+/// Lunar phase (for `MoonState::phase`) after `days` game days; day 0 is a full moon
+pub fn lunar_phase(days: f32) -> f32 {
+    (days / LUNAR_CYCLE_DAYS + 0.5).rem_euclid(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_layout() {
+        // Must match the Uniforms struct in moon.wgsl
+        assert_eq!(std::mem::size_of::<MoonUniforms>(), 144);
+    }
+
+    #[test]
+    fn test_lunar_phase_cycles() {
+        assert!((lunar_phase(0.0) - 0.5).abs() < 1e-6);
+        let new_moon = LUNAR_CYCLE_DAYS * 0.5;
+        let phase = lunar_phase(new_moon);
+        assert!(phase.min(1.0 - phase) < 1e-5);
+        assert!((lunar_phase(3.0) - lunar_phase(3.0 + LUNAR_CYCLE_DAYS)).abs() < 1e-5);
+        // Waxing after the new moon
+        assert!(lunar_phase(new_moon + 1.0) < lunar_phase(new_moon + 7.0));
+    }
+}
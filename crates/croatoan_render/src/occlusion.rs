@@ -0,0 +1,281 @@
+use glam::{Mat4, Vec3};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::sync::mpsc::{channel, Receiver};
+
+/// Most boxes queried per frame; boxes past this are never culled
+const MAX_QUERIES: u32 = 1024;
+
+/// Boxes the camera is inside (or this close to) are never culled: their near faces are clipped
+/// away, so the query could count zero samples for a box that's in plain view
+const NEAR_MARGIN: f32 = 2.0;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct BoxRaw {
+    min: [f32; 3], // 12 bytes (0-12)
+    max: [f32; 3], // 12 bytes (12-24) -> Total 24 bytes
+}
+
+/// Query results being copied back to the CPU
+struct Readback<K> {
+    /// Box keys in query order
+    keys: Vec<K>,
+    /// Set once `map_async` has been called (after the copy was submitted)
+    mapping: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+/// Hardware occlusion culling for world regions (chunks) keyed by `K`
+///
+/// Per frame: `prepare` with the boxes in view, begin the main pass with `query_set` as its
+/// occlusion query set, draw the scene skipping keys that `is_occluded`, then `render` the
+/// proxies last so they test against the finished depth buffer. `resolve` into the frame's
+/// encoder and `read_back` once it's submitted fetch the sample counts; a box with zero samples is
+/// occluded until the next results arrive (a frame or two later). Because the hidden region
+/// still gets its proxy drawn, it is drawn again as soon as any part of it comes into view.
+pub struct OcclusionCuller<K> {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    box_buffer: wgpu::Buffer,
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Keys of this frame's queries, in query order
+    queried: Vec<K>,
+    readback: Option<Readback<K>>,
+    occluded: HashSet<K>,
+}
+
+impl<K: Copy + Eq + Hash> OcclusionCuller<K> {
+    /// `scene_format` and `sample_count` must match the pass the proxies are drawn in
+    pub fn new(device: &wgpu::Device, scene_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("../../../assets/shaders/occlusion.wgsl"));
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Uniform Buffer"),
+            size: std::mem::size_of::<[[f32; 4]; 4]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Occlusion Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Occlusion Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Occlusion Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Occlusion Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<BoxRaw>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: scene_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::empty(),
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None, // Either side of the box counts
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        let box_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Box Buffer"),
+            size: (std::mem::size_of::<BoxRaw>() as u32 * MAX_QUERIES) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Occlusion Query Set"),
+            ty: wgpu::QueryType::Occlusion,
+            count: MAX_QUERIES,
+        });
+        let results_size = (std::mem::size_of::<u64>() as u32 * MAX_QUERIES) as u64;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Resolve Buffer"),
+            size: results_size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Readback Buffer"),
+            size: results_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            box_buffer,
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            queried: Vec::new(),
+            readback: None,
+            occluded: HashSet::new(),
+        }
+    }
+
+    /// The query set the pass drawing the proxies must be created with
+    pub fn query_set(&self) -> &wgpu::QuerySet {
+        &self.query_set
+    }
+
+    /// Whether `key`'s box drew no samples in the latest results
+    pub fn is_occluded(&self, key: &K) -> bool {
+        self.occluded.contains(key)
+    }
+
+    /// Queue this frame's boxes (world-space min/max per key), typically the ones in the frustum
+    pub fn prepare(&mut self, queue: &wgpu::Queue, view_proj: &Mat4, camera_pos: Vec3, boxes: impl IntoIterator<Item = (K, Vec3, Vec3)>) {
+        self.queried.clear();
+        let mut raw = Vec::new();
+        for (key, min, max) in boxes {
+            if raw.len() as u32 == MAX_QUERIES {
+                break;
+            }
+            if near_box(min, max, camera_pos) {
+                continue;
+            }
+            self.queried.push(key);
+            raw.push(BoxRaw { min: min.to_array(), max: max.to_array() });
+        }
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&view_proj.to_cols_array_2d()));
+        queue.write_buffer(&self.box_buffer, 0, bytemuck::cast_slice(&raw));
+    }
+
+    /// Draw one proxy box per query; call last in the pass, once the occluders are drawn
+    pub fn render<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        if self.queried.is_empty() {
+            return;
+        }
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, self.box_buffer.slice(..));
+        for index in 0..self.queried.len() as u32 {
+            pass.begin_occlusion_query(index);
+            pass.draw(0..36, index..index + 1);
+            pass.end_occlusion_query();
+        }
+    }
+
+    /// Resolve this frame's queries, and copy them for reading unless a readback is in flight
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let count = self.queried.len() as u32;
+        if count == 0 || self.readback.is_some() {
+            return;
+        }
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+        let size = (std::mem::size_of::<u64>() as u32 * count) as u64;
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, size);
+        self.readback = Some(Readback { keys: std::mem::take(&mut self.queried), mapping: None });
+    }
+
+    /// Call after submitting the frame: starts mapping resolved results, and applies them once
+    /// they're readable (without blocking)
+    pub fn read_back(&mut self, device: &wgpu::Device) {
+        let Some(readback) = &mut self.readback else {
+            return;
+        };
+        let size = (std::mem::size_of::<u64>() * readback.keys.len()) as u64;
+        let slice = self.readback_buffer.slice(..size);
+        let mapping = readback.mapping.get_or_insert_with(|| {
+            let (tx, rx) = channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+            rx
+        });
+        device.poll(wgpu::Maintain::Poll);
+
+        match mapping.try_recv() {
+            Ok(Ok(())) => {
+                let samples: Vec<u64> = slice.get_mapped_range()
+                    .chunks_exact(8)
+                    .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect();
+                self.readback_buffer.unmap();
+                // Anything not queried (left the view, near the camera) counts as visible
+                self.occluded = readback.keys.iter().zip(samples).filter(|(_, count)| *count == 0).map(|(key, _)| *key).collect();
+                self.readback = None;
+            }
+            Ok(Err(e)) => {
+                log::warn!("Occlusion readback failed: {}", e);
+                self.readback = None;
+            }
+            Err(_) => {} // Not mapped yet
+        }
+    }
+}
+
+/// Whether `point` is inside the box grown by `NEAR_MARGIN`
+fn near_box(min: Vec3, max: Vec3, point: Vec3) -> bool {
+    point.cmpge(min - NEAR_MARGIN).all() && point.cmple(max + NEAR_MARGIN).all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_layout() {
+        assert_eq!(std::mem::size_of::<BoxRaw>(), 24);
+    }
+
+    #[test]
+    fn test_boxes_around_the_camera_are_never_queried() {
+        let (min, max) = (Vec3::new(0.0, -10.0, 0.0), Vec3::new(256.0, 80.0, 256.0));
+        assert!(near_box(min, max, Vec3::new(128.0, 20.0, 128.0)));
+        assert!(near_box(min, max, Vec3::new(-1.0, 20.0, 128.0)));
+        assert!(!near_box(min, max, Vec3::new(-10.0, 20.0, 128.0)));
+        assert!(!near_box(min, max, Vec3::new(128.0, 200.0, 128.0)));
+    }
+}
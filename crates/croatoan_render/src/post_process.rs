@@ -0,0 +1,308 @@
+use crate::HDR_FORMAT;
+
+/// Final passes from the HDR scene target to the swapchain:
+/// bloom (bright-pass + separable Gaussian blur at half resolution), then an additive
+/// composite with exposure + ACES tonemapping. Encodes to sRGB in the shader when the
+/// surface format doesn't do it in hardware.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniforms {
+    exposure: f32,
+    encode_srgb: u32, // 1 when the surface format is linear (not *Srgb)
+    bloom_threshold: f32,
+    bloom_intensity: f32, // 0 while bloom is disabled
+}
+
+/// Half-resolution ping-pong textures for the bloom blur, sized for one source
+struct BloomTargets {
+    source_id: wgpu::Id<wgpu::Texture>,
+    views: [wgpu::TextureView; 2],
+    bright_group: wgpu::BindGroup,       // source -> views[0]
+    blur_groups: [wgpu::BindGroup; 2],   // views[0] -> views[1] (horizontal), views[1] -> views[0] (vertical)
+    composite_group: wgpu::BindGroup,    // source + views[0] -> swapchain
+}
+
+pub struct PostProcessPipeline {
+    composite_pipeline: wgpu::RenderPipeline,
+    bright_pipeline: wgpu::RenderPipeline,
+    blur_pipelines: [wgpu::RenderPipeline; 2], // Horizontal, vertical
+    composite_layout: wgpu::BindGroupLayout,
+    bloom_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    targets: Option<BloomTargets>,
+    exposure: f32,
+    encode_srgb: bool,
+    bloom_enabled: bool,
+    bloom_threshold: f32,
+    bloom_intensity: f32,
+}
+
+impl PostProcessPipeline {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let composite_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Process Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../../assets/shaders/post_process.wgsl").into()),
+        });
+        let bloom_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Bloom Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../../assets/shaders/bloom.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Process Uniform Buffer"),
+            size: std::mem::size_of::<PostUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        };
+        let sampler_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        };
+
+        // Composite: uniforms, HDR scene (read texel-for-texel), blurred bloom + sampler
+        let composite_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process Bind Group Layout"),
+            entries: &[uniform_entry, texture_entry(1), texture_entry(2), sampler_entry(3)],
+        });
+        // Bloom passes: uniforms, input texture + sampler
+        let bloom_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bloom Bind Group Layout"),
+            entries: &[uniform_entry, texture_entry(1), sampler_entry(2)],
+        });
+
+        let fullscreen_pipeline = |label: &str, layout: &wgpu::BindGroupLayout, shader: &wgpu::ShaderModule, entry_point: &str, format: wgpu::TextureFormat| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[], // Fullscreen triangle from the vertex index
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(), // Single-sampled targets
+                multiview: None,
+            })
+        };
+
+        let composite_pipeline = fullscreen_pipeline("Post Process Pipeline", &composite_layout, &composite_shader, "fs_main", surface_format);
+        let bright_pipeline = fullscreen_pipeline("Bloom Bright Pipeline", &bloom_layout, &bloom_shader, "fs_bright", HDR_FORMAT);
+        let blur_pipelines = [
+            fullscreen_pipeline("Bloom Blur H Pipeline", &bloom_layout, &bloom_shader, "fs_blur_horizontal", HDR_FORMAT),
+            fullscreen_pipeline("Bloom Blur V Pipeline", &bloom_layout, &bloom_shader, "fs_blur_vertical", HDR_FORMAT),
+        ];
+
+        Self {
+            composite_pipeline,
+            bright_pipeline,
+            blur_pipelines,
+            composite_layout,
+            bloom_layout,
+            uniform_buffer,
+            sampler,
+            targets: None,
+            exposure: 1.0,
+            encode_srgb: !surface_format.is_srgb(),
+            bloom_enabled: true,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.5,
+        }
+    }
+
+    /// Scale applied to the scene before tonemapping (1.0 = as rendered)
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure.max(0.0);
+    }
+
+    /// Bloom tuning: scene brightness (HDR, pre-exposure) where glow starts, and how
+    /// strongly the blurred glow is added back
+    pub fn set_bloom(&mut self, threshold: f32, intensity: f32) {
+        self.bloom_threshold = threshold.max(0.0);
+        self.bloom_intensity = intensity.max(0.0);
+    }
+
+    /// Skip the bloom passes entirely (low-end machines); tonemapping is unaffected
+    pub fn set_bloom_enabled(&mut self, enabled: bool) {
+        self.bloom_enabled = enabled;
+    }
+
+    /// Bloom and tonemap `source` (the resolved HDR scene) into `target` (the swapchain frame)
+    /// The bloom textures follow the source's size and are rebuilt when it's recreated (resize).
+    pub fn render(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, source: &wgpu::Texture, target: &wgpu::TextureView) {
+        if self.targets.as_ref().map(|targets| targets.source_id) != Some(source.global_id()) {
+            self.targets = Some(self.create_targets(device, source));
+        }
+        let targets = self.targets.as_ref().expect("created above");
+
+        let uniforms = PostUniforms {
+            exposure: self.exposure,
+            encode_srgb: self.encode_srgb as u32,
+            bloom_threshold: self.bloom_threshold,
+            bloom_intensity: if self.bloom_enabled { self.bloom_intensity } else { 0.0 },
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+        if self.bloom_enabled {
+            // Bright-pass into [0], blur [0] -> [1] horizontally, then [1] -> [0] vertically
+            let passes = [
+                (&self.bright_pipeline, &targets.bright_group, &targets.views[0], "Bloom Bright Pass"),
+                (&self.blur_pipelines[0], &targets.blur_groups[0], &targets.views[1], "Bloom Blur H Pass"),
+                (&self.blur_pipelines[1], &targets.blur_groups[1], &targets.views[0], "Bloom Blur V Pass"),
+            ];
+            for (pipeline, bind_group, view, label) in passes {
+                let mut render_pass = begin_fullscreen_pass(encoder, view, label);
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+        }
+
+        let mut render_pass = begin_fullscreen_pass(encoder, target, "Post Process Pass");
+        render_pass.set_pipeline(&self.composite_pipeline);
+        render_pass.set_bind_group(0, &targets.composite_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn create_targets(&self, device: &wgpu::Device, source: &wgpu::Texture) -> BloomTargets {
+        let (width, height) = bloom_size(source.width(), source.height());
+        let views = ["Bloom Texture A", "Bloom Texture B"].map(|label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: HDR_FORMAT,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+        let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bloom_group = |input: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bloom Bind Group"),
+                layout: &self.bloom_layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: self.uniform_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(input) },
+                    wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+                ],
+            })
+        };
+        let bright_group = bloom_group(&source_view);
+        let blur_groups = [bloom_group(&views[0]), bloom_group(&views[1])];
+
+        let composite_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Process Bind Group"),
+            layout: &self.composite_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: self.uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&source_view) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&views[0]) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+            ],
+        });
+
+        BloomTargets {
+            source_id: source.global_id(),
+            views,
+            bright_group,
+            blur_groups,
+            composite_group,
+        }
+    }
+}
+
+/// Bloom works at half resolution (never below one texel)
+fn bloom_size(width: u32, height: u32) -> (u32, u32) {
+    ((width / 2).max(1), (height / 2).max(1))
+}
+
+/// Pass whose single color attachment is fully overwritten by a fullscreen triangle
+fn begin_fullscreen_pass<'a>(encoder: &'a mut wgpu::CommandEncoder, view: &'a wgpu::TextureView, label: &'a str) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), // Every pixel is overwritten
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_uniform_layout() {
+        // Must match the WGSL structs in post_process.wgsl and bloom.wgsl
+        assert_eq!(std::mem::size_of::<PostUniforms>(), 16);
+    }
+
+    #[test]
+    fn test_bloom_size() {
+        assert_eq!(bloom_size(1920, 1080), (960, 540));
+        assert_eq!(bloom_size(1, 3), (1, 1));
+    }
+}
@@ -0,0 +1,213 @@
+use glam::{Mat4, Vec2, Vec3};
+use crate::{RenderOrder, TransparentQueue, WindParams};
+
+/// Drops drawn at full intensity (a heavy storm)
+pub const MAX_RAIN_DROPS: u32 = 12_000;
+
+/// Horizontal drift of a drop per unit of `WindParams::strength` (m/s)
+const WIND_DRIFT: f32 = 12.0;
+
+/// Width of the rain box around the camera; must match `BOX_SIZE` in rain.wgsl
+const BOX_SIZE: f32 = 40.0;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct RainUniforms {
+    view_proj: [[f32; 4]; 4],
+    camera_pos: [f32; 3],
+    time: f32,
+    wind_velocity: [f32; 2],
+    drift: [f32; 2],
+    light: [f32; 3],
+    intensity: f32,
+}
+
+/// Rain streaks falling in a box around the camera
+/// Each instance is one drop whose position is hashed from its index and wrapped into the
+/// box in the shader, so drops that fall out the bottom reappear at the top and the box
+/// follows the camera without any CPU simulation.
+pub struct RainPipeline {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    intensity: f32,
+    wind: WindParams,
+    /// Wind drift accumulated over time (wrapped to the box), so wind changes don't jump the drops
+    drift: Vec2,
+    last_time: Option<f32>,
+    drops: u32,
+}
+
+impl RainPipeline {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Rain Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../../assets/shaders/rain.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Rain Uniform Buffer"),
+            size: std::mem::size_of::<RainUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Rain Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Rain Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Rain Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Rain Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[], // Quad corners and drop positions come from the vertex/instance index
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: RenderOrder::Transparent.blend(),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None, // Streaks are seen from both sides
+                ..Default::default()
+            },
+            // Hidden behind terrain, but transparent: test against the scene without writing
+            depth_stencil: Some(RenderOrder::Transparent.depth_stencil()),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            intensity: 0.0,
+            wind: WindParams::default(),
+            drift: Vec2::ZERO,
+            last_time: None,
+            drops: 0,
+        }
+    }
+
+    /// Set how hard it rains: intensity 0 (dry) .. 1 (downpour) scales the number of drops,
+    /// and the wind slants the streaks and drifts the drops the way it blows
+    pub fn set_weather(&mut self, intensity: f32, wind: WindParams) {
+        self.intensity = intensity.clamp(0.0, 1.0);
+        self.wind = wind;
+    }
+
+    /// Update camera and time for this frame
+    /// light: sky ambient (color * intensity), so the rain darkens with the scene at night
+    pub fn update(&mut self, queue: &wgpu::Queue, view_proj: &Mat4, camera_pos: Vec3, time: f32, light: Vec3) {
+        let dt = time - self.last_time.unwrap_or(time);
+        self.last_time = Some(time);
+        let velocity = wind_velocity(&self.wind);
+        self.drift = (self.drift + velocity * dt).rem_euclid(Vec2::splat(BOX_SIZE));
+
+        self.drops = drop_count(self.intensity);
+        if self.drops == 0 {
+            return;
+        }
+
+        let uniforms = RainUniforms {
+            view_proj: view_proj.to_cols_array_2d(),
+            camera_pos: camera_pos.to_array(),
+            time,
+            wind_velocity: velocity.to_array(),
+            drift: self.drift.to_array(),
+            light: light.to_array(),
+            intensity: self.intensity,
+        };
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Draw the rain (nothing when dry); expects the scene depth bound read-only
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.drops == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..6, 0..self.drops); // One streak quad per drop
+    }
+
+    /// Queue the rain for the transparent pass: it surrounds the camera, so it goes over
+    /// everything else there
+    pub fn queue<'a>(&'a self, queue: &mut TransparentQueue<'a>) {
+        if self.drops > 0 {
+            queue.push(0.0, move |render_pass| self.render(render_pass));
+        }
+    }
+}
+
+/// Number of drops for an intensity in 0..1
+fn drop_count(intensity: f32) -> u32 {
+    (intensity.clamp(0.0, 1.0) * MAX_RAIN_DROPS as f32) as u32
+}
+
+/// Horizontal drop velocity (world XZ) for the wind
+fn wind_velocity(wind: &WindParams) -> Vec2 {
+    wind.direction.normalize_or_zero() * wind.strength * WIND_DRIFT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rain_uniform_layout() {
+        // WGSL: mat4 (64) + vec3/f32 (16) + vec2/vec2 (16) + vec3/f32 (16)
+        assert_eq!(std::mem::size_of::<RainUniforms>(), 112);
+    }
+
+    #[test]
+    fn test_drops_and_slant_follow_weather() {
+        assert_eq!(drop_count(0.0), 0);
+        assert_eq!(drop_count(-1.0), 0);
+        assert_eq!(drop_count(1.0), MAX_RAIN_DROPS);
+        assert_eq!(drop_count(2.0), MAX_RAIN_DROPS);
+        assert!(drop_count(0.5) < MAX_RAIN_DROPS);
+
+        let storm = WindParams { direction: Vec2::new(0.0, 2.0), strength: 0.45, frequency: 3.0 };
+        let velocity = wind_velocity(&storm);
+        assert_eq!(velocity.x, 0.0);
+        assert!(velocity.y > wind_velocity(&WindParams::default()).length());
+        assert_eq!(wind_velocity(&WindParams { direction: Vec2::ZERO, ..storm }), Vec2::ZERO);
+    }
+}
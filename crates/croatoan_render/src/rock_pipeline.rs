@@ -0,0 +1,323 @@
+use wgpu::util::DeviceExt;
+use glam::{Mat4, Vec3};
+use std::sync::Arc;
+use crate::shadows::InstancedMesh;
+use crate::TerrainLighting;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RockVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct RockInstanceRaw {
+    model: [[f32; 4]; 4],
+}
+
+/// Shared GPU mesh for one rock type, with its stone color
+pub struct RockMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    pub color: [f32; 3],
+}
+
+pub struct RockPipeline {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    mesh: Option<Arc<RockMesh>>,
+    instance_buffer: Option<wgpu::Buffer>,
+    instance_count: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],   // 64 bytes (0-64)
+    light_dir: [f32; 3],        // 12 bytes (64-76)
+    _padding: f32,              // 4 bytes (76-80)
+    view_pos: [f32; 3],         // 12 bytes (80-92)
+    _padding2: f32,             // 4 bytes (92-96)
+    fog_color: [f32; 3],        // 12 bytes (96-108)
+    fog_start: f32,             // 4 bytes (108-112)
+    base_color: [f32; 3],       // 12 bytes (112-124)
+    fog_end: f32,               // 4 bytes (124-128)
+    ambient_color: [f32; 3],    // 12 bytes (128-140)
+    ambient_intensity: f32,     // 4 bytes (140-144)
+    sun_color: [f32; 3],        // 12 bytes (144-156)
+    _padding3: f32,             // 4 bytes (156-160) -> Total 160 bytes
+}
+
+impl RockPipeline {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("../../../assets/shaders/rock.wgsl"));
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rock Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[Uniforms {
+                view_proj: Mat4::IDENTITY.to_cols_array_2d(),
+                light_dir: [0.5, 1.0, 0.3],
+                _padding: 0.0,
+                view_pos: [0.0; 3],
+                _padding2: 0.0,
+                fog_color: [0.5, 0.6, 0.7],
+                fog_start: 100.0,
+                base_color: [0.5; 3],
+                fog_end: 500.0,
+                ambient_color: [0.12, 0.14, 0.18],
+                ambient_intensity: 1.0,
+                sun_color: [1.0; 3],
+                _padding3: 0.0,
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Rock Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("Rock Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Rock Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Rock Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    // Vertex Buffer
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<RockVertex>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[
+                            wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x3, offset: 0, shader_location: 0 }, // Pos
+                            wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x3, offset: 12, shader_location: 1 }, // Normal
+                            wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x2, offset: 24, shader_location: 2 }, // UV
+                        ],
+                    },
+                    // Instance Buffer
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<RockInstanceRaw>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &[
+                            wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x4, offset: 0, shader_location: 5 },
+                            wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x4, offset: 16, shader_location: 6 },
+                            wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x4, offset: 32, shader_location: 7 },
+                            wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x4, offset: 48, shader_location: 8 },
+                        ],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            uniform_buffer,
+            mesh: None,
+            instance_buffer: None,
+            instance_count: 0,
+        }
+    }
+
+    pub fn create_mesh(
+        device: &wgpu::Device,
+        vertices: &[RockVertex],
+        indices: &[u32],
+        color: [f32; 3],
+    ) -> Arc<RockMesh> {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rock Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rock Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Arc::new(RockMesh {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            color,
+        })
+    }
+
+    pub fn set_mesh(&mut self, mesh: Arc<RockMesh>) {
+        self.mesh = Some(mesh);
+    }
+
+    pub fn upload_instances(&mut self, device: &wgpu::Device, instances: &[Mat4]) {
+        let raw_data: Vec<RockInstanceRaw> = instances.iter().map(|m| RockInstanceRaw {
+            model: m.to_cols_array_2d(),
+        }).collect();
+
+        self.instance_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rock Instance Buffer"),
+            contents: bytemuck::cast_slice(&raw_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        }));
+        self.instance_count = instances.len() as u32;
+    }
+
+    /// GPU memory held by the instance and uniform buffers (bytes); the mesh isn't counted, as
+    /// it's shared
+    pub fn buffer_bytes(&self) -> u64 {
+        self.uniform_buffer.size() + self.instance_buffer.as_ref().map_or(0, |buffer| buffer.size())
+    }
+
+    pub fn update_uniforms(
+        &self,
+        queue: &wgpu::Queue,
+        view_proj: &Mat4,
+        view_pos: Vec3,
+        lighting: &TerrainLighting,
+    ) {
+        let uniforms = Uniforms {
+            view_proj: view_proj.to_cols_array_2d(),
+            light_dir: lighting.sun_dir,
+            _padding: 0.0,
+            view_pos: view_pos.to_array(),
+            _padding2: 0.0,
+            fog_color: lighting.fog_color,
+            fog_start: lighting.fog_start,
+            base_color: self.mesh.as_ref().map_or([0.5; 3], |mesh| mesh.color),
+            fog_end: lighting.fog_end,
+            ambient_color: lighting.ambient_color,
+            ambient_intensity: lighting.ambient_intensity,
+            sun_color: lighting.sun_color,
+            _padding3: 0.0,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    pub fn render<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if let (Some(mesh), Some(instance_buffer)) = (&self.mesh, &self.instance_buffer) {
+            if self.instance_count > 0 {
+                rpass.set_pipeline(&self.pipeline);
+                rpass.set_bind_group(0, &self.bind_group, &[]);
+                rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                rpass.set_vertex_buffer(1, instance_buffer.slice(..));
+                rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                rpass.draw_indexed(0..mesh.index_count, 0, 0..self.instance_count);
+            }
+        }
+    }
+
+    /// Write the instances' depth in a depth prepass
+    pub fn render_depth<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, prepass: &'a crate::depth_prepass::DepthPrepass) {
+        if let (Some(mesh), Some(instance_buffer)) = (&self.mesh, &self.instance_buffer) {
+            prepass.render_with_stride(
+                rpass,
+                InstancedMesh {
+                    vertex_buffer: &mesh.vertex_buffer,
+                    index_buffer: &mesh.index_buffer,
+                    index_count: mesh.index_count,
+                    instance_buffer,
+                    instance_count: self.instance_count,
+                },
+                (
+                    std::mem::size_of::<RockVertex>() as wgpu::BufferAddress,
+                    std::mem::size_of::<RockInstanceRaw>() as wgpu::BufferAddress,
+                ),
+            );
+        }
+    }
+
+    /// Draw the instances into the shadow map (depth only)
+    pub fn render_shadow<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, shadow_pipeline: &'a crate::shadows::ShadowPipeline) {
+        if let (Some(mesh), Some(instance_buffer)) = (&self.mesh, &self.instance_buffer) {
+            shadow_pipeline.render_with_stride(
+                rpass,
+                InstancedMesh {
+                    vertex_buffer: &mesh.vertex_buffer,
+                    index_buffer: &mesh.index_buffer,
+                    index_count: mesh.index_count,
+                    instance_buffer,
+                    instance_count: self.instance_count,
+                },
+                (
+                    std::mem::size_of::<RockVertex>() as wgpu::BufferAddress,
+                    std::mem::size_of::<RockInstanceRaw>() as wgpu::BufferAddress,
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_layout() {
+        // Must match the Uniforms struct in rock.wgsl
+        assert_eq!(std::mem::size_of::<Uniforms>(), 160);
+        assert_eq!(std::mem::size_of::<RockVertex>(), 32);
+    }
+}
@@ -1,188 +1,586 @@
-use glam::Mat4;
-
-pub struct ShadowMap {
-    pub texture: wgpu::Texture,
-    pub view: wgpu::TextureView,
-    pub sampler: wgpu::Sampler,
-    pub size: u32,
-}
-
-impl ShadowMap {
-    pub fn new(device: &wgpu::Device, size: u32) -> Self {
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Shadow Map"),
-            size: wgpu::Extent3d {
-                width: size,
-                height: size,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Shadow Sampler"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            compare: Some(wgpu::CompareFunction::LessEqual),
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 100.0,
-            ..Default::default()
-        });
-
-        Self {
-            texture,
-            view,
-            sampler,
-            size,
-        }
-    }
-}
-
-#[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct ShadowUniforms {
-    view_proj: [[f32; 4]; 4],
-}
-
-pub struct ShadowPipeline {
-    render_pipeline: wgpu::RenderPipeline,
-    uniform_buffer: wgpu::Buffer,
-    bind_group: wgpu::BindGroup,
-}
-
-impl ShadowPipeline {
-    pub fn new(device: &wgpu::Device) -> Self {
-        // Shadow Shader (Vertex only)
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shadow Shader"),
-            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(r#"
-                struct Uniforms {
-                    view_proj: mat4x4<f32>,
-                }
-                @group(0) @binding(0) var<uniform> uniforms: Uniforms;
-
-                struct VertexInput {
-                    @location(0) position: vec3<f32>,
-                }
-
-                @vertex
-                fn vs_main(input: VertexInput) -> @builtin(position) vec4<f32> {
-                    return uniforms.view_proj * vec4<f32>(input.position, 1.0);
-                }
-            "#)),
-        });
-
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Shadow Uniform Buffer"),
-            size: std::mem::size_of::<ShadowUniforms>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Shadow Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Shadow Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Shadow Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Shadow Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[
-                    // Position only (stride 36 because we reuse the main vertex buffer which has pos+color+normal)
-                    wgpu::VertexBufferLayout {
-                        array_stride: 36,  // Match terrain buffer: 3 floats pos + 3 floats color + 3 floats normal
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[
-                            wgpu::VertexAttribute {
-                                offset: 0,
-                                shader_location: 0,
-                                format: wgpu::VertexFormat::Float32x3,
-                            },
-                        ],
-                    },
-                ],
-            },
-            fragment: None, // Depth-only
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                cull_mode: Some(wgpu::Face::Front), // Cull front faces for shadows to prevent peter-panning
-                ..Default::default()
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState {
-                    constant: 4,        // Lower constant bias
-                    slope_scale: 2.5,   // Higher slope scale for angled surfaces
-                    clamp: 0.0,
-                },
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
-
-        Self {
-            render_pipeline,
-            uniform_buffer,
-            bind_group,
-        }
-    }
-
-    pub fn update_uniforms(&self, queue: &wgpu::Queue, view_proj: &Mat4) {
-        let uniforms = ShadowUniforms {
-            view_proj: view_proj.to_cols_array_2d(),
-        };
-        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
-    }
-
-    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, vertex_buffer: &'a wgpu::Buffer, index_buffer: &'a wgpu::Buffer, index_count: u32) {
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.bind_group, &[]);
-        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..index_count, 0, 0..1);
-    }
-
-    // Note: This pipeline works with both terrain (stride 36) and grass (stride 24)
-    // because it only reads position at offset 0, regardless of what comes after
-}
+use glam::{Mat4, Vec3};
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+use crate::camera::Camera;
+
+/// Number of shadow cascades (layers of the shadow map array)
+pub const CASCADE_COUNT: usize = 3;
+
+/// Default far distance of each cascade (view-space depth, world units)
+const DEFAULT_CASCADE_SPLITS: [f32; CASCADE_COUNT] = [40.0, 150.0, 600.0];
+
+/// Extra depth behind each cascade toward the light, so tall casters outside the slice still shadow it
+const CASTER_MARGIN: f32 = 300.0;
+
+/// Position-only vertex stages (depth-only passes): `vs_main` for world-space vertices,
+/// `vs_instanced` for meshes placed by a per-instance model matrix
+pub(crate) const POSITION_ONLY_SHADER: &str = r#"
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+}
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> @builtin(position) vec4<f32> {
+    return uniforms.view_proj * vec4<f32>(input.position, 1.0);
+}
+
+struct InstanceInput {
+    @location(1) model_0: vec4<f32>,
+    @location(2) model_1: vec4<f32>,
+    @location(3) model_2: vec4<f32>,
+    @location(4) model_3: vec4<f32>,
+}
+
+@vertex
+fn vs_instanced(input: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    // Same order as the scene shaders, so a depth prepass matches them exactly
+    return uniforms.view_proj * (model * vec4<f32>(input.position, 1.0));
+}
+"#;
+
+/// Shadow edge filtering (percentage-closer filtering kernel)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FilterQuality {
+    /// Single comparison tap
+    #[default]
+    Hard,
+    Pcf3x3,
+    Pcf5x5,
+}
+
+impl FilterQuality {
+    /// Taps either side of the center texel
+    pub fn kernel_radius(self) -> i32 {
+        match self {
+            FilterQuality::Hard => 0,
+            FilterQuality::Pcf3x3 => 1,
+            FilterQuality::Pcf5x5 => 2,
+        }
+    }
+}
+
+/// Filter settings read by the terrain/grass shaders (binding 3 next to the shadow map)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowParams {
+    texel_size: f32,    // 4 bytes (0-4): one shadow map texel in UV units
+    kernel_radius: i32, // 4 bytes (4-8)
+    _padding: [f32; 2], // 8 bytes (8-16) -> Total 16 bytes
+}
+
+impl ShadowParams {
+    fn new(size: u32, filter: FilterQuality) -> Self {
+        Self {
+            texel_size: 1.0 / size as f32,
+            kernel_radius: filter.kernel_radius(),
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+/// Shadow map texture array, one layer per cascade
+pub struct ShadowMap {
+    pub texture: wgpu::Texture,
+    /// Array view of all cascades (for sampling)
+    pub view: wgpu::TextureView,
+    /// Single-layer views (render targets for each cascade's pass)
+    pub cascade_views: Vec<wgpu::TextureView>,
+    pub sampler: wgpu::Sampler,
+    /// Filter settings uniform, bound by pipelines that sample the map
+    pub params_buffer: wgpu::Buffer,
+    pub size: u32,
+    filter: FilterQuality,
+}
+
+impl ShadowMap {
+    pub fn new(device: &wgpu::Device, size: u32) -> Self {
+        Self::with_filter(device, size, FilterQuality::Hard)
+    }
+
+    /// Shadow map with soft (PCF) edges; the kernel spacing is one texel of `size`
+    pub fn with_filter(device: &wgpu::Device, size: u32, filter: FilterQuality) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: CASCADE_COUNT as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Shadow Map Array View"),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let cascade_views = (0..CASCADE_COUNT as u32)
+            .map(|layer| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Shadow Cascade View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 100.0,
+            ..Default::default()
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow Params Buffer"),
+            contents: bytemuck::cast_slice(&[ShadowParams::new(size, filter)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            texture,
+            view,
+            cascade_views,
+            sampler,
+            params_buffer,
+            size,
+            filter,
+        }
+    }
+
+    pub fn filter(&self) -> FilterQuality {
+        self.filter
+    }
+
+    /// Change the PCF kernel at runtime (takes effect on the next draw)
+    pub fn set_filter(&mut self, queue: &wgpu::Queue, filter: FilterQuality) {
+        self.filter = filter;
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[ShadowParams::new(self.size, filter)]));
+    }
+}
+
+/// Light matrices for one frame, one per cascade
+#[derive(Copy, Clone, Debug)]
+pub struct ShadowCascades {
+    pub view_projs: [Mat4; CASCADE_COUNT],
+    /// Far view-space depth of each cascade; shaders pick the first cascade past the fragment's depth
+    pub splits: [f32; CASCADE_COUNT],
+    /// Bounding sphere (center, radius) each cascade covers, for caster culling
+    pub bounds: [(Vec3, f32); CASCADE_COUNT],
+}
+
+impl ShadowCascades {
+    /// Matrices in uniform layout
+    pub fn matrices(&self) -> [[[f32; 4]; 4]; CASCADE_COUNT] {
+        self.view_projs.map(|m| m.to_cols_array_2d())
+    }
+
+    /// Splits padded to a vec4 for uniforms
+    pub fn splits_vec4(&self) -> [f32; 4] {
+        let mut splits = [0.0; 4];
+        splits[..CASCADE_COUNT].copy_from_slice(&self.splits);
+        splits
+    }
+}
+
+/// Fit a light-space ortho projection around the camera frustum slice [near, far]
+/// The result is snapped to the shadow map texel grid so shadows don't shimmer as the camera moves
+fn fit_cascade(camera: &Camera, light_dir: Vec3, near: f32, far: f32, map_size: u32) -> (Mat4, Vec3, f32) {
+    let forward = (camera.target - camera.position).normalize();
+    let right = forward.cross(camera.up).normalize();
+    let up = right.cross(forward);
+
+    let mut corners = [Vec3::ZERO; 8];
+    for (i, depth) in [near, far].into_iter().enumerate() {
+        let (half_width, half_height) = camera.half_extents_at(depth);
+        let center = camera.position + forward * depth;
+        corners[i * 4] = center + right * half_width + up * half_height;
+        corners[i * 4 + 1] = center - right * half_width + up * half_height;
+        corners[i * 4 + 2] = center + right * half_width - up * half_height;
+        corners[i * 4 + 3] = center - right * half_width - up * half_height;
+    }
+
+    // Bounding sphere: size doesn't change as the camera rotates, so texels stay the same size
+    let center = corners.iter().copied().sum::<Vec3>() / 8.0;
+    let radius = corners.iter().map(|c| c.distance(center)).fold(0.0, f32::max);
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let light_dir = light_dir.normalize();
+    let light_up = if light_dir.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let light_pos = center - light_dir * (radius + CASTER_MARGIN);
+    let light_view = Mat4::look_at_rh(light_pos, center, light_up);
+    let light_proj = Mat4::orthographic_rh(-radius, radius, -radius, radius, 0.0, radius * 2.0 + CASTER_MARGIN);
+    let view_proj = light_proj * light_view;
+
+    // Snap the world origin to a texel (NDC spans 2.0 across the map)
+    let texel = 2.0 / map_size as f32;
+    let origin = view_proj.transform_point3(Vec3::ZERO);
+    let snapped = Vec3::new((origin.x / texel).round() * texel, (origin.y / texel).round() * texel, origin.z);
+    let view_proj = Mat4::from_translation(snapped - origin) * view_proj;
+
+    (view_proj, center, radius)
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowUniforms {
+    view_proj: [[f32; 4]; 4],
+}
+
+/// An instanced mesh for the depth-only passes: its buffers and the first `instance_count`
+/// instances to draw
+#[derive(Copy, Clone)]
+pub struct InstancedMesh<'a> {
+    pub vertex_buffer: &'a wgpu::Buffer,
+    pub index_buffer: &'a wgpu::Buffer,
+    pub index_count: u32,
+    pub instance_buffer: &'a wgpu::Buffer,
+    pub instance_count: u32,
+}
+
+impl<'a> InstancedMesh<'a> {
+    /// Draw with a position-only pipeline laid out for this mesh (nothing when there are no instances)
+    pub(crate) fn draw(&self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a wgpu::RenderPipeline) {
+        if self.instance_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..self.instance_count);
+    }
+}
+
+pub struct ShadowPipeline {
+    render_pipeline: wgpu::RenderPipeline,
+    /// Instanced variants keyed by (vertex stride, instance stride) (trees, rocks, buildings)
+    instanced_pipelines: HashMap<(wgpu::BufferAddress, wgpu::BufferAddress), wgpu::RenderPipeline>,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    /// One light matrix buffer + bind group per cascade
+    uniform_buffers: Vec<wgpu::Buffer>,
+    bind_groups: Vec<wgpu::BindGroup>,
+    cascade_splits: [f32; CASCADE_COUNT],
+}
+
+impl ShadowPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        // Shadow Shader (Vertex only)
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(POSITION_ONLY_SHADER)),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let uniform_buffers: Vec<wgpu::Buffer> = (0..CASCADE_COUNT)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Shadow Uniform Buffer"),
+                    size: std::mem::size_of::<ShadowUniforms>() as u64,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        let bind_groups = uniform_buffers
+            .iter()
+            .map(|uniform_buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Shadow Bind Group"),
+                    layout: &bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    }],
+                })
+            })
+            .collect();
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    // Position only, read from the terrain vertex buffer (which also holds color, normal, splat)
+                    wgpu::VertexBufferLayout {
+                        array_stride: crate::terrain_pipeline::VERTEX_STRIDE, // Match the terrain buffer (position first)
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[
+                            wgpu::VertexAttribute {
+                                offset: 0,
+                                shader_location: 0,
+                                format: wgpu::VertexFormat::Float32x3,
+                            },
+                        ],
+                    },
+                ],
+            },
+            fragment: None, // Depth-only
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: Some(wgpu::Face::Front), // Cull front faces for shadows to prevent peter-panning
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less, // Standard depth: the light projection is orthographic
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: 4,        // Lower constant bias
+                    slope_scale: 2.5,   // Higher slope scale for angled surfaces
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let mut pipeline = Self {
+            render_pipeline,
+            instanced_pipelines: HashMap::new(),
+            shader,
+            pipeline_layout,
+            uniform_buffers,
+            bind_groups,
+            cascade_splits: DEFAULT_CASCADE_SPLITS,
+        };
+
+        // Built-in shadow casters
+        pipeline.prepare_stride(
+            device,
+            std::mem::size_of::<crate::tree_pipeline::TreeVertex>() as wgpu::BufferAddress,
+            std::mem::size_of::<crate::tree_pipeline::TreeInstanceRaw>() as wgpu::BufferAddress,
+        );
+        pipeline.prepare_stride(
+            device,
+            std::mem::size_of::<crate::building_pipeline::BuildingVertex>() as wgpu::BufferAddress,
+            std::mem::size_of::<crate::building_pipeline::InstanceRaw>() as wgpu::BufferAddress,
+        );
+        pipeline.prepare_stride(
+            device,
+            std::mem::size_of::<crate::rock_pipeline::RockVertex>() as wgpu::BufferAddress,
+            std::mem::size_of::<crate::rock_pipeline::RockInstanceRaw>() as wgpu::BufferAddress,
+        );
+        pipeline
+    }
+
+    /// Build the instanced variant for these vertex/instance strides
+    /// Vertices must start with the position; instances with a column-major model matrix.
+    pub fn prepare_stride(&mut self, device: &wgpu::Device, stride: wgpu::BufferAddress, instance_stride: wgpu::BufferAddress) {
+        if self.instanced_pipelines.contains_key(&(stride, instance_stride)) {
+            return;
+        }
+
+        let instance_attributes = wgpu::vertex_attr_array![
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+            4 => Float32x4,
+        ];
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Instanced Pipeline"),
+            layout: Some(&self.pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &self.shader,
+                entry_point: "vs_instanced",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: stride,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x3,
+                        }],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: instance_stride,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &instance_attributes,
+                    },
+                ],
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None, // Foliage cards and open meshes are single-sided
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: 4,
+                    slope_scale: 2.5,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        self.instanced_pipelines.insert((stride, instance_stride), pipeline);
+    }
+
+    /// Far view-space depth of each cascade
+    pub fn cascade_splits(&self) -> [f32; CASCADE_COUNT] {
+        self.cascade_splits
+    }
+
+    /// Tune cascade distances (increasing; the last one is the shadow draw distance)
+    pub fn set_cascade_splits(&mut self, splits: [f32; CASCADE_COUNT]) {
+        self.cascade_splits = splits;
+    }
+
+    /// Fit each cascade's light projection around its slice of the camera frustum
+    pub fn compute_cascades(&self, camera: &Camera, light_dir: Vec3, map_size: u32) -> ShadowCascades {
+        let mut view_projs = [Mat4::IDENTITY; CASCADE_COUNT];
+        let mut bounds = [(Vec3::ZERO, 0.0); CASCADE_COUNT];
+        let mut near = camera.near;
+        for (i, &far) in self.cascade_splits.iter().enumerate() {
+            let (view_proj, center, radius) = fit_cascade(camera, light_dir, near, far, map_size);
+            view_projs[i] = view_proj;
+            bounds[i] = (center, radius);
+            near = far;
+        }
+        ShadowCascades {
+            view_projs,
+            splits: self.cascade_splits,
+            bounds,
+        }
+    }
+
+    pub fn update_uniforms(&self, queue: &wgpu::Queue, cascades: &ShadowCascades) {
+        for (buffer, view_proj) in self.uniform_buffers.iter().zip(&cascades.view_projs) {
+            let uniforms = ShadowUniforms {
+                view_proj: view_proj.to_cols_array_2d(),
+            };
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[uniforms]));
+        }
+    }
+
+    /// Bind a cascade's light matrix; call at the start of that cascade's pass, before any draws
+    pub fn begin_cascade<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, cascade: usize) {
+        render_pass.set_bind_group(0, &self.bind_groups[cascade], &[]);
+    }
+
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, vertex_buffer: &'a wgpu::Buffer, index_buffer: &'a wgpu::Buffer, index_count: u32) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..index_count, 0, 0..1);
+    }
+
+    /// Draw instanced geometry (one model matrix per instance) into the shadow map
+    /// `strides` is (vertex stride, instance stride); pairs not registered via `prepare_stride` are skipped
+    pub fn render_with_stride<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        mesh: InstancedMesh<'a>,
+        strides: (wgpu::BufferAddress, wgpu::BufferAddress),
+    ) {
+        let Some(pipeline) = self.instanced_pipelines.get(&strides) else {
+            return;
+        };
+        mesh.draw(render_pass, pipeline);
+    }
+
+    // Note: `render` reads positions at the terrain vertex stride; other meshes go through
+    // `render_with_stride`
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_params() {
+        assert_eq!(std::mem::size_of::<ShadowParams>(), 16);
+
+        let params = ShadowParams::new(2048, FilterQuality::Pcf5x5);
+        assert_eq!(params.kernel_radius, 2);
+        assert_eq!(params.texel_size, 1.0 / 2048.0);
+        assert_eq!(ShadowParams::new(1024, FilterQuality::Hard).kernel_radius, 0);
+    }
+
+    #[test]
+    fn test_cascade_covers_its_slice() {
+        let camera = Camera::new(Vec3::new(10.0, 20.0, 5.0), Vec3::new(60.0, 10.0, 40.0), 16.0 / 9.0);
+        let light_dir = Vec3::new(-0.4, -0.8, -0.3);
+
+        let mut near = camera.near;
+        for far in DEFAULT_CASCADE_SPLITS {
+            let (view_proj, _, _) = fit_cascade(&camera, light_dir, near, far, 2048);
+
+            // Points inside the slice land inside the light's clip volume
+            let forward = (camera.target - camera.position).normalize();
+            for depth in [near, (near + far) * 0.5, far] {
+                let ndc = view_proj.project_point3(camera.position + forward * depth);
+                assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0, "cascade {}..{} misses depth {}", near, far, depth);
+                assert!((0.0..=1.0).contains(&ndc.z));
+            }
+            near = far;
+        }
+    }
+
+    #[test]
+    fn test_cascade_snaps_to_texels() {
+        let light_dir = Vec3::new(-0.4, -0.8, -0.3);
+        let mut camera = Camera::new(Vec3::new(0.0, 20.0, 0.0), Vec3::new(50.0, 10.0, 0.0), 1.0);
+        let (a, _, _) = fit_cascade(&camera, light_dir, 0.1, 40.0, 2048);
+        camera.position.x += 0.013;
+        camera.target.x += 0.013;
+        let (b, _, _) = fit_cascade(&camera, light_dir, 0.1, 40.0, 2048);
+
+        // Moving the camera shifts the projection by whole texels only
+        let texel = 2.0 / 2048.0;
+        let shift = (b.transform_point3(Vec3::ZERO) - a.transform_point3(Vec3::ZERO)) / texel;
+        assert!((shift.x - shift.x.round()).abs() < 1e-2);
+        assert!((shift.y - shift.y.round()).abs() < 1e-2);
+    }
+}
@@ -1,150 +1,202 @@
-use wgpu::util::DeviceExt;
-use glam::{Mat4, Vec3};
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct SkyUniforms {
-    view_proj: [f32; 16],
-    sun_dir: [f32; 3],
-    time: f32,
-    sun_color: [f32; 3],
-    cloud_coverage: f32,
-    cloud_color_base: [f32; 3],
-    cloud_density: f32,
-    cloud_color_shade: [f32; 3],
-    cloud_scale: f32,
-    wind_offset: [f32; 2],
-    _padding: [f32; 2],
-}
-
-pub struct SkyPipeline {
-    render_pipeline: wgpu::RenderPipeline,
-    uniform_buffer: wgpu::Buffer,
-    bind_group: wgpu::BindGroup,
-}
-
-impl SkyPipeline {
-    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
-        let shader = device.create_shader_module(wgpu::include_wgsl!("../../../assets/shaders/sky.wgsl"));
-
-        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Sky Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[SkyUniforms {
-                view_proj: Mat4::IDENTITY.to_cols_array(),
-                sun_dir: [0.0, 1.0, 0.0],
-                time: 0.0,
-                sun_color: [1.0, 1.0, 1.0],
-                cloud_coverage: 0.5,
-                cloud_color_base: [0.8, 0.4, 0.3], // Burnt Sienna-ish
-                cloud_density: 0.5,
-                cloud_color_shade: [0.9, 0.6, 0.6], // Pinkish
-                cloud_scale: 1.0,
-                wind_offset: [0.0, 0.0],
-                _padding: [0.0; 2],
-            }]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Sky Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Sky Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Sky Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Sky Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[], // No vertex buffers, we generate full screen quad in shader
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
-
-        Self {
-            render_pipeline,
-            uniform_buffer,
-            bind_group,
-        }
-    }
-
-    pub fn update_uniforms(
-        &self,
-        queue: &wgpu::Queue,
-        view_proj: Mat4,
-        sun_dir: Vec3,
-        sun_color: Vec3,
-        time: f32,
-        cloud_coverage: f32,
-        cloud_color_base: Vec3,
-        cloud_density: f32,
-        cloud_color_shade: Vec3,
-        cloud_scale: f32,
-        wind_offset: [f32; 2],
-    ) {
-        let uniforms = SkyUniforms {
-            view_proj: view_proj.to_cols_array(),
-            sun_dir: sun_dir.to_array(),
-            time,
-            sun_color: sun_color.to_array(),
-            cloud_coverage,
-            cloud_color_base: cloud_color_base.to_array(),
-            cloud_density,
-            cloud_color_shade: cloud_color_shade.to_array(),
-            cloud_scale,
-            wind_offset,
-            _padding: [0.0; 2],
-        };
-        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
-    }
-
-    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.bind_group, &[]);
-        render_pass.draw(0..3, 0..1); // Draw 3 vertices (full screen triangle)
-    }
-}
+use wgpu::util::DeviceExt;
+use glam::{Mat4, Vec3};
+use std::f32::consts::TAU;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkyUniforms {
+    view_proj: [f32; 16],
+    sun_dir: [f32; 3],
+    time: f32,
+    sun_color: [f32; 3],
+    cloud_coverage: f32,
+    cloud_color_base: [f32; 3],
+    cloud_density: f32,
+    cloud_color_shade: [f32; 3],
+    cloud_scale: f32,
+    wind_offset: [f32; 2],
+    star_rotation: f32,
+    star_intensity: f32,
+    inv_view_proj: [f32; 16], // Screen -> world, for the view ray the stars are looked up with
+    milky_way: f32,           // 1.0 draws the Milky Way band
+    _padding: [f32; 3],
+}
+
+/// Clouds and night sky for one frame
+#[derive(Copy, Clone, Debug)]
+pub struct SkyParams {
+    pub cloud_coverage: f32,
+    pub cloud_color_base: Vec3,
+    pub cloud_density: f32,
+    pub cloud_color_shade: Vec3,
+    pub cloud_scale: f32,
+    pub wind_offset: [f32; 2],
+    /// Hour (0-24) the star field is turned to
+    pub time_of_day: f32,
+    /// Star brightness at full night (0 hides them); they fade in as the sun sets
+    pub star_intensity: f32,
+    /// Draw the Milky Way band behind the stars
+    pub milky_way: bool,
+}
+
+pub struct SkyPipeline {
+    render_pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl SkyPipeline {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("../../../assets/shaders/sky.wgsl"));
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sky Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[SkyUniforms {
+                view_proj: Mat4::IDENTITY.to_cols_array(),
+                sun_dir: [0.0, 1.0, 0.0],
+                time: 0.0,
+                sun_color: [1.0, 1.0, 1.0],
+                cloud_coverage: 0.5,
+                cloud_color_base: [0.8, 0.4, 0.3], // Burnt Sienna-ish
+                cloud_density: 0.5,
+                cloud_color_shade: [0.9, 0.6, 0.6], // Pinkish
+                cloud_scale: 1.0,
+                wind_offset: [0.0, 0.0],
+                star_rotation: 0.0,
+                star_intensity: 1.0,
+                inv_view_proj: Mat4::IDENTITY.to_cols_array(),
+                milky_way: 1.0,
+                _padding: [0.0; 3],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sky Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sky Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sky Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[], // No vertex buffers, we generate full screen quad in shader
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            render_pipeline,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+
+    pub fn update_uniforms(
+        &self,
+        queue: &wgpu::Queue,
+        view_proj: Mat4,
+        sun_dir: Vec3,
+        sun_color: Vec3,
+        time: f32,
+        params: &SkyParams,
+    ) {
+        let uniforms = SkyUniforms {
+            view_proj: view_proj.to_cols_array(),
+            sun_dir: sun_dir.to_array(),
+            time,
+            sun_color: sun_color.to_array(),
+            cloud_coverage: params.cloud_coverage,
+            cloud_color_base: params.cloud_color_base.to_array(),
+            cloud_density: params.cloud_density,
+            cloud_color_shade: params.cloud_color_shade.to_array(),
+            cloud_scale: params.cloud_scale,
+            wind_offset: params.wind_offset,
+            star_rotation: star_rotation(params.time_of_day),
+            star_intensity: params.star_intensity.max(0.0),
+            inv_view_proj: view_proj.inverse().to_cols_array(),
+            milky_way: if params.milky_way { 1.0 } else { 0.0 },
+            _padding: [0.0; 3],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1); // Draw 3 vertices (full screen triangle)
+    }
+}
+
+/// Star field rotation (radians) at `time_of_day` (0-24 h): one turn a day, with the sun
+fn star_rotation(time_of_day: f32) -> f32 {
+    time_of_day.rem_euclid(24.0) / 24.0 * TAU
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_layout() {
+        // Must match the Uniforms struct in sky.wgsl
+        assert_eq!(std::mem::size_of::<SkyUniforms>(), 224);
+    }
+
+    #[test]
+    fn test_star_rotation_wraps_daily() {
+        assert_eq!(star_rotation(0.0), 0.0);
+        assert!((star_rotation(12.0) - TAU / 2.0).abs() < 1e-6);
+        assert!((star_rotation(30.0) - star_rotation(6.0)).abs() < 1e-6);
+        assert!((star_rotation(-6.0) - star_rotation(18.0)).abs() < 1e-6);
+    }
+}
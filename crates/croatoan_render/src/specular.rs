@@ -0,0 +1,40 @@
+/// Blinn-Phong highlight of a surface material (terrain, buildings)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Specular {
+    /// Highlight brightness relative to the key light (0 = purely diffuse)
+    pub strength: f32,
+    /// Blinn-Phong exponent: higher is a tighter, glossier highlight
+    pub shininess: f32,
+}
+
+impl Specular {
+    /// Rough, dry surfaces: a faint broad sheen
+    pub const MATTE: Self = Self { strength: 0.05, shininess: 8.0 };
+
+    pub const fn new(strength: f32, shininess: f32) -> Self {
+        Self { strength, shininess }
+    }
+
+    /// Uniform fields as the shaders take them (strength, shininess)
+    pub(crate) fn to_array(self) -> [f32; 2] {
+        [self.strength.max(0.0), self.shininess.max(1.0)]
+    }
+}
+
+impl Default for Specular {
+    fn default() -> Self {
+        Self::MATTE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_specular_uniform_is_clamped() {
+        assert_eq!(Specular::new(0.3, 32.0).to_array(), [0.3, 32.0]);
+        // pow(x, 0) lights every face: keep the exponent at least 1
+        assert_eq!(Specular::new(-1.0, 0.0).to_array(), [0.0, 1.0]);
+    }
+}
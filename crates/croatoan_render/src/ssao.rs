@@ -0,0 +1,269 @@
+use glam::Mat4;
+
+/// AO texture format (single channel, 1 = unoccluded)
+const AO_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SsaoUniforms {
+    proj: [[f32; 4]; 4],     // 64 bytes (0-64)
+    inv_proj: [[f32; 4]; 4], // 64 bytes (64-128)
+    radius: f32,             // 4 bytes (128-132)
+    strength: f32,           // 4 bytes (132-136)
+    _padding: [f32; 2],      // 8 bytes (136-144) -> Total 144 bytes
+}
+
+/// Screen-space ambient occlusion from the scene depth (normals are reconstructed from it)
+///
+/// Per frame: `set_depth_view` + `update`, then `render` after the opaque scene and before
+/// the water. The AO pass writes an occlusion texture; the composite pass blurs it and
+/// multiplies it into the scene color, darkening crevices and the bases of trees, rocks
+/// and buildings. Strength 0 skips both passes.
+pub struct SsaoPipeline {
+    ao_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    ao_layout: wgpu::BindGroupLayout,
+    composite_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    /// (bound depth view, AO view, AO pass group, composite group); rebuilt when depth is recreated
+    targets: Option<(wgpu::Id<wgpu::TextureView>, wgpu::TextureView, wgpu::BindGroup, wgpu::BindGroup)>,
+    radius: f32,
+    strength: f32,
+}
+
+impl SsaoPipeline {
+    /// `sample_count` is the scene's MSAA count (depth and color targets)
+    pub fn new(device: &wgpu::Device, scene_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        // The scene depth binding's type depends on MSAA (textureLoad takes a level or a sample index)
+        let mut source = include_str!("../../../assets/shaders/ssao.wgsl").to_string();
+        if sample_count > 1 {
+            source = source.replace("var scene_depth: texture_2d<f32>", "var scene_depth: texture_multisampled_2d<f32>");
+        }
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("SSAO Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("SSAO Uniform Buffer"),
+            size: std::mem::size_of::<SsaoUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniform_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let ao_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("SSAO Bind Group Layout"),
+            entries: &[
+                uniform_entry,
+                // Scene depth, bound as unfilterable float rather than Depth: GL can't textureLoad depth textures
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: sample_count > 1,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let composite_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("SSAO Composite Bind Group Layout"),
+            entries: &[
+                uniform_entry,
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let create_pipeline = |label: &str, layout: &wgpu::BindGroupLayout, entry_point: &str, target: wgpu::ColorTargetState, sample_count: u32| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[], // Fullscreen triangle from the vertex index
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[Some(target)],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+            })
+        };
+
+        let ao_pipeline = create_pipeline(
+            "SSAO Pipeline",
+            &ao_layout,
+            "fs_ao",
+            wgpu::ColorTargetState { format: AO_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL },
+            1,
+        );
+        // Multiply the scene by the occlusion: result = scene * ao
+        let multiply = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::Src,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let composite_pipeline = create_pipeline(
+            "SSAO Composite Pipeline",
+            &composite_layout,
+            "fs_composite",
+            wgpu::ColorTargetState {
+                format: scene_format,
+                blend: Some(wgpu::BlendState { color: multiply, alpha: multiply }),
+                write_mask: wgpu::ColorWrites::COLOR,
+            },
+            sample_count,
+        );
+
+        Self {
+            ao_pipeline,
+            composite_pipeline,
+            ao_layout,
+            composite_layout,
+            uniform_buffer,
+            targets: None,
+            radius: 1.5,
+            strength: 1.0,
+        }
+    }
+
+    /// Sampling radius (world units) and darkening strength (0 = off, 1 = full occlusion)
+    pub fn set_params(&mut self, radius: f32, strength: f32) {
+        self.radius = radius.max(0.01);
+        self.strength = strength.clamp(0.0, 2.0);
+    }
+
+    /// Bind the scene depth; rebuilds the AO target (at `width` x `height`) when it's a new view
+    pub fn set_depth_view(&mut self, device: &wgpu::Device, depth_view: &wgpu::TextureView, width: u32, height: u32) {
+        if self.targets.as_ref().is_some_and(|(id, ..)| *id == depth_view.global_id()) {
+            return;
+        }
+
+        let ao_view = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("SSAO Texture"),
+                size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: AO_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let ao_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("SSAO Bind Group"),
+            layout: &self.ao_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: self.uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(depth_view) },
+            ],
+        });
+        let composite_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("SSAO Composite Bind Group"),
+            layout: &self.composite_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: self.uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&ao_view) },
+            ],
+        });
+
+        self.targets = Some((depth_view.global_id(), ao_view, ao_group, composite_group));
+    }
+
+    /// Camera projection for reconstructing view-space positions from depth
+    pub fn update(&self, queue: &wgpu::Queue, projection: &Mat4) {
+        let uniforms = SsaoUniforms {
+            proj: projection.to_cols_array_2d(),
+            inv_proj: projection.inverse().to_cols_array_2d(),
+            radius: self.radius,
+            strength: self.strength,
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Compute the occlusion and darken `scene_view` (the scene color target) with it
+    /// Run after the opaque passes, while the scene depth isn't attached
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, scene_view: &wgpu::TextureView) {
+        let Some((_, ao_view, ao_group, composite_group)) = &self.targets else {
+            return;
+        };
+        if self.strength <= 0.0 {
+            return;
+        }
+
+        let passes = [
+            (ao_view, &self.ao_pipeline, ao_group, "SSAO Pass"),
+            (scene_view, &self.composite_pipeline, composite_group, "SSAO Composite Pass"),
+        ];
+        for (view, pipeline, bind_group, label) in passes {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load, // AO target is fully overwritten; the scene is multiplied
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_layout() {
+        // Must match the WGSL struct in ssao.wgsl
+        assert_eq!(std::mem::size_of::<SsaoUniforms>(), 144);
+    }
+}
@@ -22,7 +22,7 @@ pub struct SunPipeline {
 }
 
 impl SunPipeline {
-    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat, sample_count: u32) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Sun Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("../../../assets/shaders/sun.wgsl").into()),
@@ -87,7 +87,10 @@ impl SunPipeline {
             },
             // No depth test - sun is always in background (rendered first)
             depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
             multiview: None,
         });
 
@@ -1,48 +1,156 @@
+use std::sync::Arc;
 use wgpu::util::DeviceExt;
 use glam::Mat4;
+use crate::shadows::{ShadowCascades, CASCADE_COUNT};
+use crate::Specular;
 
 /// Uniform data structure matching WGSL layout
 /// Must match the shader struct exactly!
 #[repr(C)]
 #[derive(Copy, Clone)]
 struct Uniforms {
-    view_proj: [[f32; 4]; 4],       // 64 bytes (0-64)
-    light_view_proj: [[f32; 4]; 4], // 64 bytes (64-128)
-    fog_color: [f32; 3],            // 12 bytes (128-140)
-    time: f32,                      // 4 bytes (140-144)
-    fog_start: f32,                 // 4 bytes (144-148)
-    fog_end: f32,                   // 4 bytes (148-152)
-    _padding1: [f32; 2],            // 8 bytes (152-160)
-    sun_dir: [f32; 3],              // 12 bytes (160-172)
-    _padding2: f32,                 // 4 bytes (172-176)
-    view_pos: [f32; 3],             // 12 bytes (176-188)
-    _padding3: f32,                 // 4 bytes (188-192) -> Total 192 bytes
+    view_proj: [[f32; 4]; 4],                        // 64 bytes (0-64)
+    light_view_proj: [[[f32; 4]; 4]; CASCADE_COUNT], // 192 bytes (64-256)
+    cascade_splits: [f32; 4],                        // 16 bytes (256-272)
+    fog_color: [f32; 3],                             // 12 bytes (272-284)
+    time: f32,                                       // 4 bytes (284-288)
+    fog_start: f32,                                  // 4 bytes (288-292)
+    fog_end: f32,                                    // 4 bytes (292-296)
+    specular: [f32; 2],                              // 8 bytes (296-304) - land strength, shininess
+    sun_dir: [f32; 3],                               // 12 bytes (304-316)
+    _padding2: f32,                                  // 4 bytes (316-320)
+    view_pos: [f32; 3],                              // 12 bytes (320-332)
+    _padding3: f32,                                  // 4 bytes (332-336)
+    ambient_color: [f32; 3],                         // 12 bytes (336-348)
+    ambient_intensity: f32,                          // 4 bytes (348-352)
+    sun_color: [f32; 3],                             // 12 bytes (352-364)
+    rock_slope_cos: f32,                             // 4 bytes (364-368) -> Total 368 bytes
 }
 
 // SAFETY: Uniforms is repr(C) and contains only f32, which is Pod
 unsafe impl bytemuck::Pod for Uniforms {}
 unsafe impl bytemuck::Zeroable for Uniforms {}
 
+/// World-space size of one tile of the detail normal map (meters)
+pub const DETAIL_NORMAL_TILE: f32 = 4.0;
+
+/// World-space size of one tile of the splat textures (meters)
+pub const SPLAT_TILE: f32 = 8.0;
+
+/// Default `TerrainPipeline::set_rock_slope` (degrees)
+pub const DEFAULT_ROCK_SLOPE: f32 = 35.0;
+
+/// Bytes per terrain vertex: position, color, normal (3 floats each), splat weights (4 floats)
+pub const VERTEX_STRIDE: u64 = 52;
+
+/// Layout of the terrain splat group: sand, grass, rock and snow textures (bindings 0-3,
+/// filterable 2D) and their sampler (binding 4)
+pub fn splat_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Terrain Splat Bind Group Layout"),
+        entries: &[
+            texture(0),
+            texture(1),
+            texture(2),
+            texture(3),
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Splat group for `TerrainPipeline::new`: [sand, grass, rock, snow], sampled with repeat
+/// Sand and grass are detail over the biome colors, so trails and riverbeds keep theirs; rock and
+/// snow are sRGB colors (see `terrain_textures::material_pixels`). Alpha is how strongly each applies.
+pub fn create_splat_bind_group(device: &wgpu::Device, textures: [&wgpu::TextureView; 4]) -> wgpu::BindGroup {
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Terrain Splat Sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::Repeat,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    });
+    let [sand, grass, rock, snow] = textures;
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Terrain Splat Bind Group"),
+        layout: &splat_bind_group_layout(device),
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(sand) },
+            wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(grass) },
+            wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(rock) },
+            wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(snow) },
+            wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&sampler) },
+        ],
+    })
+}
+
+/// Fog and lighting for `TerrainPipeline::update_uniforms`, shared by every chunk in a pass
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TerrainLighting {
+    pub fog_color: [f32; 3],
+    pub fog_start: f32,
+    pub fog_end: f32,
+    /// The key light (the sun by day, the moon at night)
+    pub sun_dir: [f32; 3],
+    pub sun_color: [f32; 3],
+    pub ambient_color: [f32; 3],
+    pub ambient_intensity: f32,
+}
+
 /// Terrain rendering pipeline with vertex buffers
 pub struct TerrainPipeline {
     render_pipeline: wgpu::RenderPipeline,
+    /// Same pipeline drawing triangle edges; None without `Features::POLYGON_MODE_LINE`
+    wireframe_pipeline: Option<wgpu::RenderPipeline>,
     uniform_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
+    /// Tangent-space detail normals tiled over the ground (group 1); flat if none was given
+    detail_normal_bind_group: Arc<wgpu::BindGroup>,
+    /// Sand/grass/rock/snow textures (group 2); vertex colors alone if none were given
+    splat_bind_group: Arc<wgpu::BindGroup>,
     pub index_count: u32,
     pub vertex_buffer: wgpu::Buffer, // Made public for shadow pass
     pub index_buffer: wgpu::Buffer,  // Made public for shadow pass
+    specular: Specular, // Land highlight (water uses its own sun sparkle)
+    rock_slope: f32, // Degrees from flat where rock takes over
 }
 
 impl TerrainPipeline {
     /// Create a new terrain pipeline
+    /// `detail_normal`: a linear (non-sRGB) tangent-space normal map bound with
+    /// `asset_cache::texture_bind_group_layout`, tiled every `DETAIL_NORMAL_TILE` meters (e.g.
+    /// `terrain_textures::detail_normal_pixels`); None shades with the vertex normals alone.
+    /// `splat`: per-vertex texture weights (sand, grass, rock, snow), e.g. from
+    /// `croatoan_wfc::terrain_splat`. `splat_textures`: from `create_splat_bind_group`, rock
+    /// projected triplanar so cliffs don't stretch; None keeps the plain vertex colors.
     pub fn new(
         device: &wgpu::Device,
         surface_format: wgpu::TextureFormat,
+        sample_count: u32,
         positions: &[[f32; 3]],
         colors: &[[f32; 3]],
         normals: &[[f32; 3]],
+        splat: &[[f32; 4]],
         indices: &[u32],
         shadow_map: &crate::shadows::ShadowMap,
+        detail_normal: Option<Arc<wgpu::BindGroup>>,
+        splat_textures: Option<Arc<wgpu::BindGroup>>,
     ) -> Self {
         // Load shader
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -79,7 +187,7 @@ impl TerrainPipeline {
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Texture {
                         multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
+                        view_dimension: wgpu::TextureViewDimension::D2Array, // One layer per cascade
                         sample_type: wgpu::TextureSampleType::Depth,
                     },
                     count: None,
@@ -91,6 +199,17 @@ impl TerrainPipeline {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
                     count: None,
                 },
+                // Shadow filter params (PCF kernel)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -111,24 +230,47 @@ impl TerrainPipeline {
                     binding: 2,
                     resource: wgpu::BindingResource::Sampler(&shadow_map.sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: shadow_map.params_buffer.as_entire_binding(),
+                },
             ],
         });
 
+        // Group 1: detail normal map, or a flat one (straight up in tangent space)
+        let detail_normal_layout = crate::asset_cache::texture_bind_group_layout(device, "Terrain Detail Normal Bind Group Layout");
+        let blank = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Terrain Blank Texture"),
+            size: wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let blank = blank.create_view(&wgpu::TextureViewDescriptor::default());
+        let detail_normal_bind_group = detail_normal.unwrap_or_else(|| Arc::new(Self::flat_normal_bind_group(device, &detail_normal_layout, &blank)));
+
+        // Group 2: splat textures, or blank ones (wgpu zero-fills textures: alpha 0, no effect)
+        let splat_layout = splat_bind_group_layout(device);
+        let splat_bind_group = splat_textures.unwrap_or_else(|| Arc::new(create_splat_bind_group(device, [&blank; 4])));
+
         // Create vertex buffers
-        let (vertex_buffer, index_buffer) = Self::create_buffers(device, positions, colors, normals, indices);
+        let (vertex_buffer, index_buffer) = Self::create_buffers(device, positions, colors, normals, splat, indices);
         let index_count = indices.len() as u32;
 
         // Create pipeline layout
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Terrain Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+            bind_group_layouts: &[&bind_group_layout, &detail_normal_layout, &splat_layout],
             push_constant_ranges: &[],
         });
 
         // Define vertex buffer layout
-        // Stride: 36 bytes (3 floats position + 3 floats color + 3 floats normal)
+        // Stride: 52 bytes (3 floats position + 3 floats color + 3 floats normal + 4 floats splat)
         let vertex_buffer_layout = wgpu::VertexBufferLayout {
-            array_stride: 36,
+            array_stride: VERTEX_STRIDE,
             step_mode: wgpu::VertexStepMode::Vertex,
             attributes: &[
                 // Position (location 0)
@@ -149,17 +291,23 @@ impl TerrainPipeline {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                // Splat weights (location 3)
+                wgpu::VertexAttribute {
+                    offset: 36,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         };
 
-        // Create render pipeline
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Terrain Pipeline"),
+        // Create render pipeline (filled, or triangle edges for the wireframe debug view)
+        let create_pipeline = |label: &str, polygon_mode: wgpu::PolygonMode| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[vertex_buffer_layout],
+                buffers: std::slice::from_ref(&vertex_buffer_layout),
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
@@ -175,49 +323,81 @@ impl TerrainPipeline {
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
                 cull_mode: None, // Disable culling to debug visibility
-                polygon_mode: wgpu::PolygonMode::Fill,
+                polygon_mode,
                 unclipped_depth: false,
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: wgpu::TextureFormat::Depth32Float,
                 depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
+                depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
         });
+        let render_pipeline = create_pipeline("Terrain Pipeline", wgpu::PolygonMode::Fill);
+        let wireframe_pipeline = device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE)
+            .then(|| create_pipeline("Terrain Wireframe Pipeline", wgpu::PolygonMode::Line));
 
         Self {
             render_pipeline,
+            wireframe_pipeline,
             vertex_buffer,
             index_buffer,
             uniform_buffer,
             bind_group,
+            detail_normal_bind_group,
+            splat_bind_group,
             index_count,
+            specular: Specular::default(),
+            rock_slope: DEFAULT_ROCK_SLOPE,
         }
     }
 
+    /// Detail map that leaves the vertex normal as it is: `blank` is all zero, and zero alpha
+    /// means no detail
+    fn flat_normal_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, blank: &wgpu::TextureView) -> wgpu::BindGroup {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Terrain Flat Detail Normal Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(blank),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        })
+    }
+
     /// Create vertex and index buffers
     fn create_buffers(
         device: &wgpu::Device,
         positions: &[[f32; 3]],
         colors: &[[f32; 3]],
         normals: &[[f32; 3]],
+        splat: &[[f32; 4]],
         indices: &[u32],
     ) -> (wgpu::Buffer, wgpu::Buffer) {
-        // Interleave position, color, and normal data
-        let mut vertex_data = Vec::with_capacity(positions.len() * 9);
+        // Interleave position, color, normal and splat data
+        let mut vertex_data = Vec::with_capacity(positions.len() * 13);
         for i in 0..positions.len() {
             vertex_data.extend_from_slice(&positions[i]);
             vertex_data.extend_from_slice(&colors[i]);
             vertex_data.extend_from_slice(&normals[i]);
+            vertex_data.extend_from_slice(&splat[i]);
         }
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -235,30 +415,85 @@ impl TerrainPipeline {
         (vertex_buffer, index_buffer)
     }
 
-    /// Update uniform buffer with camera, time, fog, and light matrix
-    pub fn update_uniforms(&self, queue: &wgpu::Queue, view_proj: &Mat4, light_view_proj: &Mat4, time: f32, fog_color: [f32; 3], fog_start: f32, fog_end: f32, sun_dir: [f32; 3], view_pos: [f32; 3], camera_pos: [f32; 3]) {
+    /// Update uniform buffer with camera, time, fog, lighting, and shadow cascades
+    pub fn update_uniforms(&self, queue: &wgpu::Queue, view_proj: &Mat4, cascades: &ShadowCascades, time: f32, view_pos: [f32; 3], lighting: &TerrainLighting) {
         let uniforms = Uniforms {
             view_proj: view_proj.to_cols_array_2d(),
-            light_view_proj: light_view_proj.to_cols_array_2d(),
-            fog_color,
+            light_view_proj: cascades.matrices(),
+            cascade_splits: cascades.splits_vec4(),
+            fog_color: lighting.fog_color,
             time,
-            fog_start,
-            fog_end,
-            _padding1: [0.0; 2],
-            sun_dir,
+            fog_start: lighting.fog_start,
+            fog_end: lighting.fog_end,
+            specular: self.specular.to_array(),
+            sun_dir: lighting.sun_dir,
             _padding2: 0.0,
             view_pos,
             _padding3: 0.0,
+            ambient_color: lighting.ambient_color,
+            ambient_intensity: lighting.ambient_intensity,
+            sun_color: lighting.sun_color,
+            rock_slope_cos: self.rock_slope.to_radians().cos(),
         };
         queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
     }
 
+    /// Highlight for the land (sand, grass, rock); applied on the next `update_uniforms`
+    pub fn set_specular(&mut self, specular: Specular) {
+        self.specular = specular;
+    }
+
+    /// Replace the splat textures: [sand, grass, rock, snow] (see `create_splat_bind_group`)
+    /// To share one set between chunks, pass the same group to `new` instead.
+    pub fn set_splat_textures(&mut self, device: &wgpu::Device, textures: [&wgpu::TextureView; 4]) {
+        self.splat_bind_group = Arc::new(create_splat_bind_group(device, textures));
+    }
+
+    /// Slope (degrees from flat) past which the ground is drawn as rock; applied on the next
+    /// `update_uniforms`
+    pub fn set_rock_slope(&mut self, degrees: f32) {
+        self.rock_slope = degrees.clamp(0.0, 90.0);
+    }
+
+    /// GPU memory held by this chunk's terrain buffers (bytes); the shared textures aren't counted
+    pub fn buffer_bytes(&self) -> u64 {
+        self.vertex_buffer.size() + self.index_buffer.size() + self.uniform_buffer.size()
+    }
+
     /// Render the terrain
     pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        render_pass.set_pipeline(&self.render_pipeline);
+        self.render_with(render_pass, &self.render_pipeline);
+    }
+
+    /// Render the terrain's triangle edges (see `GraphicsContext::wireframe`); filled if the
+    /// device can't draw lines
+    pub fn render_wireframe<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        self.render_with(render_pass, self.wireframe_pipeline.as_ref().unwrap_or(&self.render_pipeline));
+    }
+
+    /// Write the terrain's depth in a depth prepass
+    pub fn render_depth<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, prepass: &'a crate::depth_prepass::DepthPrepass) {
+        prepass.render_terrain(render_pass, &self.vertex_buffer, &self.index_buffer, self.index_count);
+    }
+
+    fn render_with<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a wgpu::RenderPipeline) {
+        render_pass.set_pipeline(pipeline);
         render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_bind_group(1, &self.detail_normal_bind_group, &[]);
+        render_pass.set_bind_group(2, &self.splat_bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
         render_pass.draw_indexed(0..self.index_count, 0, 0..1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_layout() {
+        // Must match the WGSL struct in terrain.wgsl
+        assert_eq!(std::mem::size_of::<Uniforms>(), 368);
+    }
+}
@@ -0,0 +1,159 @@
+use glam::Vec3;
+
+/// Pixels per side of the built-in terrain textures
+pub const TERRAIN_TEXTURE_SIZE: u32 = 128;
+
+/// Surface a built-in terrain material texture depicts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainMaterial {
+    Sand,
+    Grass,
+    Rock,
+    Snow,
+}
+
+/// Built-in detail normal map: small tileable soil bumps, RGB = tangent-space normal * 0.5 + 0.5
+/// (x along world +x, y along world +z), alpha = full detail strength. Upload as linear.
+pub fn detail_normal_pixels() -> Vec<u8> {
+    let height = |x, y| tileable_noise(x, y, &[(8, 1.0), (16, 0.5), (32, 0.25)], 0);
+
+    // Bump height relative to a texel's width
+    let depth = 6.0;
+    image_pixels(|x, y| {
+        let dx = (height(x + 1, y) - height(x - 1, y)) * depth;
+        let dy = (height(x, y + 1) - height(x, y - 1)) * depth;
+        let normal = Vec3::new(-dx, -dy, 2.0).normalize();
+        normal.to_array().map(|c| ((c * 0.5 + 0.5) * 255.0).round() as u8)
+    })
+}
+
+/// Built-in material texture (sRGB, alpha = full strength), tileable
+///
+/// Rock and snow are their own colors, for ground whose vertex color is whatever biome it rises
+/// from. Grass and sand are near-gray detail multiplied into the biome color (x2, so linear 0.5
+/// changes nothing).
+pub fn material_pixels(material: TerrainMaterial) -> Vec<u8> {
+    match material {
+        TerrainMaterial::Rock => image_pixels(|x, y| {
+            // Lumpy stone with horizontal strata (v runs along world height on cliff faces)
+            let lumps = tileable_noise(x, y, &[(4, 1.0), (8, 0.5), (32, 0.25)], 1) / 1.75;
+            let strata = (y as f32 / TERRAIN_TEXTURE_SIZE as f32 * 12.0 * std::f32::consts::TAU + lumps * 4.0).sin();
+            let shade = 0.65 + lumps * 0.5 + strata * 0.06;
+            srgb(Vec3::new(0.36, 0.33, 0.30) * shade)
+        }),
+        TerrainMaterial::Grass => image_pixels(|x, y| {
+            // Blotchy tufts, slightly yellower where thin
+            let tufts = tileable_noise(x, y, &[(8, 0.5), (32, 1.0), (64, 0.5)], 2) / 2.0;
+            let shade = 0.75 + tufts * 0.5;
+            srgb(Vec3::new(0.5 * shade + (1.0 - tufts) * 0.04, 0.5 * shade, 0.5 * shade - (1.0 - tufts) * 0.04))
+        }),
+        TerrainMaterial::Sand => image_pixels(|x, y| {
+            // Fine grain over wind ripples
+            let grain = tileable_noise(x, y, &[(64, 1.0)], 3);
+            let wobble = tileable_noise(x, y, &[(4, 1.0)], 4);
+            let ripples = ((x as f32 / TERRAIN_TEXTURE_SIZE as f32 * 6.0 + wobble * 0.8) * std::f32::consts::TAU).sin();
+            let shade = 0.88 + ripples * 0.06 + grain * 0.18;
+            srgb(Vec3::splat(0.5 * shade))
+        }),
+        TerrainMaterial::Snow => image_pixels(|x, y| {
+            // Soft drifts, faintly blue in the hollows
+            let drifts = tileable_noise(x, y, &[(4, 1.0), (16, 0.4)], 5) / 1.4;
+            srgb(Vec3::new(0.80, 0.83, 0.88) + Vec3::new(0.1, 0.09, 0.06) * drifts)
+        }),
+    }
+}
+
+/// RGBA pixels of a `TERRAIN_TEXTURE_SIZE` square image, opaque, from each pixel's RGB
+fn image_pixels(pixel: impl Fn(i32, i32) -> [u8; 3]) -> Vec<u8> {
+    let size = TERRAIN_TEXTURE_SIZE as i32;
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            pixels.extend(pixel(x, y));
+            pixels.push(255);
+        }
+    }
+    pixels
+}
+
+/// Linear color to sRGB bytes
+fn srgb(color: Vec3) -> [u8; 3] {
+    color.to_array().map(|c| (c.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8)
+}
+
+/// Value noise at pixel (`px`, `py`) that wraps every `TERRAIN_TEXTURE_SIZE` pixels
+/// `octaves`: (lattice cells per tile, amplitude); the sum ranges 0..total amplitude
+fn tileable_noise(px: i32, py: i32, octaves: &[(i32, f32)], salt: u32) -> f32 {
+    let size = TERRAIN_TEXTURE_SIZE as i32;
+    let lattice = |x: i32, y: i32, cells: i32, octave: u32| {
+        let (x, y) = (x.rem_euclid(cells) as u32, y.rem_euclid(cells) as u32);
+        let mut h = x.wrapping_mul(0x8da6b343) ^ y.wrapping_mul(0xd8163841) ^ octave.wrapping_mul(0xcb1ab31f) ^ salt.wrapping_mul(0x27d4eb2d);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0x5bd1e995);
+        h ^= h >> 15;
+        (h & 0xffff) as f32 / 65535.0
+    };
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+
+    let mut total = 0.0;
+    for (octave, &(cells, amplitude)) in octaves.iter().enumerate() {
+        let fx = px.rem_euclid(size) as f32 / size as f32 * cells as f32;
+        let fy = py.rem_euclid(size) as f32 / size as f32 * cells as f32;
+        let (x0, y0) = (fx.floor() as i32, fy.floor() as i32);
+        let (tx, ty) = (smooth(fx.fract()), smooth(fy.fract()));
+        let value = |dx, dy| lattice(x0 + dx, y0 + dy, cells, octave as u32);
+        let top = value(0, 0) + (value(1, 0) - value(0, 0)) * tx;
+        let bottom = value(0, 1) + (value(1, 1) - value(0, 1)) * tx;
+        total += (top + (bottom - top) * ty) * amplitude;
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = TERRAIN_TEXTURE_SIZE as usize;
+
+    fn texel(pixels: &[u8], x: usize, y: usize) -> Vec3 {
+        let i = (y * SIZE + x) * 4;
+        Vec3::new(pixels[i] as f32, pixels[i + 1] as f32, pixels[i + 2] as f32) / 255.0
+    }
+
+    #[test]
+    fn test_detail_normals_tile_and_lean_gently() {
+        let pixels = detail_normal_pixels();
+        assert_eq!(pixels.len(), SIZE * SIZE * 4);
+        let normal = |x, y| texel(&pixels, x, y) * 2.0 - Vec3::ONE;
+
+        let mut min_up = 1.0_f32;
+        let mut max_tilt = 0.0_f32;
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                min_up = min_up.min(normal(x, y).z);
+                max_tilt = max_tilt.max(normal(x, y).x.abs());
+            }
+            // Wrapping across the edge is as smooth as stepping inside the tile
+            assert!(normal(0, y).distance(normal(SIZE - 1, y)) < 0.3);
+        }
+        assert!(min_up > 0.5, "too steep: {}", min_up);
+        assert!(max_tilt > 0.05, "no visible bumps");
+    }
+
+    #[test]
+    fn test_ground_detail_keeps_the_biome_color() {
+        for material in [TerrainMaterial::Grass, TerrainMaterial::Sand] {
+            let pixels = material_pixels(material);
+            // Mean linear value near 0.5, so `color * detail * 2` keeps the biome's brightness
+            let mean = (0..SIZE * SIZE)
+                .map(|i| texel(&pixels, i % SIZE, i / SIZE).powf(2.2))
+                .fold(Vec3::ZERO, |sum, c| sum + c) / (SIZE * SIZE) as f32;
+            assert!(mean.min_element() > 0.4 && mean.max_element() < 0.6, "{:?}: {:?}", material, mean);
+
+            // Tiles without a seam
+            for y in 0..SIZE {
+                assert!(texel(&pixels, 0, y).distance(texel(&pixels, SIZE - 1, y)) < 0.2);
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+/// Which scene pass a pipeline draws in, and so how it blends and uses depth
+///
+/// Opaque geometry goes in the main pass in any order, writing depth. Transparent geometry
+/// (water, rain, glass) is blended over the finished opaque scene afterwards through a
+/// `TransparentQueue`: far to near, depth-tested against the scene but never writing it, so
+/// overlapping layers don't hide each other or depend on draw order.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RenderOrder {
+    #[default]
+    Opaque,
+    Transparent,
+}
+
+impl RenderOrder {
+    /// Color blending for a pipeline in this pass
+    pub fn blend(self) -> Option<wgpu::BlendState> {
+        match self {
+            RenderOrder::Opaque => Some(wgpu::BlendState::REPLACE),
+            RenderOrder::Transparent => Some(wgpu::BlendState::ALPHA_BLENDING),
+        }
+    }
+
+    /// Depth state for a pipeline in this pass (reverse-Z, against the main depth buffer)
+    pub fn depth_stencil(self) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: wgpu::TextureFormat::Depth32Float,
+            depth_write_enabled: self == RenderOrder::Opaque,
+            depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+type Draw<'a> = Box<dyn FnOnce(&mut wgpu::RenderPass<'a>) + 'a>;
+
+/// Draws for the transparent pass, collected over the frame and rendered back to front
+///
+/// Each draw sets its own pipeline and bindings, since the sort interleaves draws from
+/// different pipelines.
+#[derive(Default)]
+pub struct TransparentQueue<'a> {
+    draws: Vec<(f32, Draw<'a>)>,
+}
+
+impl<'a> TransparentQueue<'a> {
+    pub fn new() -> Self {
+        Self { draws: Vec::new() }
+    }
+
+    /// Queue a draw of geometry whose center is `distance` from the camera
+    pub fn push(&mut self, distance: f32, draw: impl FnOnce(&mut wgpu::RenderPass<'a>) + 'a) {
+        self.draws.push((distance, Box::new(draw)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+
+    /// Draw everything queued, farthest first
+    pub fn render(mut self, render_pass: &mut wgpu::RenderPass<'a>) {
+        self.sort();
+        for (_, draw) in self.draws {
+            draw(render_pass);
+        }
+    }
+
+    /// Farthest first; equal distances keep the order they were queued in
+    fn sort(&mut self) {
+        self.draws.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_sorts_back_to_front() {
+        let mut queue = TransparentQueue::new();
+        for distance in [12.0, 250.0, 0.0, 80.0, 12.0] {
+            queue.push(distance, |_| {});
+        }
+        assert_eq!(queue.len(), 5);
+        queue.sort();
+        let order: Vec<f32> = queue.draws.iter().map(|(distance, _)| *distance).collect();
+        assert_eq!(order, [250.0, 80.0, 12.0, 12.0, 0.0]);
+    }
+
+    #[test]
+    fn test_transparent_pipelines_keep_depth_read_only() {
+        assert!(RenderOrder::Opaque.depth_stencil().depth_write_enabled);
+        assert!(!RenderOrder::Transparent.depth_stencil().depth_write_enabled);
+        assert_eq!(RenderOrder::Transparent.blend(), Some(wgpu::BlendState::ALPHA_BLENDING));
+    }
+}
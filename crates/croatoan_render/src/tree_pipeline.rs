@@ -1,12 +1,26 @@
 use wgpu::{Device, Queue, RenderPipeline, Buffer, BindGroupLayout, BindGroup};
 use wgpu::util::DeviceExt;
 use bytemuck::{Pod, Zeroable};
-use glam::Mat4;
+use glam::{Mat4, Vec3};
 use std::sync::Arc;
+use crate::frustum::Frustum;
+use crate::render_target::RenderTarget;
+use crate::shadows::InstancedMesh;
+use crate::wind::{sway_phase, WindParams, WindUniform};
+
+/// Camera movement (world units) before the visible instance set is rebuilt
+const CULL_MOVE_THRESHOLD: f32 = 2.0;
+/// Camera turn (sine of the angle, ~2.5 degrees) before the visible instance set is rebuilt
+const CULL_TURN_THRESHOLD: f32 = 0.045;
+
+/// Views baked around the tree for its impostor (must match tree.wgsl)
+pub const IMPOSTOR_VIEWS: u32 = 8;
+/// Pixels per side of one impostor view
+const IMPOSTOR_VIEW_SIZE: u32 = 256;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
-struct TreeVertex {
+pub(crate) struct TreeVertex {
     position: [f32; 3],
     normal: [f32; 3],
     uv: [f32; 2],
@@ -15,13 +29,67 @@ struct TreeVertex {
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 struct CameraUniform {
-    view_proj: [[f32; 4]; 4],
+    view_proj: [[f32; 4]; 4], // 64 bytes (0-64)
+    camera_right: [f32; 3],   // 12 bytes (64-76) - billboard axes for leaves
+    time: f32,                // 4 bytes (76-80) - seconds, drives the wind sway
+    camera_up: [f32; 3],      // 12 bytes (80-92)
+    _padding: f32,            // 4 bytes (92-96)
+    wind: WindUniform,        // 16 bytes (96-112)
+    camera_position: [f32; 3], // 12 bytes (112-124) - LOD distances are measured from here
+    lod_fade_start: f32,      // 4 bytes (124-128)
+    impostor_center: [f32; 3], // 12 bytes (128-140) - mesh-local bounding sphere
+    impostor_radius: f32,     // 4 bytes (140-144)
+    lod_fade_end: f32,        // 4 bytes (144-148)
+    _padding2: [f32; 3],      // 12 bytes (148-160) -> Total 160 bytes
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
-struct TreeInstance {
-    model_matrix: [[f32; 4]; 4],
+pub(crate) struct TreeInstanceRaw {
+    model_matrix: [[f32; 4]; 4], // 64 bytes (0-64)
+    tint: [f32; 3],              // 12 bytes (64-76)
+    species: u32,                // 4 bytes (76-80) -> Total 80 bytes
+}
+
+/// One placed tree/rock: world transform (scale and yaw baked in), species and color tint
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TreeInstance {
+    pub transform: Mat4,
+    /// Generator species id (oak, bush, ...); the pipeline draws whatever mesh it was given
+    pub species: u8,
+    /// Multiplied into the mesh color
+    pub tint: [f32; 3],
+}
+
+impl From<Mat4> for TreeInstance {
+    /// Untinted instance (rocks and other props)
+    fn from(transform: Mat4) -> Self {
+        Self {
+            transform,
+            species: 0,
+            tint: [1.0; 3],
+        }
+    }
+}
+
+/// One leaf billboard in mesh-local space (from `croatoan_procgen::generate_leaf_instances`)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LeafInstance {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub size: f32,
+}
+
+/// World-space leaf billboard (one instanced quad each)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct LeafInstanceRaw {
+    position: [f32; 3], // 12 bytes (0-12)
+    size: f32,          // 4 bytes (12-16)
+    normal: [f32; 3],   // 12 bytes (16-28)
+    sway_height: f32,   // 4 bytes (28-32) - height above the tree's root
+    tint: [f32; 3],     // 12 bytes (32-44)
+    sway_phase: f32,    // 4 bytes (44-48) -> Total 48 bytes
 }
 
 #[derive(Clone)]
@@ -30,24 +98,130 @@ pub struct TreeMesh {
     pub index_buffer: Arc<Buffer>,
     pub index_count: u32,
     pub texture_bind_group: Option<Arc<BindGroup>>, // Added for textures
+    /// Leaf billboards drawn alongside the branches (empty for meshes with baked foliage)
+    pub leaves: Arc<Vec<LeafInstance>>,
+    /// Alpha-cutout leaf texture; the pipeline's built-in leaf mask when None
+    pub leaf_texture_bind_group: Option<Arc<BindGroup>>,
+    /// Mesh-local bounding sphere (branches and leaves), for per-instance culling
+    pub bounding_center: Vec3,
+    pub bounding_radius: f32,
+    /// Billboard views for distant instances (see `TreePipeline::bake_impostor`)
+    pub impostor: Option<Arc<TreeImpostor>>,
+}
+
+/// Views of a tree mesh baked into an atlas, drawn instead of the mesh far away
+pub struct TreeImpostor {
+    /// Keeps the atlas texture alive
+    _target: RenderTarget,
+    bind_group: BindGroup,
+}
+
+impl TreeMesh {
+    /// Attach leaf billboards (and optionally a leaf texture) to this mesh
+    /// The bounding sphere grows to cover the leaf quads.
+    pub fn with_leaves(mut self, leaves: Vec<LeafInstance>, leaf_texture_bind_group: Option<Arc<BindGroup>>) -> Self {
+        for leaf in &leaves {
+            let reach = (Vec3::from_array(leaf.position) - self.bounding_center).length() + leaf.size;
+            self.bounding_radius = self.bounding_radius.max(reach);
+        }
+        self.leaves = Arc::new(leaves);
+        self.leaf_texture_bind_group = leaf_texture_bind_group;
+        self
+    }
+
+    /// Attach a baked impostor; instances past the LOD distances draw it instead
+    pub fn with_impostor(mut self, impostor: Arc<TreeImpostor>) -> Self {
+        self.impostor = Some(impostor);
+        self
+    }
+}
+
+/// Instances split by LOD after culling (indices into the uploaded instances)
+#[derive(Debug, Default, PartialEq)]
+struct LodSplit {
+    /// Full mesh and leaves
+    mesh: Vec<usize>,
+    /// Impostor quad (the cross-fade band is in both)
+    impostor: Vec<usize>,
+}
+
+/// Camera the visible instance set was last built for
+#[derive(Copy, Clone, Debug)]
+struct CullCamera {
+    position: Vec3,
+    forward: Vec3,
+}
+
+impl CullCamera {
+    /// Whether the camera moved or turned past the thresholds since this one
+    fn is_stale(&self, position: Vec3, forward: Vec3) -> bool {
+        self.position.distance(position) > CULL_MOVE_THRESHOLD
+            || self.forward.cross(forward).length() > CULL_TURN_THRESHOLD
+            || self.forward.dot(forward) < 0.0
+    }
 }
 
 pub struct TreePipeline {
     pipeline: RenderPipeline,
     mesh: Option<TreeMesh>,
-    instance_buffer: Option<Buffer>,
+    instance_buffer: Option<Buffer>, // Every instance (shadows)
     instance_count: u32,
+    instances: Vec<TreeInstance>,
+    leaf_data: Vec<LeafInstanceRaw>, // Every leaf, grouped by instance in `instances` order
+    visible_instance_buffer: Option<Buffer>, // Frustum-culled instances, compacted (main pass)
+    visible_instance_count: u32,
+    impostor_instance_buffer: Option<Buffer>, // Culled instances past the LOD distance
+    impostor_instance_count: u32,
+    cull_camera: Option<CullCamera>,
     camera_buffer: Buffer,
+    camera_bind_group_layout: BindGroupLayout,
     camera_bind_group: BindGroup,
     // We store the texture layout here so we can create bind groups later if needed
     pub texture_bind_group_layout: BindGroupLayout,
     default_bind_group: BindGroup,
+    leaf_pipeline: RenderPipeline,
+    leaf_instance_buffer: Option<Buffer>,
+    leaf_instance_count: u32,
+    default_leaf_bind_group: BindGroup,
+    impostor_pipeline: RenderPipeline,
+    wind: WindParams,
+    lod_near: f32,
+    lod_far: f32,
+    surface_format: wgpu::TextureFormat,
+    sample_count: u32,
+}
+
+/// Size of the built-in leaf mask texture (pixels per side)
+const LEAF_TEXTURE_SIZE: u32 = 32;
+
+/// Built-in leaf mask: a pointed oval with a darker midrib, transparent outside
+fn leaf_mask_pixels() -> Vec<u8> {
+    let size = LEAF_TEXTURE_SIZE as usize;
+    let mut pixels = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            // Narrow toward the tip and stem
+            let half_width = 0.6 * (1.0 - v * v).max(0.0).sqrt();
+            let inside = u.abs() < half_width;
+            let shade = if u.abs() < 0.05 { 0.75 } else { 1.0 - u.abs() * 0.3 };
+            let (r, g, b) = (0.30 * shade, 0.55 * shade, 0.18 * shade);
+            pixels.extend_from_slice(&[
+                (r * 255.0) as u8,
+                (g * 255.0) as u8,
+                (b * 255.0) as u8,
+                if inside { 255 } else { 0 },
+            ]);
+        }
+    }
+    pixels
 }
 
 
 
 impl TreePipeline {
-    pub fn new(device: &Device, queue: &Queue, surface_format: wgpu::TextureFormat) -> Self {
+    pub fn new(device: &Device, queue: &Queue, surface_format: wgpu::TextureFormat, sample_count: u32) -> Self {
         // Group 0: Camera
         let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Tree Camera Bind Group Layout"),
@@ -66,29 +240,7 @@ impl TreePipeline {
         });
 
         // Group 1: Texture
-        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Tree Texture Bind Group Layout"),
-            entries: &[
-                // Diffuse Texture
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                // Sampler
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-        });
+        let texture_bind_group_layout = crate::asset_cache::texture_bind_group_layout(device, "Tree Texture Bind Group Layout");
 
         // Create default white texture
         let default_texture_size = wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 };
@@ -146,6 +298,49 @@ impl TreePipeline {
             label: Some("Default Texture Bind Group"),
         });
 
+        // Built-in leaf mask (alpha cutout)
+        let leaf_texture_size = wgpu::Extent3d { width: LEAF_TEXTURE_SIZE, height: LEAF_TEXTURE_SIZE, depth_or_array_layers: 1 };
+        let leaf_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Default Leaf Texture"),
+            size: leaf_texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &leaf_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &leaf_mask_pixels(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * LEAF_TEXTURE_SIZE),
+                rows_per_image: None,
+            },
+            leaf_texture_size,
+        );
+        let leaf_texture_view = leaf_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let default_leaf_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&leaf_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&default_sampler),
+                },
+            ],
+            label: Some("Default Leaf Bind Group"),
+        });
+
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Tree Pipeline Layout"),
             bind_group_layouts: &[&camera_bind_group_layout, &texture_bind_group_layout],
@@ -191,7 +386,7 @@ impl TreePipeline {
                     },
                     // Instance Buffer Layout
                     wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<TreeInstance>() as wgpu::BufferAddress,
+                        array_stride: std::mem::size_of::<TreeInstanceRaw>() as wgpu::BufferAddress,
                         step_mode: wgpu::VertexStepMode::Instance,
                         attributes: &[
                             // Model Matrix (4x vec4)
@@ -215,6 +410,12 @@ impl TreePipeline {
                                 shader_location: 8,
                                 format: wgpu::VertexFormat::Float32x4,
                             },
+                            // Tint
+                            wgpu::VertexAttribute {
+                                offset: (std::mem::size_of::<[f32; 4]>() * 4) as wgpu::BufferAddress,
+                                shader_location: 9,
+                                format: wgpu::VertexFormat::Float32x3,
+                            },
                         ],
                     },
                 ],
@@ -240,18 +441,110 @@ impl TreePipeline {
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: wgpu::TextureFormat::Depth32Float,
                 depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
+                depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
         });
 
+        // Leaves: one camera-facing quad per instance, alpha-tested
+        let leaf_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tree Leaf Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_leaf",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<LeafInstanceRaw>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x4, // position + size
+                        1 => Float32x4, // normal + sway height
+                        2 => Float32x4, // tint + sway phase
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_leaf",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        // Impostors: one upright quad per distant tree, alpha-tested like the leaves
+        let impostor_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tree Impostor Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_impostor",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<TreeInstanceRaw>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![
+                        5 => Float32x4, // model matrix
+                        6 => Float32x4,
+                        7 => Float32x4,
+                        8 => Float32x4,
+                        9 => Float32x3, // tint
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_impostor",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Greater, // Reverse-Z
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
         // Create camera uniform buffer
         let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Tree Camera Buffer"),
@@ -277,10 +570,28 @@ impl TreePipeline {
             mesh: None,
             instance_buffer: None,
             instance_count: 0,
+            instances: Vec::new(),
+            leaf_data: Vec::new(),
+            visible_instance_buffer: None,
+            visible_instance_count: 0,
+            impostor_instance_buffer: None,
+            impostor_instance_count: 0,
+            cull_camera: None,
             camera_buffer,
+            camera_bind_group_layout,
             camera_bind_group,
             texture_bind_group_layout,
             default_bind_group,
+            leaf_pipeline,
+            leaf_instance_buffer: None,
+            leaf_instance_count: 0,
+            default_leaf_bind_group,
+            impostor_pipeline,
+            wind: WindParams::default(),
+            lod_near: 250.0,
+            lod_far: 300.0,
+            surface_format,
+            sample_count,
         }
     }
 
@@ -318,46 +629,288 @@ impl TreePipeline {
 
         log::info!("Created tree mesh: {} vertices, {} triangles", vertices.len(), indices.len() / 3);
 
+        let (bounding_center, bounding_radius) = bounding_sphere(positions);
+
         TreeMesh {
             vertex_buffer,
             index_buffer,
             index_count: indices.len() as u32,
             texture_bind_group,
+            leaves: Arc::new(Vec::new()),
+            leaf_texture_bind_group: None,
+            bounding_center,
+            bounding_radius,
+            impostor: None,
         }
     }
 
+    /// Render views of `mesh` from around it into an impostor atlas (once, at load)
+    /// Attach the result with `TreeMesh::with_impostor`.
+    pub fn bake_impostor(&self, device: &Device, queue: &Queue, mesh: &TreeMesh) -> TreeImpostor {
+        let target = RenderTarget::new(device, IMPOSTOR_VIEW_SIZE * IMPOSTOR_VIEWS, IMPOSTOR_VIEW_SIZE, self.surface_format, self.sample_count);
+
+        // A single untinted tree at the origin
+        let instance = TreeInstance::from(Mat4::IDENTITY);
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tree Impostor Instance Buffer"),
+            contents: bytemuck::cast_slice(&[TreeInstanceRaw::from(&instance)]),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let leaf_data = place_leaves(&mesh.leaves, &[instance]);
+        let leaf_buffer = (!leaf_data.is_empty()).then(|| device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tree Impostor Leaf Buffer"),
+            contents: bytemuck::cast_slice(&leaf_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        }));
+
+        // One orthographic camera per view, still air
+        let camera_bind_groups: Vec<BindGroup> = (0..IMPOSTOR_VIEWS)
+            .map(|view| {
+                let uniform = impostor_view_uniform(view, mesh.bounding_center, mesh.bounding_radius);
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Tree Impostor Camera Buffer"),
+                    contents: bytemuck::cast_slice(&[uniform]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Tree Impostor Camera Bind Group"),
+                    layout: &self.camera_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }],
+                })
+            })
+            .collect();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Tree Impostor Bake"),
+        });
+        {
+            let mut render_pass = target.begin_pass(&mut encoder, wgpu::Color::TRANSPARENT);
+            for (view, camera_bind_group) in camera_bind_groups.iter().enumerate() {
+                let size = IMPOSTOR_VIEW_SIZE as f32;
+                render_pass.set_viewport(view as f32 * size, 0.0, size, size, 0.0, 1.0);
+                self.draw_mesh(
+                    &mut render_pass,
+                    camera_bind_group,
+                    mesh,
+                    (&instance_buffer, 1),
+                    leaf_buffer.as_ref().map(|buffer| (buffer, leaf_data.len() as u32)),
+                );
+            }
+        }
+        queue.submit(Some(encoder.finish()));
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Tree Impostor Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Tree Impostor Bind Group"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        TreeImpostor { _target: target, bind_group }
+    }
+
     /// Set the shared mesh for this pipeline
     pub fn set_mesh(&mut self, mesh: TreeMesh) {
         self.mesh = Some(mesh);
     }
 
     /// Upload instances for a chunk
+    /// Call after `set_mesh`: the mesh's leaves are expanded into world-space billboards here.
+    /// Everything draws as a full mesh until the first `cull`.
     pub fn upload_instances(
         &mut self,
         device: &Device,
-        instances: &[Mat4],
+        instances: &[TreeInstance],
     ) {
         self.instance_count = instances.len() as u32;
+        self.instances = instances.to_vec();
+        self.leaf_data = Vec::new();
+        self.leaf_instance_buffer = None;
+        self.leaf_instance_count = 0;
+        self.impostor_instance_buffer = None;
+        self.impostor_instance_count = 0;
+        self.cull_camera = None;
         if self.instance_count == 0 {
             self.instance_buffer = None;
+            self.visible_instance_buffer = None;
+            self.visible_instance_count = 0;
             return;
         }
 
-        let instance_data: Vec<TreeInstance> = instances.iter()
-            .map(|m| TreeInstance { model_matrix: m.to_cols_array_2d() })
-            .collect();
+        if let Some(mesh) = &self.mesh {
+            self.leaf_data = place_leaves(&mesh.leaves, instances);
+            if !self.leaf_data.is_empty() {
+                self.leaf_instance_count = self.leaf_data.len() as u32;
+                self.leaf_instance_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Tree Leaf Instance Buffer"),
+                    contents: bytemuck::cast_slice(&self.leaf_data),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                }));
+            }
+        }
+
+        let instance_data: Vec<TreeInstanceRaw> = instances.iter().map(TreeInstanceRaw::from).collect();
 
         self.instance_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Tree Instance Buffer"),
             contents: bytemuck::cast_slice(&instance_data),
             usage: wgpu::BufferUsages::VERTEX,
         }));
+        self.visible_instance_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tree Visible Instance Buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        }));
+        self.visible_instance_count = self.instance_count;
+
+        if self.mesh.as_ref().is_some_and(|mesh| mesh.impostor.is_some()) {
+            self.impostor_instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Tree Impostor Instance Buffer"),
+                size: std::mem::size_of_val(instance_data.as_slice()) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+    }
+
+    /// Distances (from the camera) over which trees cross-fade from the full mesh to the
+    /// impostor: mesh only up to `near_full`, impostor only past `impostor`
+    /// Only applies once the mesh has an impostor (`TreeMesh::with_impostor`).
+    pub fn set_lod_distances(&mut self, near_full: f32, impostor: f32) {
+        self.lod_near = near_full.max(0.0);
+        self.lod_far = impostor.max(self.lod_near);
+        self.cull_camera = None; // Re-split on the next cull
+    }
+
+    /// Frustum-cull individual trees (and their leaves) for the main pass, and pick the
+    /// mesh or the impostor for each by distance
+    /// The compacted sets are only rebuilt once the camera has moved or turned past a small
+    /// threshold; the bounds and LOD distances are padded to cover that slack. Shadows still
+    /// draw every tree as a mesh, since trees outside the view can shade what's in it.
+    pub fn cull(&mut self, queue: &Queue, frustum: &Frustum, camera_position: Vec3, camera_forward: Vec3) {
+        let Some(mesh) = &self.mesh else { return };
+        if self.instances.is_empty() {
+            return;
+        }
+        if let Some(last) = &self.cull_camera {
+            if !last.is_stale(camera_position, camera_forward) {
+                return;
+            }
+        }
+
+        let bounds = (mesh.bounding_center, mesh.bounding_radius);
+        let visible = visible_instances(bounds, &self.instances, frustum, camera_position);
+        let lod = if mesh.impostor.is_some() {
+            lod_split(&self.instances, &visible, camera_position, self.lod_near, self.lod_far)
+        } else {
+            LodSplit { mesh: visible, impostor: Vec::new() }
+        };
+
+        let instance_data: Vec<TreeInstanceRaw> = lod.mesh.iter()
+            .map(|&i| TreeInstanceRaw::from(&self.instances[i]))
+            .collect();
+        if let Some(buffer) = &self.visible_instance_buffer {
+            if !instance_data.is_empty() {
+                queue.write_buffer(buffer, 0, bytemuck::cast_slice(&instance_data));
+            }
+        }
+        self.visible_instance_count = instance_data.len() as u32;
+
+        // Leaves are grouped per tree, so keep the mesh trees' runs
+        let leaves_per_tree = mesh.leaves.len();
+        if let Some(leaf_buffer) = &self.leaf_instance_buffer {
+            let leaf_data: Vec<LeafInstanceRaw> = lod.mesh.iter()
+                .flat_map(|&i| self.leaf_data[i * leaves_per_tree..(i + 1) * leaves_per_tree].iter().copied())
+                .collect();
+            if !leaf_data.is_empty() {
+                queue.write_buffer(leaf_buffer, 0, bytemuck::cast_slice(&leaf_data));
+            }
+            self.leaf_instance_count = leaf_data.len() as u32;
+        }
+
+        let impostor_data: Vec<TreeInstanceRaw> = lod.impostor.iter()
+            .map(|&i| TreeInstanceRaw::from(&self.instances[i]))
+            .collect();
+        if let Some(buffer) = &self.impostor_instance_buffer {
+            if !impostor_data.is_empty() {
+                queue.write_buffer(buffer, 0, bytemuck::cast_slice(&impostor_data));
+            }
+        }
+        self.impostor_instance_count = impostor_data.len() as u32;
+
+        self.cull_camera = Some(CullCamera { position: camera_position, forward: camera_forward });
+    }
+
+    /// Trees drawn as a full mesh by the main pass after the last `cull` (all of them before it)
+    pub fn visible_instance_count(&self) -> u32 {
+        self.visible_instance_count
+    }
+
+    /// Trees drawn as impostors by the main pass after the last `cull`
+    pub fn impostor_instance_count(&self) -> u32 {
+        self.impostor_instance_count
+    }
+
+    /// GPU memory held by this pipeline's instance buffers (bytes); the mesh isn't counted, as
+    /// it's shared between chunks
+    pub fn buffer_bytes(&self) -> u64 {
+        let own = [
+            self.instance_buffer.as_ref(),
+            self.visible_instance_buffer.as_ref(),
+            self.impostor_instance_buffer.as_ref(),
+            self.leaf_instance_buffer.as_ref(),
+            Some(&self.camera_buffer),
+        ];
+        own.iter().flatten().map(|buffer| buffer.size()).sum()
+    }
+
+    /// Wind used by the branch/leaf sway (applied on the next `update_camera`)
+    pub fn set_wind(&mut self, params: WindParams) {
+        self.wind = params;
     }
 
     /// Update camera uniform
-    pub fn update_camera(&self, queue: &Queue, view_proj: &Mat4) {
+    /// `camera_right`/`camera_up` orient the leaf billboards; `time` animates the sway;
+    /// LOD distances are measured from `camera_position`
+    pub fn update_camera(&self, queue: &Queue, view_proj: &Mat4, camera_position: Vec3, camera_right: Vec3, camera_up: Vec3, time: f32) {
+        let (impostor_center, impostor_radius) = self.mesh.as_ref()
+            .map_or((Vec3::ZERO, 0.0), |mesh| (mesh.bounding_center, mesh.bounding_radius));
+        // Without an impostor the mesh never fades out
+        let has_impostor = self.mesh.as_ref().is_some_and(|mesh| mesh.impostor.is_some());
+        let (lod_fade_start, lod_fade_end) = if has_impostor { (self.lod_near, self.lod_far) } else { (f32::MAX, f32::MAX) };
+
         let uniform = CameraUniform {
             view_proj: view_proj.to_cols_array_2d(),
+            camera_right: camera_right.to_array(),
+            time,
+            camera_up: camera_up.to_array(),
+            _padding: 0.0,
+            wind: self.wind.into(),
+            camera_position: camera_position.to_array(),
+            lod_fade_start,
+            impostor_center: impostor_center.to_array(),
+            impostor_radius,
+            lod_fade_end,
+            _padding2: [0.0; 3],
         };
         queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[uniform]));
     }
@@ -367,14 +920,42 @@ impl TreePipeline {
         &'rpass self,
         render_pass: &mut wgpu::RenderPass<'rpass>,
     ) {
-        if self.mesh.is_none() || self.instance_count == 0 || self.instance_buffer.is_none() {
-            return;
+        let Some(mesh) = &self.mesh else { return };
+
+        if let Some(instance_buffer) = &self.visible_instance_buffer {
+            if self.visible_instance_count > 0 {
+                self.draw_mesh(
+                    render_pass,
+                    &self.camera_bind_group,
+                    mesh,
+                    (instance_buffer, self.visible_instance_count),
+                    self.leaf_instance_buffer.as_ref().map(|buffer| (buffer, self.leaf_instance_count)),
+                );
+            }
         }
 
-        let mesh = self.mesh.as_ref().unwrap();
+        if let (Some(impostor), Some(instance_buffer)) = (&mesh.impostor, &self.impostor_instance_buffer) {
+            if self.impostor_instance_count > 0 {
+                render_pass.set_pipeline(&self.impostor_pipeline);
+                render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                render_pass.set_bind_group(1, &impostor.bind_group, &[]);
+                render_pass.set_vertex_buffer(0, instance_buffer.slice(..));
+                render_pass.draw(0..6, 0..self.impostor_instance_count);
+            }
+        }
+    }
 
+    /// Draw branches then leaves for `instances` (buffer, count) seen through `camera_bind_group`
+    fn draw_mesh<'rpass>(
+        &'rpass self,
+        render_pass: &mut wgpu::RenderPass<'rpass>,
+        camera_bind_group: &'rpass BindGroup,
+        mesh: &'rpass TreeMesh,
+        (instance_buffer, instance_count): (&'rpass Buffer, u32),
+        leaves: Option<(&'rpass Buffer, u32)>,
+    ) {
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
         
         if let Some(tex_bg) = &mesh.texture_bind_group {
             render_pass.set_bind_group(1, tex_bg, &[]);
@@ -383,11 +964,273 @@ impl TreePipeline {
         }
 
         render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-        render_pass.set_vertex_buffer(1, self.instance_buffer.as_ref().unwrap().slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
         render_pass.set_index_buffer(
             mesh.index_buffer.slice(..),
             wgpu::IndexFormat::Uint32,
         );
-        render_pass.draw_indexed(0..mesh.index_count, 0, 0..self.instance_count);
+        render_pass.draw_indexed(0..mesh.index_count, 0, 0..instance_count);
+
+        if let Some((leaf_buffer, leaf_count)) = leaves {
+            render_pass.set_pipeline(&self.leaf_pipeline);
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+            match &mesh.leaf_texture_bind_group {
+                Some(leaf_bg) => render_pass.set_bind_group(1, leaf_bg, &[]),
+                None => render_pass.set_bind_group(1, &self.default_leaf_bind_group, &[]),
+            }
+            render_pass.set_vertex_buffer(0, leaf_buffer.slice(..));
+            render_pass.draw(0..6, 0..leaf_count);
+        }
+    }
+
+    /// Draw the instances into the shadow map (depth only)
+    pub fn render_shadow<'rpass>(
+        &'rpass self,
+        render_pass: &mut wgpu::RenderPass<'rpass>,
+        shadow_pipeline: &'rpass crate::shadows::ShadowPipeline,
+    ) {
+        if let (Some(mesh), Some(instance_buffer)) = (&self.mesh, &self.instance_buffer) {
+            shadow_pipeline.render_with_stride(
+                render_pass,
+                InstancedMesh {
+                    vertex_buffer: &mesh.vertex_buffer,
+                    index_buffer: &mesh.index_buffer,
+                    index_count: mesh.index_count,
+                    instance_buffer,
+                    instance_count: self.instance_count,
+                },
+                (
+                    std::mem::size_of::<TreeVertex>() as wgpu::BufferAddress,
+                    std::mem::size_of::<TreeInstanceRaw>() as wgpu::BufferAddress,
+                ),
+            );
+        }
+    }
+}
+
+impl From<&TreeInstance> for TreeInstanceRaw {
+    fn from(instance: &TreeInstance) -> Self {
+        Self {
+            model_matrix: instance.transform.to_cols_array_2d(),
+            tint: instance.tint,
+            species: instance.species as u32,
+        }
+    }
+}
+
+/// Bounding sphere around the AABB of `positions`
+fn bounding_sphere(positions: &[[f32; 3]]) -> (Vec3, f32) {
+    if positions.is_empty() {
+        return (Vec3::ZERO, 0.0);
+    }
+    let (min, max) = positions.iter().fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), p| {
+        let p = Vec3::from_array(*p);
+        (min.min(p), max.max(p))
+    });
+    let center = (min + max) * 0.5;
+    let radius = positions.iter().map(|p| center.distance(Vec3::from_array(*p))).fold(0.0, f32::max);
+    (center, radius)
+}
+
+/// World-space bounding sphere of a mesh-local sphere drawn with `transform`
+fn instance_bounds((center, radius): (Vec3, f32), transform: &Mat4) -> (Vec3, f32) {
+    let scale = transform.x_axis.truncate().length()
+        .max(transform.y_axis.truncate().length())
+        .max(transform.z_axis.truncate().length());
+    (transform.transform_point3(center), radius * scale)
+}
+
+/// Indices of the instances whose bounds (mesh-local `bounds`) touch the frustum
+/// Bounds are padded by the camera slack allowed between `TreePipeline::cull` rebuilds: the
+/// move threshold, plus the sweep of the turn threshold at the instance's distance.
+fn visible_instances(bounds: (Vec3, f32), instances: &[TreeInstance], frustum: &Frustum, camera_position: Vec3) -> Vec<usize> {
+    instances.iter()
+        .enumerate()
+        .filter(|(_, instance)| {
+            let (center, radius) = instance_bounds(bounds, &instance.transform);
+            let slack = CULL_MOVE_THRESHOLD + center.distance(camera_position) * CULL_TURN_THRESHOLD;
+            frustum.contains_sphere(center, radius + slack)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Split the visible instances by distance: the mesh out to `lod_far`, the impostor from
+/// `lod_near` (both in between, where they cross-fade)
+/// Padded by the camera movement allowed between `TreePipeline::cull` rebuilds.
+fn lod_split(instances: &[TreeInstance], visible: &[usize], camera_position: Vec3, lod_near: f32, lod_far: f32) -> LodSplit {
+    let mut split = LodSplit::default();
+    for &i in visible {
+        let dist = instances[i].transform.w_axis.truncate().distance(camera_position);
+        if dist < lod_far + CULL_MOVE_THRESHOLD {
+            split.mesh.push(i);
+        }
+        if dist > lod_near - CULL_MOVE_THRESHOLD {
+            split.impostor.push(i);
+        }
+    }
+    split
+}
+
+/// Camera for impostor view `view`: orthographic, looking at the bounding sphere from
+/// angle `view * 2pi / IMPOSTOR_VIEWS` around it (atan2(z, x), as in tree.wgsl)
+fn impostor_view_uniform(view: u32, center: Vec3, radius: f32) -> CameraUniform {
+    let angle = view as f32 / IMPOSTOR_VIEWS as f32 * std::f32::consts::TAU;
+    let dir = Vec3::new(angle.cos(), 0.0, angle.sin());
+    let eye = center + dir * radius * 2.0;
+    let view_matrix = Mat4::look_at_rh(eye, center, Vec3::Y);
+    // Reverse-Z like the main camera: near and far swapped
+    let projection = Mat4::orthographic_rh(-radius, radius, -radius, radius, radius * 4.0, 0.01);
+    let right = (-dir).cross(Vec3::Y);
+
+    CameraUniform {
+        view_proj: (projection * view_matrix).to_cols_array_2d(),
+        camera_right: right.to_array(),
+        time: 0.0,
+        camera_up: Vec3::Y.to_array(),
+        _padding: 0.0,
+        wind: WindParams { strength: 0.0, ..Default::default() }.into(),
+        camera_position: eye.to_array(),
+        lod_fade_start: f32::MAX, // Always the full mesh
+        impostor_center: center.to_array(),
+        impostor_radius: radius,
+        lod_fade_end: f32::MAX,
+        _padding2: [0.0; 3],
+    }
+}
+
+/// Transform every mesh leaf by every tree instance (scale follows the instance)
+fn place_leaves(leaves: &[LeafInstance], instances: &[TreeInstance]) -> Vec<LeafInstanceRaw> {
+    let mut placed = Vec::with_capacity(leaves.len() * instances.len());
+    for instance in instances {
+        let scale = instance.transform.x_axis.truncate().length();
+        let origin = instance.transform.w_axis.truncate();
+        let phase = sway_phase(origin);
+        for leaf in leaves {
+            let position = instance.transform.transform_point3(Vec3::from_array(leaf.position));
+            let normal = instance.transform.transform_vector3(Vec3::from_array(leaf.normal)).normalize_or_zero();
+            placed.push(LeafInstanceRaw {
+                position: position.to_array(),
+                size: leaf.size * scale,
+                normal: normal.to_array(),
+                sway_height: position.y - origin.y,
+                tint: instance.tint,
+                sway_phase: phase,
+            });
+        }
+    }
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_layout() {
+        // Shader reads the matrix at 0-64 and the tint at 64
+        assert_eq!(std::mem::size_of::<TreeInstanceRaw>(), 80);
+
+        let instance = TreeInstance::from(Mat4::from_translation(glam::Vec3::new(1.0, 2.0, 3.0)));
+        assert_eq!(instance.tint, [1.0; 3]);
+        assert_eq!(instance.species, 0);
+    }
+
+    #[test]
+    fn test_leaves_follow_instances() {
+        assert_eq!(std::mem::size_of::<LeafInstanceRaw>(), 48);
+        assert_eq!(std::mem::size_of::<CameraUniform>(), 160);
+
+        let leaves = [LeafInstance { position: [0.0, 2.0, 0.0], normal: [0.0, 1.0, 0.0], size: 0.5 }];
+        let instances = [
+            TreeInstance::from(Mat4::from_translation(Vec3::new(10.0, 0.0, 0.0))),
+            TreeInstance {
+                transform: Mat4::from_scale_rotation_translation(Vec3::splat(3.0), glam::Quat::IDENTITY, Vec3::new(0.0, 1.0, 0.0)),
+                species: 1,
+                tint: [0.5, 1.0, 0.5],
+            },
+        ];
+        let placed = place_leaves(&leaves, &instances);
+        assert_eq!(placed.len(), 2);
+        assert_eq!(placed[0].position, [10.0, 2.0, 0.0]);
+        assert_eq!(placed[0].size, 0.5);
+        assert_eq!(placed[1].position, [0.0, 7.0, 0.0]);
+        assert_eq!(placed[1].size, 1.5);
+        assert_eq!(placed[1].tint, [0.5, 1.0, 0.5]);
+
+        // Leaves sway with their tree: height is measured from the tree's root
+        assert_eq!(placed[0].sway_height, 2.0);
+        assert_eq!(placed[1].sway_height, 6.0);
+        assert_eq!(placed[0].sway_phase, sway_phase(Vec3::new(10.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_cull_keeps_trees_in_view() {
+        let (center, radius) = bounding_sphere(&[[-1.0, 0.0, -1.0], [1.0, 8.0, 1.0]]);
+        assert_eq!(center, Vec3::new(0.0, 4.0, 0.0));
+        assert!((radius - 18.0_f32.sqrt()).abs() < 1e-5);
+
+        // Looking down +Z from the origin
+        let camera = crate::camera::Camera::new(Vec3::ZERO, Vec3::Z, 1.0);
+        let frustum = Frustum::from_view_proj(&camera.view_projection_matrix());
+        let at = |x: f32, z: f32| TreeInstance::from(Mat4::from_translation(Vec3::new(x, 0.0, z)));
+        let instances = [
+            at(0.0, 50.0),   // Ahead
+            at(0.0, -50.0),  // Behind
+            at(300.0, 50.0), // Far off to the side
+            at(0.0, 5.0),    // Close ahead
+        ];
+        let visible = visible_instances((center, radius), &instances, &frustum, camera.position);
+        assert_eq!(visible, vec![0, 3]);
+
+        // A tree just outside the view edge is kept while the camera could still turn to it
+        let edge = [TreeInstance::from(Mat4::from_translation(Vec3::new(27.0, 0.0, 50.0)))];
+        assert!(!frustum.contains_sphere(Vec3::new(27.0, 4.0, 50.0), radius));
+        assert_eq!(visible_instances((center, radius), &edge, &frustum, camera.position), vec![0]);
+    }
+
+    #[test]
+    fn test_cull_camera_threshold() {
+        let last = CullCamera { position: Vec3::ZERO, forward: Vec3::Z };
+        assert!(!last.is_stale(Vec3::new(1.0, 0.0, 0.0), Vec3::Z));
+        assert!(last.is_stale(Vec3::new(3.0, 0.0, 0.0), Vec3::Z));
+        assert!(!last.is_stale(Vec3::ZERO, Vec3::new(0.02, 0.0, 1.0).normalize()));
+        assert!(last.is_stale(Vec3::ZERO, Vec3::new(0.1, 0.0, 1.0).normalize()));
+        assert!(last.is_stale(Vec3::ZERO, -Vec3::Z));
+    }
+
+    #[test]
+    fn test_lod_split_cross_fades() {
+        let at = |z: f32| TreeInstance::from(Mat4::from_translation(Vec3::new(0.0, 0.0, z)));
+        let instances = [at(50.0), at(275.0), at(400.0), at(320.0)];
+        let split = lod_split(&instances, &[0, 1, 2], Vec3::ZERO, 250.0, 300.0);
+        assert_eq!(split.mesh, vec![0, 1]);
+        assert_eq!(split.impostor, vec![1, 2]);
+
+        // Culled trees get neither
+        let split = lod_split(&instances, &[3], Vec3::ZERO, 250.0, 300.0);
+        assert_eq!(split, LodSplit { mesh: vec![], impostor: vec![3] });
+    }
+
+    #[test]
+    fn test_impostor_views_surround_tree() {
+        let center = Vec3::new(0.0, 4.0, 0.0);
+        for view in 0..IMPOSTOR_VIEWS {
+            let uniform = impostor_view_uniform(view, center, 5.0);
+            let view_proj = Mat4::from_cols_array_2d(&uniform.view_proj);
+
+            // The bounding sphere fills the view, in front of the near plane (reverse-Z)
+            let projected = view_proj.project_point3(center);
+            assert!(projected.x.abs() < 1e-4 && projected.y.abs() < 1e-4);
+            assert!(projected.z > 0.0 && projected.z < 1.0);
+            let top = view_proj.project_point3(center + Vec3::Y * 5.0);
+            assert!((top.y - 1.0).abs() < 1e-4);
+
+            // Billboard axes face the view (matches vs_impostor's right vector)
+            let eye = Vec3::from_array(uniform.camera_position);
+            let to_camera = (eye - center).normalize();
+            let right = Vec3::from_array(uniform.camera_right);
+            assert!((right - Vec3::new(to_camera.z, 0.0, -to_camera.x)).length() < 1e-5);
+        }
     }
 }
@@ -0,0 +1,66 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec2, Vec3};
+
+/// Wind driving the vegetation sway (grass blades, tree branches and leaves)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WindParams {
+    /// World XZ direction the wind blows toward (normalized on upload)
+    pub direction: Vec2,
+    /// Displacement scale; the grass tips lean by about this much (world units)
+    pub strength: f32,
+    /// Gust frequency (radians per second)
+    pub frequency: f32,
+}
+
+impl Default for WindParams {
+    fn default() -> Self {
+        Self {
+            direction: Vec2::new(1.0, 0.5).normalize(),
+            strength: 0.15,
+            frequency: 2.0,
+        }
+    }
+}
+
+/// GPU layout shared by grass.wgsl and tree.wgsl (`WindParams` struct)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub(crate) struct WindUniform {
+    direction: [f32; 2], // 8 bytes (0-8)
+    strength: f32,       // 4 bytes (8-12)
+    frequency: f32,      // 4 bytes (12-16) -> Total 16 bytes
+}
+
+impl From<WindParams> for WindUniform {
+    fn from(params: WindParams) -> Self {
+        Self {
+            direction: params.direction.normalize_or_zero().to_array(),
+            strength: params.strength,
+            frequency: params.frequency,
+        }
+    }
+}
+
+/// Sway phase for a tree rooted at `origin`, so neighbours don't move in lockstep
+/// Must match `sway_phase` in tree.wgsl (leaves get it baked on the CPU)
+pub(crate) fn sway_phase(origin: Vec3) -> f32 {
+    origin.x * 0.37 + origin.z * 0.61
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wind_uniform() {
+        assert_eq!(std::mem::size_of::<WindUniform>(), 16);
+
+        let uniform = WindUniform::from(WindParams { direction: Vec2::new(3.0, 4.0), strength: 0.5, frequency: 1.0 });
+        assert!((uniform.direction[0] - 0.6).abs() < 1e-6);
+        assert!((uniform.direction[1] - 0.8).abs() < 1e-6);
+
+        // Calm air doesn't produce NaNs
+        let calm = WindUniform::from(WindParams { direction: Vec2::ZERO, strength: 0.0, frequency: 1.0 });
+        assert_eq!(calm.direction, [0.0, 0.0]);
+    }
+}
@@ -1,111 +1,209 @@
-use crate::mesh_gen::get_height_at;
-use noise::{NoiseFn, Perlin};
-use glam::{Mat4, Vec3, Quat};
-
-/// Generate buildings for a terrain chunk based on terrain features
-///
-/// Buildings require flat ground and are sparse.
-/// Returns a list of (mesh_name, transform) tuples.
-pub fn generate_buildings_for_chunk(
-    seed: u32,
-    chunk_size: f32,
-    offset_x: f32,
-    offset_z: f32,
-) -> Vec<(String, Mat4)> {
-    let noise = Perlin::new(seed + 999); // Different seed offset for buildings
-
-    // Density settings: Very sparse (e.g., 1 per 2 chunks on average)
-    // We check a grid of potential sites
-    let site_spacing = 100.0; 
-    let grid_size = (chunk_size / site_spacing).ceil() as u32;
-
-    let mut instances = Vec::new();
-
-    for x in 0..grid_size {
-        for z in 0..grid_size {
-            // Potential site center
-            let local_x = x as f32 * site_spacing + site_spacing * 0.5;
-            let local_z = z as f32 * site_spacing + site_spacing * 0.5;
-
-            // Add some jitter
-            let jitter_x = noise.get([local_x as f64 * 0.1, 0.0]) as f32 * 20.0;
-            let jitter_z = noise.get([0.0, local_z as f64 * 0.1]) as f32 * 20.0;
-
-            let world_x = offset_x + local_x + jitter_x;
-            let world_z = offset_z + local_z + jitter_z;
-
-            // Check bounds (don't spawn too close to edge to avoid mesh clipping)
-            if world_x < offset_x + 10.0 || world_x > offset_x + chunk_size - 10.0 ||
-               world_z < offset_z + 10.0 || world_z > offset_z + chunk_size - 10.0 {
-                continue;
-            }
-
-            // 1. Density Check (Noise)
-            let density_roll = noise.get([world_x as f64 * 0.01, world_z as f64 * 0.01]) as f32;
-            if density_roll < 0.6 { // Only top 20% of noise range (0.6 to 1.0 approx)
-                continue;
-            }
-
-            // 2. Flatness Check
-            // Sample height at center and corners of a 10x10 footprint
-            let (h_center, _) = get_height_at(world_x, world_z, seed);
-            
-            // Water check
-            if h_center < 2.0 { // Avoid beaches/water
-                continue;
-            }
-
-            let footprint = 5.0;
-            let (h_n, _) = get_height_at(world_x, world_z - footprint, seed);
-            let (h_s, _) = get_height_at(world_x, world_z + footprint, seed);
-            let (h_e, _) = get_height_at(world_x + footprint, world_z, seed);
-            let (h_w, _) = get_height_at(world_x - footprint, world_z, seed);
-
-            let max_diff = (h_center - h_n).abs()
-                .max((h_center - h_s).abs())
-                .max((h_center - h_e).abs())
-                .max((h_center - h_w).abs());
-
-            if max_diff > 1.5 { // Too steep
-                continue;
-            }
-
-            // Place Building
-            let angle = noise.get([world_x as f64 * 0.5, world_z as f64 * 0.5]) as f32 * 3.14;
-            
-            let transform = Mat4::from_scale_rotation_translation(
-                Vec3::splat(1.0),
-                Quat::from_rotation_y(angle),
-                Vec3::new(world_x, h_center, world_z),
-            );
-
-            // Determine type based on noise or random
-            // For now, just "building_cabin"
-            instances.push(("building_cabin".to_string(), transform));
-        }
-    }
-
-    instances
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_building_generation() {
-        let instances = generate_buildings_for_chunk(
-            12345,
-            256.0,
-            0.0,
-            0.0,
-        );
-
-        println!("Generated {} building instances", instances.len());
-        
-        for (name, instance) in instances {
-            assert_eq!(name, "building_cabin");
-            assert!(instance.w_axis.w == 1.0);
-        }
-    }
-}
+use crate::mesh_gen::{get_height_at, TerrainConfig};
+use crate::seed::WorldSeed;
+use crate::world_sample::sample_terrain;
+use croatoan_procgen::BuildingRecipe;
+use noise::{NoiseFn, Perlin};
+use glam::{Mat4, Vec2, Vec3, Quat};
+
+/// Building mesh names, matching the game's building registry
+pub const BUILDING_CABIN: &str = "building_cabin";
+pub const BUILDING_COLONIAL: &str = "building_colonial";
+pub const BUILDING_CHURCH: &str = "building_church";
+pub const BUILDING_WATCHTOWER: &str = "building_watchtower";
+pub const BUILDING_APARTMENT: &str = "building_apartment";
+
+/// Every building kind placed by `generate_buildings_for_chunk`
+pub const BUILDING_KINDS: [&str; 5] = [BUILDING_CABIN, BUILDING_COLONIAL, BUILDING_CHURCH, BUILDING_WATCHTOWER, BUILDING_APARTMENT];
+
+/// The recipe a building kind's mesh is generated from
+pub fn building_recipe(name: &str) -> Option<BuildingRecipe> {
+    match name {
+        BUILDING_CABIN => Some(BuildingRecipe::small_shack()),
+        BUILDING_COLONIAL => Some(BuildingRecipe::colonial_house()),
+        BUILDING_CHURCH => Some(BuildingRecipe::church()),
+        BUILDING_WATCHTOWER => Some(BuildingRecipe::watchtower()),
+        BUILDING_APARTMENT => Some(BuildingRecipe::apartment_block()),
+        _ => None,
+    }
+}
+
+/// Ground height to set a building at: the average terrain height under the corners of its
+/// `footprint` (see `BuildingRecipe::footprint`) placed at `position` with `yaw`. Where the
+/// ground falls below that, the foundation skirt (`FOUNDATION_SKIRT`) covers the gap.
+pub fn building_ground_height(seed: u32, position: Vec2, yaw: f32, footprint: (Vec2, Vec2)) -> f32 {
+    let (min, max) = footprint;
+    let rotation = Quat::from_rotation_y(yaw);
+    let corners = [Vec2::new(min.x, min.y), Vec2::new(max.x, min.y), Vec2::new(max.x, max.y), Vec2::new(min.x, max.y)];
+    corners
+        .iter()
+        .map(|corner| {
+            let offset = rotation * Vec3::new(corner.x, 0.0, corner.y);
+            sample_terrain(seed, position.x + offset.x, position.y + offset.z, None).height
+        })
+        .sum::<f32>()
+        / corners.len() as f32
+}
+
+/// Ground height above which a site gets a watchtower (a lookout on the hills)
+const WATCHTOWER_HEIGHT: f32 = 14.0;
+
+/// Generate buildings for a terrain chunk based on terrain features
+///
+/// Buildings require flat ground and are sparse. They sit on the natural ground, without
+/// trails: `TrailNetwork::plan` routes the trails to them.
+/// Returns a list of (mesh_name, transform) tuples.
+pub fn generate_buildings_for_chunk(
+    seed: u32,
+    chunk_size: f32,
+    offset_x: f32,
+    offset_z: f32,
+) -> Vec<(String, Mat4)> {
+    let noise = Perlin::new(WorldSeed::new(seed).derive("buildings"));
+    let config = TerrainConfig::default();
+
+    // Density settings: Very sparse (e.g., 1 per 2 chunks on average)
+    // We check a grid of potential sites
+    let site_spacing = 100.0; 
+    let grid_size = (chunk_size / site_spacing).ceil() as u32;
+
+    let mut instances = Vec::new();
+
+    for x in 0..grid_size {
+        for z in 0..grid_size {
+            // Potential site center
+            let local_x = x as f32 * site_spacing + site_spacing * 0.5;
+            let local_z = z as f32 * site_spacing + site_spacing * 0.5;
+
+            // Add some jitter
+            let jitter_x = noise.get([local_x as f64 * 0.1, 0.0]) as f32 * 20.0;
+            let jitter_z = noise.get([0.0, local_z as f64 * 0.1]) as f32 * 20.0;
+
+            let world_x = offset_x + local_x + jitter_x;
+            let world_z = offset_z + local_z + jitter_z;
+
+            // Check bounds (don't spawn too close to edge to avoid mesh clipping)
+            if world_x < offset_x + 10.0 || world_x > offset_x + chunk_size - 10.0 ||
+               world_z < offset_z + 10.0 || world_z > offset_z + chunk_size - 10.0 {
+                continue;
+            }
+
+            // 1. Density Check (Noise)
+            let density_roll = noise.get([world_x as f64 * 0.01, world_z as f64 * 0.01]) as f32;
+            if density_roll < 0.6 { // Only top 20% of noise range (0.6 to 1.0 approx)
+                continue;
+            }
+
+            // 2. Flatness Check
+            // Sample height at center and corners of a 10x10 footprint
+            let (h_center, _) = get_height_at(world_x, world_z, seed, &config, None);
+            
+            // Water check
+            if h_center < 2.0 { // Avoid beaches/water
+                continue;
+            }
+
+            let footprint = 5.0;
+            let (h_n, _) = get_height_at(world_x, world_z - footprint, seed, &config, None);
+            let (h_s, _) = get_height_at(world_x, world_z + footprint, seed, &config, None);
+            let (h_e, _) = get_height_at(world_x + footprint, world_z, seed, &config, None);
+            let (h_w, _) = get_height_at(world_x - footprint, world_z, seed, &config, None);
+
+            let max_diff = (h_center - h_n).abs()
+                .max((h_center - h_s).abs())
+                .max((h_center - h_e).abs())
+                .max((h_center - h_w).abs());
+
+            if max_diff > 1.5 { // Too steep
+                continue;
+            }
+
+            // Place Building
+            let angle = noise.get([world_x as f64 * 0.5, world_z as f64 * 0.5]) as f32 * 3.14;
+
+            // Towers on high ground; elsewhere mostly cabins and houses, the odd church or block
+            let kind_roll = noise.get([world_x as f64 * 0.37, world_z as f64 * 0.37]) as f32;
+            let name = if h_center > WATCHTOWER_HEIGHT {
+                BUILDING_WATCHTOWER
+            } else if kind_roll > 0.35 {
+                BUILDING_CHURCH
+            } else if kind_roll > 0.15 {
+                BUILDING_APARTMENT
+            } else if kind_roll > -0.2 {
+                BUILDING_COLONIAL
+            } else {
+                BUILDING_CABIN
+            };
+
+            // Sit it on the terrain under its footprint rather than the height at its center
+            let ground = building_recipe(name)
+                .map_or(h_center, |recipe| building_ground_height(seed, Vec2::new(world_x, world_z), angle, recipe.footprint()));
+            let transform = Mat4::from_scale_rotation_translation(
+                Vec3::splat(1.0),
+                Quat::from_rotation_y(angle),
+                Vec3::new(world_x, ground, world_z),
+            );
+            instances.push((name.to_string(), transform));
+        }
+    }
+
+    instances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_building_generation() {
+        let instances = generate_buildings_for_chunk(
+            12345,
+            256.0,
+            0.0,
+            0.0,
+        );
+
+        println!("Generated {} building instances", instances.len());
+        
+        for (name, instance) in instances {
+            assert!(BUILDING_KINDS.contains(&name.as_str()), "unexpected building {}", name);
+            assert!(instance.w_axis.w == 1.0);
+        }
+    }
+
+    #[test]
+    fn test_buildings_sit_on_their_footprint() {
+        let mut checked = 0;
+        for cx in -4..4 {
+            for (name, transform) in generate_buildings_for_chunk(12345, 256.0, cx as f32 * 256.0, 0.0) {
+                let (min, max) = building_recipe(&name).unwrap().footprint();
+                let corners = [Vec3::new(min.x, 0.0, min.y), Vec3::new(max.x, 0.0, min.y), Vec3::new(max.x, 0.0, max.y), Vec3::new(min.x, 0.0, max.y)];
+                let heights: Vec<f32> = corners.iter().map(|c| {
+                    let p = transform.transform_point3(*c);
+                    sample_terrain(12345, p.x, p.z, None).height
+                }).collect();
+                let y = transform.w_axis.y;
+
+                // Between the highest and lowest corner, and the skirt reaches the lowest
+                let lowest = heights.iter().cloned().fold(f32::MAX, f32::min);
+                let highest = heights.iter().cloned().fold(f32::MIN, f32::max);
+                assert!(y >= lowest - 1e-3 && y <= highest + 1e-3, "{} at {} for corners {:?}", name, y, heights);
+                assert!(y - croatoan_procgen::FOUNDATION_SKIRT <= lowest, "{} floats over {:?}", name, heights);
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_building_kinds_vary() {
+        let mut kinds = std::collections::HashSet::new();
+        for cx in -4..4 {
+            for cz in -4..4 {
+                for (name, _) in generate_buildings_for_chunk(12345, 256.0, cx as f32 * 256.0, cz as f32 * 256.0) {
+                    kinds.insert(name);
+                }
+            }
+        }
+        assert!(kinds.len() >= 3, "only {:?}", kinds);
+    }
+}
@@ -5,14 +5,18 @@ pub mod vegetation;
 pub mod trees;
 pub mod rocks;
 pub mod buildings;
+pub mod world_sample;
+pub mod trails;
 
 // Re-export commonly used items
-pub use noise_util::{fbm, ridged, turbulence};
+pub use noise_util::{fbm, ridged, turbulence, fbm_with, ridged_with, simplex, worley, domain_warp};
 pub use seed::WorldSeed;
-pub use mesh_gen::generate_terrain_chunk;
-pub use vegetation::generate_vegetation_for_chunk;
-pub use vegetation::generate_detritus_for_chunk;
+pub use croatoan_procgen::rng;
+pub use rng::SeededRng;
+pub use mesh_gen::{generate_terrain_chunk, generate_terrain_chunk_default, add_terrain_skirts, splat_weights, terrain_splat, generate_detritus_for_chunk, DetritusArrays, biome_t, raycast_terrain, carve_rivers, TerrainConfig};
+pub use vegetation::{generate_vegetation_for_chunk, generate_plants_for_chunk, GrassInstance};
 pub use trees::generate_trees_for_chunk;
-pub use trees::TreeTemplate;
+pub use trees::{TreeTemplate, TreeInstance};
 pub use rocks::generate_rocks_for_chunk;
 pub use buildings::generate_buildings_for_chunk;
+pub use world_sample::{sample_terrain, TerrainSample, Biome};
@@ -1,7 +1,22 @@
 use crate::noise_util;
+use crate::seed::WorldSeed;
+use crate::trails::{self, TrailNetwork};
+use crate::world_sample::{sample_terrain, Biome};
 use glam::{Vec2, Vec3};
 
-/// Generate a procedural terrain chunk mesh
+/// Generate a procedural terrain chunk mesh with the default `TerrainConfig` and no trails
+/// Returns (positions, colors, normals, indices)
+pub fn generate_terrain_chunk_default(
+    seed: u32,
+    size: u32,
+    offset_x: i32,
+    offset_z: i32,
+    scale: f32,
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>) {
+    generate_terrain_chunk(seed, size, offset_x, offset_z, scale, &TerrainConfig::default(), None)
+}
+
+/// Generate a procedural terrain chunk mesh, flattened along `trails` where given
 /// Returns (positions, colors, normals, indices)
 pub fn generate_terrain_chunk(
     seed: u32,
@@ -9,6 +24,8 @@ pub fn generate_terrain_chunk(
     offset_x: i32,
     offset_z: i32,
     scale: f32,
+    config: &TerrainConfig,
+    trails: Option<&TrailNetwork>,
 ) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>) {
     let grid_size = size + 1; // Number of vertices per dimension
     let vertex_count = (grid_size * grid_size) as usize;
@@ -24,7 +41,7 @@ pub fn generate_terrain_chunk(
             let global_x = (x as f32 * scale) + offset_x as f32;
             let global_z = (z as f32 * scale) + offset_z as f32;
 
-            let (height, base_color) = get_height_at(global_x, global_z, seed);
+            let (height, base_color) = get_height_at(global_x, global_z, seed, config, trails);
 
             // Global position for the mesh
             // We use global coordinates so the chunks align perfectly without needing model matrices
@@ -36,7 +53,115 @@ pub fn generate_terrain_chunk(
     // Generate indices for triangles
     let triangle_count = (size * size * 2) as usize;
     let mut indices = Vec::with_capacity(triangle_count * 3);
+    push_grid_indices(&mut indices, size);
+
+    // Calculate smooth normals on a grid padded by one ring of neighbor samples,
+    // so border vertices average the same faces as in the adjacent chunk (no lighting seams)
+    let padded_size = grid_size + 2;
+    let mut padded_positions = Vec::with_capacity((padded_size * padded_size) as usize);
+    for z in 0..padded_size {
+        for x in 0..padded_size {
+            let inner_x = x.wrapping_sub(1);
+            let inner_z = z.wrapping_sub(1);
+            if inner_x < grid_size && inner_z < grid_size {
+                padded_positions.push(positions[(inner_z * grid_size + inner_x) as usize]);
+            } else {
+                let global_x = (x as f32 - 1.0) * scale + offset_x as f32;
+                let global_z = (z as f32 - 1.0) * scale + offset_z as f32;
+                let (height, _) = get_height_at(global_x, global_z, seed, config, trails);
+                padded_positions.push([global_x, height, global_z]);
+            }
+        }
+    }
+    let mut padded_indices = Vec::with_capacity(((padded_size - 1) * (padded_size - 1) * 6) as usize);
+    push_grid_indices(&mut padded_indices, padded_size - 1);
+    let padded_normals = calculate_smooth_normals(&padded_positions, &padded_indices, padded_size);
+
+    let normals = (0..grid_size)
+        .flat_map(|z| (0..grid_size).map(move |x| ((z + 1) * padded_size + x + 1) as usize))
+        .map(|i| padded_normals[i])
+        .collect();
+
+    // VERIFICATION OUTPUT
+    if offset_x == 0 && offset_z == 0 {
+        println!("[VERIFY] Generated Terrain Chunk: {}x{} (Scale {}) at ({}, {})", size, size, scale, offset_x, offset_z);
+        println!("[VERIFY] Vertex Count: {}", positions.len());
+        println!("[VERIFY] Triangle Count: {}", indices.len() / 3);
+    }
 
+    (positions, colors, normals, indices)
+}
+
+/// Height over which a splat layer fades in around its line (rock, snow)
+const SPLAT_FADE: f32 = 5.0;
+
+/// Terrain texture blend weights at a vertex: (sand, grass, rock, snow), summing to 1
+///
+/// Sand covers the seabed and beach and gives way to grass across the scrub band (the
+/// `config` biome thresholds); bare rock takes over around `rock_line` and snow around
+/// `snow_line`. Steep slopes turn to rock in the shader, which knows the final normal.
+pub fn splat_weights(x: f32, z: f32, height: f32, seed: u32, config: &TerrainConfig) -> [f32; 4] {
+    let t = biome_t(x, z, seed, config);
+    let beach_middle = (config.beach_start + config.scrub_start) * 0.5;
+    let scrub_middle = (config.scrub_start + config.forest_start) * 0.5;
+    let underwater = 1.0 - smoothstep(-0.5, 0.5, height); // Seabed and riverbeds
+    let sand = (1.0 - smoothstep(beach_middle, scrub_middle, t)).max(underwater);
+
+    let snow = smoothstep(config.snow_line - SPLAT_FADE, config.snow_line + SPLAT_FADE, height);
+    let rock = smoothstep(config.rock_line - SPLAT_FADE, config.rock_line + SPLAT_FADE, height) * (1.0 - snow);
+    let ground = 1.0 - rock - snow;
+    [sand * ground, (1.0 - sand) * ground, rock, snow]
+}
+
+/// `splat_weights` for every vertex of a chunk mesh (positions as `generate_terrain_chunk` makes them)
+pub fn terrain_splat(positions: &[[f32; 3]], seed: u32, config: &TerrainConfig) -> Vec<[f32; 4]> {
+    positions.iter().map(|&[x, y, z]| splat_weights(x, z, y, seed, config)).collect()
+}
+
+/// Hang a vertical skirt `depth` units deep from the border of a chunk mesh
+///
+/// Neighbouring chunks at different resolutions don't share their edge vertices, so their
+/// borders leave thin cracks where the coarser one cuts straight across a bump. The skirt
+/// fills them from below; `depth` must exceed the largest height gap between the two edges.
+/// `size` is the chunk's quads per side, as passed to `generate_terrain_chunk`; skirt
+/// vertices are appended after the grid, so the first `(size + 1)^2` stay the heightfield.
+pub fn add_terrain_skirts(
+    positions: &mut Vec<[f32; 3]>,
+    colors: &mut Vec<[f32; 3]>,
+    normals: &mut Vec<[f32; 3]>,
+    splat: &mut Vec<[f32; 4]>,
+    indices: &mut Vec<u32>,
+    size: u32,
+    depth: f32,
+) {
+    let grid_size = size + 1;
+    // Walk the border once around: along z = 0, x = size, z = size (back), x = 0 (back)
+    let border: Vec<u32> = (0..size)
+        .chain((0..size).map(|z| z * grid_size + size))
+        .chain((1..=size).rev().map(|x| size * grid_size + x))
+        .chain((1..=size).rev().map(|z| z * grid_size))
+        .collect();
+
+    let base = positions.len() as u32;
+    for &edge in &border {
+        let [x, y, z] = positions[edge as usize];
+        positions.push([x, y - depth, z]);
+        colors.push(colors[edge as usize]);
+        normals.push(normals[edge as usize]);
+        splat.push(splat[edge as usize]);
+    }
+    let count = border.len() as u32;
+    for i in 0..count {
+        let next = (i + 1) % count;
+        let (top_a, top_b) = (border[i as usize], border[next as usize]);
+        let (bottom_a, bottom_b) = (base + i, base + next);
+        indices.extend_from_slice(&[top_a, bottom_a, top_b, top_b, bottom_a, bottom_b]);
+    }
+}
+
+/// Two triangles per quad for a `size` x `size` grid of quads (row-major vertices)
+fn push_grid_indices(indices: &mut Vec<u32>, size: u32) {
+    let grid_size = size + 1;
     for z in 0..size {
         for x in 0..size {
             let top_left = z * grid_size + x;
@@ -53,18 +178,6 @@ pub fn generate_terrain_chunk(
             indices.push(bottom_right);
         }
     }
-
-    // Calculate smooth normals
-    let normals = calculate_smooth_normals(&positions, &indices, grid_size);
-
-    // VERIFICATION OUTPUT
-    if offset_x == 0 && offset_z == 0 {
-        println!("[VERIFY] Generated Terrain Chunk: {}x{} (Scale {}) at ({}, {})", size, size, scale, offset_x, offset_z);
-        println!("[VERIFY] Vertex Count: {}", positions.len());
-        println!("[VERIFY] Triangle Count: {}", indices.len() / 3);
-    }
-
-    (positions, colors, normals, indices)
 }
 
 /// Calculate smooth vertex normals by averaging face normals
@@ -111,56 +224,232 @@ fn calculate_smooth_normals(positions: &[[f32; 3]], indices: &[u32], _grid_size:
     normals
 }
 
-/// Calculate height and color at a specific global position
-pub fn get_height_at(x: f32, z: f32, seed: u32) -> (f32, [f32; 3]) {
+/// Tunable terrain shape: biome layout, the sea, and the layers carved into the land
+///
+/// `generate_terrain_chunk` and the height/biome queries take one of these, so each world
+/// can shape its own coastline; keep it fixed for the life of a world. Placement
+/// (trees, rocks, buildings) and `sample_terrain` use the default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainConfig {
+    /// Frequency of the biome noise (smaller = broader biomes, slower transitions)
+    pub biome_scale: f32,
+    /// Fall of the biome blend toward the eastern sea, per world unit along X
+    pub gradient_strength: f32,
+    /// Biome blend thresholds: ocean below `beach_start`, then beach, scrub and forest
+    pub beach_start: f32,
+    pub scrub_start: f32,
+    pub forest_start: f32,
+    /// Frequency of the small-scale height detail
+    pub detail_frequency: f32,
+    /// Waterline height in the natural terrain; raising it floods the land
+    /// (the terrain is shifted down so the water itself stays at 0.0)
+    pub sea_level: f32,
+    /// Domain warp, in units of the warp field: bends coastlines and biome edges into
+    /// meanders and breaks up round hills; 0.0 disables it
+    pub warp_strength: f32,
+    /// How many rivers cut across the land (1.0 = a channel every few hundred units; 0.0 = none)
+    pub river_density: f32,
+    /// Riverbed depth below the waterline at the channel center (world units)
+    pub river_depth: f32,
+    /// Channel width, as a fraction of the river noise range (the banks are twice this again)
+    pub river_width: f32,
+    /// Heights where the ground turns to bare rock, then snow (see `splat_weights`)
+    pub rock_line: f32,
+    pub snow_line: f32,
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            biome_scale: 0.002,
+            gradient_strength: 0.001, // Transition zone ~1000 units
+            beach_start: 0.45,
+            scrub_start: 0.55,
+            forest_start: 0.65,
+            detail_frequency: 0.05,
+            sea_level: 0.0,
+            warp_strength: 0.35,
+            river_density: 1.0,
+            river_depth: 1.5,
+            river_width: 0.015,
+            rock_line: 40.0,
+            snow_line: 55.0,
+        }
+    }
+}
+
+/// Scale of the warp field: ~250 unit features, displacing up to ~90 units at the default strength
+const TERRAIN_WARP_FREQUENCY: f32 = 0.004;
+
+/// World position the terrain noise is actually sampled at for (x, z)
+fn warp_position(x: f32, z: f32, seed: u32, strength: f32) -> Vec2 {
+    if strength == 0.0 {
+        return Vec2::new(x, z); // Exact, without the scaling round trip
+    }
+    let p = Vec2::new(x, z) * TERRAIN_WARP_FREQUENCY;
+    noise_util::domain_warp(p, strength, WorldSeed::new(seed).derive("terrain_warp")) / TERRAIN_WARP_FREQUENCY
+}
+
+/// Biome blend value at a global position: 0.0 = open ocean, 1.0 = deep forest
+///
+/// Thresholds come from `config`: ocean below `beach_start`, then beach, scrub, forest.
+/// Anything that needs biome identity should call this rather than re-deriving it.
+pub fn biome_t(x: f32, z: f32, seed: u32, config: &TerrainConfig) -> f32 {
+    let p = warp_position(x, z, seed, config.warp_strength);
+    biome_t_unwarped(p.x, p.y, seed, config)
+}
+
+/// `biome_t` at exactly (x, z), without the domain warp
+fn biome_t_unwarped(x: f32, z: f32, seed: u32, config: &TerrainConfig) -> f32 {
     // 1. Biome Noise (Low Frequency)
-    let biome_scale = 0.002; // Slower transitions
+    let biome_scale = config.biome_scale;
     let biome_noise = noise_util::fbm(
         Vec2::new(x * biome_scale, z * biome_scale),
-        3, 2.0, 0.5, seed + 100
+        3, 2.0, 0.5, WorldSeed::new(seed).derive("biome")
     );
     let noise_norm = (biome_noise + 1.0) * 0.5;
 
     // 2. Eastern Sea Gradient (Global X based)
-    // We want a gentle curve.
     // Positive X -> Ocean. Negative X -> Inland.
-    // Transition zone ~1000 units.
-    let gradient = -x * 0.001; 
-    
+    let gradient = -x * config.gradient_strength;
+
     // Combined 't' value determines "Land vs Sea"
     let t = noise_norm * 0.3 + gradient + 0.5; // Bias to 0.5 at x=0
-    let t = t.clamp(0.0, 1.0);
+    t.clamp(0.0, 1.0)
+}
+
+/// Calculate height and color at a specific global position
+/// Includes trail flattening and dirt color when `trails` is given
+pub fn get_height_at(x: f32, z: f32, seed: u32, config: &TerrainConfig, trails: Option<&TrailNetwork>) -> (f32, [f32; 3]) {
+    let (height, color) = base_height_at(x, z, seed, config);
+    match trails {
+        Some(network) => network.apply(x, z, height, color),
+        None => (height, color),
+    }
+}
+
+/// March a ray against the terrain heightfield (`get_height_at`, default config)
+/// Returns the first point where the ray passes below the ground within `max_dist`,
+/// or None if it never does (sky, parallel to the ground) or starts underground.
+pub fn raycast_terrain(seed: u32, origin: Vec3, dir: Vec3, max_dist: f32, trails: Option<&TrailNetwork>) -> Option<Vec3> {
+    let dir = dir.normalize_or_zero();
+    if dir == Vec3::ZERO {
+        return None;
+    }
+    let config = TerrainConfig::default();
+    let above = |t: f32| {
+        let p = origin + dir * t;
+        p.y - get_height_at(p.x, p.z, seed, &config, trails).0
+    };
+    if above(0.0) < 0.0 {
+        return None;
+    }
+
+    // Coarse march (1 unit), then bisect the bracketing step
+    let step: f32 = 1.0;
+    let mut prev = 0.0;
+    let mut t = step;
+    while prev < max_dist {
+        t = t.min(max_dist);
+        if above(t) <= 0.0 {
+            let (mut lo, mut hi) = (prev, t);
+            for _ in 0..12 {
+                let mid = (lo + hi) * 0.5;
+                if above(mid) > 0.0 { lo = mid; } else { hi = mid; }
+            }
+            return Some(origin + dir * hi);
+        }
+        prev = t;
+        t += step;
+    }
+    None
+}
+
+/// Base frequency of the river network (at `river_density` 1.0)
+const RIVER_FREQUENCY: f32 = 0.002;
+
+/// Rivers run mostly east-west, down the sea gradient: the flow noise is stretched along X by this
+const RIVER_STRETCH: f32 = 3.0;
+
+/// Riverbed silt, blended up the banks
+const RIVERBED_COLOR: [f32; 3] = [0.30, 0.26, 0.18];
+
+/// Carve rivers into a terrain sample
+///
+/// Channels follow the zero crossings of a low-frequency fbm "flow" field (its ridged
+/// form, 1 - |n|, peaks along them), so they form long connected lines; stretching the field
+/// along X turns them toward the eastern sea. Inside the channel the ground drops to
+/// `river_depth` below the waterline (0.0), and the banks ease back up to the terrain.
+/// Returns the input unchanged where no river runs.
+pub fn carve_rivers(x: f32, z: f32, height: f32, color: [f32; 3], seed: u32, config: &TerrainConfig) -> (f32, [f32; 3]) {
+    if config.river_density <= 0.0 || config.river_width <= 0.0 {
+        return (height, color);
+    }
+
+    let frequency = RIVER_FREQUENCY * config.river_density;
+    let flow = noise_util::fbm(
+        Vec2::new(x * frequency / RIVER_STRETCH, z * frequency),
+        3, 2.0, 0.5, WorldSeed::new(seed).derive("rivers")
+    ).abs();
+
+    let channel = config.river_width;
+    let bank = channel * 3.0;
+    if flow >= bank {
+        return (height, color);
+    }
+
+    // Parabolic bed up to the waterline at the channel edge, then a smooth rise to the terrain
+    let (carved, wet) = if flow < channel {
+        let across = flow / channel;
+        (-config.river_depth * (1.0 - across * across), 1.0)
+    } else {
+        let rise = smoothstep(channel, bank, flow);
+        (lerp(0.0, height.max(0.0), rise), 1.0 - rise)
+    };
+
+    if carved >= height {
+        return (height, color); // Already lower (sea floor, existing hollows)
+    }
+    (carved, lerp_color(color, RIVERBED_COLOR, wet * 0.8))
+}
+
+/// Natural terrain height and color, ignoring trails
+pub fn base_height_at(x: f32, z: f32, seed: u32, config: &TerrainConfig) -> (f32, [f32; 3]) {
+    let p = warp_position(x, z, seed, config.warp_strength);
+    let (x, z) = (p.x, p.y);
+    let t = biome_t_unwarped(x, z, seed, config);
 
     // 3. Detail Noise
+    let detail_frequency = config.detail_frequency;
     let detail_noise = noise_util::fbm(
-        Vec2::new(x * 0.05, z * 0.05),
-        4, 2.0, 0.5, seed
+        Vec2::new(x * detail_frequency, z * detail_frequency),
+        4, 2.0, 0.5, WorldSeed::new(seed).derive("terrain_detail")
     );
+    let TerrainConfig { beach_start, scrub_start, forest_start, .. } = *config;
 
     // 4. Biome Definitions (Roanoke Spec)
-    let (base_height, height_mult, base_color) = if t < 0.45 {
+    let (base_height, height_mult, base_color) = if t < beach_start {
         // Ocean / Shallow Water
         // Add sandbars using detail noise
         let sandbar = if detail_noise > 0.5 { 0.5 } else { 0.0 };
-        let water_depth = lerp(-5.0, -0.5, t / 0.45);
+        let water_depth = lerp(-5.0, -0.5, t / beach_start);
         let h = water_depth + sandbar;
         
         // Color: Turquoise at shore, Teal deep
-        let depth_factor = (t / 0.45).clamp(0.0, 1.0);
+        let depth_factor = (t / beach_start).clamp(0.0, 1.0);
         let c = lerp_color([0.05, 0.3, 0.4], [0.2, 0.8, 0.8], depth_factor);
         (h, 0.1, c)
-    } else if t < 0.55 {
+    } else if t < scrub_start {
         // Beach / Dunes
-        let blend = (t - 0.45) / 0.1;
+        let blend = (t - beach_start) / (scrub_start - beach_start);
         let h = lerp(0.0, 2.0, blend);
         let m = 0.2; // Soft dunes
         // Warm Sandy Brown (darker, less white)
         let c = [0.76, 0.60, 0.35];
         (h, m, c)
-    } else if t < 0.65 {
+    } else if t < forest_start {
         // Subtropical Scrub
-        // Shortened from 0.75 to 0.65 to reduce middle ground
-        let blend = (t - 0.55) / 0.1; // Adjusted divisor for new range (0.1 width)
+        let blend = (t - scrub_start) / (forest_start - scrub_start);
         let h = lerp(2.0, 6.0, blend);
         let m = 1.0; // Rougher
         // Olive Green - Darkened significantly
@@ -170,7 +459,7 @@ pub fn get_height_at(x: f32, z: f32, seed: u32) -> (f32, [f32; 3]) {
         (h, m, c)
     } else {
         // Coastal Forest
-        let blend = (t - 0.65) / 0.35; // Adjusted start and divisor (remainder of 1.0)
+        let blend = (t - forest_start) / (1.0 - forest_start);
         let h = lerp(6.0, 15.0, blend);
         let m = 2.0;
         // Deep Green
@@ -178,10 +467,16 @@ pub fn get_height_at(x: f32, z: f32, seed: u32) -> (f32, [f32; 3]) {
         (h, m, c)
     };
 
-    // Apply height
-    let height = base_height + detail_noise * height_mult;
+    // Apply height, measured from the waterline
+    let height = base_height + detail_noise * height_mult - config.sea_level;
+
+    // 5. Rivers (in the warped frame, so they bend with the biome edges)
+    carve_rivers(x, z, height, base_color, seed, config)
+}
 
-    (height, base_color)
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
 }
 
 fn lerp(a: f32, b: f32, t: f32) -> f32 {
@@ -202,7 +497,7 @@ mod tests {
 
     #[test]
     fn test_mesh_generation() {
-        let (positions, colors, normals, indices) = generate_terrain_chunk(1587, 64, 0, 0, 1.0);
+        let (positions, colors, normals, indices) = generate_terrain_chunk_default(1587, 64, 0, 0, 1.0);
 
         // Verify dimensions
         assert_eq!(positions.len(), 65 * 65);
@@ -211,9 +506,84 @@ mod tests {
         assert_eq!(indices.len(), 64 * 64 * 2 * 3);
     }
 
+    #[test]
+    fn test_normals_match_across_chunk_border() {
+        // 32 quads at scale 8 = 256 units per chunk; (256, 256) lies on the shared edge
+        let (pos_a, _, nrm_a, _) = generate_terrain_chunk_default(1587, 32, 0, 0, 8.0);
+        let (pos_b, _, nrm_b, _) = generate_terrain_chunk_default(1587, 32, 256, 0, 8.0);
+
+        let a = 32 * 33 + 32; // Last column, last row of chunk A
+        let b = 32 * 33; // First column, last row of chunk B
+        assert_eq!(pos_a[a], [256.0, pos_a[a][1], 256.0]);
+        assert_eq!(pos_a[a], pos_b[b]);
+
+        let diff = Vec3::from_array(nrm_a[a]) - Vec3::from_array(nrm_b[b]);
+        assert!(diff.length() < 1e-4, "normals diverge at the border: {:?} vs {:?}", nrm_a[a], nrm_b[b]);
+    }
+
+    #[test]
+    fn test_skirts_hang_below_every_border_vertex() {
+        let (mut positions, mut colors, mut normals, mut indices) = generate_terrain_chunk_default(1587, 16, 0, 0, 16.0);
+        let grid = positions[..17 * 17].to_vec();
+        let mut splat = terrain_splat(&positions, 1587, &TerrainConfig::default());
+        add_terrain_skirts(&mut positions, &mut colors, &mut normals, &mut splat, &mut indices, 16, 32.0);
+
+        // Grid untouched, one skirt vertex per border vertex, two triangles per border segment
+        assert_eq!(&positions[..17 * 17], &grid[..]);
+        assert_eq!(positions.len(), 17 * 17 + 16 * 4);
+        assert_eq!((colors.len(), normals.len(), splat.len()), (positions.len(), positions.len(), positions.len()));
+        assert_eq!(indices.len(), 16 * 16 * 6 + 16 * 4 * 6);
+        assert!(indices.iter().all(|&i| (i as usize) < positions.len()));
+
+        for skirt in &positions[17 * 17..] {
+            let top = grid.iter().find(|p| p[0] == skirt[0] && p[2] == skirt[2]).unwrap();
+            assert!(skirt[0] == 0.0 || skirt[0] == 256.0 || skirt[2] == 0.0 || skirt[2] == 256.0);
+            assert_eq!(skirt[1], top[1] - 32.0);
+        }
+    }
+
+    #[test]
+    fn test_splat_follows_biomes_and_height() {
+        let config = TerrainConfig::default();
+        let seed = 12345;
+        let sum = |w: [f32; 4]| w.iter().sum::<f32>();
+
+        // Open sea floor is sand, deep inland is grass
+        let sea = splat_weights(1000.0, 0.0, -3.0, seed, &config);
+        assert!((sum(sea) - 1.0).abs() < 1e-5 && sea[0] > 0.99);
+        let inland = splat_weights(-1000.0, 0.0, 10.0, seed, &config);
+        assert!((sum(inland) - 1.0).abs() < 1e-5 && inland[1] > 0.99);
+
+        // Rock, then snow, with height
+        let high = splat_weights(-1000.0, 0.0, config.rock_line + SPLAT_FADE, seed, &config);
+        assert!(high[2] > 0.99);
+        let peak = splat_weights(-1000.0, 0.0, config.snow_line + SPLAT_FADE, seed, &config);
+        assert!((sum(peak) - 1.0).abs() < 1e-5 && peak[3] > 0.99);
+    }
+
+    #[test]
+    fn test_raycast_terrain() {
+        let seed = 1587;
+        let ground = get_height_at(-300.0, 40.0, seed, &TerrainConfig::default(), None).0;
+
+        // Straight down lands on the heightfield
+        let hit = raycast_terrain(seed, Vec3::new(-300.0, ground + 50.0, 40.0), Vec3::NEG_Y, 100.0, None).unwrap();
+        assert!((hit.y - ground).abs() < 0.01);
+        assert_eq!((hit.x, hit.z), (-300.0, 40.0));
+
+        // Sloped rays hit within the march range and sit on the surface
+        let hit = raycast_terrain(seed, Vec3::new(-300.0, ground + 20.0, 40.0), Vec3::new(1.0, -0.5, 0.3), 200.0, None).unwrap();
+        assert!((hit.y - get_height_at(hit.x, hit.z, seed, &TerrainConfig::default(), None).0).abs() < 0.05);
+
+        // Sky, parallel to the ground, out of range
+        assert_eq!(raycast_terrain(seed, Vec3::new(0.0, 200.0, 0.0), Vec3::Y, 500.0, None), None);
+        assert_eq!(raycast_terrain(seed, Vec3::new(0.0, 200.0, 0.0), Vec3::X, 500.0, None), None);
+        assert_eq!(raycast_terrain(seed, Vec3::new(-300.0, ground + 50.0, 40.0), Vec3::NEG_Y, 10.0, None), None);
+    }
+
     #[test]
     fn test_small_mesh() {
-        let (positions, colors, normals, indices) = generate_terrain_chunk(42, 4, 0, 0, 1.0);
+        let (positions, colors, normals, indices) = generate_terrain_chunk_default(42, 4, 0, 0, 1.0);
 
         // 5x5 grid = 25 vertices
         assert_eq!(positions.len(), 25);
@@ -227,10 +597,10 @@ mod tests {
     #[test]
     fn test_eastern_sea_gradient() {
         // Generate West Chunk (Spawn)
-        let (west_pos, _, _, _) = generate_terrain_chunk(12345, 64, 0, 0, 1.0);
+        let (west_pos, _, _, _) = generate_terrain_chunk_default(12345, 64, 0, 0, 1.0);
 
         // Generate East Chunk (Far East)
-        let (east_pos, _, _, _) = generate_terrain_chunk(12345, 64, 1000, 0, 1.0);
+        let (east_pos, _, _, _) = generate_terrain_chunk_default(12345, 64, 1000, 0, 1.0);
         
         // Calculate average height
         let west_avg: f32 = west_pos.iter().map(|p| p[1]).sum::<f32>() / west_pos.len() as f32;
@@ -241,21 +611,112 @@ mod tests {
         // The East side should be lower (Ocean)
         assert!(east_avg < west_avg, "East side should be lower than West side due to gradient");
     }
+
+    #[test]
+    fn test_sea_level_floods_land() {
+        // Inland, so most of the chunk is above water either way
+        let average_height = |config: &TerrainConfig| {
+            let (positions, _, _, _) = generate_terrain_chunk(12345, 32, -512, 0, 4.0, config, None);
+            positions.iter().map(|p| p[1]).sum::<f32>() / positions.len() as f32
+        };
+        let default = TerrainConfig::default();
+        let raised = TerrainConfig { sea_level: 2.0, ..default };
+        assert!(average_height(&raised) < average_height(&default));
+
+        // The default config is what generate_terrain_chunk_default uses
+        assert_eq!(
+            generate_terrain_chunk(12345, 4, -512, 0, 4.0, &default, None).0,
+            generate_terrain_chunk_default(12345, 4, -512, 0, 4.0).0
+        );
+    }
+
+    #[test]
+    fn test_biome_t_follows_gradient() {
+        // Far inland is forest, far east is ocean
+        let config = TerrainConfig::default();
+        assert!(biome_t(-1000.0, 0.0, 12345, &config) > config.forest_start);
+        assert!(biome_t(1000.0, 0.0, 12345, &config) < config.beach_start);
+    }
+
+    #[test]
+    fn test_domain_warp_changes_terrain() {
+        let seed = 12345;
+        let points = [(-300.0, 40.0), (120.0, -75.0), (510.0, 260.0), (-64.0, 900.0)];
+
+        // No warp samples the plain noise; warping moves the samples
+        for &(x, z) in &points {
+            assert_eq!(warp_position(x, z, seed, 0.0), Vec2::new(x, z));
+        }
+        let warped = TerrainConfig::default();
+        let unwarped = TerrainConfig { warp_strength: 0.0, ..warped };
+        let changed = points.iter()
+            .filter(|&&(x, z)| base_height_at(x, z, seed, &warped).0 != base_height_at(x, z, seed, &unwarped).0)
+            .count();
+        assert!(changed > 0);
+
+        // Biomes are warped the same way
+        let (x, z) = points[0];
+        let p = warp_position(x, z, seed, warped.warp_strength);
+        assert_eq!(biome_t(x, z, seed, &warped), biome_t_unwarped(p.x, p.y, seed, &warped));
+    }
+
+    #[test]
+    fn test_rivers_cut_below_waterline_inland() {
+        // Deep forest, ~1000 units from the sea, with a river running through it for this seed
+        let seed = 1587;
+        let (positions, _, _, _) = generate_terrain_chunk_default(seed, 64, -1024, 0, 4.0);
+        let config = TerrainConfig::default();
+        assert!(positions.iter().all(|p| biome_t(p[0], p[2], seed, &config) > config.forest_start));
+
+        let wet = positions.iter().filter(|p| p[1] < 0.0).count();
+        assert!(wet > 0, "no river cells below the waterline");
+
+        // All of it is the river's doing, and deeper rivers dig deeper
+        let no_rivers = TerrainConfig { river_density: 0.0, ..Default::default() };
+        let deep = TerrainConfig { river_depth: 4.0, ..Default::default() };
+        let mut deepest = (0.0f32, 0.0f32);
+        for p in &positions {
+            assert!(base_height_at(p[0], p[2], seed, &no_rivers).0 >= 0.0);
+            deepest.0 = deepest.0.min(p[1]);
+            deepest.1 = deepest.1.min(base_height_at(p[0], p[2], seed, &deep).0);
+        }
+        assert!(deepest.1 < deepest.0);
+    }
+
+    #[test]
+    fn test_detritus_varies_by_biome() {
+        // Forest (inland) and coast (east) chunks both produce clutter, and differ
+        let (forest_pos, forest_nrm, forest_uv, forest_idx) = generate_detritus_for_chunk(12345, 256.0, -1024.0, 0.0, None);
+        let (coast_pos, _, _, coast_idx) = generate_detritus_for_chunk(12345, 256.0, 256.0, 0.0, None);
+
+        assert!(!forest_pos.is_empty());
+        assert!(!coast_pos.is_empty());
+        assert_eq!(forest_pos.len(), forest_nrm.len());
+        assert_eq!(forest_pos.len(), forest_uv.len());
+        assert!(forest_idx.iter().all(|&i| (i as usize) < forest_pos.len()));
+        assert!(coast_idx.iter().all(|&i| (i as usize) < coast_pos.len()));
+        assert_ne!(forest_pos.len(), coast_pos.len());
+    }
 }
 
-/// Generate detritus (logs, driftwood, dead trees) for a chunk
+/// A detritus mesh as (positions, normals, uvs, indices)
+pub type DetritusArrays = (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>, Vec<u32>);
+
+/// Generate detritus (ground clutter) for a chunk, varied per biome:
+/// - Shallows: dead trees, reed clusters
+/// - Beach: driftwood, seaweed along the waterline, shells
+/// - Forest: fallen logs, stumps, ferns
+///
 /// Returns (positions, normals, uvs, indices)
 pub fn generate_detritus_for_chunk(
     seed: u32,
     size: f32,
     offset_x: f32,
     offset_z: f32,
-) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>, Vec<u32>) {
-    let mut positions = Vec::new();
-    let mut normals = Vec::new();
-    let mut uvs = Vec::new();
-    let mut indices = Vec::new();
-    let mut index_offset = 0;
+    trails: Option<&TrailNetwork>,
+) -> DetritusArrays {
+    let mut mesh = DetritusMesh::default();
+    let stream = WorldSeed::new(seed).derive("detritus");
 
     // Use a fixed grid for potential spawn points
     let grid_step = 4.0; // Check every 4 meters
@@ -265,140 +726,257 @@ pub fn generate_detritus_for_chunk(
         for x in 0..steps {
             let global_x = offset_x + (x as f32 * grid_step);
             let global_z = offset_z + (z as f32 * grid_step);
+            let cell_x = global_x.floor() as i32;
+            let cell_z = global_z.floor() as i32;
 
             // Add some jitter to position
-            let jitter_x = noise_util::hash(seed + (global_x as u32) * 73856093) * 3.0;
-            let jitter_z = noise_util::hash(seed + (global_z as u32) * 19349663) * 3.0;
+            let jitter_x = cell_hash(stream, cell_x, cell_z, 0) * 3.0;
+            let jitter_z = cell_hash(stream, cell_x, cell_z, 1) * 3.0;
             let px = global_x + jitter_x;
             let pz = global_z + jitter_z;
 
-            // Get biome info
-            // Replicating get_height_at logic partially to get 't'
-            let biome_scale = 0.002;
-            let biome_noise = noise_util::fbm(
-                Vec2::new(px * biome_scale, pz * biome_scale),
-                3, 2.0, 0.5, seed + 100
-            );
-            let noise_norm = (biome_noise + 1.0) * 0.5;
-            let gradient = -px * 0.001; 
-            let t = (noise_norm * 0.3 + gradient + 0.5).clamp(0.0, 1.0);
-
-            let (terrain_height, _) = get_height_at(px, pz, seed);
-
-            // Spawn Logic based on Biome
-            let spawn_chance = noise_util::hash(seed + (px as u32) ^ (pz as u32));
-            
-            if t < 0.45 {
+            let sample = sample_terrain(seed, px, pz, trails);
+            let terrain_height = sample.height;
+
+            // Spawn roll picks the item; variant roll drives its size/orientation
+            let spawn_chance = cell_hash(stream, cell_x, cell_z, 2);
+
+            // Trails are kept clear
+            if trails::trail_influence(px, pz, trails) > 0.2 {
+                continue;
+            }
+            let variant = cell_hash(stream, cell_x, cell_z, 3);
+            let ground = Vec3::new(px, terrain_height, pz);
+
+            if sample.biome == Biome::Ocean {
                 // Ocean / Shallow Water (Inlets)
-                // Spawn dead trees in shallow water
-                if terrain_height > -2.0 && terrain_height < 0.5 && spawn_chance > 0.95 {
+                let in_shallows = terrain_height > -2.0 && terrain_height < 0.5;
+                if in_shallows && spawn_chance > 0.95 {
                     // Dead Tree (Vertical)
-                    add_cylinder(
-                        &mut positions, &mut normals, &mut uvs, &mut indices, &mut index_offset,
-                        Vec3::new(px, terrain_height, pz),
+                    mesh.add_cylinder(
+                        ground,
                         0.3, // Radius
                         4.0 + spawn_chance * 3.0, // Height
                         Vec3::Y, // Up
-                        8 // Segments
+                        8, // Segments
                     );
+                } else if in_shallows && terrain_height > -1.5 && spawn_chance > 0.8 {
+                    mesh.add_reeds(ground, variant);
                 }
-            } else if t < 0.55 {
-                // Beach
-                // Spawn driftwood (scattered sticks)
+            } else if sample.biome == Biome::Beach {
                 if spawn_chance > 0.92 {
                     // Driftwood (Small, random orientation)
                     let rot_x = (spawn_chance * 10.0).sin();
                     let rot_z = (spawn_chance * 10.0).cos();
                     let axis = Vec3::new(rot_x, 0.1, rot_z).normalize();
-                    
-                    add_cylinder(
-                        &mut positions, &mut normals, &mut uvs, &mut indices, &mut index_offset,
-                        Vec3::new(px, terrain_height + 0.1, pz),
+
+                    mesh.add_cylinder(
+                        ground + Vec3::Y * 0.1,
                         0.1, // Radius
                         1.5, // Length
                         axis,
-                        6 // Segments
+                        6, // Segments
                     );
+                } else if terrain_height < 0.6 && spawn_chance > 0.84 {
+                    // Seaweed washed up along the waterline
+                    mesh.add_seaweed(ground, variant);
+                } else if spawn_chance > 0.86 {
+                    // Shell (small flattened cone)
+                    let tilt = Vec3::new(variant - 0.5, 1.0, 0.5 - variant).normalize();
+                    mesh.add_cone(ground, 0.08 + variant * 0.06, 0.06, tilt, 6);
                 }
-            } else if t > 0.75 {
-                // Forest
-                // Spawn fallen logs
-                if spawn_chance > 0.97 {
+            } else if matches!(sample.biome, Biome::Forest | Biome::Mountain) {
+                // Deep forest gets the big deadfall
+                let deep = sample.blend > 0.75;
+                if deep && spawn_chance > 0.97 {
                     // Fallen Log (Horizontal)
                     let angle = spawn_chance * std::f32::consts::PI * 2.0;
                     let axis = Vec3::new(angle.cos(), 0.0, angle.sin());
-                    
-                    add_cylinder(
-                        &mut positions, &mut normals, &mut uvs, &mut indices, &mut index_offset,
-                        Vec3::new(px, terrain_height + 0.3, pz),
+
+                    mesh.add_cylinder(
+                        ground + Vec3::Y * 0.3,
                         0.4, // Radius
                         3.0 + spawn_chance * 2.0, // Length
                         axis,
-                        8 // Segments
+                        8, // Segments
                     );
+                } else if deep && spawn_chance > 0.94 {
+                    // Stump: short, wide, capped
+                    let radius = 0.35 + variant * 0.2;
+                    let height = 0.4 + variant * 0.5;
+                    mesh.add_cylinder(ground + Vec3::Y * (height * 0.5 - 0.1), radius, height, Vec3::Y, 10);
+                    mesh.add_disc(ground + Vec3::Y * (height - 0.1), radius, 10);
+                } else if spawn_chance > 0.85 {
+                    mesh.add_fern(ground, variant);
                 }
             }
         }
     }
 
-    (positions, normals, uvs, indices)
+    mesh.into_arrays()
 }
 
-/// Helper to add a cylinder mesh
-fn add_cylinder(
-    positions: &mut Vec<[f32; 3]>,
-    normals: &mut Vec<[f32; 3]>,
-    uvs: &mut Vec<[f32; 2]>,
-    indices: &mut Vec<u32>,
-    index_offset: &mut u32,
-    center: Vec3,
-    radius: f32,
-    length: f32,
-    axis: Vec3,
-    segments: u32,
-) {
-    // Basis vectors for the cylinder cap
-    let up = axis.normalize();
-    let arbitrary = if up.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
-    let right = up.cross(arbitrary).normalize();
-    let forward = up.cross(right).normalize();
-
-    let half_len = length * 0.5;
-    let start = center - up * half_len;
-    let end = center + up * half_len;
-
-    // Generate vertices for the side
-    for i in 0..=segments {
-        let angle = (i as f32 / segments as f32) * std::f32::consts::PI * 2.0;
-        let x = angle.cos();
-        let z = angle.sin();
-
-        let normal = (right * x + forward * z).normalize();
-        let offset = normal * radius;
-
-        // Bottom vertex
-        positions.push((start + offset).to_array());
-        normals.push(normal.to_array());
-        uvs.push([i as f32 / segments as f32, 0.0]);
-
-        // Top vertex
-        positions.push((end + offset).to_array());
-        normals.push(normal.to_array());
-        uvs.push([i as f32 / segments as f32, 1.0]);
-    }
-
-    // Generate indices
-    for i in 0..segments {
-        let base = *index_offset + i * 2;
-        
-        indices.push(base);
-        indices.push(base + 1);
-        indices.push(base + 2);
+/// Deterministic [0, 1] hash for an integer grid cell (handles negative coordinates)
+fn cell_hash(seed: u32, x: i32, z: i32, salt: u32) -> f32 {
+    let h = seed
+        .wrapping_add((x as u32).wrapping_mul(73856093))
+        ^ (z as u32).wrapping_mul(19349663)
+        ^ salt.wrapping_mul(83492791);
+    noise_util::hash(h)
+}
+
+/// Mesh accumulator for detritus primitives
+#[derive(Default)]
+struct DetritusMesh {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl DetritusMesh {
+    fn into_arrays(self) -> DetritusArrays {
+        (self.positions, self.normals, self.uvs, self.indices)
+    }
+
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3, uv: [f32; 2]) -> u32 {
+        let index = self.positions.len() as u32;
+        self.positions.push(position.to_array());
+        self.normals.push(normal.to_array());
+        self.uvs.push(uv);
+        index
+    }
+
+    /// Orthonormal basis perpendicular to `axis`
+    fn basis(axis: Vec3) -> (Vec3, Vec3) {
+        let arbitrary = if axis.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+        let right = axis.cross(arbitrary).normalize();
+        let forward = axis.cross(right).normalize();
+        (right, forward)
+    }
+
+    /// Open cylinder centered on `center`, running along `axis`
+    fn add_cylinder(&mut self, center: Vec3, radius: f32, length: f32, axis: Vec3, segments: u32) {
+        // Basis vectors for the cylinder cap
+        let up = axis.normalize();
+        let (right, forward) = Self::basis(up);
+
+        let half_len = length * 0.5;
+        let start = center - up * half_len;
+        let end = center + up * half_len;
+        let base_index = self.positions.len() as u32;
+
+        // Generate vertices for the side
+        for i in 0..=segments {
+            let angle = (i as f32 / segments as f32) * std::f32::consts::PI * 2.0;
+            let normal = (right * angle.cos() + forward * angle.sin()).normalize();
+            let offset = normal * radius;
+            let u = i as f32 / segments as f32;
+
+            self.push_vertex(start + offset, normal, [u, 0.0]); // Bottom vertex
+            self.push_vertex(end + offset, normal, [u, 1.0]); // Top vertex
+        }
+
+        // Generate indices
+        for i in 0..segments {
+            let base = base_index + i * 2;
+            self.indices.extend_from_slice(&[base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+        }
+    }
+
+    /// Upward-facing disc (stump tops)
+    fn add_disc(&mut self, center: Vec3, radius: f32, segments: u32) {
+        let center_index = self.push_vertex(center, Vec3::Y, [0.5, 0.5]);
+        for i in 0..=segments {
+            let angle = (i as f32 / segments as f32) * std::f32::consts::PI * 2.0;
+            let (s, c) = angle.sin_cos();
+            self.push_vertex(center + Vec3::new(c, 0.0, s) * radius, Vec3::Y, [0.5 + c * 0.5, 0.5 + s * 0.5]);
+        }
+        for i in 0..segments {
+            let a = center_index + 1 + i;
+            // Counter-clockwise seen from above
+            self.indices.extend_from_slice(&[center_index, a + 1, a]);
+        }
+    }
+
+    /// Cone standing on `base` along `axis` (shells)
+    fn add_cone(&mut self, base: Vec3, radius: f32, height: f32, axis: Vec3, segments: u32) {
+        let up = axis.normalize();
+        let (right, forward) = Self::basis(up);
+        let apex = self.push_vertex(base + up * height, up, [0.5, 1.0]);
+
+        for i in 0..=segments {
+            let angle = (i as f32 / segments as f32) * std::f32::consts::PI * 2.0;
+            let radial = right * angle.cos() + forward * angle.sin();
+            let normal = (radial * height + up * radius).normalize();
+            self.push_vertex(base + radial * radius, normal, [i as f32 / segments as f32, 0.0]);
+        }
+        for i in 0..segments {
+            let a = apex + 1 + i;
+            self.indices.extend_from_slice(&[apex, a, a + 1]);
+        }
+    }
 
-        indices.push(base + 1);
-        indices.push(base + 3);
-        indices.push(base + 2);
+    /// Quad visible from both sides (detritus is drawn with back-face culling)
+    fn add_double_quad(&mut self, corners: [Vec3; 4], normal: Vec3) {
+        let uvs = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        let front = self.positions.len() as u32;
+        for (corner, uv) in corners.iter().zip(uvs) {
+            self.push_vertex(*corner, normal, uv);
+        }
+        let back = self.positions.len() as u32;
+        for (corner, uv) in corners.iter().zip(uvs) {
+            self.push_vertex(*corner, -normal, uv);
+        }
+        self.indices.extend_from_slice(&[front, front + 1, front + 2, front, front + 2, front + 3]);
+        self.indices.extend_from_slice(&[back, back + 2, back + 1, back, back + 3, back + 2]);
+    }
+
+    /// Cluster of thin upright stalks poking out of shallow water
+    fn add_reeds(&mut self, ground: Vec3, variant: f32) {
+        let stalks = 4 + (variant * 4.0) as u32;
+        for i in 0..stalks {
+            let angle = i as f32 * 2.39996 + variant * 6.0; // Golden-angle spread
+            let spread = 0.15 + (i as f32 / stalks as f32) * 0.35;
+            let height = 1.2 + noise_util::hash(i.wrapping_add((variant * 1000.0) as u32)) * 1.0;
+            let lean = Vec3::new(angle.cos() * 0.12, 1.0, angle.sin() * 0.12).normalize();
+            let foot = ground + Vec3::new(angle.cos() * spread, 0.0, angle.sin() * spread);
+            self.add_cylinder(foot + lean * (height * 0.5), 0.025, height, lean, 4);
+        }
     }
 
-    *index_offset += (segments + 1) * 2;
+    /// Strands of seaweed lying flat on the sand
+    fn add_seaweed(&mut self, ground: Vec3, variant: f32) {
+        let strands = 2 + (variant * 3.0) as u32;
+        for i in 0..strands {
+            let angle = variant * std::f32::consts::TAU + i as f32 * 1.1;
+            let dir = Vec3::new(angle.cos(), 0.0, angle.sin());
+            let side = Vec3::new(-dir.z, 0.0, dir.x) * 0.08;
+            let length = 0.6 + (i as f32 * 0.37 + variant).fract() * 0.6;
+            let base = ground + Vec3::Y * 0.03 + side * (i as f32 * 2.0);
+            let tip = base + dir * length;
+            self.add_double_quad([base - side, base + side, tip + side * 0.5, tip - side * 0.5], Vec3::Y);
+        }
+    }
+
+    /// Fan of arching fronds
+    fn add_fern(&mut self, ground: Vec3, variant: f32) {
+        let fronds = 5 + (variant * 3.0) as u32;
+        let length = 0.6 + variant * 0.5;
+        for i in 0..fronds {
+            let angle = (i as f32 / fronds as f32) * std::f32::consts::TAU + variant * 3.0;
+            let dir = Vec3::new(angle.cos(), 0.0, angle.sin());
+            let side = Vec3::new(-dir.z, 0.0, dir.x) * 0.1;
+
+            // Two segments: rising then drooping
+            let base = ground + Vec3::Y * 0.05;
+            let mid = base + dir * (length * 0.5) + Vec3::Y * (length * 0.45);
+            let tip = base + dir * length + Vec3::Y * (length * 0.2);
+
+            let normal = side.cross(mid - base).normalize();
+            self.add_double_quad([base - side * 0.3, base + side * 0.3, mid + side, mid - side], normal);
+            let normal = side.cross(tip - mid).normalize();
+            self.add_double_quad([mid - side, mid + side, tip + side * 0.2, tip - side * 0.2], normal);
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use glam::Vec2;
-use noise::{NoiseFn, Perlin};
+use croatoan_procgen::SeededRng;
+use noise::{NoiseFn, Perlin, Simplex};
 
 /// Fractional Brownian Motion (FBM) noise
 /// Combines multiple octaves of noise with decreasing amplitude
@@ -85,18 +86,136 @@ pub fn turbulence(point: Vec2, octaves: u32, lacunarity: f32, persistence: f32,
     value / max_value
 }
 
+/// Fractional Brownian Motion over any base noise
+/// `base(point, seed)` should return roughly [-1, 1] (e.g. `simplex`); the result is normalized the same way
+pub fn fbm_with<F: Fn(Vec2, u32) -> f32>(base: F, point: Vec2, octaves: u32, lacunarity: f32, persistence: f32, seed: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+
+    for _ in 0..octaves {
+        value += base(point * frequency, seed) * amplitude;
+        max_value += amplitude;
+
+        amplitude *= persistence;
+        frequency *= lacunarity;
+    }
+
+    value / max_value
+}
+
+/// Ridged Multifractal noise over any base noise in roughly [-1, 1]
+/// Same weighting as `ridged`
+pub fn ridged_with<F: Fn(Vec2, u32) -> f32>(base: F, point: Vec2, octaves: u32, lacunarity: f32, persistence: f32, seed: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut weight = 1.0;
+
+    for _ in 0..octaves {
+        let mut signal = 1.0 - base(point * frequency, seed).abs();
+        signal *= signal;
+        signal *= weight;
+        weight = signal.clamp(0.0, 1.0);
+
+        value += signal * amplitude;
+
+        amplitude *= persistence;
+        frequency *= lacunarity;
+    }
+
+    value
+}
+
+/// Domain warping: offset `point` by a pair of fbm values scaled by `strength`
+/// Sampling other noise at the warped point bends its features into meanders and swirls.
+/// `strength` is in the same units as `point`; 0 returns it unchanged.
+pub fn domain_warp(point: Vec2, strength: f32, seed: u32) -> Vec2 {
+    if strength == 0.0 {
+        return point;
+    }
+    // Decorrelated offsets for the two axes (arbitrary, just not a lattice multiple)
+    let offset = Vec2::new(
+        fbm(point, 3, 2.0, 0.5, seed),
+        fbm(point + Vec2::new(5.2, 1.3), 3, 2.0, 0.5, seed),
+    );
+    point + offset * strength
+}
+
+/// Simplex noise in [-1, 1]
+/// Smoother than Perlin with fewer grid-aligned artifacts, good for dunes and rolling ground
+pub fn simplex(point: Vec2, seed: u32) -> f32 {
+    Simplex::new(seed).get([point.x as f64, point.y as f64]) as f32
+}
+
+/// Worley (cellular) noise: distances to the nearest and second-nearest feature points
+/// One jittered feature point per unit cell. `f2 - f1` traces the cell borders
+/// (cracked mud, cobbles), `f1` alone gives round cells.
+pub fn worley(point: Vec2, seed: u32) -> (f32, f32) {
+    let cell = point.floor();
+    let mut f1 = f32::MAX;
+    let mut f2 = f32::MAX;
+
+    // The 3x3 neighborhood holds the nearest points in all but degenerate layouts
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let neighbor = cell + Vec2::new(dx as f32, dy as f32);
+            let feature = neighbor + Vec2::new(
+                hash_position(neighbor.x, neighbor.y, seed, 0),
+                hash_position(neighbor.x, neighbor.y, seed, 1),
+            );
+            let dist = point.distance(feature);
+            if dist < f1 {
+                f2 = f1;
+                f1 = dist;
+            } else if dist < f2 {
+                f2 = dist;
+            }
+        }
+    }
+
+    (f1, f2)
+}
+
 /// Simple hash function for deterministic randomness
 pub fn hash(n: u32) -> f32 {
     let mut n = n;
     n = (n << 13) ^ n;
-    n = n.wrapping_mul(n.wrapping_mul(n).wrapping_mul(15731) + 789221) + 1376312589;
+    n = n
+        .wrapping_mul(n.wrapping_mul(n).wrapping_mul(15731).wrapping_add(789221))
+        .wrapping_add(1376312589);
     (n & 0x7fffffff) as f32 / 0x7fffffff as f32
 }
 
+/// Deterministic [0, 1] value for a world position; `salt` picks independent streams
+pub fn hash_position(x: f32, z: f32, seed: u32, salt: u32) -> f32 {
+    let h = seed
+        ^ x.to_bits().wrapping_mul(73856093)
+        ^ z.to_bits().wrapping_mul(19349663)
+        ^ salt.wrapping_mul(83492791);
+    hash(hash(h).to_bits() ^ h)
+}
+
+/// Random sequence for scattering one layer (trees, rocks, ...) over a chunk
+/// `layer_seed` comes from `WorldSeed::derive`; each chunk offset gets its own stream of it.
+pub fn chunk_rng(layer_seed: u32, offset_x: f32, offset_z: f32) -> SeededRng {
+    let chunk = ((offset_x as i32 as u32 as u64) << 32) | offset_z as i32 as u32 as u64;
+    SeededRng::with_stream(layer_seed as u64, chunk)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chunk_rng_differs_per_chunk() {
+        let first = |mut rng: SeededRng| rng.next_u32();
+        assert_eq!(first(chunk_rng(5, 256.0, -512.0)), first(chunk_rng(5, 256.0, -512.0)));
+        assert_ne!(first(chunk_rng(5, 256.0, -512.0)), first(chunk_rng(5, -512.0, 256.0)));
+        assert_ne!(first(chunk_rng(5, 256.0, -512.0)), first(chunk_rng(6, 256.0, -512.0)));
+    }
+
     #[test]
     fn test_fbm() {
         let point = Vec2::new(0.5, 0.5);
@@ -117,4 +236,58 @@ mod tests {
         let value = turbulence(point, 4, 2.0, 0.5, 42);
         assert!(value >= 0.0 && value <= 1.0);
     }
+
+    #[test]
+    fn test_simplex_deterministic() {
+        for point in [Vec2::new(0.5, 0.5), Vec2::new(-12.3, 47.9), Vec2::new(1000.25, -3.75)] {
+            let value = simplex(point, 42);
+            assert_eq!(value, simplex(point, 42));
+            assert!((-1.0..=1.0).contains(&value));
+        }
+        // Different seeds give different fields
+        let differs = (0..16).any(|i| {
+            let point = Vec2::new(i as f32 * 0.37, i as f32 * 0.61);
+            simplex(point, 1) != simplex(point, 2)
+        });
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_worley_deterministic() {
+        for point in [Vec2::new(0.5, 0.5), Vec2::new(-12.3, 47.9), Vec2::new(1000.25, -3.75)] {
+            let (f1, f2) = worley(point, 42);
+            assert_eq!((f1, f2), worley(point, 42));
+            assert!(f1 >= 0.0 && f1 <= f2);
+            // Nearest point is at most a cell diagonal away
+            assert!(f1 <= std::f32::consts::SQRT_2);
+        }
+    }
+
+    #[test]
+    fn test_domain_warp() {
+        let point = Vec2::new(3.7, -8.1);
+        assert_eq!(domain_warp(point, 0.0, 7), point);
+        let warped = domain_warp(point, 2.0, 7);
+        assert_ne!(warped, point);
+        assert_eq!(warped, domain_warp(point, 2.0, 7));
+        // fbm offsets are in [-1, 1] per axis
+        assert!((warped - point).abs().max_element() <= 2.0);
+    }
+
+    #[test]
+    fn test_fbm_with_deterministic() {
+        let point = Vec2::new(3.7, -8.1);
+        let value = fbm_with(simplex, point, 5, 2.0, 0.5, 7);
+        assert_eq!(value, fbm_with(simplex, point, 5, 2.0, 0.5, 7));
+        assert!((-1.0..=1.0).contains(&value));
+
+        // Cell borders: F2 - F1 is 0 on a border and positive inside a cell
+        let cracks = |p: Vec2, seed: u32| {
+            let (f1, f2) = worley(p, seed);
+            f2 - f1
+        };
+        let value = ridged_with(cracks, point, 3, 2.0, 0.5, 7);
+        assert_eq!(value, ridged_with(cracks, point, 3, 2.0, 0.5, 7));
+        assert!(value >= 0.0);
+    }
 }
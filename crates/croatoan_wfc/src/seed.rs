@@ -1,3 +1,6 @@
+use std::convert::Infallible;
+use std::str::FromStr;
+
 /// World seed structure for procedural generation
 /// Provides deterministic random values based on a base seed
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,6 +14,34 @@ impl WorldSeed {
         Self { value: seed }
     }
 
+    /// Seed from text the player typed
+    ///
+    /// A string that parses as a `u32` (surrounding whitespace ignored) is that number, so
+    /// numeric seeds and existing saves keep their worlds. Anything else ("roanoke") is
+    /// hashed with 32-bit FNV-1a over its UTF-8 bytes: stable across runs and platforms,
+    /// and case-sensitive.
+    pub fn from_text(text: &str) -> Self {
+        let text = text.trim();
+        match text.parse::<u32>() {
+            Ok(value) => Self::new(value),
+            Err(_) => Self::new(fnv1a(text.as_bytes())),
+        }
+    }
+
+    /// The raw seed the generators take
+    pub fn as_u32(&self) -> u32 {
+        self.value
+    }
+
+    /// Independent seed for one generator layer ("biome", "grass", "trees", ...)
+    ///
+    /// Hashes the base seed together with `domain` (FNV-1a over the seed's little-endian
+    /// bytes then the domain's), so layers never share or correlate their noise the way
+    /// small offsets like `seed + 100` can. Stable across runs and platforms.
+    pub fn derive(&self, domain: &str) -> u32 {
+        fnv1a_extend(fnv1a(&self.value.to_le_bytes()), domain.as_bytes())
+    }
+
     /// Hash combine function from Boost C++ library
     /// Formula: seed ^ (value + 0x9e3779b9 + (seed << 6) + (seed >> 2))
     /// This is the standard hash combine used in Unity and many C# codebases
@@ -55,6 +86,33 @@ impl From<u32> for WorldSeed {
     }
 }
 
+impl From<&str> for WorldSeed {
+    fn from(text: &str) -> Self {
+        Self::from_text(text)
+    }
+}
+
+/// `WorldSeed::from_str` / `str::parse`, same rules as `from_text` (never fails)
+impl FromStr for WorldSeed {
+    type Err = Infallible;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_text(text))
+    }
+}
+
+/// 32-bit FNV-1a
+fn fnv1a(bytes: &[u8]) -> u32 {
+    const OFFSET_BASIS: u32 = 0x811c9dc5;
+    fnv1a_extend(OFFSET_BASIS, bytes)
+}
+
+/// Continue an FNV-1a hash over more bytes
+fn fnv1a_extend(hash: u32, bytes: &[u8]) -> u32 {
+    const PRIME: u32 = 0x01000193;
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u32).wrapping_mul(PRIME))
+}
+
 impl Default for WorldSeed {
     fn default() -> Self {
         Self::new(0)
@@ -79,6 +137,46 @@ mod tests {
         assert_ne!(hash1, 67890);
     }
 
+    #[test]
+    fn test_text_seeds() {
+        // Numbers stay numbers
+        assert_eq!(WorldSeed::from_text("12345").as_u32(), 12345);
+        assert_eq!(WorldSeed::from_text(" 42\n").as_u32(), 42);
+
+        // Words hash to fixed values (FNV-1a reference vectors), so shared seeds reproduce
+        assert_eq!(WorldSeed::from_text("").as_u32(), 0x811c9dc5);
+        assert_eq!(WorldSeed::from_text("a").as_u32(), 0xe40c292c);
+        assert_eq!(WorldSeed::from_text("foobar").as_u32(), 0xbf9cf968);
+        assert_eq!(WorldSeed::from_text("roanoke"), "roanoke".parse().unwrap());
+        assert_eq!(WorldSeed::from_text("roanoke"), WorldSeed::from("  roanoke "));
+        assert_ne!(WorldSeed::from_text("roanoke"), WorldSeed::from_text("Roanoke"));
+
+        // Out-of-range numbers are just text
+        assert_eq!(WorldSeed::from_text("4294967296").as_u32(), fnv1a(b"4294967296"));
+    }
+
+    #[test]
+    fn test_derive_separates_layers() {
+        let seed = WorldSeed::new(12345);
+        assert_eq!(seed.derive("trees"), seed.derive("trees"));
+        assert_eq!(seed.derive("trees"), WorldSeed::new(12345).derive("trees"));
+
+        let domains = ["biome", "grass", "detritus", "trees", "rocks", "buildings", "trails"];
+        for (i, a) in domains.iter().enumerate() {
+            for b in &domains[i + 1..] {
+                assert_ne!(seed.derive(a), seed.derive(b), "{} and {} collide", a, b);
+            }
+        }
+
+        // Offsets of the base seed don't line up with other layers' streams
+        assert_ne!(WorldSeed::new(12346).derive("biome"), seed.derive("biome"));
+        assert_ne!(seed.derive("biome"), seed.as_u32().wrapping_add(100));
+
+        // Word seeds near u32::MAX derive without overflow
+        let big = WorldSeed::new(u32::MAX);
+        assert_ne!(big.derive("grass"), big.derive("rocks"));
+    }
+
     #[test]
     fn test_combine() {
         let seed = WorldSeed::new(12345);
@@ -0,0 +1,250 @@
+use crate::buildings::generate_buildings_for_chunk;
+use crate::mesh_gen::{base_height_at, TerrainConfig};
+use crate::seed::WorldSeed;
+use glam::Vec2;
+use noise::{NoiseFn, Perlin};
+
+/// Worn-earth color blended into the terrain along a trail
+const DIRT_COLOR: [f32; 3] = [0.42, 0.32, 0.20];
+
+/// How far below the flattened bench the trail center is worn down
+const TRAIL_SINK: f32 = 0.15;
+
+/// A winding footpath, stored as a densely sampled polyline
+#[derive(Debug, Clone)]
+pub struct Trail {
+    points: Vec<Vec2>,
+    /// Base terrain height at each point (the level the trail flattens toward)
+    heights: Vec<f32>,
+    /// Full width of the cleared path (world units)
+    pub width: f32,
+    bounds_min: Vec2,
+    bounds_max: Vec2,
+}
+
+impl Trail {
+    /// Plan a winding trail between two world points (XZ) over the natural terrain of `config`
+    pub fn between(start: Vec2, end: Vec2, width: f32, seed: u32, config: &TerrainConfig) -> Self {
+        let noise = Perlin::new(WorldSeed::new(seed).derive("trails"));
+        let span = end - start;
+        let length = span.length();
+        let side = if length > 0.0 { Vec2::new(-span.y, span.x) / length } else { Vec2::ZERO };
+
+        // Control points every ~40 units, pushed sideways by noise (pinned at the ends)
+        let segments = ((length / 40.0).ceil() as usize).max(2);
+        let sway = (length * 0.15).min(40.0);
+        let control: Vec<Vec2> = (0..=segments)
+            .map(|i| {
+                let t = i as f32 / segments as f32;
+                let envelope = (t * std::f32::consts::PI).sin();
+                let offset = noise.get([t as f64 * 3.1, length as f64 * 0.01]) as f32;
+                start + span * t + side * offset * sway * envelope
+            })
+            .collect();
+
+        // Catmull-Rom through the control points
+        let samples_per_segment = 8;
+        let mut points = Vec::with_capacity(segments * samples_per_segment + 1);
+        for i in 0..segments {
+            let p0 = control[i.saturating_sub(1)];
+            let p1 = control[i];
+            let p2 = control[i + 1];
+            let p3 = control[(i + 2).min(segments)];
+            for s in 0..samples_per_segment {
+                let t = s as f32 / samples_per_segment as f32;
+                points.push(catmull_rom(p0, p1, p2, p3, t));
+            }
+        }
+        points.push(end);
+
+        let heights = points.iter().map(|p| base_height_at(p.x, p.y, seed, config).0).collect();
+
+        let pad = Vec2::splat(width);
+        let bounds_min = points.iter().fold(Vec2::splat(f32::MAX), |acc, p| acc.min(*p)) - pad;
+        let bounds_max = points.iter().fold(Vec2::splat(f32::MIN), |acc, p| acc.max(*p)) + pad;
+
+        Self {
+            points,
+            heights,
+            width,
+            bounds_min,
+            bounds_max,
+        }
+    }
+
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    /// Distance to the trail centerline and the centerline height at the closest point
+    fn closest(&self, p: Vec2) -> (f32, f32) {
+        let mut best = (f32::MAX, 0.0);
+        for i in 0..self.points.len() - 1 {
+            let a = self.points[i];
+            let ab = self.points[i + 1] - a;
+            let t = ((p - a).dot(ab) / ab.length_squared().max(1e-6)).clamp(0.0, 1.0);
+            let dist = (a + ab * t).distance(p);
+            if dist < best.0 {
+                let height = self.heights[i] + (self.heights[i + 1] - self.heights[i]) * t;
+                best = (dist, height);
+            }
+        }
+        best
+    }
+
+    /// Trail strength at a point: 1.0 on the centerline, fading to 0.0 at the edge
+    /// Returns the centerline height alongside for flattening
+    pub fn influence(&self, x: f32, z: f32) -> (f32, f32) {
+        let p = Vec2::new(x, z);
+        if p.cmplt(self.bounds_min).any() || p.cmpgt(self.bounds_max).any() {
+            return (0.0, 0.0);
+        }
+        let (dist, height) = self.closest(p);
+        let half_width = self.width * 0.5;
+        (1.0 - smoothstep(half_width * 0.5, half_width, dist), height)
+    }
+}
+
+/// All trails for one world seed
+///
+/// Planned once per world and passed to the terrain queries (`get_height_at`,
+/// `sample_terrain`, the chunk generators); `None` there means the natural, trail-less ground.
+#[derive(Debug, Clone, Default)]
+pub struct TrailNetwork {
+    pub trails: Vec<Trail>,
+}
+
+impl TrailNetwork {
+    /// Connect the spawn point to the nearest village (building site) within `search_radius`
+    /// `chunk_size` must match the world's chunks, so the sites found are the ones generated
+    pub fn plan(seed: u32, spawn: Vec2, search_radius: f32, chunk_size: f32, config: &TerrainConfig) -> Self {
+        let min = ((spawn - Vec2::splat(search_radius)) / chunk_size).floor();
+        let max = ((spawn + Vec2::splat(search_radius)) / chunk_size).floor();
+
+        let mut nearest: Option<Vec2> = None;
+        for cz in min.y as i32..=max.y as i32 {
+            for cx in min.x as i32..=max.x as i32 {
+                let sites = generate_buildings_for_chunk(seed, chunk_size, cx as f32 * chunk_size, cz as f32 * chunk_size);
+                for (_, transform) in sites {
+                    let site = Vec2::new(transform.w_axis.x, transform.w_axis.z);
+                    let dist = site.distance(spawn);
+                    if dist <= search_radius && nearest.is_none_or(|n| dist < n.distance(spawn)) {
+                        nearest = Some(site);
+                    }
+                }
+            }
+        }
+
+        let mut trails = Vec::new();
+        if let Some(village) = nearest {
+            // Stop short of the door so the path doesn't run under the building
+            let to_spawn = (spawn - village).normalize_or_zero();
+            trails.push(Trail::between(spawn, village + to_spawn * 8.0, 6.0, seed, config));
+        }
+        Self { trails }
+    }
+
+    /// Strongest trail influence at a point, with that trail's centerline height
+    pub fn influence(&self, x: f32, z: f32) -> (f32, f32) {
+        self.trails
+            .iter()
+            .map(|trail| trail.influence(x, z))
+            .fold((0.0, 0.0), |best, sample| if sample.0 > best.0 { sample } else { best })
+    }
+
+    /// Flatten the ground and blend in dirt where a trail passes
+    pub fn apply(&self, x: f32, z: f32, height: f32, color: [f32; 3]) -> (f32, [f32; 3]) {
+        let (influence, trail_height) = self.influence(x, z);
+        if influence <= 0.0 {
+            return (height, color);
+        }
+
+        // Level toward the centerline (a gentle bench, not a trench), worn slightly lower
+        let flat = height + (trail_height - TRAIL_SINK - height) * influence * 0.7;
+        let dirt = influence * 0.85;
+        let blended = [
+            color[0] + (DIRT_COLOR[0] - color[0]) * dirt,
+            color[1] + (DIRT_COLOR[1] - color[1]) * dirt,
+            color[2] + (DIRT_COLOR[2] - color[2]) * dirt,
+        ];
+        (flat, blended)
+    }
+}
+
+/// Trail influence (0..1) at a point; 0.0 without trails
+pub fn trail_influence(x: f32, z: f32, trails: Option<&TrailNetwork>) -> f32 {
+    trails.map_or(0.0, |network| network.influence(x, z).0)
+}
+
+/// Multiplier for grass/detritus density: cleared on trails, 1.0 elsewhere
+pub fn ground_cover_density(x: f32, z: f32, trails: Option<&TrailNetwork>) -> f32 {
+    1.0 - trail_influence(x, z, trails)
+}
+
+fn catmull_rom(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    ((p1 * 2.0)
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_gen::get_height_at;
+
+    #[test]
+    fn test_trail_endpoints_and_width() {
+        let trail = Trail::between(Vec2::new(0.0, 0.0), Vec2::new(200.0, 50.0), 6.0, 99, &TerrainConfig::default());
+
+        assert_eq!(trail.points()[0], Vec2::new(0.0, 0.0));
+        assert_eq!(*trail.points().last().unwrap(), Vec2::new(200.0, 50.0));
+
+        // Full strength on the path, nothing well off to the side
+        let mid = trail.points()[trail.points().len() / 2];
+        assert!(trail.influence(mid.x, mid.y).0 > 0.99);
+        assert_eq!(trail.influence(mid.x, mid.y + 50.0).0, 0.0);
+        assert_eq!(trail.influence(-500.0, -500.0).0, 0.0);
+    }
+
+    #[test]
+    fn test_trail_flattens_and_colors() {
+        let network = TrailNetwork {
+            trails: vec![Trail::between(Vec2::new(-100.0, 10.0), Vec2::new(-300.0, 80.0), 6.0, 5, &TerrainConfig::default())],
+        };
+        let p = network.trails[0].points()[20];
+        let (base_height, base_color) = base_height_at(p.x, p.y, 5, &TerrainConfig::default());
+        let (height, color) = network.apply(p.x, p.y, base_height, base_color);
+
+        // On the centerline: worn slightly below the natural ground, mostly dirt-colored
+        assert!(height < base_height);
+        assert!((color[0] - DIRT_COLOR[0]).abs() < 0.2 * (base_color[0] - DIRT_COLOR[0]).abs() + 1e-6);
+
+        // Untouched away from the trail
+        assert_eq!(network.apply(p.x + 100.0, p.y, base_height, base_color), (base_height, base_color));
+    }
+
+    #[test]
+    fn test_height_queries_take_the_network() {
+        let config = TerrainConfig::default();
+        let network = TrailNetwork {
+            trails: vec![Trail::between(Vec2::new(-100.0, 10.0), Vec2::new(-300.0, 80.0), 6.0, 5, &config)],
+        };
+        let p = network.trails[0].points()[20];
+
+        // Only the caller's network shapes the ground; without one it's the natural terrain
+        let natural = base_height_at(p.x, p.y, 5, &config);
+        assert_eq!(get_height_at(p.x, p.y, 5, &config, None), natural);
+        assert!(get_height_at(p.x, p.y, 5, &config, Some(&network)).0 < natural.0);
+        assert_eq!(ground_cover_density(p.x, p.y, None), 1.0);
+        assert!(ground_cover_density(p.x, p.y, Some(&network)) < 0.01);
+    }
+}
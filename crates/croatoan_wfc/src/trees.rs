@@ -1,5 +1,8 @@
 use croatoan_procgen::{TreeRecipe, generate_tree, generate_tree_mesh};
-use crate::mesh_gen::get_height_at;
+use crate::mesh_gen::{get_height_at, TerrainConfig};
+use crate::trails::TrailNetwork;
+use crate::noise_util::{chunk_rng, hash_position};
+use crate::seed::WorldSeed;
 use noise::{NoiseFn, Perlin};
 
 #[derive(Clone)]
@@ -12,17 +15,34 @@ pub struct TreeTemplate {
 
 use glam::{Mat4, Vec3, Quat};
 
+/// Species ids carried on `TreeInstance`
+pub const SPECIES_OAK: u8 = 0;
+pub const SPECIES_BUSH: u8 = 1;
+
+/// A placed tree: transform (with seeded scale, yaw and lean), species and color tint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeInstance {
+    pub transform: Mat4,
+    pub species: u8,
+    /// Per-tree color multiplier (around 1.0)
+    pub tint: [f32; 3],
+}
+
 /// Generate trees for a terrain chunk based on biome
 ///
 /// Trees appear at forest edge and become denser in deep forest
-/// Returns instance matrices for the chunk
+/// Returns the placed instances for the chunk
 pub fn generate_trees_for_chunk(
     seed: u32,
     chunk_size: f32,
     offset_x: f32,
     offset_z: f32,
-) -> Vec<Mat4> {
-    let noise = Perlin::new(seed + 777);
+    trails: Option<&TrailNetwork>,
+) -> Vec<TreeInstance> {
+    let tree_seed = WorldSeed::new(seed).derive("trees");
+    let noise = Perlin::new(tree_seed);
+    let mut rng = chunk_rng(tree_seed, offset_x, offset_z);
+    let config = TerrainConfig::default();
 
     // Sample potential tree positions
     // Optimization: Reduced density slightly to prevent overcrowding while maintaining lush look
@@ -36,19 +56,13 @@ pub fn generate_trees_for_chunk(
     let upper_treeline_start = 40.0;
     let upper_treeline_end = 55.0;
 
-    for i in 0..potential_trees {
-        // Pseudo-random position within chunk
-        let rand_x = noise.get([i as f64 * 0.1, 0.0]) as f32;
-        let rand_z = noise.get([i as f64 * 0.1, 100.0]) as f32;
-
-        let local_x = (rand_x + 1.0) * 0.5 * chunk_size;
-        let local_z = (rand_z + 1.0) * 0.5 * chunk_size;
-
-        let world_x = offset_x + local_x;
-        let world_z = offset_z + local_z;
+    for _ in 0..potential_trees {
+        // Random position within chunk
+        let world_x = offset_x + rng.next_f32() * chunk_size;
+        let world_z = offset_z + rng.next_f32() * chunk_size;
 
         // Get terrain height and determine biome
-        let (height, _color) = get_height_at(world_x, world_z, seed);
+        let (height, _color) = get_height_at(world_x, world_z, seed, &config, trails);
 
         // --- Treeline Logic ---
 
@@ -82,22 +96,14 @@ pub fn generate_trees_for_chunk(
             continue; // Skip this tree based on density
         }
 
-        // Random rotation
-        let angle = noise.get([world_x as f64 * 0.5, world_z as f64 * 0.5]) as f32 * 3.14;
-        
-        // Scale variation: Taller in deep forest, shorter at edges (both coastal and alpine)
-        let base_scale = 5.0 + (biome_factor * 2.0); 
-        let scale_var = noise.get([world_x as f64 * 0.2, world_z as f64 * 0.2]) as f32;
-        let scale = base_scale + scale_var;
-
-        // Create transform matrix
-        let transform = Mat4::from_scale_rotation_translation(
-            Vec3::splat(scale),
-            Quat::from_rotation_y(angle),
-            Vec3::new(world_x, height - 1.0, world_z), // -1.0 to sink firmly into ground
-        );
+        // Scale: Taller in deep forest, shorter at edges (both coastal and alpine)
+        let base_scale = 5.0 + (biome_factor * 2.0);
 
-        instances.push(transform);
+        instances.push(TreeInstance {
+            transform: natural_transform(world_x, world_z, height, base_scale, seed, trails),
+            species: SPECIES_OAK,
+            tint: natural_tint(world_x, world_z, seed, SPECIES_OAK),
+        });
     }
 
 
@@ -109,18 +115,12 @@ pub fn generate_trees_for_chunk(
     let bush_zone_start = 3.5;
     let bush_zone_end = 12.0;
 
-    for i in 0..potential_bushes {
-        // Offset noise lookup to avoid overlapping exactly with trees
-        let rand_x = noise.get([i as f64 * 0.1, 500.0]) as f32;
-        let rand_z = noise.get([i as f64 * 0.1, 600.0]) as f32;
+    for _ in 0..potential_bushes {
+        // Random position within chunk (the sequence continues past the trees')
+        let world_x = offset_x + rng.next_f32() * chunk_size;
+        let world_z = offset_z + rng.next_f32() * chunk_size;
 
-        let local_x = (rand_x + 1.0) * 0.5 * chunk_size;
-        let local_z = (rand_z + 1.0) * 0.5 * chunk_size;
-
-        let world_x = offset_x + local_x;
-        let world_z = offset_z + local_z;
-
-        let (height, _color) = get_height_at(world_x, world_z, seed);
+        let (height, _color) = get_height_at(world_x, world_z, seed, &config, trails);
 
         // Bush Zone Logic
         if height < bush_zone_start || height > bush_zone_end {
@@ -133,21 +133,73 @@ pub fn generate_trees_for_chunk(
             continue;
         }
 
-        let angle = noise.get([world_x as f64 * 0.5, world_z as f64 * 0.5]) as f32 * 3.14;
-        
         // Small scale for bushes
-        let scale = 0.8 + (noise.get([world_x as f64 * 0.2, world_z as f64 * 0.2]) as f32 * 0.3);
+        instances.push(TreeInstance {
+            transform: natural_transform(world_x, world_z, height, 0.8, seed, trails),
+            species: SPECIES_BUSH,
+            tint: natural_tint(world_x, world_z, seed, SPECIES_BUSH),
+        });
+    }
 
-        let transform = Mat4::from_scale_rotation_translation(
-            Vec3::splat(scale),
-            Quat::from_rotation_y(angle),
-            Vec3::new(world_x, height - 1.0, world_z), // Sink firmly
-        );
+    instances
+}
 
-        instances.push(transform);
+/// How far trees lean toward the ground normal (0 = upright, 1 = perpendicular to slope)
+const SLOPE_LEAN: f32 = 0.35;
+
+/// Extra random lean on top of the slope (radians, ~3 degrees)
+const MAX_RANDOM_LEAN: f32 = 0.05;
+
+/// Total lean never exceeds this (radians, ~12 degrees), however steep the ground
+const MAX_LEAN: f32 = 0.2;
+
+/// Instance transform with seeded yaw, 0.7-1.3x scale and a lean partway to the terrain normal.
+/// Everything derives from the world position, so a tree looks the same every time its chunk loads.
+fn natural_transform(world_x: f32, world_z: f32, height: f32, base_scale: f32, seed: u32, trails: Option<&TrailNetwork>) -> Mat4 {
+    let stream = WorldSeed::new(seed).derive("tree_shape");
+    let yaw = hash_position(world_x, world_z, stream, 0) * std::f32::consts::TAU;
+    let scale = base_scale * (0.7 + hash_position(world_x, world_z, stream, 1) * 0.6);
+
+    // Terrain normal from central differences
+    let step = 1.0;
+    let config = TerrainConfig::default();
+    let (h_east, _) = get_height_at(world_x + step, world_z, seed, &config, trails);
+    let (h_west, _) = get_height_at(world_x - step, world_z, seed, &config, trails);
+    let (h_north, _) = get_height_at(world_x, world_z + step, seed, &config, trails);
+    let (h_south, _) = get_height_at(world_x, world_z - step, seed, &config, trails);
+    let normal = Vec3::new(h_west - h_east, 2.0 * step, h_south - h_north).normalize();
+
+    // Partway to the slope, plus a small random lean in any direction
+    let lean_angle = hash_position(world_x, world_z, stream, 2) * std::f32::consts::TAU;
+    let lean_amount = hash_position(world_x, world_z, stream, 3) * MAX_RANDOM_LEAN;
+    let random_lean = Vec3::new(lean_angle.cos(), 0.0, lean_angle.sin()) * lean_amount.tan();
+    let mut up = (Vec3::Y.lerp(normal, SLOPE_LEAN) + random_lean).normalize();
+    if up.angle_between(Vec3::Y) > MAX_LEAN {
+        let outward = Vec3::new(up.x, 0.0, up.z).normalize();
+        up = Vec3::Y * MAX_LEAN.cos() + outward * MAX_LEAN.sin();
     }
 
-    instances
+    let rotation = Quat::from_rotation_arc(Vec3::Y, up) * Quat::from_rotation_y(yaw);
+
+    Mat4::from_scale_rotation_translation(
+        Vec3::splat(scale),
+        rotation,
+        Vec3::new(world_x, height - 1.0, world_z), // -1.0 to sink firmly into ground
+    )
+}
+
+/// Seeded foliage tint: +/-15% brightness with a slight green/yellow shift.
+/// Bushes run darker and greener than oaks.
+fn natural_tint(world_x: f32, world_z: f32, seed: u32, species: u8) -> [f32; 3] {
+    let stream = WorldSeed::new(seed).derive("tree_shape");
+    let brightness = 0.85 + hash_position(world_x, world_z, stream, 4) * 0.3;
+    let warmth = (hash_position(world_x, world_z, stream, 5) - 0.5) * 0.2;
+    let base = if species == SPECIES_BUSH { [0.85, 0.95, 0.85] } else { [1.0, 1.0, 1.0] };
+    [
+        base[0] * brightness * (1.0 + warmth),
+        base[1] * brightness,
+        base[2] * brightness * (1.0 - warmth),
+    ]
 }
 
 #[cfg(test)]
@@ -161,6 +213,7 @@ mod tests {
             256.0,
             0.0,
             0.0,
+            None,
         );
 
         // Should generate some trees (depends on seed and chunk)
@@ -169,7 +222,39 @@ mod tests {
         // Basic validation
         for instance in instances {
             // Check if matrix is valid (not all zeros)
-            assert!(instance.w_axis.w == 1.0);
+            assert!(instance.transform.w_axis.w == 1.0);
         }
     }
+
+    #[test]
+    fn test_tree_variation_is_stable() {
+        // Inland chunk so there's a forest to inspect
+        let first = generate_trees_for_chunk(12345, 256.0, -768.0, 256.0, None);
+        let second = generate_trees_for_chunk(12345, 256.0, -768.0, 256.0, None);
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+
+        let mut scales = Vec::new();
+        let mut tints = Vec::new();
+        for instance in &first {
+            let (scale, rotation, _) = instance.transform.to_scale_rotation_translation();
+            scales.push(scale.x);
+            tints.push(instance.tint[1]);
+            assert!(instance.tint.iter().all(|c| (0.6..=1.4).contains(c)));
+
+            // Leans, but never more than a gentle tilt
+            let up = rotation * Vec3::Y;
+            assert!(up.y > 15.0_f32.to_radians().cos(), "tree tilted too far: {:?}", up);
+        }
+
+        // Sizes actually vary
+        let min = scales.iter().cloned().fold(f32::MAX, f32::min);
+        let max = scales.iter().cloned().fold(f32::MIN, f32::max);
+        assert!(max / min > 1.2);
+
+        // Colors vary too
+        let min = tints.iter().cloned().fold(f32::MAX, f32::min);
+        let max = tints.iter().cloned().fold(f32::MIN, f32::max);
+        assert!(max - min > 0.1);
+    }
 }
@@ -1,44 +1,105 @@
-use croatoan_procgen::{GrassBladeRecipe, generate_grass_blade};
-use crate::mesh_gen::get_height_at;
-use glam::Vec3;
+use croatoan_procgen::PlantRecipe;
+use crate::noise_util::{chunk_rng, hash_position};
+use crate::world_sample::sample_terrain;
+use crate::mesh_gen::DetritusArrays;
+use crate::trails::{ground_cover_density, TrailNetwork};
+use crate::seed::WorldSeed;
+use glam::{Mat4, Quat, Vec3};
 use noise::{NoiseFn, Perlin};
 
+/// Plant mesh names, matching the game's plant registry
+pub const PLANT_BUSH: &str = "plant_bush";
+pub const PLANT_FERN: &str = "plant_fern";
+pub const PLANT_FLOWER: &str = "plant_flower";
+
+/// Every plant kind `generate_plants_for_chunk` can place
+pub const PLANT_KINDS: [&str; 3] = [PLANT_BUSH, PLANT_FERN, PLANT_FLOWER];
+
+/// Sample points per square unit for plants; far sparser than grass blades
+const PLANT_DENSITY: f32 = 0.25;
+
+/// The recipe a plant kind's mesh is generated from
+pub fn plant_recipe(name: &str) -> Option<PlantRecipe> {
+    match name {
+        PLANT_BUSH => Some(PlantRecipe::bush()),
+        PLANT_FERN => Some(PlantRecipe::fern()),
+        PLANT_FLOWER => Some(PlantRecipe::flower()),
+        _ => None,
+    }
+}
+
+/// One grass blade, drawn as an instance of the renderer's shared blade mesh
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrassInstance {
+    /// Root of the blade
+    pub position: [f32; 3],
+    pub height: f32,
+    /// How far the tip curls over, as a fraction of the height
+    pub bend: f32,
+    /// Facing around the vertical (radians)
+    pub rotation: f32,
+    /// Tip color; the renderer darkens it toward the base
+    pub color: [f32; 3],
+}
+
+/// Biome factor for a terrain height: 0.0 at the beach edge, 1.0 in deep forest
+/// None on the beach and wet sand, where nothing grows.
+fn biome_factor(height: f32) -> Option<f32> {
+    (height >= 0.8).then(|| ((height - 0.8) / 12.0).clamp(0.0, 1.0))
+}
+
+/// What grows at a spot with `biome_factor`, given a uniform `roll` in [0, 1]
+///
+/// Flowers in open scrub, ferns around the forest edge, bushes in the forest; None means
+/// the default, grass.
+pub fn vegetation_type(biome_factor: f32, roll: f32) -> Option<&'static str> {
+    // Each kind peaks in its band and fades out to either side
+    let band = |center: f32, half_width: f32| (1.0 - ((biome_factor - center) / half_width).abs()).max(0.0);
+    let flower = 0.35 * band(0.2, 0.25);
+    let fern = 0.35 * band(0.65, 0.3);
+    let bush = 0.2 * ((biome_factor - 0.5) / 0.5).clamp(0.0, 1.0);
+
+    if roll < flower {
+        Some(PLANT_FLOWER)
+    } else if roll < flower + fern {
+        Some(PLANT_FERN)
+    } else if roll < flower + fern + bush {
+        Some(PLANT_BUSH)
+    } else {
+        None
+    }
+}
+
 /// Generate vegetation (grass) for a terrain chunk based on biome
 ///
-/// Grass density and height increase toward forest edge
-/// Returns (positions, colors, indices) for grass mesh
+/// Grass density and height increase toward forest edge; the bushes, ferns and flowers
+/// growing among it come from `generate_plants_for_chunk`.
+/// Returns one instance per blade
 pub fn generate_vegetation_for_chunk(
     seed: u32,
     chunk_size: f32,
     offset_x: f32,
     offset_z: f32,
-) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>) {
-    let noise = Perlin::new(seed + 999);
+    trails: Option<&TrailNetwork>,
+) -> Vec<GrassInstance> {
+    let grass_seed = WorldSeed::new(seed).derive("grass");
+    let noise = Perlin::new(grass_seed);
+    let mut rng = chunk_rng(grass_seed, offset_x, offset_z);
 
     // Maximum density for sampling positions
-    // Keep density low to avoid GPU buffer limits (256MB max)
     // 8.0 * 256 * 256 = ~524K potential blades, but density filtering reduces to ~50K actual
     let max_density = 8.0;
     let blade_count = (chunk_size * chunk_size * max_density) as u32;
 
-    let mut all_positions = Vec::new();
-    let mut all_colors = Vec::new();
-    let mut all_indices = Vec::new();
-
-    for i in 0..blade_count {
-        // Pseudo-random position within chunk using 2D noise
-        // Use different prime multipliers to ensure good distribution
-        let rand_x = noise.get([i as f64 * 0.7341, i as f64 * 0.9127]) as f32;
-        let rand_z = noise.get([i as f64 * 0.5813, i as f64 * 0.6719]) as f32;
+    let mut instances = Vec::new();
 
-        let local_x = (rand_x + 1.0) * 0.5 * chunk_size;
-        let local_z = (rand_z + 1.0) * 0.5 * chunk_size;
-
-        let world_x = offset_x + local_x;
-        let world_z = offset_z + local_z;
+    for _ in 0..blade_count {
+        // Random position within chunk
+        let world_x = offset_x + rng.next_f32() * chunk_size;
+        let world_z = offset_z + rng.next_f32() * chunk_size;
 
         // Get terrain height and determine biome
-        let (height, _color) = get_height_at(world_x, world_z, seed);
+        let height = sample_terrain(seed, world_x, world_z, trails).height;
 
         // Beach: height < 0.8 (no grass - pure sand)
         // Transition: height 0.8-2.0 (sparse dune grass)
@@ -46,15 +107,13 @@ pub fn generate_vegetation_for_chunk(
         // Forest edge: height 6.0-12.0 (dense, tall grass)
         // Deep forest: height 12.0+ (very dense, very tall grass)
 
-        if height < 0.8 {
-            continue; // No grass on beach/wet sand
-        }
-
         // Calculate biome factor (0.0 = beach edge, 1.0 = deep forest)
-        let biome_factor = ((height - 0.8) / 12.0).clamp(0.0, 1.0);
+        let Some(biome_factor) = biome_factor(height) else {
+            continue; // No grass on beach/wet sand
+        };
 
-        // Density increases with height (scrub = 10%, forest = 100%)
-        let density_threshold = 0.1 + biome_factor * 0.9;
+        // Density increases with height (scrub = 10%, forest = 100%), cleared along trails
+        let density_threshold = (0.1 + biome_factor * 0.9) * ground_cover_density(world_x, world_z, trails);
         let density_roll = noise.get([world_x as f64 * 3.7, world_z as f64 * 3.7]) as f32;
         if (density_roll + 1.0) * 0.5 > density_threshold {
             continue; // Skip this blade based on density
@@ -73,36 +132,68 @@ pub fn generate_vegetation_for_chunk(
         let min_height = (0.4 + biome_factor * 0.8) * height_mod;
         let max_height = (0.8 + biome_factor * 1.6) * height_mod;
 
-        let recipe = GrassBladeRecipe {
-            height_range: (min_height, max_height),
-            blade_segments: 5,
-            curve_factor: 0.4 + biome_factor * 0.3, // More curve in forest
-            width_base: 0.06 + biome_factor * 0.04,
-            width_tip: 0.01,
-            // Brighter grass colors - vibrant greens
-            color_base: [
-                0.25 - biome_factor * 0.08,  // Slightly darker base in forest
-                0.55 + biome_factor * 0.15,  // Rich green
-                0.15,
-            ],
-            color_tip: [
-                0.45 - biome_factor * 0.10,  // Yellow-green tips
-                0.75 + biome_factor * 0.10,  // Bright green
-                0.20,
-            ],
+        let height_roll = hash_position(world_x, world_z, grass_seed, 0);
+        instances.push(GrassInstance {
+            position: [world_x, height, world_z],
+            height: min_height + (max_height - min_height) * height_roll,
+            bend: 0.4 + biome_factor * 0.3, // More curve in forest
+            rotation: hash_position(world_x, world_z, grass_seed, 1) * std::f32::consts::TAU,
+            // Yellow-green tips, bright green in forest
+            color: [0.45 - biome_factor * 0.10, 0.75 + biome_factor * 0.10, 0.20],
+        });
+    }
+
+    instances
+}
+
+/// Generate bushes, ferns and flowers for a terrain chunk
+///
+/// Plants thin out with the same biome density as grass (and clear along trails); where
+/// `vegetation_type` picks grass, nothing is placed since the blades already cover it.
+/// Returns a list of (mesh_name, transform) tuples, one of `PLANT_KINDS` each.
+pub fn generate_plants_for_chunk(
+    seed: u32,
+    chunk_size: f32,
+    offset_x: f32,
+    offset_z: f32,
+    trails: Option<&TrailNetwork>,
+) -> Vec<(String, Mat4)> {
+    let plant_seed = WorldSeed::new(seed).derive("plants");
+    let mut rng = chunk_rng(plant_seed, offset_x, offset_z);
+    let potential_plants = (chunk_size * chunk_size * PLANT_DENSITY) as u32;
+
+    let mut instances = Vec::new();
+
+    for _ in 0..potential_plants {
+        let world_x = offset_x + rng.next_f32() * chunk_size;
+        let world_z = offset_z + rng.next_f32() * chunk_size;
+
+        let height = sample_terrain(seed, world_x, world_z, trails).height;
+        let Some(biome_factor) = biome_factor(height) else {
+            continue;
         };
 
-        let base_pos = Vec3::new(world_x, height, world_z);
-        let blade = generate_grass_blade(&recipe, seed + i, base_pos);
+        let density_threshold = (0.1 + biome_factor * 0.9) * ground_cover_density(world_x, world_z, trails);
+        if hash_position(world_x, world_z, plant_seed, 0) > density_threshold {
+            continue;
+        }
 
-        // Append to combined mesh
-        let vertex_offset = all_positions.len() as u32;
-        all_positions.extend(blade.positions);
-        all_colors.extend(blade.colors);
-        all_indices.extend(blade.indices.iter().map(|idx| idx + vertex_offset));
+        let Some(name) = vegetation_type(biome_factor, hash_position(world_x, world_z, plant_seed, 1)) else {
+            continue;
+        };
+
+        // Random yaw, size growing with the forest
+        let yaw = hash_position(world_x, world_z, plant_seed, 2) * std::f32::consts::TAU;
+        let scale = (0.7 + biome_factor * 0.3) * (0.8 + hash_position(world_x, world_z, plant_seed, 3) * 0.4);
+        let transform = Mat4::from_scale_rotation_translation(
+            Vec3::splat(scale),
+            Quat::from_rotation_y(yaw),
+            Vec3::new(world_x, height - 0.05, world_z), // Sink slightly
+        );
+        instances.push((name.to_string(), transform));
     }
 
-    (all_positions, all_colors, all_indices)
+    instances
 }
 
 /// Generate detritus (fallen logs, rocks, etc.) for a terrain chunk
@@ -112,8 +203,11 @@ pub fn generate_detritus_for_chunk(
     chunk_size: f32,
     offset_x: f32,
     offset_z: f32,
-) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>, Vec<u32>) {
-    let noise = Perlin::new(seed + 555);
+    trails: Option<&TrailNetwork>,
+) -> DetritusArrays {
+    let detritus_seed = WorldSeed::new(seed).derive("detritus");
+    let noise = Perlin::new(detritus_seed);
+    let mut rng = chunk_rng(detritus_seed, offset_x, offset_z);
 
     // Detritus density
     let detritus_density = 0.002; // Items per square unit
@@ -124,19 +218,13 @@ pub fn generate_detritus_for_chunk(
     let mut all_uvs = Vec::new();
     let mut all_indices = Vec::new();
 
-    for i in 0..potential_items {
-        // Pseudo-random position within chunk
-        let rand_x = noise.get([i as f64 * 0.2, 0.0]) as f32;
-        let rand_z = noise.get([i as f64 * 0.2, 100.0]) as f32;
-
-        let local_x = (rand_x + 1.0) * 0.5 * chunk_size;
-        let local_z = (rand_z + 1.0) * 0.5 * chunk_size;
-
-        let world_x = offset_x + local_x;
-        let world_z = offset_z + local_z;
+    for _ in 0..potential_items {
+        // Random position within chunk
+        let world_x = offset_x + rng.next_f32() * chunk_size;
+        let world_z = offset_z + rng.next_f32() * chunk_size;
 
         // Get terrain height and determine biome
-        let (height, _color) = get_height_at(world_x, world_z, seed);
+        let height = sample_terrain(seed, world_x, world_z, trails).height;
 
         // Only place detritus on land (above beach)
         if height < 2.0 {
@@ -232,18 +320,55 @@ mod tests {
 
     #[test]
     fn test_vegetation_generation() {
-        let (positions, colors, indices) = generate_vegetation_for_chunk(
-            1587,
-            32.0,
-            0.0,
-            0.0,
-        );
+        let blades = generate_vegetation_for_chunk(1587, 32.0, 0.0, 0.0, None);
 
         // Should generate some grass
-        assert!(!positions.is_empty());
-        assert_eq!(positions.len(), colors.len());
-        assert!(indices.len() % 3 == 0);
+        assert!(!blades.is_empty());
+        for blade in &blades {
+            assert!(blade.position[0] >= 0.0 && blade.position[0] <= 32.0);
+            assert!(blade.position[2] >= 0.0 && blade.position[2] <= 32.0);
+            // Nothing on the beach; scrub to deep forest heights
+            assert!(blade.position[1] >= 0.8);
+            assert!(blade.height > 0.2 && blade.height < 3.2, "height {}", blade.height);
+            assert!((0.4..=0.7).contains(&blade.bend));
+        }
+        assert_eq!(blades, generate_vegetation_for_chunk(1587, 32.0, 0.0, 0.0, None));
+
+        println!("Generated {} grass blades", blades.len());
+    }
+
+    #[test]
+    fn test_vegetation_type_follows_biome() {
+        // Grass stays the default everywhere
+        for biome_factor in [0.0, 0.2, 0.65, 1.0] {
+            assert_eq!(vegetation_type(biome_factor, 0.99), None);
+        }
+        // Flowers in scrub, ferns at the forest edge, bushes deep in the forest
+        assert_eq!(vegetation_type(0.2, 0.0), Some(PLANT_FLOWER));
+        assert_eq!(vegetation_type(0.65, 0.0), Some(PLANT_FERN));
+        assert_eq!(vegetation_type(1.0, 0.0), Some(PLANT_BUSH));
+        assert_ne!(vegetation_type(0.2, 0.0), vegetation_type(0.65, 0.0));
+    }
 
-        println!("Generated {} grass blades", positions.len() / 10); // ~10 verts per blade
+    #[test]
+    fn test_plant_generation() {
+        let instances = generate_plants_for_chunk(1587, 128.0, 0.0, 0.0, None);
+        assert!(!instances.is_empty());
+
+        for (name, transform) in &instances {
+            assert!(PLANT_KINDS.contains(&name.as_str()), "unexpected plant {}", name);
+            assert!(plant_recipe(name).is_some());
+            let position = transform.w_axis.truncate();
+            assert!(position.x >= 0.0 && position.x <= 128.0);
+            assert!(position.z >= 0.0 && position.z <= 128.0);
+            // Nothing on the beach
+            assert!(position.y >= 0.75);
+        }
+
+        println!(
+            "Generated {} plants: {:?}",
+            instances.len(),
+            PLANT_KINDS.map(|kind| instances.iter().filter(|(name, _)| name == kind).count())
+        );
     }
 }
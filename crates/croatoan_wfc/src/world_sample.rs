@@ -0,0 +1,125 @@
+use crate::mesh_gen::{biome_t, get_height_at, TerrainConfig};
+use crate::trails::TrailNetwork;
+
+/// Named biome at a world position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Ocean,
+    Beach,
+    Scrub,
+    Forest,
+    /// High forest ground (ridges and hilltops)
+    Mountain,
+}
+
+impl Biome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Biome::Ocean => "Ocean",
+            Biome::Beach => "Beach",
+            Biome::Scrub => "Scrub",
+            Biome::Forest => "Forest",
+            Biome::Mountain => "Mountain",
+        }
+    }
+}
+
+/// Structured terrain query result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainSample {
+    pub height: f32,
+    pub color: [f32; 3],
+    pub biome: Biome,
+    /// Raw biome blend value (see `biome_t`), for finer gradations within a biome
+    pub blend: f32,
+    /// Steepness as rise over run (0.0 = flat, 1.0 = 45 degrees)
+    pub slope: f32,
+}
+
+/// Forest ground above this height is classified as Mountain
+const MOUNTAIN_HEIGHT: f32 = 16.0;
+
+/// Distance to neighbor samples for slope (world units)
+const SLOPE_STEP: f32 = 1.0;
+
+/// Query height, color, biome and slope at any global position
+///
+/// This is the one terrain query gameplay and generators should use; pass the world's
+/// `trails` (as given to `generate_terrain_chunk`) so results match the rendered mesh.
+/// Samples the default `TerrainConfig`.
+pub fn sample_terrain(seed: u32, x: f32, z: f32, trails: Option<&TrailNetwork>) -> TerrainSample {
+    let config = TerrainConfig::default();
+    let (height, color) = get_height_at(x, z, seed, &config, trails);
+
+    // Central differences
+    let (east, _) = get_height_at(x + SLOPE_STEP, z, seed, &config, trails);
+    let (west, _) = get_height_at(x - SLOPE_STEP, z, seed, &config, trails);
+    let (north, _) = get_height_at(x, z + SLOPE_STEP, seed, &config, trails);
+    let (south, _) = get_height_at(x, z - SLOPE_STEP, seed, &config, trails);
+    let dx = (east - west) / (2.0 * SLOPE_STEP);
+    let dz = (north - south) / (2.0 * SLOPE_STEP);
+    let slope = (dx * dx + dz * dz).sqrt();
+
+    let blend = biome_t(x, z, seed, &config);
+    TerrainSample {
+        height,
+        color,
+        biome: classify_biome(blend, height),
+        blend,
+        slope,
+    }
+}
+
+/// Map a biome blend value (see `biome_t`) and height to a named biome
+/// Uses the default `TerrainConfig` thresholds.
+pub fn classify_biome(t: f32, height: f32) -> Biome {
+    let config = TerrainConfig::default();
+    if t < config.beach_start {
+        Biome::Ocean
+    } else if t < config.scrub_start {
+        Biome::Beach
+    } else if t < config.forest_start {
+        Biome::Scrub
+    } else if height > MOUNTAIN_HEIGHT {
+        Biome::Mountain
+    } else {
+        Biome::Forest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_biome_classification_along_gradient() {
+        // West (inland) to east (sea) along a fixed row
+        let seed = 12345;
+        assert_eq!(sample_terrain(seed, -100.0, 7.3, None).biome, Biome::Forest);
+        assert_eq!(sample_terrain(seed, 25.0, 7.3, None).biome, Biome::Scrub);
+        assert_eq!(sample_terrain(seed, 225.0, 7.3, None).biome, Biome::Beach);
+        assert_eq!(sample_terrain(seed, 300.0, 7.3, None).biome, Biome::Ocean);
+        assert_eq!(sample_terrain(seed, 1000.0, 7.3, None).biome, Biome::Ocean);
+    }
+
+    #[test]
+    fn test_classify_mountain() {
+        assert_eq!(classify_biome(1.0, 10.0), Biome::Forest);
+        assert_eq!(classify_biome(1.0, 16.5), Biome::Mountain);
+        // Height alone doesn't make a mountain outside the forest band
+        assert_eq!(classify_biome(0.6, 16.5), Biome::Scrub);
+    }
+
+    #[test]
+    fn test_sample_matches_height_query() {
+        let sample = sample_terrain(7, -250.0, 42.0, None);
+        let (height, color) = get_height_at(-250.0, 42.0, 7, &TerrainConfig::default(), None);
+        assert_eq!(sample.height, height);
+        assert_eq!(sample.color, color);
+        assert_eq!(sample.blend, biome_t(-250.0, 42.0, 7, &TerrainConfig::default()));
+        assert!(sample.slope >= 0.0 && sample.slope.is_finite());
+
+        // Open ocean floor is nearly flat
+        assert!(sample_terrain(7, 1000.0, 7.3, None).slope < 0.1);
+    }
+}
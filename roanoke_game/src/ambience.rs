@@ -0,0 +1,123 @@
+use croatoan_audio::{AudioEngine, SoundHandle};
+use croatoan_wfc::{sample_terrain, Biome};
+use glam::Vec3;
+use crate::player::{Footstep, WATER_LEVEL};
+use crate::weather_system::WeatherSystem;
+
+// Missing files are logged once and play as silence
+const WIND_LOOP: &str = "assets/audio/ambient_wind.wav";
+const OCEAN_LOOP: &str = "assets/audio/ambient_ocean.wav";
+const UI_SELECT: &str = "assets/audio/ui_select.wav";
+
+/// Ocean sound fades in from this far from open water (m)
+const WATER_HEARING_RANGE: f32 = 120.0;
+/// Seconds between searches for nearby water (a few dozen terrain samples)
+const WATER_CHECK_INTERVAL: f32 = 0.5;
+
+/// Game sounds: menu clicks and footsteps, plus wind and ocean loops that follow the weather and the
+/// player's distance to the sea
+pub struct Ambience {
+    engine: AudioEngine,
+    wind: SoundHandle,
+    ocean: SoundHandle,
+    water_distance: f32,
+    since_water_check: f32,
+}
+
+impl Ambience {
+    pub fn new() -> Self {
+        let mut engine = AudioEngine::new();
+        let wind = engine.play_looping(WIND_LOOP, 0.0);
+        let ocean = engine.play_looping(OCEAN_LOOP, 0.0);
+        Self { engine, wind, ocean, water_distance: f32::INFINITY, since_water_check: WATER_CHECK_INTERVAL }
+    }
+
+    /// Menu button feedback
+    pub fn click(&mut self) {
+        self.engine.play_oneshot(UI_SELECT);
+    }
+
+    pub fn footstep(&mut self, step: Footstep) {
+        self.engine.play_oneshot(footstep_sound(step));
+    }
+
+    /// Set the loop volumes for a player at `listener` in world `seed`
+    pub fn update(&mut self, delta: f32, seed: u32, listener: Vec3, weather: &WeatherSystem) {
+        self.since_water_check += delta;
+        if self.since_water_check >= WATER_CHECK_INTERVAL {
+            self.since_water_check = 0.0;
+            self.water_distance = water_distance(seed, listener);
+        }
+
+        let (wind, ocean) = ambient_volumes(weather.wind_speed, weather.precipitation_intensity(), self.water_distance);
+        self.engine.set_volume(self.wind, wind);
+        self.engine.set_volume(self.ocean, ocean);
+    }
+
+    /// Quiet the loops (menus, loading)
+    pub fn silence(&mut self) {
+        self.engine.set_volume(self.wind, 0.0);
+        self.engine.set_volume(self.ocean, 0.0);
+        // Search again as soon as play resumes, possibly somewhere else
+        self.since_water_check = WATER_CHECK_INTERVAL;
+    }
+}
+
+/// Sound file for a footstep, by what's underfoot
+fn footstep_sound(step: Footstep) -> &'static str {
+    match step {
+        Footstep::Ground(Biome::Ocean | Biome::Beach) => "assets/audio/footstep_sand.wav",
+        Footstep::Ground(Biome::Scrub) => "assets/audio/footstep_grass.wav",
+        Footstep::Ground(Biome::Forest | Biome::Mountain) => "assets/audio/footstep_leaves.wav",
+        Footstep::Wading => "assets/audio/footstep_water.wav",
+        Footstep::Splash => "assets/audio/splash.wav",
+    }
+}
+
+/// Wind and ocean loop volumes (0..1)
+/// `wind_speed`: `WeatherSystem::wind_speed` (1.0 = breezy); `precipitation`: 0..1;
+/// `water_distance`: to the nearest open water (m), infinite if none in earshot
+fn ambient_volumes(wind_speed: f32, precipitation: f32, water_distance: f32) -> (f32, f32) {
+    let wind = (0.1 + wind_speed * 0.25 + precipitation * 0.2).clamp(0.0, 1.0);
+    // Rougher sea in stronger wind
+    let proximity = (1.0 - water_distance / WATER_HEARING_RANGE).clamp(0.0, 1.0);
+    let ocean = proximity * proximity * (0.6 + (wind_speed / 3.0).min(1.0) * 0.4);
+    (wind, ocean)
+}
+
+/// Roughly how far `position` is from terrain below sea level, searching outward in rings
+fn water_distance(seed: u32, position: Vec3) -> f32 {
+    const RINGS: [f32; 5] = [0.0, 15.0, 30.0, 60.0, WATER_HEARING_RANGE];
+    const DIRECTIONS: usize = 12;
+    for radius in RINGS {
+        let samples = if radius == 0.0 { 1 } else { DIRECTIONS };
+        for i in 0..samples {
+            let angle = i as f32 / DIRECTIONS as f32 * std::f32::consts::TAU;
+            let x = position.x + angle.cos() * radius;
+            let z = position.z + angle.sin() * radius;
+            // Natural ground: trails hardly move the shoreline
+            if sample_terrain(seed, x, z, None).height < WATER_LEVEL {
+                return radius;
+            }
+        }
+    }
+    f32::INFINITY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ambience_follows_weather_and_water() {
+        let (calm, ocean_far) = ambient_volumes(0.6, 0.0, f32::INFINITY);
+        let (storm, ocean_storm) = ambient_volumes(3.0, 1.0, 0.0);
+        assert!(storm > calm);
+        assert_eq!(ocean_far, 0.0);
+        assert!(ocean_storm > ambient_volumes(0.6, 0.0, 0.0).1);
+        assert!(ambient_volumes(1.0, 0.0, 10.0).1 > ambient_volumes(1.0, 0.0, 80.0).1);
+
+        // The sea off the player test's beach (~5 m deep)
+        assert_eq!(water_distance(12345, Vec3::new(700.0, 6.0, 40.0)), 0.0);
+    }
+}
@@ -1,101 +1,431 @@
-use tobj;
-use std::path::Path;
-use croatoan_wfc::TreeTemplate;
-
-pub fn load_obj(path: &str) -> Option<TreeTemplate> {
-    println!("[ASSET] Loading model: {}", path);
-    
-    let load_options = tobj::LoadOptions {
-        single_index: true,
-        triangulate: true,
-        ignore_points: true,
-        ignore_lines: true,
-    };
-
-    match tobj::load_obj(path, &load_options) {
-        Ok((models, materials)) => {
-            let materials = materials.unwrap_or_default();
-            let mut positions = Vec::new();
-            let mut normals = Vec::new();
-            let mut uvs = Vec::new();
-            let mut indices = Vec::new();
-            let mut vertex_offset = 0;
-
-            for (i, m) in models.iter().enumerate() {
-                let mesh = &m.mesh;
-                
-                // Check material name
-                if let Some(mat_id) = mesh.material_id {
-                    if mat_id < materials.len() {
-                        let mat_name = &materials[mat_id].name.to_lowercase();
-                        if mat_name.contains("leaf") || mat_name.contains("leaves") || mat_name.contains("frond") 
-                           || mat_name.contains("oak_leav") || mat_name.contains("sonnerat") || mat_name.contains("walnut_l") {
-                            println!("[ASSET] Skipping leaf mesh {}: {}", i, mat_name);
-                            continue;
-                        }
-                    }
-                }
-
-                println!("[ASSET] Mesh {}: {} vertices, {} indices", i, mesh.positions.len() / 3, mesh.indices.len());
-
-                // Positions
-                for i in 0..mesh.positions.len() / 3 {
-                    positions.push([
-                        mesh.positions[i * 3],
-                        mesh.positions[i * 3 + 1],
-                        mesh.positions[i * 3 + 2],
-                    ]);
-                }
-
-                // Normals
-                if !mesh.normals.is_empty() {
-                    for i in 0..mesh.normals.len() / 3 {
-                        normals.push([
-                            mesh.normals[i * 3],
-                            mesh.normals[i * 3 + 1],
-                            mesh.normals[i * 3 + 2],
-                        ]);
-                    }
-                } else {
-                    // Generate dummy normals if missing (up)
-                    for _ in 0..mesh.positions.len() / 3 {
-                        normals.push([0.0, 1.0, 0.0]);
-                    }
-                }
-
-                // UVs
-                if !mesh.texcoords.is_empty() {
-                    for i in 0..mesh.texcoords.len() / 2 {
-                        uvs.push([
-                            mesh.texcoords[i * 2],
-                            1.0 - mesh.texcoords[i * 2 + 1], // Flip Y
-                        ]);
-                    }
-                } else {
-                    // Generate dummy UVs
-                    for _ in 0..mesh.positions.len() / 3 {
-                        uvs.push([0.0, 0.0]);
-                    }
-                }
-
-                // Indices
-                for idx in &mesh.indices {
-                    indices.push(*idx + vertex_offset);
-                }
-
-                vertex_offset += (mesh.positions.len() / 3) as u32;
-            }
-
-            Some(TreeTemplate {
-                positions,
-                normals,
-                uvs,
-                indices,
-            })
-        }
-        Err(e) => {
-            eprintln!("[ASSET] Failed to load model '{}': {}", path, e);
-            None
-        }
-    }
-}
+use tobj;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use glam::{Mat3, Mat4, Vec3};
+use croatoan_wfc::TreeTemplate;
+
+/// Leaves are drawn as billboards, so their geometry is dropped from trunk models
+fn is_leaf_material(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("leaf") || name.contains("leaves") || name.contains("frond")
+        || name.contains("oak_leav") || name.contains("sonnerat") || name.contains("walnut_l")
+}
+
+/// Surface of an OBJ face group, from the model's .mtl
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    /// `Kd` (white if unset)
+    pub diffuse: [f32; 3],
+    /// `map_Kd`, resolved against the model's folder
+    pub diffuse_map: Option<PathBuf>,
+}
+
+/// One face group (OBJ object) kept in a loaded template
+#[derive(Debug, Clone)]
+pub struct FaceGroup {
+    /// Range of `TreeTemplate::indices` the group occupies
+    pub indices: Range<usize>,
+    pub material: Option<Material>,
+}
+
+/// Material covering the most triangles (the one to texture a single-material mesh with)
+pub fn dominant_material(groups: &[FaceGroup]) -> Option<&Material> {
+    groups
+        .iter()
+        .filter_map(|group| Some((group.indices.len(), group.material.as_ref()?)))
+        .max_by_key(|(count, _)| *count)
+        .map(|(_, material)| material)
+}
+
+/// Load an OBJ (and its .mtl), merging all non-leaf face groups into one template
+pub fn load_obj(path: &str) -> Option<(TreeTemplate, Vec<FaceGroup>)> {
+    println!("[ASSET] Loading model: {}", path);
+    
+    let load_options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+    };
+
+    match tobj::load_obj(path, &load_options) {
+        Ok((models, materials)) => {
+            let materials = materials.unwrap_or_else(|e| {
+                println!("[ASSET] No materials for '{}': {}", path, e);
+                Vec::new()
+            });
+            let model_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+            let mut groups = Vec::new();
+            let mut positions = Vec::new();
+            let mut normals = Vec::new();
+            let mut uvs = Vec::new();
+            let mut indices = Vec::new();
+            let mut vertex_offset = 0;
+
+            for (i, m) in models.iter().enumerate() {
+                let mesh = &m.mesh;
+                
+                // Check material name
+                if let Some(mat_id) = mesh.material_id {
+                    if mat_id < materials.len() {
+                        let mat_name = &materials[mat_id].name;
+                        if is_leaf_material(mat_name) {
+                            println!("[ASSET] Skipping leaf mesh {}: {}", i, mat_name);
+                            continue;
+                        }
+                    }
+                }
+
+                println!("[ASSET] Mesh {}: {} vertices, {} indices", i, mesh.positions.len() / 3, mesh.indices.len());
+
+                // Positions
+                for i in 0..mesh.positions.len() / 3 {
+                    positions.push([
+                        mesh.positions[i * 3],
+                        mesh.positions[i * 3 + 1],
+                        mesh.positions[i * 3 + 2],
+                    ]);
+                }
+
+                // Normals
+                if !mesh.normals.is_empty() {
+                    for i in 0..mesh.normals.len() / 3 {
+                        normals.push([
+                            mesh.normals[i * 3],
+                            mesh.normals[i * 3 + 1],
+                            mesh.normals[i * 3 + 2],
+                        ]);
+                    }
+                } else {
+                    // Generate dummy normals if missing (up)
+                    for _ in 0..mesh.positions.len() / 3 {
+                        normals.push([0.0, 1.0, 0.0]);
+                    }
+                }
+
+                // UVs
+                if !mesh.texcoords.is_empty() {
+                    for i in 0..mesh.texcoords.len() / 2 {
+                        uvs.push([
+                            mesh.texcoords[i * 2],
+                            1.0 - mesh.texcoords[i * 2 + 1], // Flip Y
+                        ]);
+                    }
+                } else {
+                    // Generate dummy UVs
+                    for _ in 0..mesh.positions.len() / 3 {
+                        uvs.push([0.0, 0.0]);
+                    }
+                }
+
+                groups.push(FaceGroup {
+                    indices: indices.len()..indices.len() + mesh.indices.len(),
+                    material: mesh.material_id.and_then(|id| materials.get(id)).map(|material| Material {
+                        diffuse: material.diffuse.unwrap_or([1.0; 3]),
+                        diffuse_map: material.diffuse_texture.as_deref().map(|map| resolve_texture_path(model_dir, map)),
+                    }),
+                });
+
+                // Indices
+                for idx in &mesh.indices {
+                    indices.push(*idx + vertex_offset);
+                }
+
+                vertex_offset += (mesh.positions.len() / 3) as u32;
+            }
+
+            Some((TreeTemplate {
+                positions,
+                normals,
+                uvs,
+                indices,
+            }, groups))
+        }
+        Err(e) => {
+            eprintln!("[ASSET] Failed to load model '{}': {}", path, e);
+            None
+        }
+    }
+}
+
+/// Texture path from an .mtl, relative to the model (exporters on Windows write `\\`)
+fn resolve_texture_path(model_dir: &Path, map: &str) -> PathBuf {
+    map.split(['\\', '/'])
+        .filter(|part| !part.is_empty())
+        .fold(model_dir.to_path_buf(), |path, part| path.join(part))
+}
+
+/// Decoded base-color texture, RGBA8
+pub struct LoadedTexture {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// One glTF primitive, in the layout `TreePipeline::create_mesh` takes
+pub struct LoadedMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+    pub material_name: Option<String>,
+    /// Shared between primitives that use the same image
+    pub base_color: Option<Arc<LoadedTexture>>,
+}
+
+/// Load a .gltf/.glb model, one `LoadedMesh` per triangle primitive
+/// Node transforms of the default scene are baked into the vertices.
+pub fn load_gltf(path: &str) -> Option<Vec<LoadedMesh>> {
+    println!("[ASSET] Loading model: {}", path);
+
+    let gltf = match gltf::Gltf::open(path) {
+        Ok(gltf) => gltf,
+        Err(e) => {
+            eprintln!("[ASSET] Failed to load model '{}': {}", path, e);
+            return None;
+        }
+    };
+    let base = Path::new(path).parent();
+    let gltf::Gltf { document, blob } = gltf;
+    let buffers = match gltf::import_buffers(&document, base, blob) {
+        Ok(buffers) => buffers,
+        Err(e) => {
+            eprintln!("[ASSET] Failed to read buffers of '{}': {}", path, e);
+            return None;
+        }
+    };
+
+    let mut loader = GltfLoader { base, buffers: &buffers, textures: HashMap::new(), meshes: Vec::new() };
+    match document.default_scene().or_else(|| document.scenes().next()) {
+        Some(scene) => {
+            for node in scene.nodes() {
+                loader.load_node(&node, Mat4::IDENTITY);
+            }
+        }
+        // No scene: meshes as authored
+        None => {
+            for mesh in document.meshes() {
+                loader.load_mesh(&mesh, Mat4::IDENTITY);
+            }
+        }
+    }
+
+    println!("[ASSET] {} primitive(s) loaded from {}", loader.meshes.len(), path);
+    Some(loader.meshes)
+}
+
+/// Combine a model's primitives into one tree template, skipping leaves
+/// Returns the first base-color texture found, if any.
+pub fn merge_tree_meshes(meshes: Vec<LoadedMesh>) -> (TreeTemplate, Option<Arc<LoadedTexture>>) {
+    let mut template = TreeTemplate { positions: Vec::new(), normals: Vec::new(), uvs: Vec::new(), indices: Vec::new() };
+    let mut texture = None;
+    for mesh in meshes {
+        if let Some(name) = mesh.material_name.as_deref().filter(|name| is_leaf_material(name)) {
+            println!("[ASSET] Skipping leaf mesh: {}", name);
+            continue;
+        }
+        let vertex_offset = template.positions.len() as u32;
+        template.positions.extend(mesh.positions);
+        template.normals.extend(mesh.normals);
+        template.uvs.extend(mesh.uvs);
+        template.indices.extend(mesh.indices.iter().map(|index| index + vertex_offset));
+        texture = texture.or(mesh.base_color);
+    }
+    (template, texture)
+}
+
+struct GltfLoader<'a> {
+    base: Option<&'a Path>,
+    buffers: &'a [gltf::buffer::Data],
+    textures: HashMap<usize, Option<Arc<LoadedTexture>>>,
+    meshes: Vec<LoadedMesh>,
+}
+
+impl GltfLoader<'_> {
+    fn load_node(&mut self, node: &gltf::Node, parent: Mat4) {
+        let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+        if let Some(mesh) = node.mesh() {
+            self.load_mesh(&mesh, transform);
+        }
+        for child in node.children() {
+            self.load_node(&child, transform);
+        }
+    }
+
+    fn load_mesh(&mut self, mesh: &gltf::Mesh, transform: Mat4) {
+        let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                println!("[ASSET] Skipping non-triangle primitive in mesh {:?}", mesh.name());
+                continue;
+            }
+            let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else { continue };
+            let positions: Vec<[f32; 3]> = positions
+                .map(|p| transform.transform_point3(Vec3::from_array(p)).to_array())
+                .collect();
+            let normals = match reader.read_normals() {
+                Some(normals) => normals
+                    .map(|n| (normal_matrix * Vec3::from_array(n)).normalize_or_zero().to_array())
+                    .collect(),
+                // Missing: up, as for OBJ
+                None => vec![[0.0, 1.0, 0.0]; positions.len()],
+            };
+            // glTF UVs already have a top-left origin (no flip, unlike OBJ)
+            let uvs = match reader.read_tex_coords(0) {
+                Some(uvs) => uvs.into_f32().collect(),
+                None => vec![[0.0, 0.0]; positions.len()],
+            };
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..positions.len() as u32).collect(),
+            };
+
+            let material = primitive.material();
+            let base_color = material
+                .pbr_metallic_roughness()
+                .base_color_texture()
+                .and_then(|info| self.texture(info.texture().source()));
+
+            self.meshes.push(LoadedMesh {
+                positions,
+                normals,
+                uvs,
+                indices,
+                material_name: material.name().map(str::to_string),
+                base_color,
+            });
+        }
+    }
+
+    /// Decode an image once, however many primitives use it
+    fn texture(&mut self, image: gltf::Image) -> Option<Arc<LoadedTexture>> {
+        let (base, buffers) = (self.base, self.buffers);
+        self.textures
+            .entry(image.index())
+            .or_insert_with(|| match gltf::image::Data::from_source(image.source(), base, buffers) {
+                Ok(data) => to_rgba(data).map(Arc::new),
+                Err(e) => {
+                    eprintln!("[ASSET] Failed to decode texture {}: {}", image.index(), e);
+                    None
+                }
+            })
+            .clone()
+    }
+}
+
+fn to_rgba(data: gltf::image::Data) -> Option<LoadedTexture> {
+    use gltf::image::Format;
+    let channels = match data.format {
+        Format::R8 => 1,
+        Format::R8G8 => 2,
+        Format::R8G8B8 => 3,
+        Format::R8G8B8A8 => 4,
+        format => {
+            println!("[ASSET] Unsupported texture format {:?}", format);
+            return None;
+        }
+    };
+    let rgba = data
+        .pixels
+        .chunks_exact(channels)
+        .flat_map(|pixel| match *pixel {
+            [r] => [r, r, r, 255],
+            [r, g] => [r, r, r, g], // Luminance + alpha
+            [r, g, b] => [r, g, b, 255],
+            [r, g, b, a] => [r, g, b, a],
+            _ => unreachable!(),
+        })
+        .collect();
+    Some(LoadedTexture { width: data.width, height: data.height, rgba })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One triangle in a scaled, translated node, buffer embedded as a data URI
+    const TRIANGLE_GLTF: &str = r#"{
+        "asset": {"version": "2.0"},
+        "scene": 0,
+        "scenes": [{"nodes": [0]}],
+        "nodes": [{"mesh": 0, "translation": [10, 0, 0], "scale": [2, 2, 2]}],
+        "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "indices": 1, "material": 0}]}],
+        "materials": [{"name": "Bark"}],
+        "buffers": [{"byteLength": 42, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAAAAAIA/AAABAAIAAAA="}],
+        "bufferViews": [
+            {"buffer": 0, "byteOffset": 0, "byteLength": 36},
+            {"buffer": 0, "byteOffset": 36, "byteLength": 6}
+        ],
+        "accessors": [
+            {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 0, 1]},
+            {"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_load_gltf_bakes_node_transform() {
+        let dir = std::env::temp_dir().join(format!("roanoke_gltf_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("triangle.gltf");
+        std::fs::write(&path, TRIANGLE_GLTF).unwrap();
+        let garbage = dir.join("broken.glb");
+        std::fs::write(&garbage, b"not a model").unwrap();
+
+        let meshes = load_gltf(path.to_str().unwrap()).unwrap();
+        assert_eq!(meshes.len(), 1);
+        let mesh = &meshes[0];
+        assert_eq!(mesh.positions, vec![[10.0, 0.0, 0.0], [12.0, 0.0, 0.0], [10.0, 0.0, 2.0]]);
+        assert_eq!(mesh.normals, vec![[0.0, 1.0, 0.0]; 3]);
+        assert_eq!(mesh.uvs.len(), 3);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(mesh.material_name.as_deref(), Some("Bark"));
+        assert!(mesh.base_color.is_none());
+
+        assert!(load_gltf(garbage.to_str().unwrap()).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_obj_reads_materials() {
+        let dir = std::env::temp_dir().join(format!("roanoke_obj_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tree.mtl"), "newmtl Bark\nKd 0.5 0.4 0.3\nmap_Kd Texture\\\\bark.jpg\n\nnewmtl Leaves\nKd 0.1 0.8 0.1\n\nnewmtl Moss\n").unwrap();
+        let obj = "mtllib tree.mtl\nv 0 0 0\nv 1 0 0\nv 0 0 1\nv 1 0 1\n\
+                   o Moss\nusemtl Moss\nf 1 2 3\n\
+                   o Trunk\nusemtl Bark\nf 1 2 3\nf 2 4 3\n\
+                   o Canopy\nusemtl Leaves\nf 1 2 4\n";
+        let path = dir.join("tree.obj");
+        std::fs::write(&path, obj).unwrap();
+
+        let (template, groups) = load_obj(path.to_str().unwrap()).unwrap();
+        // Leaves dropped; moss (1 triangle) then bark (2)
+        assert_eq!(template.indices.len(), 9);
+        assert_eq!(groups.iter().map(|group| group.indices.clone()).collect::<Vec<_>>(), vec![0..3, 3..9]);
+        let moss = groups[0].material.as_ref().unwrap();
+        assert_eq!((moss.diffuse, moss.diffuse_map.as_ref()), ([1.0; 3], None));
+
+        let bark = dominant_material(&groups).unwrap();
+        assert_eq!(bark.diffuse, [0.5, 0.4, 0.3]);
+        assert_eq!(bark.diffuse_map, Some(dir.join("Texture").join("bark.jpg")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_merge_skips_leaves_and_offsets_indices() {
+        let triangle = |material: &str| LoadedMesh {
+            positions: vec![[0.0; 3]; 3],
+            normals: vec![[0.0, 1.0, 0.0]; 3],
+            uvs: vec![[0.0; 2]; 3],
+            indices: vec![0, 1, 2],
+            material_name: Some(material.to_string()),
+            base_color: None,
+        };
+        let (template, texture) = merge_tree_meshes(vec![triangle("Bark"), triangle("Oak_Leaves"), triangle("Bark")]);
+        assert_eq!(template.positions.len(), 6);
+        assert_eq!(template.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert!(texture.is_none());
+    }
+}
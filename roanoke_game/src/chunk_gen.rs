@@ -0,0 +1,161 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use croatoan_wfc::{generate_terrain_chunk, add_terrain_skirts, terrain_splat, generate_vegetation_for_chunk, generate_plants_for_chunk, generate_trees_for_chunk, generate_detritus_for_chunk, generate_rocks_for_chunk, generate_buildings_for_chunk, TerrainConfig};
+use croatoan_wfc::trails::TrailNetwork;
+use glam::Vec2;
+use crate::chunk_manager::{ChunkQueue, ChunkRequest, ChunkSettings};
+use crate::chunk_store::ChunkData;
+use crate::GPU_GRASS_PLACEMENT;
+
+/// Finished chunks allowed to wait for upload, per worker. The upload loop only takes a
+/// chunk or two per frame; past this, workers block instead of piling meshes up in memory.
+pub const RESULTS_PER_WORKER: usize = 2;
+
+/// Number of workers for a `generation_threads` setting (0 = one per core)
+pub fn worker_count(setting: usize) -> usize {
+    if setting > 0 {
+        return setting;
+    }
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Start `count` generation workers that pop from `queue` and send finished chunks to `results`
+pub fn spawn_workers(count: usize, settings: ChunkSettings, queue: ChunkQueue, results: SyncSender<ChunkData>) {
+    for worker in 0..count {
+        let (queue, results) = (queue.clone(), results.clone());
+        thread::Builder::new()
+            .name(format!("chunk-gen-{}", worker))
+            .spawn(move || {
+                // Placement and gameplay queries sample the default terrain, so the mesh must too
+                let terrain_config = TerrainConfig::default();
+                loop {
+                    let req = queue.pop();
+
+                    // Saved from an earlier visit: skip generation
+                    let data = match req.store.as_ref().and_then(|store| store.load_chunk(req.coord, req.lod)) {
+                        Some(data) => data,
+                        None => {
+                            let data = generate_chunk(&req, settings, &terrain_config);
+                            if let Some(store) = &req.store {
+                                store.save_chunk(req.coord, &data);
+                            }
+                            data
+                        }
+                    };
+
+                    // Blocks while the upload loop is behind
+                    if results.send(data).is_err() {
+                        println!("[GEN] Receiver dropped, stopping worker {}.", worker);
+                        break;
+                    }
+                }
+            })
+            .unwrap();
+    }
+    println!("[GEN] Started {} generation worker(s).", count);
+}
+
+/// Plan the trails for a new or loaded world (spawn at the origin), finding villages in
+/// chunks of `settings`' size as the workers will generate them
+pub fn plan_trails(seed: u32, settings: ChunkSettings) -> Arc<TrailNetwork> {
+    let network = TrailNetwork::plan(seed, Vec2::ZERO, 600.0, settings.world_size(), &TerrainConfig::default());
+    println!("[TRAILS] Planned {} trail(s) for seed {}", network.trails.len(), seed);
+    Arc::new(network)
+}
+
+/// Generate everything for one chunk (pure function of seed and position)
+fn generate_chunk(req: &ChunkRequest, settings: ChunkSettings, terrain_config: &TerrainConfig) -> ChunkData {
+    let chunk_world_size = settings.world_size();
+    let (offset_x, offset_z) = req.coord.world_offset(chunk_world_size);
+    let offset_x = offset_x as i32;
+    let offset_z = offset_z as i32;
+
+    // Generate terrain at the requested detail, skirted so coarser neighbours leave no cracks
+    let (resolution, vertex_spacing) = settings.lod_grid(req.lod);
+    let (mut terrain_pos, mut terrain_col, mut terrain_nrm, mut terrain_idx) =
+        generate_terrain_chunk(req.seed, resolution, offset_x, offset_z, vertex_spacing, terrain_config, Some(&req.trails));
+    let mut terrain_splat = terrain_splat(&terrain_pos, req.seed, terrain_config);
+    add_terrain_skirts(&mut terrain_pos, &mut terrain_col, &mut terrain_nrm, &mut terrain_splat, &mut terrain_idx, resolution, settings.skirt_depth());
+
+    // Generate grass (GPU placement only needs the terrain heightfield)
+    let grass_instances = if GPU_GRASS_PLACEMENT {
+        Vec::new()
+    } else {
+        generate_vegetation_for_chunk(
+            req.seed,
+            chunk_world_size,
+            offset_x as f32,
+            offset_z as f32,
+            Some(&req.trails),
+        )
+    };
+
+    // Generate bushes, ferns and flowers
+    let plant_instances = generate_plants_for_chunk(
+        req.seed,
+        chunk_world_size,
+        offset_x as f32,
+        offset_z as f32,
+        Some(&req.trails),
+    );
+
+    // Generate trees
+    let tree_instances = generate_trees_for_chunk(
+        req.seed,
+        chunk_world_size,
+        offset_x as f32,
+        offset_z as f32,
+        Some(&req.trails),
+    );
+
+    // Generate detritus
+    let (det_pos, det_nrm, det_uv, det_idx) = generate_detritus_for_chunk(
+        req.seed,
+        chunk_world_size,
+        offset_x as f32,
+        offset_z as f32,
+        Some(&req.trails),
+    );
+
+    // Generate rocks
+    let rock_instances = generate_rocks_for_chunk(
+        req.seed,
+        chunk_world_size,
+        offset_x as f32,
+        offset_z as f32,
+        Some(&req.trails),
+    );
+
+    // Generate buildings
+    let building_instances = generate_buildings_for_chunk(
+        req.seed,
+        chunk_world_size,
+        offset_x as f32,
+        offset_z as f32,
+    );
+
+    ChunkData {
+        terrain_pos, terrain_col, terrain_nrm, terrain_splat, terrain_idx,
+        terrain_lod: req.lod,
+        grass_instances,
+        plant_instances,
+        tree_instances,
+        det_pos, det_nrm, det_uv, det_idx,
+        rock_instances,
+        building_instances,
+        offset_x, offset_z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_count() {
+        assert_eq!(worker_count(3), 3);
+        assert!(worker_count(0) >= 1);
+    }
+}
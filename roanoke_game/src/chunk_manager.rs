@@ -1,10 +1,16 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex};
 use std::sync::mpsc::Sender;
-use glam::Vec3;
-use croatoan_render::{TerrainPipeline, GrassPipeline, TreePipeline, DetritusPipeline, BuildingPipeline, ChunkBounds};
+use glam::{Mat4, Vec2, Vec3};
+use serde::{Serialize, Deserialize};
+use croatoan_wfc::trails::TrailNetwork;
+use croatoan_render::{TerrainPipeline, GrassPipeline, TreePipeline, DetritusPipeline, BuildingPipeline, RockPipeline, ChunkBounds, Frustum};
+use crate::collision::Collider;
+use crate::chunk_store::ChunkStore;
 
 /// Coordinates for a chunk in chunk space (not world space)
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct ChunkCoord {
     pub x: i32,
     pub z: i32,
@@ -23,15 +29,168 @@ impl ChunkCoord {
     }
 }
 
+/// Chunk dimensions, shared by the generation thread and the manager so they can't disagree
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChunkSettings {
+    /// Terrain quads per chunk side (vertices = resolution + 1)
+    pub resolution: u32,
+    /// World units between terrain vertices
+    pub vertex_spacing: f32,
+    /// Terrain detail by distance: chunks up to `lod_rings[0]` chunks from the player's are
+    /// full resolution, up to `lod_rings[1]` half, and the rest a quarter
+    pub lod_rings: [i32; 2],
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 64,
+            vertex_spacing: 4.0,
+            lod_rings: [1, 2],
+        }
+    }
+}
+
+impl ChunkSettings {
+    /// Side length of a chunk in world units
+    pub fn world_size(&self) -> f32 {
+        self.resolution as f32 * self.vertex_spacing
+    }
+
+    /// Culling bounds of a chunk not generated yet (terrain height range is fixed, not measured)
+    pub fn bounds(&self, coord: ChunkCoord) -> ChunkBounds {
+        let size = self.world_size();
+        let (offset_x, offset_z) = coord.world_offset(size);
+        ChunkBounds::new(offset_x, offset_z, size, -10.0, 50.0)
+    }
+
+    /// Terrain bounds of a generated chunk, from its vertices' heights (padded for the terrain
+    /// shader's waves); what stands on the terrain is added by `LoadedChunk::culling_box`
+    pub fn fitted_bounds(&self, coord: ChunkCoord, terrain_pos: &[[f32; 3]]) -> ChunkBounds {
+        let (low, high) = terrain_pos.iter().fold((f32::MAX, f32::MIN), |(low, high), p| (low.min(p[1]), high.max(p[1])));
+        if low > high {
+            return self.bounds(coord);
+        }
+        let size = self.world_size();
+        let (offset_x, offset_z) = coord.world_offset(size);
+        ChunkBounds::new(offset_x, offset_z, size, low - WAVE_MARGIN, high + WAVE_MARGIN)
+    }
+
+    /// Terrain LOD of a chunk `ring` chunks from the player's (0 = full resolution)
+    pub fn lod_for_ring(&self, ring: i32) -> u32 {
+        self.lod_rings.iter().filter(|&&limit| ring > limit).count() as u32
+    }
+
+    /// Quads per side and vertex spacing of a terrain LOD: each level halves the resolution
+    /// over the same chunk size
+    pub fn lod_grid(&self, lod: u32) -> (u32, f32) {
+        let resolution = (self.resolution >> lod).max(1);
+        (resolution, self.world_size() / resolution as f32)
+    }
+
+    /// Depth of the skirts that hide cracks between neighbouring LODs: twice the coarsest
+    /// vertex spacing, deeper than the terrain strays from a straight line across one quad
+    pub fn skirt_depth(&self) -> f32 {
+        2.0 * self.lod_grid(self.lod_rings.len() as u32).1
+    }
+}
+
+/// Height above a chunk's terrain bounds its trees and buildings can reach
+const OBJECT_HEIGHT: f32 = 30.0;
+
+/// How far the terrain shader's waves move water-level vertices (m)
+const WAVE_MARGIN: f32 = 1.0;
+
+/// Priority boost (in chunks of distance) for chunks inside the camera frustum
+const IN_VIEW_BONUS: f32 = 2.0;
+
+/// Chunks stay loaded this many chunks past the load radius, so walking back and forth
+/// over a chunk border doesn't reload them
+const UNLOAD_MARGIN: i32 = 2;
+
+/// Generated objects the player can remove
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum InstanceKind {
+    Tree,
+    Rock,
+}
+
+impl InstanceKind {
+    /// Inventory item gained by harvesting one
+    pub fn item(&self) -> &'static str {
+        match self {
+            InstanceKind::Tree => "wood",
+            InstanceKind::Rock => "stone",
+        }
+    }
+}
+
+/// A change to the generated world, kept in the save file
+/// Generators are deterministic per seed, so an object is named by its chunk, kind and
+/// index in that chunk's generator output.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum WorldEdit {
+    Removed { chunk: ChunkCoord, kind: InstanceKind, index: u32 },
+}
+
+/// Trees and rocks of a loaded chunk, tagged with their generator index
+#[derive(Default)]
+pub struct ChunkObjects {
+    pub trees: Vec<(u32, croatoan_wfc::TreeInstance)>,
+    pub rocks: Vec<(u32, String, Mat4)>,
+}
+
+impl ChunkObjects {
+    /// Positions of every object, for picking the nearest
+    pub fn positions(&self) -> impl Iterator<Item = (InstanceKind, u32, Vec3)> + '_ {
+        let trees = self.trees.iter().map(|(index, tree)| (InstanceKind::Tree, *index, tree.transform.w_axis.truncate()));
+        let rocks = self.rocks.iter().map(|(index, _, transform)| (InstanceKind::Rock, *index, transform.w_axis.truncate()));
+        trees.chain(rocks)
+    }
+}
+
 /// Data for a loaded chunk
 pub struct LoadedChunk {
     pub terrain: TerrainPipeline,
     pub grass: Option<GrassPipeline>,
     pub trees: Option<TreePipeline>,
     pub detritus: Option<DetritusPipeline>,
-    pub rocks: Vec<TreePipeline>, // List of pipelines for different rock types in this chunk
-    pub buildings: Vec<BuildingPipeline>, // List of pipelines for different building types in this chunk
+    pub rocks: Vec<RockPipeline>, // List of pipelines for different rock types in this chunk
+    pub buildings: Vec<(String, Mat4)>, // Named instances, drawn through the shared per-type batches
+    pub plants: Vec<BuildingPipeline>, // Bushes, ferns and flowers, one pipeline per kind
     pub bounds: ChunkBounds,
+    /// Terrain LOD the chunk was generated at (see `ChunkSettings::lod_grid`)
+    pub lod: u32,
+    /// Building boxes and tree trunks the player collides with
+    pub colliders: Vec<Collider>,
+    /// CPU copy of the removable objects (rebuilt into the pipelines after an edit)
+    pub objects: ChunkObjects,
+}
+
+impl LoadedChunk {
+    /// Box around everything drawn for the chunk (its terrain bounds raised by `OBJECT_HEIGHT`),
+    /// for frustum culling and occlusion proxies, so tree tops over a ridge keep it drawn
+    pub fn culling_box(&self) -> (Vec3, Vec3) {
+        (self.bounds.min, self.bounds.max + Vec3::Y * OBJECT_HEIGHT)
+    }
+
+    /// Approximate GPU memory of the chunk's own buffers (bytes); meshes and textures shared
+    /// between chunks aren't counted
+    pub fn gpu_bytes(&self) -> u64 {
+        self.terrain.buffer_bytes()
+            + self.grass.as_ref().map_or(0, GrassPipeline::buffer_bytes)
+            + self.trees.as_ref().map_or(0, TreePipeline::buffer_bytes)
+            + self.detritus.as_ref().map_or(0, DetritusPipeline::buffer_bytes)
+            + self.rocks.iter().map(RockPipeline::buffer_bytes).sum::<u64>()
+            + self.plants.iter().map(BuildingPipeline::buffer_bytes).sum::<u64>()
+    }
+}
+
+/// Loaded chunks and the GPU memory they hold (see `LoadedChunk::gpu_bytes`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub loaded_chunks: usize,
+    pub approx_bytes: u64,
 }
 
 /// Request to generate a chunk
@@ -39,43 +198,139 @@ pub struct LoadedChunk {
 pub struct ChunkRequest {
     pub coord: ChunkCoord,
     pub seed: u32,
+    /// Terrain LOD to generate (see `ChunkSettings::lod_grid`)
+    pub lod: u32,
+    /// Where to look for (and save) the chunk instead of always regenerating it
+    pub store: Option<ChunkStore>,
+    /// The world's trails, flattened into the terrain and kept clear of ground cover
+    pub trails: Arc<TrailNetwork>,
+    /// Higher generates sooner (see `ChunkManager::priority`)
+    pub priority: f32,
+}
+
+/// Heap entry ordered by priority alone
+struct Queued(ChunkRequest);
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.priority.total_cmp(&other.0.priority)
+    }
+}
+
+/// Pending generation requests, highest priority first
+/// The render side pushes and re-scores them; the generation thread pops.
+#[derive(Clone, Default)]
+pub struct ChunkQueue(Arc<(Mutex<BinaryHeap<Queued>>, Condvar)>);
+
+impl ChunkQueue {
+    pub fn push(&self, request: ChunkRequest) {
+        let (heap, ready) = &*self.0;
+        heap.lock().unwrap().push(Queued(request));
+        ready.notify_one();
+    }
+
+    /// Highest-priority request, blocking until there is one
+    pub fn pop(&self) -> ChunkRequest {
+        let (heap, ready) = &*self.0;
+        let mut heap = heap.lock().unwrap();
+        loop {
+            if let Some(Queued(request)) = heap.pop() {
+                return request;
+            }
+            heap = ready.wait(heap).unwrap();
+        }
+    }
+
+    /// Re-score every pending request (the camera moved or turned); None drops it
+    pub fn reprioritize(&self, mut score: impl FnMut(&ChunkRequest) -> Option<f32>) {
+        let mut heap = self.0.0.lock().unwrap();
+        let pending = std::mem::take(&mut *heap).into_vec();
+        *heap = pending
+            .into_iter()
+            .filter_map(|Queued(mut request)| {
+                request.priority = score(&request)?;
+                Some(Queued(request))
+            })
+            .collect();
+    }
 }
 
 /// Manages chunk loading/unloading based on player position
 pub struct ChunkManager {
     pub loaded_chunks: HashMap<ChunkCoord, LoadedChunk>,
-    pub loading_chunks: HashSet<ChunkCoord>,
-    pub chunk_size: f32,
+    /// Chunks requested from the generation workers, and the LOD asked for
+    pub loading_chunks: HashMap<ChunkCoord, u32>,
+    pub settings: ChunkSettings,
     pub load_radius: i32,
     pub unload_radius: i32,
     player_chunk: ChunkCoord,
+    /// Radius changed: re-run streaming even if the player hasn't crossed a chunk border
+    radius_changed: bool,
+    world_edits: HashSet<WorldEdit>,
+    store: Option<ChunkStore>,
+    trails: Arc<TrailNetwork>,
+    /// Most GPU memory loaded chunks may hold (bytes); None for no cap
+    memory_budget: Option<u64>,
+    /// Nearest ring the budget has unloaded a chunk from; chunks this far out or further
+    /// aren't requested again (until the budget or radius changes), so they don't reload and
+    /// get unloaded over and over
+    budget_ring: Option<i32>,
 }
 
 impl ChunkManager {
-    pub fn new(chunk_size: f32, load_radius: i32, unload_radius: i32) -> Self {
+    /// `load_radius` is the view distance in chunks (1 = 3x3 grid)
+    pub fn new(settings: ChunkSettings, load_radius: i32) -> Self {
         Self {
             loaded_chunks: HashMap::new(),
-            loading_chunks: HashSet::new(),
-            chunk_size,
+            loading_chunks: HashMap::new(),
+            settings,
             load_radius,
-            unload_radius,
+            unload_radius: load_radius + UNLOAD_MARGIN,
             player_chunk: ChunkCoord { x: 0, z: 0 },
+            radius_changed: false,
+            world_edits: HashSet::new(),
+            store: None,
+            trails: Arc::default(),
+            memory_budget: None,
+            budget_ring: None,
         }
     }
 
     /// Update which chunks should be loaded based on player position
-    /// Returns chunks to request for generation
-    pub fn update(&mut self, player_pos: Vec3, seed: u32) -> Vec<ChunkRequest> {
-        let new_player_chunk = ChunkCoord::from_world_pos(player_pos, self.chunk_size);
+    /// Returns chunks to request for generation, prioritized against `frustum`. Loaded chunks
+    /// whose ring now wants another terrain LOD are requested again, and replaced on arrival.
+    pub fn update(&mut self, player_pos: Vec3, seed: u32, frustum: Option<&Frustum>) -> Vec<ChunkRequest> {
+        let new_player_chunk = ChunkCoord::from_world_pos(player_pos, self.chunk_size());
 
         // Only update if player moved to a different chunk
-        if new_player_chunk == self.player_chunk && !self.loaded_chunks.is_empty() {
+        if new_player_chunk == self.player_chunk && !self.loaded_chunks.is_empty() && !self.radius_changed {
             return Vec::new();
         }
 
         self.player_chunk = new_player_chunk;
+        self.radius_changed = false;
         let mut requests = Vec::new();
 
+        // Forget pending chunks that are now out of range (add_chunk drops them on arrival)
+        let load_radius = self.load_radius;
+        self.loading_chunks.retain(|coord, _| {
+            (coord.x - new_player_chunk.x).abs() <= load_radius && (coord.z - new_player_chunk.z).abs() <= load_radius
+        });
+
         // Unload distant chunks
         let chunks_to_unload: Vec<ChunkCoord> = self.loaded_chunks
             .keys()
@@ -100,14 +355,26 @@ impl ChunkManager {
                     z: new_player_chunk.z + dz,
                 };
 
-                // Skip if already loaded or loading
-                if self.loaded_chunks.contains_key(&coord) || self.loading_chunks.contains(&coord) {
+                // Skip if the memory budget doesn't reach this far
+                let ring = dx.abs().max(dz.abs());
+                if self.budget_ring.is_some_and(|budget_ring| ring >= budget_ring) {
+                    continue;
+                }
+
+                // Skip if already loaded or loading at this detail
+                let lod = self.settings.lod_for_ring(ring);
+                if self.loaded_chunks.get(&coord).is_some_and(|chunk| chunk.lod == lod) {
+                    self.loading_chunks.remove(&coord); // Back before a re-detailed copy arrived
+                    continue;
+                }
+                if self.loading_chunks.get(&coord) == Some(&lod) {
                     continue;
                 }
 
                 // Mark as loading and request generation
-                self.loading_chunks.insert(coord);
-                requests.push(ChunkRequest { coord, seed });
+                self.loading_chunks.insert(coord, lod);
+                let priority = self.priority(coord, player_pos, frustum);
+                requests.push(ChunkRequest { coord, seed, lod, store: self.store.clone(), trails: Arc::clone(&self.trails), priority });
             }
         }
 
@@ -120,9 +387,75 @@ impl ChunkManager {
     }
 
     /// Called when a chunk has been generated and is ready to be added
+    /// Chunks that arrive after the player (or the view distance) left them behind are
+    /// dropped, since no later unload pass would catch them, as are LODs no longer wanted
+    /// for a chunk that's already loaded.
     pub fn add_chunk(&mut self, coord: ChunkCoord, chunk: LoadedChunk) {
-        self.loading_chunks.remove(&coord);
+        if self.loading_chunks.get(&coord) == Some(&chunk.lod) {
+            self.loading_chunks.remove(&coord);
+        } else if self.loaded_chunks.contains_key(&coord) {
+            return;
+        }
+        let dx = (coord.x - self.player_chunk.x).abs();
+        let dz = (coord.z - self.player_chunk.z).abs();
+        if dx > self.unload_radius || dz > self.unload_radius {
+            println!("[CHUNK] Dropped out-of-range chunk ({}, {})", coord.x, coord.z);
+            return;
+        }
         self.loaded_chunks.insert(coord, chunk);
+        self.enforce_memory_budget();
+    }
+
+    /// Cap the GPU memory of loaded chunks (bytes, None for no cap): past it, the farthest
+    /// chunks are unloaded whatever the load radius
+    pub fn set_memory_budget(&mut self, budget: Option<u64>) {
+        self.memory_budget = budget;
+        self.budget_ring = None;
+        self.radius_changed = true;
+        self.enforce_memory_budget();
+    }
+
+    pub fn memory_stats(&self) -> MemoryStats {
+        MemoryStats {
+            loaded_chunks: self.loaded_chunks.len(),
+            approx_bytes: self.loaded_chunks.values().map(LoadedChunk::gpu_bytes).sum(),
+        }
+    }
+
+    fn enforce_memory_budget(&mut self) {
+        let Some(budget) = self.memory_budget else { return };
+        let sizes = self.loaded_chunks.iter().map(|(coord, chunk)| (*coord, chunk.gpu_bytes()));
+        for coord in over_budget(sizes, self.player_chunk, budget) {
+            self.loaded_chunks.remove(&coord);
+            let ring = ring(coord, self.player_chunk);
+            self.budget_ring = Some(self.budget_ring.map_or(ring, |nearest| nearest.min(ring)));
+            println!("[CHUNK] Over the {} MB memory budget, unloaded chunk ({}, {})", budget / (1024 * 1024), coord.x, coord.z);
+        }
+    }
+
+    /// Generation priority of a chunk: nearer first, and visible ones ahead of those behind
+    pub fn priority(&self, coord: ChunkCoord, player_pos: Vec3, frustum: Option<&Frustum>) -> f32 {
+        let bounds = self.settings.bounds(coord);
+        let offset = Vec2::new(bounds.center.x - player_pos.x, bounds.center.z - player_pos.z);
+        let distance = offset.length() / self.chunk_size();
+        let in_view = frustum.is_some_and(|frustum| frustum.contains_aabb(bounds.min, bounds.max + Vec3::Y * OBJECT_HEIGHT));
+        if in_view { IN_VIEW_BONUS - distance } else { -distance }
+    }
+
+    /// Side length of a chunk in world units
+    pub fn chunk_size(&self) -> f32 {
+        self.settings.world_size()
+    }
+
+    /// Change the view distance (in chunks); loads and unloads happen on the next `update`
+    pub fn set_load_radius(&mut self, load_radius: i32) {
+        let load_radius = load_radius.max(1);
+        if load_radius != self.load_radius {
+            self.load_radius = load_radius;
+            self.unload_radius = load_radius + UNLOAD_MARGIN;
+            self.radius_changed = true;
+            self.budget_ring = None;
+        }
     }
 
     /// Get the number of chunks in each radius tier (for stats)
@@ -135,8 +468,231 @@ impl ChunkManager {
         self.loaded_chunks.iter()
     }
 
+    /// Mutable iterator over loaded chunks (per-frame pipeline settings)
+    pub fn iter_chunks_mut(&mut self) -> impl Iterator<Item = (&ChunkCoord, &mut LoadedChunk)> {
+        self.loaded_chunks.iter_mut()
+    }
+
+    /// Colliders in the chunk containing `pos` and its 8 neighbours (anything the player
+    /// can touch this step, without scanning every loaded chunk)
+    pub fn colliders_near(&self, pos: Vec3) -> impl Iterator<Item = &Collider> {
+        let center = ChunkCoord::from_world_pos(pos, self.chunk_size());
+        (-1..=1)
+            .flat_map(move |dz| (-1..=1).map(move |dx| ChunkCoord { x: center.x + dx, z: center.z + dz }))
+            .filter_map(|coord| self.loaded_chunks.get(&coord))
+            .flat_map(|chunk| chunk.colliders.iter())
+    }
+
+    /// Cache generated chunks on disk under `save_name` (loading a save, starting a new world)
+    pub fn open_store(&mut self, save_name: &str, seed: u32) {
+        self.store = Some(ChunkStore::open(save_name, seed));
+    }
+
+    /// Trails to generate new chunks with (loading a save, starting a new world)
+    pub fn set_trails(&mut self, trails: Arc<TrailNetwork>) {
+        self.trails = trails;
+    }
+
+    /// Replace the edit set (loading a save, starting a new world)
+    pub fn set_world_edits(&mut self, edits: impl IntoIterator<Item = WorldEdit>) {
+        self.world_edits = edits.into_iter().collect();
+    }
+
+    /// Edits to write into the save file
+    pub fn world_edits(&self) -> Vec<WorldEdit> {
+        self.world_edits.iter().copied().collect()
+    }
+
+    /// Tag a chunk's generated instances with their index, dropping the ones the player
+    /// removed, so a reload doesn't bring them back
+    pub fn unedited<T>(&self, chunk: ChunkCoord, kind: InstanceKind, instances: Vec<T>) -> Vec<(u32, T)> {
+        instances
+            .into_iter()
+            .enumerate()
+            .map(|(index, instance)| (index as u32, instance))
+            .filter(|(index, _)| !self.world_edits.contains(&WorldEdit::Removed { chunk, kind, index: *index }))
+            .collect()
+    }
+
+    /// Closest tree or rock within `reach` (horizontal distance) of `pos`
+    pub fn nearest_object(&self, pos: Vec3, reach: f32) -> Option<WorldEdit> {
+        let center = ChunkCoord::from_world_pos(pos, self.chunk_size());
+        let mut best: Option<(f32, WorldEdit)> = None;
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let chunk = ChunkCoord { x: center.x + dx, z: center.z + dz };
+                let Some(loaded) = self.loaded_chunks.get(&chunk) else { continue };
+                for (kind, index, position) in loaded.objects.positions() {
+                    let dist = Vec3::new(position.x - pos.x, 0.0, position.z - pos.z).length();
+                    if dist <= reach && best.is_none_or(|(d, _)| dist < d) {
+                        best = Some((dist, WorldEdit::Removed { chunk, kind, index }));
+                    }
+                }
+            }
+        }
+        best.map(|(_, edit)| edit)
+    }
+
+    /// Record an edit and drop the object from its loaded chunk's CPU list
+    /// Returns the chunk if it's loaded, so the caller can rebuild its pipelines.
+    pub fn apply_edit(&mut self, edit: WorldEdit) -> Option<&mut LoadedChunk> {
+        self.world_edits.insert(edit);
+        let WorldEdit::Removed { chunk, kind, index } = edit;
+        let loaded = self.loaded_chunks.get_mut(&chunk)?;
+        match kind {
+            InstanceKind::Tree => loaded.objects.trees.retain(|(i, _)| *i != index),
+            InstanceKind::Rock => loaded.objects.rocks.retain(|(i, _, _)| *i != index),
+        }
+        Some(loaded)
+    }
+
     /// Get total counts
     pub fn chunk_count(&self) -> usize {
         self.loaded_chunks.len()
     }
 }
+
+/// Chunk rings out from `center` (0 = the center chunk, 1 = its 8 neighbours, ...)
+fn ring(coord: ChunkCoord, center: ChunkCoord) -> i32 {
+    (coord.x - center.x).abs().max((coord.z - center.z).abs())
+}
+
+/// Chunks to unload, farthest from `center` first, to bring the total size of `chunks`
+/// (coordinate, bytes) within `budget`. The center chunk is never unloaded.
+fn over_budget(chunks: impl Iterator<Item = (ChunkCoord, u64)>, center: ChunkCoord, budget: u64) -> Vec<ChunkCoord> {
+    let mut chunks: Vec<(ChunkCoord, u64)> = chunks.collect();
+    let mut total: u64 = chunks.iter().map(|(_, bytes)| bytes).sum();
+    let distance = |coord: ChunkCoord| (ring(coord, center), (coord.x - center.x).pow(2) + (coord.z - center.z).pow(2));
+    chunks.sort_by_key(|(coord, _)| std::cmp::Reverse(distance(*coord)));
+
+    let mut unload = Vec::new();
+    for (coord, bytes) in chunks {
+        if total <= budget || coord == center {
+            break;
+        }
+        total -= bytes;
+        unload.push(coord);
+    }
+    unload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removed_instances_stay_removed() {
+        let mut manager = ChunkManager::new(ChunkSettings::default(), 2);
+        let chunk = ChunkCoord { x: 1, z: -2 };
+        manager.set_world_edits([
+            WorldEdit::Removed { chunk, kind: InstanceKind::Rock, index: 1 },
+            // Same index in another chunk, and a tree, don't affect these rocks
+            WorldEdit::Removed { chunk: ChunkCoord { x: 0, z: 0 }, kind: InstanceKind::Rock, index: 2 },
+            WorldEdit::Removed { chunk, kind: InstanceKind::Tree, index: 2 },
+        ]);
+
+        let rocks = manager.unedited(chunk, InstanceKind::Rock, vec!["a", "b", "c"]);
+        assert_eq!(rocks, vec![(0, "a"), (2, "c")]);
+        assert_eq!(manager.world_edits().len(), 3);
+    }
+
+    #[test]
+    fn test_fitted_bounds_follow_the_terrain() {
+        let settings = ChunkSettings::default();
+        let coord = ChunkCoord { x: 2, z: -1 };
+        let bounds = settings.fitted_bounds(coord, &[[0.0, 12.0, 0.0], [5.0, 3.0, 5.0], [1.0, 70.0, 2.0]]);
+        assert_eq!((bounds.min.y, bounds.max.y), (3.0 - WAVE_MARGIN, 70.0 + WAVE_MARGIN));
+        // Same footprint as the unmeasured bounds
+        let fixed = settings.bounds(coord);
+        assert_eq!((bounds.min.x, bounds.min.z, bounds.max.x, bounds.max.z), (fixed.min.x, fixed.min.z, fixed.max.x, fixed.max.z));
+        assert_eq!(settings.fitted_bounds(coord, &[]).max, fixed.max);
+    }
+
+    #[test]
+    fn test_load_radius_changes_apply_on_next_update() {
+        let mut manager = ChunkManager::new(ChunkSettings::default(), 1);
+        let player = Vec3::new(10.0, 0.0, 10.0);
+        assert_eq!(manager.update(player, 1, None).len(), 9);
+        // Pending chunks aren't requested twice
+        assert!(manager.update(player, 1, None).is_empty());
+
+        // Growing requests only the new ring, without the player moving
+        manager.set_load_radius(2);
+        assert_eq!(manager.update(player, 1, None).len(), 16);
+        assert_eq!(manager.get_stats(), (0, 25));
+
+        // Shrinking forgets the pending outer ring
+        manager.set_load_radius(1);
+        assert!(manager.update(player, 1, None).is_empty());
+        assert_eq!(manager.get_stats(), (0, 9));
+        assert_eq!(manager.unload_radius, 1 + UNLOAD_MARGIN);
+    }
+
+    #[test]
+    fn test_memory_budget_unloads_the_farthest_chunks() {
+        let center = ChunkCoord { x: 3, z: 3 };
+        let chunks = (-2..=2).flat_map(|dz| (-2..=2).map(move |dx| (ChunkCoord { x: 3 + dx, z: 3 + dz }, 10)));
+        assert!(over_budget(chunks.clone(), center, 250).is_empty());
+
+        // 25 chunks, room for 20: the 4 corners go first, then the farthest edge chunk
+        let unload = over_budget(chunks.clone(), center, 200);
+        assert_eq!(unload.len(), 5);
+        assert!(unload[..4].iter().all(|coord| (coord.x - 3).abs() == 2 && (coord.z - 3).abs() == 2));
+        assert_eq!(ring(unload[4], center), 2);
+
+        // Even a tiny budget keeps the chunk the player stands in
+        assert_eq!(over_budget(chunks, center, 0).len(), 24);
+
+        // Rings the budget unloaded aren't requested again
+        let mut manager = ChunkManager::new(ChunkSettings::default(), 2);
+        manager.budget_ring = Some(2);
+        assert_eq!(manager.update(Vec3::new(10.0, 0.0, 10.0), 1, None).len(), 9);
+        manager.set_memory_budget(None);
+        assert_eq!(manager.update(Vec3::new(10.0, 0.0, 10.0), 1, None).len(), 16);
+    }
+
+    #[test]
+    fn test_terrain_detail_follows_the_player() {
+        let settings = ChunkSettings::default();
+        assert_eq!((0..4).map(|ring| settings.lod_for_ring(ring)).collect::<Vec<_>>(), vec![0, 0, 1, 2]);
+        // Coarser levels keep the chunk size
+        assert_eq!(settings.lod_grid(0), (64, 4.0));
+        assert_eq!(settings.lod_grid(2), (16, 16.0));
+
+        let mut manager = ChunkManager::new(settings, 2);
+        let far = ChunkCoord { x: 2, z: 0 };
+        let requests = manager.update(Vec3::new(10.0, 0.0, 10.0), 1, None);
+        assert_eq!(requests.iter().find(|req| req.coord == far).unwrap().lod, 1);
+
+        // Walking a chunk towards it asks for the full-detail terrain instead
+        let requests = manager.update(Vec3::new(266.0, 0.0, 10.0), 1, None);
+        assert_eq!(requests.iter().find(|req| req.coord == far).unwrap().lod, 0);
+        assert_eq!(manager.loading_chunks[&far], 0);
+        // Chunks whose ring didn't change aren't asked for twice
+        assert!(requests.iter().all(|req| req.coord != ChunkCoord { x: 1, z: 0 }));
+    }
+
+    #[test]
+    fn test_visible_chunks_generate_first() {
+        let manager = ChunkManager::new(ChunkSettings::default(), 2);
+        let player = Vec3::new(128.0, 20.0, 128.0); // Center of chunk (0, 0)
+        let view = Mat4::look_at_rh(player, player + Vec3::X, Vec3::Y);
+        let frustum = Frustum::from_view_proj(&(Mat4::perspective_rh(1.2, 1.6, 0.1, 2000.0) * view));
+
+        let ahead = ChunkCoord { x: 2, z: 0 };
+        let behind = ChunkCoord { x: -1, z: 0 };
+        assert!(manager.priority(ahead, player, Some(&frustum)) > manager.priority(behind, player, Some(&frustum)));
+        // Without a camera, distance alone decides
+        assert!(manager.priority(ahead, player, None) < manager.priority(behind, player, None));
+
+        let queue = ChunkQueue::default();
+        for coord in [behind, ahead, ChunkCoord { x: 0, z: 2 }] {
+            queue.push(ChunkRequest { coord, seed: 1, lod: 0, store: None, trails: Arc::default(), priority: manager.priority(coord, player, Some(&frustum)) });
+        }
+        // Turned around: re-scored against no view, and (0, 2) is no longer wanted
+        queue.reprioritize(|request| (request.coord.z == 0).then(|| manager.priority(request.coord, player, None)));
+        assert_eq!(queue.pop().coord, behind);
+        assert_eq!(queue.pop().coord, ahead);
+        assert!(queue.0.0.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+use glam::Mat4;
+use serde::{Serialize, Deserialize};
+use crate::chunk_manager::ChunkCoord;
+
+/// Bump when the generators or `ChunkData` change, so old caches are regenerated
+const CHUNK_FORMAT: u32 = 9;
+
+/// Everything the generation thread produces for one chunk
+#[derive(Serialize, Deserialize)]
+pub struct ChunkData {
+    // Terrain
+    pub terrain_pos: Vec<[f32; 3]>,
+    pub terrain_col: Vec<[f32; 3]>,
+    pub terrain_nrm: Vec<[f32; 3]>,
+    pub terrain_splat: Vec<[f32; 4]>, // Texture weights: sand, grass, rock, snow
+    pub terrain_idx: Vec<u32>,
+    pub terrain_lod: u32, // Detail level (see `ChunkSettings::lod_grid`), skirt vertices after the grid
+    // Grass blades (empty with GPU placement)
+    #[serde(with = "grass_instances")]
+    pub grass_instances: Vec<croatoan_wfc::GrassInstance>,
+    pub plant_instances: Vec<(String, Mat4)>, // Named instances (bushes, ferns, flowers)
+    #[serde(with = "tree_instances")]
+    pub tree_instances: Vec<croatoan_wfc::TreeInstance>,
+    // Detritus
+    pub det_pos: Vec<[f32; 3]>,
+    pub det_nrm: Vec<[f32; 3]>,
+    pub det_uv: Vec<[f32; 2]>,
+    pub det_idx: Vec<u32>,
+    pub rock_instances: Vec<(String, Mat4)>, // Named instances
+    pub building_instances: Vec<(String, Mat4)>, // Named instances
+    // World space offset
+    pub offset_x: i32,
+    pub offset_z: i32,
+}
+
+/// Header + data as written to disk
+#[derive(Serialize)]
+struct ChunkFileRef<'a> {
+    format: u32,
+    seed: u32,
+    data: &'a ChunkData,
+}
+
+#[derive(Deserialize)]
+struct ChunkFile {
+    format: u32,
+    seed: u32,
+    data: ChunkData,
+}
+
+/// On-disk cache of generated chunks for one save (`saves/<name>/chunks/<x>_<z>_lod<n>.bin`)
+/// Loading is synchronous (call it from the generation thread); saves are encoded by the
+/// caller and written by a background writer thread. Files from another seed or format
+/// are ignored and get overwritten.
+#[derive(Clone)]
+pub struct ChunkStore {
+    dir: PathBuf,
+    seed: u32,
+    writer: Sender<(PathBuf, Vec<u8>)>,
+}
+
+impl ChunkStore {
+    pub fn open(save_name: &str, seed: u32) -> Self {
+        Self::in_dir(Path::new("saves").join(save_name).join("chunks"), seed)
+    }
+
+    fn in_dir(dir: PathBuf, seed: u32) -> Self {
+        let (writer, files) = channel::<(PathBuf, Vec<u8>)>();
+        // Exits once every clone of the store is dropped
+        thread::spawn(move || {
+            while let Ok((path, bytes)) = files.recv() {
+                if let Err(e) = write_file(&path, &bytes) {
+                    println!("[CHUNK] Failed to write {}: {}", path.display(), e);
+                }
+            }
+        });
+        Self { dir, seed, writer }
+    }
+
+    fn path(&self, coord: ChunkCoord, lod: u32) -> PathBuf {
+        self.dir.join(format!("{}_{}_lod{}.bin", coord.x, coord.z, lod))
+    }
+
+    /// Queue a generated chunk to be written
+    pub fn save_chunk(&self, coord: ChunkCoord, data: &ChunkData) {
+        match self.encode(data) {
+            Ok(bytes) => {
+                let _ = self.writer.send((self.path(coord, data.terrain_lod), bytes));
+            }
+            Err(e) => println!("[CHUNK] Failed to encode chunk ({}, {}): {}", coord.x, coord.z, e),
+        }
+    }
+
+    /// Read a chunk saved for this seed at terrain LOD `lod`, if there is one
+    pub fn load_chunk(&self, coord: ChunkCoord, lod: u32) -> Option<ChunkData> {
+        let bytes = fs::read(self.path(coord, lod)).ok()?;
+        self.decode(&bytes)
+    }
+
+    fn encode(&self, data: &ChunkData) -> bincode::Result<Vec<u8>> {
+        bincode::serialize(&ChunkFileRef { format: CHUNK_FORMAT, seed: self.seed, data })
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<ChunkData> {
+        let file: ChunkFile = bincode::deserialize(bytes).ok()?;
+        (file.format == CHUNK_FORMAT && file.seed == self.seed).then_some(file.data)
+    }
+}
+
+/// Write via a temp file so a crash mid-write never leaves a truncated chunk
+fn write_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+/// `croatoan_wfc::GrassInstance` isn't serde-aware; store its fields as a tuple
+mod grass_instances {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use croatoan_wfc::GrassInstance;
+
+    type Fields = ([f32; 3], f32, f32, f32, [f32; 3]);
+
+    pub fn serialize<S: Serializer>(blades: &[GrassInstance], serializer: S) -> Result<S::Ok, S::Error> {
+        let fields: Vec<Fields> = blades.iter().map(|blade| (blade.position, blade.height, blade.bend, blade.rotation, blade.color)).collect();
+        fields.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<GrassInstance>, D::Error> {
+        let fields = Vec::<Fields>::deserialize(deserializer)?;
+        Ok(fields.into_iter().map(|(position, height, bend, rotation, color)| GrassInstance { position, height, bend, rotation, color }).collect())
+    }
+}
+
+/// `croatoan_wfc::TreeInstance` isn't serde-aware; store its fields as a tuple
+mod tree_instances {
+    use glam::Mat4;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use croatoan_wfc::TreeInstance;
+
+    pub fn serialize<S: Serializer>(trees: &[TreeInstance], serializer: S) -> Result<S::Ok, S::Error> {
+        let fields: Vec<(Mat4, u8, [f32; 3])> = trees.iter().map(|tree| (tree.transform, tree.species, tree.tint)).collect();
+        fields.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<TreeInstance>, D::Error> {
+        let fields = Vec::<(Mat4, u8, [f32; 3])>::deserialize(deserializer)?;
+        Ok(fields.into_iter().map(|(transform, species, tint)| TreeInstance { transform, species, tint }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_chunk() -> ChunkData {
+        ChunkData {
+            terrain_pos: vec![[0.0, 1.0, 2.0], [4.0, 1.5, 0.0]],
+            terrain_col: vec![[0.2, 0.5, 0.1]; 2],
+            terrain_nrm: vec![[0.0, 1.0, 0.0]; 2],
+            terrain_splat: vec![[0.0, 1.0, 0.0, 0.0]; 2],
+            terrain_idx: vec![0, 1, 0],
+            terrain_lod: 1,
+            grass_instances: vec![croatoan_wfc::GrassInstance {
+                position: [1.0, 2.0, 3.0],
+                height: 0.8,
+                bend: 0.5,
+                rotation: 1.2,
+                color: [0.4, 0.8, 0.2],
+            }],
+            plant_instances: vec![("plant_fern".to_string(), Mat4::IDENTITY)],
+            tree_instances: vec![croatoan_wfc::TreeInstance {
+                transform: Mat4::from_translation(glam::Vec3::new(3.0, 4.0, 5.0)),
+                species: 1,
+                tint: [0.9, 1.0, 1.1],
+            }],
+            det_pos: Vec::new(),
+            det_nrm: Vec::new(),
+            det_uv: Vec::new(),
+            det_idx: Vec::new(),
+            rock_instances: vec![("boulder".to_string(), Mat4::IDENTITY)],
+            building_instances: Vec::new(),
+            offset_x: 256,
+            offset_z: -512,
+        }
+    }
+
+    #[test]
+    fn test_chunk_round_trip_and_seed_invalidation() {
+        let dir = std::env::temp_dir().join(format!("roanoke_chunk_store_{}", std::process::id()));
+        let store = ChunkStore::in_dir(dir.clone(), 42);
+        let coord = ChunkCoord { x: 1, z: -2 };
+        assert!(store.load_chunk(coord, 1).is_none());
+
+        write_file(&store.path(coord, 1), &store.encode(&sample_chunk()).unwrap()).unwrap();
+        let loaded = store.load_chunk(coord, 1).unwrap();
+        assert_eq!(loaded.terrain_pos, sample_chunk().terrain_pos);
+        assert_eq!(loaded.grass_instances, sample_chunk().grass_instances);
+        assert_eq!(loaded.tree_instances[0].transform, sample_chunk().tree_instances[0].transform);
+        assert_eq!(loaded.tree_instances[0].species, 1);
+        assert_eq!(loaded.rock_instances[0].0, "boulder");
+        assert_eq!(loaded.plant_instances[0].0, "plant_fern");
+        assert_eq!((loaded.offset_x, loaded.offset_z), (256, -512));
+        // Other detail levels are cached separately
+        assert!(store.load_chunk(coord, 0).is_none());
+
+        // Same save name, new seed: the cached chunk is stale
+        assert!(ChunkStore::in_dir(dir.clone(), 43).load_chunk(coord, 1).is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
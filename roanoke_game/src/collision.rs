@@ -0,0 +1,211 @@
+use glam::{Mat4, Vec2, Vec3};
+
+/// Trunk of the oak model in its own units (instances scale it, ~5x)
+const TRUNK_RADIUS: f32 = 0.08;
+const TRUNK_HEIGHT: f32 = 1.0;
+
+/// Solid shapes the player can't walk through. Only the horizontal overlap is resolved;
+/// the vertical extent just decides whether the player is beside the shape or above it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collider {
+    /// Building: its mesh bounds, placed by a yaw-rotated transform
+    Box {
+        center: Vec3,
+        half_extents: Vec3,
+        /// Rotation about +Y (radians)
+        yaw: f32,
+    },
+    /// Tree trunk: upright capsule from `base` up `height`
+    Capsule {
+        base: Vec3,
+        radius: f32,
+        height: f32,
+    },
+}
+
+impl Collider {
+    /// Box collider for a building mesh with local bounds `(min, max)` placed at `transform`
+    pub fn building(bounds: (Vec3, Vec3), transform: Mat4) -> Self {
+        let (_, rotation, _) = transform.to_scale_rotation_translation();
+        let (min, max) = bounds;
+        Collider::Box {
+            center: transform.transform_point3((min + max) * 0.5),
+            half_extents: (max - min) * 0.5,
+            yaw: rotation.to_euler(glam::EulerRot::YXZ).0,
+        }
+    }
+
+    /// Trunk capsule for a placed tree (scale taken from the instance transform)
+    pub fn tree_trunk(transform: Mat4) -> Self {
+        let scale = transform.x_axis.truncate().length();
+        Collider::Capsule {
+            base: transform.w_axis.truncate(),
+            radius: TRUNK_RADIUS * scale,
+            height: TRUNK_HEIGHT * scale,
+        }
+    }
+
+    /// Horizontal offset that moves a vertical cylinder (`radius`, spanning `bottom..top`
+    /// at `position.xz`) out of this shape, or None if they don't overlap
+    pub fn push_out(&self, position: Vec3, radius: f32, bottom: f32, top: f32) -> Option<Vec3> {
+        match *self {
+            Collider::Box { center, half_extents, yaw } => {
+                if top < center.y - half_extents.y || bottom > center.y + half_extents.y {
+                    return None;
+                }
+
+                // Work in the box's frame, where it's axis-aligned
+                let (sin, cos) = yaw.sin_cos();
+                let d = Vec2::new(position.x - center.x, position.z - center.z);
+                let local = Vec2::new(d.x * cos - d.y * sin, d.x * sin + d.y * cos);
+                let half = Vec2::new(half_extents.x, half_extents.z);
+
+                let closest = local.clamp(-half, half);
+                let offset = local - closest;
+                let push = if offset == Vec2::ZERO {
+                    // Center inside the box: out through the nearest face
+                    let depth = half - local.abs();
+                    if depth.x < depth.y {
+                        Vec2::new((depth.x + radius) * local.x.signum(), 0.0)
+                    } else {
+                        Vec2::new(0.0, (depth.y + radius) * local.y.signum())
+                    }
+                } else {
+                    let dist = offset.length();
+                    if dist >= radius {
+                        return None;
+                    }
+                    offset / dist * (radius - dist)
+                };
+
+                // Back to world orientation
+                let world = Vec2::new(push.x * cos + push.y * sin, -push.x * sin + push.y * cos);
+                Some(Vec3::new(world.x, 0.0, world.y))
+            }
+            Collider::Capsule { base, radius: trunk_radius, height } => {
+                if top < base.y - trunk_radius || bottom > base.y + height + trunk_radius {
+                    return None;
+                }
+                let d = Vec2::new(position.x - base.x, position.z - base.z);
+                let dist = d.length();
+                let min_dist = radius + trunk_radius;
+                if dist >= min_dist {
+                    return None;
+                }
+                let dir = if dist > 1e-5 { d / dist } else { Vec2::X };
+                let push = dir * (min_dist - dist);
+                Some(Vec3::new(push.x, 0.0, push.y))
+            }
+        }
+    }
+
+    /// Distance along the ray from `origin` (unit `dir`) to where it enters this shape, if
+    /// within `max_dist`. Trunks are treated as plain cylinders. A ray starting inside hits at 0.
+    pub fn ray_hit(&self, origin: Vec3, dir: Vec3, max_dist: f32) -> Option<f32> {
+        let (enter, exit) = match *self {
+            Collider::Box { center, half_extents, yaw } => {
+                // Same frame as `push_out`, with y along for the ride
+                let (sin, cos) = yaw.sin_cos();
+                let rotate = |v: Vec3| Vec3::new(v.x * cos - v.z * sin, v.y, v.x * sin + v.z * cos);
+                let local_origin = rotate(origin - center);
+                let local_dir = rotate(dir);
+                let (mut enter, mut exit) = (0.0_f32, max_dist);
+                for axis in 0..3 {
+                    let (o, d, half) = (local_origin[axis], local_dir[axis], half_extents[axis]);
+                    if d.abs() < 1e-8 {
+                        if o.abs() > half {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let (t0, t1) = ((-half - o) / d, (half - o) / d);
+                    enter = enter.max(t0.min(t1));
+                    exit = exit.min(t0.max(t1));
+                }
+                (enter, exit)
+            }
+            Collider::Capsule { base, radius, height } => {
+                // Side of the cylinder, in the horizontal plane
+                let o = Vec2::new(origin.x - base.x, origin.z - base.z);
+                let d = Vec2::new(dir.x, dir.z);
+                let (a, b, c) = (d.length_squared(), o.dot(d), o.length_squared() - radius * radius);
+                let (mut enter, mut exit) = if a < 1e-8 {
+                    if c > 0.0 {
+                        return None;
+                    }
+                    (0.0_f32, max_dist)
+                } else {
+                    let discriminant = b * b - a * c;
+                    if discriminant < 0.0 {
+                        return None;
+                    }
+                    let root = discriminant.sqrt();
+                    (((-b - root) / a).max(0.0), ((-b + root) / a).min(max_dist))
+                };
+                // Clipped to its height
+                if dir.y.abs() < 1e-8 {
+                    if origin.y < base.y || origin.y > base.y + height {
+                        return None;
+                    }
+                } else {
+                    let (t0, t1) = ((base.y - origin.y) / dir.y, (base.y + height - origin.y) / dir.y);
+                    enter = enter.max(t0.min(t1));
+                    exit = exit.min(t0.max(t1));
+                }
+                (enter, exit)
+            }
+        };
+        (enter <= exit).then_some(enter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    #[test]
+    fn test_rotated_box_pushes_out_along_its_faces() {
+        // 4x2 footprint turned a quarter turn about Y
+        let transform = Mat4::from_rotation_translation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2), Vec3::new(10.0, 0.0, 0.0));
+        let collider = Collider::building((Vec3::new(-2.0, 0.0, -1.0), Vec3::new(2.0, 3.0, 1.0)), transform);
+
+        // Rotated, the long (x) side runs along world z: 1.5 from center in x is outside
+        assert!(collider.push_out(Vec3::new(11.5, 1.0, 0.0), 0.4, 0.0, 1.8).is_none());
+        // ...but 1.5 along z is inside and gets pushed toward +z
+        let push = collider.push_out(Vec3::new(10.0, 1.0, 1.5), 0.4, 0.0, 1.8).unwrap();
+        assert!(push.z > 0.0 && push.x.abs() < 1e-4 && push.y == 0.0, "{:?}", push);
+        assert!((1.5 + push.z - 2.4).abs() < 1e-4);
+
+        // Standing on the roof is not a wall hit
+        assert!(collider.push_out(Vec3::new(10.0, 5.0, 0.0), 0.4, 3.5, 5.3).is_none());
+    }
+
+    #[test]
+    fn test_rays_hit_boxes_and_trunks() {
+        let transform = Mat4::from_rotation_translation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2), Vec3::new(10.0, 0.0, 0.0));
+        let building = Collider::building((Vec3::new(-2.0, 0.0, -1.0), Vec3::new(2.0, 3.0, 1.0)), transform);
+        // Rotated, the box spans x 9..11: hit its near face
+        let hit = building.ray_hit(Vec3::new(0.0, 1.0, 0.0), Vec3::X, 50.0).unwrap();
+        assert!((hit - 9.0).abs() < 1e-4, "{}", hit);
+        // Too short, over the roof, or pointing away: no hit
+        assert!(building.ray_hit(Vec3::new(0.0, 1.0, 0.0), Vec3::X, 5.0).is_none());
+        assert!(building.ray_hit(Vec3::new(0.0, 4.0, 0.0), Vec3::X, 50.0).is_none());
+        assert!(building.ray_hit(Vec3::new(0.0, 1.0, 0.0), Vec3::NEG_X, 50.0).is_none());
+
+        let trunk = Collider::Capsule { base: Vec3::new(0.0, 0.0, 5.0), radius: 0.5, height: 4.0 };
+        let hit = trunk.ray_hit(Vec3::new(0.0, 2.0, 0.0), Vec3::Z, 50.0).unwrap();
+        assert!((hit - 4.5).abs() < 1e-4, "{}", hit);
+        assert!(trunk.ray_hit(Vec3::new(0.0, 5.0, 0.0), Vec3::Z, 50.0).is_none());
+        // Starting inside
+        assert_eq!(trunk.ray_hit(Vec3::new(0.0, 2.0, 5.0), Vec3::X, 50.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_trunk_keeps_distance() {
+        let collider = Collider::tree_trunk(Mat4::from_scale_rotation_translation(Vec3::splat(5.0), Quat::IDENTITY, Vec3::ZERO));
+        let push = collider.push_out(Vec3::new(0.5, 1.0, 0.0), 0.4, 0.0, 1.8).unwrap();
+        assert!((0.5 + push.x - 0.8).abs() < 1e-4, "{:?}", push);
+        assert!(collider.push_out(Vec3::new(1.0, 1.0, 0.0), 0.4, 0.0, 1.8).is_none());
+    }
+}
@@ -1,24 +1,30 @@
-use croatoan_core::{App, CursorGrabMode, DeviceEvent, ElementState, KeyCode, PhysicalKey, WinitEvent as Event, WinitWindowEvent as WindowEvent};
-use croatoan_wfc::{generate_terrain_chunk, generate_vegetation_for_chunk, generate_trees_for_chunk, generate_detritus_for_chunk, generate_rocks_for_chunk, generate_buildings_for_chunk, TreeTemplate};
-use croatoan_render::{Camera, TerrainPipeline, ShadowMap, ShadowPipeline, GrassPipeline, TreePipeline, TreeMesh, DetritusPipeline, BuildingPipeline, BuildingMesh, BuildingVertex, Frustum, ChunkBounds, SunPipeline, SkyPipeline};
-use croatoan_procgen::{TreeRecipe, generate_tree, generate_tree_mesh, RockRecipe, generate_rock, BuildingRecipe, generate_building};
+use croatoan_core::{App, CursorGrabMode, DeviceEvent, ElementState, FrameContext, GamepadButton, KeyCode, PhysicalKey, WinitEvent as Event, WinitWindowEvent as WindowEvent};
+use croatoan_wfc::{raycast_terrain, SeededRng, WorldSeed};
+use croatoan_wfc::rocks::{ROCK_BOULDER, ROCK_CLIFF, ROCK_RIVER_STONE, ROCK_SHARP};
+use croatoan_wfc::buildings::{building_recipe, BUILDING_APARTMENT, BUILDING_CHURCH, BUILDING_COLONIAL, BUILDING_KINDS};
+use croatoan_wfc::trees::SPECIES_OAK;
+use croatoan_wfc::trails::TrailNetwork;
+use croatoan_wfc::vegetation::{plant_recipe, PLANT_KINDS};
+use croatoan_render::{AssetCache, Camera, TerrainPipeline, TerrainLighting, ShadowMap, FilterQuality, ShadowPipeline, GrassPipeline, GrassPlacement, GrassHeightfield, GrassInteraction, GrassInstance, TreePipeline, TreeMesh, TreeInstance, LeafInstance, DetritusPipeline, BuildingPipeline, BuildingMesh, BuildingVertex, RockPipeline, RockMesh, RockVertex, Frustum, SunPipeline, MoonPipeline, MoonState, SkyPipeline, SkyParams, RainPipeline, PostProcessPipeline, SsaoPipeline, OcclusionCuller, DebugLines, DepthPrepass, RenderTarget, Specular, TransparentQueue};
+use croatoan_render::moon_pipeline::lunar_phase;
+use croatoan_render::terrain_textures::{self, TerrainMaterial};
+use croatoan_procgen::{TreeRecipe, generate_tree, generate_tree_mesh, generate_leaf_instances, RockRecipe, generate_rock, generate_building, generate_plant};
 use glam::{Vec3, Mat4};
 use wgpu;
 use image; // Added image crate
 use std::sync::{Arc, Mutex, OnceLock};
-use std::time::Instant;
-use serde::{Serialize, Deserialize};
-use std::fs::File;
-use std::io::{Read, Write};
-use std::fs;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::thread;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
 
 mod player;
 mod chunk_manager;
+mod chunk_store;
+mod chunk_gen;
+mod collision;
 mod asset_loader;
 use player::Player;
-use chunk_manager::{ChunkManager, ChunkCoord, ChunkRequest, LoadedChunk};
+use chunk_manager::{ChunkManager, ChunkCoord, ChunkObjects, ChunkQueue, ChunkSettings, InstanceKind, LoadedChunk, WorldEdit};
+use collision::Collider;
+use chunk_store::ChunkData;
 
 // Extend LoadedChunk to include buildings (we can't modify the struct definition in chunk_manager.rs from here easily without replacing the file, 
 // but wait, LoadedChunk is defined in chunk_manager.rs. I need to modify chunk_manager.rs FIRST or define a wrapper.
@@ -33,11 +39,25 @@ mod water_system;
 use water_system::WaterSystem;
 mod weather_system;
 use weather_system::{WeatherSystem, WeatherType};
+mod map_view;
+use map_view::{MapView, MAP_TEXTURE_SIZE};
+mod settings;
+use settings::Settings;
+mod save;
+mod third_person;
+use third_person::{collide_camera, player_capsule, CameraMode, THIRD_PERSON_OFFSET, THIRD_PERSON_STIFFNESS};
+use save::{list_saves, load_game, save_game, SaveData, SAVE_VERSION};
+#[cfg(feature = "audio")]
+mod ambience;
 
 // ... (Existing structs remain same) ...
 
 
 
+/// Scatter and cull grass in a compute pass instead of placing blades on the generation
+/// thread. Set to false to fall back to `generate_vegetation_for_chunk`.
+const GPU_GRASS_PLACEMENT: bool = true;
+
 // --- Game State & Save System ---
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,12 +67,17 @@ enum GameState {
     Playing,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct SaveData {
-    seed: u32,
-    player_pos: [f32; 3],
-    player_rot: [f32; 2], // Yaw, Pitch
-    inventory: Vec<String>,
+/// How far the player can reach to harvest (horizontal meters)
+const HARVEST_REACH: f32 = 3.0;
+
+/// How long a toast message stays on screen (seconds)
+const TOAST_DURATION: f32 = 4.0;
+
+/// Short message at the bottom of the screen (save/load results)
+struct Toast {
+    text: String,
+    is_error: bool,
+    remaining: f32, // Seconds left on screen
 }
 
 struct LoadingProgress {
@@ -67,72 +92,183 @@ struct SharedState {
     game_state: GameState,
     seed: u32,
     seed_input: String,
+    trails: Arc<TrailNetwork>, // Planned when a world starts
     inventory: Vec<String>,
     egui_state: Option<egui_winit::State>,
     egui_ctx: egui::Context,
     // FPS & Save System
     fps: f32,
-    last_frame_time: Instant,
     save_name_input: String,
     // Player
     player: Player,
+    camera_mode: CameraMode,
+    player_model: Option<BuildingPipeline>, // Drawn in third person
     keys: std::collections::HashMap<KeyCode, ElementState>,
     // Time
     time_of_day: f32, // 0.0 - 24.0
+    day_count: u32,   // Days passed (drives the moon phase)
+    settings: Settings,
     // Loading Progress
     loading_progress: LoadingProgress,
     // Asset Registry
-    mesh_registry: std::collections::HashMap<String, TreeMesh>, // For Trees/Rocks
-    building_registry: std::collections::HashMap<String, Arc<BuildingMesh>>, // For Buildings
+    mesh_registry: std::collections::HashMap<String, TreeMesh>, // For Trees
+    asset_cache: AssetCache, // Textures, uploaded once and shared between meshes
+    rock_registry: std::collections::HashMap<String, Arc<RockMesh>>, // For Rocks
+    building_batches: std::collections::HashMap<String, BuildingPipeline>, // One instanced draw per building type, filled each frame
+    building_bounds: std::collections::HashMap<String, (Vec3, Vec3)>, // Local AABB per building type (collision)
+    plant_registry: std::collections::HashMap<String, Arc<BuildingMesh>>, // Bushes, ferns, flowers
     background_texture: Option<egui::TextureHandle>, // For Home Screen
     loading_texture: Option<egui::TextureHandle>, // For Loading Screen
     weather: WeatherSystem,
+    map: MapView,
+    grass_interaction: GrassInteraction,
+    #[cfg(feature = "audio")]
+    ambience: ambience::Ambience,
+    screenshot_requested: bool, // F12: captured just before the next present
+    harvest_requested: bool, // E: take the nearest tree/rock on the next frame
+    // Debug views
+    wireframe_toggle_requested: bool, // F3: terrain wireframe, applied on the next frame
+    show_chunk_bounds: bool, // F4: chunk boxes (green drawn, yellow frustum-culled, red occluded)
+    frozen_frustum: Option<Mat4>, // F5: view-projection kept to inspect culling from outside
+    toast: Option<Toast>,
 }
 
-fn save_game(name: &str, data: &SaveData) {
-    let _ = fs::create_dir_all("saves");
-    let path = format!("saves/{}.json", name);
-    if let Ok(json) = serde_json::to_string_pretty(data) {
-        if let Ok(mut file) = File::create(&path) {
-            let _ = file.write_all(json.as_bytes());
-            println!("[SAVE] Game saved to {}", path);
+impl SharedState {
+    fn add_item(&mut self, item: &str) {
+        self.inventory.push(item.to_string());
+        println!("[GAME] Picked up {} ({} carried)", item, self.item_count(item));
+    }
+
+    /// Remove one `item`; false if none is carried
+    fn remove_item(&mut self, item: &str) -> bool {
+        match self.inventory.iter().position(|carried| carried == item) {
+            Some(index) => {
+                self.inventory.remove(index);
+                true
+            }
+            None => false,
         }
     }
+
+    fn item_count(&self, item: &str) -> usize {
+        self.inventory.iter().filter(|carried| *carried == item).count()
+    }
+
+    /// Show `text` at the bottom of the screen for a few seconds (in red if `is_error`)
+    fn show_toast(&mut self, text: String, is_error: bool) {
+        self.toast = Some(Toast { text, is_error, remaining: TOAST_DURATION });
+    }
 }
 
-fn load_game(name: &str) -> Option<SaveData> {
-    let path = format!("saves/{}.json", name);
-    if let Ok(mut file) = File::open(&path) {
-        let mut json = String::new();
-        if file.read_to_string(&mut json).is_ok() {
-            if let Ok(data) = serde_json::from_str::<SaveData>(&json) {
-                println!("[LOAD] Game loaded: Seed {}", data.seed);
-                return Some(data);
-            }
+/// Tree pipeline for a chunk's trees (None if there are none or the mesh isn't loaded)
+fn build_tree_pipeline(ctx: &croatoan_render::GraphicsContext, mesh: Option<&TreeMesh>, objects: &ChunkObjects) -> Option<TreePipeline> {
+    let mesh = mesh?;
+    if objects.trees.is_empty() {
+        return None;
+    }
+    let mut tp = TreePipeline::new(ctx.device(), ctx.queue(), ctx.scene_format(), ctx.sample_count());
+    tp.set_mesh(mesh.clone());
+    let instances: Vec<TreeInstance> = objects.trees
+        .iter()
+        .map(|(_, tree)| TreeInstance { transform: tree.transform, species: tree.species, tint: tree.tint })
+        .collect();
+    tp.upload_instances(ctx.device(), &instances);
+    Some(tp)
+}
+
+/// One rock pipeline per rock type in the chunk
+fn build_rock_pipelines(
+    ctx: &croatoan_render::GraphicsContext,
+    registry: &std::collections::HashMap<String, Arc<RockMesh>>,
+    objects: &ChunkObjects,
+) -> Vec<RockPipeline> {
+    // Group rocks by type
+    let mut rock_groups: std::collections::HashMap<&str, Vec<Mat4>> = std::collections::HashMap::new();
+    for (_, name, transform) in &objects.rocks {
+        rock_groups.entry(name.as_str()).or_default().push(*transform);
+    }
+
+    let mut rock_pipelines = Vec::new();
+    for (name, transforms) in rock_groups {
+        if let Some(mesh) = registry.get(name) {
+            let mut rp = RockPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count());
+            rp.set_mesh(mesh.clone());
+            rp.upload_instances(ctx.device(), &transforms);
+            rock_pipelines.push(rp);
+        } else {
+            println!("[WARN] Unknown rock type '{}' requested by generator", name);
         }
     }
-    println!("[LOAD] Save file '{}' not found or invalid.", name);
-    None
+    rock_pipelines
 }
 
-fn list_saves() -> Vec<String> {
-    let mut saves = Vec::new();
-    if let Ok(entries) = fs::read_dir("saves") {
-        for entry in entries.flatten() {
-            if let Ok(file_type) = entry.file_type() {
-                if file_type.is_file() {
-                    if let Some(name) = entry.path().file_stem() {
-                        if let Some(name_str) = name.to_str() {
-                            saves.push(name_str.to_string());
-                        }
-                    }
-                }
+/// Refill the per-type building batches from this frame's chunks, one instance buffer each
+/// Chunks paired with `true` are drawn; `false` ones only cast shadows.
+fn fill_building_batches<'a>(
+    ctx: &croatoan_render::GraphicsContext,
+    batches: &mut std::collections::HashMap<String, BuildingPipeline>,
+    chunks: impl Iterator<Item = (&'a LoadedChunk, bool)>,
+) {
+    let mut instances: std::collections::HashMap<&str, (Vec<Mat4>, Vec<Mat4>)> = std::collections::HashMap::new();
+    for (chunk, visible) in chunks {
+        for (name, transform) in &chunk.buildings {
+            let (drawn, shadow_only) = instances.entry(name.as_str()).or_default();
+            if visible {
+                drawn.push(*transform);
+            } else {
+                shadow_only.push(*transform);
             }
         }
     }
-    saves
+    for (name, batch) in batches.iter_mut() {
+        let (drawn, shadow_only) = instances.remove(name.as_str()).unwrap_or_default();
+        batch.write_instances(ctx.device(), ctx.queue(), &drawn, &shadow_only);
+    }
+}
+
+/// Oak trunk colliders (bushes are walk-through)
+fn trunk_colliders(objects: &ChunkObjects) -> impl Iterator<Item = Collider> + '_ {
+    objects.trees
+        .iter()
+        .filter(|(_, tree)| tree.species == SPECIES_OAK)
+        .map(|(_, tree)| Collider::tree_trunk(tree.transform))
+}
+
+/// Key light color for a sun elevation (-1 midnight .. 1 noon), matching `light_dir`'s switch
+/// at -0.1: warm orange at sunrise/sunset, white-yellow at midday, fading out as the sun sets,
+/// then a faint cool moonlight as the moon rises
+fn key_light_color(sun_elevation: f32) -> Vec3 {
+    if sun_elevation > -0.1 {
+        let sunrise = Vec3::new(1.8, 0.6, 0.2);
+        let midday = Vec3::new(1.4, 1.3, 1.1);
+        let sunset_fade = ((sun_elevation + 0.1) / 0.15).clamp(0.0, 1.0);
+        sunrise.lerp(midday, (sun_elevation * 2.0).clamp(0.0, 1.0)) * sunset_fade
+    } else {
+        let moonlight = Vec3::new(0.12, 0.16, 0.28);
+        let moonrise_fade = ((-sun_elevation - 0.1) / 0.2).clamp(0.0, 1.0);
+        moonlight * moonrise_fade
+    }
+}
+
+/// How brightly lamplit windows glow for a sun elevation: fully on once `is_day` turns false
+/// (-0.1), fading in through dusk so villages light up as the sun goes down
+fn window_glow(sun_elevation: f32) -> f32 {
+    ((0.05 - sun_elevation) / 0.15).clamp(0.0, 1.0)
+}
+
+/// Highlight per building material: painted clapboard and whitewash have a soft sheen, concrete
+/// and glass a tighter one, rough logs and stone barely any
+fn building_specular(name: &str) -> Specular {
+    match name {
+        BUILDING_COLONIAL | BUILDING_CHURCH => Specular::new(0.25, 32.0),
+        BUILDING_APARTMENT => Specular::new(0.35, 64.0),
+        _ => Specular::MATTE,
+    }
 }
 
+// Chunk Manager: created by the render callback, also read by the update callback (collision)
+static CHUNK_MANAGER: OnceLock<Mutex<ChunkManager>> = OnceLock::new();
+
 // --- Main Entry Point ---
 
 fn main() {
@@ -140,6 +276,7 @@ fn main() {
 
     // Initialize App
     let mut app = App::new("Roanoke Engine", 1280, 720);
+    app.set_sample_count(4); // MSAA: smooths terrain/building edges
 
 
     
@@ -157,15 +294,19 @@ fn main() {
         game_state: GameState::Menu,
         seed: 12345,
         seed_input: "12345".to_string(),
+        trails: Arc::default(),
         inventory: Vec::new(),
         egui_state: None,
         egui_ctx: egui::Context::default(),
         fps: 0.0,
-        last_frame_time: Instant::now(),
         save_name_input: String::new(),
         player: Player::new(Vec3::new(0.0, 50.0, 0.0)), // Start high up
+        camera_mode: CameraMode::FirstPerson,
+        player_model: None,
         keys: std::collections::HashMap::new(),
         time_of_day: 12.0, // Start at noon
+        day_count: 0,
+        settings: Settings::load(),
         loading_progress: LoadingProgress {
             total_chunks: 0,
             chunks_generated: 0,
@@ -173,108 +314,61 @@ fn main() {
             current_status: String::new(),
         },
         mesh_registry: std::collections::HashMap::new(),
-        building_registry: std::collections::HashMap::new(),
+        asset_cache: AssetCache::default(),
+        rock_registry: std::collections::HashMap::new(),
+        building_batches: std::collections::HashMap::new(),
+        building_bounds: std::collections::HashMap::new(),
+        plant_registry: std::collections::HashMap::new(),
         background_texture: None,
         loading_texture: None,
         weather: WeatherSystem::new(),
+        map: MapView::new(),
+        grass_interaction: GrassInteraction::default(),
+        #[cfg(feature = "audio")]
+        ambience: ambience::Ambience::new(),
+        screenshot_requested: false,
+        harvest_requested: false,
+        wireframe_toggle_requested: false,
+        show_chunk_bounds: false,
+        frozen_frustum: None,
+        toast: None,
     }));
 
     // ... (Channel setup) ...
-    // Response Data: (Terrain, Grass, Trees, Detritus, Rocks, Coord X, Coord Z)
-    type ChunkData = (
-        Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>, // Terrain
-        Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>, // Grass
-        Vec<Mat4>, // Trees (Instanced)
-        Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>, Vec<u32>, // Detritus
-        Vec<(String, Mat4)>, // Rocks (Named Instances)
-        Vec<(String, Mat4)>, // Buildings (Named Instances)
-        i32, i32 // Offsets (World Space)
-    );
-    
-    // Channel for requesting chunks
-    let (request_tx, request_rx): (Sender<ChunkRequest>, Receiver<ChunkRequest>) = channel();
-    // Channel for receiving generated chunks
-    let (chunk_tx, chunk_rx): (Sender<ChunkData>, Receiver<ChunkData>) = channel();
+    let chunk_settings = ChunkSettings {
+        lod_rings: shared_state.lock().unwrap().settings.terrain_lod_rings,
+        ..Default::default()
+    };
+    // Queue of chunk requests (nearest / in view first)
+    let request_queue = ChunkQueue::default();
+    // Generation workers (one per core unless set in settings.json)
+    let workers = chunk_gen::worker_count(shared_state.lock().unwrap().settings.generation_threads);
+    // Channel for receiving generated chunks (bounded: workers wait for the upload loop)
+    let (chunk_tx, chunk_rx): (SyncSender<ChunkData>, Receiver<ChunkData>) = sync_channel(workers * chunk_gen::RESULTS_PER_WORKER);
     
     let chunk_rx = Arc::new(Mutex::new(chunk_rx));
 
-    // Spawn Persistent Generation Thread
-    thread::spawn(move || {
-        println!("[GEN] Generation thread started.");
-        while let Ok(req) = request_rx.recv() {
-            let chunk_world_size = 256.0;
-            let chunk_resolution = 64;
-            let scale = 4.0;
-            let (offset_x, offset_z) = req.coord.world_offset(chunk_world_size);
-            let offset_x = offset_x as i32;
-            let offset_z = offset_z as i32;
-
-            // Generate terrain
-            let (terrain_pos, terrain_col, terrain_nrm, terrain_idx) =
-                generate_terrain_chunk(req.seed, chunk_resolution, offset_x, offset_z, scale);
-
-            // Generate grass
-            let (grass_pos, grass_col, grass_idx) = generate_vegetation_for_chunk(
-                req.seed,
-                chunk_world_size,
-                offset_x as f32,
-                offset_z as f32,
-            );
-
-            // Generate trees
-            let tree_instances = generate_trees_for_chunk(
-                req.seed,
-                chunk_world_size,
-                offset_x as f32,
-                offset_z as f32,
-            );
+    chunk_gen::spawn_workers(workers, chunk_settings, request_queue.clone(), chunk_tx);
 
-            // Generate detritus
-            let (det_pos, det_nrm, det_uv, det_idx) = generate_detritus_for_chunk(
-                req.seed,
-                chunk_world_size,
-                offset_x as f32,
-                offset_z as f32,
-            );
-
-            // Generate rocks
-            let rock_instances = generate_rocks_for_chunk(
-                req.seed,
-                chunk_world_size,
-                offset_x as f32,
-                offset_z as f32,
-            );
+    // Terrain Data (Protected by Mutex to allow regeneration)
+    let _terrain_data = Arc::new(Mutex::new(None::<(Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>)>));
 
-            // Generate buildings
-            let building_instances = generate_buildings_for_chunk(
-                req.seed,
-                chunk_world_size,
-                offset_x as f32,
-                offset_z as f32,
-            );
+    // --- Resize Callback ---
+    let resize_state = Arc::clone(&shared_state);
+    app.set_resize_callback(move |width, height| {
+        let mut state = resize_state.lock().unwrap();
+        state.camera.set_aspect(width as f32 / height as f32);
+    });
 
-            // Send result
-            if chunk_tx.send((
-                terrain_pos, terrain_col, terrain_nrm, terrain_idx,
-                grass_pos, grass_col, grass_idx,
-                tree_instances,
-                det_pos, det_nrm, det_uv, det_idx,
-                rock_instances,
-                building_instances,
-                offset_x, offset_z
-            )).is_err() {
-                println!("[GEN] Receiver dropped, stopping thread.");
-                break;
-            }
+    // --- Focus Callback ---
+    let focus_state = Arc::clone(&shared_state);
+    app.set_focus_callback(move |focused| {
+        // Key releases made in another window never arrive; don't keep walking after alt-tab
+        if !focused {
+            focus_state.lock().unwrap().keys.clear();
         }
     });
 
-    // Terrain Data (Protected by Mutex to allow regeneration)
-    let _terrain_data = Arc::new(Mutex::new(None::<(Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>)>));
-    
-    // Time tracking
-    let start_time = Instant::now();
-
     // --- Input Callback ---
     let input_state = Arc::clone(&shared_state);
     app.set_input_callback(move |event, window| {
@@ -305,11 +399,9 @@ fn main() {
         // Handle Game Input (only if Playing, not during Loading)
         if state.game_state == GameState::Playing {
             match event {
-                Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } => {
+                Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } if !state.map.open => {
                     // Mouse Look
-                    state.player.yaw += delta.0 as f32 * 0.002;
-                    state.player.pitch -= delta.1 as f32 * 0.002;
-                    state.player.pitch = state.player.pitch.clamp(-1.5, 1.5);
+                    state.player.add_look(delta.0 as f32 * 0.002, -delta.1 as f32 * 0.002);
                 }
                 Event::WindowEvent { event: WindowEvent::KeyboardInput { event: key_event, .. }, .. } => {
                     if let PhysicalKey::Code(keycode) = key_event.physical_key {
@@ -318,6 +410,18 @@ fn main() {
                         if key_event.state == ElementState::Pressed && state.game_state == GameState::Playing {
                             match keycode {
                                 KeyCode::Space => state.player.jump(),
+                                KeyCode::KeyM => state.map.toggle(),
+                                KeyCode::F12 => state.screenshot_requested = true,
+                                KeyCode::F3 => state.wireframe_toggle_requested = true,
+                                KeyCode::F4 => state.show_chunk_bounds = !state.show_chunk_bounds,
+                                KeyCode::F5 => {
+                                    state.frozen_frustum = match state.frozen_frustum {
+                                        Some(_) => None,
+                                        None => Some(state.camera.view_projection_matrix()),
+                                    };
+                                }
+                                KeyCode::KeyE => state.harvest_requested = true,
+                                KeyCode::KeyV => state.camera_mode = state.camera_mode.toggle(),
                                 // Time controls: T = advance time, Y = reverse time
                                 KeyCode::KeyT => {
                                     state.time_of_day = (state.time_of_day + 1.0) % 24.0;
@@ -349,143 +453,126 @@ fn main() {
         }
     });
 
+    // --- Update Callback (fixed 60 Hz: player physics) ---
+    let update_state = Arc::clone(&shared_state);
+    let update_gamepad = app.gamepad_state();
+    let mut gamepad_jump_held = false; // Jump on press, not while held
+    app.set_update_callback(move |dt| {
+        let mut state = update_state.lock().unwrap();
+        if state.game_state != GameState::Playing {
+            return;
+        }
+
+        let mut input_dir = Vec3::ZERO;
+        if state.keys.get(&KeyCode::KeyW) == Some(&ElementState::Pressed) { input_dir.z += 1.0; }
+        if state.keys.get(&KeyCode::KeyS) == Some(&ElementState::Pressed) { input_dir.z -= 1.0; }
+        if state.keys.get(&KeyCode::KeyA) == Some(&ElementState::Pressed) { input_dir.x -= 1.0; }
+        if state.keys.get(&KeyCode::KeyD) == Some(&ElementState::Pressed) { input_dir.x += 1.0; }
+        // Jump is handled in input callback to avoid continuous jumping if holding space (optional, but better)
+
+        // Controller: left stick walks (analog, on top of WASD)
+        let pad = update_gamepad.get();
+        if pad.connected {
+            input_dir.x += pad.left_stick[0];
+            input_dir.z += pad.left_stick[1];
+
+            let jump_pressed = pad.is_pressed(GamepadButton::South);
+            if jump_pressed && !gamepad_jump_held {
+                state.player.jump();
+            }
+            gamepad_jump_held = jump_pressed;
+        }
+
+        let (seed, trails) = (state.seed, Arc::clone(&state.trails)); // Copied to avoid borrow error
+        state.player.look_smoothing = state.settings.look_smoothing;
+        state.player.update(dt, input_dir, seed, Some(&trails));
+
+        // Buildings and trunks in the surrounding chunks
+        if let Some(manager) = CHUNK_MANAGER.get() {
+            let manager = manager.lock().unwrap();
+            let position = state.player.position;
+            state.player.resolve_collisions(manager.colliders_near(position));
+        }
+
+        // Taken every step so they don't pile up in builds without sound
+        let footsteps = state.player.take_footsteps();
+        #[cfg(feature = "audio")]
+        for step in footsteps {
+            state.ambience.footstep(step);
+        }
+        #[cfg(not(feature = "audio"))]
+        drop(footsteps);
+    });
+
     // --- Render Callback ---
     let render_state = Arc::clone(&shared_state);
     let render_rx = Arc::clone(&chunk_rx);
+    let gamepad = app.gamepad_state();
+    let cursor = app.cursor();
     
-    app.set_render_callback(move |ctx| {
+    app.set_render_callback(move |frame| {
+        let FrameContext { ctx, dt: delta, elapsed, alpha } = frame;
+        let window = Arc::clone(ctx.window().expect("the game renders to a window"));
+
         // Initialize Asset Registry if empty
         {
             let mut state = render_state.lock().unwrap();
-            if state.mesh_registry.is_empty() {
+            if state.mesh_registry.is_empty() && state.rock_registry.is_empty() {
                 println!("[GPU] Initializing Mesh Registry...");
 
-                // 1. Oak Tree (Loaded from OBJ)
+                // 1. Oak Tree (glTF with its embedded bark texture, else OBJ)
                 {
                     println!("[ASSET] Loading tree model...");
                     // Try multiple paths for robustness
+                    let gltf_paths = ["assets/trees/oak.glb", "trees/oak.glb"];
                     let obj_paths = ["assets/trees/trees9.obj", "trees/trees9.obj"];
                     let mut template = None;
-                    for path in obj_paths {
-                        if let Some(t) = asset_loader::load_obj(path) {
+                    let mut embedded_texture = None;
+                    let mut model_path = None; // glTF: names its embedded texture in the cache
+                    let mut material = None; // OBJ: texture named by the .mtl
+                    for path in gltf_paths {
+                        if let Some(meshes) = asset_loader::load_gltf(path) {
+                            let (t, texture) = asset_loader::merge_tree_meshes(meshes);
                             template = Some(t);
+                            embedded_texture = texture;
+                            model_path = Some(path);
                             break;
                         }
                     }
-
-                    if let Some(template) = template {
-                        // Load Texture
-                        let texture_paths = ["assets/trees/Texture/Bark___0.jpg", "trees/Texture/Bark___0.jpg"];
-                        let mut texture_bytes = Vec::new();
-                        let mut loaded = false;
-                        
-                        for path in texture_paths {
-                            if let Ok(bytes) = std::fs::read(path) {
-                                texture_bytes = bytes;
-                                loaded = true;
-                                println!("[ASSET] Loaded tree texture from {}", path);
+                    if template.is_none() {
+                        for path in obj_paths {
+                            if let Some((t, groups)) = asset_loader::load_obj(path) {
+                                template = Some(t);
+                                material = asset_loader::dominant_material(&groups).cloned();
                                 break;
                             }
                         }
-                        
-                        if !loaded {
-                            println!("[WARN] Failed to load tree texture from any path, using fallback pink");
-                            texture_bytes = vec![255, 0, 255, 255];
-                        }
-
-                        let texture_image = image::load_from_memory(&texture_bytes).unwrap_or_else(|_| {
-                             image::DynamicImage::new_rgba8(1, 1)
-                        });
-                        let rgba = texture_image.to_rgba8();
-                        let dimensions = rgba.dimensions();
-
-                        let texture_size = wgpu::Extent3d {
-                            width: dimensions.0,
-                            height: dimensions.1,
-                            depth_or_array_layers: 1,
-                        };
-
-                        let texture = ctx.device().create_texture(&wgpu::TextureDescriptor {
-                            size: texture_size,
-                            mip_level_count: 1,
-                            sample_count: 1,
-                            dimension: wgpu::TextureDimension::D2,
-                            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                            label: Some("Tree Diffuse Texture"),
-                            view_formats: &[],
-                        });
-
-                        ctx.queue().write_texture(
-                            wgpu::ImageCopyTexture {
-                                texture: &texture,
-                                mip_level: 0,
-                                origin: wgpu::Origin3d::ZERO,
-                                aspect: wgpu::TextureAspect::All,
-                            },
-                            &rgba,
-                            wgpu::ImageDataLayout {
-                                offset: 0,
-                                bytes_per_row: Some(4 * dimensions.0),
-                                rows_per_image: Some(dimensions.1),
-                            },
-                            texture_size,
-                        );
-
-                        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-                        let sampler = ctx.device().create_sampler(&wgpu::SamplerDescriptor {
-                            address_mode_u: wgpu::AddressMode::Repeat,
-                            address_mode_v: wgpu::AddressMode::Repeat,
-                            mag_filter: wgpu::FilterMode::Linear,
-                            min_filter: wgpu::FilterMode::Linear,
-                            mipmap_filter: wgpu::FilterMode::Nearest,
-                            ..Default::default()
-                        });
-
-                        // We need to create a dummy pipeline to get the layout... 
-                        // Or better, expose a static function or create the layout here.
-                        // TreePipeline::new creates the layout internally.
-                        // We can just create a temporary pipeline to grab the layout or duplicate the layout creation.
-                        // Since we need the bind group to CREATE the mesh, we have a chicken-and-egg if the layout is inside pipeline.
-                        // Solution: Instantiate a dummy pipeline first to get the layout? No, expensive.
-                        // Better: Create the BindGroup here using a locally created layout that MATCHES the pipeline's layout.
-                        
-                        let texture_bind_group_layout = ctx.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                            label: Some("Tree Texture Bind Group Layout"),
-                            entries: &[
-                                wgpu::BindGroupLayoutEntry {
-                                    binding: 0,
-                                    visibility: wgpu::ShaderStages::FRAGMENT,
-                                    ty: wgpu::BindingType::Texture {
-                                        multisampled: false,
-                                        view_dimension: wgpu::TextureViewDimension::D2,
-                                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                    },
-                                    count: None,
-                                },
-                                wgpu::BindGroupLayoutEntry {
-                                    binding: 1,
-                                    visibility: wgpu::ShaderStages::FRAGMENT,
-                                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                                    count: None,
-                                },
-                            ],
-                        });
+                    }
 
-                        let bind_group = ctx.device().create_bind_group(&wgpu::BindGroupDescriptor {
-                            layout: &texture_bind_group_layout,
-                            entries: &[
-                                wgpu::BindGroupEntry {
-                                    binding: 0,
-                                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                                },
-                                wgpu::BindGroupEntry {
-                                    binding: 1,
-                                    resource: wgpu::BindingResource::Sampler(&sampler),
-                                },
-                            ],
-                            label: Some("Tree Texture Bind Group"),
-                        });
+                    if let Some(template) = template {
+                        // Texture: the model's own, else the one its material names, else a flat color
+                        let cache = &mut state.asset_cache;
+                        let texture = embedded_texture
+                            .map(|texture| {
+                                let key = format!("{}#base_color", model_path.unwrap_or_default());
+                                cache.texture_rgba(ctx.device(), ctx.queue(), &key, texture.width, texture.height, &texture.rgba)
+                            })
+                            .or_else(|| {
+                                let path = material.as_ref()?.diffuse_map.as_ref()?;
+                                let texture = cache.texture(ctx.device(), ctx.queue(), path);
+                                match &texture {
+                                    Some(_) => println!("[ASSET] Loaded tree texture from {}", path.display()),
+                                    None => println!("[WARN] Failed to load tree texture {}", path.display()),
+                                }
+                                texture
+                            })
+                            .unwrap_or_else(|| {
+                                // The material's flat color, or pink if there's no material either
+                                let color = material.as_ref().map_or([1.0, 0.0, 1.0], |material| material.diffuse);
+                                println!("[WARN] No tree texture, using flat color {:?}", color);
+                                let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8);
+                                cache.texture_rgba(ctx.device(), ctx.queue(), &format!("color#{:02x}{:02x}{:02x}", r, g, b), 1, 1, &[r, g, b, 255])
+                            });
 
                         let gpu_mesh = TreePipeline::create_mesh(
                             ctx.device(),
@@ -493,7 +580,7 @@ fn main() {
                             &template.normals,
                             &template.uvs,
                             &template.indices,
-                            Some(Arc::new(bind_group)),
+                            Some(texture.bind_group.clone()),
                         );
                         state.mesh_registry.insert("tree_oak".to_string(), gpu_mesh);
                     } else {
@@ -501,47 +588,60 @@ fn main() {
                         let recipe = TreeRecipe::oak();
                         let tree = generate_tree(&recipe, 12345);
                         let mesh = generate_tree_mesh(&tree);
-                        // ... fallback code ...
+                        let positions: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.position).collect();
+                        let normals: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.normal).collect();
+                        let uvs: Vec<[f32; 2]> = mesh.vertices.iter().map(|v| v.uv).collect();
+                        let leaves: Vec<LeafInstance> = generate_leaf_instances(&tree)
+                            .iter()
+                            .map(|leaf| LeafInstance { position: leaf.position.to_array(), normal: leaf.normal.to_array(), size: leaf.size })
+                            .collect();
+
+                        let gpu_mesh = TreePipeline::create_mesh(ctx.device(), &positions, &normals, &uvs, &mesh.indices, None)
+                            .with_leaves(leaves, None);
+                        state.mesh_registry.insert("tree_oak".to_string(), gpu_mesh);
+                    }
+
+                    // Bake the distant-tree impostor once; every chunk's pipeline shares it
+                    if let Some(mesh) = state.mesh_registry.remove("tree_oak") {
+                        let baker = TreePipeline::new(ctx.device(), ctx.queue(), ctx.scene_format(), ctx.sample_count());
+                        let impostor = baker.bake_impostor(ctx.device(), ctx.queue(), &mesh);
+                        state.mesh_registry.insert("tree_oak".to_string(), mesh.with_impostor(Arc::new(impostor)));
+                        println!("[ASSET] Baked tree impostor");
                     }
                 }
 
-                // 2. Rock (Boulder)
-                {
-                    let recipe = RockRecipe::boulder();
+                // 2. Rocks (one mesh per type, keyed like the generator's names)
+                for (name, recipe, color) in [
+                    (ROCK_BOULDER, RockRecipe::boulder(), [0.50, 0.48, 0.45]),
+                    (ROCK_RIVER_STONE, RockRecipe::river_stone(), [0.56, 0.54, 0.50]),
+                    (ROCK_SHARP, RockRecipe::sharp_rock(), [0.42, 0.42, 0.44]),
+                    (ROCK_CLIFF, RockRecipe::cliff_face(8.0, 6.0), [0.40, 0.39, 0.38]),
+                ] {
                     let mesh = generate_rock(&recipe);
-                    
-                    let positions: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.position).collect();
-                    let normals: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.normal).collect();
-                    let uvs: Vec<[f32; 2]> = mesh.vertices.iter().map(|v| v.uv).collect();
-
-                    let gpu_mesh = TreePipeline::create_mesh(
-                        ctx.device(),
-                        &positions,
-                        &normals,
-                        &uvs,
-                        &mesh.indices,
-                        None,
-                    );
-                    state.mesh_registry.insert("rock_boulder".to_string(), gpu_mesh);
+                    let vertices: Vec<RockVertex> = mesh.vertices.iter()
+                        .map(|v| RockVertex { position: v.position, normal: v.normal, uv: v.uv })
+                        .collect();
+                    let gpu_mesh = RockPipeline::create_mesh(ctx.device(), &vertices, &mesh.indices, color);
+                    state.rock_registry.insert(name.to_string(), gpu_mesh);
                 }
 
-                println!("[GPU] Assets registered: {:?}", state.mesh_registry.keys());
+                println!("[GPU] Assets registered: {:?} + rocks {:?}", state.mesh_registry.keys(), state.rock_registry.keys());
             }
 
-            if state.building_registry.is_empty() {
+            if state.building_batches.is_empty() {
                 println!("[GPU] Initializing Building Registry...");
                 
-                // 1. Colonial House
-                {
-                    let recipe = BuildingRecipe::colonial_house();
+                for name in BUILDING_KINDS {
+                    let Some(recipe) = building_recipe(name) else { continue };
                     let mesh = generate_building(&recipe);
-                    
+
                     // Convert to BuildingVertex
                     let vertices: Vec<BuildingVertex> = mesh.vertices.iter().map(|v| BuildingVertex {
                         position: v.position,
                         normal: v.normal,
                         uv: v.uv,
                         color: v.color,
+                        emissive: v.emissive,
                     }).collect();
 
                     let gpu_mesh = BuildingPipeline::create_mesh(
@@ -549,30 +649,37 @@ fn main() {
                         &vertices,
                         &mesh.indices,
                     );
-                    state.building_registry.insert("building_colonial".to_string(), gpu_mesh);
+                    let mut batch = BuildingPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count());
+                    batch.set_mesh(gpu_mesh);
+                    batch.set_specular(building_specular(name));
+                    state.building_batches.insert(name.to_string(), batch);
+                    state.building_bounds.insert(name.to_string(), mesh.bounds());
                 }
 
-                // 2. Small Shack
-                {
-                    let recipe = BuildingRecipe::small_shack();
-                    let mesh = generate_building(&recipe);
-                    
-                    let vertices: Vec<BuildingVertex> = mesh.vertices.iter().map(|v| BuildingVertex {
-                        position: v.position,
-                        normal: v.normal,
-                        uv: v.uv,
-                        color: v.color,
+                println!("[GPU] Buildings registered: {:?}", state.building_batches.keys());
+
+                // Player model (third person) shares the building pipeline too
+                let (vertices, indices) = player_capsule(state.player.height);
+                let mut model = BuildingPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count());
+                model.set_mesh(BuildingPipeline::create_mesh(ctx.device(), &vertices, &indices));
+                state.player_model = Some(model);
+
+                // Plants share the building pipeline: vertex-coloured, untextured, no glow
+                for name in PLANT_KINDS {
+                    let Some(recipe) = plant_recipe(name) else { continue };
+                    let mesh = generate_plant(&recipe, 7);
+                    let vertices: Vec<BuildingVertex> = (0..mesh.positions.len()).map(|i| BuildingVertex {
+                        position: mesh.positions[i],
+                        normal: mesh.normals[i],
+                        uv: [0.0, 0.0],
+                        color: mesh.colors[i],
+                        emissive: [0.0; 3],
                     }).collect();
-
-                    let gpu_mesh = BuildingPipeline::create_mesh(
-                        ctx.device(),
-                        &vertices,
-                        &mesh.indices,
-                    );
-                    state.building_registry.insert("building_cabin".to_string(), gpu_mesh); // Matches "building_cabin" from buildings.rs
+                    let gpu_mesh = BuildingPipeline::create_mesh(ctx.device(), &vertices, &mesh.indices);
+                    state.plant_registry.insert(name.to_string(), gpu_mesh);
                 }
-                
-                println!("[GPU] Buildings registered: {:?}", state.building_registry.keys());
+                println!("[GPU] Plants registered: {:?}", state.plant_registry.keys());
+                println!("[GPU] Textures uploaded: {}", state.asset_cache.texture_count());
             }
         }
 
@@ -587,27 +694,92 @@ fn main() {
             ))
         });
 
+        // Ambient Occlusion (darkens the opaque scene from its depth)
+        static SSAO_PIPELINE: OnceLock<Mutex<SsaoPipeline>> = OnceLock::new();
+        let ssao_pipeline_mutex = SSAO_PIPELINE.get_or_init(|| {
+            Mutex::new(SsaoPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count()))
+        });
+
+        // Occlusion culling (chunks hidden behind hills skip the main pass)
+        static OCCLUSION_CULLER: OnceLock<Mutex<OcclusionCuller<ChunkCoord>>> = OnceLock::new();
+        let occlusion_culler_mutex = OCCLUSION_CULLER.get_or_init(|| {
+            Mutex::new(OcclusionCuller::new(ctx.device(), ctx.scene_format(), ctx.sample_count()))
+        });
+
+        // Debug overlay lines (chunk bounds, frozen frustum)
+        static DEBUG_LINES: OnceLock<Mutex<DebugLines>> = OnceLock::new();
+        let debug_lines_mutex = DEBUG_LINES.get_or_init(|| {
+            Mutex::new(DebugLines::new(ctx.device(), ctx.scene_format(), ctx.sample_count()))
+        });
+
+        // Post Process (HDR scene -> bloom + tonemapped swapchain)
+        static POST_PIPELINE: OnceLock<Mutex<PostProcessPipeline>> = OnceLock::new();
+        let post_pipeline_mutex = POST_PIPELINE.get_or_init(|| {
+            Mutex::new(PostProcessPipeline::new(ctx.device(), ctx.surface_format()))
+        });
+
+        // Map View (offscreen top-down render shown through egui)
+        static MAP_TARGET: OnceLock<(RenderTarget, egui::TextureId)> = OnceLock::new();
+        let (map_target, map_texture_id) = MAP_TARGET.get_or_init(|| {
+            let target = ctx.create_render_target(MAP_TEXTURE_SIZE, MAP_TEXTURE_SIZE);
+            let texture_id = egui_renderer_mutex.lock().unwrap().register_native_texture(
+                ctx.device(),
+                &target.view,
+                wgpu::FilterMode::Linear,
+            );
+            (target, texture_id)
+        });
+
         // Chunk Manager (Stores all loaded chunks and manages streaming)
-        static CHUNK_MANAGER: OnceLock<Mutex<ChunkManager>> = OnceLock::new();
         let chunk_manager = CHUNK_MANAGER.get_or_init(|| {
-            // Load radius 2 = 5x5 grid (visible ~500 units), Unload radius 4 = buffer zone
-            // Reduced from 4 (9x9) for performance
-            Mutex::new(ChunkManager::new(256.0, 2, 4))
+            // Render distance 2 = 5x5 grid (visible ~500 units), unloaded past 4
+            let settings = render_state.lock().unwrap().settings.clone();
+            let mut manager = ChunkManager::new(chunk_settings, settings.render_distance);
+            let budget_mb = settings.chunk_memory_budget_mb;
+            manager.set_memory_budget((budget_mb > 0).then(|| budget_mb as u64 * 1024 * 1024));
+            Mutex::new(manager)
         });
 
         // Shadow System
         static SHADOW_SYSTEM: OnceLock<(Mutex<ShadowMap>, Mutex<ShadowPipeline>)> = OnceLock::new();
         let (shadow_map_mutex, shadow_pipeline_mutex) = SHADOW_SYSTEM.get_or_init(|| {
-            let shadow_map = ShadowMap::new(ctx.device(), 2048);
+            let shadow_map = ShadowMap::with_filter(ctx.device(), 2048, FilterQuality::Pcf3x3);
             let shadow_pipeline = ShadowPipeline::new(ctx.device());
             (Mutex::new(shadow_map), Mutex::new(shadow_pipeline))
         });
 
+        // Terrain detail normals: an authored map if there is one, else the built-in soil bumps
+        static TERRAIN_DETAIL_NORMAL: OnceLock<Arc<wgpu::BindGroup>> = OnceLock::new();
+        let terrain_detail_normal = TERRAIN_DETAIL_NORMAL.get_or_init(|| {
+            let cache = &mut render_state.lock().unwrap().asset_cache;
+            let path = std::path::Path::new("assets/textures/terrain_detail_normal.png");
+            let texture = path.exists().then(|| cache.linear_texture(ctx.device(), ctx.queue(), path)).flatten().unwrap_or_else(|| {
+                let size = terrain_textures::TERRAIN_TEXTURE_SIZE;
+                cache.linear_texture_rgba(ctx.device(), ctx.queue(), "terrain#detail_normal", size, size, &terrain_textures::detail_normal_pixels())
+            });
+            texture.bind_group.clone()
+        });
+
+        // Terrain splat textures: authored sand/grass/rock/snow textures where present, else built-in ones
+        static TERRAIN_SPLAT: OnceLock<Arc<wgpu::BindGroup>> = OnceLock::new();
+        let terrain_splat = TERRAIN_SPLAT.get_or_init(|| {
+            let cache = &mut render_state.lock().unwrap().asset_cache;
+            let layers = [("sand", TerrainMaterial::Sand), ("grass", TerrainMaterial::Grass), ("rock", TerrainMaterial::Rock), ("snow", TerrainMaterial::Snow)];
+            let [sand, grass, rock, snow] = layers.map(|(name, material)| {
+                let path = std::path::PathBuf::from(format!("assets/textures/terrain_{}.png", name));
+                path.exists().then(|| cache.texture(ctx.device(), ctx.queue(), &path)).flatten().unwrap_or_else(|| {
+                    let size = terrain_textures::TERRAIN_TEXTURE_SIZE;
+                    cache.texture_rgba(ctx.device(), ctx.queue(), &format!("terrain#{}", name), size, size, &terrain_textures::material_pixels(material))
+                })
+            });
+            Arc::new(croatoan_render::terrain_pipeline::create_splat_bind_group(ctx.device(), [&sand.view, &grass.view, &rock.view, &snow.view]))
+        });
+
         // Grass System (requires shadow map)
         static GRASS_PIPELINE: OnceLock<Mutex<GrassPipeline>> = OnceLock::new();
         let _grass_pipeline_mutex = GRASS_PIPELINE.get_or_init(|| {
             let shadow_map = shadow_map_mutex.lock().unwrap();
-            let grass_pipeline = GrassPipeline::new(ctx.device(), ctx.surface_format(), &shadow_map);
+            let grass_pipeline = GrassPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count(), &shadow_map);
             drop(shadow_map);  // Release lock
             Mutex::new(grass_pipeline)
         });
@@ -615,34 +787,46 @@ fn main() {
         // Tree System
         static TREE_PIPELINE: OnceLock<Mutex<TreePipeline>> = OnceLock::new();
         let _tree_pipeline_mutex = TREE_PIPELINE.get_or_init(|| {
-            let tree_pipeline = TreePipeline::new(ctx.device(), ctx.queue(), ctx.surface_format());
+            let tree_pipeline = TreePipeline::new(ctx.device(), ctx.queue(), ctx.scene_format(), ctx.sample_count());
             Mutex::new(tree_pipeline)
         });
 
         // Sun Billboard
         static SUN_PIPELINE: OnceLock<Mutex<SunPipeline>> = OnceLock::new();
         let sun_pipeline_mutex = SUN_PIPELINE.get_or_init(|| {
-            Mutex::new(SunPipeline::new(ctx.device(), ctx.surface_format()))
+            Mutex::new(SunPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count()))
+        });
+
+        // Rain
+        static RAIN_PIPELINE: OnceLock<Mutex<RainPipeline>> = OnceLock::new();
+        let rain_pipeline_mutex = RAIN_PIPELINE.get_or_init(|| {
+            Mutex::new(RainPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count()))
         });
 
         // Sky Pipeline
         static SKY_PIPELINE: OnceLock<Mutex<SkyPipeline>> = OnceLock::new();
         let sky_pipeline_mutex = SKY_PIPELINE.get_or_init(|| {
-            Mutex::new(SkyPipeline::new(ctx.device(), ctx.surface_format()))
+            Mutex::new(SkyPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count()))
         });
 
+        // Depth Prepass (opaque occluders, ahead of the main pass)
+        static DEPTH_PREPASS: OnceLock<DepthPrepass> = OnceLock::new();
+        let depth_prepass = DEPTH_PREPASS.get_or_init(|| DepthPrepass::new(ctx.device(), ctx.sample_count()));
+
         // Water System
         static WATER_SYSTEM: OnceLock<Mutex<WaterSystem>> = OnceLock::new();
-        // let water_system_mutex = WATER_SYSTEM.get_or_init(|| {
-        //     Mutex::new(WaterSystem::new(ctx.device(), ctx.surface_format()))
-        // });
+        let water_system_mutex = WATER_SYSTEM.get_or_init(|| {
+            Mutex::new(WaterSystem::new(ctx.device(), ctx.queue(), ctx.scene_format(), ctx.sample_count(), ctx.depth_view()))
+        });
 
         let mut state = render_state.lock().unwrap();
 
+        if std::mem::take(&mut state.wireframe_toggle_requested) {
+            ctx.set_wireframe(!ctx.wireframe());
+        }
+        ctx.set_depth_prepass(state.settings.depth_prepass);
+
         // Calculate FPS
-        let now = Instant::now();
-        let delta = now.duration_since(state.last_frame_time).as_secs_f32();
-        state.last_frame_time = now;
         if delta > 0.0 {
             // Simple smoothing
             state.fps = state.fps * 0.9 + (1.0 / delta) * 0.1;
@@ -654,6 +838,7 @@ fn main() {
             state.time_of_day += delta * (1.0 / 120.0);
             if state.time_of_day >= 24.0 {
                 state.time_of_day -= 24.0;
+                state.day_count += 1;
             }
             if state.time_of_day >= 24.0 {
                 state.time_of_day -= 24.0;
@@ -664,41 +849,65 @@ fn main() {
             state.weather.update(delta);
         }
 
-        // Handle Input (Player Controller)
+        // Player view (movement runs in the update callback)
         if state.game_state == GameState::Playing {
-            let mut input_dir = Vec3::ZERO;
-            if state.keys.get(&KeyCode::KeyW) == Some(&ElementState::Pressed) { input_dir.z += 1.0; }
-            if state.keys.get(&KeyCode::KeyS) == Some(&ElementState::Pressed) { input_dir.z -= 1.0; }
-            if state.keys.get(&KeyCode::KeyA) == Some(&ElementState::Pressed) { input_dir.x -= 1.0; }
-            if state.keys.get(&KeyCode::KeyD) == Some(&ElementState::Pressed) { input_dir.x += 1.0; }
-            // Jump is handled in input callback to avoid continuous jumping if holding space (optional, but better)
-
-            let seed = state.seed; // Copy seed to avoid borrow error
-            state.player.update(delta, input_dir, seed);
-
-            // Sync Camera to Player
-            state.camera.position = state.player.position;
-            state.camera.yaw = state.player.yaw;
-            state.camera.pitch = state.player.pitch;
-            state.camera.update_vectors();
+            // Controller right stick looks
+            let pad = gamepad.get();
+            if pad.connected {
+                let look_speed = 2.5 * delta; // rad/s at full tilt
+                state.player.add_look(pad.right_stick[0] * look_speed, pad.right_stick[1] * look_speed);
+            }
+
+            // Between physics steps: draw where the player is, not where they were last step
+            let eye = state.player.interpolated_position(alpha);
+
+            // Grass parts around the player's feet
+            let feet = eye - Vec3::Y * state.player.height;
+            state.grass_interaction.update(feet, delta);
+
+            // Sync Camera to Player (first person: no smoothing on top of the player's own)
+            let (yaw, pitch) = (state.player.yaw, state.player.pitch);
+            match state.camera_mode {
+                CameraMode::FirstPerson => {
+                    state.camera.set_offset(Vec3::ZERO);
+                    state.camera.follow(eye, yaw, pitch, f32::INFINITY, delta);
+                }
+                CameraMode::ThirdPerson => {
+                    state.camera.set_offset(THIRD_PERSON_OFFSET);
+                    state.camera.follow(eye, yaw, pitch, THIRD_PERSON_STIFFNESS, delta);
+                    // Never behind a wall or under a hill
+                    let manager = chunk_manager.lock().unwrap();
+                    state.camera.position = collide_camera(state.seed, eye, state.camera.position, manager.colliders_near(eye), Some(&state.trails));
+                    state.camera.update_vectors();
+                }
+            }
+
+            #[cfg(feature = "audio")]
+            {
+                let state = &mut *state;
+                state.ambience.update(delta, state.seed, eye, &state.weather);
+            }
         } else {
             // Menu Camera (Orbit)
             state.camera.yaw += 0.1 * delta;
             state.camera.update_vectors();
+
+            #[cfg(feature = "audio")]
+            state.ambience.silence();
         }
 
         // Sun Billboard
 
 
-        // Moon Billboard (Reusing SunPipeline)
-        static MOON_PIPELINE: OnceLock<Mutex<SunPipeline>> = OnceLock::new();
+        // Moon Billboard
+        static MOON_PIPELINE: OnceLock<Mutex<MoonPipeline>> = OnceLock::new();
         let moon_pipeline_mutex = MOON_PIPELINE.get_or_init(|| {
-            Mutex::new(SunPipeline::new(ctx.device(), ctx.surface_format()))
+            Mutex::new(MoonPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count()))
         });
 
         // Egui Input
         let raw_input = if let Some(egui_state) = &mut state.egui_state {
-            egui_state.take_egui_input(&ctx.window)
+            egui_state.take_egui_input(&window)
         } else {
             egui::RawInput::default()
         };
@@ -711,16 +920,11 @@ fn main() {
             style.visuals.panel_fill = egui::Color32::from_rgb(244, 228, 188);
             ui_ctx.set_style(style);
 
-            // Sync Cursor State with Game State
+            // Sync Cursor State with Game State: free in menus, kept in the window for mouse
+            // look while playing (still shown, the game menu stays clickable)
             match state.game_state {
-                GameState::Menu | GameState::Loading => {
-                    ctx.window.set_cursor_visible(true);
-                    let _ = ctx.window.set_cursor_grab(CursorGrabMode::None);
-                }
-                GameState::Playing => {
-                    ctx.window.set_cursor_visible(true);
-                    let _ = ctx.window.set_cursor_grab(CursorGrabMode::None);
-                }
+                GameState::Menu | GameState::Loading => cursor.set_grab_mode(CursorGrabMode::None),
+                GameState::Playing => cursor.set_grab_mode(CursorGrabMode::Confined),
             }
 
             match state.game_state {
@@ -844,19 +1048,24 @@ fn main() {
                             ui.text_edit_singleline(&mut state.seed_input);
                             
                             if ui.button(egui::RichText::new("New Game").size(20.0)).clicked() {
-                                // TODO: Play Menu Select Sound
-                                // audio.play("ui_select.wav");
-                                
-                                if let Ok(seed) = state.seed_input.parse::<u32>() {
+                                #[cfg(feature = "audio")]
+                                state.ambience.click();
+
+                                // Numbers are used as-is, any other text is hashed (WorldSeed::from_text)
+                                if !state.seed_input.trim().is_empty() {
+                                    let seed = WorldSeed::from_text(&state.seed_input).as_u32();
                                     state.seed = seed;
+                                    state.trails = chunk_gen::plan_trails(seed, chunk_settings);
+                                    state.weather.set_rng(Some(SeededRng::new(WorldSeed::new(seed).derive("weather") as u64)));
                                     state.game_state = GameState::Loading;
                                     state.save_name_input = format!("seed_{}", seed); // Default save name
                                     state.player = Player::new(Vec3::new(0.0, 50.0, 0.0)); // Reset player position
-                                    println!("[GAME] Starting new game with seed: {}", seed);
+                                    state.inventory.clear();
+                                    println!("[GAME] Starting new game with seed: {} ({})", seed, state.seed_input.trim());
 
                                     // Initialize loading progress
-                                    // Range 3 = 7x7 = 49 chunks
-                                    let range = 3;
+                                    // Render distance 2 = 5x5 = 25 chunks
+                                    let range = state.settings.render_distance;
                                     let total = ((range * 2 + 1) * (range * 2 + 1)) as usize;
                                     state.loading_progress = LoadingProgress {
                                         total_chunks: total,
@@ -870,6 +1079,9 @@ fn main() {
                                         let mut mgr = manager.lock().unwrap();
                                         mgr.loaded_chunks.clear();
                                         mgr.loading_chunks.clear();
+                                        mgr.set_world_edits(Vec::new());
+                                        mgr.open_store(&state.save_name_input, seed);
+                                        mgr.set_trails(Arc::clone(&state.trails));
                                     }
                                     
                                     // We don't spawn a thread here anymore. 
@@ -887,35 +1099,48 @@ fn main() {
                                 for save_name in saves {
                                     ui.horizontal(|ui| {
                                         if ui.button(format!("Load {}", save_name)).clicked() {
-                                            // TODO: Play Menu Select Sound
-                                            // audio.play("ui_select.wav");
-
-                                            if let Some(data) = load_game(&save_name) {
-                                                state.seed = data.seed;
-                                                state.inventory = data.inventory;
-                                                state.player.position = Vec3::from_array(data.player_pos);
-                                                state.player.yaw = data.player_rot[0];
-                                                state.player.pitch = data.player_rot[1];
-                                                state.game_state = GameState::Loading;
-                                                state.save_name_input = save_name.clone();
-
-                                                println!("[GAME] Loaded game: {}", save_name);
-
-                                                // Initialize loading progress
-                                                let range = 3;
-                                                let total = ((range * 2 + 1) * (range * 2 + 1)) as usize;
-                                                state.loading_progress = LoadingProgress {
-                                                    total_chunks: total,
-                                                    chunks_generated: 0,
-                                                    chunks_uploaded: 0,
-                                                    current_status: "Loading saved world...".to_string(),
-                                                };
-
-                                                // Force regeneration by clearing chunks
-                                                if let Some(manager) = CHUNK_MANAGER.get() {
-                                                    let mut mgr = manager.lock().unwrap();
-                                                    mgr.loaded_chunks.clear();
-                                                    mgr.loading_chunks.clear();
+                                            #[cfg(feature = "audio")]
+                                            state.ambience.click();
+
+                                            match load_game(&save_name) {
+                                                Err(e) => {
+                                                    println!("[LOAD] Failed to load '{}': {}", save_name, e);
+                                                    state.show_toast(format!("Couldn't load '{}': {}", save_name, e), true);
+                                                }
+                                                Ok(data) => {
+                                                    state.seed = data.seed;
+                                                    state.trails = chunk_gen::plan_trails(data.seed, chunk_settings);
+                                                    state.weather.set_rng(Some(SeededRng::new(WorldSeed::new(data.seed).derive("weather") as u64)));
+                                                    state.inventory = data.inventory;
+                                                    state.time_of_day = data.time_of_day.rem_euclid(24.0);
+                                                    state.day_count = data.day_count;
+                                                    state.camera_mode = data.camera_mode;
+                                                    state.player.set_position(Vec3::from_array(data.player_pos));
+                                                    state.player.set_look(data.player_rot[0], data.player_rot[1]);
+                                                    state.game_state = GameState::Loading;
+                                                    state.save_name_input = save_name.clone();
+
+                                                    println!("[GAME] Loaded game: {}", save_name);
+
+                                                    // Initialize loading progress
+                                                    let range = state.settings.render_distance;
+                                                    let total = ((range * 2 + 1) * (range * 2 + 1)) as usize;
+                                                    state.loading_progress = LoadingProgress {
+                                                        total_chunks: total,
+                                                        chunks_generated: 0,
+                                                        chunks_uploaded: 0,
+                                                        current_status: "Loading saved world...".to_string(),
+                                                    };
+
+                                                    // Force regeneration by clearing chunks
+                                                    if let Some(manager) = CHUNK_MANAGER.get() {
+                                                        let mut mgr = manager.lock().unwrap();
+                                                        mgr.loaded_chunks.clear();
+                                                        mgr.loading_chunks.clear();
+                                                        mgr.set_world_edits(data.world_edits);
+                                                        mgr.open_store(&save_name, data.seed);
+                                                        mgr.set_trails(Arc::clone(&state.trails));
+                                                    }
                                                 }
                                             }
                                         }
@@ -928,31 +1153,119 @@ fn main() {
                 GameState::Playing => {
                     egui::Window::new("Game Menu").show(ui_ctx, |ui| {
                         ui.label(format!("FPS: {:.1}", state.fps));
+                        if let Some(manager) = CHUNK_MANAGER.get() {
+                            let stats = manager.lock().unwrap().memory_stats();
+                            ui.label(format!("Chunks: {} (~{:.0} MB GPU)", stats.loaded_chunks, stats.approx_bytes as f64 / (1024.0 * 1024.0)));
+                        }
                         let hours = state.time_of_day as u32;
                         let minutes = ((state.time_of_day - hours as f32) * 60.0) as u32;
                         ui.label(format!("Time: {:02}:{:02}", hours, minutes));
                         ui.label("T/Y keys: Change time");
+                        let ambient_changed = ui.add(egui::Slider::new(&mut state.settings.min_ambient, 0.0..=2.0).text("Min Ambient")).changed();
+                        let exposure_changed = ui.add(egui::Slider::new(&mut state.settings.exposure, 0.25..=4.0).logarithmic(true).text("Exposure")).changed();
+                        let bloom_changed = ui.checkbox(&mut state.settings.bloom, "Bloom").changed();
+                        let prepass_changed = ui.checkbox(&mut state.settings.depth_prepass, "Depth Prepass").changed();
+                        let ssao_changed = ui.add(egui::Slider::new(&mut state.settings.ssao_radius, 0.25..=4.0).text("AO Radius")).changed()
+                            | ui.add(egui::Slider::new(&mut state.settings.ssao_strength, 0.0..=2.0).text("AO Strength")).changed();
+                        let foam_changed = ui.add(egui::Slider::new(&mut state.settings.water_foam_width, 0.0..=4.0).text("Shore Foam (m)")).changed();
+                        let smoothing_changed = ui.add(
+                            egui::Slider::new(&mut state.settings.look_smoothing, 0.0..=0.2).text("Look Smoothing (s)")
+                        ).changed();
+                        let distance_changed = ui.add(
+                            egui::Slider::new(&mut state.settings.render_distance, 1..=6).text("Render Distance (chunks)")
+                        ).changed();
+                        if distance_changed {
+                            if let Some(manager) = CHUNK_MANAGER.get() {
+                                manager.lock().unwrap().set_load_radius(state.settings.render_distance);
+                            }
+                        }
+                        if ambient_changed || exposure_changed || bloom_changed || prepass_changed || ssao_changed || foam_changed || smoothing_changed || distance_changed {
+                            state.settings.save();
+                        }
+                        let here = croatoan_wfc::sample_terrain(state.seed, state.player.position.x, state.player.position.z, Some(&state.trails));
+                        ui.label(format!("Biome: {} (height {:.1}, slope {:.2})", here.biome.name(), here.height, here.slope));
+                        if state.player.is_swimming() {
+                            ui.label("Swimming");
+                        }
+                        ui.separator();
+
+                        ui.label(egui::RichText::new("Inventory:").strong());
+                        for item in [InstanceKind::Tree.item(), InstanceKind::Rock.item()] {
+                            let count = state.item_count(item);
+                            if count > 0 {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{} x{}", item, count));
+                                    if ui.button("Drop").clicked() {
+                                        state.remove_item(item);
+                                    }
+                                });
+                            }
+                        }
+                        ui.label("E: Harvest");
                         ui.separator();
                         
                         ui.label("Save Name:");
                         ui.text_edit_singleline(&mut state.save_name_input);
 
                         if ui.button("Save Game").clicked() {
+                            let world_edits = CHUNK_MANAGER
+                                .get()
+                                .map(|manager| manager.lock().unwrap().world_edits())
+                                .unwrap_or_default();
                             let data = SaveData {
-        seed: state.seed,
-        player_pos: state.player.position.to_array(),
-        player_rot: [state.player.yaw, state.player.pitch],
-        inventory: state.inventory.clone(),
-    };
-                            save_game(&state.save_name_input, &data);
+                                version: SAVE_VERSION,
+                                seed: state.seed,
+                                player_pos: state.player.position.to_array(),
+                                player_rot: [state.player.yaw, state.player.pitch],
+                                inventory: state.inventory.clone(),
+                                world_edits,
+                                time_of_day: state.time_of_day,
+                                day_count: state.day_count,
+                                camera_mode: state.camera_mode,
+                            };
+                            match save_game(&state.save_name_input, &data) {
+                                Ok(()) => {
+                                    let message = format!("Saved '{}'", state.save_name_input);
+                                    state.show_toast(message, false);
+                                }
+                                Err(e) => {
+                                    println!("[SAVE] Failed to save '{}': {}", state.save_name_input, e);
+                                    state.show_toast(format!("Save failed: {}", e), true);
+                                }
+                            }
                         }
                         if ui.button("Back to Menu").clicked() {
                             state.game_state = GameState::Menu;
                         }
                         ui.label(format!("Camera: {:.1?}", state.camera.position));
+                        let (ray_origin, ray_dir) = state.camera.screen_ray(0.0, 0.0);
+                        match raycast_terrain(state.seed, ray_origin, ray_dir, 200.0, Some(&state.trails)) {
+                            Some(hit) => ui.label(format!("Looking at: {:.1?}", hit)),
+                            None => ui.label("Looking at: -"),
+                        };
+                        ui.label("M: Map");
                     });
+
+                    if state.map.open {
+                        let (player_pos, player_yaw) = (state.player.position, state.player.yaw);
+                        state.map.show(ui_ctx, *map_texture_id, player_pos, player_yaw);
+                    }
                 }
             }
+
+            // Save/load results
+            if let Some(toast) = state.toast.as_mut() {
+                toast.remaining -= delta;
+                let color = if toast.is_error { egui::Color32::from_rgb(255, 110, 100) } else { egui::Color32::WHITE };
+                egui::Area::new(egui::Id::new("toast"))
+                    .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -40.0])
+                    .show(ui_ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(egui::RichText::new(&toast.text).color(color)));
+                    });
+            }
+            if state.toast.as_ref().is_some_and(|toast| toast.remaining <= 0.0) {
+                state.toast = None;
+            }
         });
 
         // Handle Pipeline Updates (scoped to release locks early)
@@ -961,9 +1274,14 @@ fn main() {
 
             // Update Chunk Streaming (Request new chunks / Unload old ones)
             if state.game_state == GameState::Loading || state.game_state == GameState::Playing {
-                let requests = manager.update(state.player.position, state.seed);
-                for req in requests {
-                    let _ = request_tx.send(req);
+                let player_pos = state.player.position;
+                let frustum = Frustum::from_view_proj(&state.camera.view_projection_matrix());
+                // Re-score what's still queued first: the camera may have turned since
+                request_queue.reprioritize(|req| {
+                    (manager.loading_chunks.get(&req.coord) == Some(&req.lod)).then(|| manager.priority(req.coord, player_pos, Some(&frustum)))
+                });
+                for req in manager.update(player_pos, state.seed, Some(&frustum)) {
+                    request_queue.push(req);
                 }
                 
                 // Update Loading Progress stats
@@ -977,13 +1295,17 @@ fn main() {
                 let chunks_per_frame = if state.game_state == GameState::Loading { 1 } else { 2 };
                 for _ in 0..chunks_per_frame {
                     match rx.try_recv() {
-                        Ok((terrain_pos, terrain_col, terrain_nrm, terrain_idx,
-                            grass_pos, grass_col, grass_idx,
+                        Ok(ChunkData {
+                            terrain_pos, terrain_col, terrain_nrm, terrain_splat: terrain_weights, terrain_idx,
+                            terrain_lod,
+                            grass_instances,
+                            plant_instances,
                             tree_instances,
                             det_pos, det_nrm, det_uv, det_idx,
                             rock_instances,
                             building_instances,
-                            offset_x, offset_z)) => {
+                            offset_x, offset_z,
+                        }) => {
 
                             // Update status
                             state.loading_progress.current_status = format!(
@@ -992,85 +1314,126 @@ fn main() {
                             );
 
                             // Calculate bounds
-                            let chunk_size = 256.0;
-                            let bounds = ChunkBounds::new(
-                                offset_x as f32,
-                                offset_z as f32,
-                                chunk_size,
-                                -10.0,
-                                50.0,
-                            );
+                            let chunk_size = chunk_settings.world_size();
+                            let coord = ChunkCoord::from_world_pos(Vec3::new(offset_x as f32, 0.0, offset_z as f32), chunk_size);
+                            let bounds = chunk_settings.fitted_bounds(coord, &terrain_pos);
 
                             // Create Pipelines
                             let terrain_pipeline = {
                                 let shadow_map = shadow_map_mutex.lock().unwrap();
                                 TerrainPipeline::new(
                                     ctx.device(),
-                                    ctx.surface_format(),
-                                    &terrain_pos, &terrain_col, &terrain_nrm, &terrain_idx,
-                                    &shadow_map
+                                    ctx.scene_format(),
+                                    ctx.sample_count(),
+                                    &terrain_pos, &terrain_col, &terrain_nrm, &terrain_weights, &terrain_idx,
+                                    &shadow_map,
+                                    Some(terrain_detail_normal.clone()),
+                                    Some(terrain_splat.clone()),
                                 )
                             };
 
                             let mut grass_pipeline = None;
-                            if !grass_pos.is_empty() {
+                            if GPU_GRASS_PLACEMENT {
+                                let shadow_map = shadow_map_mutex.lock().unwrap();
+                                let mut gp = GrassPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count(), &shadow_map);
+                                drop(shadow_map);
+                                // The heightfield grid, without the skirt vertices that follow it
+                                let resolution = chunk_settings.lod_grid(terrain_lod).0;
+                                let grid = &terrain_pos[..((resolution + 1) * (resolution + 1)) as usize];
+                                let heights: Vec<f32> = grid.iter().map(|p| p[1]).collect();
+                                let density: Vec<f32> = grid
+                                    .iter()
+                                    .map(|p| croatoan_wfc::trails::ground_cover_density(p[0], p[2], Some(&state.trails)))
+                                    .collect();
+                                let heightfield = GrassHeightfield {
+                                    heights: &heights,
+                                    density: &density,
+                                    resolution,
+                                    chunk_origin: [offset_x as f32, offset_z as f32],
+                                    chunk_size,
+                                };
+                                gp.upload_heightfield(
+                                    ctx.device(),
+                                    &heightfield,
+                                    GrassPlacement {
+                                        seed: state.seed,
+                                        ..Default::default()
+                                    },
+                                );
+                                grass_pipeline = Some(gp);
+                            } else if !grass_instances.is_empty() {
                                 let shadow_map = shadow_map_mutex.lock().unwrap();
-                                let mut gp = GrassPipeline::new(ctx.device(), ctx.surface_format(), &shadow_map);
+                                let mut gp = GrassPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count(), &shadow_map);
                                 drop(shadow_map);
-                                gp.upload_mesh(ctx.device(), ctx.queue(), &grass_pos, &grass_col, &grass_idx);
+                                let blades: Vec<GrassInstance> = grass_instances
+                                    .iter()
+                                    .map(|blade| GrassInstance {
+                                        position: blade.position,
+                                        height: blade.height,
+                                        bend: blade.bend,
+                                        rotation: blade.rotation,
+                                        color: blade.color,
+                                    })
+                                    .collect();
+                                gp.upload_instances(ctx.device(), &blades);
                                 grass_pipeline = Some(gp);
                             }
 
-                            let mut tree_pipeline = None;
-                            if !tree_instances.is_empty() {
-                                if let Some(mesh) = state.mesh_registry.get("tree_oak") {
-                                    let mut tp = TreePipeline::new(ctx.device(), ctx.queue(), ctx.surface_format());
-                                    tp.set_mesh(mesh.clone());
-                                    tp.upload_instances(ctx.device(), &tree_instances);
-                                    tree_pipeline = Some(tp);
-                                }
-                            }
+                            // Trees and rocks, minus the ones the player has harvested
+                            let objects = ChunkObjects {
+                                trees: manager.unedited(coord, InstanceKind::Tree, tree_instances),
+                                rocks: manager
+                                    .unedited(coord, InstanceKind::Rock, rock_instances)
+                                    .into_iter()
+                                    .map(|(index, (name, transform))| (index, name, transform))
+                                    .collect(),
+                            };
+                            let tree_pipeline = build_tree_pipeline(ctx, state.mesh_registry.get("tree_oak"), &objects);
 
                             let mut detritus_pipeline = None;
                             if !det_pos.is_empty() {
-                                let mut dp = DetritusPipeline::new(ctx.device(), ctx.surface_format());
+                                let mut dp = DetritusPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count());
                                 dp.upload_mesh(ctx.device(), ctx.queue(), &det_pos, &det_nrm, &det_uv, &det_idx);
                                 detritus_pipeline = Some(dp);
                             }
 
-                            // Group rocks by type
-                            let mut rock_groups: std::collections::HashMap<String, Vec<Mat4>> = std::collections::HashMap::new();
-                            for (name, transform) in rock_instances {
-                                rock_groups.entry(name).or_default().push(transform);
-                            }
+                            let rock_pipelines = build_rock_pipelines(ctx, &state.rock_registry, &objects);
 
-                            let mut rock_pipelines = Vec::new();
-                            for (name, transforms) in rock_groups {
-                                if let Some(mesh) = state.mesh_registry.get(&name) {
-                                    let mut rp = TreePipeline::new(ctx.device(), ctx.queue(), ctx.surface_format());
-                                    rp.set_mesh(mesh.clone());
-                                    rp.upload_instances(ctx.device(), &transforms);
-                                    rock_pipelines.push(rp);
-                                } else {
-                                    println!("[WARN] Unknown rock type '{}' requested by generator", name);
+                            // Colliders: building boxes and tree trunks
+                            let mut colliders: Vec<Collider> = trunk_colliders(&objects).collect();
+                            for (name, transform) in &building_instances {
+                                if let Some(bounds) = state.building_bounds.get(name) {
+                                    colliders.push(Collider::building(*bounds, *transform));
                                 }
                             }
 
-                            // Process Buildings
-                            let mut building_pipelines = Vec::new();
-                            let mut buildings_by_type: std::collections::HashMap<String, Vec<Mat4>> = std::collections::HashMap::new();
-                            for (name, transform) in building_instances {
-                                buildings_by_type.entry(name).or_default().push(transform);
+                            // Buildings are drawn from the shared per-type batches; keep the known types
+                            let buildings: Vec<(String, Mat4)> = building_instances
+                                .into_iter()
+                                .filter(|(name, _)| {
+                                    let known = state.building_batches.contains_key(name);
+                                    if !known {
+                                        println!("[WARN] Building mesh '{}' not found in registry", name);
+                                    }
+                                    known
+                                })
+                                .collect();
+
+                            // Plants, one pipeline per kind in this chunk
+                            let mut plant_pipelines = Vec::new();
+                            let mut plants_by_type: std::collections::HashMap<String, Vec<Mat4>> = std::collections::HashMap::new();
+                            for (name, transform) in plant_instances {
+                                plants_by_type.entry(name).or_default().push(transform);
                             }
 
-                            for (name, transforms) in buildings_by_type {
-                                if let Some(mesh) = state.building_registry.get(&name) {
-                                    let mut pipeline = BuildingPipeline::new(ctx.device(), ctx.surface_format());
+                            for (name, transforms) in plants_by_type {
+                                if let Some(mesh) = state.plant_registry.get(&name) {
+                                    let mut pipeline = BuildingPipeline::new(ctx.device(), ctx.scene_format(), ctx.sample_count());
                                     pipeline.set_mesh(mesh.clone());
                                     pipeline.upload_instances(ctx.device(), &transforms);
-                                    building_pipelines.push(pipeline);
+                                    plant_pipelines.push(pipeline);
                                 } else {
-                                    println!("[WARN] Building mesh '{}' not found in registry", name);
+                                    println!("[WARN] Plant mesh '{}' not found in registry", name);
                                 }
                             }
 
@@ -1081,11 +1444,14 @@ fn main() {
                                 trees: tree_pipeline,
                                 detritus: detritus_pipeline,
                                 rocks: rock_pipelines,
-                                buildings: building_pipelines,
+                                buildings,
+                                plants: plant_pipelines,
                                 bounds,
+                                lod: terrain_lod,
+                                colliders,
+                                objects,
                             };
                             
-                            let coord = ChunkCoord::from_world_pos(Vec3::new(offset_x as f32, 0.0, offset_z as f32), chunk_size);
                             manager.add_chunk(coord, loaded_chunk);
 
                             // Update uploaded count
@@ -1107,23 +1473,45 @@ fn main() {
                     }
                 }
             }
+
+            // Harvest (E): remove the nearest tree/rock and rebuild its chunk's instances
+            if state.harvest_requested {
+                state.harvest_requested = false;
+                if state.game_state == GameState::Playing {
+                    if let Some(edit) = manager.nearest_object(state.player.position, HARVEST_REACH) {
+                        let WorldEdit::Removed { kind, .. } = edit;
+                        if let Some(chunk) = manager.apply_edit(edit) {
+                            chunk.trees = build_tree_pipeline(ctx, state.mesh_registry.get("tree_oak"), &chunk.objects);
+                            chunk.rocks = build_rock_pipelines(ctx, &state.rock_registry, &chunk.objects);
+                            chunk.colliders.retain(|collider| matches!(collider, Collider::Box { .. }));
+                            chunk.colliders.extend(trunk_colliders(&chunk.objects));
+                        }
+                        state.add_item(kind.item());
+                    }
+                }
+            }
         } // Release manager lock
 
         // Render frame (re-acquire locks as needed)
-        let manager = chunk_manager.lock().unwrap();
+        let mut manager = chunk_manager.lock().unwrap();
         if state.game_state == GameState::Playing && manager.chunk_count() > 0 {
-            let elapsed = start_time.elapsed().as_secs_f32();
 
             // Get the current frame
-            let output = match ctx.surface.get_current_texture() {
-                Ok(output) => output,
-                Err(wgpu::SurfaceError::Outdated) => return,
-                Err(e) => {
-                    eprintln!("Render error: {}", e);
-                    return;
+            let Some(output) = ctx.acquire_frame() else {
+                // Surface is recovering: skip drawing, but keep egui's textures (font atlas) in sync
+                let mut renderer = egui_renderer_mutex.lock().unwrap();
+                for (id, image_delta) in &full_output.textures_delta.set {
+                    renderer.update_texture(ctx.device(), ctx.queue(), *id, image_delta);
                 }
+                for id in &full_output.textures_delta.free {
+                    renderer.free_texture(id);
+                }
+                return;
             };
             let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+            // Scene passes draw into the HDR target (MSAA if enabled; the water pass resolves it),
+            // then the post-process pass tonemaps it into `view`
+            let (scene_view, resolve_target) = ctx.scene_attachment();
 
             // Create command encoder
             let mut encoder = ctx.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -1143,91 +1531,226 @@ fn main() {
             // Determine main light source (Sun or Moon)
             let is_day = sun_pos_y > -0.1; // Sun is visible or just setting
             let light_dir = if is_day { sun_dir } else { moon_dir };
+            let key_color = key_light_color(sun_pos_y);
+            let window_glow = window_glow(sun_pos_y);
 
-            // Stable shadow projection
-            let shadow_map_size = 2048.0_f32;
-            let ortho_size = 600.0_f32;
-            let shadow_center = Vec3::new(
-                (state.player.position.x / 64.0).round() * 64.0,
-                0.0,
-                (state.player.position.z / 64.0).round() * 64.0,
-            );
-            let light_pos = shadow_center - light_dir * 500.0;
-            let light_view = Mat4::look_at_rh(light_pos, shadow_center, Vec3::Y);
-            let light_proj = Mat4::orthographic_rh(-ortho_size, ortho_size, -ortho_size, ortho_size, 1.0, 1500.0);
-            let mut light_view_proj = light_proj * light_view;
-
-            // Snap to shadow map texel grid
-            let texel_size = (ortho_size * 2.0) / shadow_map_size;
-            let shadow_origin = light_view_proj.transform_point3(Vec3::ZERO);
-            let snapped_x = (shadow_origin.x / texel_size).round() * texel_size;
-            let snapped_y = (shadow_origin.y / texel_size).round() * texel_size;
-            let snap_offset = Vec3::new(snapped_x - shadow_origin.x, snapped_y - shadow_origin.y, 0.0);
-            light_view_proj = Mat4::from_translation(snap_offset) * light_view_proj;
+            // Sky ambient (time of day + weather), floored by the menu slider
+            let (ambient_color, ambient_intensity) = state.weather.ambient_light(sun_pos_y, state.settings.min_ambient);
+            let ambient_color = ambient_color.to_array();
+
+            // Cascaded shadows: each cascade fits its slice of the view frustum (texel-snapped)
+            let shadow_map_size = shadow_map_mutex.lock().unwrap().size;
+            let cascades = shadow_pipeline_mutex.lock().unwrap().compute_cascades(&state.camera, light_dir, shadow_map_size);
 
             // Update grass and tree cameras
             let view_proj = state.camera.view_projection_matrix();
             let frustum = Frustum::from_view_proj(&view_proj);
+            // Chunk boxes, not spheres: these are wide and flat, so spheres let in far too many
+            let in_view = |chunk: &LoadedChunk| {
+                let (min, max) = chunk.culling_box();
+                frustum.contains_aabb(min, max)
+            };
+
+            // LOD distances
+            let grass_max_distance = 350.0;
+            let tree_max_distance = 600.0;
+            let detritus_max_distance = 500.0;
+            let building_max_distance = 1000.0; // Buildings visible further
+            let plant_max_distance = 200.0;
 
             {
-                for (_coord, chunk) in manager.iter_chunks() {
-                    if let Some(grass) = &chunk.grass {
-                        grass.update_camera(ctx.queue(), &view_proj, &light_view_proj, light_dir.to_array(), elapsed);
+                // Billboard axes for tree leaves
+                let camera_right = state.camera.right();
+                let camera_up = camera_right.cross(state.camera.forward());
+                // Storms bend the vegetation harder
+                let wind = state.weather.wind();
+                for (_coord, chunk) in manager.iter_chunks_mut() {
+                    if let Some(grass) = &mut chunk.grass {
+                        grass.set_wind(wind);
+                        grass.set_fade_range(grass_max_distance - 50.0, grass_max_distance);
+                        grass.set_interaction(state.grass_interaction);
                     }
-                    if let Some(trees) = &chunk.trees {
-                        trees.update_camera(ctx.queue(), &view_proj);
+                    let chunk_in_view = in_view(chunk);
+                    if let Some(trees) = &mut chunk.trees {
+                        trees.set_wind(wind);
+                        trees.update_camera(ctx.queue(), &view_proj, state.camera.position, camera_right, camera_up, elapsed);
+                        // Per-tree culling for chunks that will be drawn (straddling the view edge)
+                        let dist = (chunk.bounds.center - state.camera.position).length();
+                        if dist <= tree_max_distance && chunk_in_view {
+                            trees.cull(ctx.queue(), &frustum, state.camera.position, state.camera.forward());
+                        }
                     }
                     if let Some(detritus) = &chunk.detritus {
                         detritus.update_camera(ctx.queue(), &view_proj);
                     }
-                    for rock in &chunk.rocks {
-                        rock.update_camera(ctx.queue(), &view_proj);
-                    }
                     // for building in &chunk.buildings {
                     //     building.update_camera(ctx.queue(), &view_proj);
                     // }
                 }
             }
 
-            // Update Water & Dispatch Compute
-            // {
-            //     let mut water = water_system_mutex.lock().unwrap();
-            //     water.update(ctx.queue(), elapsed, delta);
-            //     water.update_camera(ctx.queue(), view_proj.to_cols_array_2d(), state.camera.position.to_array());
-            //     water.dispatch(&mut encoder);
-            // }
+            // Occlusion proxies for the chunks in view; last frame's results pick which are drawn
+            let mut occlusion = occlusion_culler_mutex.lock().unwrap();
+            occlusion.prepare(
+                ctx.queue(),
+                &view_proj,
+                state.camera.position,
+                manager.iter_chunks()
+                    .filter(|(_, chunk)| in_view(chunk))
+                    .map(|(coord, chunk)| {
+                        let (min, max) = chunk.culling_box();
+                        (*coord, min, max)
+                    }),
+            );
+
+            // Debug overlay, coloured by how this frame culls each chunk
+            let mut debug_lines = debug_lines_mutex.lock().unwrap();
+            debug_lines.clear();
+            if state.show_chunk_bounds {
+                for (coord, chunk) in manager.iter_chunks() {
+                    let color = if !in_view(chunk) {
+                        [1.0, 0.9, 0.1]
+                    } else if occlusion.is_occluded(coord) {
+                        [1.0, 0.15, 0.1]
+                    } else {
+                        [0.2, 1.0, 0.3]
+                    };
+                    let (min, max) = chunk.culling_box();
+                    debug_lines.add_box(min, max, color);
+                }
+            }
+            if let Some(frozen) = &state.frozen_frustum {
+                debug_lines.add_frustum(frozen, [0.3, 0.7, 1.0]);
+            }
+            debug_lines.upload(ctx.device(), ctx.queue(), &view_proj);
+
+            // Grass placement/culling compute (GPU path only, chunks within grass range)
+            for (coord, chunk) in manager.iter_chunks() {
+                if let Some(grass) = &chunk.grass {
+                    let dist = (chunk.bounds.center - state.camera.position).length();
+                    if dist <= grass_max_distance && in_view(chunk) && !occlusion.is_occluded(coord) {
+                        grass.dispatch_placement(ctx.queue(), &mut encoder, &view_proj, state.camera.position);
+                    }
+                }
+            }
+
+            // Map Pass: top-down terrain + buildings into the map texture.
+            // Submitted on its own so the per-chunk uniforms can be rewritten for the main view below.
+            if state.map.open {
+                let map_view_proj = state.map.view_proj(state.player.position);
+                let map_half_extent = state.map.half_extent();
+                let map_center = state.map.center(state.player.position);
+                let map_eye = map_center + Vec3::Y * 1000.0;
+                let no_fog = (1.0e6_f32, 2.0e6_f32);
+                let map_lighting = TerrainLighting {
+                    fog_color: [0.0; 3],
+                    fog_start: no_fog.0,
+                    fog_end: no_fog.1,
+                    sun_dir: light_dir.to_array(),
+                    sun_color: key_color.to_array(),
+                    ambient_color,
+                    ambient_intensity,
+                };
+
+                let mut map_encoder = ctx.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Map Encoder"),
+                });
+                let on_map = |chunk: &LoadedChunk| {
+                    let offset = chunk.bounds.center - map_center;
+                    offset.x.abs() <= map_half_extent + chunk.bounds.radius && offset.z.abs() <= map_half_extent + chunk.bounds.radius
+                };
+                fill_building_batches(ctx, &mut state.building_batches, manager.iter_chunks().map(|(_, chunk)| (chunk, true)).filter(|(chunk, _)| on_map(chunk)));
+                {
+                    let mut map_pass = map_target.begin_pass(&mut map_encoder, wgpu::Color { r: 0.05, g: 0.3, b: 0.4, a: 1.0 });
+                    for (_coord, chunk) in manager.iter_chunks() {
+                        if !on_map(chunk) {
+                            continue;
+                        }
+                        chunk.terrain.update_uniforms(ctx.queue(), &map_view_proj, &cascades, elapsed, map_eye.to_array(), &map_lighting);
+                        chunk.terrain.render(&mut map_pass);
+                    }
+                    for building in state.building_batches.values() {
+                        building.update_uniforms(ctx.queue(), &map_view_proj, map_eye, &map_lighting, window_glow);
+                        building.render(&mut map_pass);
+                    }
+                }
+                ctx.queue().submit(std::iter::once(map_encoder.finish()));
+            }
+
+            // Buildings in range: drawn if in view and not occluded, else shadow casters if a cascade reaches them
+            let camera_position = state.camera.position;
+            fill_building_batches(
+                ctx,
+                &mut state.building_batches,
+                manager.iter_chunks().filter_map(|(coord, chunk)| {
+                    if (chunk.bounds.center - camera_position).length() > building_max_distance {
+                        return None;
+                    }
+                    let visible = in_view(chunk) && !occlusion.is_occluded(coord);
+                    let casts_shadow = cascades.bounds.iter().any(|(center, radius)| {
+                        let offset = chunk.bounds.center - *center;
+                        offset.x.abs() <= radius + chunk.bounds.radius && offset.z.abs() <= radius + chunk.bounds.radius
+                    });
+                    (visible || casts_shadow).then_some((chunk, visible))
+                }),
+            );
+            let show_player = state.game_state == GameState::Playing && state.camera_mode == CameraMode::ThirdPerson;
+            let feet = state.player.interpolated_position(alpha) - Vec3::Y * state.player.height;
+            if let Some(model) = &mut state.player_model {
+                let transform = [Mat4::from_translation(feet)];
+                model.write_instances(ctx.device(), ctx.queue(), if show_player { &transform } else { &[] }, &[]);
+            }
 
-            // 0. Shadow Pass
+            // 0. Shadow Pass (one per cascade)
             {
                 let shadow_map = shadow_map_mutex.lock().unwrap();
                 let shadow_pipeline = shadow_pipeline_mutex.lock().unwrap();
-                shadow_pipeline.update_uniforms(ctx.queue(), &light_view_proj);
-
-                let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("Shadow Pass"),
-                    color_attachments: &[],
-                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                        view: &shadow_map.view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
-                            store: wgpu::StoreOp::Store,
+                shadow_pipeline.update_uniforms(ctx.queue(), &cascades);
+
+                for (cascade, cascade_view) in shadow_map.cascade_views.iter().enumerate() {
+                    let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Shadow Pass"),
+                        color_attachments: &[],
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: cascade_view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: wgpu::StoreOp::Store,
+                            }),
+                            stencil_ops: None,
                         }),
-                        stencil_ops: None,
-                    }),
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
-                });
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    shadow_pipeline.begin_cascade(&mut shadow_pass, cascade);
+
+                    let (cascade_center, cascade_radius) = cascades.bounds[cascade];
+                    for (_coord, chunk) in manager.iter_chunks() {
+                        // Only chunks overlapping this cascade's footprint
+                        let offset = chunk.bounds.center - cascade_center;
+                        if offset.x.abs() > cascade_radius + chunk.bounds.radius || offset.z.abs() > cascade_radius + chunk.bounds.radius {
+                            continue;
+                        }
 
-                for (_coord, chunk) in manager.iter_chunks() {
-                    shadow_pipeline.render(
-                        &mut shadow_pass,
-                        &chunk.terrain.vertex_buffer,
-                        &chunk.terrain.index_buffer,
-                        chunk.terrain.index_count,
-                    );
-                    // for building in &chunk.buildings {
-                    //     building.render_shadow(&mut shadow_pass, &shadow_pipeline);
-                    // }
+                        shadow_pipeline.render(
+                            &mut shadow_pass,
+                            &chunk.terrain.vertex_buffer,
+                            &chunk.terrain.index_buffer,
+                            chunk.terrain.index_count,
+                        );
+                        if let Some(trees) = &chunk.trees {
+                            trees.render_shadow(&mut shadow_pass, &shadow_pipeline);
+                        }
+                        for rock in &chunk.rocks {
+                            rock.render_shadow(&mut shadow_pass, &shadow_pipeline);
+                        }
+                    }
+                    for building in state.building_batches.values() {
+                        building.render_shadow(&mut shadow_pass, &shadow_pipeline);
+                    }
+                    if let Some(model) = &state.player_model {
+                        model.render_shadow(&mut shadow_pass, &shadow_pipeline);
+                    }
                 }
             }
 
@@ -1269,18 +1792,23 @@ fn main() {
                     sun_dir,
                     Vec3::new(1.0, 1.0, 1.0), // Sun Color (White for now)
                     elapsed,
-                    state.weather.cloud_coverage,
-                    state.weather.cloud_color_base,
-                    state.weather.cloud_density,
-                    state.weather.cloud_color_shade,
-                    state.weather.cloud_scale,
-                    state.weather.wind_offset,
+                    &SkyParams {
+                        cloud_coverage: state.weather.cloud_coverage,
+                        cloud_color_base: state.weather.cloud_color_base,
+                        cloud_density: state.weather.cloud_density,
+                        cloud_color_shade: state.weather.cloud_color_shade,
+                        cloud_scale: state.weather.cloud_scale,
+                        wind_offset: state.weather.wind_offset,
+                        time_of_day: state.time_of_day,
+                        star_intensity: 1.0,
+                        milky_way: true,
+                    },
                 );
 
                 let mut sky_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some("Sky Pass"),
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
+                        view: scene_view,
                         resolve_target: None,
                         ops: wgpu::Operations {
                             load: wgpu::LoadOp::Clear(sky_color), // Clear with gradient base, then draw clouds over
@@ -1304,7 +1832,7 @@ fn main() {
                 let mut sun_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some("Sun/Moon Pass"),
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
+                        view: scene_view,
                         resolve_target: None,
 
                         ops: wgpu::Operations {
@@ -1325,21 +1853,108 @@ fn main() {
 
                 // Render Moon
                 if sun_pos_y < 0.2 { // Visible when sun is low or set
-                    // Hack: Pass a fixed "midday" time (12.0) to get white color from sun logic, 
-                    // or we could modify sun pipeline to take explicit color.
-                    // For now, let's rely on the fact that 12.0 gives white.
-                    moon_pipeline.update(ctx.queue(), &view_proj, moon_dir, state.camera.position, state.camera.right(), state.camera.up, 12.0);
+                    let moon = MoonState {
+                        moon_dir,
+                        sun_dir,
+                        phase: lunar_phase(state.day_count as f32 + state.time_of_day / 24.0),
+                    };
+                    moon_pipeline.update(ctx.queue(), &view_proj, &moon, state.camera.position, state.camera.right(), state.camera.up);
                     moon_pipeline.render(&mut sun_pass);
                 }
             }
 
+            // Weather fog, colored to match the sky; with the camera underwater, a short murky blue
+            // fog instead (every pass takes the fog uniforms, so this tints the whole view)
+            let (fog_color, fog_start, fog_end) = if state.player.is_submerged() && state.game_state == GameState::Playing {
+                let light = ambient_intensity.clamp(0.1, 1.0);
+                ([0.04 * light, 0.22 * light, 0.30 * light], 0.0, 30.0)
+            } else {
+                // Distance and tint from the weather (Foggy closes in, Clear sees far)
+                state.weather.fog(Vec3::new(sky_color.r as f32, sky_color.g as f32, sky_color.b as f32))
+            };
+            let terrain_lighting = TerrainLighting {
+                fog_color,
+                fog_start,
+                fog_end,
+                sun_dir: light_dir.to_array(),
+                sun_color: key_color.to_array(),
+                ambient_color,
+                ambient_intensity,
+            };
+
+            // Grass and rock uniforms: written once here, ahead of the main pass that draws them
+            for (_coord, chunk) in manager.iter_chunks() {
+                if let Some(grass) = &chunk.grass {
+                    grass.update_camera(ctx.queue(), &view_proj, state.camera.position, &cascades, elapsed, &terrain_lighting);
+                }
+                for rock in &chunk.rocks {
+                    rock.update_uniforms(ctx.queue(), &view_proj, state.camera.position, &terrain_lighting);
+                }
+            }
+
+            // Update Water & Dispatch Compute (waves are ready before the transparent pass draws them)
+            let mut water = water_system_mutex.lock().unwrap();
+            water.set_depth_view(ctx.device(), ctx.depth_view());
+            water.set_light(light_dir, key_color);
+            water.set_foam_width(state.settings.water_foam_width);
+            water.update(ctx.queue(), elapsed, delta);
+            water.update_camera(ctx.queue(), view_proj.to_cols_array_2d(), state.camera.position.to_array(), fog_color, fog_start, fog_end);
+            water.dispatch(&mut encoder);
+
+            // Rain around the camera, as heavy as the clouds; none seen from underwater
+            let mut rain = rain_pipeline_mutex.lock().unwrap();
+            let rain_intensity = if state.player.is_submerged() { 0.0 } else { state.weather.precipitation_intensity() };
+            let rain_light = Vec3::from(ambient_color) * ambient_intensity;
+            rain.set_weather(rain_intensity, state.weather.wind());
+            rain.update(ctx.queue(), &view_proj, state.camera.position, elapsed, rain_light);
+
+            // Depth prepass: terrain, rocks and buildings depth-only, so the main pass skips
+            // whatever they hide (the wireframe view needs the terrain's hidden edges)
+            let depth_prepassed = ctx.depth_prepass() && !ctx.wireframe();
+            if depth_prepassed {
+                depth_prepass.update_uniforms(ctx.queue(), &view_proj);
+                let mut prepass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Depth Prepass"),
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: ctx.depth_view(),
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(0.0), // Reverse-Z: far plane is 0.0
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                depth_prepass.begin(&mut prepass);
+
+                // Same chunks as the main pass draws
+                for (coord, chunk) in manager.iter_chunks() {
+                    if !in_view(chunk) || occlusion.is_occluded(coord) {
+                        continue;
+                    }
+                    chunk.terrain.render_depth(&mut prepass, depth_prepass);
+                    if (chunk.bounds.center - state.camera.position).length() <= tree_max_distance {
+                        for rock in &chunk.rocks {
+                            rock.render_depth(&mut prepass, depth_prepass);
+                        }
+                    }
+                }
+                for building in state.building_batches.values() {
+                    building.render_depth(&mut prepass, depth_prepass);
+                }
+                if let Some(model) = &state.player_model {
+                    model.render_depth(&mut prepass, depth_prepass);
+                }
+            }
+
             // 2. Main Render Pass
             {
-                // let water_system_guard = water_system_mutex.lock().unwrap();
                 let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some("Main Pass"),
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
+                        view: scene_view,
                         resolve_target: None,
                         ops: wgpu::Operations {
                             load: wgpu::LoadOp::Load, // Keep sky + sun from previous pass
@@ -1349,58 +1964,44 @@ fn main() {
                     depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                         view: ctx.depth_view(),
                         depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
+                            // Reverse-Z: far plane is 0.0
+                            load: if depth_prepassed { wgpu::LoadOp::Load } else { wgpu::LoadOp::Clear(0.0) },
                             store: wgpu::StoreOp::Store,
                         }),
                         stencil_ops: None,
                     }),
                     timestamp_writes: None,
-                    occlusion_query_set: None,
+                    occlusion_query_set: Some(occlusion.query_set()),
                 });
 
-                // Dynamic fog color matching sky
-                let fog_color = [
-                    sky_color.r as f32 * 0.9,
-                    sky_color.g as f32 * 0.9,
-                    sky_color.b as f32 * 0.9,
-                ];
-                let fog_start = 200.0;
-                let fog_end = 600.0;
-
-                // Render chunks with frustum culling and LOD
+                // Render chunks with frustum + occlusion culling and LOD
                 let mut terrain_rendered = 0;
                 let mut terrain_culled = 0;
+                let mut terrain_occluded = 0;
                 let mut grass_rendered = 0;
                 let mut trees_rendered = 0;
                 let mut buildings_rendered = 0;
 
-                let grass_max_distance = 350.0;
-                let tree_max_distance = 600.0;
-                let detritus_max_distance = 500.0;
-                let building_max_distance = 1000.0; // Buildings visible further
-
-                for (_coord, chunk) in manager.iter_chunks() {
+                for (coord, chunk) in manager.iter_chunks() {
                     // Frustum cull - skip chunks outside view
-                    if !frustum.contains_sphere(chunk.bounds.center, chunk.bounds.radius) {
+                    if !in_view(chunk) {
                         terrain_culled += 1;
                         continue;
                     }
+                    // Occlusion cull - skip chunks that were hidden behind terrain last frame
+                    if occlusion.is_occluded(coord) {
+                        terrain_occluded += 1;
+                        continue;
+                    }
                     terrain_rendered += 1;
 
                     // Terrain
-                    chunk.terrain.update_uniforms(
-                        ctx.queue(),
-                        &view_proj,
-                        &light_view_proj,
-                        elapsed,
-                        fog_color,
-                        fog_start,
-                        fog_end,
-                        sun_dir.to_array(),
-                        state.camera.position.to_array(),
-                        state.camera.position.to_array()
-                    );
-                    chunk.terrain.render(&mut render_pass);
+                    chunk.terrain.update_uniforms(ctx.queue(), &view_proj, &cascades, elapsed, state.camera.position.to_array(), &terrain_lighting);
+                    if ctx.wireframe() {
+                        chunk.terrain.render_wireframe(&mut render_pass);
+                    } else {
+                        chunk.terrain.render(&mut render_pass);
+                    }
 
                     let dist = (chunk.bounds.center - state.camera.position).length();
 
@@ -1434,36 +2035,86 @@ fn main() {
                         }
                     }
 
-                    // Buildings
-                    for building in &chunk.buildings {
-                        if dist <= building_max_distance {
-                            buildings_rendered += 1;
-                            building.update_uniforms(
-                                ctx.queue(),
-                                &view_proj,
-                                sun_dir,
-                                state.camera.position,
-                                fog_color,
-                                fog_start,
-                                fog_end,
-                            );
-                            building.render(&mut render_pass);
+                    // Bushes, ferns and flowers (small, so only drawn nearby)
+                    for plant in &chunk.plants {
+                        if dist <= plant_max_distance {
+                            plant.update_uniforms(ctx.queue(), &view_proj, state.camera.position, &terrain_lighting, 0.0);
+                            plant.render(&mut render_pass);
                         }
                     }
                 }
 
-                // Render Water
-                // water_system_guard.draw(&mut render_pass);
+                // Buildings: one instanced draw per type across all visible chunks
+                for building in state.building_batches.values() {
+                    buildings_rendered += 1;
+                    building.update_uniforms(ctx.queue(), &view_proj, state.camera.position, &terrain_lighting, window_glow);
+                    building.render(&mut render_pass);
+                }
+                if let Some(model) = &state.player_model {
+                    model.update_uniforms(ctx.queue(), &view_proj, state.camera.position, &terrain_lighting, 0.0);
+                    model.render(&mut render_pass);
+                }
+
+                // Proxies last, against the finished depth buffer
+                occlusion.render(&mut render_pass);
+                debug_lines.render(&mut render_pass);
 
                 // Log culling stats occasionally (every ~60 frames)
-                let _ = (terrain_rendered, terrain_culled, grass_rendered, trees_rendered, buildings_rendered);
+                let _ = (terrain_rendered, terrain_culled, terrain_occluded, grass_rendered, trees_rendered, buildings_rendered);
             } // End Main Pass
 
+            // Ambient occlusion: contact shadows in crevices and under trees, rocks and buildings,
+            // multiplied into the opaque scene before the water and rain go over it
+            {
+                let mut ssao = ssao_pipeline_mutex.lock().unwrap();
+                ssao.set_params(state.settings.ssao_radius, state.settings.ssao_strength);
+                ssao.set_depth_view(ctx.device(), ctx.depth_view(), ctx.config().width, ctx.config().height);
+                ssao.update(ctx.queue(), &state.camera.projection_matrix());
+                ssao.render(&mut encoder, scene_view);
+            }
+
+            // 3. Transparent Pass: blended geometry over the opaque scene, sorted far to near,
+            // with its depth read-only so the water both depth-tests against the land and samples
+            // it for shoreline foam (it fogs itself). Rain surrounds the camera, so it goes last
+            {
+                let mut transparent = TransparentQueue::new();
+                water.queue_draws(&mut transparent, state.camera.position);
+                rain.queue(&mut transparent);
+
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Transparent Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: scene_view,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: ctx.depth_view(),
+                        depth_ops: None, // Read-only: also bound as a texture
+                        stencil_ops: None,
+                    }),
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                transparent.render(&mut render_pass);
+            }
+
+            // 4. Post Process: bloom, exposure + tonemapping into the swapchain frame
+            {
+                let mut post = post_pipeline_mutex.lock().unwrap();
+                post.set_exposure(state.settings.exposure);
+                post.set_bloom_enabled(state.settings.bloom);
+                post.render(ctx.device(), ctx.queue(), &mut encoder, ctx.hdr_texture(), &view);
+            }
+
             // 2. Egui Pass
             {
                 let screen_descriptor = egui_wgpu::ScreenDescriptor {
                     size_in_pixels: [ctx.config().width, ctx.config().height],
-                    pixels_per_point: ctx.window.scale_factor() as f32,
+                    pixels_per_point: window.scale_factor() as f32,
                 };
 
                 let tris = state.egui_ctx.tessellate(full_output.shapes, full_output.pixels_per_point);
@@ -1505,11 +2156,36 @@ fn main() {
                 }
             }
 
+            occlusion.resolve(&mut encoder);
             ctx.queue().submit(std::iter::once(encoder.finish()));
+            occlusion.read_back(ctx.device());
+            drop(occlusion);
+
+            if std::mem::take(&mut state.screenshot_requested) {
+                let stamp = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let path = format!("screenshots/roanoke_{}.png", stamp);
+                if let Err(e) = ctx.save_screenshot(&output, std::path::Path::new(&path)) {
+                    println!("[SCREENSHOT] Failed: {}", e);
+                }
+            }
+
             output.present();
         } else {
             // Menu or Loading rendering (just egui)
-            let output = ctx.surface.get_current_texture().unwrap();
+            let Some(output) = ctx.acquire_frame() else {
+                // Surface is recovering: skip drawing, but keep egui's textures (font atlas) in sync
+                let mut renderer = egui_renderer_mutex.lock().unwrap();
+                for (id, image_delta) in &full_output.textures_delta.set {
+                    renderer.update_texture(ctx.device(), ctx.queue(), *id, image_delta);
+                }
+                for id in &full_output.textures_delta.free {
+                    renderer.free_texture(id);
+                }
+                return;
+            };
             let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
             let mut encoder = ctx.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -1543,7 +2219,7 @@ fn main() {
             {
                 let screen_descriptor = egui_wgpu::ScreenDescriptor {
                     size_in_pixels: [ctx.config().width, ctx.config().height],
-                    pixels_per_point: ctx.window.scale_factor() as f32,
+                    pixels_per_point: window.scale_factor() as f32,
                 };
 
                 let tris = state.egui_ctx.tessellate(full_output.shapes, full_output.pixels_per_point);
@@ -1595,3 +2271,31 @@ fn main() {
         eprintln!("Engine error: {}", e);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_light_dims_at_night() {
+        let noon = key_light_color(1.0);
+        let sunset = key_light_color(0.05);
+        let midnight = key_light_color(-1.0);
+        assert!(sunset.x > sunset.z, "sunset light is warm");
+        assert!(midnight.z > midnight.x, "moonlight is cool");
+        assert!(midnight.length() < noon.length() * 0.2);
+        // No pop where the key light switches from the sun to the moon
+        assert!(key_light_color(-0.099).length() < 0.05);
+        assert!(key_light_color(-0.101).length() < 0.05);
+    }
+
+    #[test]
+    fn test_windows_light_up_at_night() {
+        assert_eq!(window_glow(1.0), 0.0);
+        assert_eq!(window_glow(0.1), 0.0);
+        assert!(window_glow(0.0) > 0.0 && window_glow(0.0) < 1.0, "dusk is partly lit");
+        // Fully on wherever `is_day` (sun above -0.1) is false
+        assert_eq!(window_glow(-0.1), 1.0);
+        assert_eq!(window_glow(-1.0), 1.0);
+    }
+}
@@ -0,0 +1,90 @@
+use glam::{Mat4, Vec2, Vec3};
+use croatoan_render::Camera;
+
+/// Offscreen map texture size (pixels per side)
+pub const MAP_TEXTURE_SIZE: u32 = 1024;
+
+/// World units from the map center to its edge at zoom 1.0 (the streamed region)
+const BASE_HALF_EXTENT: f32 = 640.0;
+
+/// Full-screen top-down map (M key): pan by dragging, zoom with the scroll wheel
+pub struct MapView {
+    pub open: bool,
+    pub zoom: f32,
+    /// Offset of the map center from the player (world XZ)
+    pub pan: Vec2,
+}
+
+impl MapView {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        if self.open {
+            self.pan = Vec2::ZERO; // Re-center on the player
+        }
+    }
+
+    pub fn half_extent(&self) -> f32 {
+        BASE_HALF_EXTENT / self.zoom
+    }
+
+    pub fn center(&self, player_pos: Vec3) -> Vec3 {
+        Vec3::new(player_pos.x + self.pan.x, 0.0, player_pos.z + self.pan.y)
+    }
+
+    pub fn view_proj(&self, player_pos: Vec3) -> Mat4 {
+        Camera::top_down_view_proj(self.center(player_pos), self.half_extent())
+    }
+
+    /// Draw the map window around the offscreen texture, with the player marker
+    pub fn show(&mut self, ui_ctx: &egui::Context, texture: egui::TextureId, player_pos: Vec3, player_yaw: f32) {
+        let mut open = self.open;
+        egui::Window::new("Map")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ui_ctx, |ui| {
+                let side = (ui_ctx.screen_rect().height() - 120.0).clamp(200.0, 900.0);
+                let image = egui::Image::new(egui::load::SizedTexture::new(texture, egui::vec2(side, side)))
+                    .sense(egui::Sense::drag());
+                let response = ui.add(image);
+
+                let world_per_pixel = self.half_extent() * 2.0 / side;
+                if response.dragged() {
+                    let delta = response.drag_delta();
+                    self.pan -= Vec2::new(delta.x, delta.y) * world_per_pixel;
+                }
+                if response.hovered() {
+                    let scroll = ui.input(|i| i.raw_scroll_delta.y);
+                    if scroll != 0.0 {
+                        self.zoom = (self.zoom * (1.0 + scroll * 0.002)).clamp(0.5, 8.0);
+                    }
+                }
+
+                // Player marker (the map is centered on player + pan)
+                let rect = response.rect;
+                let offset = -self.pan / world_per_pixel;
+                let marker = rect.center() + egui::vec2(offset.x, offset.y);
+                if rect.contains(marker) {
+                    let painter = ui.painter_at(rect);
+                    let heading = egui::vec2(player_yaw.cos(), player_yaw.sin()) * 14.0;
+                    painter.line_segment([marker, marker + heading], egui::Stroke::new(3.0, egui::Color32::BLACK));
+                    painter.circle(marker, 6.0, egui::Color32::from_rgb(200, 40, 30), egui::Stroke::new(2.0, egui::Color32::WHITE));
+                }
+
+                ui.label(format!(
+                    "({:.0}, {:.0})  zoom {:.1}x  -  drag to pan, scroll to zoom",
+                    player_pos.x, player_pos.z, self.zoom
+                ));
+            });
+        self.open = open;
+    }
+}
@@ -1,67 +1,442 @@
-use glam::Vec3;
-use croatoan_wfc::mesh_gen::get_height_at;
-
-pub struct Player {
-    pub position: Vec3,
-    pub velocity: Vec3,
-    pub yaw: f32,
-    pub pitch: f32,
-    pub on_ground: bool,
-    pub speed: f32,
-    pub jump_force: f32,
-    pub gravity: f32,
-    pub height: f32, // Eye height
-}
-
-impl Player {
-    pub fn new(position: Vec3) -> Self {
-        Self {
-            position,
-            velocity: Vec3::ZERO,
-            yaw: -90.0f32.to_radians(), // Look East
-            pitch: 0.0,
-            on_ground: false,
-            speed: 10.0,
-            jump_force: 15.0,
-            gravity: 30.0,
-            height: 1.8, // Standard human height
-        }
-    }
-
-    pub fn update(&mut self, dt: f32, input_dir: Vec3, seed: u32) {
-        // Apply Gravity
-        self.velocity.y -= self.gravity * dt;
-
-        // Movement (XZ plane)
-        // Input dir is relative to camera rotation
-        let forward = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin()).normalize();
-        let right = Vec3::new(-self.yaw.sin(), 0.0, self.yaw.cos()).normalize();
-        
-        let move_vec = (forward * input_dir.z + right * input_dir.x).normalize_or_zero();
-        
-        // Simple movement (no inertia for now)
-        self.velocity.x = move_vec.x * self.speed;
-        self.velocity.z = move_vec.z * self.speed;
-
-        // Apply Velocity
-        self.position += self.velocity * dt;
-
-        // Terrain Collision
-        let (terrain_height, _) = get_height_at(self.position.x, self.position.z, seed);
-        
-        if self.position.y < terrain_height + self.height {
-            self.position.y = terrain_height + self.height;
-            self.velocity.y = 0.0;
-            self.on_ground = true;
-        } else {
-            self.on_ground = false;
-        }
-    }
-
-    pub fn jump(&mut self) {
-        if self.on_ground {
-            self.velocity.y = self.jump_force;
-            self.on_ground = false;
-        }
-    }
-}
+use glam::Vec3;
+use croatoan_wfc::{sample_terrain, Biome};
+use croatoan_wfc::trails::TrailNetwork;
+use crate::collision::Collider;
+
+/// Longest frame delta fed to physics; larger hitches (loading, window drag) are dropped
+const MAX_FRAME_DELTA: f32 = 0.25;
+
+/// Sea surface (the ocean plane sits at y = 0; rivers are carved below it)
+pub const WATER_LEVEL: f32 = 0.0;
+/// Water shallower than this is waded through on foot; deeper, the player swims
+const SWIM_DEPTH: f32 = 1.5;
+/// Gravity multiplier in the water
+const SWIM_GRAVITY_SCALE: f32 = 0.2;
+/// Upward pull per meter the eyes sit below their floating height (1/s^2)
+const BUOYANCY: f32 = 20.0;
+/// Eyes float this far above the surface
+const FLOAT_EYE_HEIGHT: f32 = 0.5;
+/// Vertical velocity damping in the water (1/s)
+const WATER_DRAG: f32 = 3.0;
+/// Move speed multipliers swimming and wading
+const SWIM_SPEED_SCALE: f32 = 0.5;
+const WADE_SPEED_SCALE: f32 = 0.7;
+/// Feet this close above the ground still step (walking downhill skims just off it)
+const FOOTSTEP_GROUND_TOLERANCE: f32 = 0.3;
+
+/// Something the player's feet (or head) did that makes a sound, from `take_footsteps`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Footstep {
+    /// A stride on dry ground of this biome
+    Ground(Biome),
+    /// A stride through water shallow enough to wade
+    Wading,
+    /// The eyes went under the surface
+    Splash,
+}
+
+pub struct Player {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+    pub speed: f32,
+    pub jump_force: f32,
+    pub gravity: f32,
+    pub height: f32, // Eye height
+    pub radius: f32, // Body radius against buildings and trunks
+    pub acceleration: f32, // Horizontal response rate while moving (1/s)
+    pub friction: f32, // Horizontal decay rate with no input (1/s)
+    /// Mouse-look smoothing time constant in seconds (0 = raw 1:1)
+    pub look_smoothing: f32,
+    /// Ground covered per footstep (m)
+    pub stride_length: f32,
+    swimming: bool,
+    /// Distance walked since the last footstep
+    stride: f32,
+    was_submerged: bool,
+    footsteps: Vec<Footstep>,
+    /// Position before the latest `update`, for drawing between physics steps
+    previous_position: Vec3,
+    jump_requested: bool,
+    target_yaw: f32,
+    target_pitch: f32,
+}
+
+impl Player {
+    pub fn new(position: Vec3) -> Self {
+        let yaw = -90.0f32.to_radians(); // Look East
+        Self {
+            position,
+            velocity: Vec3::ZERO,
+            yaw,
+            pitch: 0.0,
+            on_ground: false,
+            speed: 10.0,
+            jump_force: 15.0,
+            gravity: 30.0,
+            height: 1.8, // Standard human height
+            radius: 0.4,
+            acceleration: 12.0,
+            friction: 10.0,
+            look_smoothing: 0.0,
+            stride_length: 1.6,
+            swimming: false,
+            stride: 0.0,
+            was_submerged: position.y < WATER_LEVEL,
+            footsteps: Vec::new(),
+            previous_position: position,
+            jump_requested: false,
+            target_yaw: yaw,
+            target_pitch: 0.0,
+        }
+    }
+
+    /// Accumulate raw mouse-look input (radians). Applied, optionally smoothed, in `update`.
+    pub fn add_look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        self.target_yaw += delta_yaw;
+        self.target_pitch = (self.target_pitch + delta_pitch).clamp(-1.5, 1.5);
+        if self.look_smoothing <= 0.0 {
+            self.yaw = self.target_yaw;
+            self.pitch = self.target_pitch;
+        }
+    }
+
+    /// Snap the view direction (loading a save, respawning) without smoothing
+    pub fn set_look(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.target_yaw = yaw;
+        self.target_pitch = pitch;
+    }
+
+    /// Move the player (loading a save) without interpolating from the old spot
+    pub fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+        self.previous_position = position;
+        self.was_submerged = self.is_submerged();
+    }
+
+    /// Ease the applied yaw/pitch toward the raw target (exponential, frame-rate independent)
+    fn apply_look(&mut self, dt: f32) {
+        if self.look_smoothing <= 0.0 {
+            self.yaw = self.target_yaw;
+            self.pitch = self.target_pitch;
+            return;
+        }
+        let blend = 1.0 - (-dt / self.look_smoothing).exp();
+        self.yaw += (self.target_yaw - self.yaw) * blend;
+        self.pitch += (self.target_pitch - self.pitch) * blend;
+    }
+
+    pub fn update(&mut self, dt: f32, input_dir: Vec3, seed: u32, trails: Option<&TrailNetwork>) {
+        let dt = dt.clamp(0.0, MAX_FRAME_DELTA);
+
+        // Look once per frame, before movement reads the facing
+        self.apply_look(dt);
+        self.previous_position = self.position;
+
+        self.step(dt, input_dir, seed, trails);
+    }
+
+    /// Advance physics by exactly `dt` seconds
+    fn step(&mut self, dt: f32, input_dir: Vec3, seed: u32, trails: Option<&TrailNetwork>) {
+        // Jump impulse is applied inside the step so it lines up with the fixed timestep
+        if self.jump_requested {
+            self.jump_requested = false;
+            if self.on_ground {
+                self.velocity.y = self.jump_force;
+                self.on_ground = false;
+            }
+        }
+
+        // Water: swim where it's too deep to stand once the feet are in it, wade otherwise
+        let ground = sample_terrain(seed, self.position.x, self.position.z, trails).height;
+        let feet_in_water = self.position.y - self.height < WATER_LEVEL;
+        self.swimming = feet_in_water && WATER_LEVEL - ground > SWIM_DEPTH;
+        let speed_scale = if self.swimming {
+            SWIM_SPEED_SCALE
+        } else if feet_in_water {
+            WADE_SPEED_SCALE
+        } else {
+            1.0
+        };
+
+        // Movement (XZ plane)
+        // Input dir is relative to camera rotation
+        let forward = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin()).normalize();
+        let right = Vec3::new(-self.yaw.sin(), 0.0, self.yaw.cos()).normalize();
+
+        // Clamped rather than normalized so a half-tilted stick walks at half speed
+        let move_vec = (forward * input_dir.z + right * input_dir.x).clamp_length_max(1.0);
+        let target = move_vec * self.speed * speed_scale;
+
+        // Exponential approach to target velocity: same curve at any step size
+        let rate = if move_vec == Vec3::ZERO { self.friction } else { self.acceleration };
+        let blend = 1.0 - (-rate * dt).exp();
+        self.velocity.x += (target.x - self.velocity.x) * blend;
+        self.velocity.z += (target.z - self.velocity.z) * blend;
+
+        // Apply Velocity. Gravity is constant, so integrate it exactly
+        // (x += v*dt - g*dt^2/2) rather than with Euler, which overshoots at low FPS.
+        self.position.x += self.velocity.x * dt;
+        self.position.z += self.velocity.z * dt;
+        if self.swimming {
+            // Weak gravity against buoyancy that lifts the eyes toward the surface, damped
+            // by the water; semi-implicit so it settles instead of bouncing
+            let lift = (WATER_LEVEL + FLOAT_EYE_HEIGHT - self.position.y) * BUOYANCY;
+            self.velocity.y += (lift - self.gravity * SWIM_GRAVITY_SCALE) * dt;
+            self.velocity.y *= (-WATER_DRAG * dt).exp();
+            self.position.y += self.velocity.y * dt;
+        } else {
+            self.position.y += self.velocity.y * dt - 0.5 * self.gravity * dt * dt;
+            self.velocity.y -= self.gravity * dt;
+        }
+
+        // Terrain Collision
+        let terrain = sample_terrain(seed, self.position.x, self.position.z, trails);
+        let terrain_height = terrain.height;
+
+        if self.position.y < terrain_height + self.height {
+            self.position.y = terrain_height + self.height;
+            self.velocity.y = 0.0;
+            self.on_ground = true;
+        } else {
+            self.on_ground = false;
+        }
+
+        // Footsteps: one per stride walked on the ground; none in the air or swimming
+        let feet_above_ground = self.position.y - self.height - terrain_height;
+        if feet_above_ground < FOOTSTEP_GROUND_TOLERANCE && !self.swimming {
+            self.stride += Vec3::new(self.velocity.x, 0.0, self.velocity.z).length() * dt;
+            if self.stride >= self.stride_length {
+                self.stride -= self.stride_length;
+                let wading = self.position.y - self.height < WATER_LEVEL;
+                self.footsteps.push(if wading { Footstep::Wading } else { Footstep::Ground(terrain.biome) });
+            }
+        }
+        let submerged = self.is_submerged();
+        if submerged && !self.was_submerged {
+            self.footsteps.push(Footstep::Splash);
+        }
+        self.was_submerged = submerged;
+    }
+
+    /// Footsteps and splashes since the last call, oldest first
+    pub fn take_footsteps(&mut self) -> Vec<Footstep> {
+        std::mem::take(&mut self.footsteps)
+    }
+
+    /// Floating in water too deep to stand in
+    pub fn is_swimming(&self) -> bool {
+        self.swimming
+    }
+
+    /// Eyes below the water surface (the renderer tints the view)
+    pub fn is_submerged(&self) -> bool {
+        self.position.y < WATER_LEVEL
+    }
+
+    /// Slide out of any colliders the latest `update` walked into (horizontal only, so
+    /// walls stop the player without affecting jumps or landing)
+    pub fn resolve_collisions<'a>(&mut self, colliders: impl IntoIterator<Item = &'a Collider>) {
+        let bottom = self.position.y - self.height;
+        for collider in colliders {
+            if let Some(push) = collider.push_out(self.position, self.radius, bottom, self.position.y) {
+                self.position += push;
+            }
+        }
+    }
+
+    /// Position `alpha` (0..1) of the way from before the latest `update` to now
+    pub fn interpolated_position(&self, alpha: f32) -> Vec3 {
+        self.previous_position.lerp(self.position, alpha)
+    }
+
+    /// Queue a jump for the next physics step (ignored if airborne by then)
+    pub fn jump(&mut self) {
+        if self.on_ground {
+            self.jump_requested = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use croatoan_core::{FixedStep, UPDATE_DT};
+
+    const SEED: u32 = 12345;
+
+    /// Run one `1 / fps` frame's worth of update steps, sliced the way the App does
+    fn run_frame(player: &mut Player, fixed_step: &mut FixedStep, fps: f32) {
+        for _ in 0..fixed_step.advance(1.0 / fps) {
+            player.update(UPDATE_DT, Vec3::ZERO, SEED, None);
+        }
+    }
+
+    /// Land the player, jump, and return the highest point above the landing height
+    fn jump_apex(fps: f32) -> f32 {
+        let mut player = Player::new(Vec3::new(-200.0, 80.0, 40.0));
+        let mut fixed_step = FixedStep::new(UPDATE_DT);
+
+        while !player.on_ground {
+            run_frame(&mut player, &mut fixed_step, fps);
+        }
+        let ground = player.position.y;
+
+        player.jump();
+        let mut apex = ground;
+        for _ in 0..(fps * 2.0) as u32 {
+            run_frame(&mut player, &mut fixed_step, fps);
+            apex = apex.max(player.position.y);
+        }
+        assert!(player.on_ground, "player should have landed again");
+        apex - ground
+    }
+
+    #[test]
+    fn test_jump_apex_framerate_independent() {
+        let expected = 15.0 * 15.0 / (2.0 * 30.0); // v^2 / 2g
+        let at_30 = jump_apex(30.0);
+        let at_144 = jump_apex(144.0);
+
+        assert!((at_30 - at_144).abs() < 0.02, "30 FPS: {}, 144 FPS: {}", at_30, at_144);
+        assert!((at_144 - expected).abs() < 0.02, "apex {} vs expected {}", at_144, expected);
+    }
+
+    #[test]
+    fn test_walls_stop_the_player() {
+        // Box with its west face at x = 0, across the player's path
+        let wall = Collider::Box { center: Vec3::new(2.0, 0.0, 40.0), half_extents: Vec3::new(2.0, 100.0, 2.0), yaw: 0.0 };
+        let mut player = Player::new(Vec3::new(-5.0, 80.0, 40.0));
+        player.set_look(0.0, 0.0); // Facing +X
+
+        for _ in 0..600 {
+            player.update(1.0 / 60.0, Vec3::Z, SEED, None);
+            player.resolve_collisions([&wall]);
+            assert!(player.position.x <= -player.radius + 1e-3, "walked into the wall: {:?}", player.position);
+        }
+        assert!(player.position.x > -player.radius - 0.05, "should end up pressed against the wall");
+    }
+
+    #[test]
+    fn test_floats_in_deep_water() {
+        // Dropped into the sea (~5 m deep here): goes under, then bobs up and floats
+        let mut player = Player::new(Vec3::new(700.0, 6.0, 40.0));
+        let mut went_under = false;
+        for _ in 0..600 {
+            player.update(1.0 / 60.0, Vec3::ZERO, SEED, None);
+            went_under |= player.is_submerged();
+        }
+        assert!(went_under, "a drop from height should dunk the player");
+        assert!(player.is_swimming());
+        assert!(!player.is_submerged(), "should surface: eyes at {}", player.position.y);
+        assert!(player.position.y < WATER_LEVEL + 1.0, "floating too high: {}", player.position.y);
+        assert!(!player.on_ground);
+        assert_eq!(player.take_footsteps(), [Footstep::Splash]);
+
+        // Swimming is slower than walking, and silent underfoot
+        for _ in 0..300 {
+            player.update(1.0 / 60.0, Vec3::Z, SEED, None);
+        }
+        assert!(player.take_footsteps().is_empty());
+        let horizontal = Vec3::new(player.velocity.x, 0.0, player.velocity.z).length();
+        assert!((horizontal - player.speed * SWIM_SPEED_SCALE).abs() < 0.1, "swim speed {}", horizontal);
+    }
+
+    #[test]
+    fn test_footsteps_follow_walking() {
+        let mut player = Player::new(Vec3::new(-200.0, 80.0, 40.0));
+        // Falling and standing still: no steps
+        while !player.on_ground {
+            player.update(1.0 / 60.0, Vec3::ZERO, SEED, None);
+        }
+        for _ in 0..60 {
+            player.update(1.0 / 60.0, Vec3::ZERO, SEED, None);
+        }
+        assert!(player.take_footsteps().is_empty());
+
+        // Two seconds of walking: one step per stride, on the ground underfoot
+        for _ in 0..120 {
+            player.update(1.0 / 60.0, Vec3::Z, SEED, None);
+        }
+        let steps = player.take_footsteps();
+        let walked = 2.0 * player.speed;
+        assert!(steps.len() as f32 <= walked / player.stride_length && steps.len() as f32 > walked / player.stride_length - 3.0, "{} steps", steps.len());
+        let biome = sample_terrain(SEED, player.position.x, player.position.z, None).biome;
+        assert_eq!(steps.last(), Some(&Footstep::Ground(biome)));
+    }
+
+    #[test]
+    fn test_wades_through_shallows() {
+        // Sandbar about 0.6 m under water: stand on it, don't swim
+        let mut player = Player::new(Vec3::new(250.0, 3.0, 40.0));
+        for _ in 0..300 {
+            player.update(1.0 / 60.0, Vec3::ZERO, SEED, None);
+        }
+        assert!(player.on_ground);
+        assert!(!player.is_swimming());
+        assert!(!player.is_submerged());
+    }
+
+    #[test]
+    fn test_interpolated_position() {
+        let mut player = Player::new(Vec3::new(-200.0, 80.0, 40.0));
+        player.update(1.0 / 60.0, Vec3::ZERO, SEED, None);
+        let (before, after) = (player.previous_position, player.position);
+        assert!(after.y < before.y, "should be falling");
+        assert_eq!(player.interpolated_position(0.0), before);
+        assert_eq!(player.interpolated_position(1.0), after);
+        assert!((player.interpolated_position(0.5).y - (before.y + after.y) * 0.5).abs() < 1e-4);
+
+        // Teleports don't smear
+        player.set_position(Vec3::new(5.0, 60.0, 5.0));
+        assert_eq!(player.interpolated_position(0.3), Vec3::new(5.0, 60.0, 5.0));
+    }
+
+    #[test]
+    fn test_look_smoothing() {
+        // Off: 1:1 with the mouse, no update needed
+        let mut raw = Player::new(Vec3::ZERO);
+        let start = raw.yaw;
+        raw.add_look(0.5, 0.2);
+        assert_eq!(raw.yaw, start + 0.5);
+        assert_eq!(raw.pitch, 0.2);
+
+        // On: same glide at 30 and 144 FPS, converging on the target
+        let glide = |fps: f32, seconds: f32| {
+            let mut player = Player::new(Vec3::new(-200.0, 80.0, 40.0));
+            player.look_smoothing = 0.05;
+            let start = player.yaw;
+            player.add_look(0.5, 0.0);
+            assert_eq!(player.yaw, start);
+            for _ in 0..(fps * seconds) as u32 {
+                player.update(1.0 / fps, Vec3::ZERO, SEED, None);
+            }
+            player.yaw - start
+        };
+        assert!((glide(30.0, 0.1) - glide(144.0, 0.1)).abs() < 0.01);
+        assert!((glide(60.0, 1.0) - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_move_distance_framerate_independent() {
+        let travelled = |fps: f32| {
+            let mut player = Player::new(Vec3::new(-200.0, 80.0, 40.0));
+            let start = player.position;
+            let dt = 1.0 / fps;
+            for _ in 0..fps as u32 {
+                player.update(dt, Vec3::Z, SEED, None);
+            }
+            let offset = player.position - start;
+            Vec3::new(offset.x, 0.0, offset.z).length()
+        };
+
+        let at_30 = travelled(30.0);
+        let at_144 = travelled(144.0);
+        assert!((at_30 - at_144).abs() < 0.5, "30 FPS: {}, 144 FPS: {}", at_30, at_144);
+        assert!(at_144 > 8.0 && at_144 < 10.0);
+    }
+}
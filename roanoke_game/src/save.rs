@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Map, Value};
+use crate::chunk_manager::WorldEdit;
+use crate::third_person::CameraMode;
+
+const SAVES_DIR: &str = "saves";
+
+/// Save file format written by this build
+/// 0: seed, position, rotation, inventory (no version field)
+/// 1: + `version`, `world_edits`
+/// 2: + `time_of_day`, `day_count`
+/// 3: + `camera_mode`
+pub const SAVE_VERSION: u32 = 3;
+
+/// Older saves are upgraded by `migrate`, so every field is required here
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SaveData {
+    pub version: u32,
+    pub seed: u32,
+    pub player_pos: [f32; 3],
+    pub player_rot: [f32; 2], // Yaw, Pitch
+    pub inventory: Vec<String>,
+    pub world_edits: Vec<WorldEdit>, // Harvested trees/rocks
+    pub time_of_day: f32, // 0.0 - 24.0
+    pub day_count: u32,
+    pub camera_mode: CameraMode,
+}
+
+/// Upgrade a save's JSON from format `version` to `SAVE_VERSION`, one format at a time
+/// Each step fills in what its format added (or reshapes what it changed), so a save from
+/// any older build deserializes as the current `SaveData`.
+fn migrate(save: &mut Map<String, Value>, version: u32) {
+    if version < 1 {
+        // Nothing harvested yet
+        save.entry("world_edits").or_insert(json!([]));
+    }
+    if version < 2 {
+        // New games start at noon on day 0
+        save.entry("time_of_day").or_insert(json!(12.0));
+        save.entry("day_count").or_insert(json!(0));
+    }
+    if version < 3 {
+        save.entry("camera_mode").or_insert(json!(CameraMode::FirstPerson));
+    }
+    if version < SAVE_VERSION {
+        println!("[LOAD] Upgrading save from format {} to {}", version, SAVE_VERSION);
+    }
+    save.insert("version".to_string(), json!(SAVE_VERSION));
+}
+
+/// Why a game couldn't be saved or loaded, with the file involved
+#[derive(Debug)]
+pub enum SaveError {
+    Io { path: PathBuf, source: std::io::Error },
+    /// Not valid save JSON (or, saving, data that can't be written as JSON)
+    Format { path: PathBuf, source: serde_json::Error },
+    /// Written by a newer build in a format this one can't read
+    Version { path: PathBuf, found: u32 },
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SaveError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            SaveError::Format { path, source } => write!(f, "{}: invalid save data ({})", path.display(), source),
+            SaveError::Version { path, found } => write!(
+                f,
+                "{}: save format {} is newer than this build supports ({})",
+                path.display(),
+                found,
+                SAVE_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveError::Io { source, .. } => Some(source),
+            SaveError::Format { source, .. } => Some(source),
+            SaveError::Version { .. } => None,
+        }
+    }
+}
+
+fn save_path(name: &str) -> PathBuf {
+    Path::new(SAVES_DIR).join(format!("{}.json", name))
+}
+
+pub fn save_game(name: &str, data: &SaveData) -> Result<(), SaveError> {
+    let path = save_path(name);
+    write_save(&path, data)?;
+    println!("[SAVE] Game saved to {}", path.display());
+    Ok(())
+}
+
+pub fn load_game(name: &str) -> Result<SaveData, SaveError> {
+    let data = read_save(&save_path(name))?;
+    println!("[LOAD] Game loaded: Seed {}", data.seed);
+    Ok(data)
+}
+
+fn write_save(path: &Path, data: &SaveData) -> Result<(), SaveError> {
+    let io_error = |source| SaveError::Io { path: path.to_path_buf(), source };
+    let json = serde_json::to_string_pretty(data).map_err(|source| SaveError::Format { path: path.to_path_buf(), source })?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    fs::write(path, json).map_err(io_error)
+}
+
+fn read_save(path: &Path) -> Result<SaveData, SaveError> {
+    let json = fs::read_to_string(path).map_err(|source| SaveError::Io { path: path.to_path_buf(), source })?;
+    parse_save(&json, path)
+}
+
+/// Decode a save file's contents (from `path`, for errors) of any format up to `SAVE_VERSION`
+fn parse_save(json: &str, path: &Path) -> Result<SaveData, SaveError> {
+    let format_error = |source| SaveError::Format { path: path.to_path_buf(), source };
+    let mut value: Value = serde_json::from_str(json).map_err(format_error)?;
+    if let Some(save) = value.as_object_mut() {
+        // Format 0 had no version field
+        let version = save.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version > SAVE_VERSION as u64 {
+            return Err(SaveError::Version { path: path.to_path_buf(), found: version as u32 });
+        }
+        migrate(save, version as u32);
+    }
+    serde_json::from_value(value).map_err(format_error)
+}
+
+pub fn list_saves() -> Vec<String> {
+    let mut saves = Vec::new();
+    if let Ok(entries) = fs::read_dir(SAVES_DIR) {
+        for entry in entries.flatten() {
+            if let Ok(file_type) = entry.file_type() {
+                if file_type.is_file() {
+                    if let Some(name) = entry.path().file_stem() {
+                        if let Some(name_str) = name.to_str() {
+                            saves.push(name_str.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    saves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_manager::{ChunkCoord, InstanceKind};
+
+    fn parse(json: &str) -> SaveData {
+        parse_save(json, Path::new("test.json")).unwrap()
+    }
+
+    #[test]
+    fn test_unversioned_save_migrates() {
+        // Format 0 save, before versions and world edits
+        let json = r#"{"seed":7,"player_pos":[1.0,2.0,3.0],"player_rot":[0.5,0.0],"inventory":["wood"]}"#;
+        let data = parse(json);
+        assert_eq!(data.version, SAVE_VERSION);
+        assert!(data.world_edits.is_empty());
+        assert_eq!(data.inventory, vec!["wood".to_string()]);
+
+        // Edits survive a round trip
+        let edit = WorldEdit::Removed { chunk: ChunkCoord { x: -1, z: 4 }, kind: InstanceKind::Tree, index: 12 };
+        let saved = SaveData { world_edits: vec![edit], ..data };
+        let loaded = parse(&serde_json::to_string(&saved).unwrap());
+        assert_eq!(loaded.world_edits, vec![edit]);
+    }
+
+    #[test]
+    fn test_v1_save_gets_default_clock() {
+        let json = r#"{"version":1,"seed":7,"player_pos":[1.0,2.0,3.0],"player_rot":[0.5,0.0],"inventory":[],
+            "world_edits":[{"Removed":{"chunk":{"x":2,"z":3},"kind":"Rock","index":5}}]}"#;
+        let data = parse(json);
+        assert_eq!(data.version, SAVE_VERSION);
+        assert_eq!((data.time_of_day, data.day_count), (12.0, 0));
+        assert_eq!(data.camera_mode, CameraMode::FirstPerson);
+        assert_eq!(data.world_edits.len(), 1);
+        assert_eq!(data.player_pos, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_save_errors_name_the_file() {
+        let dir = std::env::temp_dir().join(format!("roanoke_saves_{}", std::process::id()));
+        let path = dir.join("game.json");
+        assert!(matches!(read_save(&path), Err(SaveError::Io { .. })));
+
+        let data = SaveData {
+            version: SAVE_VERSION,
+            seed: 7,
+            player_pos: [0.0; 3],
+            player_rot: [0.0; 2],
+            inventory: Vec::new(),
+            world_edits: Vec::new(),
+            time_of_day: 18.5,
+            day_count: 3,
+            camera_mode: CameraMode::ThirdPerson,
+        };
+        write_save(&path, &data).unwrap();
+        let loaded = read_save(&path).unwrap();
+        assert_eq!((loaded.seed, loaded.time_of_day, loaded.day_count), (7, 18.5, 3));
+        assert_eq!(loaded.camera_mode, CameraMode::ThirdPerson);
+
+        fs::write(&path, "{ not json").unwrap();
+        let error = read_save(&path).unwrap_err();
+        assert!(matches!(error, SaveError::Format { .. }));
+        assert!(error.to_string().contains("game.json"), "{}", error);
+
+        write_save(&path, &SaveData { version: SAVE_VERSION + 1, ..data }).unwrap();
+        assert!(matches!(read_save(&path), Err(SaveError::Version { found, .. }) if found == SAVE_VERSION + 1));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
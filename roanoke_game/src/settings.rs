@@ -0,0 +1,79 @@
+use serde::{Serialize, Deserialize};
+use std::fs;
+
+const SETTINGS_PATH: &str = "settings.json";
+
+/// Player preferences, persisted across sessions (independent of save games)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// Mouse-look smoothing time constant in seconds (0 = raw)
+    pub look_smoothing: f32,
+    /// Ambient light floor so nights stay navigable
+    pub min_ambient: f32,
+    /// Scene brightness before tonemapping (1.0 = as rendered)
+    pub exposure: f32,
+    /// Glow around the sun and bright highlights (off saves a few passes on low-end GPUs)
+    pub bloom: bool,
+    /// Draw terrain, rocks and buildings depth-only first so hidden grass and trees are skipped
+    pub depth_prepass: bool,
+    /// Ambient occlusion sampling radius in world units (how far contact shadows reach)
+    pub ssao_radius: f32,
+    /// Ambient occlusion darkening (0 = off)
+    pub ssao_strength: f32,
+    /// Width of the foam band along the shore: water shallower than this (m) foams
+    pub water_foam_width: f32,
+    /// View distance in chunks around the player (1 = 3x3)
+    pub render_distance: i32,
+    /// Chunk rings (from the player's chunk) drawn with full and half terrain detail; further
+    /// chunks get a quarter. Applied at startup
+    pub terrain_lod_rings: [i32; 2],
+    /// Chunk generation worker threads (0 = one per core); applied at startup
+    pub generation_threads: usize,
+    /// Most GPU memory loaded chunks may use, in MB (0 = no cap); past it the farthest chunks
+    /// unload whatever the render distance. Applied at startup
+    pub chunk_memory_budget_mb: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            look_smoothing: 0.0,
+            min_ambient: 0.8,
+            exposure: 1.0,
+            bloom: true,
+            depth_prepass: true,
+            ssao_radius: 1.5,
+            ssao_strength: 1.0,
+            water_foam_width: 1.5,
+            render_distance: 2,
+            terrain_lod_rings: [1, 2],
+            generation_threads: 0,
+            chunk_memory_budget_mb: 0,
+        }
+    }
+}
+
+impl Settings {
+    /// Load settings, falling back to defaults if the file is missing or invalid
+    pub fn load() -> Self {
+        match fs::read_to_string(SETTINGS_PATH) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                println!("[SETTINGS] Invalid {}: {}, using defaults", SETTINGS_PATH, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(e) = fs::write(SETTINGS_PATH, json) {
+                    println!("[SETTINGS] Failed to write {}: {}", SETTINGS_PATH, e);
+                }
+            }
+            Err(e) => println!("[SETTINGS] Failed to serialize: {}", e),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use croatoan_render::BuildingVertex;
+use croatoan_wfc::raycast_terrain;
+use croatoan_wfc::trails::TrailNetwork;
+use glam::Vec3;
+use serde::{Serialize, Deserialize};
+use crate::collision::Collider;
+
+/// Eye relative to the player's eye in third person (x right, y up, z behind)
+pub const THIRD_PERSON_OFFSET: Vec3 = Vec3::new(0.0, 0.6, 4.0);
+/// How quickly the third person camera catches up with the player (1/s, see `Camera::follow`)
+pub const THIRD_PERSON_STIFFNESS: f32 = 12.0;
+/// Kept between the camera and whatever it's pulled in front of, so the near plane doesn't clip it
+const CAMERA_MARGIN: f32 = 0.3;
+
+/// Player model (capsule, origin at the feet)
+const MODEL_RADIUS: f32 = 0.35;
+const MODEL_COLOR: [f32; 3] = [0.32, 0.22, 0.14]; // Brown coat
+const MODEL_SEGMENTS: u32 = 16;
+const MODEL_RINGS: u32 = 6; // Per hemisphere
+
+/// Where the game camera sits (toggled with V, kept in the save)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CameraMode {
+    /// At the player's eyes
+    #[default]
+    FirstPerson,
+    /// Behind and above the player, who is drawn
+    ThirdPerson,
+}
+
+impl CameraMode {
+    pub fn toggle(self) -> Self {
+        match self {
+            CameraMode::FirstPerson => CameraMode::ThirdPerson,
+            CameraMode::ThirdPerson => CameraMode::FirstPerson,
+        }
+    }
+}
+
+/// Pull a third person camera at `desired` in toward `pivot` (the player's eye) so terrain,
+/// buildings and trunks between them don't hide the player
+pub fn collide_camera<'a>(seed: u32, pivot: Vec3, desired: Vec3, colliders: impl Iterator<Item = &'a Collider>, trails: Option<&TrailNetwork>) -> Vec3 {
+    let to_camera = desired - pivot;
+    let distance = to_camera.length();
+    if distance < 1e-4 {
+        return desired;
+    }
+    let dir = to_camera / distance;
+
+    let mut clear = distance;
+    if let Some(hit) = raycast_terrain(seed, pivot, dir, distance + CAMERA_MARGIN, trails) {
+        clear = clear.min((hit - pivot).length() - CAMERA_MARGIN);
+    }
+    for collider in colliders {
+        if let Some(hit) = collider.ray_hit(pivot, dir, distance + CAMERA_MARGIN) {
+            clear = clear.min(hit - CAMERA_MARGIN);
+        }
+    }
+    pivot + dir * clear.max(0.0)
+}
+
+/// Capsule standing `height` tall on its origin, for the player model in third person
+pub fn player_capsule(height: f32) -> (Vec<BuildingVertex>, Vec<u32>) {
+    let radius = MODEL_RADIUS.min(height * 0.5);
+    let mut vertices = Vec::new();
+    // Rings run from the bottom pole to the top one; each hemisphere's centre is a radius in
+    // from its end, and the straight middle is the gap between the two equator rings
+    let centers = [radius, height - radius];
+    for ring in 0..=MODEL_RINGS * 2 + 1 {
+        let (hemisphere, step) = if ring <= MODEL_RINGS { (0, ring) } else { (1, ring - 1) };
+        let polar = step as f32 / (MODEL_RINGS * 2) as f32 * std::f32::consts::PI; // 0 at the bottom
+        let (ring_radius, y) = (polar.sin(), -polar.cos());
+        for segment in 0..=MODEL_SEGMENTS {
+            let angle = segment as f32 / MODEL_SEGMENTS as f32 * std::f32::consts::TAU;
+            let normal = Vec3::new(angle.cos() * ring_radius, y, angle.sin() * ring_radius);
+            let position = normal * radius + Vec3::Y * centers[hemisphere];
+            vertices.push(BuildingVertex {
+                position: position.to_array(),
+                normal: normal.to_array(),
+                uv: [segment as f32 / MODEL_SEGMENTS as f32, position.y / height],
+                color: MODEL_COLOR,
+                emissive: [0.0; 3],
+            });
+        }
+    }
+
+    let row = MODEL_SEGMENTS + 1;
+    let mut indices = Vec::new();
+    for ring in 0..MODEL_RINGS * 2 + 1 {
+        for segment in 0..MODEL_SEGMENTS {
+            let a = ring * row + segment;
+            let b = a + row;
+            // Counter-clockwise seen from outside
+            indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+    (vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_stays_in_front_of_walls() {
+        let wall = Collider::Box { center: Vec3::new(0.0, 52.0, 3.0), half_extents: Vec3::new(5.0, 3.0, 0.5), yaw: 0.0 };
+        // High above the ground so only the wall is in the way
+        let pivot = Vec3::new(0.0, 50.0, 0.0);
+        let camera = collide_camera(12345, pivot, Vec3::new(0.0, 50.0, 6.0), [wall].iter(), None);
+        assert!((camera.z - (2.5 - CAMERA_MARGIN)).abs() < 1e-3, "{:?}", camera);
+        // Nothing in the way: unchanged
+        let open = collide_camera(12345, pivot, Vec3::new(0.0, 50.0, -4.0), [wall].iter(), None);
+        assert_eq!(open, Vec3::new(0.0, 50.0, -4.0));
+    }
+
+    #[test]
+    fn test_capsule_spans_the_player() {
+        let (vertices, indices) = player_capsule(1.8);
+        let heights = vertices.iter().map(|v| v.position[1]);
+        let (low, high) = heights.fold((f32::MAX, f32::MIN), |(low, high), y| (low.min(y), high.max(y)));
+        assert!(low.abs() < 1e-5 && (high - 1.8).abs() < 1e-5, "{} {}", low, high);
+        assert_eq!(indices.len() % 3, 0);
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+        // Normals point outward from the axis
+        for v in &vertices {
+            let side = Vec3::new(v.position[0], 0.0, v.position[2]);
+            assert!(side.dot(Vec3::new(v.normal[0], 0.0, v.normal[2])) >= -1e-5);
+        }
+    }
+}
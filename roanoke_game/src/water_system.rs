@@ -1,598 +1,1048 @@
-use wgpu;
-use wgpu::util::DeviceExt;
-use glam::{Vec2, Vec3, Mat4, Vec4};
-use bytemuck::{Pod, Zeroable};
-use std::mem;
-
-// --- Uniforms ---
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct WaterUniforms {
-    pub time: f32,
-    pub delta_time: f32,
-    pub wind_direction: [f32; 2],
-    pub wind_speed: f32,
-    pub amplitude: f32,
-    pub choppiness: f32,
-    pub size: f32,
-    pub _padding: [f32; 1], // Align to 16 bytes
-}
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct CameraUniform {
-    pub view_proj: [[f32; 4]; 4],
-    pub position: [f32; 3],
-    pub _padding: f32,
-}
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct WaterMaterial {
-    pub deep_color: [f32; 4],
-    pub shallow_color: [f32; 4],
-    pub foam_color: [f32; 4],
-    pub smoothness: f32,
-    pub metallic: f32,
-    pub _padding: [f32; 2],
-}
-
-// --- Water System ---
-
-pub struct WaterSystem {
-    compute_pipeline: wgpu::ComputePipeline,
-    render_pipeline: wgpu::RenderPipeline,
-    
-    compute_bind_group: wgpu::BindGroup,
-    render_bind_group_0: wgpu::BindGroup, // Camera
-    render_bind_group_1: wgpu::BindGroup, // Material + Textures
-    
-    uniform_buffer: wgpu::Buffer,
-    camera_buffer: wgpu::Buffer,
-    material_buffer: wgpu::Buffer,
-    
-    // Textures / Buffers
-    h0_texture: wgpu::Texture,
-    hkt_buffer: wgpu::Buffer, // Storage buffer for H(k,t)
-    
-    displacement_texture: wgpu::Texture,
-    normal_texture: wgpu::Texture,
-    
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    num_indices: u32,
-    
-    uniforms: WaterUniforms,
-    grid_size: u32,
-}
-
-impl WaterSystem {
-    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
-        let grid_size = 256;
-        let patch_size = 256.0; // Meters
-        
-        // 1. Create Buffers & Textures
-        
-        // Uniforms
-        let uniforms = WaterUniforms {
-            time: 0.0,
-            delta_time: 0.0,
-            wind_direction: [-1.0, 0.0], // West (towards shore)
-            wind_speed: 5.0,
-            amplitude: 0.2, // Gentle waves
-            choppiness: 1.0,
-            size: patch_size,
-            _padding: [0.0],
-        };
-        
-        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Water Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[uniforms]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let camera_uniform = CameraUniform {
-            view_proj: Mat4::IDENTITY.to_cols_array_2d(),
-            position: [0.0; 3],
-            _padding: 0.0,
-        };
-        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Water Camera Buffer"),
-            contents: bytemuck::cast_slice(&[camera_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let material_uniform = WaterMaterial {
-            deep_color: [0.0, 0.1, 0.4, 1.0],
-            shallow_color: [0.0, 0.4, 0.6, 1.0],
-            foam_color: [1.0, 1.0, 1.0, 1.0],
-            smoothness: 0.9,
-            metallic: 0.0,
-            _padding: [0.0; 2],
-        };
-        let material_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Water Material Buffer"),
-            contents: bytemuck::cast_slice(&[material_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        // Textures
-        let texture_size = wgpu::Extent3d {
-            width: grid_size,
-            height: grid_size,
-            depth_or_array_layers: 1,
-        };
-
-        // H0 (Initial Spectrum) - For now just empty/noise
-        let h0_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("H0 Texture"),
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rg32Float,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST, // Read only in compute
-            view_formats: &[],
-        });
-
-        // Hkt Buffer (Intermediate)
-        let hkt_buffer_size = (grid_size * grid_size) as u64 * 8; // vec2<f32>
-        let hkt_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Hkt Buffer"),
-            size: hkt_buffer_size,
-            usage: wgpu::BufferUsages::STORAGE,
-            mapped_at_creation: false,
-        });
-
-        // Output Textures (Storage + Sampled)
-        let displacement_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Displacement Texture"),
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba32Float,
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        let normal_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Normal Texture"),
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba32Float,
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        // Butterfly Texture (Placeholder)
-        let butterfly_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Butterfly Texture"),
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba32Float,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        // 2. Create Grid Mesh
-        let mut vertices = Vec::new();
-        let mut indices = Vec::new();
-        
-        for y in 0..grid_size {
-            for x in 0..grid_size {
-                let u = x as f32 / grid_size as f32;
-                let v = y as f32 / grid_size as f32;
-                // Position is just flat plane, displaced in shader
-                // Centered around 0,0
-                let px = (u - 0.5) * patch_size;
-                let pz = (v - 0.5) * patch_size;
-                
-                vertices.push(px);
-                vertices.push(0.0);
-                vertices.push(pz);
-                
-                vertices.push(u);
-                vertices.push(v);
-            }
-        }
-        
-        for y in 0..grid_size - 1 {
-            for x in 0..grid_size - 1 {
-                let tl = y * grid_size + x;
-                let tr = tl + 1;
-                let bl = (y + 1) * grid_size + x;
-                let br = bl + 1;
-                
-                indices.push(tl);
-                indices.push(bl);
-                indices.push(tr);
-                
-                indices.push(tr);
-                indices.push(bl);
-                indices.push(br);
-            }
-        }
-        
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Water Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Water Index Buffer"),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        // 3. Compute Pipeline
-        let compute_shader = device.create_shader_module(wgpu::include_wgsl!("../../assets/shaders/water_compute.wgsl"));
-        
-        let compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Water Compute Bind Group Layout"),
-            entries: &[
-                // Uniforms
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                // H0 Texture
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // Hkt Buffer
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                // Butterfly Texture
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // Output Displacement (Storage Texture)
-                wgpu::BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rgba32Float,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                // Output Normal (Storage Texture)
-                wgpu::BindGroupLayoutEntry {
-                    binding: 5,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rgba32Float,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-        let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Water Compute Pipeline Layout"),
-            bind_group_layouts: &[&compute_bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Water Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &compute_shader,
-            entry_point: "compute_displacement", // Using the simplified kernel for now
-        });
-
-        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Water Compute Bind Group"),
-            layout: &compute_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: uniform_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&h0_texture.create_view(&Default::default())),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: hkt_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::TextureView(&butterfly_texture.create_view(&Default::default())),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::TextureView(&displacement_texture.create_view(&Default::default())),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 5,
-                    resource: wgpu::BindingResource::TextureView(&normal_texture.create_view(&Default::default())),
-                },
-            ],
-        });
-
-        // 4. Render Pipeline
-        let render_shader = device.create_shader_module(wgpu::include_wgsl!("../../assets/shaders/water.wgsl"));
-
-        let render_bind_group_layout_0 = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Water Render Bind Group Layout 0 (Camera)"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-        let render_bind_group_layout_1 = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Water Render Bind Group Layout 1 (Material)"),
-            entries: &[
-                // Material Uniform
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                // Displacement Texture
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // Displacement Sampler
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
-                    count: None,
-                },
-                // Normal Texture
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // Normal Sampler
-                wgpu::BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
-                    count: None,
-                },
-            ],
-        });
-
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Water Render Pipeline Layout"),
-            bind_group_layouts: &[&render_bind_group_layout_0, &render_bind_group_layout_1],
-            push_constant_ranges: &[],
-        });
-
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Water Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &render_shader,
-                entry_point: "vs_main",
-                buffers: &[
-                    wgpu::VertexBufferLayout {
-                        array_stride: 20, // 3 pos + 2 uv * 4 bytes
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[
-                            wgpu::VertexAttribute {
-                                format: wgpu::VertexFormat::Float32x3,
-                                offset: 0,
-                                shader_location: 0,
-                            },
-                            wgpu::VertexAttribute {
-                                format: wgpu::VertexFormat::Float32x2,
-                                offset: 12,
-                                shader_location: 1,
-                            },
-                        ],
-                    },
-                ],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &render_shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None, // Don't cull water
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
-
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
-
-        let render_bind_group_0 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Water Render Bind Group 0"),
-            layout: &render_bind_group_layout_0,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: camera_buffer.as_entire_binding(),
-                },
-            ],
-        });
-
-        let render_bind_group_1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Water Render Bind Group 1"),
-            layout: &render_bind_group_layout_1,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: material_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&displacement_texture.create_view(&Default::default())),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::TextureView(&normal_texture.create_view(&Default::default())),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-        });
-
-        Self {
-            compute_pipeline,
-            render_pipeline,
-            compute_bind_group,
-            render_bind_group_0,
-            render_bind_group_1,
-            uniform_buffer,
-            camera_buffer,
-            material_buffer,
-            h0_texture,
-            hkt_buffer,
-            displacement_texture,
-            normal_texture,
-            vertex_buffer,
-            index_buffer,
-            num_indices: indices.len() as u32,
-            uniforms,
-            grid_size: grid_size,
-        }
-    }
-
-    pub fn update(&mut self, queue: &wgpu::Queue, time: f32, delta_time: f32) {
-        self.uniforms.time = time;
-        self.uniforms.delta_time = delta_time;
-        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniforms]));
-    }
-
-    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder) {
-        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: Some("Water Compute Pass"),
-            timestamp_writes: None,
-        });
-        cpass.set_pipeline(&self.compute_pipeline);
-        cpass.set_bind_group(0, &self.compute_bind_group, &[]);
-        // Dispatch 16x16 workgroups of 16x16 threads = 256x256 threads
-        cpass.dispatch_workgroups(self.grid_size / 16, self.grid_size / 16, 1);
-    }
-
-    pub fn render(&self, _encoder: &mut wgpu::CommandEncoder, _view: &wgpu::TextureView, _depth_view: &wgpu::TextureView, _camera_view_proj: [[f32; 4]; 4], _camera_pos: [f32; 3]) {
-        // Update Camera Buffer (needs to be done before render pass, but we can't write to buffer inside render pass)
-        // Ideally this is done in update(), but we need camera info.
-        // For now, let's assume the user calls a separate update_camera() or we use a staging buffer.
-        // Actually, we can use queue.write_buffer here if we have reference to queue, but we only have encoder.
-        // So we'll assume the camera buffer is updated elsewhere or we add a method.
-    }
-    
-    pub fn update_camera(&self, queue: &wgpu::Queue, view_proj: [[f32; 4]; 4], position: [f32; 3]) {
-        let camera_uniform = CameraUniform {
-            view_proj,
-            position,
-            _padding: 0.0,
-        };
-        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
-    }
-    
-    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
-        rpass.set_pipeline(&self.render_pipeline);
-        rpass.set_bind_group(0, &self.render_bind_group_0, &[]);
-        rpass.set_bind_group(1, &self.render_bind_group_1, &[]);
-        rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        rpass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        rpass.draw_indexed(0..self.num_indices, 0, 0..1);
-    }
-}
+use wgpu;
+use wgpu::util::DeviceExt;
+use glam::{Vec2, Vec3, Mat4, Vec4};
+use bytemuck::{Pod, Zeroable};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::f32::consts::PI;
+use std::mem;
+use croatoan_render::{RenderOrder, TransparentQueue};
+
+// --- Uniforms ---
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct WaterUniforms {
+    pub time: f32,
+    pub delta_time: f32,
+    pub wind_direction: [f32; 2],
+    pub wind_speed: f32,
+    pub amplitude: f32, // Approximate RMS wave height (m)
+    pub choppiness: f32,
+    pub size: f32,
+    pub _padding: [f32; 1], // Align to 16 bytes
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct CameraUniform {
+    pub view_proj: [[f32; 4]; 4], // 64 bytes (0-64)
+    pub position: [f32; 3],       // 12 bytes (64-76)
+    pub fog_start: f32,           // 4 bytes (76-80)
+    pub fog_color: [f32; 3],      // 12 bytes (80-92)
+    pub fog_end: f32,             // 4 bytes (92-96)
+    pub tile_origin: [f32; 2],    // 8 bytes (96-104) - world XZ corner of the tile grid
+    pub tile_size: f32,           // 4 bytes (104-108)
+    pub tiles_per_side: u32,      // 4 bytes (108-112)
+    pub inv_view_proj: [[f32; 4]; 4], // 64 bytes (112-176) - reconstructs scene positions from depth
+    pub light_dir: [f32; 3],      // 12 bytes (176-188) - key light, direction light travels
+    pub _padding: f32,            // 4 bytes (188-192)
+    pub light_color: [f32; 3],    // 12 bytes (192-204)
+    pub _padding2: f32,           // 4 bytes (204-208) -> Total 208 bytes
+}
+
+/// Water tiles drawn around the camera in each direction (5x5 tiles of 256m covers the 600m fog)
+const TILE_RADIUS: i32 = 2;
+
+/// Quads per side of one water tile mesh (2m spacing on a 256m tile)
+const MESH_RESOLUTION: u32 = 128;
+
+/// Fixed seed for the wave spectrum, so the sea looks the same every run
+const SPECTRUM_SEED: u64 = 0x0CEA_0CEA;
+
+const GRAVITY: f32 = 9.81;
+
+/// Key light until `set_light` is called: a high midday sun
+const DEFAULT_LIGHT: (Vec3, Vec3) = (Vec3::new(-0.5, -0.8, -0.3), Vec3::new(1.4, 1.3, 1.1));
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct WaterMaterial {
+    pub deep_color: [f32; 4],
+    pub shallow_color: [f32; 4],
+    pub foam_color: [f32; 4],
+    pub smoothness: f32,
+    pub metallic: f32,
+    pub foam_width: f32, // Shoreline foam band: water shallower than this (m) foams
+    pub _padding: f32,
+}
+
+// --- Water System ---
+
+/// Ocean surface: a sea-level (y = 0) plane tiled around the camera, displaced by a compute pass
+///
+/// Per frame: `set_depth_view` + `update` + `update_camera` + `dispatch` before the main pass,
+/// then `queue_draws` into the transparent pass right after the opaque scene (terrain,
+/// vegetation, rocks, buildings). That pass attaches the scene depth read-only: the water depth-tests against
+/// the scene to hide the seabed, and also samples it to foam along the shoreline. Like the
+/// other scene shaders it applies the distance fog itself, so pass it the same fog parameters
+/// as the terrain; anything drawn after it (UI) sits on top of the fogged result.
+pub struct WaterSystem {
+    spectrum_pipeline: wgpu::ComputePipeline,
+    fft_horizontal_pipeline: wgpu::ComputePipeline,
+    fft_vertical_pipeline: wgpu::ComputePipeline,
+    resolve_pipeline: wgpu::ComputePipeline,
+    render_pipeline: wgpu::RenderPipeline,
+    
+    compute_bind_group: wgpu::BindGroup,
+    spectrum_bind_groups: [wgpu::BindGroup; 2], // Ping-pong: [0] reads A/writes B, [1] the reverse
+    fft_stage_bind_group: wgpu::BindGroup,
+    fft_stage_stride: u32,
+    fft_stages: u32,
+    render_bind_group_0: wgpu::BindGroup, // Camera
+    render_bind_group_1: wgpu::BindGroup, // Material + Textures + Scene Depth
+    render_bind_group_layout_1: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    depth_view_id: wgpu::Id<wgpu::TextureView>, // Bound scene depth (rebind when it's recreated)
+    
+    uniform_buffer: wgpu::Buffer,
+    camera_buffer: wgpu::Buffer,
+    material_buffer: wgpu::Buffer,
+    
+    // Textures / Buffers
+    displacement_texture: wgpu::Texture,
+    normal_texture: wgpu::Texture,
+    
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+    
+    uniforms: WaterUniforms,
+    material: WaterMaterial,
+    grid_size: u32,
+    patch_size: f32,
+    light: (Vec3, Vec3), // Key light (direction light travels, color) for the highlights
+}
+
+impl WaterSystem {
+    /// `depth_view` is the main scene depth (with `sample_count` samples)
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat, sample_count: u32, depth_view: &wgpu::TextureView) -> Self {
+        let grid_size = 256;
+        let patch_size = 256.0; // Meters
+        
+        // 1. Create Buffers & Textures
+        
+        // Uniforms
+        let uniforms = WaterUniforms {
+            time: 0.0,
+            delta_time: 0.0,
+            wind_direction: [-1.0, 0.0], // West (towards shore)
+            wind_speed: 5.0,
+            amplitude: 0.2, // Gentle waves
+            choppiness: 1.0,
+            size: patch_size,
+            _padding: [0.0],
+        };
+        
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Water Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniforms]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let camera_uniform = CameraUniform {
+            view_proj: Mat4::IDENTITY.to_cols_array_2d(),
+            position: [0.0; 3],
+            fog_start: 200.0,
+            fog_color: [0.5, 0.6, 0.7],
+            fog_end: 600.0,
+            tile_origin: tile_origin(Vec3::ZERO, patch_size),
+            tile_size: patch_size,
+            tiles_per_side: (TILE_RADIUS * 2 + 1) as u32,
+            inv_view_proj: Mat4::IDENTITY.to_cols_array_2d(),
+            light_dir: DEFAULT_LIGHT.0.to_array(),
+            _padding: 0.0,
+            light_color: DEFAULT_LIGHT.1.to_array(),
+            _padding2: 0.0,
+        };
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Water Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let material_uniform = WaterMaterial {
+            deep_color: [0.0, 0.1, 0.4, 1.0],
+            shallow_color: [0.0, 0.4, 0.6, 1.0],
+            foam_color: [1.0, 1.0, 1.0, 1.0],
+            smoothness: 0.9,
+            metallic: 0.0,
+            foam_width: 1.5,
+            _padding: 0.0,
+        };
+        let material_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Water Material Buffer"),
+            contents: bytemuck::cast_slice(&[material_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Textures
+        let texture_size = wgpu::Extent3d {
+            width: grid_size,
+            height: grid_size,
+            depth_or_array_layers: 1,
+        };
+
+        // H0 (Initial Spectrum), built once on the CPU from the wind and amplitude; only used
+        // through the compute bind group, which keeps it alive
+        let h0_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("H0 Texture"),
+                size: texture_size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST, // Read only in compute
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&phillips_spectrum(&uniforms, grid_size, SPECTRUM_SEED)),
+        );
+
+        // Ping-pong spectrum buffers for the IFFT passes (two vec4s of packed signals per texel);
+        // only used through `spectrum_bind_groups`, which keep them alive
+        let spectrum_buffer_size = (grid_size * grid_size) as u64 * 2 * mem::size_of::<[f32; 4]>() as u64;
+        let spectrum_buffers = [0, 1].map(|i| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(if i == 0 { "Water Spectrum Buffer A" } else { "Water Spectrum Buffer B" }),
+            size: spectrum_buffer_size,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        }));
+
+        // Output Textures (Storage + Sampled)
+        let displacement_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Displacement Texture"),
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let normal_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Normal Texture"),
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        // Butterfly Texture: twiddle factors and input indices, one column per FFT stage
+        let fft_stages = grid_size.trailing_zeros();
+        let butterfly_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Butterfly Texture"),
+                size: wgpu::Extent3d {
+                    width: fft_stages,
+                    height: grid_size,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&butterfly_indices(grid_size)),
+        );
+
+        // 2. Create Grid Mesh (one tile; instanced around the camera)
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let verts_per_side = MESH_RESOLUTION + 1;
+        
+        for y in 0..verts_per_side {
+            for x in 0..verts_per_side {
+                let u = x as f32 / MESH_RESOLUTION as f32;
+                let v = y as f32 / MESH_RESOLUTION as f32;
+                // Position is just flat plane, displaced in shader
+                // Spans exactly one tile from its corner, so neighbouring tiles share edges
+                let px = u * patch_size;
+                let pz = v * patch_size;
+                
+                vertices.push(px);
+                vertices.push(0.0);
+                vertices.push(pz);
+                
+                vertices.push(u);
+                vertices.push(v);
+            }
+        }
+        
+        for y in 0..MESH_RESOLUTION {
+            for x in 0..MESH_RESOLUTION {
+                let tl = y * verts_per_side + x;
+                let tr = tl + 1;
+                let bl = (y + 1) * verts_per_side + x;
+                let br = bl + 1;
+                
+                indices.push(tl);
+                indices.push(bl);
+                indices.push(tr);
+                
+                indices.push(tr);
+                indices.push(bl);
+                indices.push(br);
+            }
+        }
+        
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Water Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Water Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // 3. Compute Pipelines (spectrum -> IFFT stages -> resolve)
+        let compute_shader = device.create_shader_module(wgpu::include_wgsl!("../../assets/shaders/water_compute.wgsl"));
+
+        let read_texture = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let write_texture = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: wgpu::TextureFormat::Rgba32Float,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let storage_buffer = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Water Compute Bind Group Layout"),
+            entries: &[
+                // Uniforms
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                read_texture(1),  // H0 Texture
+                read_texture(2),  // Butterfly Texture
+                write_texture(3), // Output Displacement
+                write_texture(4), // Output Normal
+            ],
+        });
+
+        // Ping-pong: read one spectrum buffer, write the other
+        let spectrum_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Water Spectrum Bind Group Layout"),
+            entries: &[storage_buffer(0, true), storage_buffer(1, false)],
+        });
+
+        // FFT stage index, one 256-byte aligned slot per stage (selected with a dynamic offset)
+        let stage_stride = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let mut stage_data = vec![0u8; (stage_stride * fft_stages as u64) as usize];
+        for stage in 0..fft_stages {
+            let offset = (stage as u64 * stage_stride) as usize;
+            stage_data[offset..offset + 4].copy_from_slice(&stage.to_le_bytes());
+        }
+        let fft_stage_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Water FFT Stage Buffer"),
+            contents: &stage_data,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let fft_stage_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Water FFT Stage Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(4),
+                },
+                count: None,
+            }],
+        });
+
+        let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Water Compute Pipeline Layout"),
+            bind_group_layouts: &[&compute_bind_group_layout, &spectrum_bind_group_layout, &fft_stage_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let compute_pipeline = |entry_point| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&compute_pipeline_layout),
+            module: &compute_shader,
+            entry_point,
+        });
+        let spectrum_pipeline = compute_pipeline("generate_spectrum");
+        let fft_horizontal_pipeline = compute_pipeline("fft_horizontal");
+        let fft_vertical_pipeline = compute_pipeline("fft_vertical");
+        let resolve_pipeline = compute_pipeline("resolve");
+
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Water Compute Bind Group"),
+            layout: &compute_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&h0_texture.create_view(&Default::default())),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&butterfly_texture.create_view(&Default::default())),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&displacement_texture.create_view(&Default::default())),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&normal_texture.create_view(&Default::default())),
+                },
+            ],
+        });
+
+        // [0]: read A, write B; [1]: read B, write A
+        let spectrum_bind_groups = [(0, 1), (1, 0)].map(|(read, write): (usize, usize)| device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Water Spectrum Bind Group"),
+            layout: &spectrum_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: spectrum_buffers[read].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: spectrum_buffers[write].as_entire_binding(),
+                },
+            ],
+        }));
+
+        let fft_stage_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Water FFT Stage Bind Group"),
+            layout: &fft_stage_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &fft_stage_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(4),
+                }),
+            }],
+        });
+
+        // 4. Render Pipeline
+        // The scene depth binding's type depends on MSAA (textureLoad takes a level or a sample index)
+        let mut render_source = include_str!("../../assets/shaders/water.wgsl").to_string();
+        if sample_count > 1 {
+            render_source = render_source.replace("var scene_depth: texture_2d<f32>", "var scene_depth: texture_multisampled_2d<f32>");
+        }
+        let render_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Water Shader"),
+            source: wgpu::ShaderSource::Wgsl(render_source.into()),
+        });
+
+        let render_bind_group_layout_0 = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Water Render Bind Group Layout 0 (Camera)"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let render_bind_group_layout_1 = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Water Render Bind Group Layout 1 (Material)"),
+            entries: &[
+                // Material Uniform
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Displacement Texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Displacement Sampler
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+                // Normal Texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Normal Sampler
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+                // Scene Depth (shoreline foam)
+                // Bound as unfilterable float rather than Depth: GL can't textureLoad depth textures
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: sample_count > 1,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Water Render Pipeline Layout"),
+            bind_group_layouts: &[&render_bind_group_layout_0, &render_bind_group_layout_1],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Water Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &render_shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 20, // 3 pos + 2 uv * 4 bytes
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x3,
+                                offset: 0,
+                                shader_location: 0,
+                            },
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x2,
+                                offset: 12,
+                                shader_location: 1,
+                            },
+                        ],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &render_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: format,
+                    blend: RenderOrder::Transparent.blend(),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None, // Don't cull water
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            // Never writes depth: the attachment is read-only while it's sampled
+            depth_stencil: Some(RenderOrder::Transparent.depth_stencil()),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let render_bind_group_0 = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Water Render Bind Group 0"),
+            layout: &render_bind_group_layout_0,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let render_bind_group_1 = create_material_bind_group(
+            device,
+            &render_bind_group_layout_1,
+            &material_buffer,
+            &displacement_texture,
+            &normal_texture,
+            &sampler,
+            depth_view,
+        );
+
+        Self {
+            spectrum_pipeline,
+            fft_horizontal_pipeline,
+            fft_vertical_pipeline,
+            resolve_pipeline,
+            render_pipeline,
+            compute_bind_group,
+            spectrum_bind_groups,
+            fft_stage_bind_group,
+            fft_stage_stride: stage_stride as u32,
+            fft_stages,
+            render_bind_group_0,
+            render_bind_group_1,
+            render_bind_group_layout_1,
+            sampler,
+            depth_view_id: depth_view.global_id(),
+            uniform_buffer,
+            camera_buffer,
+            material_buffer,
+            displacement_texture,
+            normal_texture,
+            vertex_buffer,
+            index_buffer,
+            num_indices: indices.len() as u32,
+            uniforms,
+            material: material_uniform,
+            grid_size: grid_size,
+            patch_size,
+            light: DEFAULT_LIGHT,
+        }
+    }
+
+    pub fn update(&mut self, queue: &wgpu::Queue, time: f32, delta_time: f32) {
+        self.uniforms.time = time;
+        self.uniforms.delta_time = delta_time;
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniforms]));
+        queue.write_buffer(&self.material_buffer, 0, bytemuck::cast_slice(&[self.material]));
+    }
+
+    /// Width (m) of the foam band where the water meets the shore (applied on the next `update`)
+    pub fn set_foam_width(&mut self, meters: f32) {
+        self.material.foam_width = meters.max(0.0);
+    }
+
+    /// Rebind the scene depth after the depth texture was recreated (window resize)
+    /// No-op while it's the view already bound
+    pub fn set_depth_view(&mut self, device: &wgpu::Device, depth_view: &wgpu::TextureView) {
+        if depth_view.global_id() == self.depth_view_id {
+            return;
+        }
+        self.render_bind_group_1 = create_material_bind_group(
+            device,
+            &self.render_bind_group_layout_1,
+            &self.material_buffer,
+            &self.displacement_texture,
+            &self.normal_texture,
+            &self.sampler,
+            depth_view,
+        );
+        self.depth_view_id = depth_view.global_id();
+    }
+
+    /// Record the ocean simulation: spectrum, row and column IFFTs, then the output maps
+    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Water Compute Pass"),
+            timestamp_writes: None,
+        });
+        cpass.set_bind_group(0, &self.compute_bind_group, &[]);
+        cpass.set_bind_group(2, &self.fft_stage_bind_group, &[0]);
+        // Dispatch 16x16 workgroups of 16x16 threads = 256x256 threads
+        let groups = self.grid_size / 16;
+
+        // H(k, t) into buffer A
+        cpass.set_pipeline(&self.spectrum_pipeline);
+        cpass.set_bind_group(1, &self.spectrum_bind_groups[1], &[]);
+        cpass.dispatch_workgroups(groups, groups, 1);
+
+        // 2 * log2(N) stages ping-ponging A -> B -> A ..., ending back in A
+        let mut pass = 0;
+        for pipeline in [&self.fft_horizontal_pipeline, &self.fft_vertical_pipeline] {
+            cpass.set_pipeline(pipeline);
+            for stage in 0..self.fft_stages {
+                cpass.set_bind_group(1, &self.spectrum_bind_groups[pass % 2], &[]);
+                cpass.set_bind_group(2, &self.fft_stage_bind_group, &[stage * self.fft_stage_stride]);
+                cpass.dispatch_workgroups(groups, groups, 1);
+                pass += 1;
+            }
+        }
+
+        // Read A into the displacement/normal maps
+        cpass.set_pipeline(&self.resolve_pipeline);
+        cpass.set_bind_group(1, &self.spectrum_bind_groups[0], &[]);
+        cpass.dispatch_workgroups(groups, groups, 1);
+    }
+
+    /// Key light for the specular glint (the sun, or the moon at night); applied on the next
+    /// `update_camera`. `direction` is the way the light travels, as for the terrain.
+    pub fn set_light(&mut self, direction: Vec3, color: Vec3) {
+        self.light = (direction, color);
+    }
+
+    /// Camera and fog for the draw; also re-centers the tile grid on the camera
+    pub fn update_camera(&self, queue: &wgpu::Queue, view_proj: [[f32; 4]; 4], position: [f32; 3], fog_color: [f32; 3], fog_start: f32, fog_end: f32) {
+        let camera_uniform = CameraUniform {
+            view_proj,
+            position,
+            fog_start,
+            fog_color,
+            fog_end,
+            tile_origin: tile_origin(Vec3::from_array(position), self.patch_size),
+            tile_size: self.patch_size,
+            tiles_per_side: (TILE_RADIUS * 2 + 1) as u32,
+            inv_view_proj: Mat4::from_cols_array_2d(&view_proj).inverse().to_cols_array_2d(),
+            light_dir: self.light.0.normalize_or_zero().to_array(),
+            _padding: 0.0,
+            light_color: self.light.1.to_array(),
+            _padding2: 0.0,
+        };
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
+    }
+    
+    /// Queue the water for the transparent pass, one draw per tile so they're blended far to
+    /// near; `camera_pos` as given to `update_camera`
+    pub fn queue_draws<'a>(&'a self, queue: &mut TransparentQueue<'a>, camera_pos: Vec3) {
+        for (tile, center) in tile_centers(camera_pos, self.patch_size).into_iter().enumerate() {
+            let tile = tile as u32;
+            queue.push(center.distance(Vec2::new(camera_pos.x, camera_pos.z)), move |rpass| {
+                rpass.set_pipeline(&self.render_pipeline);
+                rpass.set_bind_group(0, &self.render_bind_group_0, &[]);
+                rpass.set_bind_group(1, &self.render_bind_group_1, &[]);
+                rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+                rpass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                // The instance index picks the tile (see water.wgsl)
+                rpass.draw_indexed(0..self.num_indices, 0, tile..tile + 1);
+            });
+        }
+    }
+}
+
+/// Material bind group: wave maps from the compute pass plus the scene depth
+fn create_material_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    material_buffer: &wgpu::Buffer,
+    displacement_texture: &wgpu::Texture,
+    normal_texture: &wgpu::Texture,
+    sampler: &wgpu::Sampler,
+    depth_view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Water Render Bind Group 1"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: material_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&displacement_texture.create_view(&Default::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(&normal_texture.create_view(&Default::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::TextureView(depth_view),
+            },
+        ],
+    })
+}
+
+/// World XZ corner of the tile grid around `camera_pos`
+/// Snapped to whole tiles so the waves (sampled by world position) don't swim with the camera
+fn tile_origin(camera_pos: Vec3, tile_size: f32) -> [f32; 2] {
+    let tile_x = (camera_pos.x / tile_size).floor() as i32 - TILE_RADIUS;
+    let tile_z = (camera_pos.z / tile_size).floor() as i32 - TILE_RADIUS;
+    [tile_x as f32 * tile_size, tile_z as f32 * tile_size]
+}
+
+/// World XZ centre of each tile around the camera, in instance order (row by row from
+/// `tile_origin`, as water.wgsl lays them out)
+fn tile_centers(camera_pos: Vec3, tile_size: f32) -> Vec<Vec2> {
+    let origin = Vec2::from(tile_origin(camera_pos, tile_size));
+    let tiles_per_side = (TILE_RADIUS * 2 + 1) as u32;
+    (0..tiles_per_side * tiles_per_side)
+        .map(|tile| origin + (Vec2::new((tile % tiles_per_side) as f32, (tile / tiles_per_side) as f32) + 0.5) * tile_size)
+        .collect()
+}
+
+/// Tessendorf initial spectrum for an `n` x `n` patch, packed per texel as (h0(k), conj(h0(-k)))
+///
+/// Phillips spectrum P(k) = A * exp(-1 / (k L)^2) / k^4 * |k.w|^2 with L = V^2 / g. A is
+/// normalized so the height variance comes out as `amplitude`^2 (the continuous spectrum
+/// integrates to A * pi * L^2 / 2), making `amplitude` the RMS wave height.
+fn phillips_spectrum(uniforms: &WaterUniforms, n: u32, seed: u64) -> Vec<[f32; 4]> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let wind = Vec2::from(uniforms.wind_direction).normalize_or_zero();
+    let largest_wave = uniforms.wind_speed * uniforms.wind_speed / GRAVITY;
+    let smallest_wave = largest_wave * 0.001; // Damp ripples far below the wind's scale
+    let dk = 2.0 * PI / uniforms.size;
+    let phillips_a = uniforms.amplitude * uniforms.amplitude * dk * dk / (PI * largest_wave * largest_wave);
+
+    let half = (n / 2) as f32;
+    let h0: Vec<Vec2> = (0..n * n)
+        .map(|i| {
+            let (x, y) = (i % n, i / n);
+            // Keep the unpaired Nyquist row/column empty so every wave has its mirror and the result stays real
+            if x == 0 || y == 0 {
+                return Vec2::ZERO;
+            }
+            let k = Vec2::new(x as f32 - half, y as f32 - half) * dk;
+            let k_len = k.length();
+            if k_len < 1e-6 {
+                return Vec2::ZERO;
+            }
+            let alignment = k.dot(wind) / k_len;
+            let kl = k_len * largest_wave;
+            let p = phillips_a * (-1.0 / (kl * kl)).exp() / k_len.powi(4)
+                * alignment * alignment
+                * (-k_len * k_len * smallest_wave * smallest_wave).exp();
+
+            // Complex Gaussian (Box-Muller), E|h0|^2 = P(k)
+            let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+            let u2: f32 = rng.gen();
+            let radius = (-2.0 * u1.ln()).sqrt();
+            let gaussian = Vec2::new((2.0 * PI * u2).cos(), (2.0 * PI * u2).sin()) * radius;
+            gaussian * (p * 0.5).sqrt()
+        })
+        .collect();
+
+    (0..n * n)
+        .map(|i| {
+            let (x, y) = (i % n, i / n);
+            let mirror = h0[(((n - y) % n) * n + (n - x) % n) as usize];
+            let h = h0[i as usize];
+            [h.x, h.y, mirror.x, -mirror.y]
+        })
+        .collect()
+}
+
+/// Radix-2 inverse FFT table for `n` points, `log2(n)` stages, laid out as the
+/// butterfly texture (row = output index, column = stage):
+/// (twiddle re, twiddle im, input a, input b), with output = in[a] + twiddle * in[b].
+/// The first stage reads the input in bit-reversed order.
+fn butterfly_indices(n: u32) -> Vec<[f32; 4]> {
+    let stages = n.trailing_zeros();
+    let bit_reverse = |index: u32| index.reverse_bits() >> (32 - stages);
+
+    let mut table = Vec::with_capacity((n * stages) as usize);
+    for index in 0..n {
+        for stage in 0..stages {
+            let span = 1 << stage;
+            let k = (index * (n >> (stage + 1))) % n;
+            let angle = 2.0 * PI * k as f32 / n as f32;
+            let top_wing = index % (span * 2) < span;
+            let (a, b) = match (stage, top_wing) {
+                (0, true) => (bit_reverse(index), bit_reverse(index + 1)),
+                (0, false) => (bit_reverse(index - 1), bit_reverse(index)),
+                (_, true) => (index, index + span),
+                (_, false) => (index - span, index),
+            };
+            table.push([angle.cos(), angle.sin(), a as f32, b as f32]);
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiles_cover_camera() {
+        // Must match the CameraUniform struct in water.wgsl
+        assert_eq!(mem::size_of::<CameraUniform>(), 208);
+        assert_eq!(mem::size_of::<WaterMaterial>(), 64);
+
+        let size = 256.0;
+        let origin = tile_origin(Vec3::new(300.0, 5.0, -10.0), size);
+        assert_eq!(origin, [-256.0, -768.0]);
+
+        // The camera sits in the middle tile, with TILE_RADIUS tiles of margin on every side
+        let extent = size * (TILE_RADIUS * 2 + 1) as f32;
+        for camera in [Vec3::new(300.0, 0.0, -10.0), Vec3::new(-1000.5, 0.0, 4096.0)] {
+            let [x, z] = tile_origin(camera, size);
+            let margin = size * TILE_RADIUS as f32;
+            assert!(camera.x - x >= margin && x + extent - camera.x >= margin);
+            assert!(camera.z - z >= margin && z + extent - camera.z >= margin);
+
+            // Sorting draws by tile centre: the camera's own tile is the middle one
+            let centers = tile_centers(camera, size);
+            assert_eq!(centers.len(), ((TILE_RADIUS * 2 + 1) * (TILE_RADIUS * 2 + 1)) as usize);
+            assert_eq!(centers[0], Vec2::new(x, z) + size * 0.5);
+            let middle = centers[centers.len() / 2];
+            assert!((middle.x - camera.x).abs() <= size * 0.5 && (middle.y - camera.z).abs() <= size * 0.5);
+        }
+    }
+
+    fn complex_mul(a: Vec2, b: Vec2) -> Vec2 {
+        Vec2::new(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x)
+    }
+
+    /// CPU mirror of one fft_horizontal pass sequence over a single row
+    fn ifft_1d(input: &[Vec2], table: &[[f32; 4]]) -> Vec<Vec2> {
+        let n = input.len();
+        let stages = table.len() / n;
+        let mut data = input.to_vec();
+        for stage in 0..stages {
+            data = (0..n)
+                .map(|index| {
+                    let [re, im, a, b] = table[index * stages + stage];
+                    data[a as usize] + complex_mul(Vec2::new(re, im), data[b as usize])
+                })
+                .collect();
+        }
+        data
+    }
+
+    #[test]
+    fn test_butterfly_matches_dft() {
+        let n = 16;
+        let table = butterfly_indices(n);
+        assert_eq!(table.len(), 16 * 4);
+
+        let input: Vec<Vec2> = (0..n).map(|i| Vec2::new((i as f32 * 0.7).sin(), (i as f32 * 1.3).cos())).collect();
+        let fast = ifft_1d(&input, &table);
+        for (m, value) in fast.iter().enumerate() {
+            // Unnormalized inverse DFT: sum_k X[k] * exp(2 pi i k m / n)
+            let expected = input.iter().enumerate().fold(Vec2::ZERO, |sum, (k, x)| {
+                let angle = 2.0 * PI * (k * m) as f32 / n as f32;
+                sum + complex_mul(*x, Vec2::new(angle.cos(), angle.sin()))
+            });
+            assert!((*value - expected).length() < 1e-4, "index {}: {} vs {}", m, value, expected);
+        }
+    }
+
+    /// Sum of squared heights at t = 0, via the same IFFT the compute passes run
+    fn height_energy(amplitude: f32) -> (f32, usize) {
+        let n = 64;
+        let uniforms = WaterUniforms {
+            time: 0.0,
+            delta_time: 0.0,
+            wind_direction: [-1.0, 0.0],
+            wind_speed: 5.0,
+            amplitude,
+            choppiness: 1.0,
+            size: 64.0,
+            _padding: [0.0],
+        };
+        let table = butterfly_indices(n);
+        let n = n as usize;
+
+        // H(k, 0) = h0(k) + conj(h0(-k))
+        let spectrum = phillips_spectrum(&uniforms, n as u32, SPECTRUM_SEED);
+        let mut grid: Vec<Vec2> = spectrum.iter().map(|[a, b, c, d]| Vec2::new(a + c, b + d)).collect();
+        for row in grid.chunks_mut(n) {
+            row.copy_from_slice(&ifft_1d(row, &table));
+        }
+        for x in 0..n {
+            let column: Vec<Vec2> = (0..n).map(|y| grid[y * n + x]).collect();
+            for (y, value) in ifft_1d(&column, &table).into_iter().enumerate() {
+                grid[y * n + x] = value;
+            }
+        }
+
+        // Heights are real (imaginary parts cancel against the mirrored waves)
+        let mut energy = 0.0;
+        for value in &grid {
+            assert!(value.y.abs() < 1e-3 * amplitude.max(1e-3));
+            energy += value.x * value.x;
+        }
+        (energy, grid.len())
+    }
+
+    #[test]
+    fn test_displacement_energy_scales_with_amplitude() {
+        let (gentle, count) = height_energy(0.2);
+        let (rough, _) = height_energy(0.4);
+        assert!(gentle > 0.0);
+        // Heights scale linearly with amplitude, so energy quadruples
+        assert!((rough / gentle - 4.0).abs() < 1e-3, "ratio {}", rough / gentle);
+
+        // amplitude is roughly the RMS wave height
+        let rms = (gentle / count as f32).sqrt();
+        assert!(rms > 0.1 && rms < 0.3, "rms {}", rms);
+    }
+}
@@ -1,168 +1,396 @@
-use glam::Vec3;
-use rand::Rng;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum WeatherType {
-    Clear,
-    PartlyCloudy,
-    Overcast,
-    Stormy,
-    Foggy,
-}
-
-pub struct WeatherSystem {
-    pub current_weather: WeatherType,
-    pub target_weather: WeatherType,
-    pub transition_timer: f32,
-    pub transition_duration: f32,
-    pub time_since_last_change: f32,
-    
-    // Cloud Parameters (Current interpolated values)
-    pub cloud_coverage: f32,
-    pub cloud_density: f32,
-    pub cloud_scale: f32,
-    pub cloud_color_base: Vec3,
-    pub cloud_color_shade: Vec3,
-    pub wind_offset: [f32; 2],
-    
-    // Target Parameters
-    target_coverage: f32,
-    target_density: f32,
-    target_scale: f32,
-    target_color_base: Vec3,
-    target_color_shade: Vec3,
-}
-
-impl WeatherSystem {
-    pub fn new() -> Self {
-        let mut system = Self {
-            current_weather: WeatherType::PartlyCloudy,
-            target_weather: WeatherType::PartlyCloudy,
-            transition_timer: 0.0,
-            transition_duration: 10.0,
-            time_since_last_change: 0.0,
-            
-            cloud_coverage: 0.5,
-            cloud_density: 0.5,
-            cloud_scale: 1.0,
-            cloud_color_base: Vec3::new(0.8, 0.4, 0.3), // Burnt Sienna
-            cloud_color_shade: Vec3::new(0.9, 0.6, 0.6), // Pinkish
-            wind_offset: [0.0, 0.0],
-            
-            target_coverage: 0.5,
-            target_density: 0.5,
-            target_scale: 1.0,
-            target_color_base: Vec3::new(0.8, 0.4, 0.3),
-            target_color_shade: Vec3::new(0.9, 0.6, 0.6),
-        };
-        system.set_weather(WeatherType::PartlyCloudy, true);
-        system
-    }
-
-    pub fn update(&mut self, dt: f32) {
-        self.time_since_last_change += dt;
-        self.wind_offset[0] += dt * 0.01; // Constant wind for now
-        
-        // Random weather change every 60-120 seconds
-        if self.time_since_last_change > 60.0 {
-            let mut rng = rand::thread_rng();
-            if rng.gen_bool(0.005) { // Small chance per frame after 60s
-                let next_weather = match rng.gen_range(0..5) {
-                    0 => WeatherType::Clear,
-                    1 => WeatherType::PartlyCloudy,
-                    2 => WeatherType::Overcast,
-                    3 => WeatherType::Stormy,
-                    _ => WeatherType::Foggy,
-                };
-                println!("[WEATHER] Changing to {:?}", next_weather);
-                self.set_weather(next_weather, false);
-                self.time_since_last_change = 0.0;
-            }
-        }
-
-        // Interpolate parameters
-        if self.transition_timer > 0.0 {
-            self.transition_timer -= dt;
-            let t = 1.0 - (self.transition_timer / self.transition_duration).clamp(0.0, 1.0);
-            
-            // Smoothstep interpolation
-            let t = t * t * (3.0 - 2.0 * t);
-            
-            self.cloud_coverage = lerp(self.cloud_coverage, self.target_coverage, t * dt); // Simple lerp for now
-            self.cloud_density = lerp(self.cloud_density, self.target_density, t * dt);
-            self.cloud_scale = lerp(self.cloud_scale, self.target_scale, t * dt);
-            self.cloud_color_base = self.cloud_color_base.lerp(self.target_color_base, t * dt);
-            self.cloud_color_shade = self.cloud_color_shade.lerp(self.target_color_shade, t * dt);
-            
-            // If transition finished
-            if self.transition_timer <= 0.0 {
-                self.current_weather = self.target_weather;
-            }
-        } else {
-             // Keep drifting towards target slowly to fix any lerp inaccuracies
-            self.cloud_coverage = lerp(self.cloud_coverage, self.target_coverage, dt);
-            self.cloud_density = lerp(self.cloud_density, self.target_density, dt);
-            self.cloud_scale = lerp(self.cloud_scale, self.target_scale, dt);
-            self.cloud_color_base = self.cloud_color_base.lerp(self.target_color_base, dt);
-            self.cloud_color_shade = self.cloud_color_shade.lerp(self.target_color_shade, dt);
-        }
-    }
-
-    pub fn set_weather(&mut self, weather: WeatherType, instant: bool) {
-        self.target_weather = weather;
-        self.transition_duration = if instant { 0.0 } else { 20.0 }; // 20s transition
-        self.transition_timer = self.transition_duration;
-
-        match weather {
-            WeatherType::Clear => {
-                self.target_coverage = 0.0;
-                self.target_density = 0.0;
-                self.target_scale = 1.0;
-                self.target_color_base = Vec3::new(0.9, 0.9, 0.9); // White
-                self.target_color_shade = Vec3::new(0.9, 0.9, 0.9);
-            }
-            WeatherType::PartlyCloudy => {
-                self.target_coverage = 0.4;
-                self.target_density = 0.6;
-                self.target_scale = 1.2;
-                // Burnt Sienna & Pink
-                self.target_color_base = Vec3::new(0.91, 0.45, 0.32); // Burnt Sienna
-                self.target_color_shade = Vec3::new(1.0, 0.75, 0.8); // Pink
-            }
-            WeatherType::Overcast => {
-                self.target_coverage = 0.9;
-                self.target_density = 0.8;
-                self.target_scale = 0.8;
-                self.target_color_base = Vec3::new(0.6, 0.5, 0.5); // Greyish Pink
-                self.target_color_shade = Vec3::new(0.5, 0.4, 0.4); // Darker
-            }
-            WeatherType::Stormy => {
-                self.target_coverage = 1.0;
-                self.target_density = 1.0;
-                self.target_scale = 0.6;
-                self.target_color_base = Vec3::new(0.2, 0.15, 0.15); // Dark Storm
-                self.target_color_shade = Vec3::new(0.3, 0.1, 0.1); // Deep Red/Brown
-            }
-            WeatherType::Foggy => {
-                self.target_coverage = 0.3;
-                self.target_density = 0.2;
-                self.target_scale = 2.0;
-                self.target_color_base = Vec3::new(0.8, 0.8, 0.85); // Foggy White
-                self.target_color_shade = Vec3::new(0.8, 0.7, 0.7); // Slight pink tint
-            }
-        }
-        
-        if instant {
-            self.cloud_coverage = self.target_coverage;
-            self.cloud_density = self.target_density;
-            self.cloud_scale = self.target_scale;
-            self.cloud_color_base = self.target_color_base;
-            self.cloud_color_shade = self.target_color_shade;
-            self.current_weather = weather;
-        }
-    }
-}
-
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
-    a + (b - a) * t
-}
+use glam::{Vec2, Vec3};
+use croatoan_render::WindParams;
+use croatoan_wfc::SeededRng;
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeatherType {
+    Clear,
+    PartlyCloudy,
+    Overcast,
+    Stormy,
+    Foggy,
+}
+
+pub struct WeatherSystem {
+    pub current_weather: WeatherType,
+    pub target_weather: WeatherType,
+    pub transition_timer: f32,
+    pub transition_duration: f32,
+    pub time_since_last_change: f32,
+    
+    // Cloud Parameters (Current interpolated values)
+    pub cloud_coverage: f32,
+    pub cloud_density: f32,
+    pub cloud_scale: f32,
+    pub cloud_color_base: Vec3,
+    pub cloud_color_shade: Vec3,
+    pub wind_offset: [f32; 2],
+    /// Relative wind speed (1.0 = a breezy day); scales cloud drift and vegetation sway
+    pub wind_speed: f32,
+
+    // Fog Parameters (Current interpolated values)
+    /// Distance where fog begins / becomes opaque (world units)
+    pub fog_start: f32,
+    pub fog_end: f32,
+    /// Weather's own fog tint, and how much of it replaces the sky-matched fog color (0..1)
+    pub fog_color: Vec3,
+    pub fog_tint: f32,
+    
+    // Target Parameters
+    target_coverage: f32,
+    target_density: f32,
+    target_scale: f32,
+    target_color_base: Vec3,
+    target_color_shade: Vec3,
+    target_wind_speed: f32,
+    target_fog_start: f32,
+    target_fog_end: f32,
+    target_fog_color: Vec3,
+    target_fog_tint: f32,
+
+    /// Source of the random weather changes; None draws from the thread RNG (unrepeatable)
+    rng: Option<SeededRng>,
+}
+
+impl WeatherSystem {
+    pub fn new() -> Self {
+        let mut system = Self {
+            current_weather: WeatherType::PartlyCloudy,
+            target_weather: WeatherType::PartlyCloudy,
+            transition_timer: 0.0,
+            transition_duration: 10.0,
+            time_since_last_change: 0.0,
+            
+            cloud_coverage: 0.5,
+            cloud_density: 0.5,
+            cloud_scale: 1.0,
+            cloud_color_base: Vec3::new(0.8, 0.4, 0.3), // Burnt Sienna
+            cloud_color_shade: Vec3::new(0.9, 0.6, 0.6), // Pinkish
+            wind_offset: [0.0, 0.0],
+            wind_speed: 1.0,
+
+            fog_start: 200.0,
+            fog_end: 600.0,
+            fog_color: Vec3::splat(0.8),
+            fog_tint: 0.0,
+            
+            target_coverage: 0.5,
+            target_density: 0.5,
+            target_scale: 1.0,
+            target_color_base: Vec3::new(0.8, 0.4, 0.3),
+            target_color_shade: Vec3::new(0.9, 0.6, 0.6),
+            target_wind_speed: 1.0,
+            target_fog_start: 200.0,
+            target_fog_end: 600.0,
+            target_fog_color: Vec3::splat(0.8),
+            target_fog_tint: 0.0,
+
+            rng: None,
+        };
+        system.set_weather(WeatherType::PartlyCloudy, true);
+        system
+    }
+
+    /// Pick weather changes with `rng` (e.g. seeded from the world seed, so a world replays the
+    /// same weather), or unpredictably with None
+    pub fn set_rng(&mut self, rng: Option<SeededRng>) {
+        self.rng = rng;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time_since_last_change += dt;
+        self.wind_offset[0] += dt * 0.01 * self.wind_speed; // Constant direction for now
+        
+        // Random weather change every 60-120 seconds
+        if self.time_since_last_change > 60.0 {
+            let change = match &mut self.rng {
+                Some(rng) => rng.gen_bool(0.005).then(|| rng.next_index(5)),
+                None => {
+                    let mut rng = rand::thread_rng();
+                    rng.gen_bool(0.005).then(|| rng.gen_range(0..5))
+                }
+            };
+            if let Some(roll) = change { // Small chance per frame after 60s
+                let next_weather = match roll {
+                    0 => WeatherType::Clear,
+                    1 => WeatherType::PartlyCloudy,
+                    2 => WeatherType::Overcast,
+                    3 => WeatherType::Stormy,
+                    _ => WeatherType::Foggy,
+                };
+                println!("[WEATHER] Changing to {:?}", next_weather);
+                self.set_weather(next_weather, false);
+                self.time_since_last_change = 0.0;
+            }
+        }
+
+        // Interpolate parameters
+        if self.transition_timer > 0.0 {
+            self.transition_timer -= dt;
+            let t = 1.0 - (self.transition_timer / self.transition_duration).clamp(0.0, 1.0);
+            
+            // Smoothstep interpolation
+            let t = t * t * (3.0 - 2.0 * t);
+            
+            self.cloud_coverage = lerp(self.cloud_coverage, self.target_coverage, t * dt); // Simple lerp for now
+            self.cloud_density = lerp(self.cloud_density, self.target_density, t * dt);
+            self.cloud_scale = lerp(self.cloud_scale, self.target_scale, t * dt);
+            self.cloud_color_base = self.cloud_color_base.lerp(self.target_color_base, t * dt);
+            self.cloud_color_shade = self.cloud_color_shade.lerp(self.target_color_shade, t * dt);
+            self.wind_speed = lerp(self.wind_speed, self.target_wind_speed, t * dt);
+            self.fog_start = lerp(self.fog_start, self.target_fog_start, t * dt);
+            self.fog_end = lerp(self.fog_end, self.target_fog_end, t * dt);
+            self.fog_color = self.fog_color.lerp(self.target_fog_color, t * dt);
+            self.fog_tint = lerp(self.fog_tint, self.target_fog_tint, t * dt);
+            
+            // If transition finished
+            if self.transition_timer <= 0.0 {
+                self.current_weather = self.target_weather;
+            }
+        } else {
+             // Keep drifting towards target slowly to fix any lerp inaccuracies
+            self.cloud_coverage = lerp(self.cloud_coverage, self.target_coverage, dt);
+            self.cloud_density = lerp(self.cloud_density, self.target_density, dt);
+            self.cloud_scale = lerp(self.cloud_scale, self.target_scale, dt);
+            self.cloud_color_base = self.cloud_color_base.lerp(self.target_color_base, dt);
+            self.cloud_color_shade = self.cloud_color_shade.lerp(self.target_color_shade, dt);
+            self.wind_speed = lerp(self.wind_speed, self.target_wind_speed, dt);
+            self.fog_start = lerp(self.fog_start, self.target_fog_start, dt);
+            self.fog_end = lerp(self.fog_end, self.target_fog_end, dt);
+            self.fog_color = self.fog_color.lerp(self.target_fog_color, dt);
+            self.fog_tint = lerp(self.fog_tint, self.target_fog_tint, dt);
+        }
+    }
+
+    /// Sky ambient light for the given sun elevation (-1 midnight .. 1 noon)
+    /// Returns (color, intensity); intensity never drops below `min_intensity`
+    pub fn ambient_light(&self, sun_elevation: f32, min_intensity: f32) -> (Vec3, f32) {
+        let day_t = (sun_elevation * 2.0).clamp(0.0, 1.0);
+        let night_t = (-sun_elevation * 5.0).clamp(0.0, 1.0); // Matches the sky's fast fade to night
+
+        // Warm at sunrise/sunset, cool sky blue at midday, moonlit blue at night
+        let sunrise = Vec3::new(0.15, 0.10, 0.08);
+        let midday = Vec3::new(0.12, 0.14, 0.18);
+        let moonlight = Vec3::new(0.08, 0.10, 0.20);
+        let mut color = sunrise.lerp(midday, day_t).lerp(moonlight, night_t);
+
+        // Cloud cover washes out the tint and dims the sky
+        let grey = Vec3::splat((color.x + color.y + color.z) / 3.0);
+        color = color.lerp(grey, self.cloud_coverage * 0.5);
+        let intensity = (1.0 - self.cloud_coverage * 0.25) * lerp(1.0, 0.5, night_t);
+
+        (color, intensity.max(min_intensity))
+    }
+
+    /// Fog (color, start, end) for the scene, given the sky color at the horizon
+    /// The sky-matched fog is pulled toward the weather's tint, scaled to the sky's
+    /// brightness so fog still darkens at night.
+    pub fn fog(&self, sky_color: Vec3) -> ([f32; 3], f32, f32) {
+        let sky_fog = sky_color * 0.9;
+        let tint = self.fog_color * sky_fog.max_element();
+        let color = sky_fog.lerp(tint, self.fog_tint);
+        (color.to_array(), self.fog_start, self.fog_end)
+    }
+
+    /// Rain strength (0 dry .. 1 downpour) from the current cloud cover
+    /// Only thick, heavy cloud rains, so Clear, PartlyCloudy and Foggy stay dry; it ramps
+    /// with the clouds during a transition rather than switching on with the weather type.
+    pub fn precipitation_intensity(&self) -> f32 {
+        let heaviness = self.cloud_coverage * self.cloud_density;
+        ((heaviness - 0.3) / 0.7).clamp(0.0, 1.0)
+    }
+
+    /// Wind for the vegetation sway: blows the way the clouds drift, harder in storms
+    pub fn wind(&self) -> WindParams {
+        let direction = Vec2::from(self.wind_offset).try_normalize().unwrap_or(Vec2::X);
+        WindParams {
+            direction,
+            strength: 0.15 * self.wind_speed,
+            frequency: 1.5 + self.wind_speed * 0.5,
+        }
+    }
+
+    pub fn set_weather(&mut self, weather: WeatherType, instant: bool) {
+        self.target_weather = weather;
+        self.transition_duration = if instant { 0.0 } else { 20.0 }; // 20s transition
+        self.transition_timer = self.transition_duration;
+
+        match weather {
+            WeatherType::Clear => {
+                self.target_coverage = 0.0;
+                self.target_density = 0.0;
+                self.target_scale = 1.0;
+                self.target_color_base = Vec3::new(0.9, 0.9, 0.9); // White
+                self.target_color_shade = Vec3::new(0.9, 0.9, 0.9);
+                self.target_wind_speed = 0.6;
+                // Crisp air: see far
+                self.target_fog_start = 250.0;
+                self.target_fog_end = 750.0;
+                self.target_fog_color = Vec3::splat(0.8);
+                self.target_fog_tint = 0.0;
+            }
+            WeatherType::PartlyCloudy => {
+                self.target_coverage = 0.4;
+                self.target_density = 0.6;
+                self.target_scale = 1.2;
+                // Burnt Sienna & Pink
+                self.target_color_base = Vec3::new(0.91, 0.45, 0.32); // Burnt Sienna
+                self.target_color_shade = Vec3::new(1.0, 0.75, 0.8); // Pink
+                self.target_wind_speed = 1.0;
+                self.target_fog_start = 200.0;
+                self.target_fog_end = 600.0;
+                self.target_fog_color = Vec3::new(0.9, 0.75, 0.7); // Warm haze
+                self.target_fog_tint = 0.15;
+            }
+            WeatherType::Overcast => {
+                self.target_coverage = 0.9;
+                self.target_density = 0.8;
+                self.target_scale = 0.8;
+                self.target_color_base = Vec3::new(0.6, 0.5, 0.5); // Greyish Pink
+                self.target_color_shade = Vec3::new(0.5, 0.4, 0.4); // Darker
+                self.target_wind_speed = 1.4;
+                self.target_fog_start = 120.0;
+                self.target_fog_end = 420.0;
+                self.target_fog_color = Vec3::new(0.65, 0.62, 0.62); // Flat grey
+                self.target_fog_tint = 0.5;
+            }
+            WeatherType::Stormy => {
+                self.target_coverage = 1.0;
+                self.target_density = 1.0;
+                self.target_scale = 0.6;
+                self.target_color_base = Vec3::new(0.2, 0.15, 0.15); // Dark Storm
+                self.target_color_shade = Vec3::new(0.3, 0.1, 0.1); // Deep Red/Brown
+                self.target_wind_speed = 3.0;
+                // Driving rain closes in the view
+                self.target_fog_start = 40.0;
+                self.target_fog_end = 250.0;
+                self.target_fog_color = Vec3::new(0.35, 0.33, 0.35); // Dark grey
+                self.target_fog_tint = 0.6;
+            }
+            WeatherType::Foggy => {
+                self.target_coverage = 0.3;
+                self.target_density = 0.2;
+                self.target_scale = 2.0;
+                self.target_color_base = Vec3::new(0.8, 0.8, 0.85); // Foggy White
+                self.target_color_shade = Vec3::new(0.8, 0.7, 0.7); // Slight pink tint
+                self.target_wind_speed = 0.3;
+                // Thick ground fog: the world fades out within ~50 units
+                self.target_fog_start = 5.0;
+                self.target_fog_end = 50.0;
+                self.target_fog_color = Vec3::new(0.85, 0.85, 0.88); // Pale white
+                self.target_fog_tint = 0.8;
+            }
+        }
+        
+        if instant {
+            self.cloud_coverage = self.target_coverage;
+            self.cloud_density = self.target_density;
+            self.cloud_scale = self.target_scale;
+            self.cloud_color_base = self.target_color_base;
+            self.cloud_color_shade = self.target_color_shade;
+            self.wind_speed = self.target_wind_speed;
+            self.fog_start = self.target_fog_start;
+            self.fog_end = self.target_fog_end;
+            self.fog_color = self.target_fog_color;
+            self.fog_tint = self.target_fog_tint;
+            self.current_weather = weather;
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ambient_floor_at_night() {
+        let mut weather = WeatherSystem::new();
+        weather.set_weather(WeatherType::Stormy, true);
+
+        let (night_color, night_intensity) = weather.ambient_light(-1.0, 0.8);
+        assert!(night_intensity >= 0.8);
+        assert!(night_color.z > night_color.x, "night ambient should lean blue");
+
+        weather.set_weather(WeatherType::Clear, true);
+        let (_, day_intensity) = weather.ambient_light(1.0, 0.0);
+        let (_, dusk_intensity) = weather.ambient_light(-1.0, 0.0);
+        assert!(day_intensity > dusk_intensity);
+    }
+
+    #[test]
+    fn test_storm_wind_is_stronger() {
+        let mut weather = WeatherSystem::new();
+        weather.set_weather(WeatherType::Clear, true);
+        let calm = weather.wind();
+        weather.set_weather(WeatherType::Stormy, true);
+        let storm = weather.wind();
+        assert!(storm.strength > calm.strength * 2.0);
+        assert!(storm.frequency > calm.frequency);
+
+        // Follows the cloud drift
+        weather.update(1.0);
+        assert!(weather.wind_offset[0] > 0.0);
+        assert_eq!(weather.wind().direction, Vec2::X);
+    }
+
+    #[test]
+    fn test_seeded_weather_repeats() {
+        let sequence = |seed| {
+            let mut weather = WeatherSystem::new();
+            weather.set_rng(Some(SeededRng::new(seed)));
+            let mut changes = Vec::new();
+            for _ in 0..20_000 {
+                weather.update(0.1);
+                if weather.time_since_last_change == 0.0 {
+                    changes.push(weather.target_weather);
+                }
+            }
+            changes
+        };
+        let first = sequence(9);
+        assert!(first.len() > 3);
+        assert_eq!(first, sequence(9));
+        assert_ne!(first, sequence(10));
+    }
+
+    #[test]
+    fn test_only_heavy_cloud_rains() {
+        let mut weather = WeatherSystem::new();
+        for dry in [WeatherType::Clear, WeatherType::PartlyCloudy, WeatherType::Foggy] {
+            weather.set_weather(dry, true);
+            assert_eq!(weather.precipitation_intensity(), 0.0, "{:?} should be dry", dry);
+        }
+        weather.set_weather(WeatherType::Overcast, true);
+        let overcast = weather.precipitation_intensity();
+        weather.set_weather(WeatherType::Stormy, true);
+        assert!(overcast > 0.0);
+        assert!(weather.precipitation_intensity() > overcast);
+        assert_eq!(weather.precipitation_intensity(), 1.0);
+    }
+
+    #[test]
+    fn test_fog_follows_weather() {
+        let mut weather = WeatherSystem::new();
+        let sky = Vec3::new(0.5, 0.6, 0.9);
+
+        weather.set_weather(WeatherType::Clear, true);
+        let (clear_color, _, clear_end) = weather.fog(sky);
+        assert!((Vec3::from(clear_color) - sky * 0.9).length() < 1e-5, "clear fog matches the sky");
+
+        weather.set_weather(WeatherType::Foggy, true);
+        let (foggy_color, foggy_start, foggy_end) = weather.fog(sky);
+        assert!(foggy_end <= 60.0 && foggy_start < foggy_end);
+        assert!(clear_end > foggy_end * 10.0);
+        assert!(foggy_color[2] - foggy_color[0] < clear_color[2] - clear_color[0], "fog washes out the blue");
+
+        // Transitions ease the fog in rather than snapping
+        weather.set_weather(WeatherType::Clear, false);
+        weather.update(0.5);
+        assert!(weather.fog_end > foggy_end && weather.fog_end < clear_end);
+    }
+}